@@ -0,0 +1,61 @@
+//! `ScreenBuffer::flush_to`: diffing a frame against the last one and
+//! writing out the cells that changed, for frames where none, some, or
+//! all of the screen changed.
+//!
+//! Run with `cargo bench --bench buffer`.
+
+use std::hint::black_box;
+use std::io;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use digitalrain::buffer::ScreenBuffer;
+use digitalrain::color::Color;
+
+const WIDTH: u16 = 200;
+const HEIGHT: u16 = 60;
+/// Share of cells that differ from the previous frame.
+const CHANGE_RATIOS: [f64; 4] = [0.0, 0.1, 0.5, 1.0];
+
+/// Compose frame `n`: cells below the ratio alternate between two looks
+/// from frame to frame, the rest stay the same.
+fn fill(buffer: &mut ScreenBuffer, ratio: f64, n: u64) {
+    let changing = (ratio * 100.0).round() as usize;
+    let toggled = n % 2 == 1;
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let index = y as usize * WIDTH as usize + x as usize;
+            let (ch, g) = if index % 100 < changing && toggled {
+                ('ﾊ', 255)
+            } else {
+                ('ｱ', 140)
+            };
+            buffer.set_cell(x, y, ch, Color::Rgb { r: 0, g, b: 0 }, Color::Reset);
+        }
+    }
+}
+
+fn flush(c: &mut Criterion) {
+    let mut group = c.benchmark_group("flush");
+    for ratio in CHANGE_RATIOS {
+        let mut buffer = ScreenBuffer::new(WIDTH, HEIGHT);
+        // The first flush draws everything; start from a steady state
+        fill(&mut buffer, ratio, 0);
+        buffer.flush_to(&mut io::sink()).unwrap();
+        let mut n = 1;
+        let id = BenchmarkId::new(
+            format!("{WIDTH}x{HEIGHT}"),
+            format!("{:.0}%", ratio * 100.0),
+        );
+        group.bench_function(id, |b| {
+            b.iter(|| {
+                fill(&mut buffer, ratio, n);
+                n += 1;
+                buffer.flush_to(black_box(&mut io::sink())).unwrap();
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, flush);
+criterion_main!(benches);
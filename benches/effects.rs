@@ -0,0 +1,54 @@
+//! Frame cost of every built-in effect: one `update` plus `render` into a
+//! cleared buffer, at a few terminal sizes.
+//!
+//! Run with `cargo bench --bench effects`; add a filter such as
+//! `cargo bench --bench effects -- fire` to time a single effect.
+
+use std::hint::black_box;
+
+use clap::Parser;
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use digitalrain::buffer::ScreenBuffer;
+use digitalrain::config::{Cli, Config, ConfigFile};
+use digitalrain::effects::registry;
+
+/// A small terminal, a large one, and a very large one (a 4K screen with a small font).
+const SIZES: [(u16, u16); 3] = [(80, 24), (200, 60), (400, 120)];
+/// Frames run before timing, so the screen has filled with rain.
+const WARM_UP_FRAMES: usize = 90;
+const DT: f64 = 1.0 / 30.0;
+
+fn effects(c: &mut Criterion) {
+    let config = Config::resolve(
+        &Cli::parse_from(["digital_rain", "--seed", "1"]),
+        &ConfigFile::default(),
+    );
+    for name in registry::effect_names() {
+        let mut group = c.benchmark_group(*name);
+        for (width, height) in SIZES {
+            let spec = config.effect_spec_for(name);
+            let mut effect = registry::create_effect(&spec, width, height, &config)
+                .unwrap_or_else(|e| panic!("{name}: {e}"));
+            let mut buffer = ScreenBuffer::new(width, height);
+            for _ in 0..WARM_UP_FRAMES {
+                effect.update(DT);
+                effect.render(&mut buffer);
+            }
+            group.bench_function(
+                BenchmarkId::from_parameter(format!("{width}x{height}")),
+                |b| {
+                    b.iter(|| {
+                        effect.update(black_box(DT));
+                        buffer.clear();
+                        effect.render(&mut buffer);
+                        black_box(&buffer);
+                    })
+                },
+            );
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, effects);
+criterion_main!(benches);
@@ -0,0 +1,53 @@
+/*
+ * Digital rain from C through the shared library's C API.
+ *
+ *   make capi-example
+ *   LD_LIBRARY_PATH=target/release ./target/rain-c '{"defaults": {"effect": "fire"}}'
+ *
+ * The optional argument is a config file in JSON. Runs for ten seconds in
+ * the terminal's alternate screen, or until Ctrl+C.
+ */
+
+#include <signal.h>
+#include <stdio.h>
+#include <sys/ioctl.h>
+#include <time.h>
+#include <unistd.h>
+
+#include "digitalrain.h"
+
+#define FPS 30
+
+static volatile sig_atomic_t running = 1;
+
+static void stop(int sig) {
+    (void)sig;
+    running = 0;
+}
+
+int main(int argc, char **argv) {
+    struct winsize ws = {.ws_col = 80, .ws_row = 24};
+    ioctl(STDOUT_FILENO, TIOCGWINSZ, &ws);
+
+    DrRain *rain = dr_create(ws.ws_col, ws.ws_row, argc > 1 ? argv[1] : NULL);
+    if (rain == NULL) {
+        fprintf(stderr, "Error: %s\n", dr_last_error());
+        return 1;
+    }
+
+    signal(SIGINT, stop);
+    /* Alternate screen, hidden cursor */
+    fputs("\x1b[?1049h\x1b[?25l", stdout);
+
+    const struct timespec frame = {.tv_sec = 0, .tv_nsec = 1000000000L / FPS};
+    for (int i = 0; running && i < 10 * FPS; i++) {
+        dr_step(rain, 1.0 / FPS);
+        fputs(dr_frame_ansi(rain), stdout);
+        fflush(stdout);
+        nanosleep(&frame, NULL);
+    }
+
+    fputs("\x1b[0m\x1b[?25h\x1b[?1049l", stdout);
+    dr_destroy(rain);
+    return 0;
+}
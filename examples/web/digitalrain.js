@@ -0,0 +1,37 @@
+// Runs the digitalrain wasm build inside an xterm.js terminal.
+//
+// The wasm module exports a handful of C-ABI functions (see src/web.rs):
+// the command line goes in through dr_input/dr_start, and every
+// dr_frame call leaves that frame's ANSI output in wasm memory for
+// term.write. Only changed cells are sent, so frames stay small.
+
+export async function startRain(term, wasmUrl, args = "") {
+  const { instance } = await WebAssembly.instantiateStreaming(fetch(wasmUrl));
+  const wasm = instance.exports;
+  const decoder = new TextDecoder();
+  const output = (len) =>
+    decoder.decode(new Uint8Array(wasm.memory.buffer, wasm.dr_output(), len));
+
+  const bytes = new TextEncoder().encode(args);
+  new Uint8Array(wasm.memory.buffer, wasm.dr_input(bytes.length), bytes.length).set(bytes);
+  // No OS entropy in the browser: the page supplies the seed
+  const seed = Math.floor(Math.random() * 2 ** 32);
+  const error = wasm.dr_start(term.cols, term.rows, seed);
+  if (error) {
+    throw new Error(output(error));
+  }
+
+  term.write("\x1b[?25l");
+  term.onResize(({ cols, rows }) => wasm.dr_resize(cols, rows));
+
+  let last = performance.now();
+  const frame = (now) => {
+    const len = wasm.dr_frame(now - last);
+    last = now;
+    if (len) {
+      term.write(output(len));
+    }
+    requestAnimationFrame(frame);
+  };
+  requestAnimationFrame(frame);
+}
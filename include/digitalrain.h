@@ -0,0 +1,80 @@
+/* Generated by cbindgen from src/capi.rs (make capi); do not edit. */
+
+#ifndef DIGITALRAIN_H
+#define DIGITALRAIN_H
+
+#include <stdint.h>
+
+/*
+ A running effect, owned by the caller between `dr_create` and
+ `dr_destroy`.
+ */
+typedef struct DrRain DrRain;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/*
+ Start a run at `width` x `height` from `config_json`: a config file in
+ JSON, or NULL (or "") for the defaults. Returns NULL on error; see
+ `dr_last_error`.
+
+ # Safety
+
+ `config_json` must be NULL or a NUL-terminated string.
+ */
+DrRain *dr_create(uint16_t width, uint16_t height, const char *config_json);
+
+/*
+ Advance the rain by `delta_time` seconds. A step longer than a tenth
+ of a second (after the host slept, say) advances only that far.
+
+ # Safety
+
+ `rain` must be NULL or a handle from `dr_create` not yet destroyed.
+ */
+void dr_step(DrRain *rain, double delta_time);
+
+/*
+ The current frame as ANSI text: cursor moves and colors for the cells
+ that changed since the last call (every cell the first time, and after
+ `dr_set_size`). The string stays valid until the next call on `rain`.
+
+ # Safety
+
+ `rain` must be NULL or a handle from `dr_create` not yet destroyed.
+ */
+const char *dr_frame_ansi(DrRain *rain);
+
+/*
+ Adopt a new size; the next frame redraws everything.
+
+ # Safety
+
+ `rain` must be NULL or a handle from `dr_create` not yet destroyed.
+ */
+void dr_set_size(DrRain *rain, uint16_t width, uint16_t height);
+
+/*
+ Free a handle from `dr_create`. NULL is ignored.
+
+ # Safety
+
+ `rain` must be NULL or a handle from `dr_create` not yet destroyed; it
+ can't be used afterwards.
+ */
+void dr_destroy(DrRain *rain);
+
+/*
+ Why the last `dr_create` on this thread failed, or the last call that
+ panicked ("" if none has). The string stays valid until the next
+ failure.
+ */
+const char *dr_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* DIGITALRAIN_H */
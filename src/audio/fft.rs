@@ -0,0 +1,135 @@
+//! Spectrum analysis: a radix-2 FFT and log-spaced band levels for the
+//! `spectrum` effect.
+//!
+//! Each block is Hann-windowed and transformed, and the bins are grouped
+//! into bands spaced evenly in pitch (so bass isn't squeezed into one bar).
+//! Band levels are in decibels, mapped from `FLOOR_DB`..0 dB onto 0.0-1.0.
+
+use std::f32::consts::TAU;
+
+/// Number of bands `band_levels` returns.
+pub const BANDS: usize = 48;
+
+/// Lowest and highest band edges, in Hz.
+const LOW_HZ: f32 = 40.0;
+const HIGH_HZ: f32 = 16_000.0;
+
+/// Level that maps to 0.0; anything quieter is silence.
+const FLOOR_DB: f32 = -60.0;
+
+/// In-place FFT of a complex signal. The length must be a power of two.
+pub fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    debug_assert!(n.is_power_of_two() && im.len() == n);
+
+    // Bit-reversal permutation
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    // Butterflies, doubling the span each pass
+    let mut len = 2;
+    while len <= n {
+        let angle = -TAU / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * cos - im[b] * sin;
+                let t_im = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Levels (0.0-1.0) of `BANDS` log-spaced bands in `block`, sampled at
+/// `sample_rate` Hz. The block length must be a power of two.
+pub fn band_levels(block: &[f32], sample_rate: u32) -> Vec<f32> {
+    let n = block.len();
+    let mut re: Vec<f32> = block
+        .iter()
+        .enumerate()
+        .map(|(i, &s)| s * 0.5 * (1.0 - (TAU * i as f32 / n as f32).cos()))
+        .collect();
+    let mut im = vec![0.0; n];
+    fft(&mut re, &mut im);
+
+    // A full-scale sine peaks at n/4 through the Hann window
+    let scale = 4.0 / n as f32;
+    let bin_hz = sample_rate as f32 / n as f32;
+    let high = HIGH_HZ.min(sample_rate as f32 / 2.0);
+    let edge = |band: usize| LOW_HZ * (high / LOW_HZ).powf(band as f32 / BANDS as f32);
+    (0..BANDS)
+        .map(|band| {
+            let lo = ((edge(band) / bin_hz) as usize).max(1);
+            let hi = ((edge(band + 1) / bin_hz) as usize).clamp(lo, n / 2 - 1);
+            let peak = (lo..=hi)
+                .map(|bin| (re[bin] * re[bin] + im[bin] * im[bin]).sqrt() * scale)
+                .fold(0.0, f32::max);
+            let db = 20.0 * peak.max(1e-9).log10();
+            (1.0 - db / FLOOR_DB).clamp(0.0, 1.0)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fft_finds_a_pure_tone_in_its_bin() {
+        let n = 64;
+        let mut re: Vec<f32> = (0..n)
+            .map(|i| (TAU * 5.0 * i as f32 / n as f32).cos())
+            .collect();
+        let mut im = vec![0.0; n];
+        fft(&mut re, &mut im);
+        let magnitude = |k: usize| (re[k] * re[k] + im[k] * im[k]).sqrt();
+        assert!((magnitude(5) - n as f32 / 2.0).abs() < 1e-3);
+        assert!((magnitude(n - 5) - n as f32 / 2.0).abs() < 1e-3);
+        assert!(
+            (0..n)
+                .filter(|&k| k != 5 && k != n - 5)
+                .all(|k| magnitude(k) < 1e-3)
+        );
+    }
+
+    #[test]
+    fn a_tone_lights_the_band_that_holds_it() {
+        let tone = |hz: f32| -> Vec<f32> {
+            (0..1024)
+                .map(|i| (TAU * hz * i as f32 / 44_100.0).sin())
+                .collect()
+        };
+        let bass = band_levels(&tone(80.0), 44_100);
+        let treble = band_levels(&tone(5_000.0), 44_100);
+        let loudest = |levels: &[f32]| {
+            (0..BANDS)
+                .max_by(|&a, &b| levels[a].total_cmp(&levels[b]))
+                .unwrap()
+        };
+        assert!(loudest(&bass) < BANDS / 4);
+        assert!(loudest(&treble) > BANDS / 2);
+        assert!(bass[loudest(&bass)] > 0.9);
+        assert!(
+            band_levels(&vec![0.0; 1024], 44_100)
+                .iter()
+                .all(|&l| l == 0.0)
+        );
+    }
+}
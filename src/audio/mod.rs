@@ -0,0 +1,143 @@
+//! Audio input for `--audio`: raw PCM samples from a pipe, file, or stdin.
+//!
+//! There's no sound-device code here; any capture tool that writes raw
+//! signed 16-bit little-endian mono samples can feed it, for example
+//! `parec --format=s16le --channels=1 --rate=44100` (PulseAudio/PipeWire)
+//! or `arecord -f S16_LE -c 1 -r 44100 -t raw` (ALSA). A background thread
+//! reads the samples in blocks and sends the main loop each block's
+//! spectrum, plus a beat whenever the detector finds one, over a channel,
+//! so a stalled pipe never blocks a frame. A FIFO is reopened when its
+//! writer goes away.
+
+pub mod fft;
+pub mod onset;
+
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use crate::event::Event;
+use onset::BeatDetector;
+
+/// Samples per analysis block (23 ms at 44.1 kHz).
+pub const BLOCK_LEN: usize = 1024;
+
+/// Default `--audio-rate`, in Hz.
+pub const DEFAULT_SAMPLE_RATE: u32 = 44_100;
+
+/// What a beat does (`--beat-action`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum BeatAction {
+    /// Light the whole screen up like lightning (the `flash` post filter)
+    Flash,
+    /// Burst of glitch corruption (the `glitch` post filter)
+    Glitch,
+    /// Flip to the next featured palette
+    Palette,
+}
+
+/// Receiving end of the audio feed.
+pub struct AudioFeed {
+    events: Receiver<Event>,
+}
+
+impl AudioFeed {
+    /// Start reading samples at `sample_rate` Hz from `path`, or from stdin
+    /// when the path is `-`. Fails up front if the path can't be read.
+    pub fn spawn(path: &Path, sample_rate: u32, sensitivity: f64) -> Result<Self, String> {
+        let (tx, rx) = mpsc::channel();
+        let analyzer = Analyzer {
+            detector: BeatDetector::new(sample_rate, BLOCK_LEN, sensitivity),
+            sample_rate,
+        };
+        if path.as_os_str() == "-" {
+            thread::spawn(move || {
+                let mut analyzer = analyzer;
+                analyzer.forward(io::stdin().lock(), &tx);
+            });
+        } else {
+            std::fs::metadata(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let path = path.to_path_buf();
+            thread::spawn(move || {
+                let mut analyzer = analyzer;
+                crate::notify::read_pipe(path, |file| analyzer.forward(file, &tx));
+            });
+        }
+        Ok(Self { events: rx })
+    }
+
+    /// Events detected since the last call, oldest first.
+    pub fn drain(&self) -> impl Iterator<Item = Event> + '_ {
+        self.events.try_iter()
+    }
+}
+
+/// Turns blocks of samples into events.
+struct Analyzer {
+    detector: BeatDetector,
+    sample_rate: u32,
+}
+
+impl Analyzer {
+    /// Send each full block's spectrum, and a beat when it holds one;
+    /// false once the receiver is gone. A partial block at the end is
+    /// dropped.
+    fn forward(&mut self, mut reader: impl Read, tx: &Sender<Event>) -> bool {
+        let mut bytes = [0u8; BLOCK_LEN * 2];
+        let mut block = [0f32; BLOCK_LEN];
+        while reader.read_exact(&mut bytes).is_ok() {
+            for (sample, pair) in block.iter_mut().zip(bytes.chunks_exact(2)) {
+                *sample = i16::from_le_bytes([pair[0], pair[1]]) as f32 / 32768.0;
+            }
+            let levels = fft::band_levels(&block, self.sample_rate);
+            if tx.send(Event::Spectrum { levels }).is_err() {
+                return false;
+            }
+            if let Some(strength) = self.detector.process(&block)
+                && tx.send(Event::Beat { strength }).is_err()
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_pcm_blocks_into_spectra_and_beats() {
+        // Half a second of near-silence, then one loud block of square wave
+        let mut pcm = Vec::new();
+        for block in 0..23 {
+            let level: i16 = if block == 22 { 20_000 } else { 300 };
+            for i in 0..BLOCK_LEN {
+                let sample = if i % 64 < 32 { level } else { -level };
+                pcm.extend_from_slice(&sample.to_le_bytes());
+            }
+        }
+        // Trailing partial block is ignored
+        pcm.extend_from_slice(&[0x7f; 10]);
+
+        let (tx, rx) = mpsc::channel();
+        let mut analyzer = Analyzer {
+            detector: BeatDetector::new(DEFAULT_SAMPLE_RATE, BLOCK_LEN, 0.5),
+            sample_rate: DEFAULT_SAMPLE_RATE,
+        };
+        assert!(analyzer.forward(pcm.as_slice(), &tx));
+        let events: Vec<Event> = rx.try_iter().collect();
+        let spectra = events
+            .iter()
+            .filter(|e| matches!(e, Event::Spectrum { levels } if levels.len() == fft::BANDS))
+            .count();
+        assert_eq!(spectra, 23);
+        let beats: Vec<&Event> = events
+            .iter()
+            .filter(|e| matches!(e, Event::Beat { .. }))
+            .collect();
+        assert_eq!(beats, [&Event::Beat { strength: 1.0 }]);
+    }
+}
@@ -0,0 +1,130 @@
+//! Beat detection: a block of audio that's much louder than the second or
+//! so before it is an onset.
+//!
+//! Each block's energy (mean square) is compared with the average of the
+//! recent blocks. Crossing `threshold` times that average counts as a beat,
+//! as long as the previous beat is at least `MIN_BEAT_GAP` ago. Sensitivity
+//! moves the threshold: 0.0 only catches hard hits, 1.0 nearly every kick.
+
+use std::collections::VecDeque;
+
+/// Seconds of history a block is compared against.
+const HISTORY_SECS: f64 = 1.0;
+
+/// Shortest time between beats, in seconds (caps out at 240 BPM).
+const MIN_BEAT_GAP: f64 = 0.25;
+
+/// Blocks quieter than this (mean square, about -40 dB) are never beats,
+/// so hiss in a silent stretch doesn't trigger anything.
+const MIN_ENERGY: f64 = 1e-4;
+
+/// Default `--beat-sensitivity`.
+pub const DEFAULT_SENSITIVITY: f64 = 0.5;
+
+/// Finds beats in a stream of fixed-size sample blocks.
+pub struct BeatDetector {
+    /// Energies of the most recent blocks
+    history: VecDeque<f64>,
+    history_len: usize,
+    /// How many times the average energy a block needs to be a beat
+    threshold: f64,
+    /// Blocks that must pass between beats, and how many have
+    min_gap: usize,
+    since_beat: usize,
+}
+
+impl BeatDetector {
+    /// A detector for blocks of `block_len` samples at `sample_rate` Hz.
+    pub fn new(sample_rate: u32, block_len: usize, sensitivity: f64) -> Self {
+        let blocks_per_sec = sample_rate as f64 / block_len.max(1) as f64;
+        let history_len = ((HISTORY_SECS * blocks_per_sec).round() as usize).max(2);
+        let min_gap = (MIN_BEAT_GAP * blocks_per_sec).ceil() as usize;
+        Self {
+            history: VecDeque::with_capacity(history_len),
+            history_len,
+            threshold: 2.5 - 1.5 * sensitivity.clamp(0.0, 1.0),
+            min_gap,
+            since_beat: min_gap,
+        }
+    }
+
+    /// Feed the next block of samples (-1.0 to 1.0). Returns the beat's
+    /// strength (0.0-1.0) if this block is one.
+    pub fn process(&mut self, block: &[f32]) -> Option<f64> {
+        if block.is_empty() {
+            return None;
+        }
+        let energy = block.iter().map(|&s| s as f64 * s as f64).sum::<f64>() / block.len() as f64;
+        self.since_beat = self.since_beat.saturating_add(1);
+
+        // Wait for half a window of history before judging anything
+        let beat = if self.history.len() >= self.history_len / 2 {
+            let average = self.history.iter().sum::<f64>() / self.history.len() as f64;
+            let ratio = energy / average.max(f64::MIN_POSITIVE);
+            (energy >= MIN_ENERGY && ratio >= self.threshold && self.since_beat >= self.min_gap)
+                .then(|| (ratio / self.threshold - 1.0).clamp(0.0, 1.0))
+        } else {
+            None
+        };
+        if beat.is_some() {
+            self.since_beat = 0;
+        }
+
+        if self.history.len() == self.history_len {
+            self.history.pop_front();
+        }
+        self.history.push_back(energy);
+        beat
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A block of a 220 Hz tone at `amplitude`.
+    fn tone(amplitude: f32) -> Vec<f32> {
+        (0..1024)
+            .map(|i| amplitude * (i as f32 * 220.0 * std::f32::consts::TAU / 44_100.0).sin())
+            .collect()
+    }
+
+    #[test]
+    fn kicks_over_a_quiet_bed_are_beats_but_steady_sound_is_not() {
+        let mut detector = BeatDetector::new(44_100, 1024, DEFAULT_SENSITIVITY);
+        // Steady tone for two seconds: no beats
+        let steady: Vec<_> = (0..86)
+            .filter_map(|_| detector.process(&tone(0.3)))
+            .collect();
+        assert!(steady.is_empty());
+
+        // A kick every half second over a quiet bed
+        let mut detector = BeatDetector::new(44_100, 1024, DEFAULT_SENSITIVITY);
+        let mut beats = Vec::new();
+        for block in 0..172 {
+            let amplitude = if block % 21 == 0 { 0.9 } else { 0.1 };
+            if let Some(strength) = detector.process(&tone(amplitude)) {
+                beats.push((block, strength));
+            }
+        }
+        assert!(beats.len() >= 6, "{beats:?}");
+        assert!(beats.iter().all(|&(block, s)| block % 21 == 0 && s > 0.5));
+
+        // Silence never triggers, however it compares to the history
+        let mut detector = BeatDetector::new(44_100, 1024, 1.0);
+        assert!((0..50).all(|i| detector.process(&tone(0.001 * (i % 3) as f32)).is_none()));
+    }
+
+    #[test]
+    fn beats_are_spaced_by_the_minimum_gap() {
+        let mut detector = BeatDetector::new(44_100, 1024, 1.0);
+        for _ in 0..43 {
+            detector.process(&tone(0.05));
+        }
+        // Loudness climbing every block: at most one beat per quarter second
+        let beats = (0..20)
+            .filter(|i| detector.process(&tone(0.2 + 0.04 * *i as f32)).is_some())
+            .count();
+        assert!((1..=2).contains(&beats), "{beats}");
+    }
+}
@@ -1,19 +1,23 @@
 //! Screen buffer for composing frames before flushing to the terminal.
 //!
 //! Instead of writing each character individually (which would be slow),
-//! we compose the entire frame in memory, then flush it all at once using
-//! crossterm's queue! macro for batched output.
+//! we compose the entire frame in memory, then flush it all at once as one
+//! batch of ANSI escape sequences.
+//!
+//! Effects draw through the [`RenderTarget`] trait, which `ScreenBuffer`
+//! implements, so they don't depend on where the frame ends up.
+//!
+//! A buffer normally covers the whole terminal, but it can be placed at an
+//! offset (`--region`) so only part of the screen is drawn.
 
 use std::io::{self, Write};
+use std::str::FromStr;
 
-use crossterm::{
-    cursor::MoveTo,
-    queue,
-    style::{Color, Print, SetBackgroundColor, SetForegroundColor},
-};
+use crate::color::Color;
+use crate::color::gradient::{lerp_color, scale_color};
 
 /// A single cell on the screen: one character with foreground and background colors.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 pub struct Cell {
     pub ch: char,
     pub fg: Color,
@@ -30,16 +34,214 @@ impl Default for Cell {
     }
 }
 
+/// A rectangle of the terminal, in cells.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Region {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Region {
+    /// The whole terminal.
+    pub fn full(width: u16, height: u16) -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        }
+    }
+
+    /// Fit this region inside a `term_width` x `term_height` terminal,
+    /// shifting and shrinking it as needed. Never smaller than one cell.
+    pub fn clip(&self, term_width: u16, term_height: u16) -> Self {
+        let (term_width, term_height) = (term_width.max(1), term_height.max(1));
+        let x = self.x.min(term_width - 1);
+        let y = self.y.min(term_height - 1);
+        Self {
+            x,
+            y,
+            width: self.width.clamp(1, term_width - x),
+            height: self.height.clamp(1, term_height - y),
+        }
+    }
+
+    /// Move by (`dx`, `dy`) cells, stopping at the top-left corner.
+    pub fn moved(&self, dx: i32, dy: i32) -> Self {
+        Self {
+            x: (self.x as i32 + dx).clamp(0, u16::MAX as i32) as u16,
+            y: (self.y as i32 + dy).clamp(0, u16::MAX as i32) as u16,
+            ..*self
+        }
+    }
+
+    /// Grow or shrink by (`dw`, `dh`) cells, keeping the top-left corner.
+    pub fn resized(&self, dw: i32, dh: i32) -> Self {
+        Self {
+            width: (self.width as i32 + dw).clamp(1, u16::MAX as i32) as u16,
+            height: (self.height as i32 + dh).clamp(1, u16::MAX as i32) as u16,
+            ..*self
+        }
+    }
+
+    /// Grow by `margin` cells on every side, stopping at the top-left corner.
+    pub fn expanded(&self, margin: u16) -> Self {
+        let x = self.x.saturating_sub(margin);
+        let y = self.y.saturating_sub(margin);
+        Self {
+            x,
+            y,
+            width: (self.x - x)
+                .saturating_add(self.width)
+                .saturating_add(margin),
+            height: (self.y - y)
+                .saturating_add(self.height)
+                .saturating_add(margin),
+        }
+    }
+
+    /// Whether cell (`x`, `y`) lies inside.
+    pub fn contains(&self, x: u16, y: u16) -> bool {
+        (self.x..self.x.saturating_add(self.width)).contains(&x)
+            && (self.y..self.y.saturating_add(self.height)).contains(&y)
+    }
+}
+
+/// Parses `x,y,w,h` (as given to `--region`).
+impl FromStr for Region {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(',').map(str::trim).collect();
+        let [x, y, width, height] = parts[..] else {
+            return Err(format!("expected x,y,w,h but got '{}'", s));
+        };
+        let num = |v: &str| {
+            v.parse::<u16>()
+                .map_err(|_| format!("invalid number '{}' in region '{}'", v, s))
+        };
+        let region = Self {
+            x: num(x)?,
+            y: num(y)?,
+            width: num(width)?,
+            height: num(height)?,
+        };
+        if region.width == 0 || region.height == 0 {
+            return Err("region width and height must be at least 1".to_string());
+        }
+        Ok(region)
+    }
+}
+
+/// A grid effects can draw into. `ScreenBuffer` is the usual target;
+/// other front ends (a TUI widget, a canvas) can implement it to receive
+/// the cells directly.
+pub trait RenderTarget {
+    fn width(&self) -> u16;
+    fn height(&self) -> u16;
+    /// The cell at (`x`, `y`), or `None` outside the grid.
+    fn get_cell(&self, x: u16, y: u16) -> Option<Cell>;
+    /// Set one cell. Does nothing outside the grid.
+    fn set_cell(&mut self, x: u16, y: u16, ch: char, fg: Color, bg: Color);
+    /// Blank every cell.
+    fn clear(&mut self);
+
+    /// Move everything drawn so far `dx` columns right and `dy` rows down
+    /// (negative moves left and up), as for a screen shake. What moves off
+    /// the grid is lost; the cells uncovered at the edges are blank.
+    fn shift(&mut self, dx: i32, dy: i32) {
+        if dx == 0 && dy == 0 {
+            return;
+        }
+        let (width, height) = (self.width() as i32, self.height() as i32);
+        let mut cells = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                cells.push(self.get_cell(x as u16, y as u16).unwrap_or_default());
+            }
+        }
+        for y in 0..height {
+            for x in 0..width {
+                let (from_x, from_y) = (x - dx, y - dy);
+                let cell = if (0..width).contains(&from_x) && (0..height).contains(&from_y) {
+                    cells[(from_y * width + from_x) as usize]
+                } else {
+                    Cell::default()
+                };
+                self.set_cell(x as u16, y as u16, cell.ch, cell.fg, cell.bg);
+            }
+        }
+    }
+}
+
+/// What the last `flush` sent to the terminal.
+#[derive(Clone, Copy, Default)]
+pub struct FlushStats {
+    /// Cells that differed from the previous frame and were redrawn
+    pub cells_changed: usize,
+    /// Bytes of escape sequences and text written
+    pub bytes_written: usize,
+}
+
+impl RenderTarget for ScreenBuffer {
+    fn width(&self) -> u16 {
+        self.width
+    }
+
+    fn height(&self) -> u16 {
+        self.height
+    }
+
+    fn get_cell(&self, x: u16, y: u16) -> Option<Cell> {
+        ScreenBuffer::get_cell(self, x, y).copied()
+    }
+
+    fn set_cell(&mut self, x: u16, y: u16, ch: char, fg: Color, bg: Color) {
+        ScreenBuffer::set_cell(self, x, y, ch, fg, bg);
+    }
+
+    fn clear(&mut self) {
+        ScreenBuffer::clear(self);
+    }
+}
+
+/// Writer adapter that counts the bytes passing through it.
+struct CountingWriter<'a, W: Write> {
+    inner: &'a mut W,
+    count: usize,
+}
+
+impl<W: Write> Write for CountingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.count += n;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
 /// A 2D grid of cells representing one frame of the display.
+#[derive(Clone)]
 pub struct ScreenBuffer {
     width: u16,
     height: u16,
+    /// Terminal position of the buffer's top-left cell
+    origin: (u16, u16),
+    /// Output counters from the most recent flush
+    last_flush: FlushStats,
     /// Current frame's cells, stored in row-major order: index = y * width + x
     cells: Vec<Cell>,
     /// Previous frame's cells, used for dirty-checking (only redraw changed cells)
     prev_cells: Vec<Cell>,
     /// Whether this is the first frame (forces a full redraw)
     first_frame: bool,
+    /// What a `Color::Reset` background is drawn as (`--background`)
+    background: Color,
 }
 
 impl ScreenBuffer {
@@ -52,34 +254,88 @@ impl ScreenBuffer {
             cells: vec![Cell::default(); size],
             prev_cells: vec![Cell::default(); size],
             first_frame: true,
+            origin: (0, 0),
+            last_flush: FlushStats::default(),
+            background: Color::Reset,
         }
     }
 
+    /// Draw over a solid `color` instead of the terminal's own background:
+    /// from the next clear on, blank cells take it, and so does any cell
+    /// drawn with a `Color::Reset` background.
+    pub fn set_background(&mut self, color: Color) {
+        self.background = color;
+    }
+
+    /// The color behind the frame (`Color::Reset` for the terminal's own).
+    pub fn background(&self) -> Color {
+        self.background
+    }
+
+    /// An empty cell over the background.
+    fn blank(&self) -> Cell {
+        Cell {
+            bg: self.background,
+            ..Cell::default()
+        }
+    }
+
+    /// Place the buffer on screen at `region`. Resizes (and clears) the
+    /// buffer, forcing a full redraw at the new position.
+    pub fn set_region(&mut self, region: Region) {
+        self.origin = (region.x, region.y);
+        self.resize(region.width, region.height);
+    }
+
     /// Resize the buffer. Clears all cells.
     pub fn resize(&mut self, width: u16, height: u16) {
         self.width = width;
         self.height = height;
         let size = (width as usize) * (height as usize);
-        self.cells = vec![Cell::default(); size];
+        self.cells = vec![self.blank(); size];
         self.prev_cells = vec![Cell::default(); size];
         self.first_frame = true;
     }
 
-    /// Clear all cells to spaces with default colors.
+    /// Clear all cells to spaces over the background.
     pub fn clear(&mut self) {
-        for cell in &mut self.cells {
-            *cell = Cell::default();
-        }
+        let blank = self.blank();
+        self.cells.fill(blank);
     }
 
     /// Set a single cell. Does nothing if coordinates are out of bounds.
+    /// A `Color::Reset` background becomes the buffer's background.
     pub fn set_cell(&mut self, x: u16, y: u16, ch: char, fg: Color, bg: Color) {
         if x < self.width && y < self.height {
             let idx = (y as usize) * (self.width as usize) + (x as usize);
+            let bg = match bg {
+                Color::Reset => self.background,
+                bg => bg,
+            };
             self.cells[idx] = Cell { ch, fg, bg };
         }
     }
 
+    /// Scale every cell's RGB colors by `factor` (0.0 = black, 1.0 = unchanged).
+    /// Used for the fade-to-black exit animation.
+    pub fn dim(&mut self, factor: f64) {
+        let factor = factor.clamp(0.0, 1.0);
+        for cell in &mut self.cells {
+            cell.fg = scale_color(cell.fg, factor);
+            cell.bg = scale_color(cell.bg, factor);
+        }
+    }
+
+    /// Blend every cell's colors toward `color` by `amount` (0.0-1.0).
+    /// Used for full-screen flashes.
+    pub fn flash(&mut self, color: Color, amount: f64) {
+        let t = amount.clamp(0.0, 1.0) as f32;
+        for cell in &mut self.cells {
+            cell.fg = lerp_color(cell.fg, color, t);
+            cell.bg = lerp_color(cell.bg, color, t);
+        }
+    }
+
     /// Read-only slice access to all cells (row-major order).
     /// Used by post-processing filters like CRT simulation.
     pub fn cells(&self) -> &[Cell] {
@@ -100,6 +356,16 @@ impl ScreenBuffer {
         self.width
     }
 
+    /// Where the buffer sits on screen.
+    pub fn region(&self) -> Region {
+        Region {
+            x: self.origin.0,
+            y: self.origin.1,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
     pub fn height(&self) -> u16 {
         self.height
     }
@@ -110,8 +376,31 @@ impl ScreenBuffer {
     /// previous frame, we only send escape sequences for cells that actually
     /// changed, dramatically reducing I/O.
     pub fn flush(&mut self) -> io::Result<()> {
-        let mut stdout = io::stdout();
+        self.flush_to(&mut io::stdout())
+    }
 
+    /// Take `frame`'s cells as the next frame to flush. A writer that keeps
+    /// its own record of the screen uses this to diff frames composed in
+    /// another buffer; the two must be the same size.
+    pub fn load_frame(&mut self, frame: &ScreenBuffer) {
+        self.cells.copy_from_slice(&frame.cells);
+    }
+
+    /// Counters from the most recent flush (for the stats overlay).
+    pub fn last_flush(&self) -> FlushStats {
+        self.last_flush
+    }
+
+    /// Flush changed cells to any writer (the terminal, or a capture sink).
+    pub fn flush_to(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let mut out = CountingWriter {
+            inner: out,
+            count: 0,
+        };
+        let mut cells_changed = 0;
+        // Colors the terminal is drawing in, so runs of cells sharing them
+        // (a solid background, a trail) don't repeat the escapes
+        let mut pen: Option<(Color, Color)> = None;
         for y in 0..self.height {
             for x in 0..self.width {
                 let idx = (y as usize) * (self.width as usize) + (x as usize);
@@ -119,77 +408,94 @@ impl ScreenBuffer {
                 let prev = self.prev_cells[idx];
 
                 // Skip cells that haven't changed (unless first frame)
-                if !self.first_frame
-                    && cell.ch == prev.ch
-                    && color_eq(cell.fg, prev.fg)
-                    && color_eq(cell.bg, prev.bg)
-                {
+                if !self.first_frame && cell == prev {
                     continue;
                 }
 
                 // Queue the draw commands (batched, not flushed yet)
-                queue!(
-                    stdout,
-                    MoveTo(x, y),
-                    SetForegroundColor(cell.fg),
-                    SetBackgroundColor(cell.bg),
-                    Print(cell.ch)
-                )?;
+                cells_changed += 1;
+                // In u32: a region at the edge of the u16 range can't overflow
+                let col = self.origin.0 as u32 + x as u32;
+                let row = self.origin.1 as u32 + y as u32;
+                write!(out, "\x1b[{};{}H", row + 1, col + 1)?;
+                if pen.is_none_or(|(fg, _)| fg != cell.fg) {
+                    write_color(&mut out, 38, cell.fg)?;
+                }
+                if pen.is_none_or(|(_, bg)| bg != cell.bg) {
+                    write_color(&mut out, 48, cell.bg)?;
+                }
+                pen = Some((cell.fg, cell.bg));
+                write!(out, "{}", cell.ch)?;
             }
         }
 
         // Send everything to the terminal in one write
-        stdout.flush()?;
+        out.flush()?;
 
         // Swap: current becomes previous for next frame's comparison
         std::mem::swap(&mut self.cells, &mut self.prev_cells);
         // Clear current for the next frame to compose into
-        for cell in &mut self.cells {
-            *cell = Cell::default();
-        }
+        self.clear();
 
         self.first_frame = false;
+        self.last_flush = FlushStats {
+            cells_changed,
+            bytes_written: out.count,
+        };
         Ok(())
     }
+
+    /// Write the whole frame as a standalone ANSI text document: one line per
+    /// row, color escapes only where the color changes, reset at the end.
+    ///
+    /// Unlike `flush`, this uses no cursor movement, so the output can be
+    /// `cat`-ed or fed to external converters.
+    pub fn write_ansi(&self, out: &mut impl Write) -> io::Result<()> {
+        for y in 0..self.height {
+            let mut fg = Color::Reset;
+            let mut bg = Color::Reset;
+            for x in 0..self.width {
+                let cell = self.cells[(y as usize) * (self.width as usize) + (x as usize)];
+                if cell.fg != fg {
+                    write_color(out, 38, cell.fg)?;
+                    fg = cell.fg;
+                }
+                if cell.bg != bg {
+                    write_color(out, 48, cell.bg)?;
+                    bg = cell.bg;
+                }
+                write!(out, "{}", cell.ch)?;
+            }
+            writeln!(out, "\x1b[0m")?;
+        }
+        out.flush()
+    }
+
+    /// The frame as a `write_ansi` document in a `String`.
+    pub fn to_ansi_string(&self) -> String {
+        let mut out = Vec::new();
+        self.write_ansi(&mut out)
+            .expect("writing to a Vec cannot fail");
+        String::from_utf8(out).expect("ANSI output is UTF-8")
+    }
+
+    /// The frame's characters only, one line per row, trailing spaces trimmed.
+    pub fn to_plain_string(&self) -> String {
+        let mut text = String::new();
+        for row in self.cells.chunks(self.width.max(1) as usize) {
+            let line: String = row.iter().map(|c| c.ch).collect();
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        text
+    }
 }
 
-/// Compare two crossterm Colors for equality.
-/// crossterm::style::Color doesn't implement PartialEq for all variants,
-/// so we compare the debug representations as a simple workaround.
-fn color_eq(a: Color, b: Color) -> bool {
-    // For the color variants we use (Rgb and Reset), direct matching works
-    match (a, b) {
-        (Color::Reset, Color::Reset) => true,
-        (
-            Color::Rgb {
-                r: r1,
-                g: g1,
-                b: b1,
-            },
-            Color::Rgb {
-                r: r2,
-                g: g2,
-                b: b2,
-            },
-        ) => r1 == r2 && g1 == g2 && b1 == b2,
-        (Color::Black, Color::Black)
-        | (Color::Red, Color::Red)
-        | (Color::Green, Color::Green)
-        | (Color::Yellow, Color::Yellow)
-        | (Color::Blue, Color::Blue)
-        | (Color::Magenta, Color::Magenta)
-        | (Color::Cyan, Color::Cyan)
-        | (Color::White, Color::White)
-        | (Color::DarkRed, Color::DarkRed)
-        | (Color::DarkGreen, Color::DarkGreen)
-        | (Color::DarkYellow, Color::DarkYellow)
-        | (Color::DarkBlue, Color::DarkBlue)
-        | (Color::DarkMagenta, Color::DarkMagenta)
-        | (Color::DarkCyan, Color::DarkCyan)
-        | (Color::Grey, Color::Grey)
-        | (Color::DarkGrey, Color::DarkGrey) => true,
-        (Color::AnsiValue(a), Color::AnsiValue(b)) => a == b,
-        _ => false,
+/// Select `color` as the foreground (`layer` 38) or background (48).
+fn write_color(out: &mut impl Write, layer: u8, color: Color) -> io::Result<()> {
+    match color {
+        Color::Reset => write!(out, "\x1b[{}m", layer + 1),
+        Color::Rgb { r, g, b } => write!(out, "\x1b[{};2;{};{};{}m", layer, r, g, b),
     }
 }
 
@@ -214,6 +520,33 @@ mod tests {
         assert!(matches!(cell.fg, Color::Rgb { r: 0, g: 255, b: 0 }));
     }
 
+    #[test]
+    fn shift_moves_the_frame_and_blanks_the_uncovered_edge() {
+        let mut buf = ScreenBuffer::new(4, 2);
+        for (x, ch) in ['a', 'b', 'c', 'd'].into_iter().enumerate() {
+            buf.set_cell(x as u16, 0, ch, Color::Reset, Color::Reset);
+        }
+        RenderTarget::shift(&mut buf, 2, 1);
+        assert_eq!(buf.to_plain_string(), "\n  ab\n");
+        RenderTarget::shift(&mut buf, -3, -1);
+        assert_eq!(buf.to_plain_string(), "b\n\n");
+    }
+
+    #[test]
+    fn region_expanded_and_contains() {
+        let r = Region {
+            x: 0,
+            y: 5,
+            width: 4,
+            height: 2,
+        };
+        let grown = r.expanded(1);
+        assert_eq!((grown.x, grown.y, grown.width, grown.height), (0, 4, 5, 4));
+        assert!(grown.contains(4, 7));
+        assert!(!grown.contains(5, 7));
+        assert!(!r.contains(0, 4));
+    }
+
     #[test]
     fn set_cell_out_of_bounds_is_ignored() {
         let mut buf = ScreenBuffer::new(10, 10);
@@ -245,40 +578,166 @@ mod tests {
     }
 
     #[test]
-    fn color_eq_works_for_rgb() {
-        assert!(color_eq(
+    fn dim_scales_rgb_and_keeps_reset() {
+        let mut buf = ScreenBuffer::new(2, 1);
+        buf.set_cell(
+            0,
+            0,
+            'A',
             Color::Rgb {
-                r: 10,
-                g: 20,
-                b: 30
+                r: 200,
+                g: 100,
+                b: 0,
             },
+            Color::Reset,
+        );
+        buf.dim(0.5);
+        let cell = buf.get_cell(0, 0).unwrap();
+        assert!(matches!(
+            cell.fg,
             Color::Rgb {
-                r: 10,
-                g: 20,
-                b: 30
-            },
-        ));
-        assert!(!color_eq(
-            Color::Rgb {
-                r: 10,
-                g: 20,
-                b: 30
-            },
-            Color::Rgb {
-                r: 10,
-                g: 20,
-                b: 31
-            },
+                r: 100,
+                g: 50,
+                b: 0
+            }
         ));
+        assert!(matches!(cell.bg, Color::Reset));
     }
 
     #[test]
-    fn color_eq_reset_matches_reset() {
-        assert!(color_eq(Color::Reset, Color::Reset));
+    fn write_ansi_emits_rows_and_only_color_changes() {
+        let mut buf = ScreenBuffer::new(3, 2);
+        let green = Color::Rgb { r: 0, g: 255, b: 0 };
+        buf.set_cell(0, 0, 'A', green, Color::Reset);
+        buf.set_cell(1, 0, 'B', green, Color::Reset);
+        let mut out = Vec::new();
+        buf.write_ansi(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        assert_eq!(text.lines().count(), 2);
+        assert_eq!(text.matches("38;2;0;255;0").count(), 1);
+        assert!(text.starts_with("\x1b[38;2;0;255;0mAB"));
+        assert_eq!(buf.to_ansi_string(), text);
     }
 
     #[test]
-    fn color_eq_different_variants_are_not_equal() {
-        assert!(!color_eq(Color::Reset, Color::Rgb { r: 0, g: 0, b: 0 }));
+    fn plain_text_trims_trailing_spaces() {
+        let mut buf = ScreenBuffer::new(4, 2);
+        buf.set_cell(1, 1, 'x', Color::Reset, Color::Reset);
+        assert_eq!(buf.to_plain_string(), "\n x\n");
+    }
+
+    #[test]
+    fn flush_writes_truecolor_and_default_escapes() {
+        let mut buf = ScreenBuffer::new(1, 1);
+        let red = Color::Rgb {
+            r: 200,
+            g: 10,
+            b: 0,
+        };
+        buf.set_cell(0, 0, 'R', red, Color::Reset);
+        let mut out = Vec::new();
+        buf.flush_to(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\x1b[1;1H\x1b[38;2;200;10;0m\x1b[49mR"
+        );
+    }
+
+    #[test]
+    fn flush_offsets_by_region_origin() {
+        let mut buf = ScreenBuffer::new(1, 1);
+        buf.set_region(Region {
+            x: 5,
+            y: 2,
+            width: 2,
+            height: 1,
+        });
+        let mut out = Vec::new();
+        buf.flush_to(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        // MoveTo is 1-based: row 3, columns 6 and 7
+        assert!(text.contains("\x1b[3;6H"));
+        assert!(text.contains("\x1b[3;7H"));
+        assert!(!text.contains("\x1b[1;1H"));
+    }
+
+    #[test]
+    fn region_parses_and_clips_to_terminal() {
+        let region: Region = "10, 5, 40, 20".parse().unwrap();
+        assert_eq!(
+            region,
+            Region {
+                x: 10,
+                y: 5,
+                width: 40,
+                height: 20
+            }
+        );
+        assert!("1,2,3".parse::<Region>().is_err());
+        assert!("0,0,0,5".parse::<Region>().is_err());
+
+        // Shrinks to fit, and a region off-screen is pulled back on
+        assert_eq!(
+            region.clip(30, 24),
+            Region {
+                x: 10,
+                y: 5,
+                width: 20,
+                height: 19
+            }
+        );
+        let far = Region {
+            x: 100,
+            y: 100,
+            width: 5,
+            height: 5,
+        };
+        assert_eq!(
+            far.clip(80, 24),
+            Region {
+                x: 79,
+                y: 23,
+                width: 1,
+                height: 1
+            }
+        );
+        assert_eq!(far.moved(-200, 1).x, 0);
+        assert_eq!(far.resized(-10, 2).width, 1);
+    }
+
+    #[test]
+    fn flush_counts_changed_cells_and_bytes() {
+        let mut buf = ScreenBuffer::new(4, 2);
+        let mut out = Vec::new();
+        buf.flush_to(&mut out).unwrap();
+        // First frame redraws everything
+        assert_eq!(buf.last_flush().cells_changed, 8);
+        assert_eq!(buf.last_flush().bytes_written, out.len());
+
+        let mut out = Vec::new();
+        buf.set_cell(2, 1, 'x', Color::Reset, Color::Reset);
+        buf.flush_to(&mut out).unwrap();
+        assert_eq!(buf.last_flush().cells_changed, 1);
+        assert_eq!(buf.last_flush().bytes_written, out.len());
+    }
+
+    #[test]
+    fn a_solid_background_fills_blanks_and_is_sent_once_per_run() {
+        let navy = Color::Rgb { r: 0, g: 0, b: 128 };
+        let green = Color::Rgb { r: 0, g: 255, b: 0 };
+        let mut buf = ScreenBuffer::new(4, 1);
+        buf.set_background(navy);
+        buf.clear();
+        buf.set_cell(1, 0, 'a', green, Color::Reset);
+        assert!(buf.cells().iter().all(|c| c.bg == navy));
+
+        let mut out = Vec::new();
+        buf.flush_to(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.matches("48;2;0;0;128").count(), 1);
+        assert_eq!(out.matches("38;").count() + out.matches("39m").count(), 3);
+        // Flushing leaves the next frame over the background too
+        assert!(buf.cells().iter().all(|c| c.ch == ' ' && c.bg == navy));
     }
 }
@@ -0,0 +1,239 @@
+//! C API (`cdylib` feature) for embedding the rain in non-Rust programs:
+//! C, C++, or Python through ctypes.
+//!
+//! A run is an opaque `DrRain` handle. Create it at the host's size from a
+//! JSON config (the config file schema, e.g. `{"defaults": {"effect":
+//! "fire"}}`), step it with the elapsed time, and write each frame's ANSI
+//! text to a terminal:
+//!
+//! ```c
+//! DrRain *rain = dr_create(80, 24, "{\"defaults\": {\"color\": \"gold\"}}");
+//! dr_step(rain, 1.0 / 30.0);
+//! fputs(dr_frame_ansi(rain), stdout);
+//! dr_destroy(rain);
+//! ```
+//!
+//! No call unwinds into the host: a panic inside the library is caught,
+//! reported through `dr_last_error`, and the call returns NULL or does
+//! nothing.
+//!
+//! `include/digitalrain.h` is generated from this file with cbindgen
+//! (`make capi`).
+
+use std::cell::RefCell;
+use std::ffi::{CStr, c_char};
+use std::panic::{self, AssertUnwindSafe};
+
+use clap::Parser;
+
+use crate::config::{Cli, Config, ConfigFormat};
+use crate::runner::{self, Runner};
+
+/// A running effect, owned by the caller between `dr_create` and
+/// `dr_destroy`.
+pub struct DrRain {
+    runner: Runner,
+    /// The last frame's ANSI text, NUL-terminated
+    frame: Vec<u8>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+fn set_error(message: String) {
+    LAST_ERROR.with_borrow_mut(|error| {
+        *error = message.replace('\0', " ").into_bytes();
+        error.push(0);
+    });
+}
+
+/// Run `body`, or if it panics, record why and return `fallback`.
+fn guard<T>(fallback: T, body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown panic");
+        set_error(format!("panicked: {}", message));
+        fallback
+    })
+}
+
+/// Start a run at `width` x `height` from `config_json`: a config file in
+/// JSON, or NULL (or "") for the defaults. Returns NULL on error; see
+/// `dr_last_error`.
+///
+/// # Safety
+///
+/// `config_json` must be NULL or a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dr_create(
+    width: u16,
+    height: u16,
+    config_json: *const c_char,
+) -> *mut DrRain {
+    let json = if config_json.is_null() {
+        ""
+    } else {
+        match unsafe { CStr::from_ptr(config_json) }.to_str() {
+            Ok(json) => json,
+            Err(e) => {
+                set_error(format!("config is not UTF-8: {e}"));
+                return std::ptr::null_mut();
+            }
+        }
+    };
+    guard(std::ptr::null_mut(), || {
+        match create(json, width.max(1), height.max(1)) {
+            Ok(runner) => Box::into_raw(Box::new(DrRain {
+                runner,
+                frame: vec![0],
+            })),
+            Err(e) => {
+                set_error(e);
+                std::ptr::null_mut()
+            }
+        }
+    })
+}
+
+fn create(json: &str, width: u16, height: u16) -> Result<Runner, String> {
+    let config_file = if json.trim().is_empty() {
+        Default::default()
+    } else {
+        ConfigFormat::Json.parse(json)?
+    };
+    let config = Config::resolve(&Cli::parse_from(["digital_rain"]), &config_file);
+    Runner::new(&config, width, height)
+}
+
+/// Advance the rain by `delta_time` seconds. A step longer than a tenth
+/// of a second (after the host slept, say) advances only that far.
+///
+/// # Safety
+///
+/// `rain` must be NULL or a handle from `dr_create` not yet destroyed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dr_step(rain: *mut DrRain, delta_time: f64) {
+    if let Some(rain) = unsafe { rain.as_mut() } {
+        guard((), || {
+            rain.runner.step(runner::host_step(delta_time));
+        });
+    }
+}
+
+/// The current frame as ANSI text: cursor moves and colors for the cells
+/// that changed since the last call (every cell the first time, and after
+/// `dr_set_size`). The string stays valid until the next call on `rain`.
+///
+/// # Safety
+///
+/// `rain` must be NULL or a handle from `dr_create` not yet destroyed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dr_frame_ansi(rain: *mut DrRain) -> *const c_char {
+    let Some(rain) = (unsafe { rain.as_mut() }) else {
+        return c"".as_ptr();
+    };
+    guard(c"".as_ptr(), || {
+        rain.frame.clear();
+        // Writing to a Vec can't fail
+        let _ = rain.runner.flush_to(&mut rain.frame);
+        rain.frame.push(0);
+        rain.frame.as_ptr().cast()
+    })
+}
+
+/// Adopt a new size; the next frame redraws everything.
+///
+/// # Safety
+///
+/// `rain` must be NULL or a handle from `dr_create` not yet destroyed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dr_set_size(rain: *mut DrRain, width: u16, height: u16) {
+    if let Some(rain) = unsafe { rain.as_mut() } {
+        guard((), || rain.runner.resize(width.max(1), height.max(1)));
+    }
+}
+
+/// Free a handle from `dr_create`. NULL is ignored.
+///
+/// # Safety
+///
+/// `rain` must be NULL or a handle from `dr_create` not yet destroyed; it
+/// can't be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dr_destroy(rain: *mut DrRain) {
+    if !rain.is_null() {
+        let rain = unsafe { Box::from_raw(rain) };
+        guard((), || drop(rain));
+    }
+}
+
+/// Why the last `dr_create` on this thread failed, or the last call that
+/// panicked ("" if none has). The string stays valid until the next
+/// failure.
+#[unsafe(no_mangle)]
+pub extern "C" fn dr_last_error() -> *const c_char {
+    LAST_ERROR.with_borrow(|error| {
+        if error.is_empty() {
+            c"".as_ptr()
+        } else {
+            error.as_ptr().cast()
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(ptr: *const c_char) -> String {
+        unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn a_handle_steps_and_draws_until_destroyed() {
+        let rain =
+            unsafe { dr_create(20, 6, c"{\"defaults\": {\"effect\": \"binary\"}}".as_ptr()) };
+        assert!(!rain.is_null());
+        unsafe { dr_step(rain, 0.5) };
+        let first = text(unsafe { dr_frame_ansi(rain) });
+        assert!(first.starts_with("\x1b[1;1H"));
+        assert_eq!(first.matches('H').count(), 20 * 6);
+        unsafe { dr_set_size(rain, 10, 3) };
+        assert_eq!(
+            text(unsafe { dr_frame_ansi(rain) }).matches('H').count(),
+            10 * 3
+        );
+        unsafe { dr_destroy(rain) };
+
+        let bad = unsafe { dr_create(20, 6, c"{\"defaults\": ".as_ptr()) };
+        assert!(bad.is_null());
+        assert!(!text(dr_last_error()).is_empty());
+        let unknown =
+            unsafe { dr_create(20, 6, c"{\"defaults\": {\"effect\": \"nope\"}}".as_ptr()) };
+        assert!(unknown.is_null());
+        assert_eq!(text(dr_last_error()), "unknown effect 'nope'");
+        let defaults = unsafe { dr_create(20, 6, std::ptr::null()) };
+        assert!(!defaults.is_null());
+        unsafe { dr_destroy(defaults) };
+    }
+
+    #[test]
+    fn long_steps_are_capped_and_panics_stay_inside() {
+        let rain = unsafe { dr_create(40, 12, c"{\"defaults\": {\"effect\": \"fire\"}}".as_ptr()) };
+        // A host back from sleep, or one passing garbage, still returns promptly
+        let started = std::time::Instant::now();
+        for delta_time in [100.0, f64::INFINITY, f64::NAN, -1.0] {
+            unsafe { dr_step(rain, delta_time) };
+        }
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+        assert!(text(unsafe { dr_frame_ansi(rain) }).starts_with("\x1b[1;1H"));
+        unsafe { dr_destroy(rain) };
+
+        assert_eq!(guard(7, || panic!("inside")), 7);
+        assert_eq!(text(dr_last_error()), "panicked: inside");
+    }
+}
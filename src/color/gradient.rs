@@ -3,7 +3,7 @@
 //! Each rain column has a "trail" of characters that fade from bright (head)
 //! to dark (tail). We interpolate between colors based on position in the trail.
 
-use crossterm::style::Color;
+use super::Color;
 
 /// Linearly interpolate between two RGB colors.
 ///
@@ -71,6 +71,60 @@ pub fn scale_color(color: Color, factor: f64) -> Color {
     }
 }
 
+/// A multi-stop color gradient: colors pinned at positions between 0.0 and
+/// 1.0, blended linearly in between.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    stops: Vec<(f32, Color)>,
+}
+
+impl Gradient {
+    /// A gradient through `stops` (position, color), in any order.
+    /// Positions are clamped to 0..1; at least one stop is required.
+    pub fn from_stops(stops: &[(f32, Color)]) -> Result<Self, String> {
+        if stops.is_empty() {
+            return Err("a gradient needs at least one stop".to_string());
+        }
+        if stops.iter().any(|(pos, _)| pos.is_nan()) {
+            return Err("gradient stop positions must be numbers".to_string());
+        }
+        let mut stops: Vec<(f32, Color)> = stops
+            .iter()
+            .map(|&(pos, color)| (pos.clamp(0.0, 1.0), color))
+            .collect();
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(Self { stops })
+    }
+
+    /// Evenly spaced stops, first color at 0.0 and last at 1.0.
+    pub fn even(colors: &[Color]) -> Result<Self, String> {
+        let last = colors.len().saturating_sub(1).max(1) as f32;
+        let stops: Vec<(f32, Color)> = colors
+            .iter()
+            .enumerate()
+            .map(|(i, &color)| (i as f32 / last, color))
+            .collect();
+        Self::from_stops(&stops)
+    }
+
+    /// The color at `t` (clamped to 0..1). Before the first stop or after
+    /// the last, the end color holds.
+    pub fn at(&self, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let next = self.stops.partition_point(|&(pos, _)| pos < t);
+        match (
+            next.checked_sub(1).map(|i| self.stops[i]),
+            self.stops.get(next),
+        ) {
+            (Some((from_pos, from)), Some(&(to_pos, to))) if to_pos > from_pos => {
+                lerp_color(from, to, (t - from_pos) / (to_pos - from_pos))
+            }
+            (_, Some(&(_, color))) | (Some((_, color)), None) => color,
+            (None, None) => unreachable!("a gradient has at least one stop"),
+        }
+    }
+}
+
 /// Linearly interpolate between two u8 values.
 fn lerp_u8(a: u8, b: u8, t: f32) -> u8 {
     let result = (a as f32) * (1.0 - t) + (b as f32) * t;
@@ -85,6 +139,25 @@ mod tests {
         Color::Rgb { r, g, b }
     }
 
+    #[test]
+    fn gradient_blends_between_sorted_stops() {
+        let g = Gradient::from_stops(&[
+            (1.0, rgb(0, 0, 200)),
+            (0.0, rgb(200, 0, 0)),
+            (0.5, rgb(0, 200, 0)),
+        ])
+        .unwrap();
+        assert_eq!(g.at(0.0), rgb(200, 0, 0));
+        assert_eq!(g.at(0.25), rgb(100, 100, 0));
+        assert_eq!(g.at(0.5), rgb(0, 200, 0));
+        assert_eq!(g.at(2.0), rgb(0, 0, 200));
+
+        let single = Gradient::even(&[rgb(9, 9, 9)]).unwrap();
+        assert_eq!(single.at(0.7), rgb(9, 9, 9));
+        assert!(Gradient::from_stops(&[]).is_err());
+        assert!(Gradient::from_stops(&[(f32::NAN, rgb(0, 0, 0))]).is_err());
+    }
+
     fn unwrap_rgb(c: Color) -> (u8, u8, u8) {
         match c {
             Color::Rgb { r, g, b } => (r, g, b),
@@ -1,6 +1,66 @@
 //! Color utilities: palettes, gradient interpolation, HSL math, and CSS colors.
+//!
+//! The simulation works only in [`Color`], never in a terminal library's
+//! color type, so effects can run anywhere a frame can be shown.
 
 pub mod css_colors;
 pub mod gradient;
 pub mod hsl;
 pub mod palette;
+
+use std::str::FromStr;
+
+/// A cell color: 24-bit RGB, or `Reset` for the display's default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Color {
+    /// The terminal's (or page's) own foreground or background
+    #[default]
+    Reset,
+    Rgb {
+        r: u8,
+        g: u8,
+        b: u8,
+    },
+}
+
+impl From<(u8, u8, u8)> for Color {
+    fn from((r, g, b): (u8, u8, u8)) -> Self {
+        Color::Rgb { r, g, b }
+    }
+}
+
+/// Parses a `#rrggbb` hex code or a CSS color name (case-insensitive).
+impl FromStr for Color {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(hex) = s.strip_prefix('#') {
+            let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+            return match (hex.len(), channel(0), channel(2), channel(4)) {
+                (6, Some(r), Some(g), Some(b)) => Ok(Color::Rgb { r, g, b }),
+                _ => Err(format!("invalid hex color '{}' (expected #rrggbb)", s)),
+            };
+        }
+        css_colors::css_color_by_name(s)
+            .map(|css| Color::Rgb {
+                r: css.r,
+                g: css.g,
+                b: css.b,
+            })
+            .ok_or_else(|| format!("unknown color '{}'", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex_and_css_names() {
+        assert_eq!("#FF8000".parse(), Ok(Color::from((255, 128, 0))));
+        assert_eq!("Coral".parse(), Ok(Color::from((255, 127, 80))));
+        assert!("#ff80".parse::<Color>().is_err());
+        assert!("#gg0000".parse::<Color>().is_err());
+        assert!("plaid".parse::<Color>().is_err());
+    }
+}
@@ -6,9 +6,9 @@
 //!
 //! Hand-tuned names always take priority over CSS auto-generation.
 
-use crossterm::style::Color;
-
+use super::Color;
 use super::css_colors;
+use super::gradient::{Gradient, lerp_color};
 use super::hsl;
 
 /// Hand-tuned palette names, in display order.
@@ -42,6 +42,15 @@ pub fn palette_names() -> Vec<&'static str> {
     names
 }
 
+/// Whether `name` resolves to a palette (hand-tuned, alias, or CSS color)
+/// without falling back to classic.
+pub fn is_palette_name(name: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    HAND_TUNED_NAMES.contains(&lower.as_str())
+        || lower == "monochrome"
+        || css_colors::css_color_by_name(&lower).is_some()
+}
+
 /// Look up a palette by name. Returns classic if the name is unknown.
 ///
 /// Priority: hand-tuned match -> "monochrome" alias -> CSS auto-gen -> fallback.
@@ -71,8 +80,19 @@ pub fn palette_by_name(name: &str) -> Palette {
     Palette::classic()
 }
 
+/// Look up a single accent color by name: a `#rrggbb` hex code, the exact
+/// CSS color, or a hand-tuned palette's bright body color. `None` if the
+/// name is unknown.
+pub fn color_by_name(name: &str) -> Option<Color> {
+    if let Ok(color) = name.parse() {
+        return Some(color);
+    }
+    let lower = name.to_ascii_lowercase();
+    is_palette_name(&lower).then(|| palette_by_name(&lower).body_bright)
+}
+
 /// A color palette defines the colors used for a rain effect.
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Palette {
     /// The bright leading character color (head of the rain column)
     pub head: Color,
@@ -89,6 +109,47 @@ pub struct Palette {
 }
 
 impl Palette {
+    /// Blend every color toward `other`; `t` runs 0.0 (self) to 1.0 (other).
+    pub fn lerp(&self, other: &Palette, t: f32) -> Palette {
+        Palette {
+            head: lerp_color(self.head, other.head, t),
+            body_bright: lerp_color(self.body_bright, other.body_bright, t),
+            body_mid: lerp_color(self.body_mid, other.body_mid, t),
+            tail: lerp_color(self.tail, other.tail, t),
+            highlight: lerp_color(self.highlight, other.highlight, t),
+            // The terminal's own background can't be blended, only swapped
+            background: match (self.background, other.background) {
+                (Color::Rgb { .. }, Color::Rgb { .. }) => {
+                    lerp_color(self.background, other.background, t)
+                }
+                _ if t < 0.5 => self.background,
+                _ => other.background,
+            },
+        }
+    }
+
+    /// Start building a palette from individual colors.
+    ///
+    /// ```
+    /// use digitalrain::color::palette::Palette;
+    ///
+    /// let ember = Palette::builder()
+    ///     .body("#ff6a00".parse().unwrap())
+    ///     .highlight("gold".parse().unwrap())
+    ///     .build();
+    /// ```
+    pub fn builder() -> PaletteBuilder {
+        PaletteBuilder::default()
+    }
+
+    /// This palette with its highlight replaced by `color`, if given.
+    pub fn with_highlight(mut self, color: Option<Color>) -> Palette {
+        if let Some(color) = color {
+            self.highlight = color;
+        }
+        self
+    }
+
     /// Classic Matrix green phosphor palette.
     pub fn classic() -> Self {
         Self {
@@ -346,6 +407,83 @@ impl Palette {
     }
 }
 
+/// Builds a `Palette` color by color.
+///
+/// Colors left unset come from the palette auto-generated from the body
+/// color, the same way CSS color names become palettes. Without a body
+/// color (or a gradient), unset colors are classic green.
+#[derive(Clone, Debug, Default)]
+pub struct PaletteBuilder {
+    head: Option<Color>,
+    body_bright: Option<Color>,
+    body_mid: Option<Color>,
+    tail: Option<Color>,
+    highlight: Option<Color>,
+    background: Option<Color>,
+}
+
+impl PaletteBuilder {
+    /// The leading character of each column.
+    pub fn head(mut self, color: Color) -> Self {
+        self.head = Some(color);
+        self
+    }
+
+    /// The body at full brightness; also the base unset colors derive from.
+    pub fn body(mut self, color: Color) -> Self {
+        self.body_bright = Some(color);
+        self
+    }
+
+    /// The body at medium brightness.
+    pub fn body_mid(mut self, color: Color) -> Self {
+        self.body_mid = Some(color);
+        self
+    }
+
+    /// The dimmest end of the trail.
+    pub fn tail(mut self, color: Color) -> Self {
+        self.tail = Some(color);
+        self
+    }
+
+    /// Occasional highlighted characters.
+    pub fn highlight(mut self, color: Color) -> Self {
+        self.highlight = Some(color);
+        self
+    }
+
+    /// Cell background (`Color::Reset` keeps the terminal's).
+    pub fn background(mut self, color: Color) -> Self {
+        self.background = Some(color);
+        self
+    }
+
+    /// Head, body, mid, and tail sampled along `gradient` at the points
+    /// where the trail changes zones (0.0, 0.15, 0.5, and 1.0).
+    pub fn gradient(self, gradient: &Gradient) -> Self {
+        self.head(gradient.at(0.0))
+            .body(gradient.at(0.15))
+            .body_mid(gradient.at(0.5))
+            .tail(gradient.at(1.0))
+    }
+
+    pub fn build(self) -> Palette {
+        let base = match self.body_bright {
+            Some(Color::Rgb { r, g, b }) => generate_from_rgb(r, g, b),
+            _ => Palette::classic(),
+        };
+        Palette {
+            head: self.head.unwrap_or(base.head),
+            body_bright: self.body_bright.unwrap_or(base.body_bright),
+            body_mid: self.body_mid.unwrap_or(base.body_mid),
+            tail: self.tail.unwrap_or(base.tail),
+            highlight: self.highlight.unwrap_or(base.highlight),
+            background: self.background.unwrap_or(base.background),
+        }
+    }
+}
+
 /// Auto-generate a palette from an RGB base color using HSL math.
 ///
 /// For chromatic colors: derives head, body, tail, and highlight from the hue.
@@ -494,6 +632,43 @@ fn generate_achromatic(base_l: f64) -> Palette {
 mod tests {
     use super::*;
 
+    #[test]
+    fn builder_fills_unset_colors_from_the_body() {
+        let coral: Color = "coral".parse().unwrap();
+        let built = Palette::builder()
+            .body(coral)
+            .highlight("#ffffff".parse().unwrap())
+            .build();
+        let generated = palette_by_name("coral");
+        assert_eq!(built.body_bright, coral);
+        assert_eq!(built.tail, generated.tail);
+        assert_eq!(built.highlight, Color::from((255, 255, 255)));
+
+        let gradient =
+            Gradient::even(&[Color::from((255, 255, 255)), Color::from((0, 0, 60))]).unwrap();
+        let faded = Palette::builder().gradient(&gradient).build();
+        assert_eq!(faded.head, Color::from((255, 255, 255)));
+        assert_eq!(faded.tail, Color::from((0, 0, 60)));
+        assert_eq!(Palette::builder().build().head, Palette::classic().head);
+    }
+
+    #[test]
+    fn color_by_name_accepts_hex_css_and_palettes() {
+        assert!(matches!(
+            color_by_name("#0A1b2c"),
+            Some(Color::Rgb {
+                r: 10,
+                g: 27,
+                b: 44
+            })
+        ));
+        assert!(color_by_name("#12345").is_none());
+        assert!(color_by_name("#gg0000").is_none());
+        assert!(color_by_name("White").is_some());
+        assert!(color_by_name("classic").is_some());
+        assert!(color_by_name("nonsense").is_none());
+    }
+
     #[test]
     fn palette_names_not_empty() {
         assert!(!palette_names().is_empty());
@@ -519,6 +694,14 @@ mod tests {
         }
     }
 
+    #[test]
+    fn is_palette_name_accepts_known_and_rejects_unknown() {
+        assert!(is_palette_name("classic"));
+        assert!(is_palette_name("Monochrome"));
+        assert!(is_palette_name("coral"));
+        assert!(!is_palette_name("nonexistent"));
+    }
+
     #[test]
     fn unknown_palette_falls_back_to_classic() {
         let unknown = palette_by_name("nonexistent");
@@ -614,4 +797,21 @@ mod tests {
             names.len()
         );
     }
+
+    #[test]
+    fn lerp_blends_each_color() {
+        let from = Palette::classic();
+        let to = Palette::red();
+        let mid = from.lerp(&to, 0.5);
+        assert!(matches!(mid.head, Color::Rgb { .. }));
+        assert_eq!(
+            crate::color::gradient::color_to_rgb(from.lerp(&to, 1.0).body_bright),
+            crate::color::gradient::color_to_rgb(to.body_bright)
+        );
+        let (r, g, _) = crate::color::gradient::color_to_rgb(mid.body_bright);
+        let (fr, fg, _) = crate::color::gradient::color_to_rgb(from.body_bright);
+        let (tr, tg, _) = crate::color::gradient::color_to_rgb(to.body_bright);
+        assert!(r >= fr.min(tr) && r <= fr.max(tr));
+        assert!(g >= fg.min(tg) && g <= fg.max(tg));
+    }
 }
@@ -0,0 +1,260 @@
+//! The `:` command line: parsing typed commands and tab completion.
+//!
+//! Commands change the running show without leaving it, e.g.
+//! `effect fire`, `color synthwave`, `speed 2.5`, `preset save night`.
+//! Names are checked against the same registries the CLI flags use, so a
+//! command either applies cleanly or reports why it can't.
+
+use crate::color::palette;
+use crate::effects::registry;
+use crate::rain::chars;
+
+/// A parsed command, ready for the main loop to apply.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Effect(String),
+    Color(String),
+    Charset(String),
+    Speed(f64),
+    Density(f64),
+    Mutation(f64),
+    /// `None` toggles
+    Crt(Option<bool>),
+    /// `None` toggles
+    Pause(Option<bool>),
+    /// Block-letter banner text; `None` removes it
+    Banner(Option<String>),
+    SavePreset(String),
+    LoadPreset(String),
+    Quit,
+}
+
+/// Command names, in the order tab completion offers them.
+const COMMANDS: &[&str] = &[
+    "effect", "color", "charset", "speed", "density", "mutation", "crt", "pause", "banner",
+    "preset", "quit",
+];
+
+/// Parse one command line. A leading `:` is optional.
+pub fn parse(line: &str) -> Result<Command, String> {
+    let line = line.trim().trim_start_matches(':');
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let Some((&name, args)) = words.split_first() else {
+        return Err("empty command".to_string());
+    };
+
+    match (name, args) {
+        ("effect", [effect]) => {
+            if registry::effect_names().contains(effect) {
+                Ok(Command::Effect(effect.to_string()))
+            } else {
+                Err(format!("unknown effect '{}'", effect))
+            }
+        }
+        ("color" | "palette", [color]) => {
+            if palette::is_palette_name(color) {
+                Ok(Command::Color(color.to_ascii_lowercase()))
+            } else {
+                Err(format!("unknown color '{}'", color))
+            }
+        }
+        ("charset", [charset]) => {
+            if chars::is_charset_name(charset) {
+                Ok(Command::Charset(charset.to_string()))
+            } else {
+                Err(format!("unknown charset '{}'", charset))
+            }
+        }
+        ("speed", [value]) => parse_multiplier(value).map(Command::Speed),
+        ("density", [value]) => parse_multiplier(value).map(Command::Density),
+        ("mutation", [value]) => parse_rate(value).map(Command::Mutation),
+        ("crt", []) => Ok(Command::Crt(None)),
+        ("crt", ["on"]) => Ok(Command::Crt(Some(true))),
+        ("crt", ["off"]) => Ok(Command::Crt(Some(false))),
+        ("pause", []) => Ok(Command::Pause(None)),
+        ("pause", ["on"]) => Ok(Command::Pause(Some(true))),
+        ("pause", ["off"]) => Ok(Command::Pause(Some(false))),
+        ("banner", ["off"]) => Ok(Command::Banner(None)),
+        ("banner", [_, ..]) => Ok(Command::Banner(Some(args.join(" ")))),
+        ("preset", ["save", name]) => Ok(Command::SavePreset(name.to_string())),
+        ("preset", ["load", name]) => Ok(Command::LoadPreset(name.to_string())),
+        ("quit" | "q", []) => Ok(Command::Quit),
+        _ => Err(usage(name)),
+    }
+}
+
+/// A speed or density multiplier, clamped like the `+`/`-` keys.
+fn parse_multiplier(value: &str) -> Result<f64, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite())
+        .map(|v| v.clamp(0.1, 10.0))
+        .ok_or_else(|| format!("'{}' is not a number", value))
+}
+
+/// A mutation rate, clamped to 0-1 like `--mutation`.
+fn parse_rate(value: &str) -> Result<f64, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite())
+        .map(|v| v.clamp(0.0, 1.0))
+        .ok_or_else(|| format!("'{}' is not a number", value))
+}
+
+/// Usage hint for a command given the wrong arguments.
+fn usage(name: &str) -> String {
+    match name {
+        "effect" => "usage: effect <name>".to_string(),
+        "color" | "palette" => "usage: color <palette>".to_string(),
+        "charset" => "usage: charset <name>".to_string(),
+        "speed" | "density" => format!("usage: {} <0.1-10>", name),
+        "mutation" => "usage: mutation <0-1>".to_string(),
+        "crt" => "usage: crt [on|off]".to_string(),
+        "pause" => "usage: pause [on|off]".to_string(),
+        "banner" => "usage: banner <text>|off".to_string(),
+        "preset" => "usage: preset save|load <name>".to_string(),
+        "quit" | "q" => "usage: quit".to_string(),
+        _ => format!("unknown command '{}'", name),
+    }
+}
+
+/// Result of tab-completing a command line.
+pub struct Completion {
+    /// The line with the last word completed as far as it's unambiguous
+    pub line: String,
+    /// Every candidate when more than one matches (empty otherwise)
+    pub matches: Vec<String>,
+}
+
+/// Complete the last word of `line` against whatever fits in that position:
+/// command names, then effect/palette/charset names, then preset names.
+///
+/// A single match is filled in with a trailing space; several matches are
+/// extended to their longest common prefix and listed in `matches`.
+pub fn complete(line: &str, presets: &[&str]) -> Completion {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    // The word being typed ("" right after a space)
+    let (done, partial) = match words.split_last() {
+        Some((last, rest)) if !line.ends_with(char::is_whitespace) => (rest, *last),
+        _ => (&words[..], ""),
+    };
+
+    let candidates: Vec<&str> = match done {
+        [] => COMMANDS.to_vec(),
+        ["effect"] => registry::effect_names().to_vec(),
+        ["color" | "palette"] => palette::palette_names(),
+        ["charset"] => chars::charset_names().to_vec(),
+        ["crt" | "pause"] => vec!["on", "off"],
+        ["preset"] => vec!["save", "load"],
+        ["preset", "save" | "load"] => presets.to_vec(),
+        _ => Vec::new(),
+    };
+    let partial_lower = partial.to_ascii_lowercase();
+    let matches: Vec<&str> = candidates
+        .into_iter()
+        .filter(|c| c.starts_with(&partial_lower))
+        .collect();
+
+    let unchanged = Completion {
+        line: line.to_string(),
+        matches: Vec::new(),
+    };
+    let completed = match matches.as_slice() {
+        [] => return unchanged,
+        [only] => format!("{} ", only),
+        [first, rest @ ..] => rest
+            .iter()
+            .fold(first.to_string(), |prefix, m| common_prefix(&prefix, m)),
+    };
+
+    let mut new_line = line[..line.len() - partial.len()].to_string();
+    new_line.push_str(&completed);
+    Completion {
+        line: new_line,
+        matches: if matches.len() > 1 {
+            matches.iter().map(|m| m.to_string()).collect()
+        } else {
+            Vec::new()
+        },
+    }
+}
+
+/// The longest prefix `a` and `b` share.
+fn common_prefix(a: &str, b: &str) -> String {
+    a.chars()
+        .zip(b.chars())
+        .take_while(|(x, y)| x == y)
+        .map(|(x, _)| x)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands_and_reports_errors() {
+        assert_eq!(parse("effect fire"), Ok(Command::Effect("fire".into())));
+        assert_eq!(
+            parse(":color SynthWave"),
+            Ok(Command::Color("synthwave".into()))
+        );
+        assert_eq!(parse("speed 2.5"), Ok(Command::Speed(2.5)));
+        assert_eq!(parse("density 50"), Ok(Command::Density(10.0)));
+        assert_eq!(parse("mutation 0"), Ok(Command::Mutation(0.0)));
+        assert_eq!(parse("mutation 3"), Ok(Command::Mutation(1.0)));
+        assert_eq!(parse("crt"), Ok(Command::Crt(None)));
+        assert_eq!(parse("crt off"), Ok(Command::Crt(Some(false))));
+        assert_eq!(
+            parse("  preset save night "),
+            Ok(Command::SavePreset("night".into()))
+        );
+        assert_eq!(parse("q"), Ok(Command::Quit));
+        assert_eq!(parse("pause on"), Ok(Command::Pause(Some(true))));
+        assert_eq!(
+            parse("banner  WAKE UP "),
+            Ok(Command::Banner(Some("WAKE UP".into())))
+        );
+        assert_eq!(parse("banner off"), Ok(Command::Banner(None)));
+
+        assert_eq!(parse("effect warp"), Err("unknown effect 'warp'".into()));
+        assert_eq!(parse("speed fast"), Err("'fast' is not a number".into()));
+        assert_eq!(parse("speed NaN"), Err("'NaN' is not a number".into()));
+        assert_eq!(
+            parse("preset night"),
+            Err("usage: preset save|load <name>".into())
+        );
+        assert_eq!(parse("banner"), Err("usage: banner <text>|off".into()));
+        assert_eq!(parse("warp 9"), Err("unknown command 'warp'".into()));
+        assert_eq!(parse(""), Err("empty command".into()));
+    }
+
+    #[test]
+    fn completes_by_position() {
+        let c = complete("eff", &[]);
+        assert_eq!(c.line, "effect ");
+        assert!(c.matches.is_empty());
+
+        // Ambiguous: extend to the common prefix and list the options
+        let c = complete("c", &[]);
+        assert_eq!(c.line, "c");
+        assert_eq!(c.matches, ["color", "charset", "crt"]);
+        let c = complete("effect c", &[]);
+        assert_eq!(c.line, "effect c");
+        assert_eq!(c.matches, ["classic", "cascade"]);
+        let c = complete("color synth", &[]);
+        assert_eq!(c.line, "color synthwave ");
+
+        let c = complete("preset load ni", &["night", "nimbus", "day"]);
+        assert_eq!(c.line, "preset load ni");
+        assert_eq!(c.matches, ["night", "nimbus"]);
+        assert_eq!(complete("preset load d", &["day"]).line, "preset load day ");
+
+        // Nothing fits: leave the line alone
+        let c = complete("speed 2", &[]);
+        assert_eq!(c.line, "speed 2");
+        assert!(c.matches.is_empty());
+    }
+}
@@ -5,23 +5,52 @@
 //!
 //! Priority resolution: CLI explicit arg > preset value > config [defaults] > hardcoded default
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use serde::{Deserialize, Serialize};
 
+use crate::audio::onset::DEFAULT_SENSITIVITY;
+use crate::audio::{BeatAction, DEFAULT_SAMPLE_RATE};
+use crate::buffer::Region;
+use crate::color::Color;
+use crate::crt::{DEFAULT_VIGNETTE_RADIUS, DEFAULT_VIGNETTE_STRENGTH};
+use crate::dejavu;
+use crate::effects::params::{EffectSpec, parse_param};
+use crate::formats;
+use crate::logging::LogLevel;
+use crate::overlay::{BannerPosition, LogoPosition};
+use crate::post::{BRIGHTNESS_RANGE, CONTRAST_RANGE, GAMMA_RANGE};
+use crate::power::{self, PowerSaverMode};
+use crate::rain::chars::CharacterPool;
+use crate::rain::stripes::{StripeDirection, is_band_name};
+use crate::rain::{
+    DEFAULT_GUST_STRENGTH, DEFAULT_HIGHLIGHT_RATE, DEFAULT_MUTATION, DeadZoneMode, MAX_GUST_PERIOD,
+    MAX_SPACING,
+};
+use crate::react::ReactSource;
+use crate::serve;
+use crate::transition::TransitionKind;
+
 // ---------- CLI Definition ----------
 
 /// Terminal-based Matrix digital rain effect.
 #[derive(Parser)]
 #[command(name = "digital_rain", version, about)]
+#[command(group = clap::ArgGroup::new("server").args(["serve", "ssh"]))]
 pub struct Cli {
     /// Effect to display
     #[arg(short, long)]
     pub effect: Option<String>,
 
+    /// Tune an effect parameter, e.g. depth=0.8 (see --list-effects; repeatable)
+    #[arg(long = "param", value_name = "NAME=VALUE", value_parser = parse_param)]
+    pub params: Vec<(String, toml::Value)>,
+
     /// Animation speed multiplier (0.1 = slow, 1.0 = normal, 3.0 = fast)
     #[arg(short, long, value_parser = clap::value_parser!(f64))]
     pub speed: Option<f64>,
@@ -30,18 +59,79 @@ pub struct Cli {
     #[arg(short, long, value_parser = clap::value_parser!(f64))]
     pub density: Option<f64>,
 
+    /// Minimum number of empty columns between falling columns (0 = none)
+    #[arg(long, value_name = "GAP", value_parser = clap::value_parser!(u16))]
+    pub spacing: Option<u16>,
+
+    /// Chance a rain character changes each frame (0.0 = static, 1.0 = frantic)
+    #[arg(long, value_name = "RATE", value_parser = clap::value_parser!(f64))]
+    pub mutation: Option<f64>,
+
+    /// Seconds between rain gusts, when density surges (0 = steady rain)
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(f64))]
+    pub gust_period: Option<f64>,
+
+    /// How hard gusts swing between drizzle and downpour (0.0-1.0)
+    #[arg(long, value_name = "STRENGTH", value_parser = clap::value_parser!(f64))]
+    pub gust_strength: Option<f64>,
+
+    /// Chance a new rain character is a highlight (0 = no highlights)
+    #[arg(long, value_name = "RATE", value_parser = clap::value_parser!(f64))]
+    pub highlight_rate: Option<f64>,
+
+    /// Highlight color: a name, CSS color, or #rrggbb (default: the palette's)
+    #[arg(long, value_name = "COLOR")]
+    pub highlight_color: Option<String>,
+
     /// Color palette
     #[arg(short, long)]
     pub color: Option<String>,
 
+    /// Solid color to draw the rain over (e.g. navy, #001a00) instead of the terminal's own
+    #[arg(long, value_name = "COLOR")]
+    pub background: Option<Color>,
+
+    /// Brighten or darken the final frame (0.1-2.0, 1.0 = unchanged)
+    #[arg(long, value_name = "LEVEL")]
+    pub brightness: Option<f64>,
+
+    /// Stretch or flatten the final frame's contrast (0.1-3.0, 1.0 = unchanged)
+    #[arg(long, value_name = "LEVEL")]
+    pub contrast: Option<f64>,
+
+    /// Gamma for the final frame (0.2-5.0; above 1.0 lifts dark colors)
+    #[arg(long, value_name = "LEVEL")]
+    pub gamma: Option<f64>,
+
+    /// Drift between two palettes and back, once per period (e.g. classic:purple:2m)
+    #[arg(long, value_name = "FROM:TO:PERIOD", value_parser = parse_color_morph)]
+    pub color_morph: Option<ColorMorph>,
+
     /// Character set to use
     #[arg(long)]
     pub charset: Option<String>,
 
+    /// Rain these characters (whitespace ignored); registered as the "custom" charset
+    #[arg(long, value_name = "CHARS")]
+    pub charset_chars: Option<String>,
+
+    /// Like --charset-chars, reading the characters from a file
+    #[arg(long, value_name = "PATH", conflicts_with = "charset_chars")]
+    pub charset_file: Option<PathBuf>,
+
+    /// Stream a file's text down the columns (source code, a novel, logs)
+    #[arg(long, value_name = "PATH")]
+    pub source_file: Option<PathBuf>,
+
     /// Target frames per second
     #[arg(long, value_parser = clap::value_parser!(u32))]
     pub fps: Option<u32>,
 
+    /// Hold the --fps rate even when the terminal can't keep up, instead of
+    /// lowering it until frames fit
+    #[arg(long)]
+    pub fixed_fps: bool,
+
     /// List available effects and exit
     #[arg(long)]
     pub list_effects: bool,
@@ -66,6 +156,10 @@ pub struct Cli {
     #[arg(long)]
     pub forward: bool,
 
+    /// Keep glyphs fixed where they appear; only the light falls (as in the film)
+    #[arg(long)]
+    pub static_glyphs: bool,
+
     /// Enable CRT monitor simulation (scanlines, phosphor glow, flicker, noise)
     #[arg(long)]
     pub crt: bool,
@@ -74,6 +168,37 @@ pub struct Cli {
     #[arg(long, value_parser = clap::value_parser!(f64))]
     pub crt_intensity: Option<f64>,
 
+    /// CRT screen curvature (0.0 = flat, 1.0 = strongly curved)
+    #[arg(long, value_parser = clap::value_parser!(f64))]
+    pub crt_curvature: Option<f64>,
+
+    /// CRT phosphor persistence: how long bright cells leave ghost trails
+    /// (0.0 = none, 1.0 = very long)
+    #[arg(long, value_parser = clap::value_parser!(f64))]
+    pub crt_persistence: Option<f64>,
+
+    /// Darken the CRT picture toward the edges
+    #[arg(long)]
+    pub crt_vignette: bool,
+
+    /// Post-processing filters to stack, in order (crt, bloom, vignette,
+    /// mirror, hue-rotate), e.g. `--post bloom,crt`
+    #[arg(
+        long,
+        value_name = "FILTERS",
+        value_delimiter = ',',
+        value_parser = clap::builder::PossibleValuesParser::new(crate::post::FILTER_NAMES)
+    )]
+    pub post: Vec<String>,
+
+    /// Distance from the center where the vignette starts (0.0 = center, 1.0 = corner)
+    #[arg(long, value_name = "RADIUS", value_parser = clap::value_parser!(f64))]
+    pub crt_vignette_radius: Option<f64>,
+
+    /// How dark the vignette gets at the corners (0.0-1.0)
+    #[arg(long, value_name = "STRENGTH", value_parser = clap::value_parser!(f64))]
+    pub crt_vignette_strength: Option<f64>,
+
     /// Path to config file (default: platform config dir)
     #[arg(long)]
     pub config: Option<String>,
@@ -89,6 +214,421 @@ pub struct Cli {
     /// List available presets from the config file and exit
     #[arg(long)]
     pub list_presets: bool,
+
+    /// Delete a named preset from the config file and exit
+    #[arg(long, value_name = "NAME")]
+    pub delete_preset: Option<String>,
+
+    /// Rename a preset (format: old:new) and exit
+    #[arg(long, value_name = "OLD:NEW")]
+    pub rename_preset: Option<String>,
+
+    /// Print a preset as TOML and exit
+    #[arg(long, value_name = "NAME")]
+    pub show_preset: Option<String>,
+
+    /// Let preset edits overwrite a config file that doesn't parse (its old contents are kept in the .bak file)
+    #[arg(long)]
+    pub force: bool,
+
+    /// Validate the config file (unknown keys, bad names, out-of-range values) and exit
+    #[arg(long)]
+    pub check_config: bool,
+
+    /// Cycle through a named playlist of presets from the config file
+    #[arg(long, value_name = "NAME")]
+    pub playlist: Option<String>,
+
+    /// Exit after this much wall-clock time (e.g. 90, 45s, 2m30s, 1h)
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub duration: Option<f64>,
+
+    /// Fade to black over the last seconds of --duration before exiting
+    #[arg(long, requires = "duration")]
+    pub exit_fade: bool,
+
+    /// Render N frames without a terminal and write them to --out, then exit
+    #[arg(long, value_name = "N", requires = "out")]
+    pub render_frames: Option<u32>,
+
+    /// Output directory for --render-frames
+    #[arg(long, value_name = "DIR", requires = "render_frames")]
+    pub out: Option<PathBuf>,
+
+    /// With --render-frames, also write each frame as plain text (.txt)
+    #[arg(long, requires = "render_frames")]
+    pub plain_text: bool,
+
+    /// Smoke-test every effect without a terminal (for CI), then exit 1 if any run failed
+    #[arg(long)]
+    pub headless: bool,
+
+    /// Frames each --headless run renders
+    #[arg(long, value_name = "N", default_value_t = 90, requires = "headless")]
+    pub frames: u32,
+
+    /// Render --duration worth of animation to an animated GIF, then exit
+    #[arg(long, value_name = "PATH", requires = "duration")]
+    pub export_gif: Option<PathBuf>,
+
+    /// Render the effect for --duration (default 3s) and save the last frame as a PNG, then exit
+    #[arg(long, value_name = "PATH")]
+    pub export_png: Option<PathBuf>,
+
+    /// Render the effect for --duration (default 3s) and save the last frame as an SVG of text, then exit
+    #[arg(long, value_name = "PATH", conflicts_with = "export_png")]
+    pub export_svg: Option<PathBuf>,
+
+    /// Stream the rain over telnet to anyone who connects, e.g. ":2323" (each viewer gets their own animation)
+    #[arg(long, value_name = "ADDR")]
+    pub serve: Option<String>,
+
+    /// Most --serve or --ssh viewers at once; others are turned away
+    #[arg(long, value_name = "N", requires = "server", default_value_t = serve::DEFAULT_MAX_CLIENTS)]
+    pub serve_max_clients: usize,
+
+    /// Serve the rain over SSH to anyone who connects, e.g. ":2222" (no login; needs the ssh feature)
+    #[arg(long, value_name = "ADDR", conflicts_with = "serve")]
+    pub ssh: Option<String>,
+
+    /// Host key for --ssh, created on first use (default: ssh_host_ed25519_key beside the config file)
+    #[arg(long, value_name = "PATH", requires = "ssh")]
+    pub ssh_host_key: Option<PathBuf>,
+
+    /// Only draw inside this part of the terminal: x,y,w,h (arrow keys move it)
+    #[arg(long, value_name = "X,Y,W,H")]
+    pub region: Option<Region>,
+
+    /// Draw TEXT in large block letters over the effect
+    #[arg(long, value_name = "TEXT")]
+    pub banner: Option<String>,
+
+    /// Vertical position of the --banner text
+    #[arg(
+        long,
+        value_name = "POSITION",
+        default_value = "center",
+        requires = "banner"
+    )]
+    pub banner_position: BannerPosition,
+
+    /// Color of the --banner text (CSS color or palette name; default: the rain's color)
+    #[arg(long, value_name = "COLOR", requires = "banner")]
+    pub banner_color: Option<String>,
+
+    /// Slowly pulse the --banner text's brightness
+    #[arg(long, requires = "banner")]
+    pub banner_pulse: bool,
+
+    /// Composite a PNG (or PPM) image over the effect as a watermark
+    #[arg(long, value_name = "PATH")]
+    pub logo: Option<PathBuf>,
+
+    /// Where to place the --logo image
+    #[arg(
+        long,
+        value_name = "POSITION",
+        default_value = "bottom-right",
+        requires = "logo"
+    )]
+    pub logo_position: LogoPosition,
+
+    /// Opacity of the --logo image (0.0-1.0)
+    #[arg(long, value_name = "VALUE", default_value_t = 1.0, requires = "logo")]
+    pub logo_opacity: f64,
+
+    /// Width of the --logo image in cells (default: up to a third of the screen)
+    #[arg(long, value_name = "COLS", requires = "logo")]
+    pub logo_width: Option<u16>,
+
+    /// Keep rain out of this area: x,y,w,h (repeatable)
+    #[arg(long = "dead-zone", value_name = "X,Y,W,H")]
+    pub dead_zones: Vec<Region>,
+
+    /// What rain does at a dead zone: stop there, or pass behind it
+    #[arg(long, value_name = "MODE")]
+    pub dead_zone_mode: Option<DeadZoneMode>,
+
+    /// Split the screen into bands of palettes, e.g. red,gold,classic (or a flag: pride)
+    #[arg(long, value_name = "PALETTES", value_delimiter = ',')]
+    pub stripes: Vec<String>,
+
+    /// Which way the --stripes bands run
+    #[arg(long, value_name = "DIRECTION")]
+    pub stripe_direction: Option<StripeDirection>,
+
+    /// Keep a one-cell halo around the banner, logo, and countdown clear of rain
+    #[arg(long)]
+    pub rain_halo: bool,
+
+    /// Count down from DURATION in large digits (e.g. 25m), then flash
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub countdown: Option<f64>,
+
+    /// Switch to this effect when the --countdown expires
+    #[arg(long, value_name = "NAME", requires = "countdown")]
+    pub countdown_effect: Option<String>,
+
+    /// Chance per minute of the déjà vu easter egg (0.0-1.0)
+    #[arg(long, value_name = "P", default_value_t = dejavu::DEFAULT_CHANCE)]
+    pub deja_vu_chance: f64,
+
+    /// Turn off easter eggs
+    #[arg(long)]
+    pub no_easter_eggs: bool,
+
+    /// Show lines from a named pipe (or `-` for stdin) as notification toasts
+    #[arg(long, value_name = "PATH")]
+    pub notify: Option<PathBuf>,
+
+    /// Rain each new line of a log file (or `-` for stdin) as falling text
+    #[arg(long, value_name = "PATH")]
+    pub tail: Option<PathBuf>,
+
+    /// Draw --tail lines matching this regular expression in the highlight color
+    #[arg(long, value_name = "REGEX", requires = "tail")]
+    pub tail_highlight: Option<String>,
+
+    /// Read newline-delimited JSON commands from stdin and apply them live, e.g. {"cmd":"set_effect","name":"fire"}
+    #[arg(long)]
+    pub control_stdin: bool,
+
+    /// Append per-second performance rows (FPS, frame time percentiles, cells and bytes flushed) to this CSV file
+    #[arg(long, value_name = "PATH")]
+    pub metrics_file: Option<PathBuf>,
+
+    /// Diff and write frames to the terminal on a separate thread, so a slow
+    /// terminal (e.g. over SSH) skips frames instead of stalling the
+    /// animation and input
+    #[arg(long, conflicts_with = "record")]
+    pub render_thread: bool,
+
+    /// On exit, print a frame-time histogram, the dropped-frame count, and
+    /// the slowest frames with the phase (update, render, post, flush) that
+    /// took the time
+    #[arg(long)]
+    pub jank_report: bool,
+
+    /// Write a debug log to this file (appending): settings, effect
+    /// switches, resizes, the terminal's capabilities, and warnings, which
+    /// can't be seen on stderr while the animation is up
+    #[arg(long, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+
+    /// How much --log-file records; debug adds config details and dropped
+    /// frames, trace adds every frame's phase timings
+    #[arg(
+        long,
+        value_enum,
+        value_name = "LEVEL",
+        default_value_t,
+        requires = "log_file"
+    )]
+    pub log_level: LogLevel,
+
+    /// Switch to a low-power profile (15 fps, half the columns, no CRT or
+    /// post-processing): on battery or after --power-saver-idle without input
+    /// (auto), always (on), or never (off)
+    #[arg(long, value_name = "MODE", default_value = "auto")]
+    pub power_saver: PowerSaverMode,
+
+    /// With --power-saver auto, how long without input before saving power
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value_t = power::DEFAULT_IDLE)]
+    pub power_saver_idle: f64,
+
+    /// Take the same JSON commands on a Unix socket (a named pipe on Windows), for `digital_rain ctl` (default path: $XDG_RUNTIME_DIR/digitalrain.sock)
+    #[arg(long, value_name = "PATH", num_args = 0..=1)]
+    pub control_socket: Option<Option<PathBuf>>,
+
+    #[command(subcommand)]
+    pub subcommand: Option<CliCommand>,
+
+    /// React to music: raw s16le mono PCM from a pipe or file (`-` for stdin)
+    #[arg(long, value_name = "PATH")]
+    pub audio: Option<PathBuf>,
+
+    /// Sample rate of the --audio input, in Hz
+    #[arg(long, value_name = "HZ", default_value_t = DEFAULT_SAMPLE_RATE, requires = "audio")]
+    pub audio_rate: u32,
+
+    /// What each beat in the --audio input does (comma-separated)
+    #[arg(
+        long,
+        value_name = "ACTIONS",
+        value_delimiter = ',',
+        default_value = "flash",
+        requires = "audio"
+    )]
+    pub beat_action: Vec<BeatAction>,
+
+    /// How easily a sound counts as a beat (0.0 = only hard hits, 1.0 = nearly everything)
+    #[arg(long, value_name = "VALUE", default_value_t = DEFAULT_SENSITIVITY, requires = "audio")]
+    pub beat_sensitivity: f64,
+
+    /// Show CPU, RAM, and network usage in the top-right corner
+    #[arg(long)]
+    pub sysmon: bool,
+
+    /// Let system load drive the rain: heavy load pours, idle drizzles
+    #[arg(long, value_name = "SOURCE")]
+    pub react: Option<ReactSource>,
+
+    /// Repository whose history the gitrain effect plays (default: current directory)
+    #[arg(long, value_name = "PATH")]
+    pub git_repo: Option<PathBuf>,
+
+    /// Text the ghostwriter effect types, a line at a time (default: built-in quotes)
+    #[arg(long, value_name = "PATH")]
+    pub text_file: Option<PathBuf>,
+
+    /// Pick the effect, palette, and density from a city's current weather
+    #[arg(long, value_name = "CITY")]
+    pub weather: Option<String>,
+
+    /// How often --weather checks the conditions again
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = "15m", requires = "weather")]
+    pub weather_refresh: f64,
+
+    /// Play the "Wake up, Neo..." boot sequence before the effect starts
+    #[arg(long)]
+    pub intro: bool,
+
+    /// How effect switches look (default: crossfade, random for auto-cycle)
+    #[arg(long, value_name = "STYLE")]
+    pub transition: Option<TransitionKind>,
+
+    /// Screensaver mode: any key press or mouse movement exits immediately
+    #[arg(long)]
+    pub screensaver: bool,
+
+    /// Leave the mouse to the terminal (keeps text selection working, but
+    /// turns off the scroll-wheel speed/density controls)
+    #[arg(long)]
+    pub no_mouse: bool,
+
+    /// With --screensaver, wait for this much input inactivity before starting
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, requires = "screensaver")]
+    pub idle: Option<f64>,
+
+    /// Record the session to an asciicast v2 file (replay with asciinema or agg)
+    #[arg(long, value_name = "PATH")]
+    pub record: Option<PathBuf>,
+
+    /// Record the seed, command line, and input to a session file for --replay
+    #[arg(long, value_name = "PATH")]
+    pub record_session: Option<PathBuf>,
+
+    /// Play back a session recorded with --record-session
+    #[arg(long, value_name = "PATH", conflicts_with = "record_session")]
+    pub replay: Option<PathBuf>,
+
+    /// Lead a synchronized group: send this session to --sync-follow instances connecting to ADDR, e.g. ":7700"
+    #[arg(long, value_name = "ADDR")]
+    pub sync_leader: Option<String>,
+
+    /// Show the same animation as the --sync-leader at HOST:PORT, frame for frame
+    #[arg(long, value_name = "HOST:PORT", conflicts_with_all = ["replay", "sync_leader"])]
+    pub sync_follow: Option<String>,
+
+    /// Seed the random number generators for reproducible animations
+    #[arg(long, value_name = "SEED")]
+    pub seed: Option<u64>,
+
+    /// Exclude names from randomization (e.g. effects=ocean,fire colors=snow,ivory)
+    #[arg(long, value_name = "KIND=LIST", num_args = 1..)]
+    pub random_exclude: Vec<String>,
+}
+
+/// Subcommands of `digital_rain`.
+#[derive(Subcommand)]
+pub enum CliCommand {
+    /// Send a command to a running instance started with --control-socket, e.g. `ctl color ocean`
+    Ctl {
+        /// The instance's socket (default: the --control-socket default)
+        #[arg(long, value_name = "PATH")]
+        socket: Option<PathBuf>,
+
+        /// A command as typed on the `:` line (`effect fire`, `banner HELLO`), or one JSON command
+        #[arg(required = true, num_args = 1.., allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+}
+
+/// Parse a human-friendly duration into seconds.
+///
+/// Accepts a bare number of seconds (`90`, `1.5`) or a sequence of
+/// number+unit pairs with units `ms`, `s`, `m`, `h` (`2m30s`, `1h 15m`).
+pub fn parse_duration(input: &str) -> Result<f64, String> {
+    let input = input.trim();
+    if let Ok(secs) = input.parse::<f64>() {
+        return checked_duration(secs, input);
+    }
+
+    let mut total = 0.0;
+    let mut rest = input;
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..number_len]
+            .parse()
+            .map_err(|_| format!("invalid duration '{}'", input))?;
+        rest = &rest[number_len..];
+
+        let unit_len = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        let scale = match &rest[..unit_len] {
+            "ms" => 0.001,
+            "s" | "sec" => 1.0,
+            "m" | "min" => 60.0,
+            "h" | "hr" => 3600.0,
+            "" => return Err(format!("missing unit in duration '{}'", input)),
+            unit => return Err(format!("unknown duration unit '{}'", unit)),
+        };
+        total += number * scale;
+        rest = rest[unit_len..].trim_start();
+    }
+    checked_duration(total, input)
+}
+
+/// `secs` if it's positive and fits in a `Duration` (the timers built from
+/// it would panic otherwise).
+fn checked_duration(secs: f64, input: &str) -> Result<f64, String> {
+    if secs.is_nan() || secs <= 0.0 {
+        return Err("duration must be positive".to_string());
+    }
+    Duration::try_from_secs_f64(secs)
+        .map(|_| secs)
+        .map_err(|_| format!("duration '{}' is too long", input))
+}
+
+/// A slow drift between two palettes (`--color-morph`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorMorph {
+    pub from: String,
+    pub to: String,
+    /// Seconds from `from` to `to` and back
+    pub period: f64,
+}
+
+/// Parse `--color-morph FROM:TO:PERIOD`, where the period is a duration
+/// (`90`, `45s`, `2m`).
+pub fn parse_color_morph(input: &str) -> Result<ColorMorph, String> {
+    let parts: Vec<&str> = input.split(':').map(str::trim).collect();
+    let [from, to, period] = parts[..] else {
+        return Err(format!("expected FROM:TO:PERIOD but got '{}'", input));
+    };
+    for name in [from, to] {
+        if !crate::color::palette::is_palette_name(name) {
+            return Err(format!("unknown palette '{}'", name));
+        }
+    }
+    Ok(ColorMorph {
+        from: from.to_string(),
+        to: to.to_string(),
+        period: parse_duration(period)?,
+    })
 }
 
 // ---------- TOML Config File Structs ----------
@@ -100,6 +640,41 @@ pub struct ConfigFile {
     pub defaults: ConfigDefaults,
     #[serde(default)]
     pub presets: HashMap<String, PresetConfig>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub playlists: HashMap<String, PlaylistConfig>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub effect_defaults: HashMap<String, EffectDefaults>,
+    #[serde(default, skip_serializing_if = "RandomConfig::is_empty")]
+    pub random: RandomConfig,
+    #[serde(default, skip_serializing_if = "OverlayConfig::is_empty")]
+    pub overlay: OverlayConfig,
+    /// `[keys]`: action name to key (or list of keys)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub keys: BTreeMap<String, KeyList>,
+    #[serde(default, skip_serializing_if = "PostConfig::is_empty")]
+    pub post: PostConfig,
+    #[serde(default, skip_serializing_if = "RainConfig::is_empty")]
+    pub rain: RainConfig,
+    /// `[charsets]`: user-defined charset name to its characters
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub charsets: BTreeMap<String, String>,
+}
+
+/// One key or several, as written in `[keys]`.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyList {
+    fn to_vec(&self) -> Vec<String> {
+        match self {
+            KeyList::One(key) => vec![key.clone()],
+            KeyList::Many(keys) => keys.clone(),
+        }
+    }
 }
 
 /// Default settings applied when no CLI or preset overrides.
@@ -108,49 +683,470 @@ pub struct ConfigDefaults {
     pub effect: Option<String>,
     pub speed: Option<f64>,
     pub density: Option<f64>,
+    pub spacing: Option<u16>,
+    pub mutation: Option<f64>,
+    pub gust_period: Option<f64>,
+    pub gust_strength: Option<f64>,
+    pub highlight_rate: Option<f64>,
+    pub highlight_color: Option<String>,
     pub color: Option<String>,
     pub charset: Option<String>,
     pub fps: Option<u32>,
     pub crt: Option<bool>,
     pub crt_intensity: Option<f64>,
+    pub crt_curvature: Option<f64>,
+    pub crt_persistence: Option<f64>,
+    pub crt_vignette: Option<bool>,
+    pub crt_vignette_radius: Option<f64>,
+    pub crt_vignette_strength: Option<f64>,
 }
 
 /// A named preset: partial config that can override defaults.
-#[derive(Deserialize, Serialize, Default)]
+///
+/// A preset may name another preset in `extends` to inherit its fields;
+/// any field set on the child overrides the inherited value.
+#[derive(Deserialize, Serialize, Default, Clone)]
 pub struct PresetConfig {
+    pub extends: Option<String>,
     pub effect: Option<String>,
     pub speed: Option<f64>,
     pub density: Option<f64>,
+    pub spacing: Option<u16>,
+    pub mutation: Option<f64>,
+    pub gust_period: Option<f64>,
+    pub gust_strength: Option<f64>,
+    pub highlight_rate: Option<f64>,
+    pub highlight_color: Option<String>,
     pub color: Option<String>,
     pub charset: Option<String>,
     pub fps: Option<u32>,
     pub crt: Option<bool>,
     pub crt_intensity: Option<f64>,
+    pub crt_curvature: Option<f64>,
+    pub crt_persistence: Option<f64>,
+    pub crt_vignette: Option<bool>,
+    pub crt_vignette_radius: Option<f64>,
+    pub crt_vignette_strength: Option<f64>,
+}
+
+/// Settings applied whenever a specific effect becomes active
+/// (`[effect_defaults.<effect>]`), e.g. fire always using the fire palette.
+#[derive(Deserialize, Serialize, Default, Clone)]
+pub struct EffectDefaults {
+    pub speed: Option<f64>,
+    pub density: Option<f64>,
+    pub spacing: Option<u16>,
+    pub mutation: Option<f64>,
+    pub gust_period: Option<f64>,
+    pub gust_strength: Option<f64>,
+    pub highlight_rate: Option<f64>,
+    pub highlight_color: Option<String>,
+    pub color: Option<String>,
+    pub charset: Option<String>,
+    pub crt: Option<bool>,
+    pub crt_intensity: Option<f64>,
+    pub crt_curvature: Option<f64>,
+    pub crt_persistence: Option<f64>,
+    pub crt_vignette: Option<bool>,
+    pub crt_vignette_radius: Option<f64>,
+    pub crt_vignette_strength: Option<f64>,
+}
+
+/// Constraints for `--random`, `r`, and auto-cycle (`[random]`).
+///
+/// Include lists restrict the pool (empty = everything); exclude lists are
+/// removed from it afterwards. Ranges are `[min, max]`.
+#[derive(Deserialize, Serialize, Default, Clone)]
+pub struct RandomConfig {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include_effects: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_effects: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include_colors: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_colors: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include_charsets: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_charsets: Vec<String>,
+    pub speed_range: Option<[f64; 2]>,
+    pub density_range: Option<[f64; 2]>,
+}
+
+impl RandomConfig {
+    fn is_empty(&self) -> bool {
+        self.include_effects.is_empty()
+            && self.exclude_effects.is_empty()
+            && self.include_colors.is_empty()
+            && self.exclude_colors.is_empty()
+            && self.include_charsets.is_empty()
+            && self.exclude_charsets.is_empty()
+            && self.speed_range.is_none()
+            && self.density_range.is_none()
+    }
+}
+
+/// The `[overlay]` section: theme for the help, status, stats, and other
+/// overlay boxes. Colors are CSS names, palette names, or `#rrggbb`.
+#[derive(Deserialize, Serialize, Default, Clone)]
+pub struct OverlayConfig {
+    pub background: Option<String>,
+    pub text: Option<String>,
+    pub title: Option<String>,
+    /// `none`, `single`, `double`, or `rounded`
+    pub border: Option<String>,
+    /// 1.0 = solid boxes; lower lets the rain show through
+    pub opacity: Option<f64>,
+    /// Run the CRT filter over the boxes too, not just the scene
+    pub crt_affects_overlays: Option<bool>,
+}
+
+impl OverlayConfig {
+    fn is_empty(&self) -> bool {
+        self.background.is_none()
+            && self.text.is_none()
+            && self.title.is_none()
+            && self.border.is_none()
+            && self.opacity.is_none()
+            && self.crt_affects_overlays.is_none()
+    }
+}
+
+/// The `[post]` section: post-processing filters, in the order they run.
+#[derive(Deserialize, Serialize, Default, Clone)]
+pub struct PostConfig {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filters: Vec<String>,
+}
+
+impl PostConfig {
+    fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+}
+
+/// The `[rain]` section: where the rain may fall.
+#[derive(Deserialize, Serialize, Default, Clone)]
+pub struct RainConfig {
+    /// Areas kept clear of rain, each `"x,y,w,h"`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dead_zones: Vec<String>,
+    /// `terminate` or `split`
+    pub dead_zone_mode: Option<String>,
+    /// Keep a halo around the banner, logo, and countdown clear of rain
+    pub halo: Option<bool>,
+    /// Palettes for striped rain, one per band (or a single flag name)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stripes: Vec<String>,
+    /// `horizontal` or `vertical`
+    pub stripe_direction: Option<String>,
+}
+
+impl RainConfig {
+    fn is_empty(&self) -> bool {
+        self.dead_zones.is_empty()
+            && self.dead_zone_mode.is_none()
+            && self.halo.is_none()
+            && self.stripes.is_empty()
+            && self.stripe_direction.is_none()
+    }
+}
+
+/// A named playlist: an ordered list of presets to cycle through.
+#[derive(Deserialize, Serialize, Default, Clone)]
+pub struct PlaylistConfig {
+    #[serde(default)]
+    pub entries: Vec<PlaylistEntryConfig>,
+}
+
+/// One step of a playlist: which preset to show and for how long.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct PlaylistEntryConfig {
+    pub preset: String,
+    /// Seconds to show this preset before moving on (default 30)
+    pub duration: Option<f64>,
+}
+
+impl PresetConfig {
+    /// Capture live runtime values as a fully-specified preset.
+    ///
+    /// Speed, density, and CRT state are passed separately because they can be
+    /// changed with hotkeys after startup, so `config` may be stale.
+    pub fn from_runtime(config: &Config, speed: f64, density: f64, crt: bool) -> Self {
+        Self {
+            extends: None,
+            effect: Some(config.effect_name.clone()),
+            speed: Some(speed),
+            density: Some(density),
+            spacing: Some(config.spacing),
+            mutation: Some(config.mutation_rate),
+            gust_period: Some(config.gust_period),
+            gust_strength: Some(config.gust_strength),
+            highlight_rate: Some(config.highlight_rate),
+            highlight_color: config.highlight_color.clone(),
+            color: Some(config.palette_name.clone()),
+            charset: Some(config.charset_name.clone()),
+            fps: Some(config.target_fps),
+            crt: Some(crt),
+            crt_intensity: Some(config.crt_intensity),
+            crt_curvature: Some(config.crt_curvature),
+            crt_persistence: Some(config.crt_persistence),
+            crt_vignette: Some(config.crt_vignette),
+            crt_vignette_radius: Some(config.crt_vignette_radius),
+            crt_vignette_strength: Some(config.crt_vignette_strength),
+        }
+    }
+
+    /// Fill any unset fields from `base`. Fields already set on `self` win.
+    fn inherit_from(self, base: &PresetConfig) -> PresetConfig {
+        PresetConfig {
+            extends: base.extends.clone(),
+            effect: self.effect.or_else(|| base.effect.clone()),
+            speed: self.speed.or(base.speed),
+            density: self.density.or(base.density),
+            spacing: self.spacing.or(base.spacing),
+            mutation: self.mutation.or(base.mutation),
+            gust_period: self.gust_period.or(base.gust_period),
+            gust_strength: self.gust_strength.or(base.gust_strength),
+            highlight_rate: self.highlight_rate.or(base.highlight_rate),
+            highlight_color: self
+                .highlight_color
+                .or_else(|| base.highlight_color.clone()),
+            color: self.color.or_else(|| base.color.clone()),
+            charset: self.charset.or_else(|| base.charset.clone()),
+            fps: self.fps.or(base.fps),
+            crt: self.crt.or(base.crt),
+            crt_intensity: self.crt_intensity.or(base.crt_intensity),
+            crt_curvature: self.crt_curvature.or(base.crt_curvature),
+            crt_persistence: self.crt_persistence.or(base.crt_persistence),
+            crt_vignette: self.crt_vignette.or(base.crt_vignette),
+            crt_vignette_radius: self.crt_vignette_radius.or(base.crt_vignette_radius),
+            crt_vignette_strength: self.crt_vignette_strength.or(base.crt_vignette_strength),
+        }
+    }
+}
+
+impl ConfigFile {
+    /// The `[keys]` section as action name to key specs.
+    pub fn key_overrides(&self) -> BTreeMap<String, Vec<String>> {
+        self.keys
+            .iter()
+            .map(|(action, keys)| (action.clone(), keys.to_vec()))
+            .collect()
+    }
+
+    /// Look up a preset by name and flatten its `extends` chain into a single
+    /// preset with every inherited field filled in.
+    ///
+    /// Returns an error if the preset (or any preset it extends) doesn't exist,
+    /// or if the chain loops back on itself (e.g. a extends b, b extends a).
+    pub fn resolve_preset(&self, name: &str) -> Result<PresetConfig, String> {
+        let mut merged = self
+            .presets
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("preset '{}' not found", name))?;
+        check_inheritance(name, |n| self.presets.get(n)?.extends.clone())?;
+
+        let mut chain = vec![name.to_string()];
+        while let Some(base_name) = merged.extends.clone() {
+            let base = self.presets.get(&base_name).ok_or_else(|| {
+                format!(
+                    "preset '{}' extends unknown preset '{}'",
+                    chain.last().unwrap(),
+                    base_name
+                )
+            })?;
+            merged = merged.inherit_from(base);
+            chain.push(base_name);
+        }
+
+        Ok(merged)
+    }
+
+    /// Preset `name` as a `[presets.<name>]` table that can be pasted into a
+    /// config file (the name is quoted when it needs to be).
+    pub fn preset_toml(&self, name: &str) -> Result<String, String> {
+        let preset = self
+            .presets
+            .get(name)
+            .ok_or_else(|| format!("preset '{}' not found", name))?;
+        let table = BTreeMap::from([("presets", BTreeMap::from([(name, preset)]))]);
+        toml::to_string_pretty(&table).map_err(|e| format!("Could not serialize preset: {}", e))
+    }
+
+    /// Remove a preset. Errors if it doesn't exist, other presets extend
+    /// it, or playlists play it.
+    pub fn delete_preset(&mut self, name: &str) -> Result<(), String> {
+        if !self.presets.contains_key(name) {
+            return Err(format!("preset '{}' not found", name));
+        }
+
+        // Refuse to leave dangling `extends` references behind
+        let mut children: Vec<&str> = self
+            .presets
+            .iter()
+            .filter(|(_, p)| p.extends.as_deref() == Some(name))
+            .map(|(n, _)| n.as_str())
+            .collect();
+        if !children.is_empty() {
+            children.sort();
+            return Err(format!(
+                "preset '{}' is extended by: {}",
+                name,
+                children.join(", ")
+            ));
+        }
+        // ... and playlists that would fail to load
+        let mut playlists: Vec<&str> = self
+            .playlists
+            .iter()
+            .filter(|(_, p)| p.entries.iter().any(|e| e.preset == name))
+            .map(|(n, _)| n.as_str())
+            .collect();
+        if !playlists.is_empty() {
+            playlists.sort();
+            return Err(format!(
+                "preset '{}' is used by playlists: {}",
+                name,
+                playlists.join(", ")
+            ));
+        }
+
+        self.presets.remove(name);
+        Ok(())
+    }
+
+    /// Rename a preset, updating the `extends` references and playlist
+    /// entries that point at it.
+    pub fn rename_preset(&mut self, old: &str, new: &str) -> Result<(), String> {
+        if new.is_empty() {
+            return Err("new preset name must not be empty".to_string());
+        }
+        if self.presets.contains_key(new) {
+            return Err(format!("preset '{}' already exists", new));
+        }
+        let preset = self
+            .presets
+            .remove(old)
+            .ok_or_else(|| format!("preset '{}' not found", old))?;
+        self.presets.insert(new.to_string(), preset);
+
+        for p in self.presets.values_mut() {
+            if p.extends.as_deref() == Some(old) {
+                p.extends = Some(new.to_string());
+            }
+        }
+        for entry in self.playlists.values_mut().flat_map(|p| &mut p.entries) {
+            if entry.preset == old {
+                entry.preset = new.to_string();
+            }
+        }
+        Ok(())
+    }
 }
 
 // ---------- Config File I/O ----------
 
+/// Config file names looked up in the config directory, in priority order.
+const CONFIG_FILE_NAMES: [&str; 4] = ["config.toml", "config.json", "config.yaml", "config.yml"];
+
 /// Get the default config file path for the current platform.
 ///
 /// Windows: %APPDATA%\digitalrain\config.toml
 /// Linux:   ~/.config/digitalrain/config.toml
 /// macOS:   ~/Library/Application Support/digitalrain/config.toml
+///
+/// If no `config.toml` exists but a `config.json` / `config.yaml` does,
+/// that file is used instead.
 pub fn config_file_path() -> Option<PathBuf> {
-    dirs::config_dir().map(|d| d.join("digitalrain").join("config.toml"))
+    let dir = platform_config_dir()?.join("digitalrain");
+    let existing = CONFIG_FILE_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists());
+    Some(existing.unwrap_or_else(|| dir.join(CONFIG_FILE_NAMES[0])))
+}
+
+/// The platform's config directory. Builds without the terminal front end
+/// (the browser build) have none.
+#[cfg(feature = "terminal")]
+fn platform_config_dir() -> Option<PathBuf> {
+    dirs::config_dir()
+}
+
+#[cfg(not(feature = "terminal"))]
+fn platform_config_dir() -> Option<PathBuf> {
+    None
+}
+
+/// On-disk config file format, chosen by file extension.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// `.json` -> JSON, `.yaml`/`.yml` -> YAML, anything else -> TOML.
+    pub fn from_path(path: &Path) -> Self {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match ext.as_deref() {
+            Some("json") => ConfigFormat::Json,
+            Some("yaml" | "yml") => ConfigFormat::Yaml,
+            _ => ConfigFormat::Toml,
+        }
+    }
+
+    /// Parse file contents into a generic TOML value tree.
+    fn parse_value(self, content: &str) -> Result<toml::Value, String> {
+        match self {
+            ConfigFormat::Toml => toml::from_str(content).map_err(|e| e.to_string()),
+            ConfigFormat::Json => formats::parse_json(content),
+            ConfigFormat::Yaml => formats::parse_yaml(content),
+        }
+    }
+
+    /// Parse file contents into a `ConfigFile`.
+    pub fn parse(self, content: &str) -> Result<ConfigFile, String> {
+        match self {
+            // Deserialize TOML directly so error messages keep their spans
+            ConfigFormat::Toml => toml::from_str(content).map_err(|e| e.to_string()),
+            _ => self
+                .parse_value(content)?
+                .try_into()
+                .map_err(|e: toml::de::Error| e.message().to_string()),
+        }
+    }
+
+    /// Serialize a `ConfigFile` in this format.
+    pub fn serialize(self, config: &ConfigFile) -> Result<String, String> {
+        match self {
+            ConfigFormat::Toml => toml::to_string_pretty(config).map_err(|e| e.to_string()),
+            _ => {
+                let value = toml::Value::try_from(config).map_err(|e| e.to_string())?;
+                Ok(match self {
+                    ConfigFormat::Json => formats::to_json(&value),
+                    _ => formats::to_yaml(&value),
+                })
+            }
+        }
+    }
 }
 
 /// Load and parse the config file. Returns Default if file doesn't exist or is invalid.
 pub fn load_config_file(path: Option<&str>) -> ConfigFile {
-    let path = match path {
-        Some(p) => PathBuf::from(p),
-        None => match config_file_path() {
-            Some(p) => p,
-            None => return ConfigFile::default(),
-        },
+    let Some(path) = resolved_config_path(path) else {
+        return ConfigFile::default();
     };
 
     match fs::read_to_string(&path) {
-        Ok(content) => match toml::from_str(&content) {
+        Ok(content) => match ConfigFormat::from_path(&path).parse(&content) {
             Ok(config) => config,
             Err(e) => {
                 eprintln!(
@@ -165,12 +1161,61 @@ pub fn load_config_file(path: Option<&str>) -> ConfigFile {
     }
 }
 
+/// The config file path that will be read/written: `--config` if given,
+/// otherwise the platform default.
+fn resolved_config_path(path: Option<&str>) -> Option<PathBuf> {
+    path.map(PathBuf::from).or_else(config_file_path)
+}
+
+/// Load the config file for a preset edit that will save it back.
+///
+/// Unlike `load_config_file`, a file that can't be read or parsed is an
+/// error, since saving would replace it with only the edit. With `force`
+/// a file that doesn't parse is treated as empty instead (the save keeps
+/// a copy in the `.bak` file).
+fn load_config_for_update(path: Option<&str>, force: bool) -> Result<ConfigFile, String> {
+    let Some(path) = resolved_config_path(path) else {
+        return Ok(ConfigFile::default());
+    };
+
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(ConfigFile::default()),
+        Err(e) => return Err(format!("Could not read {}: {}", path.display(), e)),
+    };
+    match ConfigFormat::from_path(&path).parse(&content) {
+        Ok(config) => Ok(config),
+        Err(e) if force => {
+            eprintln!(
+                "Warning: replacing config file {}, which could not be parsed: {}",
+                path.display(),
+                e
+            );
+            Ok(ConfigFile::default())
+        }
+        Err(e) => Err(format!(
+            "{} could not be parsed, so it was left alone (fix it, or pass --force to replace it): {}",
+            path.display(),
+            e
+        )),
+    }
+}
+
+/// `path` with `suffix` added to the file name (`config.toml.bak`).
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut sibling = path.as_os_str().to_owned();
+    sibling.push(suffix);
+    PathBuf::from(sibling)
+}
+
 /// Save a config file to disk, creating directories as needed.
+///
+/// The file is written to a temporary sibling, synced, and then renamed
+/// over the original, so a crash mid-write can't leave a half-written
+/// config behind. The previous version is copied to `<path>.bak` first;
+/// only the one most recent backup is kept.
 fn save_config_file(config: &ConfigFile, path: Option<&str>) -> Result<(), String> {
-    let path = match path {
-        Some(p) => PathBuf::from(p),
-        None => config_file_path().ok_or("Could not determine config directory")?,
-    };
+    let path = resolved_config_path(path).ok_or("Could not determine config directory")?;
 
     // Create parent directory if needed
     if let Some(parent) = path.parent() {
@@ -178,9 +1223,31 @@ fn save_config_file(config: &ConfigFile, path: Option<&str>) -> Result<(), Strin
             .map_err(|e| format!("Could not create config directory: {}", e))?;
     }
 
-    let content =
-        toml::to_string_pretty(config).map_err(|e| format!("Could not serialize config: {}", e))?;
-    fs::write(&path, content).map_err(|e| format!("Could not write config file: {}", e))?;
+    let content = ConfigFormat::from_path(&path)
+        .serialize(config)
+        .map_err(|e| format!("Could not serialize config: {}", e))?;
+
+    let tmp_path = sibling_path(&path, ".tmp");
+    let written = fs::File::create(&tmp_path).and_then(|mut file| {
+        file.write_all(content.as_bytes())?;
+        file.sync_all()
+    });
+    if let Err(e) = written {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(format!("Could not write config file: {}", e));
+    }
+
+    if path.exists()
+        && let Err(e) = fs::copy(&path, sibling_path(&path, ".bak"))
+    {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(format!("Could not back up config file: {}", e));
+    }
+
+    fs::rename(&tmp_path, &path).map_err(|e| {
+        let _ = fs::remove_file(&tmp_path);
+        format!("Could not replace config file: {}", e)
+    })?;
 
     Ok(())
 }
@@ -188,32 +1255,93 @@ fn save_config_file(config: &ConfigFile, path: Option<&str>) -> Result<(), Strin
 /// Save current CLI args as a named preset.
 pub fn save_preset(cli: &Cli, name: &str) -> Result<PathBuf, String> {
     let config_path = cli.config.as_deref();
-    let mut config_file = load_config_file(config_path);
+    let mut config_file = load_config_for_update(config_path, cli.force)?;
 
     let preset = PresetConfig {
+        extends: None,
         effect: cli.effect.clone(),
         speed: cli.speed,
         density: cli.density,
+        spacing: cli.spacing,
+        mutation: cli.mutation,
+        gust_period: cli.gust_period,
+        gust_strength: cli.gust_strength,
+        highlight_rate: cli.highlight_rate,
+        highlight_color: cli.highlight_color.clone(),
         color: cli.color.clone(),
         charset: cli.charset.clone(),
         fps: cli.fps,
         crt: if cli.crt { Some(true) } else { None },
         crt_intensity: cli.crt_intensity,
+        crt_curvature: cli.crt_curvature,
+        crt_persistence: cli.crt_persistence,
+        crt_vignette: if cli.crt_vignette { Some(true) } else { None },
+        crt_vignette_radius: cli.crt_vignette_radius,
+        crt_vignette_strength: cli.crt_vignette_strength,
     };
 
     config_file.presets.insert(name.to_string(), preset);
     save_config_file(&config_file, config_path)?;
 
-    let path = config_path
-        .map(PathBuf::from)
-        .or_else(config_file_path)
-        .unwrap_or_default();
-    Ok(path)
+    Ok(resolved_config_path(config_path).unwrap_or_default())
 }
 
-/// Print all presets from the config file.
-pub fn print_presets(cli: &Cli) {
-    let config_file = load_config_file(cli.config.as_deref());
+/// Save an already-built preset (e.g. captured from the running app).
+/// Overwrites any existing preset with the same name. `force` is
+/// `--force`: whether a config file that doesn't parse may be replaced.
+pub fn save_preset_config(
+    config_path: Option<&str>,
+    name: &str,
+    preset: PresetConfig,
+    force: bool,
+) -> Result<PathBuf, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("preset name must not be empty".to_string());
+    }
+
+    let mut config_file = load_config_for_update(config_path, force)?;
+    config_file.presets.insert(name.to_string(), preset);
+    save_config_file(&config_file, config_path)?;
+
+    Ok(resolved_config_path(config_path).unwrap_or_default())
+}
+
+/// Delete a named preset from the config file.
+pub fn delete_preset(cli: &Cli, name: &str) -> Result<PathBuf, String> {
+    let config_path = cli.config.as_deref();
+    let mut config_file = load_config_for_update(config_path, cli.force)?;
+
+    config_file.delete_preset(name)?;
+    save_config_file(&config_file, config_path)?;
+
+    Ok(resolved_config_path(config_path).unwrap_or_default())
+}
+
+/// Rename a preset. `spec` is in `old:new` form.
+pub fn rename_preset(cli: &Cli, spec: &str) -> Result<(String, String), String> {
+    let (old, new) = spec
+        .split_once(':')
+        .ok_or_else(|| format!("expected OLD:NEW, got '{}'", spec))?;
+    let (old, new) = (old.trim(), new.trim());
+
+    let config_path = cli.config.as_deref();
+    let mut config_file = load_config_for_update(config_path, cli.force)?;
+
+    config_file.rename_preset(old, new)?;
+    save_config_file(&config_file, config_path)?;
+
+    Ok((old.to_string(), new.to_string()))
+}
+
+/// Render a single preset as a TOML `[presets.<name>]` table.
+pub fn show_preset(cli: &Cli, name: &str) -> Result<String, String> {
+    load_config_file(cli.config.as_deref()).preset_toml(name)
+}
+
+/// Print all presets from the config file.
+pub fn print_presets(cli: &Cli) {
+    let config_file = load_config_file(cli.config.as_deref());
 
     if config_file.presets.is_empty() {
         println!("No presets defined.");
@@ -231,6 +1359,9 @@ pub fn print_presets(cli: &Cli) {
     for name in names {
         let p = &config_file.presets[name];
         let mut parts = Vec::new();
+        if let Some(ref base) = p.extends {
+            parts.push(format!("extends={}", base));
+        }
         if let Some(ref e) = p.effect {
             parts.push(format!("effect={}", e));
         }
@@ -243,6 +1374,24 @@ pub fn print_presets(cli: &Cli) {
         if let Some(d) = p.density {
             parts.push(format!("density={:.1}", d));
         }
+        if let Some(gap) = p.spacing {
+            parts.push(format!("spacing={}", gap));
+        }
+        if let Some(m) = p.mutation {
+            parts.push(format!("mutation={:.2}", m));
+        }
+        if let Some(g) = p.gust_period {
+            parts.push(format!("gust_period={:.0}", g));
+        }
+        if let Some(g) = p.gust_strength {
+            parts.push(format!("gust_strength={:.1}", g));
+        }
+        if let Some(h) = p.highlight_rate {
+            parts.push(format!("highlight_rate={:.2}", h));
+        }
+        if let Some(ref h) = p.highlight_color {
+            parts.push(format!("highlight_color={}", h));
+        }
         if let Some(ref cs) = p.charset {
             parts.push(format!("charset={}", cs));
         }
@@ -252,6 +1401,15 @@ pub fn print_presets(cli: &Cli) {
         if let Some(i) = p.crt_intensity {
             parts.push(format!("crt_intensity={:.1}", i));
         }
+        if let Some(c) = p.crt_curvature {
+            parts.push(format!("crt_curvature={:.1}", c));
+        }
+        if let Some(p) = p.crt_persistence {
+            parts.push(format!("crt_persistence={:.1}", p));
+        }
+        if let Some(true) = p.crt_vignette {
+            parts.push("crt_vignette=on".to_string());
+        }
         if let Some(f) = p.fps {
             parts.push(format!("fps={}", f));
         }
@@ -264,61 +1422,1138 @@ pub fn print_presets(cli: &Cli) {
     }
 }
 
+// ---------- Config Validation ----------
+
+/// Keys accepted in `[defaults]` and in each `[presets.<name>]` table.
+const SETTING_KEYS: &[&str] = &[
+    "effect",
+    "speed",
+    "density",
+    "spacing",
+    "mutation",
+    "gust_period",
+    "gust_strength",
+    "highlight_rate",
+    "highlight_color",
+    "color",
+    "charset",
+    "fps",
+    "crt",
+    "crt_intensity",
+    "crt_curvature",
+    "crt_persistence",
+    "crt_vignette",
+    "crt_vignette_radius",
+    "crt_vignette_strength",
+];
+
+/// Keys accepted in each `[effect_defaults.<effect>]` table. The effect is
+/// implied by the table name, and fps can't change after startup.
+const EFFECT_DEFAULT_KEYS: &[&str] = &[
+    "speed",
+    "density",
+    "spacing",
+    "mutation",
+    "gust_period",
+    "gust_strength",
+    "highlight_rate",
+    "highlight_color",
+    "color",
+    "charset",
+    "crt",
+    "crt_intensity",
+    "crt_curvature",
+    "crt_persistence",
+    "crt_vignette",
+    "crt_vignette_radius",
+    "crt_vignette_strength",
+];
+
+/// Keys accepted in the `[overlay]` table.
+const OVERLAY_KEYS: &[&str] = &[
+    "background",
+    "text",
+    "title",
+    "border",
+    "opacity",
+    "crt_affects_overlays",
+];
+
+/// Keys accepted in the `[rain]` table.
+const RAIN_KEYS: &[&str] = &[
+    "dead_zones",
+    "dead_zone_mode",
+    "halo",
+    "stripes",
+    "stripe_direction",
+];
+
+/// Follow the `extends` chain from preset `name` (`base_of` gives the preset
+/// a preset extends, if any) and fail if it loops back on itself.
+fn check_inheritance(name: &str, base_of: impl Fn(&str) -> Option<String>) -> Result<(), String> {
+    let mut chain = vec![name.to_string()];
+    while let Some(base) = base_of(chain.last().unwrap()) {
+        let looped = chain.contains(&base);
+        chain.push(base);
+        if looped {
+            return Err(format!("preset inheritance cycle: {}", chain.join(" -> ")));
+        }
+    }
+    Ok(())
+}
+
+/// A single problem found by `--check-config`, located by 1-based line/column
+/// (0 when the position is unknown).
+pub struct ConfigIssue {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+/// Validate a config file and return every issue found.
+///
+/// Returns `Err` only if the file can't be read at all; an empty `Vec`
+/// means the file is valid.
+pub fn check_config(cli: &Cli) -> Result<(PathBuf, Vec<ConfigIssue>), String> {
+    let path = resolved_config_path(cli.config.as_deref())
+        .ok_or("Could not determine config directory")?;
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Could not read config file {}: {}", path.display(), e))?;
+    let issues = match ConfigFormat::from_path(&path) {
+        ConfigFormat::Toml => check_config_str(&content),
+        format => check_config_converted(format, &content),
+    };
+    Ok((path, issues))
+}
+
+/// Validate a JSON/YAML config by converting it to TOML and checking that.
+///
+/// Each issue in the converted text is traced back through the key it was
+/// found at to where that key starts in the original file.
+fn check_config_converted(format: ConfigFormat, content: &str) -> Vec<ConfigIssue> {
+    let parsed = match format {
+        ConfigFormat::Json => formats::parse_json_located(content),
+        _ => formats::parse_yaml_located(content),
+    };
+    let converted = parsed.and_then(|(value, positions)| {
+        let text = toml::to_string(&value).map_err(|e| e.to_string())?;
+        Ok((text, positions))
+    });
+    let (toml_text, positions) = match converted {
+        Ok(converted) => converted,
+        Err(message) => {
+            let (line, column, message) = match formats::error_position(&message) {
+                Some((line, column, rest)) => (line, column, rest.to_string()),
+                None => (0, 0, message),
+            };
+            return vec![ConfigIssue {
+                line,
+                column,
+                message,
+            }];
+        }
+    };
+
+    // Byte offset in the TOML text where each key path's key or value starts
+    let mut paths = Vec::new();
+    if let Ok(root) = toml::de::DeTable::parse(&toml_text) {
+        toml_paths(root.get_ref(), &mut Vec::new(), &mut paths);
+    }
+    paths.sort_by_key(|(offset, _)| *offset);
+
+    let mut issues = check_toml(&toml_text, &|offset| {
+        let found = paths.partition_point(|(at, _)| *at <= offset);
+        let Some((_, path)) = found.checked_sub(1).map(|i| &paths[i]) else {
+            return (0, 0);
+        };
+        (0..=path.len())
+            .rev()
+            .find_map(|len| positions.get(&path[..len]).copied())
+            .unwrap_or((0, 0))
+    });
+    // The TOML translation orders keys differently from the original
+    issues.sort_by_key(|issue| (issue.line, issue.column));
+    issues
+}
+
+/// Collect the offsets where each key and value in `table` starts, with the
+/// path of keys (array items by index) that leads to it.
+fn toml_paths(
+    table: &toml::de::DeTable,
+    path: &mut Vec<String>,
+    out: &mut Vec<(usize, Vec<String>)>,
+) {
+    use toml::de::DeValue;
+
+    fn value_paths(
+        value: &toml::Spanned<DeValue>,
+        path: &mut Vec<String>,
+        out: &mut Vec<(usize, Vec<String>)>,
+    ) {
+        out.push((value.span().start, path.clone()));
+        match value.get_ref() {
+            DeValue::Table(table) => toml_paths(table, path, out),
+            DeValue::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    path.push(i.to_string());
+                    value_paths(item, path, out);
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for (key, value) in table.iter() {
+        path.push(key.get_ref().to_string());
+        out.push((key.span().start, path.clone()));
+        value_paths(value, path, out);
+        path.pop();
+    }
+}
+
+/// Validate config file contents strictly.
+///
+/// Unlike `load_config_file` (which ignores unknown keys so older binaries
+/// can read newer files), this walks the raw TOML with spans so each issue
+/// can point at the exact key or value that caused it.
+pub fn check_config_str(content: &str) -> Vec<ConfigIssue> {
+    check_toml(content, &|offset| {
+        let before = &content[..offset.min(content.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        (line, column)
+    })
+}
+
+/// Check TOML text, placing each issue with `locate` (byte offset to
+/// line/column).
+fn check_toml(content: &str, locate: &dyn Fn(usize) -> (usize, usize)) -> Vec<ConfigIssue> {
+    use toml::de::DeTable;
+
+    let mut checker = ConfigChecker {
+        locate,
+        issues: Vec::new(),
+        charsets: Vec::new(),
+    };
+
+    match DeTable::parse(content) {
+        Ok(root) => checker.check_root(root.get_ref()),
+        Err(e) => {
+            let offset = e.span().map(|s| s.start).unwrap_or(0);
+            checker.report(offset, e.message().to_string());
+        }
+    }
+
+    checker.issues
+}
+
+/// Walks a parsed TOML document, collecting issues with source positions.
+struct ConfigChecker<'a> {
+    locate: &'a dyn Fn(usize) -> (usize, usize),
+    issues: Vec<ConfigIssue>,
+    /// Names defined in `[charsets]`, which settings may refer to
+    charsets: Vec<String>,
+}
+
+impl ConfigChecker<'_> {
+    /// Record an issue at a byte offset into the source.
+    fn report(&mut self, offset: usize, message: String) {
+        let (line, column) = (self.locate)(offset);
+        self.issues.push(ConfigIssue {
+            line,
+            column,
+            message,
+        });
+    }
+
+    /// Check the top-level tables.
+    fn check_root(&mut self, root: &toml::de::DeTable) {
+        // Collect preset names first so `extends` can be checked against them
+        let preset_names: Vec<String> = root
+            .iter()
+            .find(|(k, _)| k.get_ref() == "presets")
+            .and_then(|(_, v)| match v.get_ref() {
+                toml::de::DeValue::Table(t) => {
+                    Some(t.keys().map(|k| k.get_ref().to_string()).collect())
+                }
+                _ => None,
+            })
+            .unwrap_or_default();
+        self.charsets = root
+            .iter()
+            .find(|(k, _)| k.get_ref() == "charsets")
+            .and_then(|(_, v)| match v.get_ref() {
+                toml::de::DeValue::Table(t) => {
+                    Some(t.keys().map(|k| k.get_ref().to_string()).collect())
+                }
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        for (key, value) in root.iter() {
+            match (key.get_ref().as_ref(), value.get_ref()) {
+                ("defaults", toml::de::DeValue::Table(t)) => {
+                    self.check_settings(t, "defaults", SETTING_KEYS, None);
+                }
+                ("presets", toml::de::DeValue::Table(presets)) => {
+                    // Where each preset's `extends` is, and what it names
+                    let bases: HashMap<&str, (usize, &str)> = presets
+                        .iter()
+                        .filter_map(|(name, preset)| {
+                            let toml::de::DeValue::Table(t) = preset.get_ref() else {
+                                return None;
+                            };
+                            let (_, base) = t.iter().find(|(k, _)| k.get_ref() == "extends")?;
+                            Some((
+                                name.get_ref().as_ref(),
+                                (base.span().start, base.get_ref().as_str()?),
+                            ))
+                        })
+                        .collect();
+                    for (name, preset) in presets.iter() {
+                        match preset.get_ref() {
+                            toml::de::DeValue::Table(t) => {
+                                let section = format!("presets.{}", name.get_ref());
+                                self.check_settings(t, &section, SETTING_KEYS, Some(&preset_names));
+                                let base_of = |n: &str| Some(bases.get(n)?.1.to_string());
+                                if let Err(e) = check_inheritance(name.get_ref(), base_of) {
+                                    self.report(bases[name.get_ref().as_ref()].0, e);
+                                }
+                            }
+                            _ => self.report(
+                                preset.span().start,
+                                format!("preset '{}' must be a table", name.get_ref()),
+                            ),
+                        }
+                    }
+                }
+                ("playlists", toml::de::DeValue::Table(playlists)) => {
+                    for (name, playlist) in playlists.iter() {
+                        self.check_playlist(name.get_ref(), playlist, &preset_names);
+                    }
+                }
+                ("effect_defaults", toml::de::DeValue::Table(effects)) => {
+                    for (name, settings) in effects.iter() {
+                        let name = name.get_ref();
+                        if !crate::effects::registry::effect_names().contains(&name.as_ref()) {
+                            self.report(
+                                settings.span().start,
+                                format!("effect_defaults for unknown effect '{}'", name),
+                            );
+                        }
+                        match settings.get_ref() {
+                            toml::de::DeValue::Table(t) => {
+                                let section = format!("effect_defaults.{}", name);
+                                self.check_settings(t, &section, EFFECT_DEFAULT_KEYS, None);
+                            }
+                            _ => self.report(
+                                settings.span().start,
+                                format!("effect_defaults.{} must be a table", name),
+                            ),
+                        }
+                    }
+                }
+                ("random", toml::de::DeValue::Table(t)) => self.check_random(t),
+                ("overlay", toml::de::DeValue::Table(t)) => self.check_overlay(t),
+                #[cfg(feature = "terminal")]
+                ("keys", toml::de::DeValue::Table(t)) => self.check_keys(t),
+                ("post", toml::de::DeValue::Table(t)) => self.check_post(t),
+                ("rain", toml::de::DeValue::Table(t)) => self.check_rain(t),
+                ("charsets", toml::de::DeValue::Table(t)) => self.check_charsets(t),
+                (
+                    "defaults" | "presets" | "playlists" | "effect_defaults" | "random" | "overlay"
+                    | "keys" | "post" | "rain" | "charsets",
+                    _,
+                ) => {
+                    self.report(
+                        value.span().start,
+                        format!("'{}' must be a table", key.get_ref()),
+                    );
+                }
+                (other, _) => {
+                    self.report(
+                        key.span().start,
+                        format!("unknown top-level key '{}'", other),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Check a `[playlists.<name>]` table: an `entries` array of
+    /// `{ preset = "...", duration = N }` tables.
+    fn check_playlist(
+        &mut self,
+        name: &str,
+        playlist: &toml::Spanned<toml::de::DeValue>,
+        presets: &[String],
+    ) {
+        use toml::de::DeValue;
+
+        let DeValue::Table(table) = playlist.get_ref() else {
+            self.report(
+                playlist.span().start,
+                format!("playlist '{}' must be a table", name),
+            );
+            return;
+        };
+
+        for (key, value) in table.iter() {
+            if key.get_ref() != "entries" {
+                self.report(
+                    key.span().start,
+                    format!("unknown key '{}' in [playlists.{}]", key.get_ref(), name),
+                );
+                continue;
+            }
+            let DeValue::Array(entries) = value.get_ref() else {
+                self.report(value.span().start, "'entries' must be an array".to_string());
+                continue;
+            };
+            if entries.is_empty() {
+                self.report(value.span().start, format!("playlist '{}' is empty", name));
+            }
+            for entry in entries.iter() {
+                let DeValue::Table(fields) = entry.get_ref() else {
+                    self.report(
+                        entry.span().start,
+                        "playlist entry must be a table".to_string(),
+                    );
+                    continue;
+                };
+                let mut has_preset = false;
+                for (k, v) in fields.iter() {
+                    match k.get_ref().as_ref() {
+                        "preset" => {
+                            has_preset = true;
+                            self.check_name(v.span().start, "preset", v.get_ref(), |n| {
+                                presets.iter().any(|p| p == n)
+                            });
+                        }
+                        "duration" => {
+                            self.check_number(v.span().start, "duration", v.get_ref(), 1.0, 86400.0)
+                        }
+                        other => self.report(
+                            k.span().start,
+                            format!("unknown key '{}' in playlist entry", other),
+                        ),
+                    }
+                }
+                if !has_preset {
+                    self.report(
+                        entry.span().start,
+                        "playlist entry is missing 'preset'".to_string(),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Check the `[random]` table: include/exclude name lists and
+    /// `[min, max]` ranges.
+    fn check_random(&mut self, table: &toml::de::DeTable) {
+        use toml::de::DeValue;
+
+        for (key, value) in table.iter() {
+            let name = key.get_ref().as_ref();
+            let at = value.span().start;
+
+            let is_known: fn(&str) -> bool = match name.split_once('_') {
+                Some(("include" | "exclude", "effects")) => {
+                    |n| crate::effects::registry::effect_names().contains(&n)
+                }
+                Some(("include" | "exclude", "colors")) => crate::color::palette::is_palette_name,
+                Some(("include" | "exclude", "charsets")) => crate::rain::chars::is_charset_name,
+                _ if name == "speed_range" || name == "density_range" => {
+                    match value.get_ref() {
+                        DeValue::Array(items) if items.len() == 2 => {
+                            for item in items.iter() {
+                                self.check_number(
+                                    item.span().start,
+                                    name,
+                                    item.get_ref(),
+                                    0.1,
+                                    10.0,
+                                );
+                            }
+                            let bounds: Vec<Option<f64>> =
+                                items.iter().map(|i| toml_number(i.get_ref())).collect();
+                            if let [Some(min), Some(max)] = bounds[..]
+                                && min > max
+                            {
+                                self.report(at, format!("{}: min is greater than max", name));
+                            }
+                        }
+                        _ => self.report(at, format!("'{}' must be a [min, max] array", name)),
+                    }
+                    continue;
+                }
+                _ => {
+                    self.report(
+                        key.span().start,
+                        format!("unknown key '{}' in [random]", name),
+                    );
+                    continue;
+                }
+            };
+
+            let DeValue::Array(items) = value.get_ref() else {
+                self.report(at, format!("'{}' must be an array of names", name));
+                continue;
+            };
+            let kind = name
+                .split_once('_')
+                .map(|(_, k)| k.trim_end_matches('s'))
+                .unwrap_or(name);
+            for item in items.iter() {
+                self.check_name(item.span().start, kind, item.get_ref(), is_known);
+            }
+        }
+    }
+
+    /// Check the `[overlay]` theme table.
+    fn check_overlay(&mut self, table: &toml::de::DeTable) {
+        for (key, value) in table.iter() {
+            let name = key.get_ref().as_ref();
+            let at = value.span().start;
+            let val = value.get_ref();
+            match name {
+                "background" | "text" | "title" => self.check_name(at, "color", val, |n| {
+                    crate::color::palette::color_by_name(n).is_some()
+                }),
+                "border" => self.check_name(at, name, val, |n| {
+                    n.parse::<crate::overlay::BorderStyle>().is_ok()
+                }),
+                "opacity" => self.check_number(at, name, val, 0.0, 1.0),
+                "crt_affects_overlays" => {
+                    if !matches!(val, toml::de::DeValue::Boolean(_)) {
+                        self.report(at, format!("'{}' must be true or false", name));
+                    }
+                }
+                _ => self.report(
+                    key.span().start,
+                    format!(
+                        "unknown key '{}' in [overlay] (expected one of: {})",
+                        name,
+                        OVERLAY_KEYS.join(", ")
+                    ),
+                ),
+            }
+        }
+    }
+
+    /// Check the `[post]` table: a `filters` list of known filter names.
+    fn check_post(&mut self, table: &toml::de::DeTable) {
+        for (key, value) in table.iter() {
+            let name = key.get_ref().as_ref();
+            let at = value.span().start;
+            if name != "filters" {
+                self.report(
+                    key.span().start,
+                    format!("unknown key '{}' in [post] (expected: filters)", name),
+                );
+                continue;
+            }
+            let toml::de::DeValue::Array(items) = value.get_ref() else {
+                self.report(at, "'filters' must be an array of names".to_string());
+                continue;
+            };
+            for item in items.iter() {
+                self.check_name(item.span().start, "post filter", item.get_ref(), |n| {
+                    crate::post::FILTER_NAMES.contains(&n)
+                });
+            }
+        }
+    }
+
+    /// Check the `[rain]` table: parseable zones, known palettes, and known
+    /// modes.
+    fn check_rain(&mut self, table: &toml::de::DeTable) {
+        use toml::de::DeValue;
+
+        for (key, value) in table.iter() {
+            let name = key.get_ref().as_ref();
+            let at = value.span().start;
+            let val = value.get_ref();
+            match name {
+                "dead_zones" => {
+                    let DeValue::Array(items) = val else {
+                        self.report(
+                            at,
+                            "'dead_zones' must be an array of \"x,y,w,h\"".to_string(),
+                        );
+                        continue;
+                    };
+                    for item in items.iter() {
+                        match item.get_ref().as_str().map(str::parse::<Region>) {
+                            Some(Ok(_)) => {}
+                            Some(Err(e)) => self.report(item.span().start, e),
+                            None => self.report(
+                                item.span().start,
+                                "dead zones must be strings like \"x,y,w,h\"".to_string(),
+                            ),
+                        }
+                    }
+                }
+                "dead_zone_mode" => self.check_name(at, name, val, |n| {
+                    <DeadZoneMode as clap::ValueEnum>::from_str(n, true).is_ok()
+                }),
+                "halo" => {
+                    if !matches!(val, DeValue::Boolean(_)) {
+                        self.report(at, format!("'{}' must be true or false", name));
+                    }
+                }
+                "stripes" => {
+                    let DeValue::Array(items) = val else {
+                        self.report(
+                            at,
+                            "'stripes' must be an array of palette names".to_string(),
+                        );
+                        continue;
+                    };
+                    for item in items.iter() {
+                        self.check_name(item.span().start, "palette", item.get_ref(), is_band_name);
+                    }
+                }
+                "stripe_direction" => self.check_name(at, name, val, |n| {
+                    <StripeDirection as clap::ValueEnum>::from_str(n, true).is_ok()
+                }),
+                _ => self.report(
+                    key.span().start,
+                    format!(
+                        "unknown key '{}' in [rain] (expected one of: {})",
+                        name,
+                        RAIN_KEYS.join(", ")
+                    ),
+                ),
+            }
+        }
+    }
+
+    /// Check the `[charsets]` table: new names, each with usable characters.
+    fn check_charsets(&mut self, table: &toml::de::DeTable) {
+        for (key, value) in table.iter() {
+            let name = key.get_ref().as_ref();
+            if crate::rain::chars::is_builtin_charset(name) {
+                self.report(
+                    key.span().start,
+                    format!("charset '{}' is built in and can't be redefined", name),
+                );
+            }
+            match value.get_ref().as_str().map(CharacterPool::from_text) {
+                Some(Ok(_)) => {}
+                Some(Err(e)) => {
+                    self.report(value.span().start, format!("charset '{}': {}", name, e))
+                }
+                None => self.report(
+                    value.span().start,
+                    format!("charset '{}' must be a string of characters", name),
+                ),
+            }
+        }
+    }
+
+    /// Check the `[keys]` table: known actions, parseable keys, and no
+    /// conflicting bindings.
+    #[cfg(feature = "terminal")]
+    fn check_keys(&mut self, table: &toml::de::DeTable) {
+        use toml::de::DeValue;
+
+        let mut overrides = BTreeMap::new();
+        let mut positions = HashMap::new();
+        for (key, value) in table.iter() {
+            let name = key.get_ref().to_string();
+            let keys: Option<Vec<String>> = match value.get_ref() {
+                DeValue::String(s) => Some(vec![s.to_string()]),
+                DeValue::Array(items) => items
+                    .iter()
+                    .map(|i| i.get_ref().as_str().map(str::to_string))
+                    .collect(),
+                _ => None,
+            };
+            let Some(keys) = keys else {
+                self.report(
+                    value.span().start,
+                    format!("'{}' must be a key or an array of keys", name),
+                );
+                continue;
+            };
+            positions.insert(name.clone(), key.span().start);
+            overrides.insert(name, keys);
+        }
+
+        let (_, issues) = crate::keys::KeyMap::with_overrides(&overrides);
+        for (action, message) in issues {
+            let at = positions.get(&action).copied().unwrap_or(0);
+            self.report(at, message);
+        }
+    }
+
+    /// Check a `[defaults]` or `[presets.<name>]` table.
+    /// `presets` is `Some` when checking a preset (which may use `extends`).
+    fn check_settings(
+        &mut self,
+        table: &toml::de::DeTable,
+        section: &str,
+        allowed: &[&str],
+        presets: Option<&[String]>,
+    ) {
+        use toml::de::DeValue;
+
+        for (key, value) in table.iter() {
+            let name = key.get_ref().as_ref();
+            let at = value.span().start;
+            let val = value.get_ref();
+
+            let is_extends = name == "extends" && presets.is_some();
+            if !allowed.contains(&name) && !is_extends {
+                self.report(
+                    key.span().start,
+                    format!(
+                        "unknown key '{}' in [{}] (expected one of: {})",
+                        name,
+                        section,
+                        allowed.join(", ")
+                    ),
+                );
+                continue;
+            }
+
+            match name {
+                "extends" if presets.is_some() => match val.as_str() {
+                    Some(base) if presets.unwrap().iter().any(|p| p == base) => {}
+                    Some(base) => self.report(at, format!("extends unknown preset '{}'", base)),
+                    None => self.report(at, "'extends' must be a string".to_string()),
+                },
+                "effect" => self.check_name(at, name, val, |n| {
+                    crate::effects::registry::effect_names().contains(&n)
+                }),
+                "color" => self.check_name(at, name, val, crate::color::palette::is_palette_name),
+                "charset" => {
+                    let custom = std::mem::take(&mut self.charsets);
+                    // Each part of a mix must be built in or from [charsets]
+                    let known = crate::rain::chars::charset_names();
+                    self.check_name(at, name, val, |n| {
+                        crate::rain::chars::charset_parts(n).is_ok_and(|parts| {
+                            parts
+                                .iter()
+                                .all(|(p, _)| known.contains(p) || custom.iter().any(|c| c == p))
+                        })
+                    });
+                    self.charsets = custom;
+                }
+                "speed" | "density" => self.check_number(at, name, val, 0.1, 10.0),
+                "spacing" => self.check_number(at, name, val, 0.0, MAX_SPACING as f64),
+                "gust_period" => self.check_number(at, name, val, 0.0, MAX_GUST_PERIOD),
+                "highlight_color" => self.check_name(at, "color", val, |n| {
+                    crate::color::palette::color_by_name(n).is_some()
+                }),
+                "mutation"
+                | "gust_strength"
+                | "highlight_rate"
+                | "crt_intensity"
+                | "crt_curvature"
+                | "crt_persistence"
+                | "crt_vignette_radius"
+                | "crt_vignette_strength" => self.check_number(at, name, val, 0.0, 1.0),
+                "fps" => self.check_number(at, name, val, 10.0, 120.0),
+                "crt" | "crt_vignette" if !matches!(val, DeValue::Boolean(_)) => {
+                    self.report(at, format!("'{}' must be true or false", name));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Check a string value names a known effect/palette/charset.
+    fn check_name(
+        &mut self,
+        at: usize,
+        key: &str,
+        value: &toml::de::DeValue,
+        is_known: impl Fn(&str) -> bool,
+    ) {
+        match value.as_str() {
+            Some(n) if is_known(n) => {}
+            Some(n) => self.report(at, format!("unknown {} '{}'", key, n)),
+            None => self.report(at, format!("'{}' must be a string", key)),
+        }
+    }
+
+    /// Check a numeric value lies within `min..=max`.
+    fn check_number(
+        &mut self,
+        at: usize,
+        key: &str,
+        value: &toml::de::DeValue,
+        min: f64,
+        max: f64,
+    ) {
+        match toml_number(value) {
+            Some(n) if (min..=max).contains(&n) => {}
+            Some(n) => self.report(
+                at,
+                format!("{} = {} is out of range ({} to {})", key, n, min, max),
+            ),
+            None => self.report(at, format!("'{}' must be a number", key)),
+        }
+    }
+}
+
+/// Read a TOML integer or float as f64.
+fn toml_number(value: &toml::de::DeValue) -> Option<f64> {
+    use toml::de::DeValue;
+    match value {
+        DeValue::Integer(i) => i64::from_str_radix(&i.as_str().replace('_', ""), i.radix())
+            .ok()
+            .map(|n| n as f64),
+        DeValue::Float(f) => f.as_str().replace('_', "").parse().ok(),
+        _ => None,
+    }
+}
+
 // ---------- Runtime Config ----------
 
 /// Runtime configuration derived from CLI + config file + presets.
 /// This is what gets passed around to effects and subsystems.
+#[derive(Clone)]
 pub struct Config {
     pub effect_name: String,
     pub speed_multiplier: f64,
     pub density_multiplier: f64,
+    /// Minimum empty columns between falling rain columns (`--spacing`)
+    pub spacing: u16,
+    /// Chance a rain character changes each frame (`--mutation`)
+    pub mutation_rate: f64,
+    /// Seconds per density surge (0 = steady), and how hard it swings
+    pub gust_period: f64,
+    pub gust_strength: f64,
+    /// Chance a new rain character is highlighted, and the highlight color
+    /// (`None` = the palette's)
+    pub highlight_rate: f64,
+    pub highlight_color: Option<String>,
     pub palette_name: String,
     pub charset_name: String,
     pub target_fps: u32,
     pub auto_cycle_secs: Option<f64>,
     pub forward: bool,
+    /// Fixed per-cell glyphs with only brightness falling (`--static-glyphs`)
+    pub static_glyphs: bool,
     pub crt_enabled: bool,
     pub crt_intensity: f64,
+    /// Barrel distortion of the CRT filter (0.0 = flat)
+    pub crt_curvature: f64,
+    /// Phosphor ghosting of the CRT filter (0.0 = none)
+    pub crt_persistence: f64,
+    /// Edge darkening of the CRT filter, and where and how hard it applies
+    pub crt_vignette: bool,
+    pub crt_vignette_radius: f64,
+    pub crt_vignette_strength: f64,
+    /// Post-processing filters, in order (`--post` or `[post] filters`)
+    pub post_filters: Vec<String>,
+    /// Areas kept clear of rain (`--dead-zone` or `[rain] dead_zones`)
+    pub dead_zones: Vec<Region>,
+    pub dead_zone_mode: DeadZoneMode,
+    /// Palettes for striped rain, one per band (`--stripes` or `[rain]`)
+    pub stripes: Vec<String>,
+    pub stripe_direction: StripeDirection,
+    /// `--seed` value; effects derive their RNGs from it (None = entropy)
+    pub seed: Option<u64>,
+    /// Effect parameters from `--param`, for whichever effects take them
+    pub effect_params: BTreeMap<String, toml::Value>,
+    /// System reading that scales density and speed (`--react`)
+    pub react: Option<ReactSource>,
+    /// Repository the gitrain effect reads (`--git-repo`; None = current
+    /// directory)
+    pub git_repo: Option<PathBuf>,
+    /// Text the ghostwriter effect types (`--text-file`; None = built-in
+    /// quotes)
+    pub text_file: Option<PathBuf>,
+    /// What the rain is drawn over (`--background`; Reset = the terminal's
+    /// own background)
+    pub background: Color,
+    /// Final-frame brightness (`--brightness`; 1.0 = unchanged)
+    pub brightness: f64,
+    /// Final-frame contrast (`--contrast`; 1.0 = unchanged)
+    pub contrast: f64,
+    /// Final-frame gamma (`--gamma`; 1.0 = unchanged)
+    pub gamma: f64,
+}
+
+/// `--brightness`, `--contrast`, and `--gamma`, clamped to their ranges
+/// (1.0 each when not given).
+fn color_tone(cli: &Cli) -> (f64, f64, f64) {
+    (
+        cli.brightness
+            .unwrap_or(1.0)
+            .clamp(BRIGHTNESS_RANGE.0, BRIGHTNESS_RANGE.1),
+        cli.contrast
+            .unwrap_or(1.0)
+            .clamp(CONTRAST_RANGE.0, CONTRAST_RANGE.1),
+        cli.gamma.unwrap_or(1.0).clamp(GAMMA_RANGE.0, GAMMA_RANGE.1),
+    )
+}
+
+/// The post-processing chain: `--post` if given, else `[post] filters`.
+pub fn post_filters(cli: &Cli, config_file: &ConfigFile) -> Vec<String> {
+    if cli.post.is_empty() {
+        config_file.post.filters.clone()
+    } else {
+        cli.post.clone()
+    }
+}
+
+/// The rain's dead zones: `--dead-zone` if given, else `[rain] dead_zones`
+/// (unparseable entries are skipped with a warning).
+pub fn dead_zones(cli: &Cli, config_file: &ConfigFile) -> Vec<Region> {
+    if !cli.dead_zones.is_empty() {
+        return cli.dead_zones.clone();
+    }
+    config_file
+        .rain
+        .dead_zones
+        .iter()
+        .filter_map(|zone| {
+            zone.parse()
+                .map_err(|e| eprintln!("Warning: [rain] dead zone: {}", e))
+                .ok()
+        })
+        .collect()
+}
+
+/// How rain meets a dead zone: `--dead-zone-mode`, else `[rain]`.
+pub fn dead_zone_mode(cli: &Cli, config_file: &ConfigFile) -> DeadZoneMode {
+    cli.dead_zone_mode
+        .or_else(|| {
+            let mode = config_file.rain.dead_zone_mode.as_deref()?;
+            <DeadZoneMode as clap::ValueEnum>::from_str(mode, true)
+                .map_err(|_| eprintln!("Warning: unknown dead_zone_mode '{}'", mode))
+                .ok()
+        })
+        .unwrap_or_default()
+}
+
+/// Keep a highlight color only if it names a known color (with a warning
+/// otherwise, falling back to the palette's own highlight).
+fn known_color(name: Option<String>) -> Option<String> {
+    name.filter(|n| {
+        let known = crate::color::palette::color_by_name(n).is_some();
+        if !known {
+            eprintln!("Unknown highlight color '{}', using the palette's", n);
+        }
+        known
+    })
+}
+
+/// The name `--charset-chars` and `--charset-file` register under.
+pub const CUSTOM_CHARSET: &str = "custom";
+
+/// The name `--source-file` registers under.
+pub const SOURCE_CHARSET: &str = "source";
+
+/// Register the user's charsets: each `[charsets]` entry under its own
+/// name, `--source-file` as "source", and `--charset-chars` /
+/// `--charset-file` as "custom".
+pub fn register_charsets(cli: &Cli, config_file: &ConfigFile) -> Result<(), String> {
+    use crate::rain::chars::register_charset;
+
+    for (name, text) in &config_file.charsets {
+        let pool =
+            CharacterPool::from_text(text).map_err(|e| format!("charset '{}': {}", name, e))?;
+        register_charset(name, pool)?;
+    }
+    if let Some(ref path) = cli.source_file {
+        // Logs and old files aren't always valid UTF-8; keep what we can
+        let bytes = std::fs::read(path)
+            .map_err(|e| format!("Could not read source file {}: {}", path.display(), e))?;
+        let pool = CharacterPool::from_source(&String::from_utf8_lossy(&bytes))
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        register_charset(SOURCE_CHARSET, pool)?;
+    }
+    let text = match (&cli.charset_chars, &cli.charset_file) {
+        (Some(chars), _) => chars.clone(),
+        (None, Some(path)) => std::fs::read_to_string(path)
+            .map_err(|e| format!("Could not read charset file {}: {}", path.display(), e))?,
+        (None, None) => return Ok(()),
+    };
+    let pool = CharacterPool::from_text(&text).map_err(|e| format!("custom charset: {}", e))?;
+    register_charset(CUSTOM_CHARSET, pool)
+}
+
+/// Striped rain's bands: `--stripes` if given, else `[rain] stripes`.
+pub fn stripes(cli: &Cli, config_file: &ConfigFile) -> Vec<String> {
+    if cli.stripes.is_empty() {
+        config_file.rain.stripes.clone()
+    } else {
+        cli.stripes.clone()
+    }
+}
+
+/// Which way stripes run: `--stripe-direction`, else `[rain]`.
+pub fn stripe_direction(cli: &Cli, config_file: &ConfigFile) -> StripeDirection {
+    cli.stripe_direction
+        .or_else(|| {
+            let direction = config_file.rain.stripe_direction.as_deref()?;
+            <StripeDirection as clap::ValueEnum>::from_str(direction, true)
+                .map_err(|_| eprintln!("Warning: unknown stripe_direction '{}'", direction))
+                .ok()
+        })
+        .unwrap_or_default()
 }
 
 impl Config {
+    /// The current effect with the `--param` values it takes. The rest
+    /// belong to other effects (in the cycle) and are left out.
+    pub fn effect_spec(&self) -> EffectSpec {
+        self.effect_spec_for(&self.effect_name)
+    }
+
+    /// Like `effect_spec`, for the named effect.
+    pub fn effect_spec_for(&self, name: &str) -> EffectSpec {
+        let takes = crate::effects::registry::effect_params(name).unwrap_or_default();
+        EffectSpec {
+            name: name.to_string(),
+            params: self
+                .effect_params
+                .iter()
+                .filter(|(param, _)| takes.iter().any(|t| t.name == *param))
+                .map(|(param, value)| (param.clone(), value.clone()))
+                .collect(),
+        }
+    }
+
+    /// Check every `--param`: some effect must take it, with a valid value.
+    pub fn check_effect_params(&self) -> Result<(), String> {
+        for (param, value) in &self.effect_params {
+            let owner = crate::effects::registry::effect_names()
+                .iter()
+                .find(|name| {
+                    crate::effects::registry::effect_params(name)
+                        .is_some_and(|takes| takes.iter().any(|t| t.name == param))
+                })
+                .ok_or_else(|| {
+                    format!("no effect has a parameter '{param}' (see --list-effects)")
+                })?;
+            EffectSpec::new(owner)
+                .with(param, value.clone())
+                .validate()?;
+        }
+        Ok(())
+    }
+
     /// Resolve config from CLI args, preset, and config file defaults.
     /// Priority: CLI explicit > preset > config defaults > hardcoded default
     pub fn resolve(cli: &Cli, config_file: &ConfigFile) -> Self {
-        let preset = cli
-            .preset
-            .as_ref()
-            .and_then(|name| config_file.presets.get(name));
+        Self::resolve_with_preset(cli, config_file, cli.preset.as_deref())
+    }
+
+    /// Resolve config using an explicit preset name instead of `--preset`.
+    /// Used by playlists, where each entry names its own preset.
+    pub fn resolve_with_preset(
+        cli: &Cli,
+        config_file: &ConfigFile,
+        preset_name: Option<&str>,
+    ) -> Self {
+        // Flatten the preset's inheritance chain up front so the field
+        // resolution below only has to look at one merged preset.
+        let merged_preset = preset_name.and_then(|name| {
+            config_file
+                .resolve_preset(name)
+                .map_err(|e| eprintln!("Warning: {}", e))
+                .ok()
+        });
+        let preset = merged_preset.as_ref();
+
+        let effect_name = cli
+            .effect
+            .clone()
+            .or_else(|| preset.and_then(|p| p.effect.clone()))
+            .or_else(|| config_file.defaults.effect.clone())
+            .unwrap_or_else(|| "classic".to_string());
+        let (brightness, contrast, gamma) = color_tone(cli);
+        // Per-effect defaults sit between the preset and [defaults]
+        let effect = config_file.effect_defaults.get(&effect_name);
 
         Self {
-            effect_name: cli
-                .effect
-                .clone()
-                .or_else(|| preset.and_then(|p| p.effect.clone()))
-                .or_else(|| config_file.defaults.effect.clone())
-                .unwrap_or_else(|| "classic".to_string()),
+            effect_name,
             speed_multiplier: cli
                 .speed
                 .or(preset.and_then(|p| p.speed))
+                .or(effect.and_then(|e| e.speed))
                 .or(config_file.defaults.speed)
                 .unwrap_or(1.0)
                 .clamp(0.1, 10.0),
             density_multiplier: cli
                 .density
                 .or(preset.and_then(|p| p.density))
+                .or(effect.and_then(|e| e.density))
                 .or(config_file.defaults.density)
                 .unwrap_or(1.0)
                 .clamp(0.1, 10.0),
+            spacing: cli
+                .spacing
+                .or(preset.and_then(|p| p.spacing))
+                .or(effect.and_then(|e| e.spacing))
+                .or(config_file.defaults.spacing)
+                .unwrap_or(0)
+                .min(MAX_SPACING),
+            mutation_rate: cli
+                .mutation
+                .or(preset.and_then(|p| p.mutation))
+                .or(effect.and_then(|e| e.mutation))
+                .or(config_file.defaults.mutation)
+                .unwrap_or(DEFAULT_MUTATION)
+                .clamp(0.0, 1.0),
+            gust_period: cli
+                .gust_period
+                .or(preset.and_then(|p| p.gust_period))
+                .or(effect.and_then(|e| e.gust_period))
+                .or(config_file.defaults.gust_period)
+                .unwrap_or(0.0)
+                .clamp(0.0, MAX_GUST_PERIOD),
+            gust_strength: cli
+                .gust_strength
+                .or(preset.and_then(|p| p.gust_strength))
+                .or(effect.and_then(|e| e.gust_strength))
+                .or(config_file.defaults.gust_strength)
+                .unwrap_or(DEFAULT_GUST_STRENGTH)
+                .clamp(0.0, 1.0),
+            highlight_rate: cli
+                .highlight_rate
+                .or(preset.and_then(|p| p.highlight_rate))
+                .or(effect.and_then(|e| e.highlight_rate))
+                .or(config_file.defaults.highlight_rate)
+                .unwrap_or(DEFAULT_HIGHLIGHT_RATE)
+                .clamp(0.0, 1.0),
+            highlight_color: known_color(
+                cli.highlight_color
+                    .clone()
+                    .or_else(|| preset.and_then(|p| p.highlight_color.clone()))
+                    .or_else(|| effect.and_then(|e| e.highlight_color.clone()))
+                    .or_else(|| config_file.defaults.highlight_color.clone()),
+            ),
             palette_name: cli
                 .color
                 .clone()
                 .or_else(|| preset.and_then(|p| p.color.clone()))
+                .or_else(|| effect.and_then(|e| e.color.clone()))
                 .or_else(|| config_file.defaults.color.clone())
                 .unwrap_or_else(|| "classic".to_string()),
             charset_name: cli
                 .charset
                 .clone()
+                .or_else(|| {
+                    cli.source_file
+                        .is_some()
+                        .then(|| SOURCE_CHARSET.to_string())
+                })
+                .or_else(|| {
+                    (cli.charset_chars.is_some() || cli.charset_file.is_some())
+                        .then(|| CUSTOM_CHARSET.to_string())
+                })
                 .or_else(|| preset.and_then(|p| p.charset.clone()))
+                .or_else(|| effect.and_then(|e| e.charset.clone()))
                 .or_else(|| config_file.defaults.charset.clone())
                 .unwrap_or_else(|| "matrix".to_string()),
             target_fps: cli
@@ -329,48 +2564,410 @@ impl Config {
                 .clamp(10, 120),
             auto_cycle_secs: cli.timer.map(|t| t.max(1.0)),
             forward: cli.forward,
+            static_glyphs: cli.static_glyphs,
             crt_enabled: cli.crt
+                || post_filters(cli, config_file).iter().any(|f| f == "crt")
                 || preset.and_then(|p| p.crt).unwrap_or(false)
+                || effect.and_then(|e| e.crt).unwrap_or(false)
                 || config_file.defaults.crt.unwrap_or(false),
             crt_intensity: cli
                 .crt_intensity
                 .or(preset.and_then(|p| p.crt_intensity))
+                .or(effect.and_then(|e| e.crt_intensity))
                 .or(config_file.defaults.crt_intensity)
                 .unwrap_or(0.7)
                 .clamp(0.0, 1.0),
+            crt_curvature: cli
+                .crt_curvature
+                .or(preset.and_then(|p| p.crt_curvature))
+                .or(effect.and_then(|e| e.crt_curvature))
+                .or(config_file.defaults.crt_curvature)
+                .unwrap_or(0.0)
+                .clamp(0.0, 1.0),
+            crt_persistence: cli
+                .crt_persistence
+                .or(preset.and_then(|p| p.crt_persistence))
+                .or(effect.and_then(|e| e.crt_persistence))
+                .or(config_file.defaults.crt_persistence)
+                .unwrap_or(0.0)
+                .clamp(0.0, 1.0),
+            crt_vignette: cli.crt_vignette
+                || preset.and_then(|p| p.crt_vignette).unwrap_or(false)
+                || effect.and_then(|e| e.crt_vignette).unwrap_or(false)
+                || config_file.defaults.crt_vignette.unwrap_or(false),
+            crt_vignette_radius: cli
+                .crt_vignette_radius
+                .or(preset.and_then(|p| p.crt_vignette_radius))
+                .or(effect.and_then(|e| e.crt_vignette_radius))
+                .or(config_file.defaults.crt_vignette_radius)
+                .unwrap_or(DEFAULT_VIGNETTE_RADIUS)
+                .clamp(0.0, 1.0),
+            crt_vignette_strength: cli
+                .crt_vignette_strength
+                .or(preset.and_then(|p| p.crt_vignette_strength))
+                .or(effect.and_then(|e| e.crt_vignette_strength))
+                .or(config_file.defaults.crt_vignette_strength)
+                .unwrap_or(DEFAULT_VIGNETTE_STRENGTH)
+                .clamp(0.0, 1.0),
+            post_filters: post_filters(cli, config_file),
+            dead_zones: dead_zones(cli, config_file),
+            dead_zone_mode: dead_zone_mode(cli, config_file),
+            stripes: stripes(cli, config_file),
+            stripe_direction: stripe_direction(cli, config_file),
+            seed: cli.seed,
+            effect_params: cli.params.iter().cloned().collect(),
+            react: cli.react,
+            git_repo: cli.git_repo.clone(),
+            text_file: cli.text_file.clone(),
+            background: cli.background.unwrap_or_default(),
+            brightness,
+            contrast,
+            gamma,
+        }
+    }
+
+    /// Apply `[effect_defaults.<effect>]` for the current effect, overriding
+    /// whatever the config had. Called whenever the effect changes at runtime
+    /// (`n`, `r`, auto-cycle) so each effect keeps its preferred settings.
+    pub fn apply_effect_defaults(&mut self, config_file: &ConfigFile) {
+        let Some(defaults) = config_file.effect_defaults.get(&self.effect_name) else {
+            return;
+        };
+        if let Some(speed) = defaults.speed {
+            self.speed_multiplier = speed.clamp(0.1, 10.0);
+        }
+        if let Some(density) = defaults.density {
+            self.density_multiplier = density.clamp(0.1, 10.0);
+        }
+        if let Some(spacing) = defaults.spacing {
+            self.spacing = spacing.min(MAX_SPACING);
+        }
+        if let Some(mutation) = defaults.mutation {
+            self.mutation_rate = mutation.clamp(0.0, 1.0);
+        }
+        if let Some(period) = defaults.gust_period {
+            self.gust_period = period.clamp(0.0, MAX_GUST_PERIOD);
+        }
+        if let Some(strength) = defaults.gust_strength {
+            self.gust_strength = strength.clamp(0.0, 1.0);
+        }
+        if let Some(rate) = defaults.highlight_rate {
+            self.highlight_rate = rate.clamp(0.0, 1.0);
+        }
+        if defaults.highlight_color.is_some() {
+            self.highlight_color = known_color(defaults.highlight_color.clone());
+        }
+        if let Some(ref color) = defaults.color {
+            self.palette_name = color.clone();
+        }
+        if let Some(ref charset) = defaults.charset {
+            self.charset_name = charset.clone();
+        }
+        if let Some(crt) = defaults.crt {
+            self.crt_enabled = crt;
+        }
+        if let Some(intensity) = defaults.crt_intensity {
+            self.crt_intensity = intensity.clamp(0.0, 1.0);
+        }
+        if let Some(curvature) = defaults.crt_curvature {
+            self.crt_curvature = curvature.clamp(0.0, 1.0);
+        }
+        if let Some(persistence) = defaults.crt_persistence {
+            self.crt_persistence = persistence.clamp(0.0, 1.0);
+        }
+        if let Some(vignette) = defaults.crt_vignette {
+            self.crt_vignette = vignette;
+        }
+        if let Some(radius) = defaults.crt_vignette_radius {
+            self.crt_vignette_radius = radius.clamp(0.0, 1.0);
+        }
+        if let Some(strength) = defaults.crt_vignette_strength {
+            self.crt_vignette_strength = strength.clamp(0.0, 1.0);
         }
     }
 
-    /// Create a Config from CLI arguments only (backwards-compatible convenience).
-    pub fn from_cli(cli: &Cli) -> Self {
-        let config_file = load_config_file(cli.config.as_deref());
-        Self::resolve(cli, &config_file)
+    /// Resolve every entry of a named playlist into a runtime config.
+    ///
+    /// CLI flags still take priority over each entry's preset, so e.g.
+    /// `--playlist evening -s 2` runs the whole playlist at double speed.
+    pub fn playlist(
+        cli: &Cli,
+        config_file: &ConfigFile,
+        name: &str,
+    ) -> Result<Vec<PlaylistStep>, String> {
+        let playlist = config_file
+            .playlists
+            .get(name)
+            .ok_or_else(|| format!("playlist '{}' not found", name))?;
+        if playlist.entries.is_empty() {
+            return Err(format!("playlist '{}' has no entries", name));
+        }
+
+        playlist
+            .entries
+            .iter()
+            .map(|entry| {
+                if !config_file.presets.contains_key(&entry.preset) {
+                    return Err(format!(
+                        "playlist '{}' references unknown preset '{}'",
+                        name, entry.preset
+                    ));
+                }
+                Ok(PlaylistStep {
+                    preset: entry.preset.clone(),
+                    config: Self::resolve_with_preset(cli, config_file, Some(&entry.preset)),
+                    duration: entry.duration.unwrap_or(30.0).max(1.0),
+                })
+            })
+            .collect()
     }
 
-    /// Create a randomized config.
-    pub fn randomized() -> Self {
-        use rand::Rng;
-        let mut rng = rand::rng();
+    /// Create a randomized config, picking only from the allowed names and ranges.
+    ///
+    /// The result has no seed; callers carry over `seed` from the active config.
+    pub fn randomized(constraints: &RandomConstraints, rng: &mut impl rand::Rng) -> Self {
+        use rand::RngExt;
 
-        let effects = crate::effects::registry::effect_names();
-        let palettes = crate::color::palette::palette_names();
-        let charsets = crate::rain::chars::charset_names();
+        let effects = &constraints.effects;
+        let palettes = &constraints.colors;
+        let charsets = &constraints.charsets;
+        let (speed_min, speed_max) = constraints.speed;
+        let (density_min, density_max) = constraints.density;
 
         Self {
-            effect_name: effects[rng.random_range(0..effects.len())].to_string(),
-            speed_multiplier: rng.random_range(0.5..2.5),
-            density_multiplier: rng.random_range(0.3..2.0),
-            palette_name: palettes[rng.random_range(0..palettes.len())].to_string(),
-            charset_name: charsets[rng.random_range(0..charsets.len())].to_string(),
+            effect_name: effects[rng.random_range(0..effects.len())].clone(),
+            speed_multiplier: rng.random_range(speed_min..=speed_max),
+            density_multiplier: rng.random_range(density_min..=density_max),
+            spacing: 0,
+            mutation_rate: DEFAULT_MUTATION,
+            gust_period: 0.0,
+            gust_strength: DEFAULT_GUST_STRENGTH,
+            highlight_rate: DEFAULT_HIGHLIGHT_RATE,
+            highlight_color: None,
+            palette_name: palettes[rng.random_range(0..palettes.len())].clone(),
+            charset_name: charsets[rng.random_range(0..charsets.len())].clone(),
             target_fps: 30,
             auto_cycle_secs: None,
             forward: false,
+            static_glyphs: false,
             crt_enabled: rng.random_range(0.0..1.0) < 0.07, // ~7% chance
             crt_intensity: 0.7,
+            crt_curvature: 0.0,
+            crt_persistence: 0.0,
+            crt_vignette: false,
+            crt_vignette_radius: DEFAULT_VIGNETTE_RADIUS,
+            crt_vignette_strength: DEFAULT_VIGNETTE_STRENGTH,
+            post_filters: Vec::new(),
+            dead_zones: Vec::new(),
+            dead_zone_mode: DeadZoneMode::default(),
+            stripes: Vec::new(),
+            stripe_direction: StripeDirection::default(),
+            seed: None,
+            effect_params: BTreeMap::new(),
+            react: None,
+            git_repo: None,
+            text_file: None,
+            background: Color::Reset,
+            brightness: 1.0,
+            contrast: 1.0,
+            gamma: 1.0,
+        }
+    }
+
+    /// Take every setting a randomize leaves alone from `from`: all but the
+    /// effect, palette, charset, speed, density, and CRT switch that
+    /// `randomized` picks. Used by `--random`, `r`, and the auto-cycle, so
+    /// what survives a new look is the same everywhere.
+    pub fn keep_runtime_settings(&mut self, from: &Config) {
+        self.spacing = from.spacing;
+        self.mutation_rate = from.mutation_rate;
+        self.gust_period = from.gust_period;
+        self.gust_strength = from.gust_strength;
+        self.highlight_rate = from.highlight_rate;
+        self.highlight_color = from.highlight_color.clone();
+        self.target_fps = from.target_fps;
+        self.auto_cycle_secs = from.auto_cycle_secs;
+        self.forward = from.forward;
+        self.static_glyphs = from.static_glyphs;
+        self.crt_intensity = from.crt_intensity;
+        self.crt_curvature = from.crt_curvature;
+        self.crt_persistence = from.crt_persistence;
+        self.crt_vignette = from.crt_vignette;
+        self.crt_vignette_radius = from.crt_vignette_radius;
+        self.crt_vignette_strength = from.crt_vignette_strength;
+        self.post_filters = from.post_filters.clone();
+        self.dead_zones = from.dead_zones.clone();
+        self.dead_zone_mode = from.dead_zone_mode;
+        self.stripes = from.stripes.clone();
+        self.stripe_direction = from.stripe_direction;
+        self.seed = from.seed;
+        self.effect_params = from.effect_params.clone();
+        self.react = from.react;
+        self.git_repo = from.git_repo.clone();
+        self.text_file = from.text_file.clone();
+        self.background = from.background;
+        self.brightness = from.brightness;
+        self.contrast = from.contrast;
+        self.gamma = from.gamma;
+    }
+}
+
+/// The pools and ranges `Config::randomized` draws from.
+///
+/// Built from `[random]` plus `--random-exclude`; every pool is guaranteed
+/// non-empty and every range has `min <= max`.
+#[derive(Clone)]
+pub struct RandomConstraints {
+    pub effects: Vec<String>,
+    pub colors: Vec<String>,
+    pub charsets: Vec<String>,
+    pub speed: (f64, f64),
+    pub density: (f64, f64),
+}
+
+impl Default for RandomConstraints {
+    fn default() -> Self {
+        let owned = |names: &[&str]| names.iter().map(|n| n.to_string()).collect();
+        Self {
+            effects: owned(crate::effects::registry::effect_names()),
+            colors: owned(&crate::color::palette::palette_names()),
+            charsets: owned(&crate::rain::chars::charset_names()),
+            speed: (0.5, 2.5),
+            density: (0.3, 2.0),
+        }
+    }
+}
+
+impl RandomConstraints {
+    /// Combine the config file's `[random]` section with `--random-exclude`.
+    ///
+    /// Errors on unknown names, malformed ranges, or a pool that ends up empty.
+    pub fn resolve(cli: &Cli, config_file: &ConfigFile) -> Result<Self, String> {
+        let random = &config_file.random;
+        let mut exclude_effects = random.exclude_effects.clone();
+        let mut exclude_colors = random.exclude_colors.clone();
+        let mut exclude_charsets = random.exclude_charsets.clone();
+
+        for spec in &cli.random_exclude {
+            let (kind, list) = spec
+                .split_once('=')
+                .ok_or_else(|| format!("expected KIND=LIST in --random-exclude, got '{}'", spec))?;
+            let names = list
+                .split(',')
+                .map(|n| n.trim().to_string())
+                .filter(|n| !n.is_empty());
+            match kind.trim() {
+                "effects" => exclude_effects.extend(names),
+                "colors" => exclude_colors.extend(names),
+                "charsets" => exclude_charsets.extend(names),
+                other => {
+                    return Err(format!(
+                        "unknown --random-exclude kind '{}' (expected effects, colors, or charsets)",
+                        other
+                    ));
+                }
+            }
+        }
+
+        let mut defaults = Self::default();
+        // Effects fed by an input that isn't set up would only play their
+        // stand-ins, so they're left out unless `include_effects` names them
+        defaults.effects.retain(|name| match name.as_str() {
+            "spectrum" => cli.audio.is_some(),
+            "gitrain" => cli.git_repo.is_some(),
+            _ => true,
+        });
+        let is_effect = |n: &str| crate::effects::registry::effect_names().contains(&n);
+        let is_charset = crate::rain::chars::is_charset_name;
+        let is_color = crate::color::palette::is_palette_name;
+
+        Ok(Self {
+            effects: Self::pool(
+                "effect",
+                defaults.effects,
+                &random.include_effects,
+                &exclude_effects,
+                is_effect,
+            )?,
+            colors: Self::pool(
+                "color",
+                defaults.colors,
+                &random.include_colors,
+                &exclude_colors,
+                is_color,
+            )?,
+            charsets: Self::pool(
+                "charset",
+                defaults.charsets,
+                &random.include_charsets,
+                &exclude_charsets,
+                is_charset,
+            )?,
+            speed: Self::range("speed_range", random.speed_range, defaults.speed)?,
+            density: Self::range("density_range", random.density_range, defaults.density)?,
+        })
+    }
+
+    /// Apply include/exclude lists to the full list of names.
+    fn pool(
+        kind: &str,
+        all: Vec<String>,
+        include: &[String],
+        exclude: &[String],
+        is_known: impl Fn(&str) -> bool,
+    ) -> Result<Vec<String>, String> {
+        if let Some(unknown) = include.iter().chain(exclude).find(|n| !is_known(n)) {
+            return Err(format!(
+                "unknown {} '{}' in random constraints",
+                kind, unknown
+            ));
+        }
+
+        let base = if include.is_empty() {
+            all
+        } else {
+            include.to_vec()
+        };
+        let pool: Vec<String> = base
+            .into_iter()
+            .filter(|n| !exclude.iter().any(|x| x.eq_ignore_ascii_case(n)))
+            .collect();
+
+        if pool.is_empty() {
+            return Err(format!("random constraints exclude every {}", kind));
+        }
+        Ok(pool)
+    }
+
+    /// Validate a `[min, max]` range, falling back to the default when unset.
+    fn range(
+        key: &str,
+        range: Option<[f64; 2]>,
+        default: (f64, f64),
+    ) -> Result<(f64, f64), String> {
+        match range {
+            None => Ok(default),
+            Some([min, max]) if (0.1..=10.0).contains(&min) && (0.1..=10.0).contains(&max) => {
+                if min <= max {
+                    Ok((min, max))
+                } else {
+                    Err(format!("{}: min {} is greater than max {}", key, min, max))
+                }
+            }
+            Some(_) => Err(format!("{} values must be between 0.1 and 10.0", key)),
         }
     }
 }
 
+/// One resolved playlist entry, ready to hand to the scheduler.
+pub struct PlaylistStep {
+    /// Preset name (shown in the status message)
+    pub preset: String,
+    pub config: Config,
+    /// Seconds to stay on this entry
+    pub duration: f64,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -455,6 +3052,807 @@ mod tests {
         assert_eq!(config.target_fps, 10);
     }
 
+    #[test]
+    fn resolve_preset_inherits_from_base() {
+        let cli = Cli::parse_from(["digital_rain", "--preset", "night"]);
+        let mut config_file = ConfigFile::default();
+        config_file.presets.insert(
+            "base".to_string(),
+            PresetConfig {
+                effect: Some("glitch".to_string()),
+                speed: Some(1.5),
+                color: Some("purple".to_string()),
+                ..Default::default()
+            },
+        );
+        config_file.presets.insert(
+            "night".to_string(),
+            PresetConfig {
+                extends: Some("base".to_string()),
+                color: Some("cyan".to_string()),
+                ..Default::default()
+            },
+        );
+
+        let config = Config::resolve(&cli, &config_file);
+        // Inherited from base
+        assert_eq!(config.effect_name, "glitch");
+        assert!((config.speed_multiplier - 1.5).abs() < 0.01);
+        // Overridden by the child
+        assert_eq!(config.palette_name, "cyan");
+    }
+
+    #[test]
+    fn resolve_preset_follows_multi_level_chain() {
+        let mut config_file = ConfigFile::default();
+        config_file.presets.insert(
+            "a".to_string(),
+            PresetConfig {
+                fps: Some(60),
+                ..Default::default()
+            },
+        );
+        config_file.presets.insert(
+            "b".to_string(),
+            PresetConfig {
+                extends: Some("a".to_string()),
+                density: Some(2.0),
+                ..Default::default()
+            },
+        );
+        config_file.presets.insert(
+            "c".to_string(),
+            PresetConfig {
+                extends: Some("b".to_string()),
+                ..Default::default()
+            },
+        );
+
+        let merged = config_file.resolve_preset("c").unwrap();
+        assert_eq!(merged.fps, Some(60));
+        assert_eq!(merged.density, Some(2.0));
+    }
+
+    #[test]
+    fn resolve_preset_detects_cycles() {
+        let mut config_file = ConfigFile::default();
+        config_file.presets.insert(
+            "a".to_string(),
+            PresetConfig {
+                extends: Some("b".to_string()),
+                ..Default::default()
+            },
+        );
+        config_file.presets.insert(
+            "b".to_string(),
+            PresetConfig {
+                extends: Some("a".to_string()),
+                ..Default::default()
+            },
+        );
+
+        let err = config_file.resolve_preset("a").err().unwrap();
+        assert!(err.contains("cycle"), "unexpected error: {}", err);
+    }
+
+    #[test]
+    fn resolve_preset_reports_missing_base() {
+        let mut config_file = ConfigFile::default();
+        config_file.presets.insert(
+            "child".to_string(),
+            PresetConfig {
+                extends: Some("ghost".to_string()),
+                ..Default::default()
+            },
+        );
+
+        assert!(config_file.resolve_preset("child").is_err());
+        assert!(config_file.resolve_preset("missing").is_err());
+    }
+
+    #[test]
+    fn preset_from_runtime_captures_live_values() {
+        let cli = Cli::parse_from(["digital_rain", "-e", "fire", "-c", "red", "-s", "1.0"]);
+        let config = Config::resolve(&cli, &ConfigFile::default());
+        let preset = PresetConfig::from_runtime(&config, 2.4, 0.6, true);
+
+        assert_eq!(preset.effect, Some("fire".to_string()));
+        assert_eq!(preset.color, Some("red".to_string()));
+        assert_eq!(preset.speed, Some(2.4));
+        assert_eq!(preset.density, Some(0.6));
+        assert_eq!(preset.crt, Some(true));
+        assert!(preset.extends.is_none());
+    }
+
+    #[test]
+    fn saving_keeps_a_backup_and_leaves_unparseable_files_alone() {
+        let dir = std::env::temp_dir().join(format!("digitalrain-config-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("config.toml");
+        let backup = dir.join("config.toml.bak");
+        let path_str = path.to_str().unwrap();
+        let preset = || PresetConfig {
+            effect: Some("fire".to_string()),
+            ..Default::default()
+        };
+
+        save_preset_config(Some(path_str), "one", preset(), false).unwrap();
+        assert!(!backup.exists());
+        let first = fs::read_to_string(&path).unwrap();
+        save_preset_config(Some(path_str), "two", preset(), false).unwrap();
+        assert_eq!(fs::read_to_string(&backup).unwrap(), first);
+        assert_eq!(load_config_file(Some(path_str)).presets.len(), 2);
+
+        fs::write(&path, "[presets.one\neffect = ").unwrap();
+        let err = save_preset_config(Some(path_str), "three", preset(), false).unwrap_err();
+        assert!(err.contains("--force"));
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[presets.one\neffect = "
+        );
+
+        save_preset_config(Some(path_str), "three", preset(), true).unwrap();
+        assert_eq!(
+            fs::read_to_string(&backup).unwrap(),
+            "[presets.one\neffect = "
+        );
+        let saved = load_config_file(Some(path_str));
+        assert_eq!(saved.presets.keys().collect::<Vec<_>>(), ["three"]);
+        assert!(!dir.join("config.toml.tmp").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn preset_toml_quotes_names_and_pastes_back() {
+        let mut config_file = ConfigFile::default();
+        config_file.presets.insert(
+            "late \"night\".v2".to_string(),
+            PresetConfig {
+                effect: Some("matrix".to_string()),
+                ..Default::default()
+            },
+        );
+
+        let shown = config_file.preset_toml("late \"night\".v2").unwrap();
+        assert!(
+            shown.starts_with("[presets.'late \"night\".v2']"),
+            "{shown}"
+        );
+        let pasted: ConfigFile = toml::from_str(&shown).unwrap();
+        assert_eq!(
+            pasted.presets["late \"night\".v2"].effect.as_deref(),
+            Some("matrix")
+        );
+        assert!(config_file.preset_toml("missing").is_err());
+    }
+
+    #[test]
+    fn delete_preset_removes_entry() {
+        let mut config_file = ConfigFile::default();
+        config_file
+            .presets
+            .insert("gone".to_string(), PresetConfig::default());
+
+        config_file.delete_preset("gone").unwrap();
+        assert!(!config_file.presets.contains_key("gone"));
+        assert!(config_file.delete_preset("gone").is_err());
+    }
+
+    #[test]
+    fn delete_preset_refuses_when_extended() {
+        let mut config_file = ConfigFile::default();
+        config_file
+            .presets
+            .insert("base".to_string(), PresetConfig::default());
+        config_file.presets.insert(
+            "child".to_string(),
+            PresetConfig {
+                extends: Some("base".to_string()),
+                ..Default::default()
+            },
+        );
+
+        assert!(config_file.delete_preset("base").is_err());
+        assert!(config_file.presets.contains_key("base"));
+    }
+
+    #[test]
+    fn preset_changes_follow_playlists() {
+        let mut config_file: ConfigFile = toml::from_str(
+            r#"
+            [presets.calm]
+            effect = "rain"
+            [presets.loud]
+            effect = "fire"
+            [playlists.evening]
+            entries = [{ preset = "calm" }, { preset = "loud", duration = 5 }]
+            [playlists.night]
+            entries = [{ preset = "calm" }]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config_file.delete_preset("calm"),
+            Err("preset 'calm' is used by playlists: evening, night".to_string())
+        );
+        assert!(config_file.presets.contains_key("calm"));
+
+        config_file.rename_preset("calm", "quiet").unwrap();
+        let entries = |name: &str| -> Vec<String> {
+            config_file.playlists[name]
+                .entries
+                .iter()
+                .map(|e| e.preset.clone())
+                .collect()
+        };
+        assert_eq!(entries("evening"), ["quiet", "loud"]);
+        assert_eq!(entries("night"), ["quiet"]);
+    }
+
+    #[test]
+    fn rename_preset_moves_entry_and_updates_extends() {
+        let mut config_file = ConfigFile::default();
+        config_file.presets.insert(
+            "old".to_string(),
+            PresetConfig {
+                effect: Some("fire".to_string()),
+                ..Default::default()
+            },
+        );
+        config_file.presets.insert(
+            "child".to_string(),
+            PresetConfig {
+                extends: Some("old".to_string()),
+                ..Default::default()
+            },
+        );
+
+        config_file.rename_preset("old", "new").unwrap();
+        assert!(!config_file.presets.contains_key("old"));
+        assert_eq!(config_file.presets["new"].effect, Some("fire".to_string()));
+        assert_eq!(
+            config_file.presets["child"].extends,
+            Some("new".to_string())
+        );
+    }
+
+    #[test]
+    fn rename_preset_rejects_existing_target() {
+        let mut config_file = ConfigFile::default();
+        config_file
+            .presets
+            .insert("a".to_string(), PresetConfig::default());
+        config_file
+            .presets
+            .insert("b".to_string(), PresetConfig::default());
+
+        assert!(config_file.rename_preset("a", "b").is_err());
+        assert!(config_file.rename_preset("missing", "c").is_err());
+    }
+
+    #[test]
+    fn check_config_accepts_valid_file() {
+        let content = r#"
+[defaults]
+effect = "classic"
+speed = 1.5
+fps = 60
+
+[presets.base]
+color = "coral"
+crt = true
+
+[presets.child]
+extends = "base"
+charset = "binary"
+"#;
+        let issues = check_config_str(content);
+        assert!(issues.is_empty(), "unexpected: {}", issues[0].message);
+    }
+
+    #[test]
+    fn check_config_reports_unknown_key_with_position() {
+        let content = "[defaults]\nefect = \"fire\"\n";
+        let issues = check_config_str(content);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, 2);
+        assert_eq!(issues[0].column, 1);
+        assert!(issues[0].message.contains("efect"));
+    }
+
+    #[test]
+    fn check_config_reports_bad_names_and_ranges() {
+        let content = r#"
+[presets.bad]
+effect = "nope"
+color = "notacolor"
+charset = "klingon"
+speed = 50.0
+fps = 5
+crt_intensity = "high"
+crt_curvature = 3
+extends = "missing"
+"#;
+        let issues = check_config_str(content);
+        assert_eq!(issues.len(), 8);
+    }
+
+    #[test]
+    fn check_config_validates_playlists() {
+        let content = r#"
+[presets.a]
+effect = "fire"
+
+[playlists.good]
+entries = [{ preset = "a", duration = 10 }]
+
+[playlists.bad]
+entries = [{ preset = "missing", duration = 0 }, { duration = 5, extra = 1 }]
+"#;
+        let issues = check_config_str(content);
+        // unknown preset, duration out of range, unknown key, missing preset
+        assert_eq!(issues.len(), 4);
+    }
+
+    #[test]
+    fn playlist_resolves_entries_in_order() {
+        let cli = Cli::parse_from(["digital_rain", "-s", "2.0"]);
+        let mut config_file = ConfigFile::default();
+        for (name, effect) in [("one", "fire"), ("two", "ocean")] {
+            config_file.presets.insert(
+                name.to_string(),
+                PresetConfig {
+                    effect: Some(effect.to_string()),
+                    ..Default::default()
+                },
+            );
+        }
+        config_file.playlists.insert(
+            "show".to_string(),
+            PlaylistConfig {
+                entries: vec![
+                    PlaylistEntryConfig {
+                        preset: "two".to_string(),
+                        duration: Some(5.0),
+                    },
+                    PlaylistEntryConfig {
+                        preset: "one".to_string(),
+                        duration: None,
+                    },
+                ],
+            },
+        );
+
+        let steps = Config::playlist(&cli, &config_file, "show").unwrap();
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].config.effect_name, "ocean");
+        assert_eq!(steps[1].config.effect_name, "fire");
+        assert!((steps[0].duration - 5.0).abs() < 0.01);
+        assert!((steps[1].duration - 30.0).abs() < 0.01);
+        // CLI override applies to every entry
+        assert!((steps[1].config.speed_multiplier - 2.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn playlist_rejects_unknown_names() {
+        let cli = Cli::parse_from(["digital_rain"]);
+        let mut config_file = ConfigFile::default();
+        assert!(Config::playlist(&cli, &config_file, "nope").is_err());
+
+        config_file.playlists.insert(
+            "broken".to_string(),
+            PlaylistConfig {
+                entries: vec![PlaylistEntryConfig {
+                    preset: "ghost".to_string(),
+                    duration: None,
+                }],
+            },
+        );
+        assert!(Config::playlist(&cli, &config_file, "broken").is_err());
+    }
+
+    #[test]
+    fn check_config_reports_syntax_errors() {
+        let issues = check_config_str("[defaults\nspeed = 1.0\n");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, 1);
+    }
+
+    #[test]
+    fn effect_defaults_sit_between_preset_and_defaults() {
+        let config_file: ConfigFile = toml::from_str(
+            r#"
+            [defaults]
+            color = "gold"
+            density = 0.5
+
+            [effect_defaults.fire]
+            color = "fire"
+            density = 1.5
+
+            [presets.hot]
+            effect = "fire"
+            density = 3.0
+            "#,
+        )
+        .unwrap();
+
+        let cli = Cli::parse_from(["digital_rain", "-e", "fire"]);
+        let config = Config::resolve(&cli, &config_file);
+        assert_eq!(config.palette_name, "fire");
+        assert_eq!(config.density_multiplier, 1.5);
+
+        // Preset beats effect defaults; CLI beats both
+        let cli = Cli::parse_from(["digital_rain", "--preset", "hot", "-c", "snow"]);
+        let config = Config::resolve(&cli, &config_file);
+        assert_eq!(config.density_multiplier, 3.0);
+        assert_eq!(config.palette_name, "snow");
+
+        // Other effects still get [defaults]
+        let cli = Cli::parse_from(["digital_rain", "-e", "ocean"]);
+        assert_eq!(Config::resolve(&cli, &config_file).palette_name, "gold");
+    }
+
+    #[test]
+    fn apply_effect_defaults_overrides_on_switch() {
+        let config_file: ConfigFile =
+            toml::from_str("[effect_defaults.fire]\ncolor = \"fire\"\nspeed = 20.0\ncrt = true\n")
+                .unwrap();
+        let mut config = Config::resolve(
+            &Cli::parse_from(["digital_rain", "-c", "gold"]),
+            &config_file,
+        );
+
+        // No defaults for classic: unchanged
+        config.apply_effect_defaults(&config_file);
+        assert_eq!(config.palette_name, "gold");
+
+        config.effect_name = "fire".to_string();
+        config.apply_effect_defaults(&config_file);
+        assert_eq!(config.palette_name, "fire");
+        assert_eq!(config.speed_multiplier, 10.0);
+        assert!(config.crt_enabled);
+    }
+
+    #[test]
+    fn check_config_validates_effect_defaults() {
+        let issues = check_config_str(
+            "[effect_defaults.fire]\ncolor = \"fire\"\nfps = 60\n\n[effect_defaults.nope]\n",
+        );
+        assert_eq!(issues.len(), 2);
+        assert!(issues[0].message.contains("unknown key 'fps'"));
+        assert!(issues[1].message.contains("unknown effect 'nope'"));
+    }
+
+    #[test]
+    fn random_constraints_combine_config_and_cli() {
+        let config_file: ConfigFile = toml::from_str(
+            r#"
+            [random]
+            include_effects = ["classic", "fire", "ocean"]
+            exclude_colors = ["snow"]
+            speed_range = [1.0, 1.5]
+            "#,
+        )
+        .unwrap();
+        let cli = Cli::parse_from([
+            "digital_rain",
+            "--random-exclude",
+            "effects=ocean,fire",
+            "colors=ivory",
+        ]);
+        let constraints = RandomConstraints::resolve(&cli, &config_file).unwrap();
+        assert_eq!(constraints.effects, vec!["classic"]);
+        assert!(
+            !constraints
+                .colors
+                .iter()
+                .any(|c| c == "snow" || c == "ivory")
+        );
+        assert_eq!(constraints.speed, (1.0, 1.5));
+
+        let mut rng = crate::rng::make_rng(Some(3), crate::rng::Stream::Config);
+        for _ in 0..50 {
+            let config = Config::randomized(&constraints, &mut rng);
+            assert_eq!(config.effect_name, "classic");
+            assert!((1.0..=1.5).contains(&config.speed_multiplier));
+            assert_ne!(config.palette_name, "snow");
+        }
+    }
+
+    #[test]
+    fn randomize_keeps_runtime_settings_and_rolls_the_look() {
+        let cli = Cli::parse_from([
+            "digital_rain",
+            "--fps",
+            "60",
+            "--mutation",
+            "0",
+            "--brightness",
+            "0.8",
+            "--seed",
+            "2",
+        ]);
+        let current = Config::resolve(&cli, &ConfigFile::default());
+        let constraints = RandomConstraints::resolve(&cli, &ConfigFile::default()).unwrap();
+        let mut rng = crate::rng::make_rng(Some(3), crate::rng::Stream::Config);
+        let mut next = Config::randomized(&constraints, &mut rng);
+        next.keep_runtime_settings(&current);
+        assert_eq!(next.target_fps, 60);
+        assert_eq!(next.mutation_rate, 0.0);
+        assert_eq!(next.brightness, 0.8);
+        assert_eq!(next.seed, Some(2));
+        assert_ne!(
+            (next.speed_multiplier, &next.palette_name),
+            (current.speed_multiplier, &current.palette_name)
+        );
+    }
+
+    #[test]
+    fn random_pool_skips_effects_without_their_input() {
+        let effects = |args: &[&str], config_file: &ConfigFile| {
+            let argv = [&["digital_rain"][..], args].concat();
+            RandomConstraints::resolve(&Cli::parse_from(argv), config_file)
+                .unwrap()
+                .effects
+        };
+        let plain = effects(&[], &ConfigFile::default());
+        assert!(plain.iter().all(|e| e != "spectrum" && e != "gitrain"));
+        assert!(plain.iter().any(|e| e == "classic"));
+
+        let fed = effects(&["--audio", "-", "--git-repo", "."], &ConfigFile::default());
+        assert!(fed.iter().any(|e| e == "spectrum") && fed.iter().any(|e| e == "gitrain"));
+
+        let mut config_file = ConfigFile::default();
+        config_file.random.include_effects = vec!["gitrain".to_string()];
+        assert_eq!(effects(&[], &config_file), ["gitrain"]);
+    }
+
+    #[test]
+    fn random_constraints_reject_bad_input() {
+        let file = ConfigFile::default();
+        let resolve = |args: &[&str]| {
+            let mut argv = vec!["digital_rain", "--random-exclude"];
+            argv.extend_from_slice(args);
+            RandomConstraints::resolve(&Cli::parse_from(argv), &file).err()
+        };
+        assert!(
+            resolve(&["effects=nope"])
+                .unwrap()
+                .contains("unknown effect")
+        );
+        assert!(
+            resolve(&["shapes=x"])
+                .unwrap()
+                .contains("unknown --random-exclude kind")
+        );
+        assert!(resolve(&["effects"]).unwrap().contains("KIND=LIST"));
+        let all = crate::rain::chars::charset_names().join(",");
+        assert!(
+            resolve(&[&format!("charsets={}", all)])
+                .unwrap()
+                .contains("exclude every charset")
+        );
+
+        let inverted: ConfigFile =
+            toml::from_str("[random]\ndensity_range = [2.0, 1.0]\n").unwrap();
+        let cli = Cli::parse_from(["digital_rain"]);
+        assert!(RandomConstraints::resolve(&cli, &inverted).is_err());
+    }
+
+    #[test]
+    fn check_config_validates_random_section() {
+        let issues = check_config_str(
+            "[random]\nexclude_effects = [\"fire\", \"nope\"]\nspeed_range = [3.0, 1.0]\nbogus = 1\n",
+        );
+        let messages: Vec<&str> = issues.iter().map(|i| i.message.as_str()).collect();
+        assert_eq!(messages.len(), 3, "{:?}", messages);
+        for expected in [
+            "unknown effect 'nope'",
+            "min is greater than max",
+            "unknown key 'bogus'",
+        ] {
+            assert!(
+                messages.iter().any(|m| m.contains(expected)),
+                "{:?}",
+                messages
+            );
+        }
+    }
+
+    #[test]
+    fn check_config_validates_overlay_section() {
+        let ok = check_config_str(
+            "[overlay]\nbackground = \"#101820\"\ntitle = \"gold\"\nborder = \"rounded\"\nopacity = 0.7\ncrt_affects_overlays = true\n",
+        );
+        assert!(ok.is_empty());
+
+        let issues = check_config_str(
+            "[overlay]\ntext = \"#zzz\"\nborder = \"wavy\"\nopacity = 2\nshadow = true\ncrt_affects_overlays = 1\n",
+        );
+        let messages: Vec<&str> = issues.iter().map(|i| i.message.as_str()).collect();
+        assert_eq!(messages.len(), 5, "{:?}", messages);
+        for expected in [
+            "unknown color '#zzz'",
+            "unknown border 'wavy'",
+            "opacity = 2 is out of range",
+            "unknown key 'shadow' in [overlay]",
+            "'crt_affects_overlays' must be true or false",
+        ] {
+            assert!(
+                messages.iter().any(|m| m.contains(expected)),
+                "{:?}",
+                messages
+            );
+        }
+    }
+
+    #[test]
+    fn check_config_validates_keys_section() {
+        let ok = check_config_str(
+            "[keys]\npause = \"p\"\nnext_effect = [\"right\", \"ctrl+n\"]\nquit = []\n",
+        );
+        assert!(ok.is_empty());
+
+        let issues =
+            check_config_str("[keys]\npause = \"x\"\nquit = \"x\"\nwarp = \"w\"\nrandomize = 3\n");
+        let messages: Vec<&str> = issues.iter().map(|i| i.message.as_str()).collect();
+        assert_eq!(messages.len(), 3, "{:?}", messages);
+        assert!(messages.contains(&"key 'x' is bound to both pause and quit"));
+        assert!(messages.contains(&"unknown action 'warp'"));
+        assert!(messages.contains(&"'randomize' must be a key or an array of keys"));
+        // The conflict is reported on the line of the losing binding
+        let conflict = issues.iter().find(|i| i.message.contains("both")).unwrap();
+        assert_eq!(conflict.line, 3);
+    }
+
+    #[test]
+    fn parse_color_morph_checks_palettes_and_period() {
+        assert_eq!(
+            parse_color_morph("classic:purple:2m"),
+            Ok(ColorMorph {
+                from: "classic".to_string(),
+                to: "purple".to_string(),
+                period: 120.0,
+            })
+        );
+        assert!(parse_color_morph("classic:coral:30").is_ok());
+        assert!(parse_color_morph("classic:purple").is_err());
+        assert!(parse_color_morph("classic:nope:30").is_err());
+        assert!(parse_color_morph("classic:purple:0").is_err());
+    }
+
+    #[test]
+    fn parse_duration_accepts_units_and_bare_seconds() {
+        assert_eq!(parse_duration("90"), Ok(90.0));
+        assert_eq!(parse_duration("2m30s"), Ok(150.0));
+        assert_eq!(parse_duration("1h 15m"), Ok(4500.0));
+        assert_eq!(parse_duration("1.5m"), Ok(90.0));
+        assert_eq!(parse_duration("500ms"), Ok(0.5));
+        assert!(parse_duration("0").is_err());
+        assert!(parse_duration("5x").is_err());
+        assert!(parse_duration("2m30").is_err());
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("1e20").is_err());
+        assert!(parse_duration("inf").is_err());
+        assert!(parse_duration("99999999999999999999h").is_err());
+    }
+
+    #[test]
+    fn config_format_from_extension() {
+        use std::path::Path;
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("a/config.toml")),
+            ConfigFormat::Toml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config.JSON")),
+            ConfigFormat::Json
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config.yml")),
+            ConfigFormat::Yaml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config")),
+            ConfigFormat::Toml
+        );
+    }
+
+    #[test]
+    fn json_and_yaml_configs_roundtrip() {
+        let mut config = ConfigFile::default();
+        config.defaults.speed = Some(1.0);
+        config.defaults.crt = Some(true);
+        config.presets.insert(
+            "late night".to_string(),
+            PresetConfig {
+                extends: Some("base".to_string()),
+                fps: Some(30),
+                ..Default::default()
+            },
+        );
+        config.playlists.insert(
+            "evening".to_string(),
+            PlaylistConfig {
+                entries: vec![PlaylistEntryConfig {
+                    preset: "late night".to_string(),
+                    duration: Some(45.0),
+                }],
+            },
+        );
+
+        for format in [ConfigFormat::Json, ConfigFormat::Yaml] {
+            let text = format.serialize(&config).unwrap();
+            let parsed = format.parse(&text).unwrap();
+            assert_eq!(parsed.defaults.speed, Some(1.0), "{:?}", format);
+            assert_eq!(parsed.defaults.crt, Some(true));
+            let preset = &parsed.presets["late night"];
+            assert_eq!(preset.extends.as_deref(), Some("base"));
+            assert_eq!(preset.fps, Some(30));
+            assert_eq!(parsed.playlists["evening"].entries[0].duration, Some(45.0));
+        }
+    }
+
+    #[test]
+    fn json_config_accepts_integer_for_float_field() {
+        let parsed = ConfigFormat::Json
+            .parse(r#"{"defaults": {"speed": 2, "effect": "fire"}}"#)
+            .unwrap();
+        assert_eq!(parsed.defaults.speed, Some(2.0));
+    }
+
+    #[test]
+    fn check_config_places_json_and_yaml_issues() {
+        let issues = check_config_converted(
+            ConfigFormat::Yaml,
+            "defaults:\n  speed: 50\n  colour: green\n",
+        );
+        let at: Vec<_> = issues.iter().map(|i| (i.line, i.column)).collect();
+        assert_eq!(at, [(2, 3), (3, 3)]);
+
+        let issues = check_config_converted(
+            ConfigFormat::Json,
+            "{\"playlists\": {\"p\": {\"entries\": [\n  {\"preset\": \"a\"},\n  {\"duration\": 5}\n]}}}",
+        );
+        let found: Vec<_> = issues
+            .iter()
+            .map(|i| (i.line, i.column, i.message.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (2, 4, "unknown preset 'a'"),
+                (3, 3, "playlist entry is missing 'preset'"),
+            ]
+        );
+
+        let syntax = check_config_converted(ConfigFormat::Json, "{\"defaults\":\n  tru}");
+        assert_eq!(syntax.len(), 1);
+        assert_eq!((syntax[0].line, syntax[0].column), (2, 6));
+        assert!(!syntax[0].message.starts_with("2:"));
+    }
+
+    #[test]
+    fn check_config_rejects_extends_cycles() {
+        let issues = check_config_str(
+            "[presets.a]\nextends = \"b\"\n\n[presets.b]\nextends = \"a\"\n\n[presets.c]\nextends = \"c\"\n",
+        );
+        let found: Vec<_> = issues
+            .iter()
+            .map(|i| (i.line, i.message.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (2, "preset inheritance cycle: a -> b -> a"),
+                (5, "preset inheritance cycle: b -> a -> b"),
+                (8, "preset inheritance cycle: c -> c"),
+            ]
+        );
+    }
+
     #[test]
     fn config_file_roundtrip() {
         let mut config = ConfigFile::default();
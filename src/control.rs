@@ -0,0 +1,633 @@
+//! Scripted control for `--control-stdin`: newline-delimited JSON commands.
+//!
+//! Each line is one object naming a command and its arguments:
+//!
+//! ```text
+//! {"cmd": "set_effect", "name": "fire"}
+//! {"cmd": "set_palette", "name": "synthwave"}
+//! {"cmd": "set_charset", "name": "binary"}
+//! {"cmd": "set_speed", "value": 2.5}
+//! {"cmd": "set_density", "value": 0.5}
+//! {"cmd": "set_mutation", "value": 0.1}
+//! {"cmd": "crt", "on": true}            (without "on": toggle)
+//! {"cmd": "pause", "paused": true}      (without "paused": toggle)
+//! {"cmd": "show_banner", "text": "DEPLOYED"}
+//! {"cmd": "hide_banner"}
+//! {"cmd": "load_preset", "name": "night"}
+//! {"cmd": "save_preset", "name": "night"}
+//! {"cmd": "quit"}
+//! ```
+//!
+//! Every command becomes a `command::Command`, checked exactly like the
+//! same thing typed on the `:` command line, and the main loop applies it
+//! the same way. A background thread reads the lines, like `--notify`.
+//!
+//! `--control-socket` takes the same lines on a Unix domain socket (by
+//! default `$XDG_RUNTIME_DIR/digitalrain.sock`), or a named pipe on Windows
+//! (`\\.\pipe\digitalrain-<user>`), one connection per command or many,
+//! and answers each line with `ok` or `error: <why>`.
+//! `digital_rain ctl effect fire` is the client: it parses a `:` command
+//! line (or takes a JSON command as is) and sends it as JSON.
+
+use std::env;
+use std::io::{self, BufRead};
+#[cfg(any(unix, windows))]
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+#[cfg(any(unix, windows))]
+use std::sync::mpsc::Sender;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use toml::Value;
+
+use crate::command::{self, Command};
+use crate::formats;
+use crate::notify;
+
+/// Receiving end of a control stream.
+pub struct ControlFeed {
+    commands: Receiver<Result<Command, String>>,
+    /// Socket file to remove when the feed is dropped
+    #[cfg(unix)]
+    socket: Option<SocketFile>,
+}
+
+/// A socket file we bound, known by its inode so that only that file is
+/// removed, not whatever has replaced it since.
+#[cfg(unix)]
+struct SocketFile {
+    path: PathBuf,
+    id: (u64, u64),
+}
+
+#[cfg(unix)]
+impl SocketFile {
+    /// Device and inode of the socket at `path`, or `None` if there's
+    /// something else there (or nothing).
+    fn id_at(path: &Path) -> Option<(u64, u64)> {
+        use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+        let meta = std::fs::symlink_metadata(path).ok()?;
+        meta.file_type()
+            .is_socket()
+            .then(|| (meta.dev(), meta.ino()))
+    }
+}
+
+impl ControlFeed {
+    /// Start reading commands from stdin.
+    pub fn spawn_stdin() -> Self {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let Ok(line) = line else { break };
+                let line = notify::clean_line(&line);
+                if !line.is_empty() && tx.send(parse(&line)).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            commands: rx,
+            #[cfg(unix)]
+            socket: None,
+        }
+    }
+
+    /// Listen for commands on a Unix socket at `path`. A socket file left
+    /// behind by an instance that's gone is replaced; one that's still
+    /// answering is an error, and so is anything at `path` that isn't a
+    /// socket. Only the current user may connect.
+    #[cfg(unix)]
+    pub fn listen(path: &Path) -> Result<Self, String> {
+        use std::fs;
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+        use std::os::unix::net::{UnixListener, UnixStream};
+
+        if fs::symlink_metadata(path).is_ok() {
+            if SocketFile::id_at(path).is_none() {
+                return Err(format!("{} exists and isn't a socket", path.display()));
+            }
+            if UnixStream::connect(path).is_ok() {
+                return Err(format!(
+                    "another instance is listening on {}",
+                    path.display()
+                ));
+            }
+            fs::remove_file(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+        // Bound inside a directory only we can enter, so nobody can connect
+        // before the mode is set, then moved into place
+        let private = path.with_file_name(format!(".digitalrain-{}.tmp", std::process::id()));
+        fs::DirBuilder::new()
+            .mode(0o700)
+            .create(&private)
+            .map_err(|e| format!("{}: {}", private.display(), e))?;
+        let staged = private.join("control.sock");
+        let bound = UnixListener::bind(&staged).and_then(|listener| {
+            fs::set_permissions(&staged, fs::Permissions::from_mode(0o600))?;
+            let id = SocketFile::id_at(&staged)
+                .ok_or_else(|| io::Error::other("the bound socket vanished"))?;
+            fs::rename(&staged, path)?;
+            Ok((listener, id))
+        });
+        let _ = fs::remove_file(&staged);
+        let _ = fs::remove_dir(&private);
+        let (listener, id) = bound.map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    if let Ok(reply) = stream.try_clone() {
+                        serve(stream, reply, &tx);
+                    }
+                });
+            }
+        });
+        Ok(Self {
+            commands: rx,
+            socket: Some(SocketFile {
+                path: path.to_path_buf(),
+                id,
+            }),
+        })
+    }
+
+    /// Listen for commands on the named pipe `path`. Only one instance may
+    /// own a pipe name, and remote clients are turned away.
+    #[cfg(windows)]
+    pub fn listen(path: &Path) -> Result<Self, String> {
+        let mut waiting = pipe::create(path, true).map_err(|e| match e.raw_os_error() {
+            Some(pipe::ERROR_ACCESS_DENIED) => {
+                format!("another instance is listening on {}", path.display())
+            }
+            _ => format!("{}: {}", path.display(), e),
+        })?;
+
+        let (tx, rx) = mpsc::channel();
+        let path = path.to_path_buf();
+        thread::spawn(move || {
+            loop {
+                if pipe::accept(&waiting).is_err() {
+                    break;
+                }
+                // The next sender needs an instance of its own to connect to
+                let Ok(next) = pipe::create(&path, false) else {
+                    break;
+                };
+                let connected = std::mem::replace(&mut waiting, next);
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    if let Ok(reply) = connected.try_clone() {
+                        serve(connected, reply, &tx);
+                    }
+                });
+            }
+        });
+        // A pipe goes away with its last handle; there's no file to remove
+        Ok(Self { commands: rx })
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    pub fn listen(_path: &Path) -> Result<Self, String> {
+        Err("control sockets aren't available on this platform".to_string())
+    }
+
+    /// Commands received since the last call, each parsed or with the
+    /// reason it was rejected.
+    pub fn drain(&self) -> Vec<Result<Command, String>> {
+        self.commands.try_iter().collect()
+    }
+}
+
+#[cfg(unix)]
+impl Drop for ControlFeed {
+    fn drop(&mut self) {
+        if let Some(ref socket) = self.socket
+            && SocketFile::id_at(&socket.path) == Some(socket.id)
+        {
+            let _ = std::fs::remove_file(&socket.path);
+        }
+    }
+}
+
+/// Answer the lines of one connection until the sender hangs up.
+#[cfg(any(unix, windows))]
+fn serve(connection: impl Read, mut reply: impl Write, tx: &Sender<Result<Command, String>>) {
+    for line in io::BufReader::new(connection).lines() {
+        let Ok(line) = line else { break };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let answer = match parse(&line) {
+            Ok(command) => {
+                if tx.send(Ok(command)).is_err() {
+                    return;
+                }
+                "ok".to_string()
+            }
+            // Told to the sender rather than the screen
+            Err(e) => format!("error: {}", e),
+        };
+        if writeln!(reply, "{}", answer).is_err() {
+            break;
+        }
+    }
+}
+
+/// Where `--control-socket` listens and `ctl` connects without a path:
+/// `$XDG_RUNTIME_DIR/digitalrain.sock`, or a per-user name in the temp
+/// directory.
+#[cfg(not(windows))]
+pub fn default_socket_path() -> PathBuf {
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir).join("digitalrain.sock"),
+        _ => {
+            let user = env::var("USER").unwrap_or_else(|_| "user".to_string());
+            env::temp_dir().join(format!("digitalrain-{}.sock", user))
+        }
+    }
+}
+
+/// Where `--control-socket` listens and `ctl` connects without a path: a
+/// per-user pipe name.
+#[cfg(windows)]
+pub fn default_socket_path() -> PathBuf {
+    let user = env::var("USERNAME").unwrap_or_else(|_| "user".to_string());
+    PathBuf::from(format!(r"\\.\pipe\digitalrain-{}", user))
+}
+
+/// Send one command to the instance listening on `path` (for `ctl`).
+/// `words` are a `:` command line, or a single JSON command.
+pub fn send(path: &Path, words: &[String]) -> Result<(), String> {
+    let json = match words {
+        [json] if json.trim_start().starts_with('{') => {
+            parse(json)?;
+            json.trim().to_string()
+        }
+        _ => to_json(&command::parse(&words.join(" "))?),
+    };
+    request(path, &json)
+}
+
+#[cfg(unix)]
+fn request(path: &Path, json: &str) -> Result<(), String> {
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(path)
+        .map_err(|e| format!("no instance is listening on {} ({})", path.display(), e))?;
+    writeln!(stream, "{}", json).map_err(|e| e.to_string())?;
+    stream
+        .shutdown(std::net::Shutdown::Write)
+        .map_err(|e| e.to_string())?;
+    read_answer(stream)
+}
+
+#[cfg(windows)]
+fn request(path: &Path, json: &str) -> Result<(), String> {
+    use std::fs::OpenOptions;
+    use std::time::Duration;
+
+    let mut tries = 0;
+    let mut stream = loop {
+        match OpenOptions::new().read(true).write(true).open(path) {
+            Ok(stream) => break stream,
+            // Every instance is taken; the listener makes another shortly
+            Err(e) if e.raw_os_error() == Some(pipe::ERROR_PIPE_BUSY) && tries < 50 => {
+                tries += 1;
+                thread::sleep(Duration::from_millis(20));
+            }
+            Err(e) => {
+                return Err(format!(
+                    "no instance is listening on {} ({})",
+                    path.display(),
+                    e
+                ));
+            }
+        }
+    };
+    writeln!(stream, "{}", json).map_err(|e| e.to_string())?;
+    read_answer(stream)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn request(_path: &Path, _json: &str) -> Result<(), String> {
+    Err("control sockets aren't available on this platform".to_string())
+}
+
+/// The listener's reply to one command.
+#[cfg(any(unix, windows))]
+fn read_answer(connection: impl Read) -> Result<(), String> {
+    let mut answer = String::new();
+    io::BufReader::new(connection)
+        .read_line(&mut answer)
+        .map_err(|e| e.to_string())?;
+    match answer.trim() {
+        "ok" => Ok(()),
+        other => Err(other.strip_prefix("error: ").unwrap_or(other).to_string()),
+    }
+}
+
+/// Named pipes, for `--control-socket` on Windows.
+#[cfg(windows)]
+mod pipe {
+    use std::fs::File;
+    use std::io;
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::io::{AsRawHandle, FromRawHandle, RawHandle};
+    use std::path::Path;
+    use std::ptr;
+
+    const PIPE_ACCESS_DUPLEX: u32 = 0x3;
+    const FILE_FLAG_FIRST_PIPE_INSTANCE: u32 = 0x0008_0000;
+    /// Byte stream, blocking, local clients only
+    const PIPE_MODE: u32 = 0x8;
+    const PIPE_UNLIMITED_INSTANCES: u32 = 255;
+    const BUFFER_SIZE: u32 = 4096;
+    const ERROR_PIPE_CONNECTED: i32 = 535;
+    pub const ERROR_ACCESS_DENIED: i32 = 5;
+    pub const ERROR_PIPE_BUSY: i32 = 231;
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn CreateNamedPipeW(
+            name: *const u16,
+            open_mode: u32,
+            pipe_mode: u32,
+            max_instances: u32,
+            out_buffer_size: u32,
+            in_buffer_size: u32,
+            default_timeout: u32,
+            security: *mut std::ffi::c_void,
+        ) -> RawHandle;
+        fn ConnectNamedPipe(pipe: RawHandle, overlapped: *mut std::ffi::c_void) -> i32;
+    }
+
+    /// A new instance of the pipe `path`. The `first` one fails if some
+    /// other process already owns the name.
+    pub fn create(path: &Path, first: bool) -> io::Result<File> {
+        let name: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+        let mut open_mode = PIPE_ACCESS_DUPLEX;
+        if first {
+            open_mode |= FILE_FLAG_FIRST_PIPE_INSTANCE;
+        }
+        // SAFETY: `name` is NUL-terminated and outlives the call; the
+        // default security descriptor is asked for with a null pointer
+        let handle = unsafe {
+            CreateNamedPipeW(
+                name.as_ptr(),
+                open_mode,
+                PIPE_MODE,
+                PIPE_UNLIMITED_INSTANCES,
+                BUFFER_SIZE,
+                BUFFER_SIZE,
+                0,
+                ptr::null_mut(),
+            )
+        };
+        // INVALID_HANDLE_VALUE
+        if handle as isize == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: a valid handle that nothing else owns
+        Ok(unsafe { File::from_raw_handle(handle) })
+    }
+
+    /// Wait for a client to connect to `pipe`.
+    pub fn accept(pipe: &File) -> io::Result<()> {
+        // SAFETY: a live pipe handle, opened without overlapped I/O
+        if unsafe { ConnectNamedPipe(pipe.as_raw_handle(), ptr::null_mut()) } != 0 {
+            return Ok(());
+        }
+        let e = io::Error::last_os_error();
+        // A client that connected before the call is just as good
+        if e.raw_os_error() == Some(ERROR_PIPE_CONNECTED) {
+            Ok(())
+        } else {
+            Err(e)
+        }
+    }
+}
+
+/// The JSON line for a command; `parse` reads it back.
+pub fn to_json(command: &Command) -> String {
+    let named = |cmd: &str, key: &str, value: &str| {
+        format!(r#"{{"cmd":"{}","{}":{}}}"#, cmd, key, formats::quote(value))
+    };
+    let valued = |cmd: &str, value: f64| format!(r#"{{"cmd":"{}","value":{}}}"#, cmd, value);
+    let bare = |cmd: &str| format!(r#"{{"cmd":"{}"}}"#, cmd);
+    let flag = |cmd: &str, key: &str, on: Option<bool>| match on {
+        Some(on) => format!(r#"{{"cmd":"{}","{}":{}}}"#, cmd, key, on),
+        None => bare(cmd),
+    };
+    match command {
+        Command::Effect(name) => named("set_effect", "name", name),
+        Command::Color(name) => named("set_palette", "name", name),
+        Command::Charset(name) => named("set_charset", "name", name),
+        Command::Speed(value) => valued("set_speed", *value),
+        Command::Density(value) => valued("set_density", *value),
+        Command::Mutation(value) => valued("set_mutation", *value),
+        Command::Crt(on) => flag("crt", "on", *on),
+        Command::Pause(paused) => flag("pause", "paused", *paused),
+        Command::Banner(Some(text)) => named("show_banner", "text", text),
+        Command::Banner(None) => bare("hide_banner"),
+        Command::SavePreset(name) => named("save_preset", "name", name),
+        Command::LoadPreset(name) => named("load_preset", "name", name),
+        Command::Quit => bare("quit"),
+    }
+}
+
+/// Parse one JSON command line.
+pub fn parse(line: &str) -> Result<Command, String> {
+    let value = formats::parse_json(line)?;
+    let cmd = value
+        .get("cmd")
+        .and_then(Value::as_str)
+        .ok_or("missing \"cmd\"")?;
+    let text = |key: &str| {
+        value
+            .get(key)
+            .and_then(Value::as_str)
+            .ok_or_else(|| format!("{} needs a \"{}\" string", cmd, key))
+    };
+    let number = |key: &str| match value.get(key) {
+        Some(Value::Integer(i)) => Ok(*i as f64),
+        Some(Value::Float(f)) => Ok(*f),
+        _ => Err(format!("{} needs a \"{}\" number", cmd, key)),
+    };
+    let flag = |key: &str| match value.get(key) {
+        None => Ok(None),
+        Some(Value::Boolean(b)) => Ok(Some(*b)),
+        Some(_) => Err(format!("\"{}\" must be true or false", key)),
+    };
+    // Names and numbers go through the `:` command parser so they're
+    // checked and clamped the same way
+    let named = |command: &str| -> Result<Command, String> {
+        let name = text("name")?;
+        if name.split_whitespace().count() != 1 {
+            return Err(format!("'{}' is not a {} name", name, command));
+        }
+        command::parse(&format!("{} {}", command, name))
+    };
+    let valued = |command: &str| -> Result<Command, String> {
+        command::parse(&format!("{} {}", command, number("value")?))
+    };
+
+    match cmd {
+        "set_effect" => named("effect"),
+        "set_palette" | "set_color" => named("color"),
+        "set_charset" => named("charset"),
+        "set_speed" => valued("speed"),
+        "set_density" => valued("density"),
+        "set_mutation" => valued("mutation"),
+        "crt" => flag("on").map(Command::Crt),
+        "pause" => flag("paused").map(Command::Pause),
+        "show_banner" => {
+            let banner = text("text")?.trim();
+            if banner.is_empty() {
+                Err("show_banner needs some text".to_string())
+            } else {
+                Ok(Command::Banner(Some(banner.to_string())))
+            }
+        }
+        "hide_banner" => Ok(Command::Banner(None)),
+        "load_preset" => text("name").map(|name| Command::LoadPreset(name.to_string())),
+        "save_preset" => text("name").map(|name| Command::SavePreset(name.to_string())),
+        "quit" => Ok(Command::Quit),
+        _ => Err(format!("unknown cmd '{}'", cmd)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_json_onto_commands() {
+        assert_eq!(
+            parse(r#"{"cmd":"set_effect","name":"fire"}"#),
+            Ok(Command::Effect("fire".into()))
+        );
+        assert_eq!(
+            parse(r#"{"cmd": "set_palette", "name": "SynthWave"}"#),
+            Ok(Command::Color("synthwave".into()))
+        );
+        // Whole numbers are fine, and values are clamped like typed ones
+        assert_eq!(
+            parse(r#"{"cmd":"set_speed","value":2}"#),
+            Ok(Command::Speed(2.0))
+        );
+        assert_eq!(
+            parse(r#"{"cmd":"set_density","value":99.5}"#),
+            Ok(Command::Density(10.0))
+        );
+        assert_eq!(parse(r#"{"cmd":"pause"}"#), Ok(Command::Pause(None)));
+        assert_eq!(
+            parse(r#"{"cmd":"crt","on":false}"#),
+            Ok(Command::Crt(Some(false)))
+        );
+        assert_eq!(
+            parse(r#"{"cmd":"show_banner","text":"  BUILD  PASSED "}"#),
+            Ok(Command::Banner(Some("BUILD  PASSED".into())))
+        );
+        assert_eq!(parse(r#"{"cmd":"quit"}"#), Ok(Command::Quit));
+
+        assert_eq!(
+            parse(r#"{"cmd":"set_effect","name":"warp"}"#),
+            Err("unknown effect 'warp'".into())
+        );
+        assert_eq!(
+            parse(r#"{"cmd":"set_speed","value":"fast"}"#),
+            Err("set_speed needs a \"value\" number".into())
+        );
+        assert_eq!(
+            parse(r#"{"cmd":"pause","paused":"yes"}"#),
+            Err("\"paused\" must be true or false".into())
+        );
+        assert_eq!(parse(r#"{"name":"fire"}"#), Err("missing \"cmd\"".into()));
+        assert_eq!(parse(r#"{"cmd":"warp"}"#), Err("unknown cmd 'warp'".into()));
+        assert!(parse("effect fire").is_err());
+    }
+
+    #[test]
+    fn typed_commands_survive_the_json_trip() {
+        for line in [
+            "effect fire",
+            "color ocean",
+            "charset binary",
+            "speed 2.5",
+            "density 1",
+            "mutation 0.05",
+            "crt",
+            "crt on",
+            "pause off",
+            "banner \"QUOTED\" TEXT",
+            "banner off",
+            "preset save night",
+            "preset load night",
+            "quit",
+        ] {
+            let command = command::parse(line).unwrap();
+            assert_eq!(parse(&to_json(&command)), Ok(command), "{}", line);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn ctl_reaches_a_listening_instance() {
+        let path = env::temp_dir().join(format!("digitalrain-ctl-{}.sock", std::process::id()));
+        let feed = ControlFeed::listen(&path).unwrap();
+        assert!(ControlFeed::listen(&path).is_err());
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let words = |line: &str| line.split(' ').map(String::from).collect::<Vec<_>>();
+        send(&path, &words("banner HELLO THERE")).unwrap();
+        send(&path, &[r#"{"cmd":"pause"}"#.to_string()]).unwrap();
+        assert_eq!(
+            send(&path, &words("effect warp")),
+            Err("unknown effect 'warp'".into())
+        );
+
+        // Replies come after the command is queued
+        assert_eq!(
+            feed.drain(),
+            vec![
+                Ok(Command::Banner(Some("HELLO THERE".into()))),
+                Ok(Command::Pause(None)),
+            ]
+        );
+        drop(feed);
+        assert!(!path.exists());
+        assert!(send(&path, &words("quit")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn listening_leaves_other_files_alone() {
+        let path = env::temp_dir().join(format!("digitalrain-notes-{}.txt", std::process::id()));
+        std::fs::write(&path, "important").unwrap();
+        assert!(
+            ControlFeed::listen(&path)
+                .err()
+                .unwrap()
+                .ends_with("isn't a socket")
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "important");
+
+        // Nor does a feed remove what has replaced its socket
+        std::fs::remove_file(&path).unwrap();
+        let feed = ControlFeed::listen(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        std::fs::write(&path, "important").unwrap();
+        drop(feed);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "important");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,29 +1,65 @@
 //! CRT monitor simulation post-processing filter.
 //!
-//! Applies four sub-effects to the screen buffer after the rain effect renders
-//! but before overlays, simulating a vintage CRT monitor look:
+//! Applies up to seven sub-effects to the screen buffer after the rain effect
+//! renders but before overlays, simulating a vintage CRT monitor look:
 //!
 //! 1. **Phosphor glow** -- bright cells bleed light to cardinal neighbors
 //! 2. **Scanlines** -- alternate rows are dimmed
 //! 3. **Screen flicker** -- global brightness oscillates via dual-sine wave
-//! 4. **Noise** -- random cell corruption for analog feel
+//! 4. **Persistence** -- a fading copy of earlier frames ghosts underneath
+//!    (off by default)
+//! 5. **Noise** -- random cell corruption for analog feel
+//! 6. **Curvature** -- barrel distortion with dark corners (off by default)
+//! 7. **Vignette** -- brightness falls off toward the edges (off by default)
 //!
 //! Application order matters: glow reads original brightness before scanlines
-//! modify it, and noise is applied last so corruption looks jarring on purpose.
+//! modify it, persistence keeps the finished phosphor image (but not the
+//! noise, which would otherwise smear), noise is applied after those so
+//! corruption looks jarring on purpose, curvature bends the result so the
+//! scanlines follow the glass, and the vignette shades the final screen
+//! position.
 //!
 //! References:
 //! - CRT effect techniques inspired by classic demoscene and retro shader posts
 //! - Dual-sine flicker approach based on real CRT refresh characteristics
 
-use crossterm::style::Color;
-use rand::Rng;
+use rand::RngExt;
 
-use crate::buffer::ScreenBuffer;
+use crate::buffer::{Cell, ScreenBuffer};
+use crate::color::Color;
 use crate::color::gradient::{color_to_rgb, scale_color};
+use crate::post::PostProcessor;
+use crate::rng::{self, SimRng, Stream};
 
 /// Characters used for noise corruption -- chosen to look like analog glitches.
 const NOISE_CHARS: &[char] = &['#', '%', '&', '@', '!', '/', '\\', '|', '.', ':'];
 
+/// Barrel distortion at full curvature: how much farther out a point at the
+/// corner samples from.
+const MAX_BARREL: f64 = 0.12;
+
+/// Width of the darkened band along the curved edge, in normalized screen
+/// units (the screen spans -1..1).
+const EDGE_SHADE: f64 = 0.12;
+
+/// Share of a ghost's brightness kept each frame (at 30 fps) at full
+/// persistence.
+const MAX_PERSISTENCE: f64 = 0.92;
+
+/// Ghosts dimmer than this (brightest channel) are dropped.
+const GHOST_FLOOR: u8 = 12;
+
+/// Where the vignette starts to darken, as a share of the center-to-corner
+/// distance.
+pub const DEFAULT_VIGNETTE_RADIUS: f64 = 0.6;
+
+/// How dark the vignette gets at the corners (1.0 = black).
+pub const DEFAULT_VIGNETTE_STRENGTH: f64 = 0.5;
+
+/// Where a curved screen cell takes its content from: a source cell index
+/// and a brightness factor, or nothing (off the edge of the tube).
+type RemapEntry = Option<(usize, f64)>;
+
 /// CRT monitor simulation filter applied as a post-processing pass.
 ///
 /// Holds all state needed for time-varying effects (flicker phase, frame count).
@@ -37,6 +73,22 @@ pub struct CrtFilter {
     flicker_phase: f64,
     /// Frame counter for noise RNG seeding.
     frame_count: u64,
+    rng: SimRng,
+    /// Barrel distortion amount (0.0 = flat screen)
+    curvature: f64,
+    /// Per-cell curvature lookup, rebuilt when empty (cleared on resize and
+    /// on curvature changes)
+    remap: Vec<RemapEntry>,
+    /// Phosphor persistence amount (0.0 = no ghosting)
+    persistence: f64,
+    /// Last frame's phosphor image, for ghosting (empty until the first
+    /// frame, and after a resize)
+    history: Vec<Cell>,
+    vignette: bool,
+    vignette_radius: f64,
+    vignette_strength: f64,
+    /// Per-cell vignette brightness, rebuilt when empty like `remap`
+    vignette_map: Vec<f64>,
 }
 
 impl CrtFilter {
@@ -49,9 +101,43 @@ impl CrtFilter {
             height,
             flicker_phase: 0.0,
             frame_count: 0,
+            rng: rng::make_rng(None, Stream::Crt),
+            curvature: 0.0,
+            remap: Vec::new(),
+            persistence: 0.0,
+            history: Vec::new(),
+            vignette: false,
+            vignette_radius: DEFAULT_VIGNETTE_RADIUS,
+            vignette_strength: DEFAULT_VIGNETTE_STRENGTH,
+            vignette_map: Vec::new(),
         }
     }
 
+    /// Bend the picture like a curved tube (0.0 = flat, 1.0 = strong).
+    pub fn with_curvature(mut self, curvature: f64) -> Self {
+        self.set_curvature(curvature);
+        self
+    }
+
+    /// Let bright cells leave fading ghosts (0.0 = none, 1.0 = long trails).
+    pub fn with_persistence(mut self, persistence: f64) -> Self {
+        self.set_persistence(persistence);
+        self
+    }
+
+    /// Darken toward the edges, starting at `radius` (0.0 = center,
+    /// 1.0 = corner) and reaching `strength` at the corners.
+    pub fn with_vignette(mut self, enabled: bool, radius: f64, strength: f64) -> Self {
+        self.set_vignette(enabled, radius, strength);
+        self
+    }
+
+    /// Seed the noise generator (for `--seed`).
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.rng = rng::make_rng(seed, Stream::Crt);
+        self
+    }
+
     /// Toggle the CRT filter on/off. Returns the new enabled state.
     pub fn toggle(&mut self) -> bool {
         self.enabled = !self.enabled;
@@ -59,7 +145,6 @@ impl CrtFilter {
     }
 
     /// Whether the CRT filter is currently active.
-    #[allow(dead_code)] // available for future use (e.g., status display)
     pub fn is_enabled(&self) -> bool {
         self.enabled
     }
@@ -69,32 +154,28 @@ impl CrtFilter {
         self.enabled = enabled;
     }
 
-    /// Update dimensions after a terminal resize.
-    pub fn resize(&mut self, width: u16, height: u16) {
-        self.width = width;
-        self.height = height;
+    /// Set how strongly the sub-effects apply (clamped to 0.0-1.0).
+    pub fn set_intensity(&mut self, intensity: f64) {
+        self.intensity = intensity.clamp(0.0, 1.0);
     }
 
-    /// Apply all CRT sub-effects to the buffer in order.
-    ///
-    /// Called once per frame between effect.render() and overlay rendering.
-    /// `delta_time` is seconds since last frame (for flicker oscillation).
-    pub fn apply(&mut self, buffer: &mut ScreenBuffer, delta_time: f64) {
-        if !self.enabled {
-            return;
-        }
+    /// Set the barrel distortion amount (clamped to 0.0-1.0).
+    pub fn set_curvature(&mut self, curvature: f64) {
+        self.curvature = curvature.clamp(0.0, 1.0);
+        self.remap.clear();
+    }
 
-        self.width = buffer.width();
-        self.height = buffer.height();
-        self.flicker_phase += delta_time;
-        self.frame_count = self.frame_count.wrapping_add(1);
+    /// Set the phosphor persistence amount (clamped to 0.0-1.0).
+    pub fn set_persistence(&mut self, persistence: f64) {
+        self.persistence = persistence.clamp(0.0, 1.0);
+    }
 
-        // Order matters: glow reads original brightness, scanlines dim rows,
-        // flicker scales everything, noise corrupts last.
-        self.apply_glow(buffer);
-        self.apply_scanlines(buffer);
-        self.apply_flicker(buffer);
-        self.apply_noise(buffer);
+    /// Turn the vignette on or off and set its shape (both clamped to 0.0-1.0).
+    pub fn set_vignette(&mut self, enabled: bool, radius: f64, strength: f64) {
+        self.vignette = enabled;
+        self.vignette_radius = radius.clamp(0.0, 1.0);
+        self.vignette_strength = strength.clamp(0.0, 1.0);
+        self.vignette_map.clear();
     }
 
     /// Phosphor glow: bright cells bleed dimmed color to cardinal neighbors' backgrounds.
@@ -167,14 +248,15 @@ impl CrtFilter {
     /// Scanlines: dim every even row to simulate CRT horizontal scan gaps.
     ///
     /// The dimming factor interpolates between 1.0 (no effect) and 0.45 (heavy)
-    /// based on intensity. Empty cells (spaces) are skipped.
+    /// based on intensity. Empty cells (spaces) are skipped unless they show
+    /// a solid background.
     fn apply_scanlines(&self, buffer: &mut ScreenBuffer) {
         let dim_factor = 1.0 - (0.55 * self.intensity); // 1.0 at intensity=0, 0.45 at intensity=1
 
         for y in (0..self.height).step_by(2) {
             for x in 0..self.width {
                 if let Some(cell) = buffer.get_cell(x, y) {
-                    if cell.ch == ' ' {
+                    if cell.ch == ' ' && matches!(cell.bg, Color::Reset) {
                         continue;
                     }
                     let new_fg = scale_color(cell.fg, dim_factor);
@@ -207,7 +289,7 @@ impl CrtFilter {
         for y in 0..self.height {
             for x in 0..self.width {
                 if let Some(cell) = buffer.get_cell(x, y) {
-                    if cell.ch == ' ' {
+                    if cell.ch == ' ' && matches!(cell.bg, Color::Reset) {
                         continue;
                     }
                     let new_fg = scale_color(cell.fg, factor);
@@ -218,17 +300,63 @@ impl CrtFilter {
         }
     }
 
+    /// Persistence: show a decayed copy of the previous frame wherever it's
+    /// brighter than the current one, then remember the result.
+    ///
+    /// Empty cells take the ghost's character too, so a falling head leaves
+    /// a faint trail behind it. Decay is scaled by frame time so trails last
+    /// as long at any frame rate.
+    fn apply_persistence(&mut self, buffer: &mut ScreenBuffer, delta_time: f64) {
+        if self.persistence < 0.001 {
+            self.history.clear();
+            return;
+        }
+
+        let keep = (MAX_PERSISTENCE * self.persistence).powf(delta_time * 30.0);
+        if self.history.len() == buffer.cells().len() {
+            let w = self.width as usize;
+            for (idx, ghost) in self.history.iter().enumerate() {
+                let (gr, gg, gb) = color_to_rgb(scale_color(ghost.fg, keep));
+                if gr.max(gg).max(gb) < GHOST_FLOOR {
+                    continue;
+                }
+                let (x, y) = ((idx % w) as u16, (idx / w) as u16);
+                let Some(&cell) = buffer.get_cell(x, y) else {
+                    continue;
+                };
+                if cell.ch == ' ' {
+                    let fg = Color::Rgb {
+                        r: gr,
+                        g: gg,
+                        b: gb,
+                    };
+                    buffer.set_cell(x, y, ghost.ch, fg, cell.bg);
+                } else if let Color::Rgb { r, g, b } = cell.fg {
+                    let fg = Color::Rgb {
+                        r: r.max(gr),
+                        g: g.max(gg),
+                        b: b.max(gb),
+                    };
+                    buffer.set_cell(x, y, cell.ch, fg, cell.bg);
+                }
+            }
+        }
+
+        self.history.clear();
+        self.history.extend_from_slice(buffer.cells());
+    }
+
     /// Noise: randomly corrupt a small fraction of cells for analog feel.
     ///
     /// Per-cell probability is 0.2% at full intensity. Corrupted cells get a
     /// random character and a brightness-shifted foreground color.
-    fn apply_noise(&self, buffer: &mut ScreenBuffer) {
+    fn apply_noise(&mut self, buffer: &mut ScreenBuffer) {
         let probability = 0.002 * self.intensity;
         if probability < 0.0001 {
             return;
         }
 
-        let mut rng = rand::rng();
+        let rng = &mut self.rng;
 
         for y in 0..self.height {
             for x in 0..self.width {
@@ -247,6 +375,157 @@ impl CrtFilter {
             }
         }
     }
+
+    /// Curvature: pull each cell's content from farther out the nearer it
+    /// is to the corners, and shade off the edges of the tube.
+    fn apply_curvature(&mut self, buffer: &mut ScreenBuffer) {
+        if self.curvature < 0.001 {
+            return;
+        }
+        if self.remap.is_empty() {
+            self.remap = build_remap(self.width, self.height, self.curvature);
+        }
+
+        let source = buffer.cells().to_vec();
+        let w = self.width as usize;
+        for (idx, entry) in self.remap.iter().enumerate() {
+            let (x, y) = ((idx % w) as u16, (idx / w) as u16);
+            match *entry {
+                Some((src, shade)) => {
+                    let cell = source[src];
+                    let fg = scale_color(cell.fg, shade);
+                    let bg = scale_color(cell.bg, shade);
+                    buffer.set_cell(x, y, cell.ch, fg, bg);
+                }
+                None => buffer.set_cell(x, y, ' ', Color::Reset, Color::Reset),
+            }
+        }
+    }
+
+    /// Vignette: scale each cell by its precomputed edge falloff.
+    fn apply_vignette(&mut self, buffer: &mut ScreenBuffer) {
+        if !self.vignette || self.vignette_strength < 0.001 {
+            return;
+        }
+        if self.vignette_map.is_empty() {
+            self.vignette_map = build_vignette(
+                self.width,
+                self.height,
+                self.vignette_radius,
+                self.vignette_strength,
+            );
+        }
+
+        let w = self.width as usize;
+        for (idx, &factor) in self.vignette_map.iter().enumerate() {
+            if factor >= 0.999 {
+                continue;
+            }
+            let (x, y) = ((idx % w) as u16, (idx / w) as u16);
+            if let Some(cell) = buffer.get_cell(x, y) {
+                if cell.ch == ' ' && matches!(cell.bg, Color::Reset) {
+                    continue;
+                }
+                let fg = scale_color(cell.fg, factor);
+                let bg = scale_color(cell.bg, factor);
+                buffer.set_cell(x, y, cell.ch, fg, bg);
+            }
+        }
+    }
+}
+
+impl PostProcessor for CrtFilter {
+    fn name(&self) -> &str {
+        "crt"
+    }
+
+    /// Update dimensions after a terminal resize.
+    fn resize(&mut self, width: u16, height: u16) {
+        self.width = width;
+        self.height = height;
+        self.remap.clear();
+        self.vignette_map.clear();
+        self.history.clear();
+    }
+
+    /// Apply all CRT sub-effects to the buffer in order.
+    ///
+    /// Called once per frame from the post-processing chain, between
+    /// effect.render() and overlay rendering.
+    /// `delta_time` is seconds since last frame (for flicker oscillation).
+    fn apply(&mut self, buffer: &mut ScreenBuffer, delta_time: f64) {
+        if !self.enabled {
+            // Don't ghost a stale frame when switched back on
+            self.history.clear();
+            return;
+        }
+
+        if (self.width, self.height) != (buffer.width(), buffer.height()) {
+            self.resize(buffer.width(), buffer.height());
+        }
+        self.flicker_phase += delta_time;
+        self.frame_count = self.frame_count.wrapping_add(1);
+
+        // Order matters: glow reads original brightness, scanlines dim rows,
+        // flicker scales everything, persistence remembers the result, noise
+        // corrupts, curvature bends it all, and the vignette shades the bent
+        // picture.
+        self.apply_glow(buffer);
+        self.apply_scanlines(buffer);
+        self.apply_flicker(buffer);
+        self.apply_persistence(buffer, delta_time);
+        self.apply_noise(buffer);
+        self.apply_curvature(buffer);
+        self.apply_vignette(buffer);
+    }
+}
+
+/// Precompute the vignette brightness of each cell: 1.0 inside `radius`,
+/// easing down to `1.0 - strength` at the corners.
+pub fn build_vignette(width: u16, height: u16, radius: f64, strength: f64) -> Vec<f64> {
+    let (w, h) = (width as usize, height as usize);
+    let mut map = Vec::with_capacity(w * h);
+    for y in 0..h {
+        for x in 0..w {
+            let u = (x as f64 + 0.5) / w as f64 * 2.0 - 1.0;
+            let v = (y as f64 + 0.5) / h as f64 * 2.0 - 1.0;
+            // 0.0 at the center, 1.0 at the corners
+            let distance = (u.hypot(v) / std::f64::consts::SQRT_2).min(1.0);
+            let t = ((distance - radius) / (1.0 - radius).max(0.001)).clamp(0.0, 1.0);
+            let falloff = t * t * (3.0 - 2.0 * t);
+            map.push(1.0 - strength * falloff);
+        }
+    }
+    map
+}
+
+/// Precompute where each cell of a `width` x `height` curved screen samples
+/// from. Distortion grows with the square of the distance from the center.
+fn build_remap(width: u16, height: u16, curvature: f64) -> Vec<RemapEntry> {
+    let (w, h) = (width as usize, height as usize);
+    let k = MAX_BARREL * curvature;
+    let edge = EDGE_SHADE * curvature;
+    let mut remap = Vec::with_capacity(w * h);
+    for y in 0..h {
+        for x in 0..w {
+            // Cell center in -1..1 on both axes
+            let u = (x as f64 + 0.5) / w as f64 * 2.0 - 1.0;
+            let v = (y as f64 + 0.5) / h as f64 * 2.0 - 1.0;
+            let stretch = 1.0 + k * (u * u + v * v);
+            let (su, sv) = (u * stretch, v * stretch);
+            if su.abs() > 1.0 || sv.abs() > 1.0 {
+                remap.push(None);
+                continue;
+            }
+            let sx = (((su + 1.0) / 2.0 * w as f64) as usize).min(w - 1);
+            let sy = (((sv + 1.0) / 2.0 * h as f64) as usize).min(h - 1);
+            // Fade toward the edge the sample comes from
+            let margin = (1.0 - su.abs()).min(1.0 - sv.abs());
+            let shade = (margin / edge).clamp(0.0, 1.0).sqrt();
+            remap.push(Some((sy * w + sx, shade)));
+        }
+    }
+    remap
 }
 
 // scale_color is now imported from crate::color::gradient
@@ -254,7 +533,6 @@ impl CrtFilter {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::buffer::ScreenBuffer;
 
     fn rgb(r: u8, g: u8, b: u8) -> Color {
         Color::Rgb { r, g, b }
@@ -286,9 +564,6 @@ mod tests {
     fn scale_color_passes_through_non_rgb() {
         let result = scale_color(Color::Reset, 0.5);
         assert!(matches!(result, Color::Reset));
-
-        let result2 = scale_color(Color::Green, 0.5);
-        assert!(matches!(result2, Color::Green));
     }
 
     // --- CrtFilter toggle tests ---
@@ -372,6 +647,20 @@ mod tests {
         assert!(matches!(cell.fg, Color::Reset));
     }
 
+    #[test]
+    fn scanlines_stripe_a_solid_background() {
+        let mut buffer = ScreenBuffer::new(5, 2);
+        buffer.set_background(rgb(0, 0, 128));
+        buffer.clear();
+
+        let filter = CrtFilter::new(5, 2, true, 1.0);
+        filter.apply_scanlines(&mut buffer);
+
+        let (_, _, even_b) = unwrap_rgb(buffer.get_cell(2, 0).unwrap().bg);
+        let (_, _, odd_b) = unwrap_rgb(buffer.get_cell(2, 1).unwrap().bg);
+        assert!(even_b < odd_b);
+    }
+
     // --- glow tests ---
 
     #[test]
@@ -448,4 +737,120 @@ mod tests {
         assert_eq!(cells.len(), 6); // 3 * 2
         assert_eq!(cells[1].ch, 'A'); // index = 0*3 + 1 = 1
     }
+
+    // --- curvature tests ---
+
+    #[test]
+    fn curvature_blanks_corners_and_keeps_the_center() {
+        let mut buffer = ScreenBuffer::new(40, 20);
+        for y in 0..20 {
+            for x in 0..40 {
+                buffer.set_cell(x, y, 'X', rgb(0, 100, 0), Color::Reset);
+            }
+        }
+
+        let mut filter = CrtFilter::new(40, 20, true, 0.0).with_curvature(1.0);
+        filter.apply_curvature(&mut buffer);
+
+        assert_eq!(buffer.get_cell(0, 0).unwrap().ch, ' ');
+        assert_eq!(buffer.get_cell(39, 19).unwrap().ch, ' ');
+        let center = buffer.get_cell(20, 10).unwrap();
+        assert_eq!(center.ch, 'X');
+        assert_eq!(unwrap_rgb(center.fg), (0, 100, 0));
+        // Near the top edge: present but shaded
+        let (_, g, _) = unwrap_rgb(buffer.get_cell(20, 1).unwrap().fg);
+        assert!(g < 100, "edge should be darkened, got {}", g);
+    }
+
+    #[test]
+    fn curvature_table_is_rebuilt_on_resize() {
+        let mut filter = CrtFilter::new(10, 5, true, 0.5).with_curvature(0.5);
+        filter.apply(&mut ScreenBuffer::new(10, 5), 0.033);
+        assert_eq!(filter.remap.len(), 50);
+
+        filter.resize(20, 8);
+        assert!(filter.remap.is_empty());
+        filter.apply(&mut ScreenBuffer::new(20, 8), 0.033);
+        assert_eq!(filter.remap.len(), 160);
+
+        // Flat screens never build one
+        let mut flat = CrtFilter::new(10, 5, true, 0.5);
+        flat.apply(&mut ScreenBuffer::new(10, 5), 0.033);
+        assert!(flat.remap.is_empty());
+    }
+
+    // --- vignette tests ---
+
+    #[test]
+    fn vignette_darkens_edges_only() {
+        let mut buffer = ScreenBuffer::new(40, 20);
+        for y in 0..20 {
+            for x in 0..40 {
+                buffer.set_cell(x, y, 'X', rgb(0, 200, 0), Color::Reset);
+            }
+        }
+
+        let mut filter = CrtFilter::new(40, 20, true, 0.0).with_vignette(true, 0.5, 0.8);
+        filter.apply_vignette(&mut buffer);
+
+        let (_, center, _) = unwrap_rgb(buffer.get_cell(20, 10).unwrap().fg);
+        let (_, side, _) = unwrap_rgb(buffer.get_cell(0, 10).unwrap().fg);
+        let (_, corner, _) = unwrap_rgb(buffer.get_cell(0, 0).unwrap().fg);
+        assert_eq!(center, 200);
+        assert!(
+            corner < side && side < center,
+            "{} {} {}",
+            corner,
+            side,
+            center
+        );
+        assert!(
+            corner <= 60,
+            "corner should approach 20% brightness, got {}",
+            corner
+        );
+    }
+
+    #[test]
+    fn vignette_map_follows_toggle_and_resize() {
+        let mut filter = CrtFilter::new(10, 5, true, 0.5).with_vignette(false, 0.5, 0.5);
+        filter.apply(&mut ScreenBuffer::new(10, 5), 0.033);
+        assert!(filter.vignette_map.is_empty());
+
+        filter.set_vignette(true, 0.5, 0.5);
+        filter.apply(&mut ScreenBuffer::new(10, 5), 0.033);
+        assert_eq!(filter.vignette_map.len(), 50);
+
+        filter.resize(12, 6);
+        assert!(filter.vignette_map.is_empty());
+        filter.apply(&mut ScreenBuffer::new(12, 6), 0.033);
+        assert_eq!(filter.vignette_map.len(), 72);
+    }
+
+    // --- persistence tests ---
+
+    #[test]
+    fn persistence_leaves_fading_ghosts() {
+        let mut filter = CrtFilter::new(5, 5, true, 0.0).with_persistence(1.0);
+        let mut buffer = ScreenBuffer::new(5, 5);
+        buffer.set_cell(2, 2, 'X', rgb(0, 200, 0), Color::Reset);
+        filter.apply_persistence(&mut buffer, 1.0 / 30.0);
+
+        // The head moves on: its old cell keeps a dimmer ghost
+        let mut greens = Vec::new();
+        for _ in 0..3 {
+            let mut buffer = ScreenBuffer::new(5, 5);
+            filter.apply_persistence(&mut buffer, 1.0 / 30.0);
+            let cell = buffer.get_cell(2, 2).unwrap();
+            assert_eq!(cell.ch, 'X');
+            greens.push(unwrap_rgb(cell.fg).1);
+        }
+        assert!(greens[0] < 200 && greens[1] < greens[0] && greens[2] < greens[1]);
+
+        // Off: nothing carries over
+        filter.set_persistence(0.0);
+        let mut buffer = ScreenBuffer::new(5, 5);
+        filter.apply_persistence(&mut buffer, 1.0 / 30.0);
+        assert_eq!(buffer.get_cell(2, 2).unwrap().ch, ' ');
+    }
 }
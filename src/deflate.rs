@@ -0,0 +1,189 @@
+//! DEFLATE / zlib compression (RFC 1950/1951), for writing PNG images.
+//!
+//! The counterpart of `inflate`: greedy LZ77 matching over a 32 KiB window
+//! with hash chains, coded as a single block with the fixed Huffman codes.
+//! Rasterized frames are long runs of background with repeated glyph
+//! shapes, which this squeezes well without dynamic tables.
+
+use crate::inflate::{DIST_BASE, DIST_EXTRA, LENGTH_BASE, LENGTH_EXTRA};
+
+const WINDOW: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+/// Candidates tried per position; more finds longer matches, slower.
+const MAX_CHAIN: usize = 32;
+const HASH_BITS: u32 = 15;
+
+/// Compress `data` into a zlib stream (2-byte header, DEFLATE data,
+/// Adler-32).
+pub fn zlib_compress(data: &[u8]) -> Vec<u8> {
+    // 32 KiB window, default compression level, header check bits
+    let mut out = vec![0x78, 0x9C];
+    out.extend(deflate(data));
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+/// Compress `data` into raw DEFLATE data.
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut out = BitWriter::default();
+    // One final block with fixed codes
+    out.bits(1, 1);
+    out.bits(1, 2);
+
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; data.len()];
+    let mut pos = 0;
+    while pos < data.len() {
+        let (length, distance) = longest_match(data, pos, &head, &prev);
+        let step = if length >= MIN_MATCH {
+            write_match(&mut out, length, distance);
+            length
+        } else {
+            write_literal(&mut out, data[pos] as u16);
+            1
+        };
+        for p in pos..(pos + step) {
+            if p + MIN_MATCH <= data.len() {
+                let h = hash(&data[p..]);
+                prev[p] = head[h];
+                head[h] = p;
+            }
+        }
+        pos += step;
+    }
+    write_literal(&mut out, 256);
+    out.finish()
+}
+
+fn hash(bytes: &[u8]) -> usize {
+    let v = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+    (v.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+}
+
+/// The longest earlier occurrence of the bytes at `pos`, as (length,
+/// distance); length 0 when there's none worth coding.
+fn longest_match(data: &[u8], pos: usize, head: &[usize], prev: &[usize]) -> (usize, usize) {
+    if pos + MIN_MATCH > data.len() {
+        return (0, 0);
+    }
+    let max = (data.len() - pos).min(MAX_MATCH);
+    let mut best = (0, 0);
+    let mut candidate = head[hash(&data[pos..])];
+    for _ in 0..MAX_CHAIN {
+        if candidate == usize::MAX || pos - candidate > WINDOW {
+            break;
+        }
+        let length = data[candidate..]
+            .iter()
+            .zip(&data[pos..pos + max])
+            .take_while(|(a, b)| a == b)
+            .count();
+        if length > best.0 {
+            best = (length, pos - candidate);
+            if length == max {
+                break;
+            }
+        }
+        candidate = prev[candidate];
+    }
+    best
+}
+
+/// Fixed Huffman code for a literal/length symbol.
+fn write_literal(out: &mut BitWriter, symbol: u16) {
+    let (code, len) = match symbol {
+        0..=143 => (0x30 + symbol, 8),
+        144..=255 => (0x190 + symbol - 144, 9),
+        256..=279 => (symbol - 256, 7),
+        _ => (0xC0 + symbol - 280, 8),
+    };
+    out.huffman(code, len);
+}
+
+fn write_match(out: &mut BitWriter, length: usize, distance: usize) {
+    // Codes are in order of their base; take the last base not above the value
+    let l = LENGTH_BASE.partition_point(|&base| base as usize <= length) - 1;
+    write_literal(out, 257 + l as u16);
+    out.bits(
+        (length - LENGTH_BASE[l] as usize) as u32,
+        LENGTH_EXTRA[l] as u32,
+    );
+
+    let d = DIST_BASE.partition_point(|&base| base as usize <= distance) - 1;
+    out.huffman(d as u16, 5);
+    out.bits(
+        (distance - DIST_BASE[d] as usize) as u32,
+        DIST_EXTRA[d] as u32,
+    );
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65_521;
+        b %= 65_521;
+    }
+    b << 16 | a
+}
+
+/// LSB-first bit writer.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    /// Pending bits, not yet a whole byte
+    acc: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    /// Write the low `count` bits of `value`, least significant first.
+    fn bits(&mut self, value: u32, count: u32) {
+        self.acc |= value << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.bytes.push(self.acc as u8);
+            self.acc >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Write a Huffman code, which DEFLATE packs most significant bit first.
+    fn huffman(&mut self, code: u16, len: u32) {
+        let reversed = code.reverse_bits() >> (16 - len);
+        self.bits(reversed as u32, len);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.acc as u8);
+        }
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inflate::zlib_decompress;
+
+    #[test]
+    fn round_trips_through_inflate() {
+        let mut frame = vec![0u8; 20_000];
+        for (i, byte) in frame.iter_mut().enumerate() {
+            if i % 97 < 9 {
+                *byte = (i % 7) as u8 * 30;
+            }
+        }
+        let text = b"wake up, neo... the matrix has you. follow the white rabbit.".repeat(40);
+        for data in [&frame[..], &text[..], b"", b"ab", &[7; 300]] {
+            let compressed = zlib_compress(data);
+            assert_eq!(zlib_decompress(&compressed).unwrap(), data);
+        }
+        assert!(zlib_compress(&frame).len() < frame.len() / 10);
+    }
+}
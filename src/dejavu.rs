@@ -0,0 +1,198 @@
+//! The "déjà vu" easter egg (`--deja-vu-chance`, off with
+//! `--no-easter-eggs`).
+//!
+//! Every so often a small black cat walks along the bottom of the screen.
+//! The rain stutters, holding still for a moment and then jumping ahead as
+//! if frames were skipped, and the same cat walks by again: a glitch in the
+//! Matrix.
+//!
+//! `DejaVu::advance` runs every frame. It rolls for the egg and steps the
+//! cat, and returns how far the effect should move this frame: nothing
+//! during the stutter, then the whole held-back time at once.
+
+use rand::RngExt;
+
+use crate::buffer::RenderTarget;
+use crate::color::Color;
+use crate::rng::SimRng;
+use crate::sprite::{Sprite, SpriteWalker};
+
+/// Chance per minute of the egg, by default.
+pub const DEFAULT_CHANCE: f64 = 0.01;
+
+/// The cat, facing right, in two steps.
+const CAT: [&[&str]; 2] = [
+    &["    /\\_/\\", "~(__( o.o)", "  / \\  / \\"],
+    &["    /\\_/\\", "~(__( o.o)", "   |  |  |"],
+];
+/// Dark enough to read as a black cat, light enough to see against black
+const CAT_COLOR: Color = Color::Rgb {
+    r: 70,
+    g: 70,
+    b: 70,
+};
+/// Cells per second
+const CAT_SPEED: f64 = 9.0;
+/// Seconds per step of the walk
+const CAT_STEP_SECS: f64 = 0.25;
+/// How long the rain holds still between the two walks
+const STUTTER_SECS: f64 = 0.4;
+
+enum State {
+    Idle,
+    /// The cat's first walk (`again` false) or its second
+    Walking {
+        walker: SpriteWalker,
+        again: bool,
+    },
+    /// The rain frozen, with the time it has missed
+    Stutter {
+        remaining: f64,
+        held: f64,
+    },
+}
+
+/// Rolls for and plays the déjà vu easter egg.
+pub struct DejaVu {
+    /// Chance per minute
+    chance: f64,
+    rng: SimRng,
+    state: State,
+}
+
+impl DejaVu {
+    /// An egg that plays with probability `chance` in any given minute.
+    pub fn new(chance: f64, rng: SimRng) -> Self {
+        Self {
+            chance: chance.clamp(0.0, 1.0),
+            rng,
+            state: State::Idle,
+        }
+    }
+
+    /// Start the egg now, unless it's already playing.
+    pub fn trigger(&mut self, height: u16) {
+        if matches!(self.state, State::Idle) {
+            self.state = State::Walking {
+                walker: cat_walker(height),
+                again: false,
+            };
+        }
+    }
+
+    /// Whether the egg is playing.
+    pub fn is_active(&self) -> bool {
+        !matches!(self.state, State::Idle)
+    }
+
+    /// Move on by `dt` on a `width` x `height` screen, returning how many
+    /// seconds the effect should advance this frame.
+    pub fn advance(&mut self, dt: f64, width: u16, height: u16) -> f64 {
+        match &mut self.state {
+            State::Idle => {
+                // The per-frame chance that makes `chance` per minute
+                let chance = 1.0 - (1.0 - self.chance).powf(dt / 60.0);
+                if self.rng.random_bool(chance.clamp(0.0, 1.0)) {
+                    self.trigger(height);
+                }
+                dt
+            }
+            State::Walking { walker, again } => {
+                walker.update(dt);
+                if walker.is_finished(width) {
+                    self.state = if *again {
+                        State::Idle
+                    } else {
+                        State::Stutter {
+                            remaining: STUTTER_SECS,
+                            held: 0.0,
+                        }
+                    };
+                }
+                dt
+            }
+            State::Stutter { remaining, held } => {
+                *remaining -= dt;
+                *held += dt;
+                if *remaining > 0.0 {
+                    return 0.0;
+                }
+                let skipped = *held;
+                self.state = State::Walking {
+                    walker: cat_walker(height),
+                    again: true,
+                };
+                skipped
+            }
+        }
+    }
+
+    /// Draw the cat, if it's out.
+    pub fn render(&self, buffer: &mut dyn RenderTarget) {
+        if let State::Walking { walker, .. } = &self.state {
+            walker.render(buffer);
+        }
+    }
+}
+
+/// The cat, about to walk along the bottom of a screen `height` rows tall.
+fn cat_walker(height: u16) -> SpriteWalker {
+    let sprite = Sprite::new(&CAT, CAT_COLOR);
+    let y = height.saturating_sub(sprite.height());
+    SpriteWalker::new(sprite, y, CAT_SPEED, CAT_STEP_SECS)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::buffer::ScreenBuffer;
+
+    #[test]
+    fn the_cat_walks_twice_with_a_stutter_between() {
+        let mut egg = DejaVu::new(0.0, SimRng::seed_from_u64(1));
+        let dt = 0.125;
+        // Never plays by chance at 0
+        for _ in 0..1000 {
+            assert_eq!(egg.advance(dt, 20, 6), dt);
+        }
+        assert!(!egg.is_active());
+
+        egg.trigger(6);
+        let mut buffer = ScreenBuffer::new(20, 6);
+        let mut frames = Vec::new();
+        while egg.is_active() {
+            let step = egg.advance(dt, 20, 6);
+            buffer.clear();
+            egg.render(&mut buffer);
+            let cat_shown = buffer.cells().iter().any(|c| c.ch != ' ');
+            frames.push((step, cat_shown));
+            assert!(frames.len() < 1000);
+        }
+
+        // Walk, stutter (no time passes, then it all does), walk again
+        let held: Vec<usize> = (0..frames.len()).filter(|&i| frames[i].0 == 0.0).collect();
+        assert_eq!(held.len(), 3);
+        assert!(held.windows(2).all(|w| w[1] == w[0] + 1));
+        let resume = held[2] + 1;
+        assert_eq!(frames[resume].0, 0.5);
+        assert!(frames[..held[0]].iter().any(|f| f.1));
+        assert!(frames[held[0]..resume].iter().all(|f| !f.1));
+        assert!(frames[resume..].iter().any(|f| f.1));
+        // Along the bottom rows only
+        egg.trigger(6);
+        egg.advance(1.5, 20, 6);
+        buffer.clear();
+        egg.render(&mut buffer);
+        assert!(buffer.cells()[..60].iter().all(|c| c.ch == ' '));
+        assert!(buffer.cells()[60..].iter().any(|c| c.ch != ' '));
+    }
+
+    #[test]
+    fn a_certain_chance_plays_within_a_minute() {
+        let mut egg = DejaVu::new(1.0, SimRng::seed_from_u64(7));
+        egg.advance(1.0 / 30.0, 80, 24);
+        assert!(egg.is_active());
+    }
+}
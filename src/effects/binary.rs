@@ -1,38 +1,81 @@
 //! Binary rain effect: dense columns of 0s and 1s.
 //!
 //! Thin wrapper around RainField that forces the binary character set
-//! and uses slightly higher density (1.3x) for a dense data stream look.
+//! and uses higher density (1.3x by default) for a dense data stream look.
+//! It keeps its 0s and 1s when the charset is changed at runtime.
 
 use super::Effect;
-use crate::buffer::ScreenBuffer;
+use super::params::{EffectSpec, ParamSpec};
+use crate::buffer::{Region, RenderTarget};
+use crate::color::palette::Palette;
 use crate::config::Config;
+use crate::event::Event;
 use crate::rain::RainField;
 
+/// Tunables for `--param`.
+pub const PARAMS: &[ParamSpec] = &[ParamSpec {
+    name: "boost",
+    description: "Density relative to classic rain",
+    default: 1.3,
+    min: 0.5,
+    max: 3.0,
+}];
+
 /// Binary rain: dense columns of 0s and 1s.
 pub struct BinaryRain {
     rain: RainField,
+    /// Density multiplier on top of the configured density
+    boost: f64,
 }
 
 impl BinaryRain {
-    pub fn with_config(width: u16, height: u16, config: &Config) -> Self {
-        // Override charset to binary and boost density by 1.3x
+    pub fn with_config(width: u16, height: u16, config: &Config, spec: &EffectSpec) -> Self {
+        let boost = spec.get("boost");
+        // Override charset to binary and boost density
         let mut binary_config = Config {
             effect_name: config.effect_name.clone(),
             speed_multiplier: config.speed_multiplier,
-            density_multiplier: config.density_multiplier * 1.3,
+            density_multiplier: config.density_multiplier * boost,
+            spacing: config.spacing,
+            mutation_rate: config.mutation_rate,
+            gust_period: config.gust_period,
+            gust_strength: config.gust_strength,
+            highlight_rate: config.highlight_rate,
+            highlight_color: config.highlight_color.clone(),
             palette_name: config.palette_name.clone(),
             charset_name: "binary".to_string(),
             target_fps: config.target_fps,
             auto_cycle_secs: config.auto_cycle_secs,
             forward: config.forward,
+            static_glyphs: config.static_glyphs,
             crt_enabled: config.crt_enabled,
             crt_intensity: config.crt_intensity,
+            crt_curvature: config.crt_curvature,
+            crt_persistence: config.crt_persistence,
+            crt_vignette: config.crt_vignette,
+            crt_vignette_radius: config.crt_vignette_radius,
+            crt_vignette_strength: config.crt_vignette_strength,
+            post_filters: config.post_filters.clone(),
+            dead_zones: config.dead_zones.clone(),
+            dead_zone_mode: config.dead_zone_mode,
+            stripes: config.stripes.clone(),
+            stripe_direction: config.stripe_direction,
+            seed: config.seed,
+            effect_params: config.effect_params.clone(),
+            react: None,
+            git_repo: None,
+            text_file: None,
+            background: config.background,
+            brightness: config.brightness,
+            contrast: config.contrast,
+            gamma: config.gamma,
         };
         // Clamp density after boosting
         binary_config.density_multiplier = binary_config.density_multiplier.clamp(0.1, 10.0);
 
         Self {
             rain: RainField::with_config(width, height, &binary_config),
+            boost,
         }
     }
 }
@@ -46,7 +89,7 @@ impl Effect for BinaryRain {
         self.rain.update(delta_time);
     }
 
-    fn render(&self, buffer: &mut ScreenBuffer) {
+    fn render(&self, buffer: &mut dyn RenderTarget) {
         self.rain.render(buffer);
     }
 
@@ -63,10 +106,30 @@ impl Effect for BinaryRain {
     }
 
     fn set_density(&mut self, multiplier: f64) {
-        self.rain.set_density(multiplier * 1.3);
+        self.rain.set_density(multiplier * self.boost);
     }
 
     fn density(&self) -> f64 {
-        self.rain.density() / 1.3
+        self.rain.density() / self.boost
+    }
+
+    fn set_dead_zones(&mut self, zones: &[Region]) {
+        self.rain.set_dead_zones(zones);
+    }
+
+    fn set_mutation(&mut self, rate: f64) {
+        self.rain.set_mutation(rate);
+    }
+
+    fn set_palette(&mut self, palette: &Palette) {
+        self.rain.set_palette(palette.clone());
+    }
+
+    fn on_event(&mut self, event: &Event) {
+        self.rain.on_event(event);
+    }
+
+    fn active_columns(&self) -> Option<usize> {
+        Some(self.rain.column_count())
     }
 }
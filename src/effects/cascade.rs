@@ -5,20 +5,37 @@
 //! in sequence rather than randomly.
 
 use super::Effect;
-use crate::buffer::ScreenBuffer;
-use crate::color::palette::{Palette, palette_by_name};
+use super::params::{EffectSpec, ParamSpec};
+use crate::buffer::{Region, RenderTarget};
+use crate::color::Color;
+use crate::color::palette::{Palette, color_by_name, palette_by_name};
 use crate::config::Config;
 use crate::rain::chars::{CharacterPool, charset_by_name};
-use crate::rain::column::RainColumn;
-use crate::rain::render_rain_column;
+use crate::rain::column::{ColumnPool, RainColumn};
+use crate::rain::glyphs::GlyphGrid;
+use crate::rain::stripes::{Coloring, Stripes};
+use crate::rain::{DeadZoneMode, render_rain_column, stop_at_dead_zones};
+use crate::rng::{self, SimRng, Stream};
+
+/// Tunables for `--param`.
+pub const PARAMS: &[ParamSpec] = &[ParamSpec {
+    name: "sweep",
+    description: "Seconds for the wave to cross the screen",
+    default: 3.5,
+    min: 0.5,
+    max: 30.0,
+}];
 
 /// Cascade rain: columns activate in a wave-front sweep.
 pub struct CascadeRain {
     columns: Vec<RainColumn>,
+    /// Finished columns, kept to respawn
+    pool: ColumnPool,
     /// Current wave position (fractional column index)
     wave_x: f64,
-    /// Wave speed in columns per second
+    /// Wave speed in columns per second, and seconds per sweep at 1x
     wave_speed: f64,
+    sweep_secs: f64,
     /// Which x positions have been activated
     activated: Vec<bool>,
     palette: Palette,
@@ -27,25 +44,56 @@ pub struct CascadeRain {
     height: u16,
     speed_multiplier: f64,
     forward: bool,
+    /// Chance that a trail character changes each frame
+    mutation_rate: f64,
+    /// Chance that a new trail character is a highlight, and its color
+    /// override (`--highlight-color`)
+    highlight_rate: f64,
+    highlight_color: Option<Color>,
+    /// Per-band palettes (`--stripes`), used instead of `palette`
+    stripes: Option<Stripes>,
+    /// Fixed per-cell glyphs (`--static-glyphs`)
+    glyphs: Option<GlyphGrid>,
+    /// Screen areas kept clear of rain
+    dead_zones: Vec<Region>,
+    dead_zone_mode: DeadZoneMode,
+    rng: SimRng,
 }
 
 impl CascadeRain {
-    pub fn with_config(width: u16, height: u16, config: &Config) -> Self {
-        // Wave speed scales with screen width so the sweep completes
-        // in about 3-5 seconds regardless of terminal size
-        let wave_speed = width as f64 / 3.5 * config.speed_multiplier;
+    pub fn with_config(width: u16, height: u16, config: &Config, spec: &EffectSpec) -> Self {
+        // Wave speed scales with screen width so the sweep takes the same
+        // time regardless of terminal size
+        let sweep_secs = spec.get("sweep");
+        let wave_speed = width as f64 / sweep_secs * config.speed_multiplier;
+        let char_pool = charset_by_name(&config.charset_name);
+        let mut rng = rng::make_rng(config.seed, Stream::Cascade);
+        let glyphs = config
+            .static_glyphs
+            .then(|| GlyphGrid::new(width, height, &char_pool, &mut rng));
+        let highlight_color = config.highlight_color.as_deref().and_then(color_by_name);
 
         Self {
-            columns: Vec::new(),
+            columns: Vec::with_capacity(width as usize),
+            pool: ColumnPool::default(),
             wave_x: 0.0,
             wave_speed,
+            sweep_secs,
             activated: vec![false; width as usize],
-            palette: palette_by_name(&config.palette_name),
-            char_pool: charset_by_name(&config.charset_name),
+            palette: palette_by_name(&config.palette_name).with_highlight(highlight_color),
+            char_pool,
             width,
             height,
             speed_multiplier: config.speed_multiplier,
             forward: config.forward,
+            mutation_rate: config.mutation_rate,
+            highlight_rate: config.highlight_rate,
+            highlight_color,
+            stripes: Stripes::new(&config.stripes, config.stripe_direction, highlight_color),
+            glyphs,
+            dead_zones: config.dead_zones.clone(),
+            dead_zone_mode: config.dead_zone_mode,
+            rng,
         }
     }
 
@@ -62,7 +110,6 @@ impl Effect for CascadeRain {
     }
 
     fn update(&mut self, delta_time: f64) {
-        let mut rng = rand::rng();
         let effective_dt = delta_time * self.speed_multiplier;
 
         // Advance the wave front
@@ -73,16 +120,27 @@ impl Effect for CascadeRain {
         for x in 0..wave_end {
             if x < self.activated.len() && !self.activated[x] {
                 self.activated[x] = true;
-                self.columns
-                    .push(RainColumn::spawn(x as u16, self.height, &mut rng));
+                let cursor = self.char_pool.start_cursor(&mut self.rng);
+                self.columns.push(
+                    self.pool
+                        .spawn(x as u16, self.height, self.mutation_rate, &mut self.rng)
+                        .with_highlight_rate(self.highlight_rate)
+                        .with_cursor(cursor),
+                );
             }
         }
 
         // Update existing columns
-        self.columns.retain_mut(|col| {
-            col.update(effective_dt, self.height, &self.char_pool, &mut rng);
+        self.pool.retain(&mut self.columns, |col| {
+            col.update(effective_dt, self.height, &self.char_pool, &mut self.rng);
             !col.is_dead(self.height)
         });
+        stop_at_dead_zones(&mut self.columns, &self.dead_zones, self.dead_zone_mode);
+        if let Some(glyphs) = &mut self.glyphs {
+            for col in &self.columns {
+                glyphs.mutate_under(col, self.mutation_rate, &self.char_pool, &mut self.rng);
+            }
+        }
 
         // If all columns have drained and wave has passed, reset for another sweep
         if self.wave_x > self.width as f64 + 10.0 && self.columns.is_empty() {
@@ -90,27 +148,66 @@ impl Effect for CascadeRain {
         }
     }
 
-    fn render(&self, buffer: &mut ScreenBuffer) {
+    fn render(&self, buffer: &mut dyn RenderTarget) {
         for col in &self.columns {
-            render_rain_column(col, &self.palette, self.height, self.forward, buffer);
+            render_rain_column(
+                col,
+                Coloring::new(&self.palette, self.stripes.as_ref()),
+                self.height,
+                self.forward,
+                self.glyphs.as_ref(),
+                &self.dead_zones,
+                buffer,
+            );
         }
     }
 
     fn resize(&mut self, width: u16, height: u16) {
         self.width = width;
         self.height = height;
-        self.columns.retain(|c| c.x < width);
+        self.pool.retain(&mut self.columns, |c| c.x < width);
+        if let Some(glyphs) = &mut self.glyphs {
+            glyphs.resize(width, height, &self.char_pool, &mut self.rng);
+        }
         // Reset wave for new dimensions
         self.reset_wave();
-        self.wave_speed = width as f64 / 3.5 * self.speed_multiplier;
+        self.wave_speed = width as f64 / self.sweep_secs * self.speed_multiplier;
     }
 
     fn set_speed(&mut self, multiplier: f64) {
         self.speed_multiplier = multiplier;
-        self.wave_speed = self.width as f64 / 3.5 * multiplier;
+        self.wave_speed = self.width as f64 / self.sweep_secs * multiplier;
     }
 
     fn speed(&self) -> f64 {
         self.speed_multiplier
     }
+
+    fn set_dead_zones(&mut self, zones: &[Region]) {
+        if self.dead_zones != zones {
+            self.dead_zones = zones.to_vec();
+        }
+    }
+
+    fn set_mutation(&mut self, rate: f64) {
+        self.mutation_rate = rate;
+        for col in &mut self.columns {
+            col.set_mutation_rate(rate);
+        }
+    }
+
+    fn set_palette(&mut self, palette: &Palette) {
+        self.palette = palette.clone().with_highlight(self.highlight_color);
+    }
+
+    fn set_charset(&mut self, pool: &CharacterPool) {
+        self.char_pool = pool.clone();
+        if let Some(glyphs) = &mut self.glyphs {
+            glyphs.fill(&self.char_pool, &mut self.rng);
+        }
+    }
+
+    fn active_columns(&self) -> Option<usize> {
+        Some(self.columns.len())
+    }
 }
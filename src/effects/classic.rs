@@ -1,9 +1,12 @@
 //! Classic Matrix rain effect: the iconic green falling characters.
 
 use super::Effect;
-use crate::buffer::ScreenBuffer;
+use crate::buffer::{Region, RenderTarget};
+use crate::color::palette::Palette;
 use crate::config::Config;
+use crate::event::Event;
 use crate::rain::RainField;
+use crate::rain::chars::CharacterPool;
 
 /// The classic Matrix digital rain effect.
 pub struct ClassicRain {
@@ -27,7 +30,7 @@ impl Effect for ClassicRain {
         self.rain.update(delta_time);
     }
 
-    fn render(&self, buffer: &mut ScreenBuffer) {
+    fn render(&self, buffer: &mut dyn RenderTarget) {
         self.rain.render(buffer);
     }
 
@@ -50,4 +53,28 @@ impl Effect for ClassicRain {
     fn density(&self) -> f64 {
         self.rain.density()
     }
+
+    fn set_dead_zones(&mut self, zones: &[Region]) {
+        self.rain.set_dead_zones(zones);
+    }
+
+    fn set_mutation(&mut self, rate: f64) {
+        self.rain.set_mutation(rate);
+    }
+
+    fn set_palette(&mut self, palette: &Palette) {
+        self.rain.set_palette(palette.clone());
+    }
+
+    fn set_charset(&mut self, pool: &CharacterPool) {
+        self.rain.set_charset(pool.clone());
+    }
+
+    fn on_event(&mut self, event: &Event) {
+        self.rain.on_event(event);
+    }
+
+    fn active_columns(&self) -> Option<usize> {
+        Some(self.rain.column_count())
+    }
 }
@@ -0,0 +1,267 @@
+//! Digital corruption events shared by the glitch effect and the glitch
+//! transition.
+//!
+//! Three types of event, each living for a fraction of a second:
+//! - Horizontal tear: a band of rows shifts left/right
+//! - Block corruption: a rectangular region fills with noise characters
+//! - Color separation: RGB channel offset in a region
+//!
+//! Events are applied as a post-process over whatever is already in the
+//! buffer. `severity` scales their size: 1.0 is the glitch effect's normal
+//! look, the transition ramps it up as it goes.
+//!
+//! Every event covers a `Region` from `random_area`, which always fits the
+//! screen however small it is, and `apply` clips each one to the buffer it
+//! draws into, so neither has any arithmetic that can wrap or panic.
+
+use std::ops::{Range, RangeInclusive};
+
+use rand::{Rng, RngExt, SeedableRng};
+
+use crate::buffer::{Region, RenderTarget};
+use crate::color::Color;
+use crate::color::gradient::{color_to_rgb, scale_color};
+use crate::rng::SimRng;
+
+/// Characters used for block corruption glitches.
+const GLITCH_CHARS: &[char] = &[
+    '#', '%', '&', '@', '!', '/', '\\', '|', '.', ':', '<', '>', '~', '^', '*', '=',
+];
+
+/// A single active glitch event with a lifetime.
+enum GlitchEvent {
+    /// Horizontal tear: a full-width band of rows shifts sideways
+    HorizontalTear { area: Region, offset: i16, ttl: f64 },
+    /// Block corruption: rectangular noise region
+    BlockCorrupt { area: Region, ttl: f64 },
+    /// Color separation: RGB channel offset in a full-width band of rows
+    ColorSep {
+        area: Region,
+        r_offset: i16,
+        ttl: f64,
+    },
+}
+
+impl GlitchEvent {
+    fn ttl_mut(&mut self) -> &mut f64 {
+        match self {
+            Self::HorizontalTear { ttl, .. }
+            | Self::BlockCorrupt { ttl, .. }
+            | Self::ColorSep { ttl, .. } => ttl,
+        }
+    }
+}
+
+/// The set of live corruption events for a screen.
+pub struct Corruption {
+    events: Vec<GlitchEvent>,
+    width: u16,
+    height: u16,
+    /// Seed for this frame's block-corruption noise, refreshed each update
+    /// (apply only has `&self`, so it can't draw from an rng itself)
+    render_seed: u64,
+}
+
+impl Corruption {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            events: Vec::new(),
+            width,
+            height,
+            render_seed: 0,
+        }
+    }
+
+    /// Spawn one random event, `severity` times the normal size.
+    pub fn spawn(&mut self, rng: &mut impl Rng, severity: f64) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        // Scale an upper bound by severity
+        let scaled = |n: u16| ((n as f64 * severity).round() as u16).max(1);
+        let reach = |n: u16| scaled(n).min(i16::MAX as u16) as i16;
+        let screen = (self.width, self.height);
+        let full_width = self.width..=self.width;
+        let glitch_type = rng.random_range(0..3);
+        match glitch_type {
+            0 => {
+                // Horizontal tear
+                let area = random_area(rng, screen, full_width, 1..=scaled(4));
+                let reach = reach(8);
+                self.events.push(GlitchEvent::HorizontalTear {
+                    area,
+                    offset: rng.random_range(-reach..=reach),
+                    ttl: rng.random_range(0.05..0.2),
+                });
+            }
+            1 => {
+                // Block corruption
+                let area = random_area(rng, screen, 3..=scaled(12), 2..=scaled(5));
+                self.events.push(GlitchEvent::BlockCorrupt {
+                    area,
+                    ttl: rng.random_range(0.03..0.15),
+                });
+            }
+            _ => {
+                // Color separation
+                let area = random_area(rng, screen, full_width, 2..=scaled(6));
+                let reach = reach(3);
+                self.events.push(GlitchEvent::ColorSep {
+                    area,
+                    r_offset: rng.random_range(-reach..=reach),
+                    ttl: rng.random_range(0.05..0.15),
+                });
+            }
+        }
+    }
+
+    /// Age events by `delta_time`, drop expired ones, and pick this frame's
+    /// noise seed.
+    pub fn update(&mut self, delta_time: f64, rng: &mut impl Rng) {
+        self.render_seed = rng.random();
+        for event in &mut self.events {
+            *event.ttl_mut() -= delta_time;
+        }
+        self.events.retain_mut(|e| *e.ttl_mut() > 0.0);
+    }
+
+    /// Drop every event and adopt a new screen size.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.width = width;
+        self.height = height;
+        self.events.clear();
+    }
+
+    /// Corrupt what's already in `buffer`.
+    pub fn apply(&self, buffer: &mut dyn RenderTarget) {
+        let mut rng = SimRng::seed_from_u64(self.render_seed);
+        let width = self.width.min(buffer.width());
+        let height = self.height.min(buffer.height());
+
+        for event in &self.events {
+            match event {
+                GlitchEvent::HorizontalTear { area, offset, .. } => {
+                    // Shift rows horizontally by reading and rewriting cells
+                    for y in span(area.y, area.height, height) {
+                        // Read entire row into a temp buffer
+                        let mut row: Vec<_> = (0..width)
+                            .map(|x| buffer.get_cell(x, y).unwrap_or_default())
+                            .collect();
+                        // Shift the row
+                        let len = row.len();
+                        if *offset > 0 {
+                            row.rotate_right((*offset as usize).min(len));
+                        } else if *offset < 0 {
+                            row.rotate_left(((-*offset) as usize).min(len));
+                        }
+                        // Write back
+                        for (x, cell) in row.iter().enumerate() {
+                            buffer.set_cell(x as u16, y, cell.ch, cell.fg, cell.bg);
+                        }
+                    }
+                }
+                GlitchEvent::BlockCorrupt { area, .. } => {
+                    // Fill block with random noise characters
+                    for by in span(area.y, area.height, height) {
+                        for bx in span(area.x, area.width, width) {
+                            let ch = GLITCH_CHARS[rng.random_range(0..GLITCH_CHARS.len())];
+                            let brightness = rng.random_range(0.5..1.5);
+                            if let Some(cell) = buffer.get_cell(bx, by) {
+                                let fg = scale_color(cell.fg, brightness);
+                                buffer.set_cell(bx, by, ch, fg, cell.bg);
+                            }
+                        }
+                    }
+                }
+                GlitchEvent::ColorSep { area, r_offset, .. } => {
+                    // Shift the red channel by reading from offset position
+                    for y in span(area.y, area.height, height) {
+                        for x in 0..width {
+                            if let Some(cell) = buffer.get_cell(x, y) {
+                                if cell.ch == ' ' {
+                                    continue;
+                                }
+                                let (r, g, b) = color_to_rgb(cell.fg);
+                                // Read red from an offset position
+                                let src_x =
+                                    (x as i32 + *r_offset as i32).clamp(0, width as i32 - 1) as u16;
+                                let shifted_r = if let Some(src) = buffer.get_cell(src_x, y) {
+                                    let (sr, _, _) = color_to_rgb(src.fg);
+                                    sr
+                                } else {
+                                    r
+                                };
+                                let new_fg = Color::Rgb { r: shifted_r, g, b };
+                                buffer.set_cell(x, y, cell.ch, new_fg, cell.bg);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A random area on a `screen` (width, height) that isn't empty: each side
+/// is picked from its range, cut down to fit the screen, then the area is
+/// placed where it fits whole. The screen must not be empty.
+fn random_area(
+    rng: &mut impl Rng,
+    screen: (u16, u16),
+    width: RangeInclusive<u16>,
+    height: RangeInclusive<u16>,
+) -> Region {
+    fn side(rng: &mut impl Rng, range: RangeInclusive<u16>, limit: u16) -> u16 {
+        let max = (*range.end()).clamp(1, limit);
+        let min = (*range.start()).clamp(1, max);
+        rng.random_range(min..=max)
+    }
+    let w = side(rng, width, screen.0);
+    let h = side(rng, height, screen.1);
+    Region {
+        x: rng.random_range(0..=screen.0 - w),
+        y: rng.random_range(0..=screen.1 - h),
+        width: w,
+        height: h,
+    }
+}
+
+/// The cells from `start` for `len`, cut off at `limit`.
+fn span(start: u16, len: u16, limit: u16) -> Range<u16> {
+    start.min(limit)..start.saturating_add(len).min(limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::ScreenBuffer;
+
+    #[test]
+    fn events_fit_any_screen_and_any_buffer() {
+        let mut rng = SimRng::seed_from_u64(11);
+        for width in 0..=6 {
+            for height in 0..=6 {
+                for severity in [0.0, 0.5, 1.0, 4.0, 1e6] {
+                    let mut corruption = Corruption::new(width, height);
+                    for _ in 0..40 {
+                        corruption.spawn(&mut rng, severity);
+                    }
+                    for event in &corruption.events {
+                        let (GlitchEvent::HorizontalTear { area, .. }
+                        | GlitchEvent::BlockCorrupt { area, .. }
+                        | GlitchEvent::ColorSep { area, .. }) = event;
+                        assert!(area.width >= 1 && area.height >= 1);
+                        assert!(area.x + area.width <= width && area.y + area.height <= height);
+                    }
+                    // Buffers that lag behind a resize, either way
+                    for (bw, bh) in [(width, height), (width / 2, height), (width + 3, 1)] {
+                        let mut buffer = ScreenBuffer::new(bw, bh);
+                        buffer.set_cell(0, 0, 'x', Color::Rgb { r: 9, g: 9, b: 9 }, Color::Reset);
+                        corruption.update(0.0, &mut rng);
+                        corruption.apply(&mut buffer);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -7,12 +7,14 @@
 //! Inspired by the classic Doom fire effect algorithm.
 //! Reference: https://fabiensanglard.net/doom_fire_psx/
 
-use crossterm::style::Color;
-use rand::Rng;
+use rand::RngExt;
 
 use super::Effect;
-use crate::buffer::ScreenBuffer;
+use super::params::{EffectSpec, ParamSpec};
+use crate::buffer::RenderTarget;
+use crate::color::Color;
 use crate::color::gradient::lerp_color;
+use crate::rng::{self, SimRng, Stream};
 
 /// Fire gradient stops: black -> dark red -> red -> orange -> yellow -> white
 const FIRE_GRADIENT: [(f32, Color); 6] = [
@@ -55,6 +57,15 @@ const FIRE_GRADIENT: [(f32, Color); 6] = [
 /// Characters used for fire rendering, ordered by heat intensity.
 const FIRE_CHARS: &[char] = &[' ', '.', ':', '^', '*', '#', '%', '@'];
 
+/// Tunables for `--param`.
+pub const PARAMS: &[ParamSpec] = &[ParamSpec {
+    name: "cooling",
+    description: "How fast heat fades as it rises; higher means lower flames",
+    default: 0.04,
+    min: 0.005,
+    max: 0.2,
+}];
+
 /// Classic cellular automata fire simulation.
 pub struct FireEffect {
     width: u16,
@@ -64,17 +75,24 @@ pub struct FireEffect {
     speed_multiplier: f64,
     /// Cooling factor: higher = fire dies faster
     cooling_factor: f64,
+    rng: SimRng,
 }
 
 impl FireEffect {
-    pub fn with_config(width: u16, height: u16, config: &crate::config::Config) -> Self {
+    pub fn with_config(
+        width: u16,
+        height: u16,
+        config: &crate::config::Config,
+        spec: &EffectSpec,
+    ) -> Self {
         let size = width as usize * height as usize;
         Self {
             width,
             height,
             heat_map: vec![0.0; size],
             speed_multiplier: config.speed_multiplier,
-            cooling_factor: 0.04,
+            cooling_factor: spec.get("cooling"),
+            rng: rng::make_rng(config.seed, Stream::Fire),
         }
     }
 
@@ -107,7 +125,6 @@ impl Effect for FireEffect {
     }
 
     fn update(&mut self, delta_time: f64) {
-        let mut rng = rand::rng();
         let w = self.width as usize;
         let h = self.height as usize;
         if w == 0 || h == 0 {
@@ -121,7 +138,7 @@ impl Effect for FireEffect {
             // Seed the bottom row with random heat
             for x in 0..w {
                 let idx = (h - 1) * w + x;
-                self.heat_map[idx] = rng.random_range(0.6..1.0);
+                self.heat_map[idx] = self.rng.random_range(0.6..1.0);
             }
 
             // Propagate heat upward: each cell averages neighbors below with cooling
@@ -141,14 +158,14 @@ impl Effect for FireEffect {
                     // Average of 3 neighbors below with random cooling
                     let avg =
                         (self.heat_map[below] + self.heat_map[left] + self.heat_map[right]) / 3.0;
-                    let cooling = rng.random_range(0.0..self.cooling_factor);
+                    let cooling = self.rng.random_range(0.0..self.cooling_factor);
                     self.heat_map[idx] = (avg - cooling).max(0.0);
                 }
             }
         }
     }
 
-    fn render(&self, buffer: &mut ScreenBuffer) {
+    fn render(&self, buffer: &mut dyn RenderTarget) {
         let w = self.width as usize;
         for y in 0..self.height {
             for x in 0..self.width {
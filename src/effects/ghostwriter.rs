@@ -0,0 +1,476 @@
+//! Ghostwriter effect: an invisible typist at the terminal.
+//!
+//! Lines of text (built-in quotes, or `--text-file`) are typed a key at a
+//! time behind a block cursor, with uneven human timing: quicker inside
+//! words, slower after spaces and punctuation, and the odd pause to think.
+//! Now and then a key is missed; the typist carries on for a key or two,
+//! notices, backspaces, and retypes. Finished lines scroll up and fade out.
+
+use std::collections::VecDeque;
+
+use rand::RngExt;
+
+use super::Effect;
+use super::params::{EffectSpec, ParamSpec};
+use crate::buffer::RenderTarget;
+use crate::color::Color;
+use crate::color::gradient::lerp_color;
+use crate::color::palette::{Palette, palette_by_name};
+use crate::config::Config;
+use crate::rng::{self, SimRng, Stream};
+
+/// Tunables for `--param`.
+pub const PARAMS: &[ParamSpec] = &[
+    ParamSpec {
+        name: "cps",
+        description: "Typing speed, in characters per second",
+        default: 9.0,
+        min: 1.0,
+        max: 40.0,
+    },
+    ParamSpec {
+        name: "typos",
+        description: "Chance of a typo on each key",
+        default: 0.04,
+        min: 0.0,
+        max: 0.5,
+    },
+];
+
+/// Typed when there's no `--text-file`.
+const QUOTES: &[&str] = &[
+    "Wake up, Neo...",
+    "The Matrix has you...",
+    "Follow the white rabbit.",
+    "Knock, knock, Neo.",
+    "There is no spoon.",
+    "Welcome to the desert of the real.",
+    "I know kung fu.",
+    "Free your mind.",
+    "There is a difference between knowing the path and walking the path.",
+    "Unfortunately, no one can be told what the Matrix is. You have to see it for yourself.",
+    "What is real? How do you define real?",
+    "Everything that has a beginning has an end.",
+    "Choice is an illusion created between those with power and those without.",
+    "I'm trying to free your mind, Neo. But I can only show you the door.",
+];
+
+/// Keyboard rows, for missing a key by one.
+const KEY_ROWS: [&str; 3] = ["qwertyuiop", "asdfghjkl", "zxcvbnm"];
+/// How much each key's delay varies, either way, as a share of the average
+const JITTER: f64 = 0.5;
+/// Extra delay after a space, and after punctuation, in keystrokes
+const SPACE_PAUSE: f64 = 1.0;
+const PUNCTUATION_PAUSE: f64 = 4.0;
+/// Chance per key of stopping to think mid-line, and for how long
+const THINK_CHANCE: f64 = 0.02;
+const THINK_SECS: f64 = 0.8;
+/// Seconds between a typo being noticed and the first backspace
+const NOTICE_SECS: f64 = 0.35;
+/// Seconds per backspace
+const BACKSPACE_SECS: f64 = 0.07;
+/// Seconds between finishing a line and starting the next
+const LINE_PAUSE_SECS: f64 = 1.2;
+/// Seconds a finished line takes to fade out
+const FADE_SECS: f64 = 24.0;
+/// Cursor blink period in seconds, while the typist is idle
+const BLINK_PERIOD: f64 = 1.0;
+
+/// What the typist is doing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Typist {
+    /// Typing the line, or carrying on past a typo not yet noticed
+    Typing,
+    /// Staring at a typo, about to fix it
+    Noticing,
+    /// Deleting back to the typo
+    Backspacing,
+    /// Between lines
+    Resting,
+}
+
+/// A finished line, drifting up the screen.
+struct Line {
+    text: String,
+    /// Seconds since it was finished
+    age: f64,
+}
+
+/// Types, mistypes, and corrects lines of text.
+pub struct Ghostwriter {
+    /// Every line to type, in order, over and over
+    source: Vec<String>,
+    /// Index into `source` of the next line to queue
+    next: usize,
+    /// The next line's pieces, wrapped to the screen
+    queue: VecDeque<String>,
+    /// The line being typed
+    target: Vec<char>,
+    /// What's been typed of it, typos and all
+    typed: Vec<char>,
+    /// How much of `typed` is right
+    correct: usize,
+    /// Keys left to type before the typist notices a typo
+    blind: usize,
+    state: Typist,
+    /// Seconds until the typist's next action
+    wait: f64,
+    /// Finished lines, newest last
+    history: Vec<Line>,
+    /// Total running time, for the cursor blink
+    time: f64,
+    cps: f64,
+    typo_chance: f64,
+    speed_multiplier: f64,
+    palette: Palette,
+    width: u16,
+    height: u16,
+    rng: SimRng,
+}
+
+impl Ghostwriter {
+    pub fn with_config(width: u16, height: u16, config: &Config, spec: &EffectSpec) -> Self {
+        let mut rng = rng::make_rng(config.seed, Stream::Ghostwriter);
+        let source = config
+            .text_file
+            .as_deref()
+            .map(read_lines)
+            .filter(|lines| !lines.is_empty())
+            .unwrap_or_else(|| QUOTES.iter().map(|q| q.to_string()).collect());
+        Self {
+            // The quotes start somewhere different on every seed; a file
+            // starts at the top
+            next: match config.text_file {
+                Some(_) => 0,
+                None => rng.random_range(0..source.len()),
+            },
+            source,
+            queue: VecDeque::new(),
+            target: Vec::new(),
+            typed: Vec::new(),
+            correct: 0,
+            blind: 0,
+            state: Typist::Resting,
+            wait: LINE_PAUSE_SECS,
+            history: Vec::new(),
+            time: 0.0,
+            cps: spec.get("cps"),
+            typo_chance: spec.get("typos"),
+            speed_multiplier: config.speed_multiplier,
+            palette: palette_by_name(&config.palette_name),
+            width,
+            height,
+            rng,
+        }
+    }
+
+    /// The row the typist types on; finished lines stack above it.
+    fn typing_row(&self) -> u16 {
+        (self.height.saturating_mul(2) / 3).min(self.height.saturating_sub(1))
+    }
+
+    /// Take the next line to type, wrapping the source's next line to the
+    /// screen when the last one has run out.
+    fn start_line(&mut self) {
+        if self.queue.is_empty() {
+            let line = &self.source[self.next % self.source.len()];
+            self.next = (self.next + 1) % self.source.len();
+            self.queue = wrap(line, self.width.saturating_sub(2).max(1) as usize).into();
+        }
+        self.target = self.queue.pop_front().unwrap_or_default().chars().collect();
+        self.typed.clear();
+        self.correct = 0;
+        self.blind = 0;
+        self.state = Typist::Typing;
+        self.wait = self.key_delay(' ');
+    }
+
+    /// Seconds before the key after `prev`, jittered.
+    fn key_delay(&mut self, prev: char) -> f64 {
+        let mut keys = 1.0 + self.rng.random_range(-JITTER..JITTER);
+        if prev == ' ' {
+            keys += SPACE_PAUSE;
+        } else if prev.is_ascii_punctuation() {
+            keys += PUNCTUATION_PAUSE;
+        }
+        let mut delay = keys / self.cps;
+        if self.rng.random_bool(THINK_CHANCE) {
+            delay += THINK_SECS * self.rng.random_range(0.5..1.5);
+        }
+        delay
+    }
+
+    /// The typist's next action, now that `wait` has run out.
+    fn act(&mut self) {
+        match self.state {
+            Typist::Resting => self.start_line(),
+            Typist::Typing => {
+                let has_typo = self.typed.len() > self.correct;
+                let at_end = self.typed.len() >= self.target.len();
+                if has_typo && (self.blind == 0 || at_end) {
+                    self.state = Typist::Noticing;
+                    self.wait = NOTICE_SECS * self.rng.random_range(0.7..1.5);
+                    return;
+                }
+                let Some(&intended) = self.target.get(self.typed.len()) else {
+                    // Done (a typo still pending at the end is noticed above)
+                    self.history.push(Line {
+                        text: self.typed.iter().collect(),
+                        age: 0.0,
+                    });
+                    self.target.clear();
+                    self.typed.clear();
+                    self.correct = 0;
+                    self.state = Typist::Resting;
+                    self.wait = LINE_PAUSE_SECS * self.rng.random_range(0.6..1.6);
+                    return;
+                };
+                if has_typo {
+                    self.typed.push(intended);
+                    self.blind -= 1;
+                } else if intended != ' ' && self.rng.random_bool(self.typo_chance) {
+                    self.typed.push(miss(intended, &mut self.rng));
+                    self.blind = self.rng.random_range(0..=2);
+                } else {
+                    self.typed.push(intended);
+                    self.correct += 1;
+                }
+                self.wait = self.key_delay(intended);
+            }
+            Typist::Noticing => {
+                self.state = Typist::Backspacing;
+                self.wait = BACKSPACE_SECS;
+            }
+            Typist::Backspacing => {
+                self.typed.pop();
+                if self.typed.len() > self.correct {
+                    self.wait = BACKSPACE_SECS;
+                } else {
+                    self.state = Typist::Typing;
+                    self.wait = self.key_delay(' ');
+                }
+            }
+        }
+    }
+
+    fn draw(&self, buffer: &mut dyn RenderTarget, y: u16, text: &[char], color: Color) {
+        for (i, &ch) in text.iter().enumerate() {
+            let x = 1 + i as u16;
+            if x >= self.width {
+                break;
+            }
+            buffer.set_cell(x, y, ch, color, Color::Reset);
+        }
+    }
+}
+
+impl Effect for Ghostwriter {
+    fn name(&self) -> &str {
+        "ghostwriter"
+    }
+
+    fn update(&mut self, delta_time: f64) {
+        let dt = delta_time * self.speed_multiplier;
+        self.time += dt;
+        for line in &mut self.history {
+            line.age += dt;
+        }
+
+        // A long frame may hold several keystrokes
+        let mut budget = dt;
+        while budget >= self.wait {
+            budget -= self.wait;
+            self.act();
+        }
+        self.wait -= budget;
+
+        // Lines fade out, or scroll off the top
+        let room = self.typing_row() as usize;
+        self.history.retain(|line| line.age < FADE_SECS);
+        if self.history.len() > room {
+            self.history.drain(..self.history.len() - room);
+        }
+    }
+
+    fn render(&self, buffer: &mut dyn RenderTarget) {
+        if self.width < 3 || self.height == 0 {
+            return;
+        }
+        let row = self.typing_row();
+        for (i, line) in self.history.iter().rev().enumerate() {
+            let Some(y) = row.checked_sub(1 + i as u16) else {
+                break;
+            };
+            let fade = (line.age / FADE_SECS) as f32;
+            let color = lerp_color(self.palette.body_mid, self.palette.tail, fade);
+            let text: Vec<char> = line.text.chars().collect();
+            self.draw(buffer, y, &text, color);
+        }
+
+        self.draw(buffer, row, &self.typed, self.palette.body_bright);
+        // Solid while typing, blinking while idle
+        let typing = matches!(self.state, Typist::Typing | Typist::Backspacing);
+        let cursor_x = 1 + self.typed.len() as u16;
+        if (typing || self.time % BLINK_PERIOD < BLINK_PERIOD / 2.0) && cursor_x < self.width {
+            buffer.set_cell(cursor_x, row, '█', self.palette.head, Color::Reset);
+        }
+    }
+
+    fn resize(&mut self, width: u16, height: u16) {
+        self.width = width;
+        self.height = height;
+    }
+
+    fn set_speed(&mut self, multiplier: f64) {
+        self.speed_multiplier = multiplier;
+    }
+
+    fn speed(&self) -> f64 {
+        self.speed_multiplier
+    }
+
+    fn set_palette(&mut self, palette: &Palette) {
+        self.palette = palette.clone();
+    }
+}
+
+/// The non-blank lines of a text file, or none if it can't be read.
+fn read_lines(path: &std::path::Path) -> Vec<String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => text
+            .lines()
+            .map(|line| line.trim_end().replace('\t', "    "))
+            .filter(|line| !line.trim().is_empty())
+            .collect(),
+        Err(e) => {
+            eprintln!("Warning: --text-file {}: {}", path.display(), e);
+            Vec::new()
+        }
+    }
+}
+
+/// Word-wrap `text` into lines of at most `width` characters.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split(' ') {
+        let mut word: Vec<char> = word.chars().collect();
+        // Break words too long for a line of their own
+        while word.len() > width {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            lines.push(word.drain(..width).collect());
+        }
+        let len = line.chars().count();
+        if len > 0 && len + 1 + word.len() > width {
+            lines.push(std::mem::take(&mut line));
+        } else if len > 0 {
+            line.push(' ');
+        }
+        line.extend(word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// A key next to the one meant, in the same case.
+fn miss(intended: char, rng: &mut SimRng) -> char {
+    let lower = intended.to_ascii_lowercase();
+    let missed = KEY_ROWS
+        .iter()
+        .find_map(|row| {
+            let keys: Vec<char> = row.chars().collect();
+            let i = keys.iter().position(|&k| k == lower)?;
+            let neighbors: Vec<char> = [i.checked_sub(1), Some(i + 1)]
+                .into_iter()
+                .flatten()
+                .filter_map(|j| keys.get(j).copied())
+                .collect();
+            Some(neighbors[rng.random_range(0..neighbors.len())])
+        })
+        .unwrap_or_else(|| KEY_ROWS[1].as_bytes()[rng.random_range(0..9)] as char);
+    if intended.is_ascii_uppercase() {
+        missed.to_ascii_uppercase()
+    } else {
+        missed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::buffer::ScreenBuffer;
+    use crate::config::{Cli, ConfigFile};
+
+    fn ghostwriter(args: &[&str], spec: EffectSpec) -> Ghostwriter {
+        let cli = Cli::parse_from([&["digital_rain", "--seed", "3"][..], args].concat());
+        let config = Config::resolve(&cli, &ConfigFile::default());
+        Ghostwriter::with_config(60, 12, &config, &spec)
+    }
+
+    #[test]
+    fn typos_are_noticed_and_corrected() {
+        let spec = EffectSpec::new("ghostwriter").with("typos", 0.3);
+        let mut effect = ghostwriter(&[], spec);
+        let (mut states, mut lines) = (Vec::new(), Vec::new());
+        for _ in 0..30 * 120 {
+            let finished = effect.history.len();
+            effect.update(1.0 / 30.0);
+            states.push(effect.state);
+            if effect.history.len() > finished {
+                lines.push(effect.history.last().unwrap().text.clone());
+            }
+            // What's typed only ever strays from the line after a typo
+            let right = effect.typed.iter().zip(&effect.target);
+            assert_eq!(right.take_while(|(a, b)| a == b).count(), effect.correct);
+        }
+        assert!(states.contains(&Typist::Backspacing));
+        // Every finished line is (a piece of) one of the quotes, typed right
+        assert!(lines.len() >= 3);
+        for line in lines {
+            assert!(QUOTES.iter().any(|q| q.contains(&line)), "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn lines_come_from_the_text_file_and_scroll_up_fading() {
+        let path = std::env::temp_dir().join(format!("digitalrain-ghost-{}", std::process::id()));
+        std::fs::write(&path, "first line\n\nsecond line\nthird\n").unwrap();
+        let spec = EffectSpec::new("ghostwriter")
+            .with("typos", 0.0)
+            .with("cps", 40.0);
+        let mut effect = ghostwriter(&["--text-file", path.to_str().unwrap()], spec);
+        std::fs::remove_file(&path).unwrap();
+        while effect.history.len() < 3 {
+            effect.update(0.1);
+        }
+
+        let mut buffer = ScreenBuffer::new(60, 12);
+        effect.render(&mut buffer);
+        let row = |y: u16| -> String {
+            (0..60)
+                .map(|x| buffer.get_cell(x, y).unwrap().ch)
+                .collect::<String>()
+                .trim()
+                .to_string()
+        };
+        assert_eq!(
+            [row(5), row(6), row(7)],
+            ["first line", "second line", "third"]
+        );
+        let fg = |y: u16| buffer.get_cell(1, y).unwrap().fg;
+        assert!(fg(5) != fg(7));
+
+        // The file goes round again, and the oldest lines scroll off the top
+        for _ in 0..30 {
+            effect.update(1.0);
+        }
+        assert_eq!(effect.history.len(), effect.typing_row() as usize);
+        assert!(effect.history.iter().any(|l| l.text == "first line"));
+    }
+}
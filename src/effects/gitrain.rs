@@ -0,0 +1,457 @@
+//! Git rain effect: a repository's history falling as rain.
+//!
+//! Reads the commit log of `--git-repo` (the current directory by default)
+//! through `git log`, then plays it back as a timeline from the first
+//! commit to the last. Each commit drops a column spelling its short hash
+//! and the author's initials, so a busy day arrives as a burst and a quiet
+//! month as a few scattered drops. The date being played shows in the
+//! bottom-right corner, and the timeline starts over after the last commit.
+//!
+//! Without git or a repository there's no history to play, so random
+//! hashes fall at a steady pace instead. The same trickle covers the wait
+//! while the log is read: that happens once per repository, on a
+//! background thread, and every later gitrain shares the result, so
+//! cycling back to the effect never stalls the frame loop on a big log.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+
+use rand::RngExt;
+
+use super::Effect;
+use super::params::{EffectSpec, ParamSpec};
+use crate::buffer::RenderTarget;
+use crate::color::gradient::lerp_color;
+use crate::color::palette::{Palette, palette_by_name};
+use crate::config::Config;
+use crate::rng::{self, SimRng, Stream};
+
+/// Tunables for `--param`.
+pub const PARAMS: &[ParamSpec] = &[ParamSpec {
+    name: "pace",
+    description: "Days of history played per second (0 = fit it all into a minute)",
+    default: 0.0,
+    min: 0.0,
+    max: 365.0,
+}];
+
+const SECS_PER_DAY: f64 = 86_400.0;
+
+/// How long an automatic-pace playback of the whole history takes.
+const PLAYBACK_SECS: f64 = 60.0;
+
+/// Slowest automatic pace, so a history of a few days doesn't crawl.
+const MIN_DAYS_PER_SEC: f64 = 0.25;
+
+/// Seconds of empty screen between the last commit and starting over.
+const RESTART_SECS: f64 = 2.0;
+
+/// Drop fall speed range, in rows per second.
+const FALL_SPEED: std::ops::Range<f64> = 6.0..16.0;
+
+/// Most drops on screen at once, per column, so a mass import doesn't
+/// bury the screen.
+const MAX_DROPS_PER_COLUMN: usize = 2;
+
+/// Commits made up per day when there's no history to play.
+const SYNTHETIC_PER_DAY: usize = 6;
+
+/// One commit from the log.
+#[derive(Clone, Debug, PartialEq)]
+struct Commit {
+    /// Author time, in Unix seconds
+    time: i64,
+    /// Short hash, then the author's initials
+    label: String,
+}
+
+/// A repository's commits, oldest first, filled in once the background
+/// read finishes.
+type History = Arc<OnceLock<Arc<[Commit]>>>;
+
+/// Every history asked for so far, by repository.
+static HISTORIES: Mutex<BTreeMap<PathBuf, History>> = Mutex::new(BTreeMap::new());
+
+/// A commit label falling down one column.
+struct CommitDrop {
+    x: u16,
+    /// Row of the label's last character
+    y: f64,
+    /// Rows per second
+    speed: f64,
+    chars: Vec<char>,
+}
+
+/// Commit history falling as rain.
+pub struct GitRain {
+    /// Commits oldest first; empty when there's no history (yet)
+    commits: Arc<[Commit]>,
+    /// The history being read, until it arrives
+    loading: Option<History>,
+    /// The `pace` param (0 = fit the history into a minute)
+    pace: f64,
+    /// Index of the next commit to drop
+    next: usize,
+    /// Timeline position, in Unix seconds
+    clock: f64,
+    days_per_sec: f64,
+    /// Seconds left before the timeline starts over
+    restart_in: f64,
+    drops: Vec<CommitDrop>,
+    palette: Palette,
+    speed_multiplier: f64,
+    density_multiplier: f64,
+    width: u16,
+    height: u16,
+    rng: SimRng,
+}
+
+impl GitRain {
+    pub fn with_config(width: u16, height: u16, config: &Config, spec: &EffectSpec) -> Self {
+        let repo = config.git_repo.as_deref().unwrap_or(Path::new("."));
+        let mut effect = Self {
+            commits: Arc::new([]),
+            loading: Some(history(repo)),
+            pace: spec.get("pace"),
+            clock: 0.0,
+            next: 0,
+            days_per_sec: 1.0,
+            restart_in: 0.0,
+            drops: Vec::new(),
+            palette: palette_by_name(&config.palette_name),
+            speed_multiplier: config.speed_multiplier,
+            density_multiplier: config.density_multiplier,
+            width,
+            height,
+            rng: rng::make_rng(config.seed, Stream::GitRain),
+        };
+        // Already read for an earlier gitrain: play it from the start
+        effect.poll_history();
+        effect
+    }
+
+    /// Start playing the history once it has been read.
+    fn poll_history(&mut self) {
+        let Some(commits) = self.loading.as_ref().and_then(|h| h.get()) else {
+            return;
+        };
+        self.commits = Arc::clone(commits);
+        self.loading = None;
+        self.next = 0;
+        self.clock = self.commits.first().map_or(0.0, |c| c.time as f64);
+        self.days_per_sec = match (self.commits.first(), self.commits.last()) {
+            _ if self.pace > 0.0 => self.pace,
+            (Some(first), Some(last)) => {
+                ((last.time - first.time) as f64 / SECS_PER_DAY / PLAYBACK_SECS)
+                    .max(MIN_DAYS_PER_SEC)
+            }
+            _ => 1.0,
+        };
+    }
+
+    fn spawn(&mut self, label: &str) {
+        if self.width == 0 || self.drops.len() >= self.width as usize * MAX_DROPS_PER_COLUMN {
+            return;
+        }
+        self.drops.push(CommitDrop {
+            x: self.rng.random_range(0..self.width),
+            y: -1.0,
+            speed: self.rng.random_range(FALL_SPEED),
+            chars: label.chars().collect(),
+        });
+    }
+
+    /// Advance the timeline by `days` and drop every commit it passes.
+    fn play(&mut self, days: f64) {
+        if self.commits.is_empty() {
+            // No history: a steady trickle of made-up hashes
+            let expected = days * SYNTHETIC_PER_DAY as f64;
+            let count = expected.floor() as usize + self.rng.random_bool(expected.fract()) as usize;
+            for _ in 0..count {
+                let label = format!("{:07x}", self.rng.random_range(0..0x1000_0000u32));
+                self.spawn(&label);
+            }
+            return;
+        }
+
+        if self.next >= self.commits.len() {
+            // Let the last drops land, then start over
+            if self.drops.is_empty() {
+                self.restart_in -= days / self.days_per_sec;
+                if self.restart_in <= 0.0 {
+                    self.next = 0;
+                    self.clock = self.commits[0].time as f64;
+                }
+            }
+            return;
+        }
+
+        self.clock += days * SECS_PER_DAY;
+        while let Some(commit) = self.commits.get(self.next)
+            && commit.time as f64 <= self.clock
+        {
+            let label = commit.label.clone();
+            self.spawn(&label);
+            self.next += 1;
+        }
+        if self.next >= self.commits.len() {
+            self.restart_in = RESTART_SECS;
+        }
+    }
+}
+
+/// The shared history of `repo`, starting a background read the first
+/// time it's asked for.
+fn history(repo: &Path) -> History {
+    let mut histories = HISTORIES.lock().unwrap_or_else(|e| e.into_inner());
+    let history = histories.entry(repo.to_path_buf()).or_insert_with(|| {
+        let history = History::default();
+        let (slot, path) = (Arc::clone(&history), repo.to_path_buf());
+        let spawned = thread::Builder::new()
+            .name("gitrain".to_string())
+            .spawn(move || {
+                let _ = slot.set(read_history(&path).into());
+            });
+        // No threads on this platform: read it here instead
+        if spawned.is_err() {
+            let _ = history.set(read_history(repo).into());
+        }
+        history
+    });
+    Arc::clone(history)
+}
+
+/// The commits in `repo`, oldest first. Empty if git isn't installed or
+/// `repo` isn't a repository.
+fn read_history(repo: &Path) -> Vec<Commit> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["log", "--format=%h%x09%an%x09%at"])
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            let mut commits = parse_log(&String::from_utf8_lossy(&output.stdout));
+            commits.sort_by_key(|c| c.time);
+            commits
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Parse `git log --format=%h%x09%an%x09%at` output, skipping lines that
+/// don't fit.
+fn parse_log(log: &str) -> Vec<Commit> {
+    log.lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let hash = fields.next()?.trim();
+            let author = fields.next()?;
+            let time = fields.next()?.trim().parse().ok()?;
+            if hash.is_empty() {
+                return None;
+            }
+            let initials: String = author
+                .split_whitespace()
+                .filter_map(|word| word.chars().next())
+                .flat_map(char::to_uppercase)
+                .take(3)
+                .collect();
+            Some(Commit {
+                time,
+                label: format!("{hash} {initials}").trim_end().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// `YYYY-MM-DD` for a Unix time (UTC).
+fn format_date(unix_secs: i64) -> String {
+    // Days to civil date, after Howard Hinnant's `civil_from_days`
+    let days = unix_secs.div_euclid(SECS_PER_DAY as i64) + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+impl Effect for GitRain {
+    fn name(&self) -> &str {
+        "gitrain"
+    }
+
+    fn update(&mut self, delta_time: f64) {
+        self.poll_history();
+        let days = delta_time * self.days_per_sec * self.density_multiplier;
+        self.play(days);
+
+        let height = self.height as f64;
+        for drop in &mut self.drops {
+            drop.y += drop.speed * self.speed_multiplier * delta_time;
+        }
+        // Gone once the label's first character has passed the bottom
+        self.drops
+            .retain(|drop| drop.y - (drop.chars.len() as f64 - 1.0) < height);
+    }
+
+    fn render(&self, buffer: &mut dyn RenderTarget) {
+        let palette = &self.palette;
+        for drop in &self.drops {
+            let len = drop.chars.len();
+            let bottom = drop.y.floor() as i32;
+            for (i, &ch) in drop.chars.iter().enumerate() {
+                let row = bottom - (len - 1 - i) as i32;
+                if ch == ' ' || row < 0 || row >= self.height as i32 {
+                    continue;
+                }
+                let fg = if i == len - 1 {
+                    palette.head
+                } else {
+                    lerp_color(
+                        palette.tail,
+                        palette.body_bright,
+                        (i + 1) as f32 / len as f32,
+                    )
+                };
+                buffer.set_cell(drop.x, row as u16, ch, fg, palette.background);
+            }
+        }
+
+        // Where the timeline is
+        if !self.commits.is_empty() && self.height > 0 {
+            let date = format_date(self.clock as i64);
+            if let Some(x) = self.width.checked_sub(date.len() as u16 + 1) {
+                for (i, ch) in date.chars().enumerate() {
+                    buffer.set_cell(
+                        x + i as u16,
+                        self.height - 1,
+                        ch,
+                        palette.body_mid,
+                        palette.background,
+                    );
+                }
+            }
+        }
+    }
+
+    fn resize(&mut self, width: u16, height: u16) {
+        self.width = width;
+        self.height = height;
+        self.drops.retain(|drop| drop.x < width);
+    }
+
+    fn set_speed(&mut self, multiplier: f64) {
+        self.speed_multiplier = multiplier;
+    }
+
+    fn speed(&self) -> f64 {
+        self.speed_multiplier
+    }
+
+    fn set_density(&mut self, multiplier: f64) {
+        self.density_multiplier = multiplier;
+    }
+
+    fn density(&self) -> f64 {
+        self.density_multiplier
+    }
+
+    fn set_palette(&mut self, palette: &Palette) {
+        self.palette = palette.clone();
+    }
+
+    fn active_columns(&self) -> Option<usize> {
+        Some(self.drops.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Cli, ConfigFile};
+    use clap::Parser;
+
+    #[test]
+    fn parses_the_log_into_labels() {
+        let log = "a1b2c3d\tAda Lovelace\t1700000000\n\
+                   e4f5a6b\tgrace brewster murray hopper\t1700086400\n\
+                   junk line\n\
+                   0fedcba\t\t1699913600\n";
+        let commits = parse_log(log);
+        assert_eq!(commits.len(), 3);
+        assert_eq!(commits[0].label, "a1b2c3d AL");
+        assert_eq!(commits[1].label, "e4f5a6b GBM");
+        assert_eq!(commits[2].label, "0fedcba");
+        assert_eq!(commits[0].time, 1_700_000_000);
+    }
+
+    #[test]
+    fn formats_dates_in_utc() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_700_000_000), "2023-11-14");
+    }
+
+    #[test]
+    fn the_history_is_read_once_in_the_background_and_shared() {
+        let repo = Path::new(".");
+        let shared = history(repo);
+        assert!(Arc::ptr_eq(&shared, &history(repo)));
+        let start = std::time::Instant::now();
+        while shared.get().is_none() && start.elapsed().as_secs() < 30 {
+            thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let commits = shared.get().expect("the log should have been read");
+
+        // A gitrain made afterwards plays it from the start
+        let config = Config::resolve(&Cli::parse_from(["digital_rain"]), &ConfigFile::default());
+        let effect = GitRain::with_config(40, 20, &config, &EffectSpec::new("gitrain"));
+        assert!(effect.loading.is_none());
+        assert!(Arc::ptr_eq(&effect.commits, commits));
+    }
+
+    #[test]
+    fn busy_days_burst_and_the_timeline_starts_over() {
+        let config = Config::resolve(&Cli::parse_from(["digital_rain"]), &ConfigFile::default());
+        let spec = EffectSpec::new("gitrain").with("pace", 1.0);
+        let mut effect = GitRain::with_config(40, 200, &config, &spec);
+        effect.loading = None;
+        let day = SECS_PER_DAY as i64;
+        effect.commits = parse_log(&format!(
+            "aaaaaaa\tA\t{}\nbbbbbbb\tB\t{}\nccccccc\tC\t{}\nddddddd\tD\t{}\n",
+            day * 10,
+            day * 12,
+            day * 12 + 60,
+            day * 12 + 120,
+        ))
+        .into();
+        effect.clock = (day * 10) as f64;
+
+        effect.update(0.5);
+        assert_eq!(effect.drops.len(), 1);
+        // Day 12's three commits land together
+        effect.update(1.6);
+        assert_eq!(effect.drops.len(), 4);
+        assert_eq!(effect.next, 4);
+
+        // The drops fall away, then playback restarts from the first commit
+        for _ in 0..1000 {
+            if effect.next != 4 {
+                break;
+            }
+            effect.update(0.1);
+        }
+        assert!(effect.drops.is_empty());
+        assert_eq!(effect.next, 0);
+        assert_eq!(format_date(effect.clock as i64), "1970-01-11");
+    }
+}
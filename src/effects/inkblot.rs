@@ -0,0 +1,293 @@
+//! Inkblot effect: slowly morphing Rorschach blots.
+//!
+//! Smooth noise, weighted toward the middle of the screen, is thresholded
+//! into ink and mirrored about the vertical center line, so every blot is
+//! symmetric left to right. The noise drifts through time, so the blots
+//! swell, split, and merge. Each cell is two pixels tall, drawn with half
+//! blocks (`▀`/`▄`), and the ink takes the palette's darker tones, deeper
+//! inside a blot.
+
+use rand::RngExt;
+
+use super::Effect;
+use super::params::{EffectSpec, ParamSpec};
+use crate::buffer::RenderTarget;
+use crate::color::Color;
+use crate::color::gradient::lerp_color;
+use crate::color::palette::{Palette, palette_by_name};
+use crate::config::Config;
+use crate::rng::{self, SimRng, Stream};
+
+/// Tunables for `--param`.
+pub const PARAMS: &[ParamSpec] = &[
+    ParamSpec {
+        name: "morph",
+        description: "How fast the blots change shape",
+        default: 0.08,
+        min: 0.0,
+        max: 1.0,
+    },
+    ParamSpec {
+        name: "ink",
+        description: "How much of the screen the blots cover",
+        default: 0.5,
+        min: 0.1,
+        max: 1.0,
+    },
+];
+
+/// Size of the noise's features, as a share of the screen width.
+const FEATURE_SIZE: f64 = 0.12;
+/// Noise octaves, each half the size and strength of the last.
+const OCTAVES: usize = 3;
+/// How much ink past the threshold reaches the deepest tone.
+const DEPTH_RANGE: f32 = 0.25;
+
+/// Smooth 3D value noise over a seeded lattice, in 0.0-1.0.
+struct ValueNoise {
+    perm: Vec<u8>,
+}
+
+impl ValueNoise {
+    fn new(rng: &mut SimRng) -> Self {
+        let mut perm: Vec<u8> = (0..=255).collect();
+        for i in (1..perm.len()).rev() {
+            perm.swap(i, rng.random_range(0..=i));
+        }
+        Self { perm }
+    }
+
+    /// The lattice value at an integer point.
+    fn lattice(&self, x: i64, y: i64, z: i64) -> f64 {
+        let p = |i: i64| self.perm[(i & 255) as usize] as i64;
+        p(x + p(y + p(z))) as f64 / 255.0
+    }
+
+    fn sample(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+        let fade = |t: f64| t * t * (3.0 - 2.0 * t);
+        let (tx, ty, tz) = (fade(x - x0), fade(y - y0), fade(z - z0));
+        let (x0, y0, z0) = (x0 as i64, y0 as i64, z0 as i64);
+        let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+        let plane = |z: i64| {
+            let top = lerp(self.lattice(x0, y0, z), self.lattice(x0 + 1, y0, z), tx);
+            let bottom = lerp(
+                self.lattice(x0, y0 + 1, z),
+                self.lattice(x0 + 1, y0 + 1, z),
+                tx,
+            );
+            lerp(top, bottom, ty)
+        };
+        lerp(plane(z0), plane(z0 + 1), tz)
+    }
+
+    /// Several octaves summed and scaled back to 0.0-1.0.
+    fn fractal(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (mut total, mut weight, mut scale) = (0.0, 0.0, 1.0);
+        for octave in 0..OCTAVES {
+            let strength = 0.5f64.powi(octave as i32);
+            total += strength * self.sample(x * scale, y * scale, z + octave as f64 * 17.0);
+            weight += strength;
+            scale *= 2.0;
+        }
+        total / weight
+    }
+}
+
+/// Symmetric ink blots, morphing over time.
+pub struct InkblotEffect {
+    noise: ValueNoise,
+    /// How far past the threshold each pixel's ink is (0.0 = bare),
+    /// `width` x `height * 2`, row-major
+    ink: Vec<f32>,
+    /// Position along the noise's time axis
+    time: f64,
+    /// Noise units per second (`--param morph`)
+    morph: f64,
+    /// Coverage before the density multiplier (`--param ink`)
+    coverage: f64,
+    density_multiplier: f64,
+    speed_multiplier: f64,
+    palette: Palette,
+    width: u16,
+    height: u16,
+}
+
+impl InkblotEffect {
+    pub fn with_config(width: u16, height: u16, config: &Config, spec: &EffectSpec) -> Self {
+        let mut rng = rng::make_rng(config.seed, Stream::Inkblot);
+        let mut effect = Self {
+            noise: ValueNoise::new(&mut rng),
+            ink: Vec::new(),
+            // Start somewhere different on every seed
+            time: rng.random_range(0.0..1000.0),
+            morph: spec.get("morph"),
+            coverage: spec.get("ink"),
+            density_multiplier: config.density_multiplier,
+            speed_multiplier: config.speed_multiplier,
+            palette: palette_by_name(&config.palette_name),
+            width,
+            height,
+        };
+        effect.trace();
+        effect
+    }
+
+    /// Recompute the ink for the current time, mirroring the left half
+    /// onto the right.
+    fn trace(&mut self) {
+        let (width, rows) = (self.width as usize, self.height as usize * 2);
+        self.ink.clear();
+        self.ink.resize(width * rows, 0.0);
+        if width == 0 || rows == 0 {
+            return;
+        }
+
+        let coverage = (self.coverage * self.density_multiplier).clamp(0.05, 1.0);
+        let threshold = 0.8 - 0.5 * coverage;
+        let feature = (width as f64 * FEATURE_SIZE).max(2.0);
+        let center = (width as f64 / 2.0, rows as f64 / 2.0);
+        for py in 0..rows {
+            for px in 0..width.div_ceil(2) {
+                // Distance from the fold, so both halves sample the same noise
+                let from_fold = center.0 - (px as f64 + 0.5);
+                let noise = self
+                    .noise
+                    .fractal(from_fold / feature, py as f64 / feature, self.time);
+                // Ink pools toward the middle and dries up toward the edges
+                let dx = from_fold / center.0;
+                let dy = (py as f64 + 0.5 - center.1) / center.1;
+                let pool = 1.0 - (dx * dx * 0.8 + dy * dy);
+                let ink = (3.0 * (noise - 0.5) + 0.9 * pool - threshold) as f32;
+                if ink > 0.0 {
+                    self.ink[py * width + px] = ink;
+                    self.ink[py * width + (width - 1 - px)] = ink;
+                }
+            }
+        }
+    }
+
+    /// The color of a pixel's ink, or `None` for bare paper.
+    fn ink_color(&self, x: usize, py: usize) -> Option<Color> {
+        let ink = self.ink[py * self.width as usize + x];
+        (ink > 0.0).then(|| {
+            lerp_color(
+                self.palette.tail,
+                self.palette.body_mid,
+                (ink / DEPTH_RANGE).min(1.0),
+            )
+        })
+    }
+}
+
+impl Effect for InkblotEffect {
+    fn name(&self) -> &str {
+        "inkblot"
+    }
+
+    fn update(&mut self, delta_time: f64) {
+        self.time += delta_time * self.morph * self.speed_multiplier;
+        self.trace();
+    }
+
+    fn render(&self, buffer: &mut dyn RenderTarget) {
+        let width = (self.width as usize).min(buffer.width() as usize);
+        let height = (self.height as usize).min(buffer.height() as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let top = self.ink_color(x, y * 2);
+                let bottom = self.ink_color(x, y * 2 + 1);
+                let (ch, fg, bg) = match (top, bottom) {
+                    (Some(top), Some(bottom)) => ('▀', top, bottom),
+                    (Some(top), None) => ('▀', top, Color::Reset),
+                    (None, Some(bottom)) => ('▄', bottom, Color::Reset),
+                    (None, None) => continue,
+                };
+                buffer.set_cell(x as u16, y as u16, ch, fg, bg);
+            }
+        }
+    }
+
+    fn resize(&mut self, width: u16, height: u16) {
+        self.width = width;
+        self.height = height;
+        self.trace();
+    }
+
+    fn set_speed(&mut self, multiplier: f64) {
+        self.speed_multiplier = multiplier;
+    }
+
+    fn speed(&self) -> f64 {
+        self.speed_multiplier
+    }
+
+    fn set_density(&mut self, multiplier: f64) {
+        self.density_multiplier = multiplier;
+        self.trace();
+    }
+
+    fn density(&self) -> f64 {
+        self.density_multiplier
+    }
+
+    fn set_palette(&mut self, palette: &Palette) {
+        self.palette = palette.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::buffer::ScreenBuffer;
+    use crate::config::{Cli, ConfigFile};
+
+    fn inkblot(width: u16, height: u16) -> InkblotEffect {
+        let cli = Cli::parse_from(["digital_rain", "--seed", "11"]);
+        let config = Config::resolve(&cli, &ConfigFile::default());
+        let spec = EffectSpec::new("inkblot").with("morph", 0.5);
+        InkblotEffect::with_config(width, height, &config, &spec)
+    }
+
+    #[test]
+    fn blots_are_mirrored_about_the_center() {
+        for width in [40, 41] {
+            let mut effect = inkblot(width, 16);
+            effect.update(0.5);
+            let mut buffer = ScreenBuffer::new(width, 16);
+            effect.render(&mut buffer);
+
+            let cells = buffer.cells();
+            let inked = cells.iter().filter(|c| c.ch != ' ').count();
+            assert!(inked > 20 && inked < cells.len(), "{} inked", inked);
+            for y in 0..16 {
+                for x in 0..width / 2 {
+                    let (a, b) = (
+                        buffer.get_cell(x, y).unwrap(),
+                        buffer.get_cell(width - 1 - x, y).unwrap(),
+                    );
+                    assert!(a == b, "({}, {}) isn't mirrored", x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn blots_morph_and_follow_the_density() {
+        let mut effect = inkblot(40, 16);
+        let before = effect.ink.clone();
+        for _ in 0..30 {
+            effect.update(1.0 / 30.0);
+        }
+        assert_ne!(effect.ink, before);
+
+        let count = |effect: &InkblotEffect| effect.ink.iter().filter(|&&i| i > 0.0).count();
+        let normal = count(&effect);
+        effect.set_density(0.3);
+        assert!(count(&effect) < normal);
+        effect.set_density(3.0);
+        assert!(count(&effect) > normal);
+    }
+}
@@ -0,0 +1,234 @@
+//! Intro effect: the cinematic "Wake up, Neo..." boot sequence (`--intro`).
+//!
+//! A scripted pre-roll rather than a selectable effect. Call-trace lines
+//! scroll in at terminal speed, then each message is typed out one
+//! character at a time behind a blinking block cursor, held, and cleared.
+//! The main loop dissolves into the selected effect once `is_finished()`.
+
+use super::Effect;
+use crate::buffer::RenderTarget;
+use crate::color::Color;
+use crate::color::palette::palette_by_name;
+
+/// Cursor blink period in seconds (on for half, off for half).
+const BLINK_PERIOD: f64 = 1.0;
+/// Typing speed for the call-trace lines, in characters per second.
+const TRACE_CPS: f64 = 60.0;
+/// Typing speed for the messages, in characters per second.
+const MESSAGE_CPS: f64 = 12.0;
+
+/// One step of the intro script.
+enum Step {
+    /// Type a line at `cps` characters per second
+    Type(&'static str, f64),
+    /// Leave the screen as is for a number of seconds
+    Hold(f64),
+    /// Blank the screen
+    Clear,
+}
+
+/// The boot sequence, in order.
+const SCRIPT: &[Step] = &[
+    Step::Type(
+        "Call trans opt: received. 2-19-98 13:24:18 REC:Log>",
+        TRACE_CPS,
+    ),
+    Step::Type("Trace program: running", TRACE_CPS),
+    Step::Hold(1.5),
+    Step::Clear,
+    Step::Type("Wake up, Neo...", MESSAGE_CPS),
+    Step::Hold(2.0),
+    Step::Clear,
+    Step::Type("The Matrix has you...", MESSAGE_CPS),
+    Step::Hold(2.0),
+    Step::Clear,
+    Step::Type("Follow the white rabbit.", MESSAGE_CPS),
+    Step::Hold(2.0),
+    Step::Clear,
+    Step::Type("Knock, knock, Neo.", MESSAGE_CPS),
+    Step::Hold(1.5),
+];
+
+/// Scripted boot sequence drawn in the active palette's colors.
+pub struct IntroEffect {
+    width: u16,
+    height: u16,
+    /// Index of the current script step
+    step: usize,
+    /// Seconds spent in the current step
+    step_time: f64,
+    /// Total running time, for the cursor blink
+    time: f64,
+    /// Fully typed lines on screen
+    lines: Vec<&'static str>,
+    /// Characters typed so far of the line in progress
+    typed: usize,
+    text_color: Color,
+    cursor_color: Color,
+}
+
+impl IntroEffect {
+    pub fn with_config(width: u16, height: u16, config: &crate::config::Config) -> Self {
+        let palette = palette_by_name(&config.palette_name);
+        Self {
+            width,
+            height,
+            step: 0,
+            step_time: 0.0,
+            time: 0.0,
+            lines: Vec::new(),
+            typed: 0,
+            text_color: palette.body_bright,
+            cursor_color: palette.head,
+        }
+    }
+
+    /// Whether the whole script has played.
+    pub fn is_finished(&self) -> bool {
+        self.step >= SCRIPT.len()
+    }
+
+    /// Move to the next script step.
+    fn advance(&mut self) {
+        self.step += 1;
+        self.step_time = 0.0;
+        self.typed = 0;
+    }
+}
+
+impl Effect for IntroEffect {
+    fn name(&self) -> &str {
+        "intro"
+    }
+
+    fn update(&mut self, delta_time: f64) {
+        self.time += delta_time;
+        let mut budget = delta_time;
+
+        // A long frame may complete several steps; carry leftover time over
+        while budget > 0.0 && !self.is_finished() {
+            self.step_time += budget;
+            budget = 0.0;
+            match SCRIPT[self.step] {
+                Step::Type(text, cps) => {
+                    let len = text.chars().count();
+                    self.typed = ((self.step_time * cps) as usize).min(len);
+                    if self.typed == len {
+                        budget = self.step_time - len as f64 / cps;
+                        self.lines.push(text);
+                        self.advance();
+                    }
+                }
+                Step::Hold(secs) => {
+                    if self.step_time >= secs {
+                        budget = self.step_time - secs;
+                        self.advance();
+                    }
+                }
+                Step::Clear => {
+                    budget = self.step_time;
+                    self.lines.clear();
+                    self.advance();
+                }
+            }
+        }
+    }
+
+    fn render(&self, buffer: &mut dyn RenderTarget) {
+        if self.width < 4 || self.height < 2 {
+            return;
+        }
+
+        // Text starts one cell in from the top-left, like a terminal prompt
+        let draw = |buffer: &mut dyn RenderTarget, row: usize, text: &str| {
+            for (i, ch) in text.chars().enumerate() {
+                let x = 1 + i as u16;
+                if x >= self.width {
+                    break;
+                }
+                buffer.set_cell(x, 1 + row as u16, ch, self.text_color, Color::Reset);
+            }
+        };
+
+        for (row, line) in self.lines.iter().enumerate() {
+            draw(buffer, row, line);
+        }
+
+        // The line being typed (if any) and the cursor after it
+        let row = self.lines.len();
+        let cursor_x = match SCRIPT.get(self.step) {
+            Some(Step::Type(text, _)) => {
+                let partial: String = text.chars().take(self.typed).collect();
+                draw(buffer, row, &partial);
+                self.typed
+            }
+            _ => 0,
+        };
+        let cursor_row = 1 + row as u16;
+        let cursor_x = 1 + cursor_x as u16;
+        if self.time % BLINK_PERIOD < BLINK_PERIOD / 2.0
+            && cursor_x < self.width
+            && cursor_row < self.height
+        {
+            buffer.set_cell(cursor_x, cursor_row, '█', self.cursor_color, Color::Reset);
+        }
+    }
+
+    fn resize(&mut self, width: u16, height: u16) {
+        self.width = width;
+        self.height = height;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::ScreenBuffer;
+    use crate::config::{Cli, Config, ConfigFile};
+    use clap::Parser;
+
+    fn intro() -> IntroEffect {
+        let cli = Cli::parse_from(["digital_rain"]);
+        IntroEffect::with_config(60, 10, &Config::resolve(&cli, &ConfigFile::default()))
+    }
+
+    fn row_text(buffer: &ScreenBuffer, y: u16) -> String {
+        (0..buffer.width())
+            .map(|x| buffer.get_cell(x, y).unwrap().ch)
+            .collect::<String>()
+            .trim()
+            .to_string()
+    }
+
+    #[test]
+    fn types_trace_lines_then_messages() {
+        let mut intro = intro();
+        let mut buffer = ScreenBuffer::new(60, 10);
+
+        // After a second and a half both trace lines are on screen
+        for _ in 0..45 {
+            intro.update(1.0 / 30.0);
+        }
+        intro.render(&mut buffer);
+        assert!(row_text(&buffer, 1).starts_with("Call trans opt"));
+        assert!(row_text(&buffer, 2).starts_with("Trace program: running"));
+
+        // Then the screen clears and the first message types out
+        for _ in 0..90 {
+            intro.update(1.0 / 30.0);
+        }
+        buffer.clear();
+        intro.render(&mut buffer);
+        assert!(row_text(&buffer, 1).starts_with("Wake up"));
+        assert!(!intro.is_finished());
+    }
+
+    #[test]
+    fn finishes_even_with_one_huge_frame() {
+        let mut intro = intro();
+        intro.update(1000.0);
+        assert!(intro.is_finished());
+        // Rendering a finished intro is harmless
+        intro.render(&mut ScreenBuffer::new(60, 10));
+    }
+}
@@ -6,14 +6,27 @@
 pub mod binary;
 pub mod cascade;
 pub mod classic;
+pub mod corruption;
 pub mod fire;
+pub mod ghostwriter;
+pub mod gitrain;
 pub mod glitch;
+pub mod inkblot;
+pub mod intro;
 pub mod ocean;
 pub mod parallax;
+pub mod params;
 pub mod pulse;
+pub mod pulseline;
 pub mod registry;
+pub mod spectrum;
+pub mod thunderstorm;
+pub mod timefall;
 
-use crate::buffer::ScreenBuffer;
+use crate::buffer::{Region, RenderTarget};
+use crate::color::palette::Palette;
+use crate::event::Event;
+use crate::rain::chars::CharacterPool;
 
 /// The core trait that all visual effects implement.
 ///
@@ -29,7 +42,7 @@ pub trait Effect {
     fn update(&mut self, delta_time: f64);
 
     /// Draw the current state into the screen buffer.
-    fn render(&self, buffer: &mut ScreenBuffer);
+    fn render(&self, buffer: &mut dyn RenderTarget);
 
     /// Handle a terminal resize.
     fn resize(&mut self, width: u16, height: u16);
@@ -49,4 +62,30 @@ pub trait Effect {
     fn density(&self) -> f64 {
         1.0
     }
+
+    /// Set the chance a rain character changes each frame (0.0 = static
+    /// glyphs). Default no-op for effects without rain columns.
+    fn set_mutation(&mut self, _rate: f64) {}
+
+    /// Replace the screen areas rain stays out of (`--dead-zone`, plus the
+    /// banner/logo halo). Default no-op for effects without rain columns.
+    fn set_dead_zones(&mut self, _zones: &[Region]) {}
+
+    /// Swap the color palette in place, without restarting the effect.
+    /// Default no-op for effects with their own fixed colors.
+    fn set_palette(&mut self, _palette: &Palette) {}
+
+    /// Swap the character set in place, keeping the columns already
+    /// falling. Default no-op for effects that don't draw from a charset.
+    fn set_charset(&mut self, _pool: &CharacterPool) {}
+
+    /// React to an outside event, such as a beat in the `--audio` input.
+    /// Default no-op.
+    fn on_event(&mut self, _event: &Event) {}
+
+    /// Number of rain columns currently falling, for the stats overlay.
+    /// Default `None` for effects that aren't column-based.
+    fn active_columns(&self) -> Option<usize> {
+        None
+    }
 }
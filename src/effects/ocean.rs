@@ -3,12 +3,14 @@
 //! Multiple overlapping sine waves at different frequencies create a
 //! water surface with depth shading. Blue palette with white foam at crests.
 
-use crossterm::style::Color;
-use rand::Rng;
+use rand::RngExt;
 
 use super::Effect;
-use crate::buffer::ScreenBuffer;
+use super::params::{EffectSpec, ParamSpec};
+use crate::buffer::RenderTarget;
+use crate::color::Color;
 use crate::color::gradient::lerp_color;
+use crate::rng::{self, Stream};
 
 /// Ocean color palette: deep to shallow
 const DEEP_BLUE: Color = Color::Rgb { r: 0, g: 20, b: 60 };
@@ -36,6 +38,15 @@ const FOAM: Color = Color::Rgb {
 /// Water texture characters ordered by wave height
 const WATER_CHARS: &[char] = &[' ', '.', ',', ';', '~', '=', '#'];
 
+/// Tunables for `--param`.
+pub const PARAMS: &[ParamSpec] = &[ParamSpec {
+    name: "swell",
+    description: "Wave height relative to normal",
+    default: 1.0,
+    min: 0.0,
+    max: 3.0,
+}];
+
 /// Parameters for a single sine wave component.
 struct WaveParams {
     frequency: f64,
@@ -54,32 +65,38 @@ pub struct OceanEffect {
 }
 
 impl OceanEffect {
-    pub fn with_config(width: u16, height: u16, config: &crate::config::Config) -> Self {
-        let mut rng = rand::rng();
+    pub fn with_config(
+        width: u16,
+        height: u16,
+        config: &crate::config::Config,
+        spec: &EffectSpec,
+    ) -> Self {
+        let mut rng = rng::make_rng(config.seed, Stream::Ocean);
+        let swell = spec.get("swell");
 
         // Create 4 overlapping wave components with varied parameters
         let waves = vec![
             WaveParams {
                 frequency: 0.08,
-                amplitude: 3.0,
+                amplitude: 3.0 * swell,
                 speed: 1.2,
                 phase: rng.random_range(0.0..std::f64::consts::TAU),
             },
             WaveParams {
                 frequency: 0.15,
-                amplitude: 1.5,
+                amplitude: 1.5 * swell,
                 speed: -0.8, // counter-direction for realism
                 phase: rng.random_range(0.0..std::f64::consts::TAU),
             },
             WaveParams {
                 frequency: 0.25,
-                amplitude: 0.8,
+                amplitude: 0.8 * swell,
                 speed: 2.0,
                 phase: rng.random_range(0.0..std::f64::consts::TAU),
             },
             WaveParams {
                 frequency: 0.04,
-                amplitude: 5.0, // big slow swell
+                amplitude: 5.0 * swell, // big slow swell
                 speed: 0.5,
                 phase: rng.random_range(0.0..std::f64::consts::TAU),
             },
@@ -128,7 +145,7 @@ impl Effect for OceanEffect {
         self.time += delta_time;
     }
 
-    fn render(&self, buffer: &mut ScreenBuffer) {
+    fn render(&self, buffer: &mut dyn RenderTarget) {
         let mid_y = self.height as f64 * 0.4; // Water surface at ~40% from top
 
         for x in 0..self.width {
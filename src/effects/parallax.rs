@@ -5,10 +5,14 @@
 //! Foreground layers are fast, bright, and dense.
 
 use super::Effect;
-use crate::buffer::ScreenBuffer;
+use crate::buffer::{Region, RenderTarget};
 use crate::color::gradient::scale_color;
+use crate::color::palette::Palette;
 use crate::config::Config;
+use crate::event::Event;
 use crate::rain::RainField;
+use crate::rain::chars::CharacterPool;
+use crate::rng;
 
 /// A single depth layer with its own RainField and brightness.
 struct ParallaxLayer {
@@ -34,18 +38,46 @@ impl ParallaxRain {
 
         let layers = layer_params
             .iter()
-            .map(|&(speed_scale, density_scale, brightness)| {
+            .enumerate()
+            .map(|(i, &(speed_scale, density_scale, brightness))| {
                 let mut layer_config = Config {
                     effect_name: config.effect_name.clone(),
                     speed_multiplier: config.speed_multiplier * speed_scale,
                     density_multiplier: config.density_multiplier * density_scale,
+                    spacing: config.spacing,
+                    mutation_rate: config.mutation_rate,
+                    gust_period: config.gust_period,
+                    gust_strength: config.gust_strength,
+                    highlight_rate: config.highlight_rate,
+                    highlight_color: config.highlight_color.clone(),
                     palette_name: config.palette_name.clone(),
                     charset_name: config.charset_name.clone(),
                     target_fps: config.target_fps,
                     auto_cycle_secs: config.auto_cycle_secs,
                     forward: config.forward,
+                    static_glyphs: config.static_glyphs,
                     crt_enabled: config.crt_enabled,
                     crt_intensity: config.crt_intensity,
+                    crt_curvature: config.crt_curvature,
+                    crt_persistence: config.crt_persistence,
+                    crt_vignette: config.crt_vignette,
+                    crt_vignette_radius: config.crt_vignette_radius,
+                    crt_vignette_strength: config.crt_vignette_strength,
+                    post_filters: config.post_filters.clone(),
+                    dead_zones: config.dead_zones.clone(),
+                    dead_zone_mode: config.dead_zone_mode,
+                    stripes: config.stripes.clone(),
+                    stripe_direction: config.stripe_direction,
+                    // Each layer needs its own stream or they'd rain in lockstep
+                    seed: rng::derive_seed(config.seed, i as u64),
+                    effect_params: config.effect_params.clone(),
+                    react: None,
+                    git_repo: None,
+                    text_file: None,
+                    background: config.background,
+                    brightness: config.brightness,
+                    contrast: config.contrast,
+                    gamma: config.gamma,
                 };
                 // Clamp density
                 layer_config.density_multiplier = layer_config.density_multiplier.clamp(0.1, 10.0);
@@ -75,7 +107,7 @@ impl Effect for ParallaxRain {
         }
     }
 
-    fn render(&self, buffer: &mut ScreenBuffer) {
+    fn render(&self, buffer: &mut dyn RenderTarget) {
         // Render back-to-front: background first, foreground overwrites
         for layer in &self.layers {
             if layer.brightness >= 1.0 {
@@ -150,4 +182,39 @@ impl Effect for ParallaxRain {
             .map(|l| l.rain.density() / 1.2)
             .unwrap_or(1.0)
     }
+
+    fn set_dead_zones(&mut self, zones: &[Region]) {
+        for layer in &mut self.layers {
+            layer.rain.set_dead_zones(zones);
+        }
+    }
+
+    fn set_mutation(&mut self, rate: f64) {
+        for layer in &mut self.layers {
+            layer.rain.set_mutation(rate);
+        }
+    }
+
+    fn set_palette(&mut self, palette: &Palette) {
+        for layer in &mut self.layers {
+            layer.rain.set_palette(palette.clone());
+        }
+    }
+
+    fn set_charset(&mut self, pool: &CharacterPool) {
+        for layer in &mut self.layers {
+            layer.rain.set_charset(pool.clone());
+        }
+    }
+
+    fn on_event(&mut self, event: &Event) {
+        // Log lines fall in the foreground, where they're readable
+        if let Some(front) = self.layers.last_mut() {
+            front.rain.on_event(event);
+        }
+    }
+
+    fn active_columns(&self) -> Option<usize> {
+        Some(self.layers.iter().map(|l| l.rain.column_count()).sum())
+    }
 }
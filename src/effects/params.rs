@@ -0,0 +1,159 @@
+//! Effect parameters: the tunables each effect advertises, and the spec
+//! that carries chosen values into `registry::create_effect`.
+//!
+//! Every effect lists its parameters (name, range, default) in a `PARAMS`
+//! table, so `--list-effects` and the UI can show what's adjustable without
+//! knowing about the effect. Values arrive as `--param NAME=VALUE`; any
+//! parameter left out takes its default.
+
+use std::collections::BTreeMap;
+
+use toml::Value;
+
+/// One tunable number an effect accepts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParamSpec {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub default: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// An effect to create, and the values for its parameters.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EffectSpec {
+    pub name: String,
+    pub params: BTreeMap<String, Value>,
+}
+
+impl EffectSpec {
+    /// `name` with every parameter at its default.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            params: BTreeMap::new(),
+        }
+    }
+
+    /// Set parameter `name` to `value`.
+    pub fn with(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.params.insert(name.to_string(), value.into());
+        self
+    }
+
+    /// Check every value against the effect's parameters: each must be a
+    /// parameter the effect has, a number, and within its range.
+    pub fn validate(&self) -> Result<(), String> {
+        let specs = super::registry::effect_params(&self.name)
+            .ok_or_else(|| format!("unknown effect '{}'", self.name))?;
+        for (name, value) in &self.params {
+            let Some(spec) = specs.iter().find(|s| s.name == name) else {
+                return Err(if specs.is_empty() {
+                    format!("effect '{}' has no parameters", self.name)
+                } else {
+                    format!(
+                        "effect '{}' has no parameter '{name}' (has: {})",
+                        self.name,
+                        specs.iter().map(|s| s.name).collect::<Vec<_>>().join(", ")
+                    )
+                });
+            };
+            let number = as_number(value)
+                .ok_or_else(|| format!("parameter '{name}' must be a number, got {value}"))?;
+            if !(spec.min..=spec.max).contains(&number) {
+                return Err(format!(
+                    "parameter '{name}' must be between {} and {}, got {number}",
+                    spec.min, spec.max
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The value of parameter `name`: the one given (clamped to its range)
+    /// or its default. 0.0 if the effect has no such parameter.
+    pub fn get(&self, name: &str) -> f64 {
+        let Some(spec) = super::registry::effect_params(&self.name)
+            .and_then(|specs| specs.iter().find(|s| s.name == name))
+        else {
+            return 0.0;
+        };
+        self.params
+            .get(name)
+            .and_then(as_number)
+            .map_or(spec.default, |v| v.clamp(spec.min, spec.max))
+    }
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Float(f) => Some(*f),
+        Value::Integer(i) => Some(*i as f64),
+        _ => None,
+    }
+}
+
+/// Parse `--param NAME=VALUE`. VALUE is read as TOML (so `0.5`, `3`, and
+/// `true` keep their types), falling back to a plain string.
+pub fn parse_param(s: &str) -> Result<(String, Value), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=VALUE, got '{s}'"))?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(format!("missing parameter name in '{s}'"));
+    }
+    let value = value.trim();
+    let value = format!("v = {value}")
+        .parse::<toml::Table>()
+        .ok()
+        .and_then(|mut table| table.remove("v"))
+        .unwrap_or_else(|| Value::String(value.to_string()));
+    Ok((name.to_string(), value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_checked_against_the_effect_and_default_when_missing() {
+        let pulse = EffectSpec::new("pulse").with("depth", 0.8);
+        assert_eq!(pulse.validate(), Ok(()));
+        assert_eq!(pulse.get("depth"), 0.8);
+        assert_eq!(pulse.get("rate"), 12.0);
+        assert_eq!(EffectSpec::new("pulse").with("depth", 1).get("depth"), 1.0);
+
+        let errors = [
+            EffectSpec::new("pulse").with("depth", 3.0),
+            EffectSpec::new("pulse").with("depth", "deep"),
+            EffectSpec::new("pulse").with("cooling", 0.1),
+            EffectSpec::new("classic").with("depth", 0.5),
+            EffectSpec::new("nope"),
+        ];
+        let messages: Vec<String> = errors.iter().map(|s| s.validate().unwrap_err()).collect();
+        assert!(messages[0].contains("between 0 and 1"));
+        assert!(messages[1].contains("must be a number"));
+        assert!(messages[2].contains("has: wavelength, depth, rate"));
+        assert_eq!(messages[3], "effect 'classic' has no parameters");
+        assert_eq!(messages[4], "unknown effect 'nope'");
+    }
+
+    #[test]
+    fn param_values_keep_their_toml_types() {
+        assert_eq!(
+            parse_param("depth=0.25"),
+            Ok(("depth".to_string(), Value::Float(0.25)))
+        );
+        assert_eq!(
+            parse_param(" rate = 20"),
+            Ok(("rate".into(), Value::Integer(20)))
+        );
+        assert_eq!(
+            parse_param("mode=wide"),
+            Ok(("mode".into(), Value::from("wide")))
+        );
+        assert!(parse_param("depth").is_err() && parse_param("=1").is_err());
+    }
+}
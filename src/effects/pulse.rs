@@ -5,34 +5,67 @@
 //! breathing feel on top of normal rain.
 
 use super::Effect;
-use crate::buffer::ScreenBuffer;
+use super::params::{EffectSpec, ParamSpec};
+use crate::buffer::{Region, RenderTarget};
 use crate::color::gradient::{color_to_rgb, scale_color};
+use crate::color::palette::Palette;
 use crate::config::Config;
+use crate::event::Event;
 use crate::rain::RainField;
+use crate::rain::chars::CharacterPool;
+
+/// Tunables for `--param`.
+pub const PARAMS: &[ParamSpec] = &[
+    ParamSpec {
+        name: "wavelength",
+        description: "Distance between wave peaks, in screen heights",
+        default: 0.6,
+        min: 0.1,
+        max: 3.0,
+    },
+    ParamSpec {
+        name: "depth",
+        description: "How far the troughs dim the rain",
+        default: 0.5,
+        min: 0.0,
+        max: 1.0,
+    },
+    ParamSpec {
+        name: "rate",
+        description: "Wave speed in rows per second",
+        default: 12.0,
+        min: 1.0,
+        max: 60.0,
+    },
+];
 
 /// Pulse rain: brightness wave sweeping over standard rain.
 pub struct PulseRain {
     rain: RainField,
     /// Current phase of the pulse wave (radians)
     pulse_phase: f64,
-    /// Distance between wave peaks in rows
+    /// Distance between wave peaks in rows, and as a fraction of the height
     pulse_wavelength: f64,
+    wavelength_ratio: f64,
     /// How deep the modulation goes (0.0 = no effect, 1.0 = full dark-to-bright)
     pulse_amplitude: f64,
-    /// Speed of the wave in rows per second
+    /// Speed of the wave in rows per second, and at 1x speed
     pulse_speed: f64,
+    pulse_rate: f64,
     width: u16,
     height: u16,
 }
 
 impl PulseRain {
-    pub fn with_config(width: u16, height: u16, config: &Config) -> Self {
+    pub fn with_config(width: u16, height: u16, config: &Config, spec: &EffectSpec) -> Self {
         Self {
             rain: RainField::with_config(width, height, config),
             pulse_phase: 0.0,
-            pulse_wavelength: height as f64 * 0.6, // ~60% of screen height between peaks
-            pulse_amplitude: 0.5,                  // modulate brightness by up to 50%
-            pulse_speed: 12.0 * config.speed_multiplier,
+            pulse_wavelength: height as f64 * spec.get("wavelength"),
+            wavelength_ratio: spec.get("wavelength"),
+            pulse_amplitude: spec.get("depth"),
+            pulse_speed: spec.get("rate") * config.speed_multiplier,
+            pulse_rate: spec.get("rate"),
             width,
             height,
         }
@@ -50,7 +83,7 @@ impl Effect for PulseRain {
         self.pulse_phase += self.pulse_speed * delta_time;
     }
 
-    fn render(&self, buffer: &mut ScreenBuffer) {
+    fn render(&self, buffer: &mut dyn RenderTarget) {
         // First render the base rain
         self.rain.render(buffer);
 
@@ -87,12 +120,12 @@ impl Effect for PulseRain {
         self.rain.resize(width, height);
         self.width = width;
         self.height = height;
-        self.pulse_wavelength = height as f64 * 0.6;
+        self.pulse_wavelength = height as f64 * self.wavelength_ratio;
     }
 
     fn set_speed(&mut self, multiplier: f64) {
         self.rain.set_speed(multiplier);
-        self.pulse_speed = 12.0 * multiplier;
+        self.pulse_speed = self.pulse_rate * multiplier;
     }
 
     fn speed(&self) -> f64 {
@@ -106,4 +139,28 @@ impl Effect for PulseRain {
     fn density(&self) -> f64 {
         self.rain.density()
     }
+
+    fn set_dead_zones(&mut self, zones: &[Region]) {
+        self.rain.set_dead_zones(zones);
+    }
+
+    fn set_mutation(&mut self, rate: f64) {
+        self.rain.set_mutation(rate);
+    }
+
+    fn set_palette(&mut self, palette: &Palette) {
+        self.rain.set_palette(palette.clone());
+    }
+
+    fn set_charset(&mut self, pool: &CharacterPool) {
+        self.rain.set_charset(pool.clone());
+    }
+
+    fn on_event(&mut self, event: &Event) {
+        self.rain.on_event(event);
+    }
+
+    fn active_columns(&self) -> Option<usize> {
+        Some(self.rain.column_count())
+    }
 }
@@ -0,0 +1,344 @@
+//! Pulseline effect: a heart monitor's ECG trace.
+//!
+//! A synthetic heartbeat (P wave, QRS spike, T wave) scrolls in from the
+//! right, drawn as a line with box-drawing corners, and fades as it ages
+//! toward the left edge. The tracing head glows. The heart rate varies a
+//! little from beat to beat, and can show in the top-right corner. The
+//! speed multiplier speeds up the heart and the paper together.
+
+use std::collections::VecDeque;
+
+use rand::RngExt;
+
+use super::Effect;
+use super::params::{EffectSpec, ParamSpec};
+use crate::buffer::RenderTarget;
+use crate::color::Color;
+use crate::color::gradient::{lerp_color, scale_color};
+use crate::color::palette::{Palette, palette_by_name};
+use crate::config::Config;
+use crate::rng::{self, SimRng, Stream};
+
+/// Tunables for `--param`.
+pub const PARAMS: &[ParamSpec] = &[
+    ParamSpec {
+        name: "bpm",
+        description: "Heart rate, in beats per minute",
+        default: 72.0,
+        min: 30.0,
+        max: 200.0,
+    },
+    ParamSpec {
+        name: "readout",
+        description: "1 shows the heart rate in the top-right corner",
+        default: 1.0,
+        min: 0.0,
+        max: 1.0,
+    },
+];
+
+/// Columns of trace per second, before the speed multiplier
+const COLUMNS_PER_SEC: f64 = 30.0;
+/// Points of the waveform sampled per column, so no spike falls between
+const SAMPLES_PER_COLUMN: usize = 8;
+/// How much each beat's length varies, either way
+const BEAT_JITTER: f64 = 0.05;
+/// Columns behind the head drawn brighter
+const GLOW_COLUMNS: usize = 5;
+/// Columns between the head and the right edge
+const HEAD_MARGIN: u16 = 3;
+/// How long the readout's heart lights up after each beat, in seconds
+const HEART_SECS: f64 = 0.2;
+
+/// The waves of one beat: (height, center, width), in beats.
+const WAVES: [(f64, f64, f64); 5] = [
+    (0.12, 0.20, 0.025),  // P
+    (-0.12, 0.36, 0.010), // Q
+    (1.00, 0.39, 0.012),  // R
+    (-0.25, 0.42, 0.012), // S
+    (0.30, 0.62, 0.045),  // T
+];
+/// Where in the beat the R spike peaks
+const R_PEAK: f64 = 0.39;
+
+/// The trace's height at `phase` (0.0-1.0) through a beat; 0.0 is the
+/// baseline and 1.0 the top of the R spike.
+fn waveform(phase: f64) -> f64 {
+    WAVES
+        .iter()
+        .map(|&(height, center, width)| {
+            let d = (phase - center) / width;
+            height * (-0.5 * d * d).exp()
+        })
+        .sum()
+}
+
+/// A scrolling ECG trace.
+pub struct PulseLine {
+    /// Trace heights, one per column, newest last
+    trace: VecDeque<f64>,
+    /// How far through the current beat (0.0-1.0)
+    phase: f64,
+    /// Columns the current beat lasts
+    beat_columns: f64,
+    /// Columns owed to the trace, carried between frames
+    pending: f64,
+    /// Seconds since the last R spike
+    since_beat: f64,
+    bpm: f64,
+    readout: bool,
+    speed_multiplier: f64,
+    palette: Palette,
+    width: u16,
+    height: u16,
+    rng: SimRng,
+}
+
+impl PulseLine {
+    pub fn with_config(width: u16, height: u16, config: &Config, spec: &EffectSpec) -> Self {
+        let mut effect = Self {
+            trace: VecDeque::new(),
+            phase: 0.0,
+            beat_columns: 1.0,
+            pending: 0.0,
+            since_beat: f64::INFINITY,
+            bpm: spec.get("bpm"),
+            readout: spec.get("readout") >= 0.5,
+            speed_multiplier: config.speed_multiplier,
+            palette: palette_by_name(&config.palette_name),
+            width,
+            height,
+            rng: rng::make_rng(config.seed, Stream::PulseLine),
+        };
+        effect.beat_columns = effect.next_beat_columns();
+        // Start with a screenful of trace, not an empty monitor
+        for _ in 0..effect.capacity() {
+            effect.advance_column();
+        }
+        effect
+    }
+
+    /// Columns of trace on screen, up to and including the head.
+    fn capacity(&self) -> usize {
+        self.width.saturating_sub(HEAD_MARGIN) as usize
+    }
+
+    /// The length of the next beat, in columns, a little off the average.
+    fn next_beat_columns(&mut self) -> f64 {
+        let jitter = 1.0 + self.rng.random_range(-BEAT_JITTER..BEAT_JITTER);
+        (COLUMNS_PER_SEC * 60.0 / self.bpm * jitter).max(1.0)
+    }
+
+    /// The heart rate the current beat works out to.
+    fn current_bpm(&self) -> f64 {
+        COLUMNS_PER_SEC * 60.0 / self.beat_columns * self.speed_multiplier
+    }
+
+    /// Trace one more column: the strongest point of the waveform it
+    /// covers, so the narrow QRS spikes always show.
+    fn advance_column(&mut self) {
+        let step = 1.0 / self.beat_columns;
+        let mut peak: f64 = 0.0;
+        for i in 0..SAMPLES_PER_COLUMN {
+            let value = waveform(self.phase + step * i as f64 / SAMPLES_PER_COLUMN as f64);
+            if value.abs() > peak.abs() {
+                peak = value;
+            }
+        }
+        let before = self.phase;
+        self.phase += step;
+        if before < R_PEAK && self.phase >= R_PEAK {
+            self.since_beat = 0.0;
+        }
+        if self.phase >= 1.0 {
+            self.phase -= 1.0;
+            self.beat_columns = self.next_beat_columns();
+        }
+        self.trace.push_back(peak);
+        while self.trace.len() > self.capacity() {
+            self.trace.pop_front();
+        }
+    }
+
+    /// The screen row for a trace height.
+    fn row(&self, value: f64) -> u16 {
+        let baseline = self.height as f64 * 0.6;
+        let amplitude = self.height as f64 * 0.45;
+        (baseline - value * amplitude)
+            .round()
+            .clamp(0.0, self.height.saturating_sub(1) as f64) as u16
+    }
+
+    /// The trace's color `age` columns behind the head.
+    fn trace_color(&self, age: usize) -> Color {
+        if age < GLOW_COLUMNS {
+            let t = age as f32 / GLOW_COLUMNS as f32;
+            return lerp_color(self.palette.head, self.palette.body_bright, t);
+        }
+        let t = age as f32 / self.trace.len().max(1) as f32;
+        lerp_color(self.palette.body_bright, self.palette.tail, t)
+    }
+
+    fn render_readout(&self, buffer: &mut dyn RenderTarget) {
+        let text = format!("♥ {:.0} BPM", self.current_bpm());
+        let Some(x) = self.width.checked_sub(text.chars().count() as u16 + 1) else {
+            return;
+        };
+        let heart = if self.since_beat < HEART_SECS {
+            self.palette.head
+        } else {
+            self.palette.body_mid
+        };
+        for (i, ch) in text.chars().enumerate() {
+            let fg = if i == 0 { heart } else { self.palette.body_mid };
+            buffer.set_cell(x + i as u16, 0, ch, fg, Color::Reset);
+        }
+    }
+}
+
+impl Effect for PulseLine {
+    fn name(&self) -> &str {
+        "pulseline"
+    }
+
+    fn update(&mut self, delta_time: f64) {
+        let dt = delta_time * self.speed_multiplier;
+        self.since_beat += dt;
+        self.pending += dt * COLUMNS_PER_SEC;
+        while self.pending >= 1.0 {
+            self.pending -= 1.0;
+            self.advance_column();
+        }
+    }
+
+    fn render(&self, buffer: &mut dyn RenderTarget) {
+        if self.trace.is_empty() || self.height == 0 {
+            return;
+        }
+        let left = self.capacity() - self.trace.len();
+        let mut prev = self.row(self.trace[0]);
+        for (i, &value) in self.trace.iter().enumerate() {
+            let x = (left + i) as u16;
+            let y = self.row(value);
+            let fg = self.trace_color(self.trace.len() - 1 - i);
+            // Level, or a vertical stroke from the last row with corners
+            if y == prev {
+                buffer.set_cell(x, y, '─', fg, Color::Reset);
+            } else {
+                let (from, to) = if y < prev {
+                    ('╯', '╭')
+                } else {
+                    ('╮', '╰')
+                };
+                for row in y.min(prev) + 1..y.max(prev) {
+                    buffer.set_cell(x, row, '│', fg, Color::Reset);
+                }
+                buffer.set_cell(x, prev, from, fg, Color::Reset);
+                buffer.set_cell(x, y, to, fg, Color::Reset);
+            }
+            prev = y;
+        }
+
+        // A soft glow around the head
+        let head_x = (self.capacity() - 1) as i32;
+        let glow = scale_color(self.palette.head, 0.25);
+        for dy in -1..=1i32 {
+            for dx in -1..=1i32 {
+                let (x, y) = (head_x + dx, prev as i32 + dy);
+                if x < 0 || y < 0 {
+                    continue;
+                }
+                if let Some(cell) = buffer.get_cell(x as u16, y as u16) {
+                    buffer.set_cell(x as u16, y as u16, cell.ch, cell.fg, glow);
+                }
+            }
+        }
+        buffer.set_cell(head_x as u16, prev, '●', self.palette.head, glow);
+
+        if self.readout {
+            self.render_readout(buffer);
+        }
+    }
+
+    fn resize(&mut self, width: u16, height: u16) {
+        self.width = width;
+        self.height = height;
+        while self.trace.len() > self.capacity() {
+            self.trace.pop_front();
+        }
+    }
+
+    fn set_speed(&mut self, multiplier: f64) {
+        self.speed_multiplier = multiplier;
+    }
+
+    fn speed(&self) -> f64 {
+        self.speed_multiplier
+    }
+
+    fn set_palette(&mut self, palette: &Palette) {
+        self.palette = palette.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::buffer::ScreenBuffer;
+    use crate::config::{Cli, ConfigFile};
+
+    fn pulseline(args: &[&str], spec: EffectSpec) -> PulseLine {
+        let cli = Cli::parse_from([&["digital_rain", "--seed", "5"][..], args].concat());
+        let config = Config::resolve(&cli, &ConfigFile::default());
+        PulseLine::with_config(60, 16, &config, &spec)
+    }
+
+    #[test]
+    fn the_trace_spikes_once_a_beat_and_glows_at_the_head() {
+        let effect = pulseline(&[], EffectSpec::new("pulseline").with("bpm", 60.0));
+        let mut buffer = ScreenBuffer::new(60, 16);
+        effect.render(&mut buffer);
+
+        // At 60 bpm a beat is 30 columns, so two R spikes are on screen
+        let spikes = buffer.cells()[..60 * effect.row(0.5) as usize]
+            .iter()
+            .filter(|c| c.ch == '╭')
+            .count();
+        assert_eq!(spikes, 2, "{}", buffer.to_plain_string());
+        // The baseline is drawn level
+        let baseline = effect.row(0.0);
+        let level = (0..60)
+            .filter(|&x| buffer.get_cell(x, baseline).unwrap().ch == '─')
+            .count();
+        assert!(level > 20);
+
+        let head = buffer.cells().iter().position(|c| c.ch == '●').unwrap() as u16;
+        assert_eq!(head % 60, 60 - HEAD_MARGIN - 1);
+        assert!(buffer.get_cell(head % 60 - 1, head / 60).unwrap().bg != Color::Reset);
+        assert!(buffer.to_plain_string().contains("♥ 6"));
+    }
+
+    #[test]
+    fn speed_scrolls_the_trace_and_the_heart_faster() {
+        let mut normal = pulseline(&[], EffectSpec::new("pulseline").with("readout", 0.0));
+        let mut fast = pulseline(&["--speed", "2"], EffectSpec::new("pulseline"));
+        let (a, b) = (normal.trace.clone(), fast.trace.clone());
+        normal.update(0.5);
+        fast.update(0.5);
+        // A column a frame at 30 fps, or two at double speed
+        let moved = |before: &VecDeque<f64>, after: &PulseLine| {
+            (0..40)
+                .find(|&n| before.iter().skip(n).zip(&after.trace).all(|(x, y)| x == y))
+                .unwrap()
+        };
+        assert_eq!(moved(&a, &normal), 15);
+        assert_eq!(moved(&b, &fast), 30);
+        assert!(fast.current_bpm() > normal.current_bpm() * 1.7);
+
+        let mut buffer = ScreenBuffer::new(60, 16);
+        normal.render(&mut buffer);
+        assert!(!buffer.to_plain_string().contains("BPM"));
+    }
+}
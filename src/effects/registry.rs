@@ -5,16 +5,43 @@ use super::binary::BinaryRain;
 use super::cascade::CascadeRain;
 use super::classic::ClassicRain;
 use super::fire::FireEffect;
+use super::ghostwriter::Ghostwriter;
+use super::gitrain::GitRain;
 use super::glitch::GlitchRain;
+use super::inkblot::InkblotEffect;
 use super::ocean::OceanEffect;
 use super::parallax::ParallaxRain;
+use super::params::{EffectSpec, ParamSpec};
 use super::pulse::PulseRain;
+use super::pulseline::PulseLine;
+use super::spectrum::SpectrumEffect;
+use super::thunderstorm::Thunderstorm;
+use super::timefall::Timefall;
+use super::{
+    binary, cascade, fire, ghostwriter, gitrain, glitch, inkblot, ocean, pulse, pulseline,
+    spectrum, thunderstorm, timefall,
+};
 use crate::config::Config;
+use crate::react::Reactive;
 
 /// Returns the list of available effect names.
 pub fn effect_names() -> &'static [&'static str] {
     &[
-        "classic", "binary", "cascade", "pulse", "glitch", "fire", "ocean", "parallax",
+        "classic",
+        "binary",
+        "cascade",
+        "pulse",
+        "glitch",
+        "fire",
+        "ocean",
+        "parallax",
+        "spectrum",
+        "gitrain",
+        "thunderstorm",
+        "timefall",
+        "inkblot",
+        "ghostwriter",
+        "pulseline",
     ]
 }
 
@@ -26,38 +53,108 @@ pub fn next_effect_name(current: &str) -> &'static str {
     names[next_idx]
 }
 
-/// Create an effect by name, using the provided config and screen dimensions.
-/// Returns None if the effect name is unknown.
+/// Get the effect name before the given name in the cycle (wraps around).
+pub fn prev_effect_name(current: &str) -> &'static str {
+    let names = effect_names();
+    let current_idx = names.iter().position(|&n| n == current).unwrap_or(0);
+    let prev_idx = (current_idx + names.len() - 1) % names.len();
+    names[prev_idx]
+}
+
+/// Get the effect at a 0-based position in the registry, if there is one.
+pub fn effect_name_at(index: usize) -> Option<&'static str> {
+    effect_names().get(index).copied()
+}
+
+/// The parameters an effect can be tuned with (`--param`), or `None` if
+/// there's no effect by that name.
+pub fn effect_params(name: &str) -> Option<&'static [ParamSpec]> {
+    match name {
+        "classic" | "parallax" => Some(&[]),
+        "binary" => Some(binary::PARAMS),
+        "cascade" => Some(cascade::PARAMS),
+        "pulse" => Some(pulse::PARAMS),
+        "glitch" => Some(glitch::PARAMS),
+        "fire" => Some(fire::PARAMS),
+        "ocean" => Some(ocean::PARAMS),
+        "spectrum" => Some(spectrum::PARAMS),
+        "gitrain" => Some(gitrain::PARAMS),
+        "thunderstorm" => Some(thunderstorm::PARAMS),
+        "timefall" => Some(timefall::PARAMS),
+        "inkblot" => Some(inkblot::PARAMS),
+        "ghostwriter" => Some(ghostwriter::PARAMS),
+        "pulseline" => Some(pulseline::PARAMS),
+        _ => None,
+    }
+}
+
+/// Create the effect `spec` describes, using the provided config and screen
+/// dimensions, wrapped to follow system load under `--react`. Fails for an
+/// unknown effect or a bad parameter.
 pub fn create_effect(
-    name: &str,
+    spec: &EffectSpec,
     width: u16,
     height: u16,
     config: &Config,
-) -> Option<Box<dyn Effect>> {
-    match name {
-        "classic" => Some(Box::new(ClassicRain::with_config(width, height, config))),
-        "binary" => Some(Box::new(BinaryRain::with_config(width, height, config))),
-        "cascade" => Some(Box::new(CascadeRain::with_config(width, height, config))),
-        "pulse" => Some(Box::new(PulseRain::with_config(width, height, config))),
-        "glitch" => Some(Box::new(GlitchRain::with_config(width, height, config))),
-        "fire" => Some(Box::new(FireEffect::with_config(width, height, config))),
-        "ocean" => Some(Box::new(OceanEffect::with_config(width, height, config))),
-        "parallax" => Some(Box::new(ParallaxRain::with_config(width, height, config))),
-        _ => None,
-    }
+) -> Result<Box<dyn Effect>, String> {
+    spec.validate()?;
+    let effect: Box<dyn Effect> = match spec.name.as_str() {
+        "classic" => Box::new(ClassicRain::with_config(width, height, config)),
+        "binary" => Box::new(BinaryRain::with_config(width, height, config, spec)),
+        "cascade" => Box::new(CascadeRain::with_config(width, height, config, spec)),
+        "pulse" => Box::new(PulseRain::with_config(width, height, config, spec)),
+        "glitch" => Box::new(GlitchRain::with_config(width, height, config, spec)),
+        "fire" => Box::new(FireEffect::with_config(width, height, config, spec)),
+        "ocean" => Box::new(OceanEffect::with_config(width, height, config, spec)),
+        "parallax" => Box::new(ParallaxRain::with_config(width, height, config)),
+        "spectrum" => Box::new(SpectrumEffect::with_config(width, height, config, spec)),
+        "gitrain" => Box::new(GitRain::with_config(width, height, config, spec)),
+        "thunderstorm" => Box::new(Thunderstorm::with_config(width, height, config, spec)),
+        "timefall" => Box::new(Timefall::with_config(width, height, config, spec)),
+        "inkblot" => Box::new(InkblotEffect::with_config(width, height, config, spec)),
+        "ghostwriter" => Box::new(Ghostwriter::with_config(width, height, config, spec)),
+        "pulseline" => Box::new(PulseLine::with_config(width, height, config, spec)),
+        name => return Err(format!("unknown effect '{name}'")),
+    };
+    Ok(match config.react {
+        Some(_) => Box::new(Reactive::new(effect)),
+        None => effect,
+    })
 }
 
-/// Print available effects to stdout (for --list-effects).
+/// Print available effects to stdout (for --list-effects), each with the
+/// parameters it takes.
 pub fn print_effects() {
     println!("Available effects:");
-    println!("  classic    - Classic Matrix digital rain");
-    println!("  binary     - Dense binary 0/1 data stream");
-    println!("  cascade    - Wave-front column spawning (curtain effect)");
-    println!("  pulse      - Rain with brightness wave overlay");
-    println!("  glitch     - Rain with digital corruption events");
-    println!("  fire       - Classic cellular automata fire simulation");
-    println!("  ocean      - Sine-wave water surface simulation");
-    println!("  parallax   - Multi-layer rain with depth (foreground/background)");
+    for name in effect_names() {
+        let desc = match *name {
+            "classic" => "Classic Matrix digital rain",
+            "binary" => "Dense binary 0/1 data stream",
+            "cascade" => "Wave-front column spawning (curtain effect)",
+            "pulse" => "Rain with brightness wave overlay",
+            "glitch" => "Rain with digital corruption events",
+            "fire" => "Classic cellular automata fire simulation",
+            "ocean" => "Sine-wave water surface simulation",
+            "parallax" => "Multi-layer rain with depth (foreground/background)",
+            "spectrum" => "Audio spectrum bars or waterfall (with --audio)",
+            "gitrain" => "A git repository's commit history as rain (--git-repo)",
+            "thunderstorm" => "Wind-blown downpour with lightning, flashes, and thunder shake",
+            "timefall" => "A clock in huge digits drawn by the rain, dissolving each minute",
+            "inkblot" => "Slowly morphing, mirror-symmetric Rorschach ink blots",
+            "ghostwriter" => "An invisible typist at the terminal, typos and all (--text-file)",
+            "pulseline" => "A scrolling ECG heartbeat trace with a glowing head",
+            _ => "",
+        };
+        println!("  {:<12} - {}", name, desc);
+        for param in effect_params(name).unwrap_or_default() {
+            println!(
+                "      {:<12} {} ({}-{}, default {})",
+                param.name, param.description, param.min, param.max, param.default
+            );
+        }
+    }
+    println!();
+    println!("Tune a parameter with --param NAME=VALUE, e.g. -e pulse --param depth=0.8");
 }
 
 /// Print available color palettes to stdout (for --list-colors).
@@ -113,15 +210,72 @@ pub fn print_palettes() {
 pub fn print_charsets() {
     println!("Available character sets:");
     for name in crate::rain::chars::charset_names() {
-        let desc = match *name {
+        let custom;
+        let desc = match name {
             "matrix" => "Half-width katakana + digits + symbols (film-authentic)",
             "ascii" => "Full printable ASCII characters",
             "binary" => "0 and 1 only",
             "digits" => "0-9 only",
             "katakana" => "Half-width katakana only",
             "latin" => "A-Z, a-z letters",
-            _ => "",
+            "greek" => "Greek capitals and small letters",
+            "cyrillic" => "Cyrillic capitals and small letters",
+            "hebrew" => "Hebrew alphabet",
+            "runes" => "Elder Futhark runes",
+            "box" => "Box-drawing lines and corners",
+            "blocks" => "Block elements and shades",
+            _ => {
+                let pool = crate::rain::chars::charset_by_name(name);
+                custom = format!("Custom: {}", pool.sample(pool.len().min(24)));
+                &custom
+            }
         };
         println!("  {:<12} - {}", name, desc);
     }
+    if !crate::rain::chars::unicode_terminal() {
+        println!();
+        println!("No UTF-8 locale detected: greek, cyrillic, and hebrew fall back to latin;");
+        println!("runes, box, and blocks to ASCII lines.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cycles_both_ways_and_looks_up_by_index() {
+        assert_eq!(next_effect_name("classic"), "binary");
+        assert_eq!(prev_effect_name("binary"), "classic");
+        assert_eq!(prev_effect_name("classic"), "pulseline");
+        assert_eq!(next_effect_name(prev_effect_name("fire")), "fire");
+        assert_eq!(effect_name_at(0), Some("classic"));
+        assert_eq!(effect_name_at(effect_names().len()), None);
+    }
+
+    #[test]
+    fn every_effect_advertises_sane_params_and_builds_from_a_spec() {
+        use crate::config::{Cli, ConfigFile};
+        use clap::Parser;
+
+        let config = Config::resolve(&Cli::parse_from(["digital_rain"]), &ConfigFile::default());
+        for name in effect_names() {
+            let params = effect_params(name).unwrap();
+            assert!(
+                params
+                    .iter()
+                    .all(|p| p.min <= p.default && p.default <= p.max),
+                "{name}"
+            );
+            let mut spec = EffectSpec::new(name);
+            for param in params {
+                spec = spec.with(param.name, param.max);
+            }
+            let effect = create_effect(&spec, 20, 10, &config).unwrap();
+            assert_eq!(effect.name(), *name);
+        }
+        assert!(effect_params("nope").is_none());
+        let bad = EffectSpec::new("fire").with("cooling", 9.0);
+        assert!(create_effect(&bad, 20, 10, &config).is_err());
+    }
 }
@@ -0,0 +1,341 @@
+//! Spectrum effect: an audio visualizer fed by `--audio`.
+//!
+//! The audio feed sends band levels (see `audio::fft`) as `Spectrum`
+//! events. They're drawn as vertical bars rising from the bottom, or as a
+//! waterfall scrolling down from the top, colored along the palette from
+//! tail to head. Bars jump up to a new level and sink back smoothly. Rain
+//! keeps falling behind them unless turned off.
+//!
+//! With no audio arriving (no `--audio`, or a silent pipe), a slow
+//! synthetic spectrum plays so the effect still has something to show.
+
+use std::collections::VecDeque;
+
+use super::Effect;
+use super::params::{EffectSpec, ParamSpec};
+use crate::audio::fft::BANDS;
+use crate::buffer::{Region, RenderTarget};
+use crate::color::gradient::Gradient;
+use crate::color::palette::{Palette, palette_by_name};
+use crate::config::Config;
+use crate::event::Event;
+use crate::rain::RainField;
+use crate::rain::chars::{self, CharacterPool};
+
+/// Tunables for `--param`.
+pub const PARAMS: &[ParamSpec] = &[
+    ParamSpec {
+        name: "waterfall",
+        description: "1 draws a scrolling waterfall instead of bars",
+        default: 0.0,
+        min: 0.0,
+        max: 1.0,
+    },
+    ParamSpec {
+        name: "rain",
+        description: "1 keeps rain falling behind the spectrum, 0 hides it",
+        default: 1.0,
+        min: 0.0,
+        max: 1.0,
+    },
+    ParamSpec {
+        name: "smoothing",
+        description: "How slowly bars sink after a peak",
+        default: 0.7,
+        min: 0.0,
+        max: 0.95,
+    },
+];
+
+/// Seconds without a spectrum before the synthetic one takes over.
+const IDLE_SECS: f64 = 1.0;
+
+/// Bar tops in eighths of a cell, emptiest first.
+const PARTIAL_BLOCKS: [char; 8] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇'];
+
+/// Waterfall characters, ordered by level.
+const WATERFALL_CHARS: &[char] = &[' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+
+/// Spectrum bars or waterfall, optionally over rain.
+pub struct SpectrumEffect {
+    rain: RainField,
+    /// Rain falls behind the spectrum (`--param rain`)
+    show_rain: bool,
+    /// Waterfall instead of bars (`--param waterfall`)
+    waterfall: bool,
+    /// Fraction of a falling level kept every 1/30 s (`--param smoothing`)
+    smoothing: f64,
+    /// Displayed band levels (0.0-1.0), after smoothing
+    levels: Vec<f32>,
+    /// The latest levels from the audio feed
+    target: Vec<f32>,
+    /// Seconds since the last `Spectrum` event
+    since_input: f64,
+    /// Waterfall rows, newest (top) first
+    history: VecDeque<Vec<f32>>,
+    /// Tail to head of the palette, indexed by level
+    gradient: Gradient,
+    palette: Palette,
+    unicode: bool,
+    time: f64,
+    width: u16,
+    height: u16,
+}
+
+impl SpectrumEffect {
+    pub fn with_config(width: u16, height: u16, config: &Config, spec: &EffectSpec) -> Self {
+        let palette = palette_by_name(&config.palette_name);
+        Self {
+            rain: RainField::with_config(width, height, config),
+            show_rain: spec.get("rain") >= 0.5,
+            waterfall: spec.get("waterfall") >= 0.5,
+            smoothing: spec.get("smoothing"),
+            levels: vec![0.0; BANDS],
+            target: vec![0.0; BANDS],
+            since_input: IDLE_SECS,
+            history: VecDeque::new(),
+            gradient: gradient_for(&palette),
+            palette,
+            unicode: chars::unicode_terminal(),
+            time: 0.0,
+            width,
+            height,
+        }
+    }
+
+    /// The band drawn in column `x`.
+    fn band_at(&self, x: u16) -> usize {
+        (x as usize * BANDS / self.width.max(1) as usize).min(BANDS - 1)
+    }
+
+    /// Whether column `x` is left empty to separate its band from the next
+    /// (only when bands are at least two columns wide).
+    fn is_gap(&self, x: u16) -> bool {
+        self.width as usize >= BANDS * 2 && self.band_at(x + 1) != self.band_at(x)
+    }
+
+    fn render_bars(&self, buffer: &mut dyn RenderTarget) {
+        let height = self.height as f32;
+        for x in 0..self.width {
+            if self.is_gap(x) {
+                continue;
+            }
+            let bar = self.levels[self.band_at(x)] * height;
+            for row in 0..self.height {
+                // How much of this cell (counted up from the bottom) is filled
+                let fill = (bar - row as f32).min(1.0);
+                let ch = if fill >= 1.0 {
+                    if self.unicode { '█' } else { '#' }
+                } else if self.unicode {
+                    PARTIAL_BLOCKS[(fill.max(0.0) * 8.0) as usize]
+                } else if fill >= 0.5 {
+                    '#'
+                } else {
+                    ' '
+                };
+                if ch == ' ' {
+                    break;
+                }
+                let color = self.gradient.at((row as f32 + 1.0) / height);
+                buffer.set_cell(x, self.height - 1 - row, ch, color, self.palette.background);
+            }
+        }
+    }
+
+    fn render_waterfall(&self, buffer: &mut dyn RenderTarget) {
+        let top = (WATERFALL_CHARS.len() - 1) as f32;
+        for (y, row) in (0..self.height).zip(&self.history) {
+            for x in 0..self.width {
+                let level = row[self.band_at(x)];
+                let ch = WATERFALL_CHARS[(level * top).round() as usize];
+                if ch != ' ' {
+                    let color = self.gradient.at(level);
+                    buffer.set_cell(x, y, ch, color, self.palette.background);
+                }
+            }
+        }
+    }
+}
+
+/// Levels map onto the palette from its dim tail up to its head.
+fn gradient_for(palette: &Palette) -> Gradient {
+    Gradient::even(&[
+        palette.tail,
+        palette.body_mid,
+        palette.body_bright,
+        palette.head,
+    ])
+    .expect("four stops make a gradient")
+}
+
+/// A slowly rolling, bass-heavy spectrum for when no audio is arriving.
+fn demo_levels(time: f64) -> Vec<f32> {
+    (0..BANDS)
+        .map(|band| {
+            let b = band as f64;
+            let tilt = 1.0 - 0.5 * b / BANDS as f64;
+            let level = 0.45 * tilt
+                + 0.25 * (time * 1.7 + b * 0.35).sin()
+                + 0.12 * (time * 3.1 - b * 0.9).sin();
+            level.clamp(0.0, 1.0) as f32
+        })
+        .collect()
+}
+
+impl Effect for SpectrumEffect {
+    fn name(&self) -> &str {
+        "spectrum"
+    }
+
+    fn update(&mut self, delta_time: f64) {
+        self.time += delta_time;
+        self.since_input += delta_time;
+        if self.since_input >= IDLE_SECS {
+            self.target = demo_levels(self.time);
+        }
+
+        // Rise straight to a peak, then sink back
+        let decay = self.smoothing.powf(delta_time * 30.0) as f32;
+        for (level, &target) in self.levels.iter_mut().zip(&self.target) {
+            *level = target.max(*level * decay);
+        }
+
+        if self.waterfall {
+            self.history.push_front(self.levels.clone());
+            self.history.truncate(self.height as usize);
+        }
+        if self.show_rain {
+            self.rain.update(delta_time);
+        }
+    }
+
+    fn render(&self, buffer: &mut dyn RenderTarget) {
+        if self.show_rain {
+            self.rain.render(buffer);
+        }
+        if self.waterfall {
+            self.render_waterfall(buffer);
+        } else {
+            self.render_bars(buffer);
+        }
+    }
+
+    fn resize(&mut self, width: u16, height: u16) {
+        self.width = width;
+        self.height = height;
+        self.history.truncate(height as usize);
+        self.rain.resize(width, height);
+    }
+
+    fn set_speed(&mut self, multiplier: f64) {
+        self.rain.set_speed(multiplier);
+    }
+
+    fn speed(&self) -> f64 {
+        self.rain.speed()
+    }
+
+    fn set_density(&mut self, multiplier: f64) {
+        self.rain.set_density(multiplier);
+    }
+
+    fn density(&self) -> f64 {
+        self.rain.density()
+    }
+
+    fn set_dead_zones(&mut self, zones: &[Region]) {
+        self.rain.set_dead_zones(zones);
+    }
+
+    fn set_mutation(&mut self, rate: f64) {
+        self.rain.set_mutation(rate);
+    }
+
+    fn set_palette(&mut self, palette: &Palette) {
+        self.gradient = gradient_for(palette);
+        self.palette = palette.clone();
+        self.rain.set_palette(palette.clone());
+    }
+
+    fn set_charset(&mut self, pool: &CharacterPool) {
+        self.rain.set_charset(pool.clone());
+    }
+
+    fn on_event(&mut self, event: &Event) {
+        match event {
+            Event::Spectrum { levels } => {
+                self.target = levels.clone();
+                self.target.resize(BANDS, 0.0);
+                self.since_input = 0.0;
+            }
+            _ if self.show_rain => self.rain.on_event(event),
+            _ => {}
+        }
+    }
+
+    fn active_columns(&self) -> Option<usize> {
+        self.show_rain.then(|| self.rain.column_count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::ScreenBuffer;
+    use crate::config::{Cli, ConfigFile};
+    use clap::Parser;
+
+    fn spectrum(spec: EffectSpec) -> SpectrumEffect {
+        let config = Config::resolve(&Cli::parse_from(["digital_rain"]), &ConfigFile::default());
+        let mut effect = SpectrumEffect::with_config(BANDS as u16, 10, &config, &spec);
+        effect.unicode = false;
+        effect
+    }
+
+    fn column_height(buffer: &ScreenBuffer, x: u16) -> usize {
+        (0..buffer.height())
+            .filter(|&y| buffer.get_cell(x, y).is_some_and(|c| c.ch == '#'))
+            .count()
+    }
+
+    #[test]
+    fn bars_follow_the_spectrum_and_sink_back() {
+        let mut effect = spectrum(EffectSpec::new("spectrum").with("rain", 0));
+        let mut levels = vec![0.0; BANDS];
+        levels[3] = 1.0;
+        levels[40] = 0.5;
+        effect.on_event(&Event::Spectrum { levels });
+        effect.update(0.0);
+
+        let mut buffer = ScreenBuffer::new(BANDS as u16, 10);
+        effect.render(&mut buffer);
+        assert_eq!(column_height(&buffer, 3), 10);
+        assert_eq!(column_height(&buffer, 40), 5);
+        assert_eq!(column_height(&buffer, 20), 0);
+
+        // Silence: the tall bar falls, but not all at once
+        effect.on_event(&Event::Spectrum {
+            levels: vec![0.0; BANDS],
+        });
+        effect.update(0.1);
+        let mut buffer = ScreenBuffer::new(BANDS as u16, 10);
+        effect.render(&mut buffer);
+        assert!((1..10).contains(&column_height(&buffer, 3)));
+    }
+
+    #[test]
+    fn plays_a_demo_without_audio_and_scrolls_a_waterfall() {
+        let mut effect = spectrum(
+            EffectSpec::new("spectrum")
+                .with("rain", 0)
+                .with("waterfall", 1),
+        );
+        for _ in 0..20 {
+            effect.update(1.0 / 30.0);
+        }
+        assert_eq!(effect.history.len(), 10);
+        let mut buffer = ScreenBuffer::new(BANDS as u16, 10);
+        effect.render(&mut buffer);
+        assert!(buffer.cells().iter().any(|c| c.ch != ' '));
+    }
+}
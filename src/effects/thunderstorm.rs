@@ -0,0 +1,432 @@
+//! Thunderstorm effect: dense, wind-blown rain with lightning.
+//!
+//! The rain is heavier and faster than classic, and the wind slants it:
+//! each row is drawn shifted a little further sideways than the one above,
+//! wrapping at the edges. Lightning strikes at random intervals. A strike is
+//! a short schedule of events: the bolt forks down from the top as the
+//! screen flashes white, the flash flickers once more, and a moment later
+//! the thunder arrives and shakes the picture sideways for a few frames.
+
+use rand::RngExt;
+
+use super::Effect;
+use super::params::{EffectSpec, ParamSpec};
+use crate::buffer::{Cell, Region, RenderTarget};
+use crate::color::Color;
+use crate::color::gradient::lerp_color;
+use crate::color::palette::Palette;
+use crate::config::Config;
+use crate::event::Event;
+use crate::rain::RainField;
+use crate::rain::chars::CharacterPool;
+use crate::rng::{self, SimRng, Stream};
+
+/// Tunables for `--param`.
+pub const PARAMS: &[ParamSpec] = &[
+    ParamSpec {
+        name: "wind",
+        description: "Slant of the rain in columns per row (negative blows left)",
+        default: 0.4,
+        min: -1.5,
+        max: 1.5,
+    },
+    ParamSpec {
+        name: "interval",
+        description: "Average seconds between lightning strikes",
+        default: 6.0,
+        min: 1.0,
+        max: 60.0,
+    },
+    ParamSpec {
+        name: "shake",
+        description: "How far the thunder shakes the screen, in columns",
+        default: 2.0,
+        min: 0.0,
+        max: 6.0,
+    },
+];
+
+/// Rain density and speed relative to the classic effect.
+const STORM_DENSITY: f64 = 2.5;
+const STORM_SPEED: f64 = 1.4;
+
+/// How long a bolt stays on screen, in seconds.
+const BOLT_SECS: f64 = 0.3;
+/// Seconds for a full-strength flash to fade out.
+const FLASH_FADE_SECS: f64 = 0.35;
+/// When the flash flickers back after the strike, and how bright.
+const FLICKER_DELAY: f64 = 0.12;
+const FLICKER_LEVEL: f64 = 0.6;
+/// The thunder's delay after the strike, and how long it shakes.
+const THUNDER_DELAY: std::ops::Range<f64> = 0.15..0.6;
+const SHAKE_SECS: f64 = 0.4;
+/// Most forks one bolt can have, and the chance of one starting per row.
+const MAX_FORKS: usize = 3;
+const FORK_CHANCE: f64 = 0.12;
+
+const BOLT_COLOR: Color = Color::Rgb {
+    r: 225,
+    g: 235,
+    b: 255,
+};
+const WHITE: Color = Color::Rgb {
+    r: 255,
+    g: 255,
+    b: 255,
+};
+
+/// Something a strike sets off.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum StormEvent {
+    /// Draw a new bolt
+    Bolt,
+    /// Light the screen up to this level
+    Flash(f64),
+    /// Start shaking the screen
+    Thunder,
+}
+
+/// Dense slanted rain, lightning, flashes, and screen shake.
+pub struct Thunderstorm {
+    rain: RainField,
+    /// Columns each row is shifted by, relative to the row above
+    wind: f64,
+    /// Average seconds between strikes (`--param interval`)
+    interval: f64,
+    /// Widest shake, in columns
+    shake: f64,
+    /// Seconds until the next strike
+    next_strike: f64,
+    /// Events of the current strike and seconds until each is due
+    scheduled: Vec<(f64, StormEvent)>,
+    /// The bolt's cells, and how long it has been showing
+    bolt: Vec<(u16, u16, char)>,
+    bolt_age: f64,
+    /// Brightness of the flash (0.0 = none)
+    flash: f64,
+    /// Seconds of shaking left, and this frame's offset
+    shake_left: f64,
+    shake_offset: i32,
+    speed_multiplier: f64,
+    width: u16,
+    height: u16,
+    rng: SimRng,
+}
+
+impl Thunderstorm {
+    pub fn with_config(width: u16, height: u16, config: &Config, spec: &EffectSpec) -> Self {
+        let mut rain = RainField::with_config(width, height, config);
+        rain.set_density(config.density_multiplier * STORM_DENSITY);
+        rain.set_speed(config.speed_multiplier * STORM_SPEED);
+        let mut rng = rng::make_rng(config.seed, Stream::Storm);
+        let interval = spec.get("interval");
+        Self {
+            rain,
+            wind: spec.get("wind"),
+            interval,
+            shake: spec.get("shake"),
+            // The first strike comes early, so the storm shows itself
+            next_strike: rng.random_range(0.5..1.5),
+            scheduled: Vec::new(),
+            bolt: Vec::new(),
+            bolt_age: BOLT_SECS,
+            flash: 0.0,
+            shake_left: 0.0,
+            shake_offset: 0,
+            speed_multiplier: config.speed_multiplier,
+            width,
+            height,
+            rng,
+        }
+    }
+
+    /// Schedule a strike's events. `strength` (0.0-1.0) scales the flash.
+    fn strike(&mut self, strength: f64) {
+        let thunder = self.rng.random_range(THUNDER_DELAY);
+        self.scheduled.extend([
+            (0.0, StormEvent::Bolt),
+            (0.0, StormEvent::Flash(0.5 + 0.5 * strength)),
+            (FLICKER_DELAY, StormEvent::Flash(FLICKER_LEVEL * strength)),
+            (thunder, StormEvent::Thunder),
+        ]);
+    }
+
+    fn fire(&mut self, event: StormEvent) {
+        match event {
+            StormEvent::Bolt => {
+                self.bolt = bolt(&mut self.rng, self.width, self.height);
+                self.bolt_age = 0.0;
+            }
+            StormEvent::Flash(level) => self.flash = self.flash.max(level),
+            StormEvent::Thunder => self.shake_left = SHAKE_SECS,
+        }
+    }
+}
+
+/// A jagged bolt from the top of a `width` x `height` screen, forking on
+/// the way down.
+fn bolt(rng: &mut SimRng, width: u16, height: u16) -> Vec<(u16, u16, char)> {
+    let mut cells = Vec::new();
+    if width == 0 || height == 0 {
+        return cells;
+    }
+    // Each arm: column, first row, rows to go, and the way it leans
+    let mut arms = vec![(
+        rng.random_range(0..width) as i32,
+        0u16,
+        rng.random_range(height / 2..=height),
+        0i32,
+    )];
+    let mut forks = 0;
+    while let Some((mut x, mut y, mut rows, lean)) = arms.pop() {
+        while rows > 0 && y < height {
+            let step = match lean {
+                0 => rng.random_range(-1..=1),
+                lean if rng.random_bool(0.7) => lean,
+                _ => 0,
+            };
+            x += step;
+            if !(0..width as i32).contains(&x) {
+                break;
+            }
+            let ch = match step {
+                -1 => '/',
+                1 => '\\',
+                _ => '|',
+            };
+            cells.push((x as u16, y, ch));
+            if forks < MAX_FORKS && lean == 0 && rng.random_bool(FORK_CHANCE) {
+                forks += 1;
+                let lean = if rng.random_bool(0.5) { -1 } else { 1 };
+                arms.push((x, y + 1, rng.random_range(2..=(height / 4).max(2)), lean));
+            }
+            y += 1;
+            rows -= 1;
+        }
+    }
+    cells
+}
+
+/// Draws into `inner` with each row shifted `slant` columns further than
+/// the one above, wrapping around, so vertical rain comes out slanted.
+struct Slanted<'a> {
+    inner: &'a mut dyn RenderTarget,
+    slant: f64,
+}
+
+impl Slanted<'_> {
+    /// Where column `x` of row `y` lands.
+    fn column(&self, x: u16, y: u16) -> u16 {
+        let width = self.inner.width() as i64;
+        if width == 0 {
+            return x;
+        }
+        let offset = (y as f64 * self.slant).round() as i64;
+        (x as i64 + offset).rem_euclid(width) as u16
+    }
+}
+
+impl RenderTarget for Slanted<'_> {
+    fn width(&self) -> u16 {
+        self.inner.width()
+    }
+
+    fn height(&self) -> u16 {
+        self.inner.height()
+    }
+
+    fn get_cell(&self, x: u16, y: u16) -> Option<Cell> {
+        if x >= self.width() {
+            return None;
+        }
+        self.inner.get_cell(self.column(x, y), y)
+    }
+
+    fn set_cell(&mut self, x: u16, y: u16, ch: char, fg: Color, bg: Color) {
+        if x < self.width() {
+            let x = self.column(x, y);
+            self.inner.set_cell(x, y, ch, fg, bg);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.inner.clear();
+    }
+}
+
+impl Effect for Thunderstorm {
+    fn name(&self) -> &str {
+        "thunderstorm"
+    }
+
+    fn update(&mut self, delta_time: f64) {
+        self.rain.update(delta_time);
+        let dt = delta_time * self.speed_multiplier;
+
+        self.next_strike -= dt;
+        if self.next_strike <= 0.0 {
+            self.strike(1.0);
+            self.next_strike = self.interval * self.rng.random_range(0.4..1.6);
+        }
+
+        for (due, _) in &mut self.scheduled {
+            *due -= dt;
+        }
+        while let Some(i) = self.scheduled.iter().position(|(due, _)| *due <= 0.0) {
+            let (_, event) = self.scheduled.swap_remove(i);
+            self.fire(event);
+        }
+
+        self.bolt_age += dt;
+        self.flash = (self.flash - dt / FLASH_FADE_SECS).max(0.0);
+        self.shake_left -= dt;
+        let reach = self.shake.round() as i32;
+        self.shake_offset = if self.shake_left > 0.0 && reach > 0 {
+            self.rng.random_range(-reach..=reach)
+        } else {
+            0
+        };
+    }
+
+    fn render(&self, buffer: &mut dyn RenderTarget) {
+        self.rain.render(&mut Slanted {
+            inner: &mut *buffer,
+            slant: self.wind,
+        });
+
+        let (width, height) = (buffer.width(), buffer.height());
+        if self.bolt_age < BOLT_SECS {
+            for &(x, y, ch) in &self.bolt {
+                if x < width && y < height {
+                    buffer.set_cell(x, y, ch, BOLT_COLOR, Color::Reset);
+                }
+            }
+        }
+
+        if self.flash > 0.0 {
+            let t = self.flash as f32;
+            for y in 0..height {
+                for x in 0..width {
+                    if let Some(cell) = buffer.get_cell(x, y) {
+                        // The sky lights up too, less than the rain
+                        let fg = lerp_color(cell.fg, WHITE, t);
+                        let bg = lerp_color(cell.bg, WHITE, t * 0.4);
+                        buffer.set_cell(x, y, cell.ch, fg, bg);
+                    }
+                }
+            }
+        }
+
+        buffer.shift(self.shake_offset, 0);
+    }
+
+    fn resize(&mut self, width: u16, height: u16) {
+        self.rain.resize(width, height);
+        self.width = width;
+        self.height = height;
+        self.bolt.clear();
+    }
+
+    fn set_speed(&mut self, multiplier: f64) {
+        self.rain.set_speed(multiplier * STORM_SPEED);
+        self.speed_multiplier = multiplier;
+    }
+
+    fn speed(&self) -> f64 {
+        self.speed_multiplier
+    }
+
+    fn set_density(&mut self, multiplier: f64) {
+        self.rain.set_density(multiplier * STORM_DENSITY);
+    }
+
+    fn density(&self) -> f64 {
+        self.rain.density() / STORM_DENSITY
+    }
+
+    fn set_dead_zones(&mut self, zones: &[Region]) {
+        self.rain.set_dead_zones(zones);
+    }
+
+    fn set_mutation(&mut self, rate: f64) {
+        self.rain.set_mutation(rate);
+    }
+
+    fn set_palette(&mut self, palette: &Palette) {
+        self.rain.set_palette(palette.clone());
+    }
+
+    fn set_charset(&mut self, pool: &CharacterPool) {
+        self.rain.set_charset(pool.clone());
+    }
+
+    /// A beat brings a strike, brighter the stronger it is.
+    fn on_event(&mut self, event: &Event) {
+        self.rain.on_event(event);
+        if let Event::Beat { strength } = *event {
+            self.strike(strength);
+        }
+    }
+
+    fn active_columns(&self) -> Option<usize> {
+        Some(self.rain.column_count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::buffer::ScreenBuffer;
+    use crate::config::{Cli, ConfigFile};
+
+    #[test]
+    fn a_strike_draws_a_bolt_flashes_then_shakes() {
+        let cli = Cli::parse_from(["digital_rain", "--seed", "4"]);
+        let config = Config::resolve(&cli, &ConfigFile::default());
+        let spec = EffectSpec::new("thunderstorm").with("interval", 60.0);
+        let mut storm = Thunderstorm::with_config(40, 20, &config, &spec);
+        storm.next_strike = f64::INFINITY;
+        let dt = 1.0 / 30.0;
+        for _ in 0..30 {
+            storm.update(dt);
+        }
+        assert!(storm.bolt_age >= BOLT_SECS && storm.flash == 0.0);
+
+        storm.on_event(&Event::Beat { strength: 1.0 });
+        storm.update(dt);
+        assert!(!storm.bolt.is_empty() && storm.bolt[0].1 == 0);
+        assert!(storm.flash > 0.9);
+        let mut buffer = ScreenBuffer::new(40, 20);
+        storm.render(&mut buffer);
+        let &(x, y, ch) = &storm.bolt[0];
+        let cell = buffer.get_cell(x, y).unwrap();
+        assert_eq!(cell.ch, ch);
+
+        // The thunder follows within the second, then the screen settles
+        let mut shook = false;
+        for _ in 0..30 {
+            storm.update(dt);
+            shook |= storm.shake_left > 0.0;
+        }
+        assert!(shook);
+        assert!(storm.scheduled.is_empty());
+        assert_eq!((storm.shake_offset, storm.flash), (0, 0.0));
+        assert!(storm.bolt_age >= BOLT_SECS);
+    }
+
+    #[test]
+    fn rain_is_slanted_by_the_wind() {
+        let mut buffer = ScreenBuffer::new(10, 3);
+        let mut slanted = Slanted {
+            inner: &mut buffer,
+            slant: 1.0,
+        };
+        for y in 0..3 {
+            slanted.set_cell(8, y, '*', Color::Reset, Color::Reset);
+        }
+        assert_eq!(slanted.get_cell(8, 2).map(|c| c.ch), Some('*'));
+        assert_eq!(buffer.to_plain_string(), "        *\n         *\n*\n");
+    }
+}
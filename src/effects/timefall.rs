@@ -0,0 +1,477 @@
+//! Timefall effect: a clock drawn by the rain itself.
+//!
+//! The time is laid out in huge block digits (the 3x5 font, scaled up) and
+//! painted into a density mask on the rain: columns crossing a digit's
+//! strokes fall much more often, and the rain is drawn brighter inside the
+//! strokes and dimmer everywhere else. Stroke cells the rain hasn't reached
+//! keep a faint glyph, so the time stays legible between drops. When the
+//! minute changes, the new time dissolves in cell by cell over the old.
+//!
+//! The clock is the local wall-clock time, not the animation's, so it
+//! keeps time through pauses and speed changes.
+
+use rand::RngExt;
+
+use super::Effect;
+use super::params::{EffectSpec, ParamSpec};
+use crate::buffer::{Region, RenderTarget};
+use crate::color::gradient::scale_color;
+use crate::color::palette::{Palette, color_by_name, palette_by_name};
+use crate::config::Config;
+use crate::event::Event;
+use crate::font;
+use crate::rain::RainField;
+use crate::rain::chars::{CharacterPool, charset_by_name};
+use crate::rain::glyphs::GlyphGrid;
+use crate::rain::mask::DensityMask;
+use crate::rng::{self, SimRng, Stream};
+
+/// Tunables for `--param`.
+pub const PARAMS: &[ParamSpec] = &[
+    ParamSpec {
+        name: "hour12",
+        description: "1 shows a 12-hour clock instead of a 24-hour one",
+        default: 0.0,
+        min: 0.0,
+        max: 1.0,
+    },
+    ParamSpec {
+        name: "dim",
+        description: "Brightness of the rain outside the digits",
+        default: 0.4,
+        min: 0.0,
+        max: 1.0,
+    },
+];
+
+/// Seconds for a new time to dissolve in over the old one.
+const DISSOLVE_SECS: f64 = 1.5;
+/// Seconds between looks at the wall clock.
+const CHECK_SECS: f64 = 0.5;
+/// Brightness of the glyphs holding a stroke's shape between drops.
+const GHOST_LEVEL: f64 = 0.35;
+/// Share of the screen the digits may fill, across and down.
+const FILL_WIDTH: f64 = 0.85;
+const FILL_HEIGHT: f64 = 0.6;
+/// Font pixels between characters.
+const LETTER_GAP: usize = 1;
+
+/// A clock made of rain.
+pub struct Timefall {
+    rain: RainField,
+    /// 12-hour clock (`--param hour12`)
+    hour12: bool,
+    /// Brightness of the rain outside the digits (`--param dim`)
+    dim: f64,
+    /// Reads the wall clock as (hour, minute)
+    clock: fn() -> (u8, u8),
+    /// The time on screen
+    shown: (u8, u8),
+    /// The time dissolving out, the time dissolving in, and how far along
+    /// (1.0 = done)
+    from: DensityMask,
+    to: DensityMask,
+    progress: f64,
+    /// The blend of the two the rain is using
+    mask: DensityMask,
+    /// When each cell switches during a dissolve (0.0-1.0)
+    thresholds: Vec<f32>,
+    /// Faint glyphs filling the strokes
+    ghosts: GlyphGrid,
+    char_pool: CharacterPool,
+    palette: Palette,
+    since_check: f64,
+    width: u16,
+    height: u16,
+    rng: SimRng,
+}
+
+impl Timefall {
+    pub fn with_config(width: u16, height: u16, config: &Config, spec: &EffectSpec) -> Self {
+        let mut rng = rng::make_rng(config.seed, Stream::Timefall);
+        let char_pool = charset_by_name(&config.charset_name);
+        let highlight = config.highlight_color.as_deref().and_then(color_by_name);
+        let mut timefall = Self {
+            rain: RainField::with_config(width, height, config),
+            hour12: spec.get("hour12") >= 0.5,
+            dim: spec.get("dim"),
+            clock: local_time,
+            shown: local_time(),
+            from: DensityMask::new(0, 0),
+            to: DensityMask::new(0, 0),
+            progress: 1.0,
+            mask: DensityMask::new(0, 0),
+            thresholds: Vec::new(),
+            ghosts: GlyphGrid::new(width, height, &char_pool, &mut rng),
+            char_pool,
+            palette: palette_by_name(&config.palette_name).with_highlight(highlight),
+            since_check: 0.0,
+            width,
+            height,
+            rng,
+        };
+        timefall.layout();
+        timefall
+    }
+
+    /// The time as drawn, e.g. `09:41` (or `9:41` on a 12-hour clock).
+    fn text(&self) -> String {
+        let (hour, minute) = self.shown;
+        if self.hour12 {
+            let hour = match hour % 12 {
+                0 => 12,
+                h => h,
+            };
+            format!("{}:{:02}", hour, minute)
+        } else {
+            format!("{:02}:{:02}", hour, minute)
+        }
+    }
+
+    /// Redraw the digits for the screen size, without a dissolve.
+    fn layout(&mut self) {
+        self.to = clock_mask(&self.text(), self.width, self.height);
+        self.from = self.to.clone();
+        self.mask = self.to.clone();
+        self.progress = 1.0;
+        let cells = self.width as usize * self.height as usize;
+        self.thresholds = (0..cells).map(|_| self.rng.random()).collect();
+        self.rain.set_density_mask(Some(self.mask.clone()));
+    }
+
+    /// Blend `from` into `to` at the current progress.
+    fn blend(&mut self) {
+        let progress = self.progress as f32;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let i = y as usize * self.width as usize + x as usize;
+                let mask = if self.thresholds[i] < progress {
+                    &self.to
+                } else {
+                    &self.from
+                };
+                self.mask.set(x, y, mask.get(x, y));
+            }
+        }
+        self.rain.set_density_mask(Some(self.mask.clone()));
+    }
+}
+
+/// The local time as (hour, minute), or UTC where it can't be had.
+fn local_time() -> (u8, u8) {
+    platform::local_time().unwrap_or_else(|| {
+        let secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        ((secs / 3600 % 24) as u8, (secs / 60 % 60) as u8)
+    })
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::ffi::{c_char, c_int, c_long};
+
+    /// `struct tm` as glibc, musl, macOS, and the BSDs lay it out
+    #[repr(C)]
+    struct Tm {
+        tm_sec: c_int,
+        tm_min: c_int,
+        tm_hour: c_int,
+        tm_mday: c_int,
+        tm_mon: c_int,
+        tm_year: c_int,
+        tm_wday: c_int,
+        tm_yday: c_int,
+        tm_isdst: c_int,
+        tm_gmtoff: c_long,
+        tm_zone: *const c_char,
+    }
+
+    unsafe extern "C" {
+        fn time(t: *mut c_long) -> c_long;
+        fn localtime_r(t: *const c_long, tm: *mut Tm) -> *mut Tm;
+    }
+
+    pub fn local_time() -> Option<(u8, u8)> {
+        // SAFETY: all zeros is a valid `Tm` (integers and a null pointer),
+        // and localtime_r only writes into the one it's given
+        unsafe {
+            let now = time(std::ptr::null_mut());
+            let mut tm: Tm = std::mem::zeroed();
+            if localtime_r(&now, &mut tm).is_null() {
+                return None;
+            }
+            Some((tm.tm_hour as u8, tm.tm_min as u8))
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    /// `SYSTEMTIME`
+    #[repr(C)]
+    #[derive(Default)]
+    struct SystemTime {
+        year: u16,
+        month: u16,
+        day_of_week: u16,
+        day: u16,
+        hour: u16,
+        minute: u16,
+        second: u16,
+        milliseconds: u16,
+    }
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn GetLocalTime(time: *mut SystemTime);
+    }
+
+    pub fn local_time() -> Option<(u8, u8)> {
+        let mut now = SystemTime::default();
+        // SAFETY: GetLocalTime fills in the SYSTEMTIME it's given
+        unsafe {
+            GetLocalTime(&mut now);
+        }
+        Some((now.hour as u8, now.minute as u8))
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    pub fn local_time() -> Option<(u8, u8)> {
+        None
+    }
+}
+
+/// `text` in block characters as large as fits, centered on a `width` x
+/// `height` screen. Font pixels are up to twice as wide as they are tall in
+/// cells, since cells are about twice as tall as they are wide.
+fn clock_mask(text: &str, width: u16, height: u16) -> DensityMask {
+    let mut mask = DensityMask::new(width, height);
+    let chars: Vec<char> = text.chars().collect();
+    let pixels_wide = (chars.len() * (font::GLYPH_WIDTH + LETTER_GAP)).saturating_sub(LETTER_GAP);
+    if pixels_wide == 0 {
+        return mask;
+    }
+    let fit_down = (height as f64 * FILL_HEIGHT / font::GLYPH_HEIGHT as f64) as usize;
+    let fit_across = (width as f64 * FILL_WIDTH / pixels_wide as f64) as usize;
+    let tall = fit_down.min(fit_across).max(1);
+    let wide = (tall * 2).min(fit_across).max(1);
+
+    let left = (width as usize).saturating_sub(pixels_wide * wide) / 2;
+    let top = (height as usize).saturating_sub(font::GLYPH_HEIGHT * tall) / 2;
+    for (i, &ch) in chars.iter().enumerate() {
+        let Some(bits) = font::known_glyph(ch) else {
+            continue;
+        };
+        let x0 = left + i * (font::GLYPH_WIDTH + LETTER_GAP) * wide;
+        for py in 0..font::GLYPH_HEIGHT {
+            for px in 0..font::GLYPH_WIDTH {
+                if !font::pixel(bits, px, py) {
+                    continue;
+                }
+                for dy in 0..tall {
+                    for dx in 0..wide {
+                        let (x, y) = (x0 + px * wide + dx, top + py * tall + dy);
+                        if x < width as usize && y < height as usize {
+                            mask.set(x as u16, y as u16, 1.0);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    mask
+}
+
+impl Effect for Timefall {
+    fn name(&self) -> &str {
+        "timefall"
+    }
+
+    fn update(&mut self, delta_time: f64) {
+        self.rain.update(delta_time);
+
+        self.since_check += delta_time;
+        if self.since_check >= CHECK_SECS {
+            self.since_check = 0.0;
+            let now = (self.clock)();
+            if now != self.shown {
+                self.shown = now;
+                self.from = self.mask.clone();
+                self.to = clock_mask(&self.text(), self.width, self.height);
+                self.progress = 0.0;
+            }
+        }
+
+        if self.progress < 1.0 {
+            self.progress = (self.progress + delta_time / DISSOLVE_SECS).min(1.0);
+            self.blend();
+        }
+    }
+
+    fn render(&self, buffer: &mut dyn RenderTarget) {
+        let (width, height) = (buffer.width(), buffer.height());
+
+        // Faint glyphs hold the strokes' shape; the rain falls over them
+        for y in 0..height {
+            for x in 0..width {
+                let weight = self.mask.get(x, y) as f64;
+                if let Some(ch) = self.ghosts.get(x, y).filter(|_| weight > 0.0) {
+                    let fg = scale_color(self.palette.body_mid, GHOST_LEVEL * weight);
+                    buffer.set_cell(x, y, ch, fg, self.palette.background);
+                }
+            }
+        }
+
+        self.rain.render(buffer);
+
+        // Dim everything outside the digits so they stand out
+        for y in 0..height {
+            for x in 0..width {
+                if self.mask.get(x, y) > 0.0 {
+                    continue;
+                }
+                if let Some(cell) = buffer.get_cell(x, y).filter(|c| c.ch != ' ') {
+                    buffer.set_cell(x, y, cell.ch, scale_color(cell.fg, self.dim), cell.bg);
+                }
+            }
+        }
+    }
+
+    fn resize(&mut self, width: u16, height: u16) {
+        self.rain.resize(width, height);
+        self.ghosts
+            .resize(width, height, &self.char_pool, &mut self.rng);
+        self.width = width;
+        self.height = height;
+        self.layout();
+    }
+
+    fn set_speed(&mut self, multiplier: f64) {
+        self.rain.set_speed(multiplier);
+    }
+
+    fn speed(&self) -> f64 {
+        self.rain.speed()
+    }
+
+    fn set_density(&mut self, multiplier: f64) {
+        self.rain.set_density(multiplier);
+    }
+
+    fn density(&self) -> f64 {
+        self.rain.density()
+    }
+
+    fn set_dead_zones(&mut self, zones: &[Region]) {
+        self.rain.set_dead_zones(zones);
+    }
+
+    fn set_mutation(&mut self, rate: f64) {
+        self.rain.set_mutation(rate);
+    }
+
+    fn set_palette(&mut self, palette: &Palette) {
+        self.rain.set_palette(palette.clone());
+        self.palette = palette.clone();
+    }
+
+    fn set_charset(&mut self, pool: &CharacterPool) {
+        self.rain.set_charset(pool.clone());
+        self.char_pool = pool.clone();
+        self.ghosts.fill(&self.char_pool, &mut self.rng);
+    }
+
+    fn on_event(&mut self, event: &Event) {
+        self.rain.on_event(event);
+    }
+
+    fn active_columns(&self) -> Option<usize> {
+        Some(self.rain.column_count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU8, Ordering};
+
+    use clap::Parser;
+
+    use super::*;
+    use crate::buffer::ScreenBuffer;
+    use crate::color::gradient::color_to_rgb;
+    use crate::config::{Cli, ConfigFile};
+
+    fn timefall(hour12: f64) -> Timefall {
+        let cli = Cli::parse_from(["digital_rain", "--seed", "8"]);
+        let config = Config::resolve(&cli, &ConfigFile::default());
+        let spec = EffectSpec::new("timefall").with("hour12", hour12);
+        Timefall::with_config(80, 24, &config, &spec)
+    }
+
+    #[test]
+    fn the_digits_are_lit_and_the_rest_is_dimmed() {
+        let mut effect = timefall(1.0);
+        effect.clock = || (21, 5);
+        effect.shown = (21, 5);
+        assert_eq!(effect.text(), "9:05");
+        effect.layout();
+
+        // The '9' starts at the left of the centered text with a full top row
+        let mask = effect.mask.clone();
+        let lit: Vec<(u16, u16)> = (0..24)
+            .flat_map(|y| (0..80).map(move |x| (x, y)))
+            .filter(|&(x, y)| mask.get(x, y) > 0.0)
+            .collect();
+        assert!(lit.len() > 100);
+        let (x0, y0) = lit[0];
+        assert!((10..20).contains(&x0) && (4..8).contains(&y0));
+
+        for _ in 0..90 {
+            effect.update(1.0 / 30.0);
+        }
+        let mut buffer = ScreenBuffer::new(80, 24);
+        effect.render(&mut buffer);
+        for y in 0..24 {
+            for x in 0..80 {
+                let cell = buffer.get_cell(x, y).unwrap();
+                if mask.get(x, y) > 0.0 {
+                    assert_ne!(cell.ch, ' ', "stroke cell ({}, {}) is empty", x, y);
+                } else if cell.ch != ' ' {
+                    let (r, g, b) = color_to_rgb(cell.fg);
+                    assert!(r.max(g).max(b) <= 103, "({}, {}) wasn't dimmed", x, y);
+                }
+            }
+        }
+    }
+
+    static MINUTE: AtomicU8 = AtomicU8::new(0);
+
+    fn test_clock() -> (u8, u8) {
+        (10, MINUTE.load(Ordering::SeqCst))
+    }
+
+    #[test]
+    fn a_new_minute_dissolves_in() {
+        let mut effect = timefall(0.0);
+        effect.clock = test_clock;
+        effect.shown = (10, 0);
+        effect.layout();
+        let (before, after) = (clock_mask("10:00", 80, 24), clock_mask("10:01", 80, 24));
+        assert_ne!(before, after);
+
+        MINUTE.store(1, Ordering::SeqCst);
+        let dt = 0.1;
+        let mut mixed = false;
+        for _ in 0..((CHECK_SECS + DISSOLVE_SECS) / dt) as usize + 2 {
+            effect.update(dt);
+            mixed |= effect.mask != before && effect.mask != after;
+        }
+        assert!(mixed, "the change should happen cell by cell");
+        assert_eq!(effect.mask, after);
+        assert_eq!(effect.text(), "10:01");
+    }
+}
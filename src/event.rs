@@ -0,0 +1,20 @@
+//! Outside events delivered to the running effect and post filters.
+//!
+//! Effects and filters react through `Effect::on_event` and
+//! `PostProcessor::on_event`; both default to ignoring everything.
+
+/// Something that happened outside the animation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// A beat in the `--audio` input; `strength` runs from 0.0 (just over
+    /// the threshold) to 1.0 (a hard hit)
+    Beat { strength: f64 },
+    /// The latest block of `--audio` input, as band levels (0.0-1.0) from
+    /// bass to treble (see `audio::fft`)
+    Spectrum { levels: Vec<f32> },
+    /// The latest `--react` reading, from 0.0 (idle) to 1.0 (flat out)
+    Load { level: f64 },
+    /// A new line of the `--tail` log, and whether it matched
+    /// `--tail-highlight`
+    Line { text: String, highlight: bool },
+}
@@ -0,0 +1,155 @@
+//! Tiny embedded bitmap font for rasterizing frames (GIF export) and
+//! drawing block-letter banners.
+//!
+//! Glyphs are 3x5 pixels, stored as 15-bit masks read row by row from the
+//! top-left (bit 14 = top-left pixel). Digits, Latin letters (lowercase is
+//! drawn as uppercase), and common punctuation have hand-drawn shapes.
+//! Everything else -- katakana, box drawing, noise glyphs -- gets a stable
+//! pseudo-random pattern derived from its code point, which reads as
+//! "alien glyph" at this size, much like the rain itself.
+
+/// Glyph width in font pixels.
+pub const GLYPH_WIDTH: usize = 3;
+/// Glyph height in font pixels.
+pub const GLYPH_HEIGHT: usize = 5;
+
+/// Hand-drawn glyphs. Each row is three bits, top row first.
+const GLYPHS: &[(char, u16)] = &[
+    ('0', 0b111_101_101_101_111),
+    ('1', 0b010_110_010_010_111),
+    ('2', 0b111_001_111_100_111),
+    ('3', 0b111_001_011_001_111),
+    ('4', 0b101_101_111_001_001),
+    ('5', 0b111_100_111_001_111),
+    ('6', 0b111_100_111_101_111),
+    ('7', 0b111_001_010_010_010),
+    ('8', 0b111_101_111_101_111),
+    ('9', 0b111_101_111_001_111),
+    ('A', 0b010_101_111_101_101),
+    ('B', 0b110_101_110_101_110),
+    ('C', 0b011_100_100_100_011),
+    ('D', 0b110_101_101_101_110),
+    ('E', 0b111_100_110_100_111),
+    ('F', 0b111_100_110_100_100),
+    ('G', 0b011_100_101_101_011),
+    ('H', 0b101_101_111_101_101),
+    ('I', 0b111_010_010_010_111),
+    ('J', 0b001_001_001_101_010),
+    ('K', 0b101_101_110_101_101),
+    ('L', 0b100_100_100_100_111),
+    ('M', 0b101_111_111_101_101),
+    ('N', 0b110_101_101_101_101),
+    ('O', 0b010_101_101_101_010),
+    ('P', 0b110_101_110_100_100),
+    ('Q', 0b010_101_101_110_011),
+    ('R', 0b110_101_110_101_101),
+    ('S', 0b011_100_010_001_110),
+    ('T', 0b111_010_010_010_010),
+    ('U', 0b101_101_101_101_111),
+    ('V', 0b101_101_101_101_010),
+    ('W', 0b101_101_111_111_101),
+    ('X', 0b101_101_010_101_101),
+    ('Y', 0b101_101_010_010_010),
+    ('Z', 0b111_001_010_100_111),
+    ('.', 0b000_000_000_000_010),
+    (',', 0b000_000_000_010_100),
+    (':', 0b000_010_000_010_000),
+    (';', 0b000_010_000_010_100),
+    ('-', 0b000_000_111_000_000),
+    ('+', 0b000_010_111_010_000),
+    ('=', 0b000_111_000_111_000),
+    ('*', 0b101_010_101_000_000),
+    ('/', 0b001_001_010_100_100),
+    ('\\', 0b100_100_010_001_001),
+    ('|', 0b010_010_010_010_010),
+    ('_', 0b000_000_000_000_111),
+    ('^', 0b010_101_000_000_000),
+    ('!', 0b010_010_010_000_010),
+    ('?', 0b111_001_010_000_010),
+    ('#', 0b101_111_101_111_101),
+    ('%', 0b101_001_010_100_101),
+    ('@', 0b111_101_111_100_111),
+    ('<', 0b001_010_100_010_001),
+    ('>', 0b100_010_001_010_100),
+    ('(', 0b001_010_010_010_001),
+    (')', 0b100_010_010_010_100),
+    ('[', 0b011_010_010_010_011),
+    (']', 0b110_010_010_010_110),
+    ('"', 0b101_101_000_000_000),
+    ('\'', 0b010_010_000_000_000),
+    ('$', 0b011_110_010_011_110),
+    ('&', 0b010_101_010_101_011),
+    ('~', 0b000_011_110_000_000),
+];
+
+/// The 15-bit mask for a character.
+pub fn glyph_bits(ch: char) -> u16 {
+    if ch == ' ' {
+        return 0;
+    }
+    known_glyph(ch).unwrap_or_else(|| fallback_glyph(ch))
+}
+
+/// The hand-drawn mask for a character, if it has one.
+pub fn known_glyph(ch: char) -> Option<u16> {
+    let upper = ch.to_ascii_uppercase();
+    GLYPHS
+        .iter()
+        .find(|(c, _)| *c == upper)
+        .map(|&(_, bits)| bits)
+}
+
+/// Whether the pixel at (`x`, `y`) of a glyph is lit.
+pub fn pixel(bits: u16, x: usize, y: usize) -> bool {
+    let shift = (GLYPH_HEIGHT - 1 - y) * GLYPH_WIDTH + (GLYPH_WIDTH - 1 - x);
+    bits >> shift & 1 == 1
+}
+
+/// Stable pseudo-glyph for characters without a hand-drawn shape.
+///
+/// Hashes the code point and keeps re-hashing until the pattern has enough
+/// lit pixels to read as a character rather than a speck.
+fn fallback_glyph(ch: char) -> u16 {
+    let mut h = ch as u32;
+    loop {
+        h ^= h >> 16;
+        h = h.wrapping_mul(0x7FEB_352D);
+        h ^= h >> 15;
+        h = h.wrapping_mul(0x846C_A68B);
+        h ^= h >> 16;
+        let bits = (h & 0x7FFF) as u16;
+        if (6..=11).contains(&bits.count_ones()) {
+            return bits;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_glyph_pixels() {
+        let one = glyph_bits('1');
+        // Top row of '1' is .#.
+        assert!(!pixel(one, 0, 0));
+        assert!(pixel(one, 1, 0));
+        assert!(!pixel(one, 2, 0));
+        // Bottom row is ###
+        assert!((0..3).all(|x| pixel(one, x, 4)));
+    }
+
+    #[test]
+    fn lowercase_uses_uppercase_shape_and_space_is_blank() {
+        assert_eq!(glyph_bits('a'), glyph_bits('A'));
+        assert_eq!(glyph_bits(' '), 0);
+    }
+
+    #[test]
+    fn fallback_glyphs_are_stable_and_visible() {
+        let k = glyph_bits('ｱ');
+        assert_eq!(k, glyph_bits('ｱ'));
+        assert!(k.count_ones() >= 6);
+        assert_ne!(glyph_bits('ｱ'), glyph_bits('ｲ'));
+    }
+}
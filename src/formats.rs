@@ -0,0 +1,1004 @@
+//! Minimal JSON and YAML readers/writers for config files.
+//!
+//! The config structs are (de)serialized through `toml::Value`, so these
+//! codecs only need to translate between text and that value tree. That keeps
+//! TOML as the single source of truth for the schema while letting users keep
+//! their config as `config.json` or `config.yaml` (e.g. when a dotfile manager
+//! templates JSON).
+//!
+//! Supported subsets:
+//! - **JSON**: full RFC 8259 syntax. `null` object members are dropped (TOML
+//!   has no null; every config field is optional anyway); a `null` anywhere
+//!   else is an error.
+//! - **YAML**: block mappings and sequences, flow `[...]`/`{...}` collections,
+//!   plain/single/double-quoted scalars, and `#` comments. Anchors, tags,
+//!   multi-document streams, and block scalars (`|`, `>`) are not supported.
+//!
+//! Both readers give up on collections nested more than [`MAX_DEPTH`] deep
+//! rather than recurse without bound, since control commands arrive as JSON
+//! from anyone who can write to the socket.
+//!
+//! Errors start with the 1-based `line:column` they were found at, and the
+//! `*_located` readers also report where each key starts so `--check-config`
+//! can point into the original file rather than its TOML translation.
+
+use std::collections::HashMap;
+
+use toml::Value;
+use toml::map::Map;
+
+/// How deeply `[...]`/`{...}` collections may nest.
+pub const MAX_DEPTH: usize = 64;
+
+// ---------- JSON ----------
+
+/// 1-based `(line, column)` where each key or array item starts, by its path
+/// from the root (table keys by name, array items by decimal index).
+pub type Positions = HashMap<Vec<String>, (usize, usize)>;
+
+/// Split the `line:column: ` prefix off an error from these readers.
+pub fn error_position(error: &str) -> Option<(usize, usize, &str)> {
+    let (line, rest) = error.split_once(':')?;
+    let (column, message) = rest.split_once(": ")?;
+    Some((line.parse().ok()?, column.parse().ok()?, message))
+}
+
+/// Parse JSON text into a TOML value tree.
+pub fn parse_json(input: &str) -> Result<Value, String> {
+    parse_json_located(input).map(|(value, _)| value)
+}
+
+/// Parse JSON text, also returning where each key starts.
+pub fn parse_json_located(input: &str) -> Result<(Value, Positions), String> {
+    let mut p = Cursor::new(input);
+    p.json = true;
+    p.skip_ws();
+    let value = p
+        .json_value()?
+        .ok_or_else(|| p.error("null is not allowed at the top level"))?;
+    p.skip_ws();
+    if p.peek().is_some() {
+        return Err(p.error("unexpected trailing characters"));
+    }
+    Ok((value, p.positions))
+}
+
+/// Serialize a TOML value tree as pretty-printed JSON (2-space indent).
+pub fn to_json(value: &Value) -> String {
+    let mut out = String::new();
+    write_json(value, 0, &mut out);
+    out.push('\n');
+    out
+}
+
+fn write_json(value: &Value, indent: usize, out: &mut String) {
+    let pad = "  ".repeat(indent + 1);
+    match value {
+        Value::Table(t) if t.is_empty() => out.push_str("{}"),
+        Value::Table(t) => {
+            out.push_str("{\n");
+            for (i, (k, v)) in t.iter().enumerate() {
+                out.push_str(&pad);
+                out.push_str(&quote(k));
+                out.push_str(": ");
+                write_json(v, indent + 1, out);
+                if i + 1 < t.len() {
+                    out.push(',');
+                }
+                out.push('\n');
+            }
+            out.push_str(&"  ".repeat(indent));
+            out.push('}');
+        }
+        Value::Array(a) if a.is_empty() => out.push_str("[]"),
+        Value::Array(a) => {
+            out.push_str("[\n");
+            for (i, v) in a.iter().enumerate() {
+                out.push_str(&pad);
+                write_json(v, indent + 1, out);
+                if i + 1 < a.len() {
+                    out.push(',');
+                }
+                out.push('\n');
+            }
+            out.push_str(&"  ".repeat(indent));
+            out.push(']');
+        }
+        other => out.push_str(&scalar_text(other)),
+    }
+}
+
+// ---------- YAML ----------
+
+/// Parse a YAML document (see module docs for the supported subset).
+pub fn parse_yaml(input: &str) -> Result<Value, String> {
+    parse_yaml_located(input).map(|(value, _)| value)
+}
+
+/// Parse a YAML document, also returning where each key starts.
+pub fn parse_yaml_located(input: &str) -> Result<(Value, Positions), String> {
+    let lines: Vec<YamlLine> = input
+        .lines()
+        .enumerate()
+        .filter_map(|(i, raw)| {
+            let text = strip_yaml_comment(raw).trim_end();
+            if text.trim().is_empty() || text.trim() == "---" {
+                return None;
+            }
+            let indent = text.len() - text.trim_start().len();
+            Some(YamlLine {
+                number: i + 1,
+                indent,
+                text: text.trim_start().to_string(),
+            })
+        })
+        .collect();
+
+    if lines.is_empty() {
+        return Ok((Value::Table(Map::new()), Positions::new()));
+    }
+
+    let mut parser = YamlParser {
+        lines,
+        pos: 0,
+        path: Vec::new(),
+        positions: Positions::new(),
+    };
+    let indent = parser.lines[0].indent;
+    let value = parser.block(indent)?;
+    if parser.lines.get(parser.pos).is_some() {
+        return Err(parser.error("unexpected indentation"));
+    }
+    let value = value.unwrap_or_else(|| Value::Table(Map::new()));
+    Ok((value, parser.positions))
+}
+
+/// Serialize a TOML value tree as block-style YAML.
+pub fn to_yaml(value: &Value) -> String {
+    let mut out = String::new();
+    match value {
+        Value::Table(t) if !t.is_empty() => write_yaml_table(t, 0, &mut out),
+        other => {
+            out.push_str(&yaml_inline(other));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn write_yaml_table(table: &Map<String, Value>, indent: usize, out: &mut String) {
+    let pad = " ".repeat(indent);
+    for (k, v) in table {
+        out.push_str(&pad);
+        out.push_str(&yaml_key(k));
+        out.push(':');
+        match v {
+            Value::Table(t) if !t.is_empty() => {
+                out.push('\n');
+                write_yaml_table(t, indent + 2, out);
+            }
+            Value::Array(a) if !a.is_empty() => {
+                out.push('\n');
+                write_yaml_array(a, indent + 2, out);
+            }
+            other => {
+                out.push(' ');
+                out.push_str(&yaml_inline(other));
+                out.push('\n');
+            }
+        }
+    }
+}
+
+fn write_yaml_array(array: &[Value], indent: usize, out: &mut String) {
+    let pad = " ".repeat(indent);
+    for v in array {
+        out.push_str(&pad);
+        out.push('-');
+        match v {
+            Value::Table(t) if !t.is_empty() => {
+                // First key goes on the dash line, the rest are indented under it
+                let mut nested = String::new();
+                write_yaml_table(t, indent + 2, &mut nested);
+                out.push(' ');
+                out.push_str(&nested[indent + 2..]);
+            }
+            Value::Array(a) if !a.is_empty() => {
+                out.push('\n');
+                write_yaml_array(a, indent + 2, out);
+            }
+            other => {
+                out.push(' ');
+                out.push_str(&yaml_inline(other));
+                out.push('\n');
+            }
+        }
+    }
+}
+
+/// Inline (flow) form of a value; used for scalars and empty collections.
+fn yaml_inline(value: &Value) -> String {
+    match value {
+        Value::Table(t) => {
+            let parts: Vec<String> = t
+                .iter()
+                .map(|(k, v)| format!("{}: {}", yaml_key(k), yaml_inline(v)))
+                .collect();
+            format!("{{{}}}", parts.join(", "))
+        }
+        Value::Array(a) => {
+            let parts: Vec<String> = a.iter().map(yaml_inline).collect();
+            format!("[{}]", parts.join(", "))
+        }
+        other => scalar_text(other),
+    }
+}
+
+/// Keys are written bare when that's unambiguous, quoted otherwise.
+fn yaml_key(key: &str) -> String {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare { key.to_string() } else { quote(key) }
+}
+
+/// One non-blank, comment-stripped YAML line.
+struct YamlLine {
+    number: usize,
+    indent: usize,
+    text: String,
+}
+
+/// Indentation-driven recursive parser over pre-split lines.
+struct YamlParser {
+    lines: Vec<YamlLine>,
+    pos: usize,
+    /// Keys leading to the value being parsed
+    path: Vec<String>,
+    positions: Positions,
+}
+
+impl YamlParser {
+    /// Error message positioned at the start of the current line's text.
+    fn error(&self, msg: &str) -> String {
+        let line = &self.lines[self.pos.min(self.lines.len() - 1)];
+        format!("{}:{}: {}", line.number, line.indent + 1, msg)
+    }
+
+    /// Record that the entry at `path` + `key` starts at `column` of the
+    /// current line.
+    fn locate(&mut self, key: String, column: usize) {
+        let mut path = self.path.clone();
+        path.push(key);
+        self.positions
+            .insert(path, (self.lines[self.pos].number, column));
+    }
+
+    /// Parse `text`, which starts at `column` of the current line, as a
+    /// scalar or flow collection belonging to `key`.
+    fn flow_value(
+        &mut self,
+        key: String,
+        text: &str,
+        column: usize,
+    ) -> Result<Option<Value>, String> {
+        let mut p = Cursor::new(text);
+        p.origin = (self.lines[self.pos].number, column);
+        p.path = self.path.clone();
+        p.path.push(key);
+        let value = p.yaml_flow_value()?;
+        p.skip_ws();
+        if p.peek().is_some() {
+            return Err(p.error("unexpected characters after value"));
+        }
+        self.positions.extend(p.positions);
+        Ok(value)
+    }
+
+    /// Parse the block (mapping or sequence) starting at the current line,
+    /// whose lines are indented exactly `indent` spaces.
+    fn block(&mut self, indent: usize) -> Result<Option<Value>, String> {
+        let Some(first) = self.lines.get(self.pos) else {
+            return Ok(None);
+        };
+        if first.text == "-" || first.text.starts_with("- ") {
+            self.sequence(indent).map(Some)
+        } else {
+            self.mapping(indent).map(Some)
+        }
+    }
+
+    fn sequence(&mut self, indent: usize) -> Result<Value, String> {
+        let mut items = Vec::new();
+        while let Some(line) = self.lines.get(self.pos) {
+            if line.indent != indent || !(line.text == "-" || line.text.starts_with("- ")) {
+                break;
+            }
+            let rest = line.text[1..].trim_start().to_string();
+            let dash_width = line.text.len() - rest.len();
+            let index = items.len().to_string();
+            self.locate(index.clone(), indent + 1);
+
+            if rest.is_empty() {
+                // "-" alone: the item is the nested block on the following lines
+                let empty = self.error("empty list item");
+                self.pos += 1;
+                self.path.push(index);
+                let item = match self.lines.get(self.pos) {
+                    Some(next) if next.indent > indent => {
+                        let child = next.indent;
+                        self.block(child)?
+                    }
+                    _ => None,
+                };
+                self.path.pop();
+                items.push(item.ok_or(empty)?);
+            } else if split_mapping_entry(&rest).is_some() && !rest.starts_with(['{', '[']) {
+                // "- key: value": a mapping whose first entry shares the dash line.
+                // Re-indent this line as if the dash were a space and parse a mapping.
+                self.lines[self.pos].indent = indent + dash_width;
+                self.lines[self.pos].text = rest;
+                self.path.push(index);
+                items.push(self.mapping(indent + dash_width)?);
+                self.path.pop();
+            } else {
+                let item = self
+                    .flow_value(index, &rest, indent + dash_width + 1)?
+                    .ok_or_else(|| self.error("null list items are not supported"))?;
+                self.pos += 1;
+                items.push(item);
+            }
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn mapping(&mut self, indent: usize) -> Result<Value, String> {
+        let mut table = Map::new();
+        while let Some(line) = self.lines.get(self.pos) {
+            if line.indent < indent {
+                break;
+            }
+            if line.indent > indent {
+                return Err(self.error("unexpected indentation"));
+            }
+            let (key, rest) = split_mapping_entry(&line.text)
+                .ok_or_else(|| self.error("expected 'key: value'"))?;
+            let key = unquote_key(&key).map_err(|e| self.error(&e))?;
+            let key_width = line.text[..line.text.len() - rest.len()].chars().count();
+            self.locate(key.clone(), indent + 1);
+
+            let value = if rest.is_empty() {
+                // Nested block: deeper-indented lines, or a sequence at the same indent
+                self.pos += 1;
+                self.path.push(key.clone());
+                let value = match self.lines.get(self.pos) {
+                    Some(next) if next.indent > indent => {
+                        let child = next.indent;
+                        self.block(child)?
+                    }
+                    Some(next)
+                        if next.indent == indent
+                            && (next.text == "-" || next.text.starts_with("- ")) =>
+                    {
+                        Some(self.sequence(indent)?)
+                    }
+                    _ => None,
+                };
+                self.path.pop();
+                value
+            } else {
+                let value = self.flow_value(key.clone(), &rest, indent + key_width + 1)?;
+                self.pos += 1;
+                value
+            };
+
+            // Null values are dropped: every config field is optional
+            if let Some(v) = value {
+                table.insert(key, v);
+            }
+        }
+        Ok(Value::Table(table))
+    }
+}
+
+/// Split `key: value` (or `key:`) at the first mapping colon outside quotes.
+fn split_mapping_entry(text: &str) -> Option<(String, String)> {
+    let mut quote_char: Option<char> = None;
+    for (i, c) in text.char_indices() {
+        match (quote_char, c) {
+            (Some(q), c) if c == q => quote_char = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') if i == 0 => quote_char = Some(c),
+            (None, '[' | '{') if i == 0 => return None,
+            (None, ':') => {
+                let after = &text[i + 1..];
+                if after.is_empty() || after.starts_with(' ') {
+                    return Some((text[..i].trim().to_string(), after.trim().to_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn unquote_key(key: &str) -> Result<String, String> {
+    let Some(q @ ('"' | '\'')) = key.chars().next() else {
+        return Ok(key.to_string());
+    };
+    let mut p = Cursor::new(key);
+    match p.string(q) {
+        Ok(s) if p.peek().is_none() => Ok(s),
+        _ => Err(format!("invalid key {}", key)),
+    }
+}
+
+/// Remove a trailing `# comment` (only when `#` starts a word and isn't quoted).
+fn strip_yaml_comment(line: &str) -> &str {
+    let mut quote_char: Option<char> = None;
+    let mut prev = ' ';
+    for (i, c) in line.char_indices() {
+        match (quote_char, c) {
+            (Some(q), c) if c == q => quote_char = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote_char = Some(c),
+            (None, '#') if prev.is_whitespace() => return &line[..i],
+            _ => {}
+        }
+        prev = c;
+    }
+    line
+}
+
+/// Interpret an unquoted YAML scalar (bool, null, number, or plain string).
+fn plain_scalar(text: &str) -> Option<Value> {
+    match text {
+        "" | "~" | "null" | "Null" | "NULL" => None,
+        "true" | "True" | "TRUE" => Some(Value::Boolean(true)),
+        "false" | "False" | "FALSE" => Some(Value::Boolean(false)),
+        _ => Some(number_value(text).unwrap_or_else(|| Value::String(text.to_string()))),
+    }
+}
+
+// ---------- Shared helpers ----------
+
+/// Parse a JSON/YAML number, keeping integers as integers.
+fn number_value(text: &str) -> Option<Value> {
+    if let Ok(i) = text.parse::<i64>() {
+        return Some(Value::Integer(i));
+    }
+    let looks_numeric = text
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'));
+    if looks_numeric && text.chars().any(|c| c.is_ascii_digit()) {
+        text.parse::<f64>().ok().map(Value::Float)
+    } else {
+        None
+    }
+}
+
+/// Text of a scalar value, valid in both JSON and YAML.
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => quote(s),
+        Value::Integer(i) => i.to_string(),
+        // Debug formatting keeps a ".0" on whole floats so they stay floats
+        Value::Float(f) => format!("{:?}", f),
+        Value::Boolean(b) => b.to_string(),
+        Value::Datetime(d) => quote(&d.to_string()),
+        Value::Table(_) | Value::Array(_) => String::new(),
+    }
+}
+
+/// Double-quote a string with JSON escapes (also valid YAML).
+pub fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Whether `text` is a number as JSON spells it:
+/// `-?(0|[1-9][0-9]*)(\.[0-9]+)?([eE][+-]?[0-9]+)?`.
+fn is_json_number(text: &str) -> bool {
+    fn digits(s: &str) -> (&str, &str) {
+        let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        s.split_at(end)
+    }
+    let rest = text.strip_prefix('-').unwrap_or(text);
+    let (int, mut rest) = digits(rest);
+    if int.is_empty() || (int.len() > 1 && int.starts_with('0')) {
+        return false;
+    }
+    if let Some(after) = rest.strip_prefix('.') {
+        let (frac, after) = digits(after);
+        if frac.is_empty() {
+            return false;
+        }
+        rest = after;
+    }
+    if let Some(after) = rest.strip_prefix(['e', 'E']) {
+        let (exp, after) = digits(after.strip_prefix(['+', '-']).unwrap_or(after));
+        if exp.is_empty() {
+            return false;
+        }
+        rest = after;
+    }
+    rest.is_empty()
+}
+
+/// Character cursor shared by the JSON parser and YAML flow-value parser.
+struct Cursor<'a> {
+    input: &'a str,
+    pos: usize,
+    /// Collections currently open around the position
+    depth: usize,
+    /// Strict JSON whitespace and strings rather than YAML's looser ones
+    json: bool,
+    /// Line and column the input starts at (YAML values start mid-line)
+    origin: (usize, usize),
+    /// Keys leading to the value being parsed
+    path: Vec<String>,
+    positions: Positions,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            pos: 0,
+            depth: 0,
+            json: false,
+            origin: (1, 1),
+            path: Vec::new(),
+            positions: Positions::new(),
+        }
+    }
+
+    /// 1-based line and column of the current position.
+    fn position(&self) -> (usize, usize) {
+        let before = &self.input[..self.pos];
+        let line = before.matches('\n').count();
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count();
+        match line {
+            0 => (self.origin.0, self.origin.1 + column),
+            _ => (self.origin.0 + line, column + 1),
+        }
+    }
+
+    /// Parse the value of `key` (a name or array index) starting at the
+    /// current position, recording where it starts.
+    fn entry(
+        &mut self,
+        key: String,
+        at: (usize, usize),
+        parse: fn(&mut Self) -> Result<Option<Value>, String>,
+    ) -> Result<Option<Value>, String> {
+        self.path.push(key);
+        self.positions.insert(self.path.clone(), at);
+        let value = self.nested(parse);
+        self.path.pop();
+        value
+    }
+
+    /// Parse a value one collection deeper, unless that's too deep.
+    fn nested(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<Option<Value>, String>,
+    ) -> Result<Option<Value>, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(&format!("nested more than {} deep", MAX_DEPTH)));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_ws(&mut self) {
+        let json = self.json;
+        let space = |c: char| {
+            if json {
+                matches!(c, ' ' | '\t' | '\n' | '\r')
+            } else {
+                c.is_whitespace()
+            }
+        };
+        while self.peek().is_some_and(space) {
+            self.bump();
+        }
+    }
+
+    fn expect(&mut self, want: char) -> Result<(), String> {
+        match self.bump() {
+            Some(c) if c == want => Ok(()),
+            Some(c) => Err(self.error(&format!("expected '{}', found '{}'", want, c))),
+            None => Err(self.error(&format!("expected '{}', found end of input", want))),
+        }
+    }
+
+    /// Error message with a 1-based line:column position.
+    fn error(&self, msg: &str) -> String {
+        let (line, column) = self.position();
+        format!("{}:{}: {}", line, column, msg)
+    }
+
+    /// Parse one JSON value. Returns `None` for `null`.
+    fn json_value(&mut self) -> Result<Option<Value>, String> {
+        self.skip_ws();
+        match self.peek() {
+            Some('{') => {
+                self.bump();
+                let mut table = Map::new();
+                self.skip_ws();
+                if self.peek() == Some('}') {
+                    self.bump();
+                    return Ok(Some(Value::Table(table)));
+                }
+                loop {
+                    self.skip_ws();
+                    let at = self.position();
+                    let key = self.string('"')?;
+                    self.skip_ws();
+                    self.expect(':')?;
+                    if let Some(v) = self.entry(key.clone(), at, Self::json_value)? {
+                        table.insert(key, v);
+                    }
+                    self.skip_ws();
+                    match self.bump() {
+                        Some(',') => continue,
+                        Some('}') => return Ok(Some(Value::Table(table))),
+                        _ => return Err(self.error("expected ',' or '}'")),
+                    }
+                }
+            }
+            Some('[') => {
+                self.bump();
+                let mut items = Vec::new();
+                self.skip_ws();
+                if self.peek() == Some(']') {
+                    self.bump();
+                    return Ok(Some(Value::Array(items)));
+                }
+                loop {
+                    self.skip_ws();
+                    let at = self.position();
+                    let item = self
+                        .entry(items.len().to_string(), at, Self::json_value)?
+                        .ok_or_else(|| self.error("null array items are not supported"))?;
+                    items.push(item);
+                    self.skip_ws();
+                    match self.bump() {
+                        Some(',') => continue,
+                        Some(']') => return Ok(Some(Value::Array(items))),
+                        _ => return Err(self.error("expected ',' or ']'")),
+                    }
+                }
+            }
+            Some('"') => Ok(Some(Value::String(self.string('"')?))),
+            Some(_) => {
+                let word = self.take_while(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+                match word {
+                    "true" => Ok(Some(Value::Boolean(true))),
+                    "false" => Ok(Some(Value::Boolean(false))),
+                    "null" => Ok(None),
+                    _ => number_value(word)
+                        .filter(|_| is_json_number(word))
+                        .map(Some)
+                        .ok_or_else(|| self.error(&format!("invalid value '{}'", word))),
+                }
+            }
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    /// Parse a YAML flow value: `[...]`, `{...}`, a quoted string, or a plain scalar.
+    fn yaml_flow_value(&mut self) -> Result<Option<Value>, String> {
+        self.skip_ws();
+        match self.peek() {
+            Some('[') => {
+                self.bump();
+                let mut items = Vec::new();
+                loop {
+                    self.skip_ws();
+                    if self.peek() == Some(']') {
+                        self.bump();
+                        return Ok(Some(Value::Array(items)));
+                    }
+                    let at = self.position();
+                    if let Some(v) =
+                        self.entry(items.len().to_string(), at, Self::yaml_flow_value)?
+                    {
+                        items.push(v);
+                    }
+                    self.skip_ws();
+                    match self.bump() {
+                        Some(',') => continue,
+                        Some(']') => return Ok(Some(Value::Array(items))),
+                        _ => return Err(self.error("expected ',' or ']'")),
+                    }
+                }
+            }
+            Some('{') => {
+                self.bump();
+                let mut table = Map::new();
+                loop {
+                    self.skip_ws();
+                    if self.peek() == Some('}') {
+                        self.bump();
+                        return Ok(Some(Value::Table(table)));
+                    }
+                    let at = self.position();
+                    let key = match self.peek() {
+                        Some(q @ ('"' | '\'')) => self.string(q)?,
+                        _ => self.take_while(|c| c != ':').trim().to_string(),
+                    };
+                    self.skip_ws();
+                    self.expect(':')?;
+                    if let Some(v) = self.entry(key.clone(), at, Self::yaml_flow_value)? {
+                        table.insert(key, v);
+                    }
+                    self.skip_ws();
+                    match self.bump() {
+                        Some(',') => continue,
+                        Some('}') => return Ok(Some(Value::Table(table))),
+                        _ => return Err(self.error("expected ',' or '}'")),
+                    }
+                }
+            }
+            Some(q @ ('"' | '\'')) => Ok(Some(Value::String(self.string(q)?))),
+            _ => {
+                let word = self.take_while(|c| !",]}".contains(c)).trim();
+                Ok(plain_scalar(word))
+            }
+        }
+    }
+
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        while self.peek().is_some_and(&pred) {
+            self.bump();
+        }
+        &self.input[start..self.pos]
+    }
+
+    /// Parse a quoted string. Double quotes use JSON escapes; single quotes
+    /// (YAML) only escape `''` as a literal quote.
+    fn string(&mut self, quote_char: char) -> Result<String, String> {
+        self.expect(quote_char)?;
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                None => return Err(self.error("unterminated string")),
+                Some(c) if c == quote_char => {
+                    if quote_char == '\'' && self.peek() == Some('\'') {
+                        self.bump();
+                        out.push('\'');
+                        continue;
+                    }
+                    return Ok(out);
+                }
+                Some('\\') if quote_char == '"' => {
+                    let escaped = match self.bump() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('/') => '/',
+                        Some('\\') => '\\',
+                        Some('"') => '"',
+                        Some('u') => self.unicode_escape()?,
+                        _ => return Err(self.error("invalid escape sequence")),
+                    };
+                    out.push(escaped);
+                }
+                Some(c) if self.json && c < ' ' => {
+                    return Err(self.error("control characters in strings must be escaped"));
+                }
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    /// The character after a `\u`: four hex digits, and for characters
+    /// outside the Basic Multilingual Plane a second `\u` with the low half
+    /// of the UTF-16 surrogate pair.
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.hex4()?;
+        let code = match high {
+            0xD800..=0xDBFF => {
+                if self.bump() != Some('\\') || self.bump() != Some('u') {
+                    return Err(self.error("unpaired surrogate in \\u escape"));
+                }
+                let low = self.hex4()?;
+                if !(0xDC00..=0xDFFF).contains(&low) {
+                    return Err(self.error("unpaired surrogate in \\u escape"));
+                }
+                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+            }
+            code => code,
+        };
+        char::from_u32(code).ok_or_else(|| self.error("unpaired surrogate in \\u escape"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let start = self.pos;
+        for _ in 0..4 {
+            if !self.bump().is_some_and(|c| c.is_ascii_hexdigit()) {
+                return Err(self.error("invalid \\u escape"));
+            }
+        }
+        u32::from_str_radix(&self.input[start..self.pos], 16)
+            .map_err(|_| self.error("invalid \\u escape"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(v: &Value) -> &Map<String, Value> {
+        v.as_table().expect("expected table")
+    }
+
+    #[test]
+    fn json_parses_nested_objects_and_drops_null() {
+        let v = parse_json(
+            r#"{"defaults": {"effect": "fire", "speed": 1.5, "fps": 60, "crt": true, "color": null},
+                "presets": {}}"#,
+        )
+        .unwrap();
+        let defaults = table(&v["defaults"]);
+        assert_eq!(defaults["effect"].as_str(), Some("fire"));
+        assert_eq!(defaults["speed"].as_float(), Some(1.5));
+        assert_eq!(defaults["fps"].as_integer(), Some(60));
+        assert_eq!(defaults["crt"].as_bool(), Some(true));
+        assert!(!defaults.contains_key("color"));
+    }
+
+    #[test]
+    fn json_reports_error_position() {
+        let err = parse_json("{\n  \"a\": tru\n}").unwrap_err();
+        assert!(err.starts_with("2:"), "unexpected error: {}", err);
+    }
+
+    #[test]
+    fn json_follows_the_rfc_and_limits_nesting() {
+        let v = parse_json(r#"{"s": "\uD83D\uDE00 \u00e9", "n": [-0.5, 1e3, 0]}"#).unwrap();
+        assert_eq!(v["s"].as_str(), Some("\u{1F600} \u{e9}"));
+        assert_eq!(v["n"][1].as_float(), Some(1000.0));
+        for bad in [
+            "+1",
+            ".5",
+            "1.",
+            "01",
+            "-",
+            "1e",
+            r#""\uD83D""#,
+            r#""\u12""#,
+            "\"a\tb\"",
+        ] {
+            assert!(parse_json(bad).is_err(), "accepted {}", bad);
+        }
+
+        // Deep nesting is an error rather than a stack overflow
+        let deep = |n: usize| format!("{{\"a\":{}1{}}}", "[".repeat(n), "]".repeat(n));
+        assert!(parse_json(&deep(MAX_DEPTH - 1)).is_ok());
+        let err = parse_json(&deep(200_000)).unwrap_err();
+        assert!(err.ends_with("nested more than 64 deep"), "{}", err);
+        assert!(
+            parse_yaml(&format!(
+                "a: {}1{}",
+                "[".repeat(200_000),
+                "]".repeat(200_000)
+            ))
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn json_roundtrip_preserves_values() {
+        let v = parse_json(r#"{"a": {"s": "x\"y", "n": [1, 2.5, false]}}"#).unwrap();
+        let again = parse_json(&to_json(&v)).unwrap();
+        assert_eq!(v, again);
+    }
+
+    #[test]
+    fn yaml_parses_mappings_sequences_and_comments() {
+        let v = parse_yaml(
+            r#"
+# top comment
+defaults:
+  effect: fire   # trailing comment
+  speed: 1.5
+  crt: yes-not-bool
+presets:
+  "night mode":
+    color: 'it''s'
+playlists:
+  evening:
+    entries:
+      - preset: retro
+        duration: 60
+      - { preset: fire, duration: 5 }
+"#,
+        )
+        .unwrap();
+        let defaults = table(&v["defaults"]);
+        assert_eq!(defaults["effect"].as_str(), Some("fire"));
+        assert_eq!(defaults["speed"].as_float(), Some(1.5));
+        assert_eq!(defaults["crt"].as_str(), Some("yes-not-bool"));
+        assert_eq!(v["presets"]["night mode"]["color"].as_str(), Some("it's"));
+
+        let entries = v["playlists"]["evening"]["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["preset"].as_str(), Some("retro"));
+        assert_eq!(entries[0]["duration"].as_integer(), Some(60));
+        assert_eq!(entries[1]["preset"].as_str(), Some("fire"));
+    }
+
+    #[test]
+    fn yaml_sequence_at_same_indent_as_key() {
+        let v = parse_yaml("list:\n- 1\n- two\nafter: true\n").unwrap();
+        let list = v["list"].as_array().unwrap();
+        assert_eq!(list[0].as_integer(), Some(1));
+        assert_eq!(list[1].as_str(), Some("two"));
+        assert_eq!(v["after"].as_bool(), Some(true));
+    }
+
+    #[test]
+    fn yaml_rejects_bad_indentation() {
+        let err = parse_yaml("a: 1\n    b: 2\n").unwrap_err();
+        assert_eq!(error_position(&err), Some((2, 5, "unexpected indentation")));
+    }
+
+    #[test]
+    fn readers_report_where_keys_start() {
+        let key = |k: &[&str]| k.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let (_, at) = parse_yaml_located("a:\n  - x: 1\n  - {y: [2, 3]}\n").unwrap();
+        assert_eq!(at[&key(&["a"])], (1, 1));
+        assert_eq!(at[&key(&["a", "0", "x"])], (2, 5));
+        assert_eq!(at[&key(&["a", "1"])], (3, 3));
+        assert_eq!(at[&key(&["a", "1", "y", "1"])], (3, 13));
+
+        let (_, at) = parse_json_located("{\"a\": [\n  {\"b\": 1}]}").unwrap();
+        assert_eq!(at[&key(&["a", "0"])], (2, 3));
+        assert_eq!(at[&key(&["a", "0", "b"])], (2, 4));
+    }
+
+    #[test]
+    fn yaml_roundtrip_preserves_values() {
+        let v = parse_json(
+            r#"{"defaults": {"speed": 1.0, "effect": "classic"},
+                "playlists": {"p": {"entries": [{"preset": "a", "duration": 5}, {"preset": "b"}]}},
+                "presets": {"x y": {}}}"#,
+        )
+        .unwrap();
+        let yaml = to_yaml(&v);
+        let again = parse_yaml(&yaml).unwrap();
+        assert_eq!(v, again, "yaml was:\n{}", yaml);
+    }
+}
@@ -0,0 +1,276 @@
+//! Minimal animated GIF (GIF89a) encoder.
+//!
+//! Frames are 8-bit palette indices against a single global 256-color
+//! palette (3-3-2 RGB: 8 red x 8 green x 4 blue levels). Green gets the most
+//! resolution after red, which suits the mostly-green rain. Image data is
+//! LZW-compressed per the GIF spec; the animation loops forever.
+
+use std::io::{self, Write};
+
+/// Maximum LZW code width allowed by GIF.
+const MAX_CODE_BITS: u32 = 12;
+/// Palette indices are 8 bits, so codes start at 9 bits.
+const MIN_CODE_SIZE: u8 = 8;
+const CLEAR_CODE: u16 = 1 << MIN_CODE_SIZE;
+const END_CODE: u16 = CLEAR_CODE + 1;
+
+/// Map an RGB color to its 3-3-2 palette index.
+pub fn palette_index(r: u8, g: u8, b: u8) -> u8 {
+    (r & 0xE0) | ((g & 0xE0) >> 3) | (b >> 6)
+}
+
+/// The 768-byte global color table matching `palette_index`.
+fn palette_table() -> Vec<u8> {
+    let mut table = Vec::with_capacity(768);
+    for i in 0..=255u8 {
+        // Spread each channel's levels across the full 0-255 range
+        let r = (i >> 5) as u32 * 255 / 7;
+        let g = ((i >> 2) & 0x07) as u32 * 255 / 7;
+        let b = (i & 0x03) as u32 * 255 / 3;
+        table.extend_from_slice(&[r as u8, g as u8, b as u8]);
+    }
+    table
+}
+
+/// Streams frames into a looping animated GIF.
+pub struct GifEncoder<W: Write> {
+    out: W,
+    width: u16,
+    height: u16,
+}
+
+impl<W: Write> GifEncoder<W> {
+    /// Write the header, global palette, and loop extension.
+    pub fn new(mut out: W, width: u16, height: u16) -> io::Result<Self> {
+        out.write_all(b"GIF89a")?;
+        out.write_all(&width.to_le_bytes())?;
+        out.write_all(&height.to_le_bytes())?;
+        // Global color table present, 8-bit color resolution, 256 entries
+        out.write_all(&[0xF7, 0, 0])?;
+        out.write_all(&palette_table())?;
+        // NETSCAPE2.0 application extension: loop forever
+        out.write_all(&[0x21, 0xFF, 0x0B])?;
+        out.write_all(b"NETSCAPE2.0")?;
+        out.write_all(&[0x03, 0x01, 0x00, 0x00, 0x00])?;
+        Ok(Self { out, width, height })
+    }
+
+    /// Append one frame of palette indices (row-major, `width * height` long)
+    /// shown for `delay_cs` hundredths of a second.
+    pub fn add_frame(&mut self, indices: &[u8], delay_cs: u16) -> io::Result<()> {
+        debug_assert_eq!(indices.len(), self.width as usize * self.height as usize);
+
+        // Graphic control extension: no disposal, delay, no transparency
+        self.out.write_all(&[0x21, 0xF9, 0x04, 0x04])?;
+        self.out.write_all(&delay_cs.to_le_bytes())?;
+        self.out.write_all(&[0x00, 0x00])?;
+
+        // Image descriptor: full-canvas frame, no local palette
+        self.out.write_all(&[0x2C, 0, 0, 0, 0])?;
+        self.out.write_all(&self.width.to_le_bytes())?;
+        self.out.write_all(&self.height.to_le_bytes())?;
+        self.out.write_all(&[0x00, MIN_CODE_SIZE])?;
+
+        // LZW data in sub-blocks of at most 255 bytes
+        let data = lzw_encode(indices);
+        for block in data.chunks(255) {
+            self.out.write_all(&[block.len() as u8])?;
+            self.out.write_all(block)?;
+        }
+        self.out.write_all(&[0x00])
+    }
+
+    /// Write the trailer and return the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.write_all(&[0x3B])?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/// Packs variable-width codes LSB-first, as GIF requires.
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, width: u32) {
+        self.acc |= (code as u32) << self.bits;
+        self.bits += width;
+        while self.bits >= 8 {
+            self.bytes.push(self.acc as u8);
+            self.acc >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.acc as u8);
+        }
+        self.bytes
+    }
+}
+
+/// LZW-compress palette indices into a GIF code stream.
+///
+/// The code width grows when the next free code no longer fits, and the
+/// dictionary is reset with a clear code once it's full (4096 entries).
+fn lzw_encode(indices: &[u8]) -> Vec<u8> {
+    use std::collections::HashMap;
+
+    let mut writer = BitWriter {
+        bytes: Vec::new(),
+        acc: 0,
+        bits: 0,
+    };
+    let mut dict: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut code_size = MIN_CODE_SIZE as u32 + 1;
+    let mut next_code = END_CODE + 1;
+
+    writer.write(CLEAR_CODE, code_size);
+
+    let Some((&first, rest)) = indices.split_first() else {
+        writer.write(END_CODE, code_size);
+        return writer.finish();
+    };
+
+    let mut prefix = first as u16;
+    for &k in rest {
+        if let Some(&code) = dict.get(&(prefix, k)) {
+            prefix = code;
+            continue;
+        }
+
+        writer.write(prefix, code_size);
+        if next_code < (1 << MAX_CODE_BITS) {
+            dict.insert((prefix, k), next_code);
+            next_code += 1;
+            // The decoder widens once the table outgrows the current width
+            if next_code > (1 << code_size) && code_size < MAX_CODE_BITS {
+                code_size += 1;
+            }
+        } else {
+            writer.write(CLEAR_CODE, code_size);
+            dict.clear();
+            next_code = END_CODE + 1;
+            code_size = MIN_CODE_SIZE as u32 + 1;
+        }
+        prefix = k as u16;
+    }
+
+    writer.write(prefix, code_size);
+    writer.write(END_CODE, code_size);
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reference GIF LZW decoder, used to check the encoder round-trips.
+    fn lzw_decode(data: &[u8]) -> Vec<u8> {
+        let mut pos = 0usize; // bit position
+        let read = |pos: &mut usize, width: u32| -> u16 {
+            let mut code = 0u32;
+            for i in 0..width {
+                let bit = (data[*pos / 8] >> (*pos % 8)) & 1;
+                code |= (bit as u32) << i;
+                *pos += 1;
+            }
+            code as u16
+        };
+
+        let mut out = Vec::new();
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut width = MIN_CODE_SIZE as u32 + 1;
+        let mut prev: Option<Vec<u8>> = None;
+        let reset = |table: &mut Vec<Vec<u8>>| {
+            table.clear();
+            table.extend((0..=255u8).map(|i| vec![i]));
+            table.push(Vec::new());
+            table.push(Vec::new());
+        };
+        reset(&mut table);
+
+        loop {
+            let code = read(&mut pos, width);
+            if code == CLEAR_CODE {
+                reset(&mut table);
+                width = MIN_CODE_SIZE as u32 + 1;
+                prev = None;
+                continue;
+            }
+            if code == END_CODE {
+                break;
+            }
+            let entry = if (code as usize) < table.len() {
+                table[code as usize].clone()
+            } else {
+                let mut e = prev.clone().unwrap();
+                e.push(e[0]);
+                e
+            };
+            out.extend_from_slice(&entry);
+            if let Some(mut p) = prev {
+                if table.len() < 4096 {
+                    p.push(entry[0]);
+                    table.push(p);
+                }
+                if table.len() == (1 << width) && width < MAX_CODE_BITS {
+                    width += 1;
+                }
+            }
+            prev = Some(entry);
+        }
+        out
+    }
+
+    #[test]
+    fn lzw_roundtrips_small_and_repetitive_input() {
+        for input in [
+            vec![],
+            vec![7],
+            vec![1, 1, 1, 1, 1, 1, 1, 1, 1],
+            (0..=255).collect::<Vec<u8>>(),
+        ] {
+            assert_eq!(lzw_decode(&lzw_encode(&input)), input);
+        }
+    }
+
+    #[test]
+    fn lzw_roundtrips_past_dictionary_reset() {
+        // Pseudo-random data fills the 4096-entry table several times over
+        let mut x: u32 = 12345;
+        let input: Vec<u8> = (0..60_000)
+            .map(|_| {
+                x = x.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (x >> 16) as u8 & 0x3F
+            })
+            .collect();
+        assert_eq!(lzw_decode(&lzw_encode(&input)), input);
+    }
+
+    #[test]
+    fn palette_index_matches_table() {
+        let table = palette_table();
+        let idx = palette_index(255, 255, 255) as usize;
+        assert_eq!(&table[idx * 3..idx * 3 + 3], &[255, 255, 255]);
+        assert_eq!(palette_index(0, 0, 0), 0);
+        // Pure green keeps full green resolution
+        let g = palette_index(0, 255, 0) as usize;
+        assert_eq!(&table[g * 3..g * 3 + 3], &[0, 255, 0]);
+    }
+
+    #[test]
+    fn encoder_writes_header_and_trailer() {
+        let mut enc = GifEncoder::new(Vec::new(), 2, 2).unwrap();
+        enc.add_frame(&[0, 1, 2, 3], 5).unwrap();
+        let bytes = enc.finish().unwrap();
+        assert!(bytes.starts_with(b"GIF89a"));
+        assert_eq!(&bytes[6..10], &[2, 0, 2, 0]);
+        assert_eq!(*bytes.last().unwrap(), 0x3B);
+    }
+}
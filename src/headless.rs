@@ -0,0 +1,674 @@
+//! Headless frame export: run the simulation without a terminal and write
+//! each frame to disk (`--render-frames N --out DIR`).
+//!
+//! Frames are rendered into a plain `ScreenBuffer` and saved as standalone
+//! ANSI text (`frame_00001.ans`), plus plain text (`frame_00001.txt`) with
+//! `--plain-text`. Time advances by a fixed `1 / fps` step per frame, so with
+//! `--seed` the output is fully reproducible.
+//!
+//! `--export-gif` uses the same fixed-step loop but rasterizes each frame with
+//! the embedded bitmap font and encodes the result as an animated GIF.
+//!
+//! `--export-png` rasterizes a single frame the same way, in full color,
+//! as a PNG; `--export-svg` writes that frame as vector text instead.
+//!
+//! The interactive screenshot keys write the live frame in the same two
+//! text formats, or as a PNG, under a timestamped name.
+//!
+//! `render_frames` does the same in memory for library users and snapshot
+//! tests: seeded frames of any effect, read back with `to_plain_string` or
+//! `to_ansi_string`.
+//!
+//! `smoke_test` (`--headless --frames N`) is an end-to-end check for CI:
+//! every effect at a few sizes, through the CRT filter and a transition,
+//! into memory. A panic fails the run it happened in, not the whole check.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::{Parser, ValueEnum};
+
+use crate::buffer::ScreenBuffer;
+use crate::color::Color;
+use crate::color::gradient::color_to_rgb;
+use crate::config::{Cli, Config, ConfigFile};
+use crate::crt::{CrtFilter, DEFAULT_VIGNETTE_RADIUS, DEFAULT_VIGNETTE_STRENGTH};
+use crate::effects::registry;
+use crate::font;
+use crate::gif::{self, GifEncoder};
+use crate::png;
+use crate::post::PostProcessor;
+use crate::runner::Runner;
+use crate::svg;
+use crate::transition::{Transition, TransitionKind};
+
+/// Frame size used when no terminal is attached to query.
+const FALLBACK_SIZE: (u16, u16) = (80, 24);
+
+/// How long `--export-png` and `--export-svg` run the effect when no `--duration` is given,
+/// so the rain has filled the screen.
+pub const STILL_WARMUP_SECS: f64 = 3.0;
+
+/// Pixel size of one terminal cell in exported GIFs and PNGs. Glyphs are drawn at 2x
+/// with a one-pixel margin, leaving gaps between columns and rows.
+const CELL_WIDTH: usize = 8;
+const CELL_HEIGHT: usize = 12;
+const GLYPH_SCALE: usize = 2;
+
+/// Foreground used for cells left at the terminal's default color.
+const DEFAULT_FG: (u8, u8, u8) = (204, 204, 204);
+
+/// The current terminal size, or 80x24 if there is none.
+pub fn terminal_size() -> (u16, u16) {
+    #[cfg(feature = "terminal")]
+    return crossterm::terminal::size().unwrap_or(FALLBACK_SIZE);
+    #[cfg(not(feature = "terminal"))]
+    FALLBACK_SIZE
+}
+
+/// Options for a headless export run.
+pub struct ExportOptions<'a> {
+    pub frames: u32,
+    pub out_dir: &'a Path,
+    /// Also write a `.txt` file per frame with colors stripped
+    pub plain_text: bool,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl<'a> ExportOptions<'a> {
+    /// Export options sized to the current terminal (or 80x24 if there is none).
+    pub fn for_current_terminal(frames: u32, out_dir: &'a Path, plain_text: bool) -> Self {
+        let (width, height) = terminal_size();
+        Self {
+            frames,
+            out_dir,
+            plain_text,
+            width,
+            height,
+        }
+    }
+}
+
+/// Render `options.frames` frames of `config`'s effect into `options.out_dir`.
+///
+/// Returns the number of files written.
+pub fn export_frames(config: &Config, options: &ExportOptions) -> Result<usize, String> {
+    let (width, height) = (options.width, options.height);
+    fs::create_dir_all(options.out_dir).map_err(|e| {
+        format!(
+            "Could not create output directory {}: {}",
+            options.out_dir.display(),
+            e
+        )
+    })?;
+
+    let mut runner = Runner::new(config, width, height)?;
+    let delta_time = 1.0 / config.target_fps as f64;
+    let mut written = 0;
+
+    for frame in 1..=options.frames {
+        let buffer = runner.step(delta_time);
+
+        write_ansi_file(buffer, &frame_path(options.out_dir, frame, "ans"))?;
+        written += 1;
+
+        if options.plain_text {
+            write_text_file(buffer, &frame_path(options.out_dir, frame, "txt"))?;
+            written += 1;
+        }
+    }
+
+    Ok(written)
+}
+
+/// Size of the frames `render_frames` produces.
+pub const SNAPSHOT_SIZE: (u16, u16) = (40, 12);
+
+/// The first `frames` frames of `effect` with default settings and `seed`,
+/// at `SNAPSHOT_SIZE` and 30 fps. No terminal is needed and the result is
+/// the same on every run, so it suits snapshot tests:
+///
+/// ```
+/// use digitalrain::headless::render_frames;
+///
+/// let frames = render_frames("binary", 3, 7);
+/// assert_eq!(frames[2].to_ansi_string(), render_frames("binary", 3, 7)[2].to_ansi_string());
+/// ```
+///
+/// Panics on an unknown effect name.
+pub fn render_frames(effect: &str, frames: usize, seed: u64) -> Vec<ScreenBuffer> {
+    let mut config = Config::resolve(&Cli::parse_from(["digital_rain"]), &ConfigFile::default());
+    config.effect_name = effect.to_string();
+    config.seed = Some(seed);
+    let (width, height) = SNAPSHOT_SIZE;
+    let mut runner = Runner::new(&config, width, height).unwrap_or_else(|e| panic!("{}", e));
+    (0..frames)
+        .map(|_| runner.step(1.0 / 30.0).clone())
+        .collect()
+}
+
+/// Screen sizes `smoke_test` runs each effect at: tiny, a standard
+/// terminal, and a large one.
+pub const SMOKE_SIZES: [(u16, u16); 3] = [(3, 2), (80, 24), (200, 60)];
+
+/// Run every registered effect for `frames` frames at each of
+/// `SMOKE_SIZES`, with `config`'s settings and a fixed 30 fps step. Each
+/// frame is rendered into a buffer, put through the CRT filter (with every
+/// sub-effect on), and diffed as if flushed. A third of the way in the
+/// effect switches to the next one in the registry with a transition, each
+/// run using a different style.
+///
+/// Writes a line per run to `log` and returns how many failed: an effect
+/// that couldn't be created, or a panic.
+pub fn smoke_test(config: &Config, frames: u32, log: &mut impl Write) -> io::Result<usize> {
+    let names = registry::effect_names();
+    let styles: Vec<TransitionKind> = TransitionKind::value_variants()
+        .iter()
+        .copied()
+        .filter(|&kind| kind != TransitionKind::Random)
+        .collect();
+    let mut failures = 0;
+    let mut run = 0;
+    for (i, name) in names.iter().enumerate() {
+        let next = names[(i + 1) % names.len()];
+        for (width, height) in SMOKE_SIZES {
+            let style = styles[run % styles.len()];
+            run += 1;
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                smoke_run(config, name, next, style, width, height, frames)
+            }))
+            .unwrap_or_else(|payload| {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .copied()
+                    .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                    .unwrap_or("unknown panic");
+                Err(format!("panicked: {}", message))
+            });
+            let style_name = style
+                .to_possible_value()
+                .map(|v| v.get_name().to_string())
+                .unwrap_or_default();
+            let label = format!(
+                "{} -> {} ({}) at {}x{}",
+                name, next, style_name, width, height
+            );
+            match result {
+                Ok(()) => writeln!(log, "ok    {}", label)?,
+                Err(e) => {
+                    failures += 1;
+                    writeln!(log, "FAIL  {}: {}", label, e)?;
+                }
+            }
+        }
+    }
+    writeln!(
+        log,
+        "{} of {} runs passed ({} frames each)",
+        run - failures,
+        run,
+        frames
+    )?;
+    Ok(failures)
+}
+
+/// One `smoke_test` run: `name`, then `next` via a `style` transition.
+fn smoke_run(
+    config: &Config,
+    name: &str,
+    next: &str,
+    style: TransitionKind,
+    width: u16,
+    height: u16,
+    frames: u32,
+) -> Result<(), String> {
+    let delta_time = 1.0 / 30.0;
+    let create =
+        |name: &str| registry::create_effect(&config.effect_spec_for(name), width, height, config);
+    let mut effect = create(name)?;
+    let mut crt = CrtFilter::new(width, height, true, config.crt_intensity)
+        .with_curvature(0.5)
+        .with_persistence(0.5)
+        .with_vignette(true, DEFAULT_VIGNETTE_RADIUS, DEFAULT_VIGNETTE_STRENGTH)
+        .with_seed(config.seed);
+    let mut buffer = ScreenBuffer::new(width, height);
+    buffer.set_background(config.background);
+    let switch_at = frames / 3;
+    let mut transition = None;
+
+    for frame in 0..frames {
+        if frame == switch_at {
+            let outgoing = std::mem::replace(&mut effect, create(next)?);
+            let duration = switch_at.max(1) as f64 * delta_time;
+            transition = Some(
+                Transition::new(outgoing, width, height, duration).with_kind(style, config.seed),
+            );
+        }
+        effect.update(delta_time);
+        effect.render(&mut buffer);
+        if let Some(t) = &mut transition {
+            t.update(delta_time);
+            t.render(&mut buffer);
+            if t.is_complete() {
+                transition = None;
+            }
+        }
+        crt.apply(&mut buffer, delta_time);
+        buffer
+            .flush_to(&mut io::sink())
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Save `buffer` into `dir` as `rain_YYYYMMDD_HHMMSS.txt` (plain text) and
+/// `.ans` (ANSI colors), named after the current UTC time. A numeric suffix
+/// keeps two shots in the same second apart.
+///
+/// Returns the path of the plain-text file.
+pub fn save_screenshot(buffer: &ScreenBuffer, dir: &Path) -> Result<PathBuf, String> {
+    let name = screenshot_name(dir, &["txt", "ans"]);
+    let txt_path = dir.join(format!("{}.txt", name));
+    write_text_file(buffer, &txt_path)?;
+    write_ansi_file(buffer, &dir.join(format!("{}.ans", name)))?;
+    Ok(txt_path)
+}
+
+/// Save `buffer` into `dir` as a rasterized `rain_YYYYMMDD_HHMMSS.png`,
+/// named like `save_screenshot`. Returns its path.
+pub fn save_png_screenshot(buffer: &ScreenBuffer, dir: &Path) -> Result<PathBuf, String> {
+    let path = dir.join(format!("{}.png", screenshot_name(dir, &["png"])));
+    save_png(buffer, &path)?;
+    Ok(path)
+}
+
+/// `rain_YYYYMMDD_HHMMSS` for now, with a numeric suffix if a file with
+/// that name and any of `extensions` is already in `dir`.
+fn screenshot_name(dir: &Path, extensions: &[&str]) -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let stem = format!("rain_{}", utc_timestamp(secs));
+    let mut name = stem.clone();
+    let mut n = 1;
+    while extensions
+        .iter()
+        .any(|ext| dir.join(format!("{}.{}", name, ext)).exists())
+    {
+        n += 1;
+        name = format!("{}_{}", stem, n);
+    }
+    name
+}
+
+/// Rasterize `buffer` with the GIF export's font and cell size and write
+/// it to `path` as a PNG, colors exact.
+pub fn save_png(buffer: &ScreenBuffer, path: &Path) -> Result<(), String> {
+    let rgb: Vec<u8> = rasterize(buffer, |color, default| match color {
+        Color::Reset => default,
+        other => color_to_rgb(other),
+    })
+    .into_iter()
+    .flat_map(|(r, g, b)| [r, g, b])
+    .collect();
+    let file =
+        File::create(path).map_err(|e| format!("Could not create {}: {}", path.display(), e))?;
+    png::write_png(
+        &mut BufWriter::new(file),
+        (buffer.width() as usize * CELL_WIDTH) as u32,
+        (buffer.height() as usize * CELL_HEIGHT) as u32,
+        &rgb,
+    )
+    .map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+/// Run `config`'s effect for `duration` seconds and save the last frame to
+/// `path` as a PNG.
+pub fn export_png(
+    config: &Config,
+    path: &Path,
+    duration: f64,
+    width: u16,
+    height: u16,
+) -> Result<(), String> {
+    save_png(run_for(config, duration, width, height)?.buffer(), path)
+}
+
+/// Run `config`'s effect for `duration` seconds and write the last frame to
+/// `path` as an SVG.
+pub fn export_svg(
+    config: &Config,
+    path: &Path,
+    duration: f64,
+    width: u16,
+    height: u16,
+) -> Result<(), String> {
+    let runner = run_for(config, duration, width, height)?;
+    let file =
+        File::create(path).map_err(|e| format!("Could not create {}: {}", path.display(), e))?;
+    svg::write_svg(&mut BufWriter::new(file), runner.buffer())
+        .map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+/// A runner stepped through `duration` seconds at the configured frame rate.
+fn run_for(config: &Config, duration: f64, width: u16, height: u16) -> Result<Runner, String> {
+    let mut runner = Runner::new(config, width, height)?;
+    let delta_time = 1.0 / config.target_fps as f64;
+    let frames = ((duration / delta_time).round() as u32).max(1);
+    for _ in 0..frames {
+        runner.step(delta_time);
+    }
+    Ok(runner)
+}
+
+/// Write the frame as standalone ANSI text.
+fn write_ansi_file(buffer: &ScreenBuffer, path: &Path) -> Result<(), String> {
+    let file =
+        File::create(path).map_err(|e| format!("Could not create {}: {}", path.display(), e))?;
+    buffer
+        .write_ansi(&mut BufWriter::new(file))
+        .map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+/// Write the frame's characters with colors stripped.
+fn write_text_file(buffer: &ScreenBuffer, path: &Path) -> Result<(), String> {
+    fs::write(path, buffer.to_plain_string())
+        .map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+/// `YYYYMMDD_HHMMSS` for a Unix time, in UTC.
+fn utc_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}{:02}{:02}_{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem / 60 % 60,
+        rem % 60
+    )
+}
+
+/// Render `duration` seconds of `config`'s effect into an animated GIF at `path`.
+///
+/// Returns the number of frames encoded.
+pub fn export_gif(
+    config: &Config,
+    path: &Path,
+    duration: f64,
+    width: u16,
+    height: u16,
+) -> Result<u32, String> {
+    let pixel_width = width as usize * CELL_WIDTH;
+    let pixel_height = height as usize * CELL_HEIGHT;
+    if pixel_width > u16::MAX as usize || pixel_height > u16::MAX as usize {
+        return Err(format!("{}x{} is too large for a GIF", width, height));
+    }
+
+    let mut runner = Runner::new(config, width, height)?;
+    let fps = config.target_fps as f64;
+    let delta_time = 1.0 / fps;
+    let frames = ((duration * fps).round() as u32).max(1);
+
+    let file =
+        File::create(path).map_err(|e| format!("Could not create {}: {}", path.display(), e))?;
+    let write_err = |e: std::io::Error| format!("Could not write {}: {}", path.display(), e);
+    let mut encoder = GifEncoder::new(
+        BufWriter::new(file),
+        pixel_width as u16,
+        pixel_height as u16,
+    )
+    .map_err(write_err)?;
+
+    // GIF delays are whole centiseconds; carry the rounding so the total
+    // playback time matches the requested duration
+    let mut elapsed_cs = 0u32;
+    for frame in 1..=frames {
+        let buffer = runner.step(delta_time);
+        let end_cs = (frame as f64 * 100.0 / fps).round() as u32;
+        let delay = (end_cs - elapsed_cs) as u16;
+        elapsed_cs = end_cs;
+        encoder
+            .add_frame(&rasterize(buffer, gif_index), delay)
+            .map_err(write_err)?;
+    }
+    encoder.finish().map_err(write_err)?;
+
+    Ok(frames)
+}
+
+/// Draw a buffer with the embedded font, `CELL_WIDTH x CELL_HEIGHT` pixels
+/// per cell. `pixel` turns a cell color (and the RGB to use for
+/// `Color::Reset`) into a pixel: a GIF palette index or an RGB triple.
+fn rasterize<P: Copy + Default>(
+    buffer: &ScreenBuffer,
+    pixel: impl Fn(Color, (u8, u8, u8)) -> P,
+) -> Vec<P> {
+    let width = buffer.width() as usize;
+    let pixel_width = width * CELL_WIDTH;
+    let mut pixels = vec![P::default(); pixel_width * buffer.height() as usize * CELL_HEIGHT];
+
+    for (i, cell) in buffer.cells().iter().enumerate() {
+        let (col, row) = (i % width, i / width);
+        let bg = pixel(cell.bg, (0, 0, 0));
+        let fg = pixel(cell.fg, DEFAULT_FG);
+        let bits = font::glyph_bits(cell.ch);
+        let origin = row * CELL_HEIGHT * pixel_width + col * CELL_WIDTH;
+
+        for py in 0..CELL_HEIGHT {
+            let line = origin + py * pixel_width;
+            for px in 0..CELL_WIDTH {
+                // Glyph pixel under this cell pixel, if inside the 1px margin
+                let lit = px >= 1
+                    && py >= 1
+                    && (px - 1) / GLYPH_SCALE < font::GLYPH_WIDTH
+                    && (py - 1) / GLYPH_SCALE < font::GLYPH_HEIGHT
+                    && font::pixel(bits, (px - 1) / GLYPH_SCALE, (py - 1) / GLYPH_SCALE);
+                pixels[line + px] = if lit { fg } else { bg };
+            }
+        }
+    }
+    pixels
+}
+
+/// Palette index for a cell color, using `default` for `Color::Reset`.
+fn gif_index(color: Color, default: (u8, u8, u8)) -> u8 {
+    let (r, g, b) = match color {
+        Color::Reset => default,
+        other => color_to_rgb(other),
+    };
+    gif::palette_index(r, g, b)
+}
+
+/// `dir/frame_00001.ext` -- zero-padded so files sort in frame order.
+fn frame_path(dir: &Path, frame: u32, ext: &str) -> PathBuf {
+    dir.join(format!("frame_{:05}.{}", frame, ext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "digitalrain-headless-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn export(dir: &Path, seed: &str) -> Vec<String> {
+        let cli = Cli::parse_from(["digital_rain", "--seed", seed]);
+        let config = Config::resolve(&cli, &ConfigFile::default());
+        let options = ExportOptions {
+            frames: 3,
+            out_dir: dir,
+            plain_text: true,
+            width: 20,
+            height: 10,
+        };
+        assert_eq!(export_frames(&config, &options).unwrap(), 6);
+
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+            .iter()
+            .map(|n| fs::read_to_string(dir.join(n)).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn writes_numbered_ansi_and_text_frames() {
+        let dir = temp_dir("numbered");
+        let frames = export(&dir, "1");
+        assert!(dir.join("frame_00001.ans").exists());
+        assert!(dir.join("frame_00003.txt").exists());
+        // Plain text has one line per row and no escape sequences
+        let txt = &frames[1];
+        assert_eq!(txt.lines().count(), 10);
+        assert!(!txt.contains('\x1b'));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn gif_export_has_expected_size_and_frame_count() {
+        let dir = temp_dir("gif");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("out.gif");
+        let cli = Cli::parse_from(["digital_rain", "--seed", "3", "--fps", "10"]);
+        let config = Config::resolve(&cli, &ConfigFile::default());
+
+        assert_eq!(export_gif(&config, &path, 0.5, 10, 4).unwrap(), 5);
+        let bytes = fs::read(&path).unwrap();
+        assert!(bytes.starts_with(b"GIF89a"));
+        // Logical screen is 10x4 cells at 8x12 pixels each
+        assert_eq!(&bytes[6..10], &[80, 0, 48, 0]);
+        // One graphic control extension per frame
+        assert_eq!(
+            bytes
+                .windows(3)
+                .filter(|w| w == &[0x21, 0xF9, 0x04])
+                .count(),
+            5
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn screenshots_get_timestamped_unique_names() {
+        assert_eq!(utc_timestamp(0), "19700101_000000");
+        assert_eq!(utc_timestamp(1_709_210_096), "20240229_123456");
+
+        let dir = temp_dir("screenshot");
+        fs::create_dir_all(&dir).unwrap();
+        let mut buffer = ScreenBuffer::new(4, 2);
+        buffer.set_cell(0, 0, 'x', Color::Rgb { r: 0, g: 255, b: 0 }, Color::Reset);
+        let first = save_screenshot(&buffer, &dir).unwrap();
+        let second = save_screenshot(&buffer, &dir).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(fs::read_to_string(&first).unwrap(), "x\n\n");
+        let ans = fs::read_to_string(first.with_extension("ans")).unwrap();
+        assert!(ans.contains('\x1b'));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn png_export_keeps_exact_colors() {
+        let dir = temp_dir("png");
+        fs::create_dir_all(&dir).unwrap();
+        let mut buffer = ScreenBuffer::new(3, 2);
+        let teal = Color::Rgb {
+            r: 1,
+            g: 130,
+            b: 129,
+        };
+        buffer.set_cell(1, 1, '#', teal, Color::Reset);
+        let path = save_png_screenshot(&buffer, &dir).unwrap();
+        assert_eq!(path.extension().unwrap(), "png");
+
+        let image = crate::image::Image::decode(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(
+            (image.width, image.height),
+            (3 * CELL_WIDTH, 2 * CELL_HEIGHT)
+        );
+        // Only the glyph's cell has its color; the rest is background
+        let colored: Vec<usize> = (0..image.pixels.len())
+            .filter(|&i| image.pixels[i] == [1, 130, 129, 255])
+            .collect();
+        assert!(!colored.is_empty());
+        assert!(colored.iter().all(|&i| {
+            let (x, y) = (i % image.width, i / image.width);
+            (x / CELL_WIDTH, y / CELL_HEIGHT) == (1, 1)
+        }));
+
+        let cli = Cli::parse_from(["digital_rain", "--seed", "3"]);
+        let config = Config::resolve(&cli, &ConfigFile::default());
+        export_png(&config, &dir.join("rain.png"), 1.0, 10, 4).unwrap();
+        assert!(
+            fs::read(dir.join("rain.png"))
+                .unwrap()
+                .starts_with(b"\x89PNG")
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn every_effect_renders_the_same_frames_for_a_seed() {
+        for &name in registry::effect_names() {
+            let (a, b) = (render_frames(name, 5, 42), render_frames(name, 5, 42));
+            assert_eq!(a.len(), 5);
+            for (a, b) in a.iter().zip(&b) {
+                assert_eq!(a.to_ansi_string(), b.to_ansi_string(), "{}", name);
+                assert_eq!(a.to_plain_string().lines().count(), 12);
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_exports_identical_frames() {
+        let (a, b) = (temp_dir("seed-a"), temp_dir("seed-b"));
+        assert_eq!(export(&a, "9"), export(&b, "9"));
+        fs::remove_dir_all(&a).unwrap();
+        fs::remove_dir_all(&b).unwrap();
+    }
+
+    #[test]
+    fn smoke_test_runs_every_effect_at_every_size() {
+        let cli = Cli::parse_from(["digital_rain", "--seed", "5"]);
+        let config = Config::resolve(&cli, &ConfigFile::default());
+        let mut log = Vec::new();
+        assert_eq!(smoke_test(&config, 6, &mut log).unwrap(), 0);
+
+        let log = String::from_utf8(log).unwrap();
+        let runs = registry::effect_names().len() * SMOKE_SIZES.len();
+        assert_eq!(log.lines().filter(|l| l.starts_with("ok ")).count(), runs);
+        assert!(log.contains("classic -> binary (crossfade) at 3x2"));
+        assert!(log.contains("cascade -> pulse (glitch) at 200x60"));
+        assert!(log.ends_with(&format!(
+            "{} of {} runs passed (6 frames each)\n",
+            runs, runs
+        )));
+    }
+}
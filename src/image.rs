@@ -0,0 +1,421 @@
+//! Image loading and conversion to terminal cells.
+//!
+//! Decodes PNG (non-interlaced, any color type, 1-16 bit) and binary PPM
+//! (`P6`) into RGBA pixels, then resamples them into half-block cells: each
+//! terminal cell shows two vertically stacked pixels as `▀` with the top
+//! pixel as foreground and the bottom pixel as background. Used by the
+//! `--logo` watermark overlay.
+
+use std::path::Path;
+
+use crate::inflate;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// An RGBA image, row-major.
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<[u8; 4]>,
+}
+
+/// One terminal cell's worth of image: two stacked RGBA pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HalfBlock {
+    pub top: [u8; 4],
+    pub bottom: [u8; 4],
+}
+
+/// An image resampled to a grid of half-block cells.
+pub struct HalfBlockImage {
+    pub width: u16,
+    pub height: u16,
+    /// Row-major, `width * height` cells
+    pub cells: Vec<HalfBlock>,
+}
+
+impl Image {
+    /// Load a PNG or PPM file, detected by its contents.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let data =
+            std::fs::read(path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        Self::decode(&data).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Decode PNG or PPM bytes.
+    pub fn decode(data: &[u8]) -> Result<Self, String> {
+        if data.starts_with(PNG_SIGNATURE) {
+            decode_png(data)
+        } else if data.starts_with(b"P6") {
+            decode_ppm(data)
+        } else {
+            Err("unsupported image format (expected PNG or binary PPM)".to_string())
+        }
+    }
+
+    /// Resample to `columns` cells wide, keeping the aspect ratio (each cell
+    /// is one pixel wide and two pixels tall). Pixels are box-averaged with
+    /// alpha weighting so transparent areas don't darken edges.
+    pub fn to_half_blocks(&self, columns: u16) -> HalfBlockImage {
+        let columns = columns.max(1) as usize;
+        let pixel_rows = ((self.height * columns) as f64 / self.width.max(1) as f64)
+            .round()
+            .max(1.0) as usize;
+        let rows = pixel_rows.div_ceil(2);
+
+        let sample = |tx: usize, ty: usize| -> [u8; 4] {
+            if ty >= pixel_rows {
+                return [0; 4];
+            }
+            let x0 = tx * self.width / columns;
+            let x1 = ((tx + 1) * self.width / columns).max(x0 + 1);
+            let y0 = ty * self.height / pixel_rows;
+            let y1 = ((ty + 1) * self.height / pixel_rows).max(y0 + 1);
+            let (mut rgb, mut alpha, mut count) = ([0u64; 3], 0u64, 0u64);
+            for y in y0..y1.min(self.height) {
+                for x in x0..x1.min(self.width) {
+                    let p = self.pixels[y * self.width + x];
+                    let a = p[3] as u64;
+                    for c in 0..3 {
+                        rgb[c] += p[c] as u64 * a;
+                    }
+                    alpha += a;
+                    count += 1;
+                }
+            }
+            if alpha == 0 {
+                return [0; 4];
+            }
+            [
+                (rgb[0] / alpha) as u8,
+                (rgb[1] / alpha) as u8,
+                (rgb[2] / alpha) as u8,
+                (alpha / count.max(1)) as u8,
+            ]
+        };
+
+        let mut cells = Vec::with_capacity(columns * rows);
+        for row in 0..rows {
+            for col in 0..columns {
+                cells.push(HalfBlock {
+                    top: sample(col, row * 2),
+                    bottom: sample(col, row * 2 + 1),
+                });
+            }
+        }
+        HalfBlockImage {
+            width: columns as u16,
+            height: rows as u16,
+            cells,
+        }
+    }
+}
+
+/// Decode a binary PPM (`P6`, maxval up to 255).
+fn decode_ppm(data: &[u8]) -> Result<Image, String> {
+    // Header: magic, width, height, maxval, separated by whitespace/comments
+    let mut fields = Vec::new();
+    let mut pos = 2;
+    while fields.len() < 3 {
+        match data.get(pos) {
+            Some(b'#') => {
+                while data.get(pos).is_some_and(|&b| b != b'\n') {
+                    pos += 1;
+                }
+            }
+            Some(b) if b.is_ascii_whitespace() => pos += 1,
+            Some(b) if b.is_ascii_digit() => {
+                let start = pos;
+                while data.get(pos).is_some_and(|b| b.is_ascii_digit()) {
+                    pos += 1;
+                }
+                let text = std::str::from_utf8(&data[start..pos]).unwrap_or("0");
+                fields.push(text.parse::<usize>().map_err(|_| "bad PPM header")?);
+            }
+            _ => return Err("bad PPM header".to_string()),
+        }
+    }
+    // Exactly one whitespace byte separates the header from the pixels
+    pos += 1;
+
+    let (width, height, maxval) = (fields[0], fields[1], fields[2]);
+    if maxval == 0 || maxval > 255 {
+        return Err("only 8-bit PPM images are supported".to_string());
+    }
+    let bytes = data
+        .get(pos..pos + width * height * 3)
+        .ok_or("truncated PPM pixel data")?;
+    let scale = |v: u8| (v as usize * 255 / maxval) as u8;
+    let pixels = bytes
+        .chunks_exact(3)
+        .map(|p| [scale(p[0]), scale(p[1]), scale(p[2]), 255])
+        .collect();
+    Ok(Image {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Decode a non-interlaced PNG into RGBA.
+fn decode_png(data: &[u8]) -> Result<Image, String> {
+    let mut pos = PNG_SIGNATURE.len();
+    let mut header = None;
+    let mut palette: Vec<[u8; 4]> = Vec::new();
+    let mut transparent: Option<[u16; 3]> = None;
+    let mut compressed = Vec::new();
+
+    while pos + 8 <= data.len() {
+        let len = u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) as usize;
+        let kind = &data[pos + 4..pos + 8];
+        let body = data
+            .get(pos + 8..pos + 8 + len)
+            .ok_or("truncated PNG chunk")?;
+        pos += 12 + len; // length + type + body + CRC
+
+        match kind {
+            b"IHDR" => {
+                if body.len() < 13 {
+                    return Err("bad PNG header".to_string());
+                }
+                let width = u32::from_be_bytes(body[0..4].try_into().unwrap()) as usize;
+                let height = u32::from_be_bytes(body[4..8].try_into().unwrap()) as usize;
+                if body[12] != 0 {
+                    return Err("interlaced PNGs are not supported".to_string());
+                }
+                header = Some((width, height, body[8], body[9]));
+            }
+            b"PLTE" => {
+                palette = body
+                    .chunks_exact(3)
+                    .map(|c| [c[0], c[1], c[2], 255])
+                    .collect();
+            }
+            b"tRNS" => match header {
+                // Palette images: one alpha per palette entry
+                Some((_, _, _, 3)) => {
+                    for (entry, &alpha) in palette.iter_mut().zip(body) {
+                        entry[3] = alpha;
+                    }
+                }
+                // Gray/RGB images: a single fully transparent color
+                _ => {
+                    let sample = |i: usize| {
+                        body.get(i * 2..i * 2 + 2)
+                            .map_or(0, |b| u16::from_be_bytes([b[0], b[1]]))
+                    };
+                    transparent = Some(if body.len() >= 6 {
+                        [sample(0), sample(1), sample(2)]
+                    } else {
+                        [sample(0); 3]
+                    });
+                }
+            },
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {}
+        }
+    }
+
+    let (width, height, depth, color_type) = header.ok_or("PNG has no IHDR chunk")?;
+    let channels = match color_type {
+        0 | 3 => 1,
+        2 => 3,
+        4 => 2,
+        6 => 4,
+        _ => return Err(format!("unknown PNG color type {}", color_type)),
+    };
+    if !matches!(depth, 1 | 2 | 4 | 8 | 16) || (depth < 8 && channels != 1) {
+        return Err(format!("unsupported PNG bit depth {}", depth));
+    }
+
+    let raw = inflate::zlib_decompress(&compressed)?;
+    let bits_per_pixel = channels * depth as usize;
+    let stride = (width * bits_per_pixel).div_ceil(8);
+    let bpp = bits_per_pixel.div_ceil(8);
+    let rows = unfilter(&raw, stride, height, bpp)?;
+
+    // Read the `index`-th sample of a row at the image's bit depth
+    let sample = |row: &[u8], index: usize| -> u16 {
+        match depth {
+            16 => u16::from_be_bytes([row[index * 2], row[index * 2 + 1]]),
+            8 => row[index] as u16,
+            _ => {
+                let bit = index * depth as usize;
+                let shift = 8 - depth as usize - bit % 8;
+                ((row[bit / 8] >> shift) & ((1 << depth) - 1)) as u16
+            }
+        }
+    };
+    // Scale a sample to 8 bits
+    let to8 = |v: u16| -> u8 {
+        match depth {
+            16 => (v >> 8) as u8,
+            d => (v as u32 * 255 / ((1u32 << d) - 1)) as u8,
+        }
+    };
+
+    let mut pixels = Vec::with_capacity(width * height);
+    for row in rows.chunks_exact(stride) {
+        for x in 0..width {
+            let s = |c: usize| sample(row, x * channels + c);
+            let pixel = match color_type {
+                3 => *palette
+                    .get(s(0) as usize)
+                    .ok_or("PNG palette index out of range")?,
+                0 => {
+                    let g = s(0);
+                    let alpha = if transparent == Some([g; 3]) { 0 } else { 255 };
+                    [to8(g), to8(g), to8(g), alpha]
+                }
+                2 => {
+                    let rgb = [s(0), s(1), s(2)];
+                    let alpha = if transparent == Some(rgb) { 0 } else { 255 };
+                    [to8(rgb[0]), to8(rgb[1]), to8(rgb[2]), alpha]
+                }
+                4 => [to8(s(0)), to8(s(0)), to8(s(0)), to8(s(1))],
+                _ => [to8(s(0)), to8(s(1)), to8(s(2)), to8(s(3))],
+            };
+            pixels.push(pixel);
+        }
+    }
+
+    Ok(Image {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Undo PNG scanline filters, returning the raw rows back to back.
+fn unfilter(data: &[u8], stride: usize, height: usize, bpp: usize) -> Result<Vec<u8>, String> {
+    if data.len() < (stride + 1) * height {
+        return Err("truncated PNG image data".to_string());
+    }
+    let mut out = vec![0u8; stride * height];
+    for y in 0..height {
+        let filter = data[y * (stride + 1)];
+        let line = &data[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+        for x in 0..stride {
+            let a = if x >= bpp {
+                out[y * stride + x - bpp]
+            } else {
+                0
+            };
+            let b = if y > 0 { out[(y - 1) * stride + x] } else { 0 };
+            let c = if x >= bpp && y > 0 {
+                out[(y - 1) * stride + x - bpp]
+            } else {
+                0
+            };
+            let predictor = match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => return Err(format!("unknown PNG filter type {}", filter)),
+            };
+            out[y * stride + x] = line[x].wrapping_add(predictor);
+        }
+    }
+    Ok(out)
+}
+
+/// The Paeth predictor from the PNG spec.
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2x2 RGBA: red, half-transparent green / blue, transparent
+    /// (second row Sub-filtered).
+    const RGBA_PNG: &[u8] = &[
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44,
+        0x52, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x02, 0x08, 0x06, 0x00, 0x00, 0x00, 0x72,
+        0xb6, 0x0d, 0x24, 0x00, 0x00, 0x00, 0x16, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0xf8,
+        0xcf, 0xc0, 0xf0, 0x1f, 0x08, 0x1b, 0x18, 0x81, 0x34, 0x90, 0xcd, 0xc8, 0x08, 0x00, 0x3a,
+        0xe8, 0x05, 0x7f, 0xdb, 0x8e, 0x2c, 0x20, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44,
+        0xae, 0x42, 0x60, 0x82,
+    ];
+
+    /// 3x1 4-bit palette image with tRNS making entry 2 transparent.
+    const PALETTE_PNG: &[u8] = &[
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44,
+        0x52, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01, 0x04, 0x03, 0x00, 0x00, 0x00, 0xe9,
+        0xce, 0x09, 0x87, 0x00, 0x00, 0x00, 0x09, 0x50, 0x4c, 0x54, 0x45, 0x0a, 0x14, 0x1e, 0xc8,
+        0x64, 0x32, 0x00, 0x00, 0x00, 0x12, 0x76, 0xc6, 0x62, 0x00, 0x00, 0x00, 0x03, 0x74, 0x52,
+        0x4e, 0x53, 0xff, 0xff, 0x00, 0xd7, 0xca, 0x0d, 0x41, 0x00, 0x00, 0x00, 0x0b, 0x49, 0x44,
+        0x41, 0x54, 0x78, 0x9c, 0x63, 0x60, 0x54, 0x00, 0x00, 0x00, 0x25, 0x00, 0x22, 0x7a, 0x26,
+        0xf0, 0x02, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+    ];
+
+    #[test]
+    fn decodes_rgba_png_with_filters() {
+        let image = Image::decode(RGBA_PNG).unwrap();
+        assert_eq!((image.width, image.height), (2, 2));
+        assert_eq!(
+            image.pixels,
+            [
+                [255, 0, 0, 255],
+                [0, 255, 0, 128],
+                [0, 0, 255, 255],
+                [0, 0, 0, 0]
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_low_depth_palette_png_with_transparency() {
+        let image = Image::decode(PALETTE_PNG).unwrap();
+        assert_eq!(
+            image.pixels,
+            [[10, 20, 30, 255], [200, 100, 50, 255], [0, 0, 0, 0]]
+        );
+    }
+
+    #[test]
+    fn decodes_ppm_and_rejects_unknown_formats() {
+        let mut ppm = b"P6\n# comment\n2 1\n255\n".to_vec();
+        ppm.extend_from_slice(&[1, 2, 3, 4, 5, 6]);
+        let image = Image::decode(&ppm).unwrap();
+        assert_eq!(image.pixels, [[1, 2, 3, 255], [4, 5, 6, 255]]);
+        assert!(Image::decode(b"GIF89a").is_err());
+    }
+
+    #[test]
+    fn half_blocks_stack_two_pixel_rows_per_cell() {
+        let image = Image::decode(RGBA_PNG).unwrap();
+        let blocks = image.to_half_blocks(2);
+        assert_eq!((blocks.width, blocks.height), (2, 1));
+        assert_eq!(
+            blocks.cells[0],
+            HalfBlock {
+                top: [255, 0, 0, 255],
+                bottom: [0, 0, 255, 255]
+            }
+        );
+        assert_eq!(blocks.cells[1].bottom[3], 0);
+
+        // Downscaling to one cell averages by alpha: the transparent pixel
+        // lowers coverage but doesn't pull the color toward black
+        let one = image.to_half_blocks(1);
+        assert_eq!(one.cells.len(), 1);
+        assert_eq!(one.cells[0].top, [101, 51, 101, 159]);
+    }
+}
@@ -0,0 +1,290 @@
+//! DEFLATE / zlib decompression (RFC 1950/1951), for reading PNG images.
+//!
+//! A small canonical-Huffman decoder in the style of zlib's `puff`:
+//! decode-only, no streaming, favoring clarity over speed. Logos are tiny,
+//! so this is never on a hot path.
+
+/// Maximum Huffman code length in DEFLATE.
+const MAX_BITS: usize = 15;
+
+/// Base lengths for length codes 257..285.
+pub(crate) const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+/// Extra bits for length codes 257..285.
+pub(crate) const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+/// Base offsets for distance codes 0..29.
+pub(crate) const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+/// Extra bits for distance codes 0..29.
+pub(crate) const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// Order in which code-length code lengths are stored in a dynamic block.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Decompress a zlib stream (2-byte header, DEFLATE data, Adler-32).
+pub fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>, String> {
+    let [cmf, flg, ..] = *data else {
+        return Err("zlib stream too short".to_string());
+    };
+    if cmf & 0x0F != 8 || !(cmf as u16 * 256 + flg as u16).is_multiple_of(31) {
+        return Err("not a zlib stream".to_string());
+    }
+    if flg & 0x20 != 0 {
+        return Err("zlib preset dictionaries are not supported".to_string());
+    }
+    inflate(&data[2..])
+}
+
+/// Decompress raw DEFLATE data.
+pub fn inflate(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut input = BitReader { data, pos: 0 };
+    let mut out = Vec::new();
+
+    loop {
+        let last = input.bits(1)? == 1;
+        match input.bits(2)? {
+            0 => stored_block(&mut input, &mut out)?,
+            1 => {
+                let (lit, dist) = fixed_tables();
+                codes(&mut input, &mut out, &lit, &dist)?;
+            }
+            2 => {
+                let (lit, dist) = dynamic_tables(&mut input)?;
+                codes(&mut input, &mut out, &lit, &dist)?;
+            }
+            _ => return Err("invalid DEFLATE block type".to_string()),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+/// LSB-first bit reader over a byte slice.
+struct BitReader<'a> {
+    data: &'a [u8],
+    /// Position in bits
+    pos: usize,
+}
+
+impl BitReader<'_> {
+    fn bits(&mut self, count: u32) -> Result<u32, String> {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = self
+                .data
+                .get(self.pos / 8)
+                .ok_or("unexpected end of compressed data")?;
+            value |= ((byte >> (self.pos % 8)) as u32 & 1) << i;
+            self.pos += 1;
+        }
+        Ok(value)
+    }
+
+    /// Skip to the next byte boundary.
+    fn align(&mut self) {
+        self.pos = self.pos.div_ceil(8) * 8;
+    }
+}
+
+/// Canonical Huffman table: code counts per length and symbols in code order.
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    /// Build from per-symbol code lengths (0 = unused).
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for len in 1..=MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Self { counts, symbols }
+    }
+
+    /// Read one symbol, one bit at a time.
+    fn decode(&self, input: &mut BitReader) -> Result<u16, String> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..=MAX_BITS {
+            code |= input.bits(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                return self
+                    .symbols
+                    .get((index + code - first) as usize)
+                    .copied()
+                    .ok_or_else(|| "invalid Huffman code".to_string());
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("invalid Huffman code".to_string())
+    }
+}
+
+fn stored_block(input: &mut BitReader, out: &mut Vec<u8>) -> Result<(), String> {
+    input.align();
+    let len = input.bits(16)? as usize;
+    let nlen = input.bits(16)? as usize;
+    if len != !nlen & 0xFFFF {
+        return Err("corrupt stored block length".to_string());
+    }
+    let start = input.pos / 8;
+    let bytes = input
+        .data
+        .get(start..start + len)
+        .ok_or("unexpected end of compressed data")?;
+    out.extend_from_slice(bytes);
+    input.pos += len * 8;
+    Ok(())
+}
+
+/// The fixed literal/length and distance tables (block type 1).
+fn fixed_tables() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+/// Read the code-length-encoded tables of a dynamic block (type 2).
+fn dynamic_tables(input: &mut BitReader) -> Result<(Huffman, Huffman), String> {
+    let nlen = input.bits(5)? as usize + 257;
+    let ndist = input.bits(5)? as usize + 1;
+    let ncode = input.bits(4)? as usize + 4;
+    if nlen > 286 || ndist > 30 {
+        return Err("bad dynamic block counts".to_string());
+    }
+
+    let mut code_lengths = [0u8; 19];
+    for &i in &CODE_LENGTH_ORDER[..ncode] {
+        code_lengths[i] = input.bits(3)? as u8;
+    }
+    let code_table = Huffman::new(&code_lengths);
+
+    let mut lengths = Vec::with_capacity(nlen + ndist);
+    while lengths.len() < nlen + ndist {
+        let (value, repeat) = match code_table.decode(input)? {
+            sym @ 0..=15 => (sym as u8, 1),
+            16 => {
+                let prev = *lengths.last().ok_or("repeat with no previous length")?;
+                (prev, 3 + input.bits(2)?)
+            }
+            17 => (0, 3 + input.bits(3)?),
+            _ => (0, 11 + input.bits(7)?),
+        };
+        for _ in 0..repeat {
+            lengths.push(value);
+        }
+    }
+    if lengths.len() > nlen + ndist {
+        return Err("too many code lengths".to_string());
+    }
+
+    Ok((
+        Huffman::new(&lengths[..nlen]),
+        Huffman::new(&lengths[nlen..]),
+    ))
+}
+
+/// Decode literal/length + distance codes until end-of-block.
+fn codes(
+    input: &mut BitReader,
+    out: &mut Vec<u8>,
+    lit: &Huffman,
+    dist: &Huffman,
+) -> Result<(), String> {
+    loop {
+        let symbol = lit.decode(input)? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let i = symbol - 257;
+                if i >= LENGTH_BASE.len() {
+                    return Err("invalid length code".to_string());
+                }
+                let len = LENGTH_BASE[i] as usize + input.bits(LENGTH_EXTRA[i] as u32)? as usize;
+                let d = dist.decode(input)? as usize;
+                if d >= DIST_BASE.len() {
+                    return Err("invalid distance code".to_string());
+                }
+                let distance = DIST_BASE[d] as usize + input.bits(DIST_EXTRA[d] as u32)? as usize;
+                if distance > out.len() {
+                    return Err("distance too far back".to_string());
+                }
+                // Byte by byte: the copy may overlap what it is producing
+                let start = out.len() - distance;
+                for k in 0..len {
+                    out.push(out[start + k]);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inflates_fixed_huffman_block() {
+        let data = [
+            0x78, 0xda, 0x4b, 0x4c, 0x4a, 0x4e, 0x84, 0x21, 0x00, 0x1d, 0xe0, 0x04, 0x99,
+        ];
+        assert_eq!(zlib_decompress(&data).unwrap(), b"abcabcabcabc");
+    }
+
+    #[test]
+    fn inflates_dynamic_huffman_block() {
+        let data = [
+            0x78, 0xda, 0xdd, 0xcd, 0xd1, 0x0d, 0x80, 0x20, 0x0c, 0x84, 0xe1, 0x55, 0x6e, 0x00,
+            0xe3, 0x02, 0x4e, 0x53, 0xb4, 0x0a, 0x8a, 0x56, 0xa1, 0x88, 0x38, 0xbd, 0x84, 0x31,
+            0x7c, 0xbd, 0x2f, 0x97, 0x5f, 0x2d, 0xe3, 0x4a, 0x6e, 0xdc, 0x60, 0x82, 0xe4, 0x03,
+            0xb3, 0x3c, 0x58, 0xd3, 0x7e, 0x46, 0xc8, 0xcd, 0x01, 0x5a, 0xd9, 0xd3, 0x5b, 0x30,
+            0xc9, 0x32, 0x20, 0xd3, 0xc6, 0x48, 0x27, 0x0e, 0x96, 0xae, 0xd1, 0x4e, 0x1a, 0xdc,
+            0x03, 0x4b, 0x11, 0x45, 0x52, 0x5f, 0xdf, 0xde, 0x4b, 0x6e, 0x94, 0xad, 0x53, 0x46,
+            0x20, 0x63, 0x9c, 0xf6, 0x6d, 0xf9, 0x4f, 0xe6, 0x03, 0x4d, 0xa8, 0x6e, 0xaa,
+        ];
+        let expected = "the quick brown fox jumps over the lazy dog; wake up neo, \
+                        the matrix has you. follow the white rabbit. "
+            .repeat(3);
+        assert_eq!(zlib_decompress(&data).unwrap(), expected.as_bytes());
+    }
+
+    #[test]
+    fn inflates_stored_block_and_rejects_garbage() {
+        // Final stored block holding "hi"
+        let data = [0x01, 0x02, 0x00, 0xfd, 0xff, b'h', b'i'];
+        assert_eq!(inflate(&data).unwrap(), b"hi");
+        assert!(zlib_decompress(&[0x12, 0x34, 0x56]).is_err());
+        assert!(inflate(&[0x07]).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+//! Frame-time jank tracking for the stats overlay and `--jank-report`.
+//!
+//! The main loop times each frame's work in phases (update, render, post
+//! filters, flush) with `PhaseTimes`. `JankTracker` pairs each frame
+//! interval with the phases of the frame before it, since that's the work
+//! that made the interval long, and keeps a histogram of intervals, a count
+//! of dropped frames, and the worst intervals with their phases.
+//!
+//! A frame counts as dropped for each whole frame budget an interval runs
+//! over: an interval of 2.5 budgets dropped about one and a half frames,
+//! which rounds to two.
+
+use std::fmt::Write as _;
+use std::time::Instant;
+
+/// Upper edges of the histogram buckets, in milliseconds; the last
+/// bucket takes everything slower.
+const BUCKET_EDGES_MS: [f64; 7] = [10.0, 20.0, 30.0, 40.0, 50.0, 100.0, 250.0];
+/// Worst intervals kept for the report.
+const WORST_KEPT: usize = 5;
+
+/// A part of the frame's work.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// Input effects, feeds, and stepping the simulation
+    Update,
+    /// Drawing the scene and the overlays into the buffer
+    Render,
+    /// Post-processing filters (CRT)
+    Post,
+    /// Diffing the buffer and writing it to the terminal
+    Flush,
+}
+
+const PHASES: [Phase; 4] = [Phase::Update, Phase::Render, Phase::Post, Phase::Flush];
+
+impl Phase {
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Update => "update",
+            Phase::Render => "render",
+            Phase::Post => "post",
+            Phase::Flush => "flush",
+        }
+    }
+}
+
+/// Seconds one frame spent in each phase.
+#[derive(Clone, Copy, Debug)]
+pub struct PhaseTimes {
+    seconds: [f64; PHASES.len()],
+    last_mark: Instant,
+}
+
+impl PhaseTimes {
+    /// Start timing a frame whose work began at `start`.
+    pub fn start(start: Instant) -> Self {
+        Self {
+            seconds: [0.0; PHASES.len()],
+            last_mark: start,
+        }
+    }
+
+    /// Charge the time since the previous mark to `phase`.
+    pub fn mark(&mut self, phase: Phase) {
+        let now = Instant::now();
+        self.seconds[phase as usize] += now.duration_since(self.last_mark).as_secs_f64();
+        self.last_mark = now;
+    }
+
+    pub fn get(&self, phase: Phase) -> f64 {
+        self.seconds[phase as usize]
+    }
+
+    pub fn total(&self) -> f64 {
+        self.seconds.iter().sum()
+    }
+
+    /// The phase that took longest.
+    pub fn slowest(&self) -> Phase {
+        PHASES
+            .into_iter()
+            .max_by(|a, b| self.get(*a).total_cmp(&self.get(*b)))
+            .unwrap_or(Phase::Update)
+    }
+}
+
+/// One slow interval and the work behind it.
+#[derive(Clone, Copy, Debug)]
+pub struct JankFrame {
+    /// Seconds into the run
+    pub at: f64,
+    /// The interval, in seconds
+    pub frame_time: f64,
+    pub phases: PhaseTimes,
+}
+
+impl JankFrame {
+    /// What to blame: the slowest phase, or "other" when the work was less
+    /// than half the interval (the time went to input handling or the
+    /// process wasn't scheduled).
+    pub fn culprit(&self) -> &'static str {
+        if self.phases.total() < self.frame_time / 2.0 {
+            "other"
+        } else {
+            self.phases.slowest().name()
+        }
+    }
+}
+
+/// Counts and worst cases of slow frames over a run.
+#[derive(Default)]
+pub struct JankTracker {
+    histogram: [u64; BUCKET_EDGES_MS.len() + 1],
+    frames: u64,
+    dropped: u64,
+    /// Slowest first
+    worst: Vec<JankFrame>,
+}
+
+impl JankTracker {
+    /// Record an interval of `frame_time` seconds against a `budget`-second
+    /// frame, with the phases of the frame before it.
+    pub fn record(&mut self, at: f64, frame_time: f64, budget: f64, phases: PhaseTimes) {
+        let ms = frame_time * 1000.0;
+        let bucket = BUCKET_EDGES_MS
+            .iter()
+            .position(|&edge| ms < edge)
+            .unwrap_or(BUCKET_EDGES_MS.len());
+        self.histogram[bucket] += 1;
+        self.frames += 1;
+        if budget > 0.0 {
+            self.dropped += ((frame_time / budget).round() as u64).saturating_sub(1);
+        }
+
+        if self.worst.len() < WORST_KEPT
+            || self.worst.last().is_some_and(|w| frame_time > w.frame_time)
+        {
+            let frame = JankFrame {
+                at,
+                frame_time,
+                phases,
+            };
+            let index = self.worst.partition_point(|w| w.frame_time >= frame_time);
+            self.worst.insert(index, frame);
+            self.worst.truncate(WORST_KEPT);
+        }
+    }
+
+    /// Frames dropped so far.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// The slowest interval so far.
+    pub fn worst(&self) -> Option<&JankFrame> {
+        self.worst.first()
+    }
+
+    /// A plain-text summary: the histogram, then the worst intervals.
+    pub fn report(&self) -> String {
+        let mut out = format!(
+            "Frame times: {} frames, {} dropped\n",
+            self.frames, self.dropped
+        );
+        let most = self.histogram.iter().copied().max().unwrap_or(0).max(1);
+        for (i, &count) in self.histogram.iter().enumerate() {
+            let label = match BUCKET_EDGES_MS.get(i) {
+                Some(edge) => format!("<{:>4} ms", edge),
+                None => format!(">={:>3} ms", BUCKET_EDGES_MS[i - 1]),
+            };
+            let bar = "#".repeat((count * 40).div_ceil(most) as usize);
+            let _ = writeln!(out, "  {} {:>7}  {}", label, count, bar);
+        }
+        if !self.worst.is_empty() {
+            out.push_str("Worst frames:\n");
+        }
+        for frame in &self.worst {
+            let phases: Vec<String> = PHASES
+                .iter()
+                .map(|&p| format!("{} {:.1}", p.name(), frame.phases.get(p) * 1000.0))
+                .collect();
+            let _ = writeln!(
+                out,
+                "  {:>8.1} ms at {:.1}s  {:<6}  ({} ms)",
+                frame.frame_time * 1000.0,
+                frame.at,
+                frame.culprit(),
+                phases.join(", ")
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phases(update: f64, flush: f64) -> PhaseTimes {
+        let mut phases = PhaseTimes::start(Instant::now());
+        phases.seconds[Phase::Update as usize] = update;
+        phases.seconds[Phase::Flush as usize] = flush;
+        phases
+    }
+
+    #[test]
+    fn slow_frames_are_counted_and_blamed() {
+        let budget = 1.0 / 30.0;
+        let mut jank = JankTracker::default();
+        for i in 0..100 {
+            jank.record(i as f64 / 30.0, budget, budget, phases(0.002, 0.003));
+        }
+        // A slow terminal write, then a stall outside the frame's work
+        jank.record(4.0, 0.1, budget, phases(0.002, 0.09));
+        jank.record(5.0, 0.2, budget, phases(0.002, 0.003));
+
+        assert_eq!(jank.dropped(), 2 + 5);
+        let worst = jank.worst().unwrap();
+        assert_eq!((worst.at, worst.culprit()), (5.0, "other"));
+        assert_eq!(jank.worst[1].culprit(), "flush");
+        assert_eq!(jank.worst.len(), WORST_KEPT);
+
+        let report = jank.report();
+        assert!(report.starts_with("Frame times: 102 frames, 7 dropped"));
+        assert!(report.contains("<  40 ms     100"));
+        assert!(report.contains("<  30 ms       0"));
+        assert!(report.contains(">=250 ms       0"));
+        assert!(report.contains("flush"));
+    }
+}
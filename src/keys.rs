@@ -0,0 +1,694 @@
+//! Keybindings: the actions the interactive controls trigger, and the keys
+//! bound to them.
+//!
+//! Every action has default keys in `ACTIONS`; the `[keys]` config section
+//! can rebind any of them. The main loop dispatches on `Action` rather than
+//! raw key codes, and the help overlay is generated from the active
+//! `KeyMap`, so remapped keys show up in `?` automatically.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::effects::registry;
+
+/// Something the user can do from the keyboard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Pause,
+    SpeedUp,
+    SpeedDown,
+    SpeedUpFine,
+    SpeedDownFine,
+    DensityDown,
+    DensityUp,
+    DensityDownFine,
+    DensityUpFine,
+    MutationUp,
+    MutationDown,
+    BrightnessUp,
+    BrightnessDown,
+    ContrastUp,
+    ContrastDown,
+    GammaUp,
+    GammaDown,
+    NextEffect,
+    PrevEffect,
+    /// Jump to the Nth registered effect (1-based, like its key)
+    JumpToEffect(u8),
+    BrowsePalettes,
+    NextCharset,
+    BrowseCharsets,
+    Randomize,
+    Reset,
+    ToggleTimer,
+    ToggleCrt,
+    ToggleStats,
+    SavePreset,
+    Screenshot,
+    ScreenshotPng,
+    CommandLine,
+    ToggleHelp,
+    HelpPageUp,
+    HelpPageDown,
+    Quit,
+}
+
+/// Config name, help text, and default keys of an action.
+struct ActionInfo {
+    action: Action,
+    name: &'static str,
+    description: &'static str,
+    default_keys: &'static [&'static str],
+}
+
+/// Every action, in help order.
+const ACTIONS: &[ActionInfo] = &[
+    ActionInfo {
+        action: Action::Pause,
+        name: "pause",
+        description: "Pause / Resume",
+        default_keys: &["space"],
+    },
+    ActionInfo {
+        action: Action::SpeedUp,
+        name: "speed_up",
+        description: "Speed up",
+        default_keys: &["="],
+    },
+    ActionInfo {
+        action: Action::SpeedDown,
+        name: "speed_down",
+        description: "Speed down",
+        default_keys: &["-"],
+    },
+    ActionInfo {
+        action: Action::SpeedUpFine,
+        name: "speed_up_fine",
+        description: "Speed up (fine)",
+        default_keys: &["+"],
+    },
+    ActionInfo {
+        action: Action::SpeedDownFine,
+        name: "speed_down_fine",
+        description: "Speed down (fine)",
+        default_keys: &["_"],
+    },
+    ActionInfo {
+        action: Action::DensityDown,
+        name: "density_down",
+        description: "Density down",
+        default_keys: &["["],
+    },
+    ActionInfo {
+        action: Action::DensityUp,
+        name: "density_up",
+        description: "Density up",
+        default_keys: &["]"],
+    },
+    ActionInfo {
+        action: Action::DensityDownFine,
+        name: "density_down_fine",
+        description: "Density down (fine)",
+        default_keys: &["{"],
+    },
+    ActionInfo {
+        action: Action::DensityUpFine,
+        name: "density_up_fine",
+        description: "Density up (fine)",
+        default_keys: &["}"],
+    },
+    ActionInfo {
+        action: Action::MutationUp,
+        name: "mutation_up",
+        description: "More glyph mutation",
+        default_keys: &["m"],
+    },
+    ActionInfo {
+        action: Action::MutationDown,
+        name: "mutation_down",
+        description: "Less glyph mutation",
+        default_keys: &["M"],
+    },
+    ActionInfo {
+        action: Action::BrightnessUp,
+        name: "brightness_up",
+        description: "Brighter",
+        default_keys: &["b"],
+    },
+    ActionInfo {
+        action: Action::BrightnessDown,
+        name: "brightness_down",
+        description: "Dimmer",
+        default_keys: &["B"],
+    },
+    ActionInfo {
+        action: Action::ContrastUp,
+        name: "contrast_up",
+        description: "More contrast",
+        default_keys: &["v"],
+    },
+    ActionInfo {
+        action: Action::ContrastDown,
+        name: "contrast_down",
+        description: "Less contrast",
+        default_keys: &["V"],
+    },
+    ActionInfo {
+        action: Action::GammaUp,
+        name: "gamma_up",
+        description: "Gamma up (lift dark colors)",
+        default_keys: &["g"],
+    },
+    ActionInfo {
+        action: Action::GammaDown,
+        name: "gamma_down",
+        description: "Gamma down",
+        default_keys: &["G"],
+    },
+    ActionInfo {
+        action: Action::NextEffect,
+        name: "next_effect",
+        description: "Next effect",
+        default_keys: &["n"],
+    },
+    ActionInfo {
+        action: Action::PrevEffect,
+        name: "prev_effect",
+        description: "Previous effect",
+        default_keys: &["p", "N"],
+    },
+    ActionInfo {
+        action: Action::JumpToEffect(1),
+        name: "effect_1",
+        description: "Effect 1",
+        default_keys: &["1"],
+    },
+    ActionInfo {
+        action: Action::JumpToEffect(2),
+        name: "effect_2",
+        description: "Effect 2",
+        default_keys: &["2"],
+    },
+    ActionInfo {
+        action: Action::JumpToEffect(3),
+        name: "effect_3",
+        description: "Effect 3",
+        default_keys: &["3"],
+    },
+    ActionInfo {
+        action: Action::JumpToEffect(4),
+        name: "effect_4",
+        description: "Effect 4",
+        default_keys: &["4"],
+    },
+    ActionInfo {
+        action: Action::JumpToEffect(5),
+        name: "effect_5",
+        description: "Effect 5",
+        default_keys: &["5"],
+    },
+    ActionInfo {
+        action: Action::JumpToEffect(6),
+        name: "effect_6",
+        description: "Effect 6",
+        default_keys: &["6"],
+    },
+    ActionInfo {
+        action: Action::JumpToEffect(7),
+        name: "effect_7",
+        description: "Effect 7",
+        default_keys: &["7"],
+    },
+    ActionInfo {
+        action: Action::JumpToEffect(8),
+        name: "effect_8",
+        description: "Effect 8",
+        default_keys: &["8"],
+    },
+    ActionInfo {
+        action: Action::JumpToEffect(9),
+        name: "effect_9",
+        description: "Effect 9",
+        default_keys: &["9"],
+    },
+    ActionInfo {
+        action: Action::BrowsePalettes,
+        name: "browse_palettes",
+        description: "Browse palettes (live preview)",
+        default_keys: &["C"],
+    },
+    ActionInfo {
+        action: Action::NextCharset,
+        name: "next_charset",
+        description: "Next charset",
+        default_keys: &["k"],
+    },
+    ActionInfo {
+        action: Action::BrowseCharsets,
+        name: "browse_charsets",
+        description: "Browse charsets (live preview)",
+        default_keys: &["K"],
+    },
+    ActionInfo {
+        action: Action::Randomize,
+        name: "randomize",
+        description: "Randomize",
+        default_keys: &["r"],
+    },
+    ActionInfo {
+        action: Action::Reset,
+        name: "reset",
+        description: "Reset to startup settings",
+        default_keys: &["0", "backspace"],
+    },
+    ActionInfo {
+        action: Action::ToggleTimer,
+        name: "toggle_timer",
+        description: "Toggle auto-cycle timer",
+        default_keys: &["t"],
+    },
+    ActionInfo {
+        action: Action::ToggleCrt,
+        name: "toggle_crt",
+        description: "Toggle CRT simulation",
+        default_keys: &["c"],
+    },
+    ActionInfo {
+        action: Action::ToggleStats,
+        name: "toggle_stats",
+        description: "Toggle performance stats",
+        default_keys: &["f"],
+    },
+    ActionInfo {
+        action: Action::SavePreset,
+        name: "save_preset",
+        description: "Save settings as preset",
+        default_keys: &["S"],
+    },
+    ActionInfo {
+        action: Action::Screenshot,
+        name: "screenshot",
+        description: "Screenshot (.txt + .ans)",
+        default_keys: &["s"],
+    },
+    ActionInfo {
+        action: Action::ScreenshotPng,
+        name: "screenshot_png",
+        description: "Screenshot as a PNG image",
+        default_keys: &["P"],
+    },
+    ActionInfo {
+        action: Action::CommandLine,
+        name: "command",
+        description: "Command line (:effect fire)",
+        default_keys: &[":"],
+    },
+    ActionInfo {
+        action: Action::ToggleHelp,
+        name: "toggle_help",
+        description: "Toggle this help",
+        default_keys: &["?"],
+    },
+    ActionInfo {
+        action: Action::HelpPageUp,
+        name: "help_page_up",
+        description: "Previous help page",
+        default_keys: &["pageup"],
+    },
+    ActionInfo {
+        action: Action::HelpPageDown,
+        name: "help_page_down",
+        description: "Next help page",
+        default_keys: &["pagedown"],
+    },
+    ActionInfo {
+        action: Action::Quit,
+        name: "quit",
+        description: "Quit",
+        default_keys: &["q", "esc"],
+    },
+];
+
+impl Action {
+    fn info(self) -> &'static ActionInfo {
+        ACTIONS.iter().find(|i| i.action == self).unwrap()
+    }
+
+    /// The action's name in the `[keys]` config section.
+    pub fn name(self) -> &'static str {
+        self.info().name
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        ACTIONS.iter().find(|i| i.name == name).map(|i| i.action)
+    }
+}
+
+/// A key plus the modifiers that matter for matching. Shift is not tracked:
+/// for characters it is already part of the character ('S' vs 's').
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Key {
+    code: KeyCode,
+    ctrl: bool,
+    alt: bool,
+}
+
+impl Key {
+    pub fn from_event(event: &KeyEvent) -> Self {
+        Self {
+            code: event.code,
+            ctrl: event.modifiers.contains(KeyModifiers::CONTROL),
+            alt: event.modifiers.contains(KeyModifiers::ALT),
+        }
+    }
+}
+
+/// Named keys accepted in `[keys]`, with the label shown in the help.
+const NAMED_KEYS: &[(&str, KeyCode, &str)] = &[
+    ("space", KeyCode::Char(' '), "Space"),
+    ("esc", KeyCode::Esc, "Esc"),
+    ("escape", KeyCode::Esc, "Esc"),
+    ("enter", KeyCode::Enter, "Enter"),
+    ("tab", KeyCode::Tab, "Tab"),
+    ("backspace", KeyCode::Backspace, "Backspace"),
+    ("delete", KeyCode::Delete, "Del"),
+    ("insert", KeyCode::Insert, "Ins"),
+    ("home", KeyCode::Home, "Home"),
+    ("end", KeyCode::End, "End"),
+    ("pageup", KeyCode::PageUp, "PgUp"),
+    ("pgup", KeyCode::PageUp, "PgUp"),
+    ("pagedown", KeyCode::PageDown, "PgDn"),
+    ("pgdn", KeyCode::PageDown, "PgDn"),
+    ("up", KeyCode::Up, "Up"),
+    ("down", KeyCode::Down, "Down"),
+    ("left", KeyCode::Left, "Left"),
+    ("right", KeyCode::Right, "Right"),
+];
+
+impl FromStr for Key {
+    type Err = String;
+
+    /// Parse `"x"`, `"space"`, `"pagedown"`, `"f5"`, optionally prefixed by
+    /// `ctrl+` and/or `alt+`. Single characters are case-sensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rest = s;
+        let (mut ctrl, mut alt) = (false, false);
+        loop {
+            let lower = rest.to_ascii_lowercase();
+            if lower.starts_with("ctrl+") && rest.len() > 5 {
+                ctrl = true;
+                rest = &rest[5..];
+            } else if lower.starts_with("alt+") && rest.len() > 4 {
+                alt = true;
+                rest = &rest[4..];
+            } else {
+                break;
+            }
+        }
+
+        let lower = rest.to_ascii_lowercase();
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if !c.is_control() => KeyCode::Char(c),
+            _ => {
+                if let Some(&(_, code, _)) = NAMED_KEYS.iter().find(|(n, _, _)| *n == lower) {
+                    code
+                } else if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse().ok())
+                    && (1..=12).contains(&n)
+                {
+                    KeyCode::F(n)
+                } else {
+                    return Err(format!("unknown key '{}'", s));
+                }
+            }
+        };
+        let key = Key { code, ctrl, alt };
+        if ctrl && matches!(code, KeyCode::Char('c' | 'C')) {
+            return Err("Ctrl+C is reserved for quitting".to_string());
+        }
+        Ok(key)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        if let Some(&(_, _, label)) = NAMED_KEYS.iter().find(|(_, c, _)| *c == self.code) {
+            return write!(f, "{}", label);
+        }
+        match self.code {
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+/// One line of the help overlay.
+pub struct KeyBinding {
+    /// Key label as shown to the user ("Space", "+ =")
+    pub keys: String,
+    pub description: String,
+}
+
+/// Active key-to-action bindings.
+pub struct KeyMap {
+    bindings: Vec<(Key, Action)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self::with_overrides(&BTreeMap::new()).0
+    }
+}
+
+impl KeyMap {
+    /// Build the map from the defaults plus `[keys]` overrides (action name
+    /// to keys; an empty list unbinds the action). Overrides win over
+    /// defaults. Returns the map and any problems, each tagged with the
+    /// action it concerns: unknown names or keys, a key bound to two
+    /// overridden actions (the first keeps it), or an action left with no
+    /// key because an override took its default.
+    pub fn with_overrides(
+        overrides: &BTreeMap<String, Vec<String>>,
+    ) -> (Self, Vec<(String, String)>) {
+        let mut issues = Vec::new();
+        let mut bindings: Vec<(Key, Action)> = Vec::new();
+
+        for (name, keys) in overrides {
+            let Some(action) = Action::from_name(name) else {
+                issues.push((name.clone(), format!("unknown action '{}'", name)));
+                continue;
+            };
+            for spec in keys {
+                match spec.parse::<Key>() {
+                    Ok(key) => match bindings.iter().find(|(k, _)| *k == key) {
+                        Some(&(_, other)) if other != action => issues.push((
+                            name.clone(),
+                            format!(
+                                "key '{}' is bound to both {} and {}",
+                                key,
+                                other.name(),
+                                name
+                            ),
+                        )),
+                        Some(_) => {}
+                        None => bindings.push((key, action)),
+                    },
+                    Err(e) => issues.push((name.clone(), e)),
+                }
+            }
+        }
+
+        for info in ACTIONS {
+            if overrides.contains_key(info.name) {
+                continue;
+            }
+            let mut displaced = None;
+            let mut bound = false;
+            for spec in info.default_keys {
+                let key: Key = spec.parse().expect("default keys parse");
+                match bindings.iter().find(|(k, _)| *k == key) {
+                    Some(&(_, other)) => displaced = Some((key, other)),
+                    None => {
+                        bindings.push((key, info.action));
+                        bound = true;
+                    }
+                }
+            }
+            if let (false, Some((key, other))) = (bound, displaced) {
+                issues.push((
+                    other.name().to_string(),
+                    format!(
+                        "'{}' has no key left: its default '{}' is now bound to {}",
+                        info.name,
+                        key,
+                        other.name()
+                    ),
+                ));
+            }
+        }
+
+        (Self { bindings }, issues)
+    }
+
+    /// The action bound to a key press, if any.
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        let key = Key::from_event(event);
+        self.bindings
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|&(_, action)| action)
+    }
+
+    /// Help overlay lines for the bound actions, in help order. The region
+    /// keys are fixed and only listed when drawing into a `--region`.
+    pub fn help_entries(&self, region: bool) -> Vec<KeyBinding> {
+        let mut entries: Vec<KeyBinding> = ACTIONS
+            .iter()
+            .filter_map(|info| {
+                let keys: Vec<String> = self
+                    .bindings
+                    .iter()
+                    .filter(|(_, a)| *a == info.action)
+                    .map(|(k, _)| k.to_string())
+                    .collect();
+                // Effect hotkeys name their effect; keys past the end of
+                // the registry do nothing and stay out of the help
+                let description = match info.action {
+                    Action::JumpToEffect(n) => {
+                        format!("Effect: {}", registry::effect_name_at(n as usize - 1)?)
+                    }
+                    _ => info.description.to_string(),
+                };
+                (!keys.is_empty()).then(|| KeyBinding {
+                    keys: keys.join(" "),
+                    description,
+                })
+            })
+            .collect();
+        if region {
+            let at = entries.len().saturating_sub(1);
+            entries.insert(
+                at,
+                KeyBinding {
+                    keys: "Arrows".to_string(),
+                    description: "Move region (Shift: resize)".to_string(),
+                },
+            );
+        }
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    fn overrides(pairs: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+        pairs
+            .iter()
+            .map(|(a, keys)| (a.to_string(), keys.iter().map(|k| k.to_string()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_and_labels_keys() {
+        let key: Key = "ctrl+alt+x".parse().unwrap();
+        assert_eq!(key.to_string(), "Ctrl+Alt+x");
+        assert_eq!("PageDown".parse::<Key>().unwrap().to_string(), "PgDn");
+        assert_eq!("f5".parse::<Key>().unwrap().to_string(), "F5");
+        assert_eq!("+".parse::<Key>().unwrap().to_string(), "+");
+        assert!("f13".parse::<Key>().is_err());
+        assert!("hyper+x".parse::<Key>().is_err());
+        assert!("ctrl+c".parse::<Key>().is_err());
+    }
+
+    #[test]
+    fn default_map_dispatches_like_the_old_controls() {
+        let map = KeyMap::default();
+        let none = KeyModifiers::NONE;
+        assert_eq!(
+            map.action(&press(KeyCode::Char(' '), none)),
+            Some(Action::Pause)
+        );
+        assert_eq!(
+            map.action(&press(KeyCode::Char('='), none)),
+            Some(Action::SpeedUp)
+        );
+        // Shift doesn't get in the way of shifted characters
+        assert_eq!(
+            map.action(&press(KeyCode::Char('+'), KeyModifiers::SHIFT)),
+            Some(Action::SpeedUpFine)
+        );
+        assert_eq!(
+            map.action(&press(KeyCode::Char('S'), KeyModifiers::SHIFT)),
+            Some(Action::SavePreset)
+        );
+        assert_eq!(map.action(&press(KeyCode::Esc, none)), Some(Action::Quit));
+        assert_eq!(map.action(&press(KeyCode::Char('x'), none)), None);
+        assert_eq!(
+            map.action(&press(KeyCode::Char('n'), KeyModifiers::CONTROL)),
+            None
+        );
+    }
+
+    #[test]
+    fn overrides_replace_defaults_and_report_conflicts() {
+        let (map, issues) = KeyMap::with_overrides(&overrides(&[
+            ("pause", &["p"]),
+            ("next_effect", &["right", "ctrl+n"]),
+        ]));
+        assert!(issues.is_empty(), "{:?}", issues);
+        let none = KeyModifiers::NONE;
+        assert_eq!(map.action(&press(KeyCode::Char(' '), none)), None);
+        assert_eq!(
+            map.action(&press(KeyCode::Char('p'), none)),
+            Some(Action::Pause)
+        );
+        assert_eq!(
+            map.action(&press(KeyCode::Char('n'), KeyModifiers::CONTROL)),
+            Some(Action::NextEffect)
+        );
+        let help = map.help_entries(false);
+        assert_eq!(help[0].keys, "p");
+        assert!(
+            help.iter()
+                .any(|b| b.keys == "Right Ctrl+n" && b.description == "Next effect")
+        );
+
+        // Taking another action's only key leaves it unbound
+        let (map, issues) = KeyMap::with_overrides(&overrides(&[("toggle_stats", &["r"])]));
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].1.contains("'randomize' has no key left"));
+        assert_eq!(
+            map.action(&press(KeyCode::Char('r'), KeyModifiers::NONE)),
+            Some(Action::ToggleStats)
+        );
+
+        // Two overrides fighting over a key, plus bad names
+        let (_, issues) = KeyMap::with_overrides(&overrides(&[
+            ("pause", &["x"]),
+            ("quit", &["x"]),
+            ("warp", &["w"]),
+            ("randomize", &["nope"]),
+        ]));
+        let messages: Vec<&str> = issues.iter().map(|(_, m)| m.as_str()).collect();
+        assert_eq!(messages.len(), 3, "{:?}", messages);
+        assert!(messages.contains(&"key 'x' is bound to both pause and quit"));
+        assert!(messages.contains(&"unknown action 'warp'"));
+        assert!(messages.contains(&"unknown key 'nope'"));
+    }
+}
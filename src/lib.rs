@@ -0,0 +1,99 @@
+//! DigitalRain - Matrix digital rain effects as a library.
+//!
+//! Everything the `digital_rain` binary draws is built from the pieces
+//! here, so other programs can embed the effects:
+//!
+//! - [`buffer::ScreenBuffer`]: the grid of colored cells every effect
+//!   renders into, which the caller then flushes or reads back
+//! - [`effects::Effect`] and [`effects::registry`]: the effect trait, and
+//!   creating any built-in effect by name
+//! - [`rain::RainField`]: the core falling-rain simulation
+//! - [`color::palette`] and [`rain::chars`]: palettes and character sets
+//! - [`config::Config`]: the settings an effect is created from
+//! - [`effects::params::EffectSpec`]: an effect name plus values for the
+//!   parameters it advertises (`registry::effect_params`)
+//! - [`runner::Runner`]: steps an effect frame by frame, with observers
+//!   that receive every composed frame
+//!
+//! ```
+//! use clap::Parser;
+//! use digitalrain::buffer::ScreenBuffer;
+//! use digitalrain::config::{Cli, Config, ConfigFile};
+//! use digitalrain::effects::params::EffectSpec;
+//! use digitalrain::effects::registry;
+//!
+//! let config = Config::resolve(&Cli::parse_from(["digital_rain"]), &ConfigFile::default());
+//! let spec = EffectSpec::new("pulse").with("depth", 0.8);
+//! let mut effect = registry::create_effect(&spec, 80, 24, &config).unwrap();
+//! let mut buffer = ScreenBuffer::new(80, 24);
+//! effect.update(1.0 / 30.0);
+//! effect.render(&mut buffer);
+//! ```
+//!
+//! The remaining modules (terminal handling, overlays, key bindings,
+//! recording) support the binary's interactive mode.
+
+// Effects and the pieces they're built from
+pub mod buffer;
+pub mod color;
+pub mod effects;
+pub mod event;
+pub mod font;
+pub mod rain;
+pub mod rng;
+pub mod runner;
+
+// Configuration and post-processing
+#[cfg(feature = "cdylib")]
+pub mod capi;
+pub mod config;
+pub mod crt;
+pub mod formats;
+pub mod post;
+pub mod transition;
+#[cfg(feature = "ratatui")]
+pub mod tui;
+#[cfg(feature = "wasm")]
+pub mod web;
+
+// Support for the interactive binary
+pub mod audio;
+pub mod command;
+pub mod control;
+pub mod deflate;
+pub mod dejavu;
+pub mod gif;
+pub mod headless;
+pub mod image;
+pub mod inflate;
+pub mod jank;
+#[cfg(feature = "terminal")]
+pub mod keys;
+pub mod logging;
+pub mod metrics;
+pub mod notify;
+pub mod overlay;
+pub mod pattern;
+pub mod png;
+pub mod power;
+pub mod presenter;
+pub mod react;
+pub mod recorder;
+pub mod scheduler;
+pub mod serve;
+#[cfg(feature = "terminal")]
+pub mod session;
+pub mod shutdown;
+pub mod sprite;
+#[cfg(feature = "ssh")]
+pub mod ssh;
+pub mod svg;
+#[cfg(feature = "terminal")]
+pub mod sync;
+#[cfg(feature = "terminal")]
+pub mod sysmon;
+pub mod tail;
+#[cfg(feature = "terminal")]
+pub mod terminal;
+pub mod timing;
+pub mod weather;
@@ -0,0 +1,204 @@
+//! Debug log for `--log-file` and `--log-level`.
+//!
+//! Anything printed to stderr while the animation runs lands on the
+//! alternate screen and is gone when it's left, so what happened during a
+//! run goes to a file instead. Code logs through the `tracing` macros
+//! (`tracing::info!`, `tracing::debug!`, ...); `FileLogger` writes each
+//! event as one line:
+//!
+//! ```text
+//! [    1.204] INFO  digital_rain: effect switched from="classic" to="fire"
+//! ```
+//!
+//! The time is seconds since the log was opened; the first line records
+//! the wall-clock time. Lines are flushed as they're written, so the log
+//! survives a crash. Spans aren't used, so they're only given ids.
+
+use std::fmt::{self, Write as _};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+/// How much `--log-level` lets through.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    /// Startup settings, effect switches, resizes, and warnings
+    #[default]
+    Info,
+    /// Also config details and dropped frames
+    Debug,
+    /// Also every frame's phase timings
+    Trace,
+}
+
+impl LogLevel {
+    fn level(self) -> Level {
+        match self {
+            LogLevel::Error => Level::ERROR,
+            LogLevel::Warn => Level::WARN,
+            LogLevel::Info => Level::INFO,
+            LogLevel::Debug => Level::DEBUG,
+            LogLevel::Trace => Level::TRACE,
+        }
+    }
+}
+
+/// A `tracing` subscriber that writes events to a file, one per line.
+pub struct FileLogger<W: Write> {
+    out: Mutex<W>,
+    /// The most verbose level written
+    level: Level,
+    start: Instant,
+    next_span: AtomicU64,
+}
+
+impl<W: Write> FileLogger<W> {
+    /// Log events at `level` and above to `out`, starting with a line that
+    /// records when.
+    pub fn new(mut out: W, level: LogLevel) -> Self {
+        let unix_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let _ = writeln!(
+            out,
+            "--- digital_rain {} started at unix time {}",
+            env!("CARGO_PKG_VERSION"),
+            unix_time
+        );
+        let _ = out.flush();
+        Self {
+            out: Mutex::new(out),
+            level: level.level(),
+            start: Instant::now(),
+            next_span: AtomicU64::new(1),
+        }
+    }
+}
+
+/// Append to the log file at `path` and send all `tracing` events there
+/// from now on.
+pub fn init(path: &Path, level: LogLevel) -> Result<(), String> {
+    let file: File = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    tracing::subscriber::set_global_default(FileLogger::new(file, level))
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+impl<W: Write + Send + 'static> Subscriber for FileLogger<W> {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        // More verbose levels compare greater
+        *metadata.level() <= self.level
+    }
+
+    fn max_level_hint(&self) -> Option<tracing::level_filters::LevelFilter> {
+        Some(tracing::level_filters::LevelFilter::from_level(self.level))
+    }
+
+    fn new_span(&self, _span: &Attributes<'_>) -> Id {
+        Id::from_u64(self.next_span.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let metadata = event.metadata();
+        let mut line = format!(
+            "[{:>9.3}] {:<5} {}:",
+            self.start.elapsed().as_secs_f64(),
+            metadata.level(),
+            metadata.target()
+        );
+        event.record(&mut LineVisitor(&mut line));
+        let mut out = self.out.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writeln!(out, "{}", line);
+        let _ = out.flush();
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+/// Appends an event's message, then its other fields as `name=value`.
+struct LineVisitor<'a>(&'a mut String);
+
+impl Visit for LineVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            let _ = write!(self.0, " {}", value);
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        let _ = write!(self.0, " {}={:.3}", field.name(), value);
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, " {:?}", value);
+        } else {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    /// A writer the test can read back.
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn events_at_the_level_are_written_one_per_line() {
+        let out = Shared::default();
+        let logger = FileLogger::new(out.clone(), LogLevel::Debug);
+        tracing::subscriber::with_default(logger, || {
+            tracing::info!(from = "classic", to = "fire", "effect switched");
+            tracing::debug!(width = 80, height = 24, update_ms = 1.5, "resized");
+            tracing::trace!("left out");
+        });
+
+        let text = String::from_utf8(out.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("--- digital_rain"));
+        assert!(lines[1].ends_with(
+            "] INFO  digitalrain::logging::tests: effect switched from=\"classic\" to=\"fire\""
+        ));
+        assert!(lines[2].ends_with(
+            "DEBUG digitalrain::logging::tests: resized width=80 height=24 update_ms=1.500"
+        ));
+    }
+}
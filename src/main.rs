@@ -3,28 +3,52 @@
 //! A visual effects application that renders the iconic Matrix falling
 //! characters in your terminal. Built with Rust and crossterm for
 //! cross-platform compatibility (Windows-first).
+//!
+//! This is the command-line front end; the effects themselves live in the
+//! `digitalrain` library (src/lib.rs).
 
-mod buffer;
-mod color;
-mod config;
-mod crt;
-mod effects;
-mod overlay;
-mod rain;
-mod terminal;
-mod timing;
-mod transition;
+use std::time::{Duration, Instant};
 
 use clap::Parser;
-use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseEvent, MouseEventKind,
+};
+
+use digitalrain::{
+    audio, buffer, color, command, config, control, crt, dejavu, effects, headless, image, jank,
+    keys, logging, metrics, notify, overlay, pattern, post, power, presenter, rain, recorder, rng,
+    scheduler, serve, session, shutdown, sync, sysmon, tail, terminal, timing, transition, weather,
+};
 
-use buffer::ScreenBuffer;
-use config::{Cli, Config};
+use audio::{AudioFeed, BeatAction};
+use buffer::{Region, ScreenBuffer};
+use color::{Color, palette};
+use command::Command;
+use config::{Cli, CliCommand, Config, ConfigFile, PresetConfig, RandomConstraints};
+use control::ControlFeed;
 use crt::CrtFilter;
+use effects::Effect;
+use effects::intro::IntroEffect;
 use effects::registry;
+use jank::{JankTracker, Phase, PhaseTimes};
+use keys::{Action, KeyMap};
+use metrics::{FrameSample, MetricsLog};
+use notify::NotifyFeed;
+use overlay::{EditResult, LineEditor, OverlayStyle, Picker, PickerKind};
+use pattern::Pattern;
+use post::{ColorTransform, PipelineChain, PostProcessor};
+use power::{NormalProfile, PowerSaver, PowerSaverMode, SAVER_DENSITY, SaverReason};
+use presenter::Presenter;
+use rain::chars;
+use recorder::CastRecorder;
+use scheduler::Scheduler;
+use session::{SessionHeader, SessionRecorder, SessionReplay};
+use sysmon::SysMonitor;
+use tail::TailFeed;
 use terminal::Terminal;
-use timing::FrameClock;
-use transition::Transition;
+use timing::{FrameClock, FrameStats, TimerResolution};
+use transition::{PaletteFade, PaletteMorph, Transition, TransitionKind};
+use weather::{Conditions, WeatherFeed};
 
 /// How many frames to show the status message after a parameter change.
 const STATUS_DISPLAY_FRAMES: u32 = 60;
@@ -33,9 +57,80 @@ const STATUS_DISPLAY_FRAMES: u32 = 60;
 const SPEED_STEP: f64 = 0.2;
 /// Density adjustment step per keypress.
 const DENSITY_STEP: f64 = 0.2;
+/// Speed/density step for the fine-adjust keys and each mouse wheel notch.
+const FINE_STEP: f64 = 0.05;
+/// Glyph mutation rate step per keypress.
+const MUTATION_STEP: f64 = 0.02;
+/// Brightness and contrast step per keypress.
+const TONE_STEP: f64 = 0.05;
+/// Gamma step per keypress.
+const GAMMA_STEP: f64 = 0.1;
+
+/// Length of the transition when the effect changes.
+const TRANSITION_DURATION: f64 = 0.75;
+
+/// How long a palette-only change takes to blend in.
+const PALETTE_FADE_SECS: f64 = 1.0;
+
+/// How long the --intro sequence takes to dissolve into the selected effect.
+const INTRO_DISSOLVE_SECS: f64 = 1.5;
+
+/// Length of the full-screen flash when a `--countdown` expires.
+const COUNTDOWN_FLASH_SECS: f64 = 1.5;
+/// Color the screen flashes to when a `--countdown` expires.
+const FLASH_COLOR: Color = Color::Rgb {
+    r: 255,
+    g: 255,
+    b: 255,
+};
+
+/// Length of the fade-to-black at the end of a `--duration --exit-fade` run.
+const EXIT_FADE_SECS: f64 = 2.0;
 
 fn main() {
-    let cli = Cli::parse();
+    terminal::install_panic_hook();
+    let mut cli = Cli::parse();
+    // `ctl` talks to another instance and never draws anything
+    if let Some(CliCommand::Ctl { socket, command }) = &cli.subcommand {
+        let path = socket.clone().unwrap_or_else(control::default_socket_path);
+        if let Err(e) = control::send(&path, command) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    // Before --replay swaps in the recorded command line, so it's this
+    // one's --log-file that counts
+    if let Some(path) = cli.log_file.as_deref()
+        && let Err(e) = logging::init(path, cli.log_level)
+    {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    tracing::info!(args = ?std::env::args().skip(1).collect::<Vec<_>>(), "starting");
+    // --replay and --sync-follow run the session's command line instead of
+    // this one
+    let loaded = match cli.replay.as_deref() {
+        Some(path) => Some(SessionReplay::load(path)),
+        None => cli.sync_follow.as_deref().map(sync::follow),
+    };
+    let mut replay = match loaded {
+        Some(Ok(replay)) => {
+            let args = replay.header().args.iter().map(String::as_str);
+            cli = Cli::parse_from(std::iter::once("digital_rain").chain(args));
+            cli.seed = Some(replay.header().seed);
+            Some(replay)
+        }
+        Some(Err(e)) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        None => None,
+    };
+    // One session seed behind every random choice: --seed, or a fresh one
+    // that the stats overlay shows so the run can be replayed
+    let simulation = rng::Simulation::new(cli.seed);
+    cli.seed = Some(simulation.seed());
 
     // Handle list flags (print info and exit, no terminal setup needed)
     if cli.list_effects {
@@ -47,6 +142,11 @@ fn main() {
         return;
     }
     if cli.list_charsets {
+        let config_file = config::load_config_file(cli.config.as_deref());
+        if let Err(e) = config::register_charsets(&cli, &config_file) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
         registry::print_charsets();
         return;
     }
@@ -63,6 +163,70 @@ fn main() {
             }
             Err(e) => {
                 eprintln!("Error saving preset: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // Handle --check-config (validate and exit non-zero on problems)
+    if cli.check_config {
+        match config::check_config(&cli) {
+            Ok((path, issues)) if issues.is_empty() => {
+                println!("Config OK: {}", path.display());
+            }
+            Ok((path, issues)) => {
+                for issue in &issues {
+                    if issue.line == 0 {
+                        eprintln!("{}: {}", path.display(), issue.message);
+                    } else {
+                        eprintln!(
+                            "{}:{}:{}: {}",
+                            path.display(),
+                            issue.line,
+                            issue.column,
+                            issue.message
+                        );
+                    }
+                }
+                eprintln!("{} problem(s) found", issues.len());
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // Handle preset management commands (act on the config file and exit)
+    if let Some(ref name) = cli.delete_preset {
+        match config::delete_preset(&cli, name) {
+            Ok(path) => println!("Preset '{}' deleted from {}", name, path.display()),
+            Err(e) => {
+                eprintln!("Error deleting preset: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if let Some(ref spec) = cli.rename_preset {
+        match config::rename_preset(&cli, spec) {
+            Ok((old, new)) => println!("Preset '{}' renamed to '{}'", old, new),
+            Err(e) => {
+                eprintln!("Error renaming preset: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if let Some(ref name) = cli.show_preset {
+        match config::show_preset(&cli, name) {
+            Ok(toml) => print!("{}", toml),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
             }
         }
         return;
@@ -70,195 +234,1021 @@ fn main() {
 
     // Build config from CLI args + config file + preset (or randomize if --random)
     // When randomizing, carry over CLI flags that shouldn't be randomized
-    // (timer, forward direction, CRT settings).
+    // (timer, forward direction, static glyphs, column spacing, mutation,
+    // gusts, CRT settings).
+    let mut config_file = config::load_config_file(cli.config.as_deref());
+    let overlay_style = OverlayStyle::from_config(&config_file.overlay);
+    let crt_affects_overlays = config_file.overlay.crt_affects_overlays.unwrap_or(false);
+    let rain_halo = cli.rain_halo || config_file.rain.halo.unwrap_or(false);
+    if let Err(e) = config::register_charsets(&cli, &config_file) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    let keymap = {
+        let (keymap, issues) = KeyMap::with_overrides(&config_file.key_overrides());
+        for (_, issue) in issues {
+            eprintln!("Warning: [keys] {}", issue);
+        }
+        keymap
+    };
+    let random_constraints = match RandomConstraints::resolve(&cli, &config_file) {
+        Ok(constraints) => constraints,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    // Drives --random and the 'r' key; seeded by --seed for reproducible runs
+    let mut config_rng = simulation.rng(rng::Stream::Config);
+    // Effect switches crossfade unless --transition says otherwise;
+    // auto-cycle mixes styles by default
+    let manual_transition = cli.transition.unwrap_or(TransitionKind::Crossfade);
+    let auto_transition = cli.transition.unwrap_or(TransitionKind::Random);
+    let mut transition_rng = simulation.rng(rng::Stream::Transition);
+    let mut deja_vu = (!cli.no_easter_eggs)
+        .then(|| dejavu::DejaVu::new(cli.deja_vu_chance, simulation.rng(rng::Stream::DejaVu)));
     let mut config = if cli.random {
-        let mut c = Config::randomized();
-        c.forward = cli.forward;
-        c.auto_cycle_secs = cli.timer.map(|t| t.max(1.0));
-        c.crt_enabled = cli.crt;
-        c.crt_intensity = cli.crt_intensity.unwrap_or(0.7).clamp(0.0, 1.0);
+        // Random looks, with everything else resolved as usual
+        let resolved = Config::resolve(&cli, &config_file);
+        let mut c = Config::randomized(&random_constraints, &mut config_rng);
+        c.keep_runtime_settings(&resolved);
+        c.crt_enabled = resolved.crt_enabled;
+        c.apply_effect_defaults(&config_file);
         c
     } else {
-        Config::from_cli(&cli)
+        Config::resolve(&cli, &config_file)
+    };
+
+    // A playlist drives auto-cycling through presets and picks the starting config
+    let mut scheduler = match cli.playlist {
+        Some(ref name) => match Config::playlist(&cli, &config_file, name) {
+            Ok(steps) => Scheduler::playlist(steps),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        None => Scheduler::random(config.auto_cycle_secs, random_constraints.clone(), cli.seed),
     };
+    if let Some(first) = scheduler.current_config() {
+        config = first.clone();
+    }
+    // A --color-morph starts from its first palette
+    if let Some(ref morph) = cli.color_morph {
+        config.palette_name = morph.from.clone();
+    }
+    // What the reset key goes back to
+    let startup_config = config.clone();
+    tracing::info!(
+        effect = %config.effect_name,
+        palette = %config.palette_name,
+        charset = %config.charset_name,
+        speed = config.speed_multiplier,
+        density = config.density_multiplier,
+        fps = config.target_fps,
+        seed = ?config.seed,
+        "config resolved"
+    );
+    tracing::debug!(
+        config_file = ?cli.config,
+        preset = ?cli.preset,
+        random = cli.random,
+        playlist = ?cli.playlist,
+        post_filters = ?config.post_filters,
+        crt = config.crt_enabled,
+        auto_cycle_secs = ?config.auto_cycle_secs,
+        "config sources"
+    );
+
+    if let Err(e) = config.check_effect_params() {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    if let Some(ref repo) = cli.git_repo
+        && !repo.is_dir()
+    {
+        eprintln!("Error: --git-repo {} is not a directory", repo.display());
+        std::process::exit(1);
+    }
+    if let Some(ref path) = cli.text_file
+        && let Err(e) = std::fs::read_to_string(path)
+    {
+        eprintln!("Error: --text-file {}: {}", path.display(), e);
+        std::process::exit(1);
+    }
 
     if cli.random {
         eprintln!(
-            "Random: effect={}, color={}, charset={}, speed={:.1}, density={:.1}",
+            "Random: effect={}, color={}, charset={}, speed={:.1}, density={:.1}, seed={}",
             config.effect_name,
             config.palette_name,
             config.charset_name,
             config.speed_multiplier,
             config.density_multiplier,
+            simulation.seed(),
         );
     }
 
-    // Initialize the terminal (alternate screen, raw mode, hidden cursor)
-    let mut term = Terminal::init().expect("Failed to initialize terminal");
+    // Smoke test: every effect, in memory, for CI
+    if cli.headless {
+        match headless::smoke_test(&config, cli.frames, &mut std::io::stdout()) {
+            Ok(0) => {}
+            Ok(_) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
 
-    let mut buffer = ScreenBuffer::new(term.width, term.height);
-    let mut clock = FrameClock::new(config.target_fps);
+    // Headless export: render frames to files without touching the terminal
+    if let (Some(frames), Some(out_dir)) = (cli.render_frames, cli.out.as_deref()) {
+        let options =
+            headless::ExportOptions::for_current_terminal(frames, out_dir, cli.plain_text);
+        match headless::export_frames(&config, &options) {
+            Ok(written) => println!("Wrote {} file(s) to {}", written, out_dir.display()),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
 
-    // Create the selected effect
-    let mut effect = registry::create_effect(&config.effect_name, term.width, term.height, &config)
-        .unwrap_or_else(|| {
+    // GIF export: rasterize --duration worth of frames into an animated GIF
+    if let (Some(path), Some(duration)) = (cli.export_gif.as_deref(), cli.duration) {
+        let (width, height) = headless::terminal_size();
+        match headless::export_gif(&config, path, duration, width, height) {
+            Ok(frames) => println!("Wrote {} frame(s) to {}", frames, path.display()),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // Still export: run the effect headless, then save its last frame as a
+    // rasterized PNG or as SVG text
+    let still = match (cli.export_png.as_deref(), cli.export_svg.as_deref()) {
+        (Some(path), _) => Some((path, headless::export_png as StillExport)),
+        (None, Some(path)) => Some((path, headless::export_svg as StillExport)),
+        (None, None) => None,
+    };
+    if let Some((path, export)) = still {
+        let (width, height) = headless::terminal_size();
+        let duration = cli.duration.unwrap_or(headless::STILL_WARMUP_SECS);
+        match export(&config, path, duration, width, height) {
+            Ok(()) => println!("Wrote {}", path.display()),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // Streaming server: the terminal is left alone; viewers get the rain
+    if let Some(address) = cli.serve.as_deref() {
+        match serve::Server::bind(address, cli.serve_max_clients) {
+            Ok(server) => {
+                if let Ok(bound) = server.local_addr() {
+                    eprintln!("Serving rain on {} (telnet or nc; Ctrl+C to stop)", bound);
+                }
+                server.run(&config);
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // SSH server: the same, for `ssh -p PORT host`
+    if let Some(address) = cli.ssh.as_deref() {
+        serve_ssh(address, &cli, &config);
+        return;
+    }
+
+    // Logo watermark: decode up front so a bad file is reported plainly
+    let logo_image = match cli.logo.as_deref().map(image::Image::load) {
+        Some(Ok(image)) => Some(image),
+        Some(Err(e)) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        None => None,
+    };
+
+    if let Some(ref name) = cli.countdown_effect
+        && !registry::effect_names().contains(&name.as_str())
+    {
+        eprintln!(
+            "Error: unknown --countdown-effect '{}'. Run --list-effects to see options.",
+            name
+        );
+        std::process::exit(1);
+    }
+
+    // Notification feed: check the pipe exists before taking over the terminal
+    let notify_feed = match cli.notify.as_deref().map(NotifyFeed::spawn) {
+        Some(Ok(feed)) => Some(feed),
+        Some(Err(e)) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        None => None,
+    };
+
+    // Audio input and the tailed log: only one feed can read stdin
+    let stdin_readers: Vec<&str> = [
+        ("--notify", &cli.notify),
+        ("--audio", &cli.audio),
+        ("--tail", &cli.tail),
+    ]
+    .into_iter()
+    .filter(|(_, path)| path.as_deref().is_some_and(|p| p.as_os_str() == "-"))
+    .map(|(flag, _)| flag)
+    .chain(cli.control_stdin.then_some("--control-stdin"))
+    .collect();
+    if let [first, second, ..] = stdin_readers[..] {
+        eprintln!("Error: {first} and {second} can't both read stdin");
+        std::process::exit(1);
+    }
+    let audio_feed = match cli.audio.as_deref() {
+        Some(path) => match AudioFeed::spawn(path, cli.audio_rate, cli.beat_sensitivity) {
+            Ok(feed) => Some(feed),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    // Scripted commands: from stdin, and from a socket for `ctl`
+    let mut control_feeds: Vec<ControlFeed> = Vec::new();
+    if cli.control_stdin {
+        control_feeds.push(ControlFeed::spawn_stdin());
+    }
+    if let Some(path) = &cli.control_socket {
+        let path = path.clone().unwrap_or_else(control::default_socket_path);
+        match ControlFeed::listen(&path) {
+            Ok(feed) => control_feeds.push(feed),
+            Err(e) => {
+                eprintln!("Error: --control-socket: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    let tail_highlight = cli.tail_highlight.as_deref().map(|source| {
+        Pattern::new(source).unwrap_or_else(|e| {
+            eprintln!("Error: --tail-highlight: {}", e);
+            std::process::exit(1);
+        })
+    });
+    let tail_feed = match cli.tail.as_deref() {
+        Some(path) => match TailFeed::spawn(path) {
+            Ok(feed) => Some(feed),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    // Current weather for --weather, checked on a background thread
+    #[cfg(feature = "weather")]
+    let weather_feed = cli.weather.as_deref().map(|city| {
+        let refresh = cli.weather_refresh.max(weather::MIN_REFRESH_SECS);
+        WeatherFeed::spawn(
+            Box::new(weather::OpenMeteo::new(city)),
+            Duration::from_secs_f64(refresh),
+        )
+    });
+    #[cfg(not(feature = "weather"))]
+    let weather_feed: Option<WeatherFeed> = cli.weather.as_ref().map(|_| {
+        eprintln!("Error: --weather needs a build with the weather feature");
+        std::process::exit(1);
+    });
+    let mut weather_now: Option<Conditions> = None;
+    // Beat flashes and glitch bursts are post filters that wait for events
+    if audio_feed.is_some() {
+        for (action, filter) in [(BeatAction::Flash, "flash"), (BeatAction::Glitch, "glitch")] {
+            if cli.beat_action.contains(&action) && !config.post_filters.iter().any(|f| f == filter)
+            {
+                config.post_filters.push(filter.to_string());
+            }
+        }
+    }
+
+    // Session recording: open the cast file before taking over the terminal
+    // so a bad path is reported plainly
+    let mut recorder = match cli.record.as_deref() {
+        Some(path) => {
+            let (width, height) = headless::terminal_size();
+            match CastRecorder::create(path, width, height) {
+                Ok(recorder) => Some(recorder),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => None,
+    };
+
+    // Session file for --replay: seed, command line, then frames and input
+    let session_header = {
+        let (width, height) = headless::terminal_size();
+        SessionHeader {
+            seed: simulation.seed(),
+            width,
+            height,
+            args: session::recorded_args(std::env::args().skip(1)),
+        }
+    };
+    let mut session_recorder = match cli.record_session.as_deref() {
+        Some(path) => match SessionRecorder::create(path, &session_header) {
+            Ok(recorder) => Some(recorder),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    // --sync-leader sends the same session, live, to every follower
+    let mut sync_recorder = match cli.sync_leader.as_deref() {
+        Some(address) => {
+            let recorder = sync::SyncLeader::bind(address).and_then(|leader| {
+                let bound = leader.local_addr();
+                SessionRecorder::new(leader, &session_header)
+                    .map(|recorder| (recorder, bound))
+                    .map_err(|e| e.to_string())
+            });
+            match recorder {
+                Ok((recorder, bound)) => {
+                    eprintln!("Leading sync on {}", bound);
+                    Some(recorder)
+                }
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => None,
+    };
+    if let Some(ref replay) = replay {
+        let (width, height) = headless::terminal_size();
+        let header = replay.header();
+        if (width, height) != (header.width, header.height) && replay.is_live() {
             eprintln!(
-                "Unknown effect '{}', using classic. Run --list-effects to see options.",
-                config.effect_name
+                "Warning: the leader's terminal is {}x{} and this one is {}x{}; the animation will differ",
+                header.width, header.height, width, height
             );
-            config.effect_name = "classic".to_string();
-            registry::create_effect("classic", term.width, term.height, &config).unwrap()
-        });
+        } else if (width, height) != (header.width, header.height) {
+            eprintln!(
+                "Warning: the session was recorded at {}x{} and this terminal is {}x{}; the replay will differ",
+                header.width, header.height, width, height
+            );
+        }
+    }
+
+    // From here on SIGTERM and friends end the run cleanly instead of
+    // leaving the terminal in raw mode
+    shutdown::install();
 
-    // CRT simulation filter (post-processing)
-    let mut crt_filter = CrtFilter::new(
-        term.width,
-        term.height,
-        config.crt_enabled,
-        config.crt_intensity,
+    // Idle activation: hold off until the user stops typing for --idle
+    if let Some(idle) = cli.idle {
+        match terminal::wait_for_idle(Duration::from_secs_f64(idle)) {
+            Ok(true) => {}
+            Ok(false) => std::process::exit(shutdown::exit_status().unwrap_or(0)),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Initialize the terminal (alternate screen, raw mode, hidden cursor)
+    let mut term = match cli.region {
+        Some(_) => Terminal::init_region(),
+        None => Terminal::init(),
+    }
+    .expect("Failed to initialize terminal");
+    let env = |name| std::env::var(name).unwrap_or_default();
+    tracing::info!(
+        width = term.width,
+        height = term.height,
+        term = %env("TERM"),
+        colorterm = %env("COLORTERM"),
+        term_program = %env("TERM_PROGRAM"),
+        "terminal"
     );
+    // The wheel adjusts speed and density; in screensaver mode mouse
+    // movement counts as activity, so it has to be reported either way
+    if (cli.screensaver || !cli.no_mouse)
+        && let Err(e) = term.enable_mouse_capture()
+    {
+        tracing::warn!("no mouse capture: {}", e);
+    }
+
+    // Drawing area: the whole terminal, or the --region inside it
+    let mut region = cli.region;
+    let mut view = fit_view(region, term.width, term.height);
+    let mut buffer = ScreenBuffer::new(view.width, view.height);
+    buffer.set_background(config.background);
+    buffer.set_region(view);
+    let mut clock = FrameClock::new(config.target_fps);
+    let mut timer_resolution = Some(TimerResolution::fine());
+    // --render-thread: frames are diffed and written off the main loop
+    let mut presenter = cli
+        .render_thread
+        .then(|| Presenter::spawn(std::io::stdout()));
+    clock.set_adaptive(!cli.fixed_fps);
+    // --power-saver: when to switch to the low-power profile, and whether
+    // it's on. Sessions being recorded or replayed keep their density, so
+    // they stay frame-exact
+    let exact = replay.is_some() || session_recorder.is_some() || sync_recorder.is_some();
+    let mut power_saver = PowerSaver::new(
+        if exact {
+            PowerSaverMode::Off
+        } else {
+            cli.power_saver
+        },
+        Duration::from_secs_f64(cli.power_saver_idle),
+        Instant::now(),
+    );
+    // Dropped frames and what caused them, for the stats and --jank-report;
+    // an interval is charged to the work of the frame before it
+    let mut jank = JankTracker::default();
+    let mut last_phases: Option<PhaseTimes> = None;
+
+    // Create the selected effect
+    let mut effect =
+        registry::create_effect(&config.effect_spec(), view.width, view.height, &config)
+            .unwrap_or_else(|e| {
+                eprintln!("{e}, using classic. Run --list-effects to see options.");
+                config.effect_name = "classic".to_string();
+                registry::create_effect(&config.effect_spec(), view.width, view.height, &config)
+                    .unwrap()
+            });
+
+    // Block-letter banner; without --banner-color it follows the palette
+    let banner_color = cli.banner_color.as_deref().and_then(|name| {
+        let color = palette::color_by_name(name);
+        if color.is_none() {
+            eprintln!("Unknown banner color '{}', using the palette color", name);
+        }
+        color
+    });
+    let mut banner = cli.banner.clone().map(|text| overlay::Banner {
+        text,
+        position: cli.banner_position,
+        color: Color::Reset,
+        pulse: cli.banner_pulse,
+    });
+
+    let mut logo = logo_image.map(|image| {
+        overlay::Logo::new(
+            image,
+            cli.logo_position,
+            cli.logo_opacity,
+            cli.logo_width,
+            view.width,
+        )
+    });
+
+    // CPU/RAM/network/disk readings, sampled once a second in the
+    // background, for the --sysmon overlay and --react
+    let sysmon = (cli.sysmon || cli.react.is_some()).then(SysMonitor::spawn);
+
+    // Cinematic boot sequence that plays before the selected effect
+    let mut intro = cli
+        .intro
+        .then(|| IntroEffect::with_config(view.width, view.height, &config));
+
+    // Post-processing chain: CRT simulation and any --post filters
+    let mut post_chain = PipelineChain::from_config(view.width, view.height, &config);
+    // Brightness/contrast/gamma, applied to the finished frame
+    let mut color_transform = ColorTransform::from_config(&config);
 
     // Runtime state
     let mut paused = false;
     let mut show_help = false;
+    let mut help_page = 0;
+    let mut show_stats = false;
+    // Recent frame times for the stats overlay (about 4 seconds at 30fps)
+    let mut frame_stats = FrameStats::new(120);
+    let mut metrics_log = match cli.metrics_file.as_deref().map(MetricsLog::create) {
+        Some(Ok(log)) => Some(log),
+        Some(Err(e)) => {
+            eprintln!("Error: --metrics-file: {}", e);
+            std::process::exit(1);
+        }
+        None => None,
+    };
     let mut status_message: Option<String> = None;
     let mut status_frames_remaining: u32 = 0;
-
-    // Auto-cycle timer state
-    const DEFAULT_CYCLE_SECS: f64 = 30.0;
-    let mut auto_cycle_enabled = config.auto_cycle_secs.is_some();
-    let mut auto_cycle_interval = config.auto_cycle_secs;
-    let mut auto_cycle_elapsed: f64 = 0.0;
+    // Open text prompt: save-preset name or `:` command (None when not typing)
+    let mut prompt: Option<(Prompt, LineEditor)> = None;
+    // Set by the screenshot keys; the next frame is saved before overlays
+    let mut screenshot_pending: Option<Screenshot> = None;
+    // Open palette or charset picker (None when closed)
+    let mut picker: Option<Picker> = None;
 
     // Crossfade transition state (None when no transition is active)
-    const TRANSITION_DURATION: f64 = 0.75;
     let mut active_transition: Option<Transition> = None;
+    // Palette change in progress, with the palette name it's heading to
+    let mut palette_fade: Option<(String, PaletteFade)> = None;
+    // --color-morph drift, with the palette name it runs under
+    let mut palette_morph: Option<(String, PaletteMorph)> = cli.color_morph.as_ref().map(|m| {
+        let (from, to) = (
+            palette::palette_by_name(&m.from),
+            palette::palette_by_name(&m.to),
+        );
+        (m.from.clone(), PaletteMorph::new(from, to, m.period))
+    });
+
+    // Wall-clock start, for --duration (keeps counting while paused)
+    let run_start = Instant::now();
+    // --notify messages waiting for or on screen
+    let mut toasts = overlay::ToastQueue::default();
+    // Set once the --countdown reaches zero
+    let mut countdown_expired = false;
+
+    // Commands waiting to be applied at the top of the next frame
+    let mut pending_commands: Vec<Command> = Vec::new();
 
     // Main loop: poll events, update, render
-    loop {
-        match term.poll_event(clock.poll_timeout()) {
+    'frames: loop {
+        // Set when the drawing area changes (terminal resize or region keys)
+        let mut relayout = false;
+        // Speed/density change requested by a key or the mouse wheel
+        let mut speed_step = 0.0;
+        let mut density_step = 0.0;
+        let mut mutation_step = 0.0;
+
+        if shutdown::requested() {
+            tracing::info!("shutdown signal received");
+            break 'frames;
+        }
+
+        // Commands from the : prompt, --control-stdin, and --control-socket
+        for command in std::mem::take(&mut pending_commands) {
+            let msg = match command {
+                Command::Quit => break 'frames,
+                Command::SavePreset(name) => save_live_preset(
+                    &cli,
+                    &mut config_file,
+                    &config,
+                    effect.as_ref(),
+                    post_chain.crt().is_enabled(),
+                    &name,
+                ),
+                // Kept on the config too, so switching effects keeps them
+                Command::Speed(speed) => {
+                    config.speed_multiplier = speed;
+                    effect.set_speed(speed);
+                    format!("Speed: {:.1}x", speed)
+                }
+                Command::Density(density) => {
+                    config.density_multiplier = density;
+                    effect.set_density(density);
+                    format!("Density: {:.1}x", density)
+                }
+                Command::Mutation(rate) => {
+                    config.mutation_rate = rate;
+                    effect.set_mutation(rate);
+                    format!("Mutation: {:.2}", rate)
+                }
+                Command::Crt(on) => {
+                    let on = match on {
+                        Some(on) => {
+                            post_chain.crt_mut().set_enabled(on);
+                            on
+                        }
+                        None => post_chain.crt_mut().toggle(),
+                    };
+                    (if on { "CRT: ON" } else { "CRT: OFF" }).to_string()
+                }
+                // The rest rebuild the effect from the config: a preset
+                // brings its own speed and density, while a new effect
+                // keeps the live ones stored above
+                Command::LoadPreset(name) => match config_file.resolve_preset(&name) {
+                    Ok(_) => {
+                        config = Config::resolve_with_preset(&cli, &config_file, Some(&name));
+                        post_chain.crt_mut().set_enabled(config.crt_enabled);
+                        active_transition = switch_effect(
+                            &mut effect,
+                            &config,
+                            view,
+                            manual_transition.pick(&mut transition_rng),
+                        )
+                        .or(active_transition);
+                        scheduler.reset();
+                        format!("Preset: {}", name)
+                    }
+                    Err(e) => e,
+                },
+                Command::Effect(name) => {
+                    config.effect_name = name;
+                    config.apply_effect_defaults(&config_file);
+                    if let Some(crt) = config_file
+                        .effect_defaults
+                        .get(&config.effect_name)
+                        .and_then(|d| d.crt)
+                    {
+                        post_chain.crt_mut().set_enabled(crt);
+                    }
+                    active_transition = switch_effect(
+                        &mut effect,
+                        &config,
+                        view,
+                        manual_transition.pick(&mut transition_rng),
+                    )
+                    .or(active_transition);
+                    format!("Effect: {}", config.effect_name)
+                }
+                Command::Color(name) => {
+                    fade_palette(&mut palette_fade, &config.palette_name, &name);
+                    config.palette_name = name;
+                    format!("Palette: {}", config.palette_name)
+                }
+                Command::Charset(name) => {
+                    effect.set_charset(&chars::charset_by_name(&name));
+                    config.charset_name = name;
+                    format!("Charset: {}", config.charset_name)
+                }
+                Command::Pause(on) => {
+                    paused = on.unwrap_or(!paused);
+                    (if paused { "PAUSED" } else { "RESUMED" }).to_string()
+                }
+                Command::Banner(Some(text)) => {
+                    let msg = format!("Banner: {}", text);
+                    match banner.as_mut() {
+                        Some(b) => b.text = text,
+                        None => {
+                            banner = Some(overlay::Banner {
+                                text,
+                                position: cli.banner_position,
+                                color: Color::Reset,
+                                pulse: cli.banner_pulse,
+                            })
+                        }
+                    }
+                    msg
+                }
+                Command::Banner(None) => {
+                    banner = None;
+                    "Banner: off".to_string()
+                }
+            };
+            set_status(&mut status_message, &mut status_frames_remaining, &msg);
+        }
+
+        let polled = match replay.as_mut() {
+            // Replaying: the recorded input drives the session, and the only
+            // live keys are the ones that quit
+            Some(replay) => match term.poll_event(Duration::ZERO) {
+                Ok(Some(event)) if is_quit(&event, &keymap) => Ok(Some(event)),
+                _ => Ok(replay.next_input(clock.poll_timeout())),
+            },
+            None => term.poll_event(clock.poll_timeout()),
+        };
+        if let (Some(rec), Ok(Some(event))) = (session_recorder.as_mut(), &polled) {
+            rec.input(event).ok();
+        }
+        if let (Some(rec), Ok(Some(event))) = (sync_recorder.as_mut(), &polled) {
+            rec.input(event).ok();
+        }
+        match polled {
             Ok(Some(event)) => {
-                if Terminal::should_quit(&event) {
+                // Screensaver: any input ends the session instead of acting
+                // as a control
+                if cli.screensaver && Terminal::is_activity(&event) {
                     break;
                 }
-
-                if let Event::Resize(_, _) = event {
-                    term.update_size().ok();
-                    buffer.resize(term.width, term.height);
-                    effect.resize(term.width, term.height);
-                    crt_filter.resize(term.width, term.height);
-                    if let Some(ref mut t) = active_transition {
-                        t.resize(term.width, term.height);
-                    }
+                if Terminal::is_activity(&event) {
+                    power_saver.input(Instant::now());
                 }
 
-                // Handle interactive key controls (Press only — ignore Release/Repeat
-                // which Windows/crossterm sends and would double-toggle states)
-                if let Event::Key(KeyEvent {
-                    code,
-                    kind: KeyEventKind::Press,
-                    ..
-                }) = event
+                // A picker takes the keys while it's open: moving the
+                // selection previews that palette or charset on the running
+                // effect
+                if let Some(browser) = picker.as_mut()
+                    && let Event::Key(
+                        key @ KeyEvent {
+                            kind: KeyEventKind::Press,
+                            ..
+                        },
+                    ) = event
                 {
-                    match code {
-                        // Pause / Resume
-                        KeyCode::Char(' ') => {
-                            paused = !paused;
+                    let kind = browser.kind();
+                    let name = match browser.handle_key(key) {
+                        EditResult::Continue => browser.selected().to_string(),
+                        EditResult::Submit => {
+                            let name = browser.selected().to_string();
+                            picker = None;
+                            let label = match kind {
+                                PickerKind::Palette => "Palette",
+                                PickerKind::Charset => "Charset",
+                            };
                             set_status(
                                 &mut status_message,
                                 &mut status_frames_remaining,
-                                if paused { "PAUSED" } else { "RESUMED" },
+                                &format!("{}: {}", label, name),
                             );
+                            name
+                        }
+                        EditResult::Cancel => {
+                            let name = browser.original().to_string();
+                            picker = None;
+                            name
                         }
+                    };
+                    match kind {
+                        PickerKind::Palette if name != config.palette_name => {
+                            fade_palette(&mut palette_fade, &config.palette_name, &name);
+                            config.palette_name = name;
+                        }
+                        PickerKind::Charset if name != config.charset_name => {
+                            effect.set_charset(&chars::charset_by_name(&name));
+                            config.charset_name = name;
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
 
-                        // Speed up
-                        KeyCode::Char('+') | KeyCode::Char('=') => {
-                            let new_speed = (effect.speed() + SPEED_STEP).clamp(0.1, 10.0);
-                            effect.set_speed(new_speed);
+                // While a prompt is open, key presses go to the line editor
+                // instead of the normal controls (so typing 'q' doesn't quit).
+                if let Some((kind, editor)) = prompt.as_mut()
+                    && let Event::Key(
+                        key @ KeyEvent {
+                            kind: KeyEventKind::Press,
+                            ..
+                        },
+                    ) = event
+                {
+                    // Tab completes commands and names; ambiguous matches
+                    // are listed in the status line
+                    if *kind == Prompt::Command && key.code == KeyCode::Tab {
+                        let presets: Vec<&str> =
+                            config_file.presets.keys().map(String::as_str).collect();
+                        let completion = command::complete(editor.text(), &presets);
+                        editor.set_text(completion.line);
+                        if !completion.matches.is_empty() {
                             set_status(
                                 &mut status_message,
                                 &mut status_frames_remaining,
-                                &format!("Speed: {:.1}x", new_speed),
+                                &completion.matches.join("  "),
                             );
                         }
+                        continue;
+                    }
 
-                        // Speed down
-                        KeyCode::Char('-') => {
-                            let new_speed = (effect.speed() - SPEED_STEP).clamp(0.1, 10.0);
-                            effect.set_speed(new_speed);
-                            set_status(
-                                &mut status_message,
-                                &mut status_frames_remaining,
-                                &format!("Speed: {:.1}x", new_speed),
-                            );
+                    let kind = *kind;
+                    let text = editor.text().trim().to_string();
+                    let parsed = match editor.handle_key(key) {
+                        EditResult::Continue => continue,
+                        EditResult::Cancel => {
+                            prompt = None;
+                            if kind == Prompt::SavePreset {
+                                set_status(
+                                    &mut status_message,
+                                    &mut status_frames_remaining,
+                                    "Save cancelled",
+                                );
+                            }
+                            continue;
+                        }
+                        EditResult::Submit if kind == Prompt::SavePreset => {
+                            Ok(Command::SavePreset(text))
                         }
+                        EditResult::Submit => command::parse(&text),
+                    };
+                    prompt = None;
+
+                    match parsed {
+                        Ok(command) => pending_commands.push(command),
+                        Err(e) => set_status(&mut status_message, &mut status_frames_remaining, &e),
+                    }
+                    continue;
+                }
 
-                        // Density up
-                        KeyCode::Char(']') => {
-                            let new_density = (effect.density() + DENSITY_STEP).clamp(0.1, 10.0);
-                            effect.set_density(new_density);
+                if Terminal::is_interrupt(&event) {
+                    break;
+                }
+
+                if let Event::Resize(_, _) = event {
+                    term.update_size().ok();
+                    tracing::info!(width = term.width, height = term.height, "terminal resized");
+                    if let Some(rec) = recorder.as_mut() {
+                        rec.resize(term.width, term.height).ok();
+                    }
+                    relayout = true;
+                }
+
+                // Mouse wheel: fine speed steps, or density with Ctrl
+                if let Event::Mouse(MouseEvent {
+                    kind: kind @ (MouseEventKind::ScrollUp | MouseEventKind::ScrollDown),
+                    modifiers,
+                    ..
+                }) = event
+                {
+                    let step = if kind == MouseEventKind::ScrollUp {
+                        FINE_STEP
+                    } else {
+                        -FINE_STEP
+                    };
+                    if modifiers.contains(KeyModifiers::CONTROL) {
+                        density_step = step;
+                    } else {
+                        speed_step = step;
+                    }
+                }
+
+                // Any other key skips the intro straight to the dissolve
+                if let Event::Key(KeyEvent {
+                    kind: KeyEventKind::Press,
+                    ..
+                }) = event
+                    && let Some(skipped) = intro.take()
+                {
+                    active_transition = Some(Transition::new(
+                        Box::new(skipped),
+                        view.width,
+                        view.height,
+                        INTRO_DISSOLVE_SECS,
+                    ));
+                    continue;
+                }
+
+                // Handle interactive key controls (Press only — ignore Release/Repeat
+                // which Windows/crossterm sends and would double-toggle states)
+                if let Event::Key(
+                    key @ KeyEvent {
+                        code,
+                        modifiers,
+                        kind: KeyEventKind::Press,
+                        ..
+                    },
+                ) = event
+                {
+                    // Move the --region (Shift: resize it instead). Fixed keys,
+                    // not part of the remappable key map.
+                    if region.is_some()
+                        && matches!(
+                            code,
+                            KeyCode::Left | KeyCode::Right | KeyCode::Up | KeyCode::Down
+                        )
+                    {
+                        let (dx, dy) = match code {
+                            KeyCode::Left => (-1, 0),
+                            KeyCode::Right => (1, 0),
+                            KeyCode::Up => (0, -1),
+                            _ => (0, 1),
+                        };
+                        let adjusted = if modifiers.contains(KeyModifiers::SHIFT) {
+                            view.resized(dx, dy)
+                        } else {
+                            view.moved(dx, dy)
+                        };
+                        let adjusted = adjusted.clip(term.width, term.height);
+                        region = Some(adjusted);
+                        relayout = true;
+                        set_status(
+                            &mut status_message,
+                            &mut status_frames_remaining,
+                            &format!(
+                                "Region: {},{} {}x{}",
+                                adjusted.x, adjusted.y, adjusted.width, adjusted.height
+                            ),
+                        );
+                    }
+
+                    match keymap.action(&key) {
+                        // Pause / Resume
+                        Some(Action::Pause) => {
+                            paused = !paused;
                             set_status(
                                 &mut status_message,
                                 &mut status_frames_remaining,
-                                &format!("Density: {:.1}x", new_density),
+                                if paused { "PAUSED" } else { "RESUMED" },
                             );
                         }
 
-                        // Density down
-                        KeyCode::Char('[') => {
-                            let new_density = (effect.density() - DENSITY_STEP).clamp(0.1, 10.0);
-                            effect.set_density(new_density);
-                            set_status(
-                                &mut status_message,
-                                &mut status_frames_remaining,
-                                &format!("Density: {:.1}x", new_density),
+                        // Speed and density, in coarse or fine steps
+                        Some(Action::SpeedUp) => speed_step = SPEED_STEP,
+                        Some(Action::SpeedDown) => speed_step = -SPEED_STEP,
+                        Some(Action::SpeedUpFine) => speed_step = FINE_STEP,
+                        Some(Action::SpeedDownFine) => speed_step = -FINE_STEP,
+                        Some(Action::DensityUp) => density_step = DENSITY_STEP,
+                        Some(Action::DensityDown) => density_step = -DENSITY_STEP,
+                        Some(Action::DensityUpFine) => density_step = FINE_STEP,
+                        Some(Action::DensityDownFine) => density_step = -FINE_STEP,
+                        Some(Action::MutationUp) => mutation_step = MUTATION_STEP,
+                        Some(Action::MutationDown) => mutation_step = -MUTATION_STEP,
+
+                        // Brightness, contrast, and gamma of the final frame
+                        Some(
+                            action @ (Action::BrightnessUp
+                            | Action::BrightnessDown
+                            | Action::ContrastUp
+                            | Action::ContrastDown
+                            | Action::GammaUp
+                            | Action::GammaDown),
+                        ) => {
+                            let (mut b, mut c, mut g) = (
+                                color_transform.brightness(),
+                                color_transform.contrast(),
+                                color_transform.gamma(),
                             );
+                            match action {
+                                Action::BrightnessUp => b += TONE_STEP,
+                                Action::BrightnessDown => b -= TONE_STEP,
+                                Action::ContrastUp => c += TONE_STEP,
+                                Action::ContrastDown => c -= TONE_STEP,
+                                Action::GammaUp => g += GAMMA_STEP,
+                                _ => g -= GAMMA_STEP,
+                            }
+                            color_transform.set(b, c, g);
+                            config.brightness = color_transform.brightness();
+                            config.contrast = color_transform.contrast();
+                            config.gamma = color_transform.gamma();
+                            let message = match action {
+                                Action::BrightnessUp | Action::BrightnessDown => {
+                                    format!("Brightness: {:.2}", config.brightness)
+                                }
+                                Action::ContrastUp | Action::ContrastDown => {
+                                    format!("Contrast: {:.2}", config.contrast)
+                                }
+                                _ => format!("Gamma: {:.1}", config.gamma),
+                            };
+                            set_status(&mut status_message, &mut status_frames_remaining, &message);
                         }
 
-                        // Next effect (with crossfade transition)
-                        KeyCode::Char('n') => {
-                            let next_name = registry::next_effect_name(&config.effect_name);
-                            config.effect_name = next_name.to_string();
-                            if let Some(new_effect) =
-                                registry::create_effect(next_name, term.width, term.height, &config)
-                            {
-                                let old_effect = std::mem::replace(&mut effect, new_effect);
-                                active_transition = Some(Transition::new(
-                                    old_effect,
-                                    term.width,
-                                    term.height,
-                                    TRANSITION_DURATION,
-                                ));
+                        // Next, previous, or numbered effect (with crossfade
+                        // transition); numbers past the last effect do nothing
+                        Some(
+                            action @ (Action::NextEffect
+                            | Action::PrevEffect
+                            | Action::JumpToEffect(_)),
+                        ) => {
+                            let target = match action {
+                                Action::PrevEffect => {
+                                    Some(registry::prev_effect_name(&config.effect_name))
+                                }
+                                Action::JumpToEffect(n) => registry::effect_name_at(n as usize - 1),
+                                _ => Some(registry::next_effect_name(&config.effect_name)),
+                            };
+                            if let Some(next_name) = target {
+                                config.effect_name = next_name.to_string();
+                                config.apply_effect_defaults(&config_file);
+                                // Only touch CRT if the effect asks for it, so a
+                                // manual 'c' toggle survives cycling
+                                if let Some(crt) = config_file
+                                    .effect_defaults
+                                    .get(next_name)
+                                    .and_then(|d| d.crt)
+                                {
+                                    post_chain.crt_mut().set_enabled(crt);
+                                }
+                                active_transition = switch_effect(
+                                    &mut effect,
+                                    &config,
+                                    view,
+                                    manual_transition.pick(&mut transition_rng),
+                                )
+                                .or(active_transition);
+                                set_status(
+                                    &mut status_message,
+                                    &mut status_frames_remaining,
+                                    &format!("Effect: {}", config.effect_name),
+                                );
                             }
-                            set_status(
-                                &mut status_message,
-                                &mut status_frames_remaining,
-                                &format!("Effect: {}", config.effect_name),
-                            );
                         }
 
                         // Randomize (with crossfade transition)
-                        KeyCode::Char('r') => {
-                            let forward = config.forward;
-                            config = Config::randomized();
-                            config.forward = forward;
-                            crt_filter.set_enabled(config.crt_enabled);
-                            if let Some(new_effect) = registry::create_effect(
-                                &config.effect_name,
-                                term.width,
-                                term.height,
+                        Some(Action::Randomize) => {
+                            let mut next = Config::randomized(&random_constraints, &mut config_rng);
+                            next.keep_runtime_settings(&config);
+                            config = next;
+                            config.apply_effect_defaults(&config_file);
+                            post_chain.crt_mut().set_enabled(config.crt_enabled);
+                            active_transition = switch_effect(
+                                &mut effect,
                                 &config,
-                            ) {
-                                let old_effect = std::mem::replace(&mut effect, new_effect);
-                                active_transition = Some(Transition::new(
-                                    old_effect,
-                                    term.width,
-                                    term.height,
-                                    TRANSITION_DURATION,
-                                ));
-                            }
+                                view,
+                                manual_transition.pick(&mut transition_rng),
+                            )
+                            .or(active_transition);
                             // Reset auto-cycle timer so it counts from the new effect
-                            auto_cycle_elapsed = 0.0;
+                            scheduler.reset();
                             set_status(
                                 &mut status_message,
                                 &mut status_frames_remaining,
@@ -271,25 +1261,38 @@ fn main() {
                             );
                         }
 
-                        // Toggle auto-cycle timer
-                        KeyCode::Char('t') => {
-                            // If no interval was set via --timer, use the default
-                            if auto_cycle_interval.is_none() {
-                                auto_cycle_interval = Some(DEFAULT_CYCLE_SECS);
-                            }
-                            auto_cycle_enabled = !auto_cycle_enabled;
-                            auto_cycle_elapsed = 0.0;
-                            let msg = if auto_cycle_enabled {
-                                format!("Auto-cycle: ON ({:.0}s)", auto_cycle_interval.unwrap())
-                            } else {
-                                "Auto-cycle: OFF".to_string()
-                            };
+                        // Back to the startup speed, density, colors, tone, and CRT
+                        // intensity, keeping the effect running
+                        Some(Action::Reset) => {
+                            reset_tuning(
+                                effect.as_mut(),
+                                post_chain.crt_mut(),
+                                &mut config,
+                                &startup_config,
+                            );
+                            color_transform = ColorTransform::from_config(&config);
+                            set_status(
+                                &mut status_message,
+                                &mut status_frames_remaining,
+                                &format!(
+                                    "Reset: {:.1}x speed, {:.1}x density, {} / {}",
+                                    config.speed_multiplier,
+                                    config.density_multiplier,
+                                    config.palette_name,
+                                    config.charset_name,
+                                ),
+                            );
+                        }
+
+                        // Toggle auto-cycle timer (random or playlist)
+                        Some(Action::ToggleTimer) => {
+                            let msg = scheduler.toggle();
                             set_status(&mut status_message, &mut status_frames_remaining, &msg);
                         }
 
                         // Toggle CRT simulation
-                        KeyCode::Char('c') => {
-                            let on = crt_filter.toggle();
+                        Some(Action::ToggleCrt) => {
+                            let on = post_chain.crt_mut().toggle();
                             set_status(
                                 &mut status_message,
                                 &mut status_frames_remaining,
@@ -297,11 +1300,68 @@ fn main() {
                             );
                         }
 
+                        // Toggle performance stats overlay
+                        Some(Action::ToggleStats) => {
+                            show_stats = !show_stats;
+                        }
+
+                        // Save the live settings as a preset (opens a name prompt)
+                        Some(Action::SavePreset) => {
+                            prompt = Some((Prompt::SavePreset, LineEditor::new()));
+                        }
+
+                        // Open the palette or charset picker
+                        Some(Action::BrowsePalettes) => {
+                            picker = Some(Picker::new(PickerKind::Palette, &config.palette_name));
+                        }
+                        Some(Action::BrowseCharsets) => {
+                            picker = Some(Picker::new(PickerKind::Charset, &config.charset_name));
+                        }
+
+                        // Cycle the charset in place (the effect keeps running)
+                        Some(Action::NextCharset) => {
+                            let next = chars::next_charset_name(&config.charset_name);
+                            effect.set_charset(&chars::charset_by_name(next));
+                            config.charset_name = next.to_string();
+                            set_status(
+                                &mut status_message,
+                                &mut status_frames_remaining,
+                                &format!("Charset: {}", next),
+                            );
+                        }
+
+                        // Save the next frame as text files in the current directory
+                        Some(Action::Screenshot) => {
+                            screenshot_pending = Some(Screenshot::Text);
+                        }
+                        Some(Action::ScreenshotPng) => {
+                            screenshot_pending = Some(Screenshot::Png);
+                        }
+
+                        // Open the `:` command line
+                        Some(Action::CommandLine) => {
+                            prompt = Some((Prompt::Command, LineEditor::new()));
+                        }
+
                         // Toggle help overlay
-                        KeyCode::Char('?') => {
+                        Some(Action::ToggleHelp) => {
                             show_help = !show_help;
+                            help_page = 0;
                         }
 
+                        // Page through the help when it doesn't fit
+                        Some(Action::HelpPageDown) if show_help => {
+                            let count = keymap.help_entries(region.is_some()).len();
+                            let (_, pages) =
+                                overlay::help_layout(view.height, count, &overlay_style);
+                            help_page = (help_page + 1).min(pages - 1);
+                        }
+                        Some(Action::HelpPageUp) if show_help => {
+                            help_page = help_page.saturating_sub(1);
+                        }
+
+                        Some(Action::Quit) => break,
+
                         _ => {}
                     }
                 }
@@ -310,46 +1370,332 @@ fn main() {
             Err(_) => break,
         }
 
-        if !clock.tick() {
+        // Speed and density are kept on the config too, like the mutation
+        // rate, so the next effect runs the same way
+        if speed_step != 0.0 {
+            let new_speed = (effect.speed() + speed_step).clamp(0.1, 10.0);
+            config.speed_multiplier = new_speed;
+            effect.set_speed(new_speed);
+            set_status(
+                &mut status_message,
+                &mut status_frames_remaining,
+                &format!("Speed: {:.2}x", new_speed),
+            );
+        }
+        if density_step != 0.0 {
+            let new_density = (effect.density() + density_step).clamp(0.1, 10.0);
+            config.density_multiplier = new_density;
+            effect.set_density(new_density);
+            set_status(
+                &mut status_message,
+                &mut status_frames_remaining,
+                &format!("Density: {:.2}x", new_density),
+            );
+        }
+        if mutation_step != 0.0 {
+            config.mutation_rate = (config.mutation_rate + mutation_step).clamp(0.0, 1.0);
+            effect.set_mutation(config.mutation_rate);
+            set_status(
+                &mut status_message,
+                &mut status_frames_remaining,
+                &format!("Mutation: {:.2}", config.mutation_rate),
+            );
+        }
+
+        if relayout {
+            // Blank the old area first so a moved region leaves nothing behind
+            if region.is_some() {
+                buffer.clear();
+                present(&mut buffer, &mut recorder, &mut presenter).ok();
+            }
+            view = fit_view(region, term.width, term.height);
+            buffer.set_region(view);
+            effect.resize(view.width, view.height);
+            post_chain.resize(view.width, view.height);
+            if let Some(ref mut t) = active_transition {
+                t.resize(view.width, view.height);
+            }
+            if let Some(ref mut i) = intro {
+                i.resize(view.width, view.height);
+            }
+            if let Some(ref mut l) = logo {
+                l.fit(view.width);
+            }
+        }
+
+        let frame_due = clock.tick();
+        // Following a leader: frames run as the leader's arrive, not on the
+        // local clock
+        let frame_due = match replay {
+            Some(ref replay) if replay.is_live() => replay.frame_ready(),
+            _ => frame_due,
+        };
+        if !frame_due {
             continue;
         }
+        if let Some(ref mut replay) = replay {
+            match replay.next_frame() {
+                Some(delta_time) => clock.set_delta_time(delta_time),
+                None => break,
+            }
+        }
+        if let Some(rec) = session_recorder.as_mut() {
+            rec.frame(clock.delta_time()).ok();
+        }
+        if let Some(rec) = sync_recorder.as_mut() {
+            rec.frame(clock.delta_time()).ok();
+        }
+        frame_stats.record(clock.delta_time());
+        let work_start = Instant::now();
+        if let Some(phases) = last_phases {
+            let at = run_start.elapsed().as_secs_f64();
+            let dropped = jank.dropped();
+            jank.record(at, clock.delta_time(), 1.0 / clock.fps(), phases);
+            if jank.dropped() > dropped {
+                tracing::debug!(
+                    frame_ms = clock.delta_time() * 1000.0,
+                    slowest = phases.slowest().name(),
+                    "dropped {} frame(s)",
+                    jank.dropped() - dropped
+                );
+            }
+        }
+        let mut phases = PhaseTimes::start(work_start);
 
-        // Update the effect (skip when paused)
-        if !paused {
-            effect.update(clock.delta_time());
-
-            // Auto-cycle: accumulate time and randomize when interval reached
-            if auto_cycle_enabled && let Some(interval) = auto_cycle_interval {
-                auto_cycle_elapsed += clock.delta_time();
-                if auto_cycle_elapsed >= interval {
-                    auto_cycle_elapsed = 0.0;
-                    let forward = config.forward;
-                    config = Config::randomized();
-                    config.forward = forward;
-                    crt_filter.set_enabled(config.crt_enabled);
-                    if let Some(new_effect) = registry::create_effect(
-                        &config.effect_name,
-                        term.width,
-                        term.height,
-                        &config,
-                    ) {
-                        let old_effect = std::mem::replace(&mut effect, new_effect);
-                        active_transition = Some(Transition::new(
-                            old_effect,
-                            term.width,
-                            term.height,
-                            TRANSITION_DURATION,
-                        ));
+        // Low-power profile: fewer frames and columns, no post filters, and
+        // the coarse OS timer
+        power_saver.update(work_start);
+        if power_saver.active().is_some() != power_saver.applied() {
+            if let Some(normal) = power_saver.leave() {
+                // Back to what ran before, even if `r` or the auto-cycle
+                // swapped the config in the meantime
+                clock.set_max_fps(normal.fps);
+                timer_resolution.get_or_insert_with(TimerResolution::fine);
+                config.target_fps = normal.fps;
+                config.density_multiplier = normal.density;
+                effect.set_density(normal.effect_density);
+            } else {
+                power_saver.enter(NormalProfile {
+                    fps: config.target_fps,
+                    density: config.density_multiplier,
+                    effect_density: effect.density(),
+                });
+                clock.set_max_fps(config.target_fps.min(power::SAVER_FPS));
+                timer_resolution.take();
+                // Effects started while saving get the thinner density too
+                config.density_multiplier = (config.density_multiplier * SAVER_DENSITY).max(0.1);
+                effect.set_density((effect.density() * SAVER_DENSITY).clamp(0.1, 10.0));
+            }
+            let msg = match power_saver.active() {
+                Some(reason) => format!("Power saver: {}", reason.label()),
+                None => "Power saver: off".to_string(),
+            };
+            tracing::info!("{}", msg);
+            set_status(&mut status_message, &mut status_frames_remaining, &msg);
+        }
+
+        // Timed run: exit once --duration has elapsed
+        let elapsed = run_start.elapsed().as_secs_f64();
+        if cli.duration.is_some_and(|d| elapsed >= d) {
+            break;
+        }
+
+        // Pick up new notifications; toasts age even while paused
+        if let Some(ref feed) = notify_feed {
+            for line in feed.drain() {
+                toasts.push(line);
+            }
+        }
+        toasts.update(clock.delta_time());
+
+        // Scripted commands from --control-stdin and --control-socket; bad
+        // lines from stdin are reported in the status line
+        for feed in &control_feeds {
+            for parsed in feed.drain() {
+                match parsed {
+                    Ok(command) => pending_commands.push(command),
+                    Err(e) => set_status(
+                        &mut status_message,
+                        &mut status_frames_remaining,
+                        &format!("Control: {}", e),
+                    ),
+                }
+            }
+        }
+
+        // Beats from --audio go to the effect and filters, and may flip the palette
+        if let Some(ref feed) = audio_feed {
+            for event in feed.drain() {
+                effect.on_event(&event);
+                post_chain.on_event(&event);
+                if matches!(event, digitalrain::event::Event::Beat { .. })
+                    && cli.beat_action.contains(&BeatAction::Palette)
+                {
+                    let next = next_featured_palette(&config.palette_name);
+                    effect.set_palette(&palette::palette_by_name(next));
+                    config.palette_name = next.to_string();
+                    palette_fade = None;
+                }
+            }
+        }
+
+        // New lines of the --tail log fall as text
+        if let Some(ref feed) = tail_feed {
+            for text in feed.drain() {
+                let highlight = tail_highlight.as_ref().is_some_and(|p| p.is_match(&text));
+                effect.on_event(&digitalrain::event::Event::Line { text, highlight });
+            }
+        }
+
+        // System load from --react scales the rain's density and speed
+        if let (Some(source), Some(monitor)) = (config.react, &sysmon)
+            && let Some(level) = monitor.with_history(|h| h.latest().map(|s| s.load(source)))
+        {
+            effect.on_event(&digitalrain::event::Event::Load { level });
+        }
+
+        // Palette fade: recolor the running effect a step at a time (dropped
+        // if something else has changed the palette since)
+        if let Some((name, fade)) = palette_fade.as_mut() {
+            if *name == config.palette_name {
+                effect.set_palette(&fade.update(clock.delta_time()));
+            }
+            if *name != config.palette_name || fade.is_complete() {
+                palette_fade = None;
+            }
+        }
+
+        // Palette morph: drift the running effect's colors every frame, until
+        // another palette is picked
+        if let Some((name, morph)) = palette_morph.as_mut() {
+            if *name == config.palette_name {
+                effect.set_palette(&morph.update(clock.delta_time()));
+            } else {
+                palette_morph = None;
+            }
+        }
+
+        // Countdown expiry: switch effect (if asked) under the flash
+        if !countdown_expired && cli.countdown.is_some_and(|c| elapsed >= c) {
+            countdown_expired = true;
+            if let Some(ref name) = cli.countdown_effect {
+                config.effect_name = name.clone();
+                config.apply_effect_defaults(&config_file);
+                active_transition = switch_effect(
+                    &mut effect,
+                    &config,
+                    view,
+                    manual_transition.pick(&mut transition_rng),
+                )
+                .or(active_transition);
+                scheduler.reset();
+            }
+        }
+
+        // New weather outside: crossfade to the look it calls for
+        if let Some(ref feed) = weather_feed
+            && let Some(reading) = feed.latest()
+        {
+            match reading {
+                Ok(conditions) if weather_now != Some(conditions) => {
+                    weather_now = Some(conditions);
+                    // Switched like any other effect change, with the
+                    // weather's palette, density, and speed on top
+                    let look = conditions.look();
+                    config.effect_name = look.effect.to_string();
+                    config.apply_effect_defaults(&config_file);
+                    if let Some(crt) = config_file
+                        .effect_defaults
+                        .get(&config.effect_name)
+                        .and_then(|d| d.crt)
+                    {
+                        post_chain.crt_mut().set_enabled(crt);
                     }
+                    config.palette_name = look.palette.to_string();
+                    config.density_multiplier = look.density;
+                    config.speed_multiplier = look.speed;
+                    active_transition = switch_effect(
+                        &mut effect,
+                        &config,
+                        view,
+                        auto_transition.pick(&mut transition_rng),
+                    )
+                    .or(active_transition);
+                    palette_fade = None;
                     set_status(
                         &mut status_message,
                         &mut status_frames_remaining,
-                        &format!(
-                            "Auto: {} / {} / {:.1}x",
-                            config.effect_name, config.palette_name, config.speed_multiplier,
-                        ),
+                        &format!("Weather: {}", conditions),
                     );
                 }
+                Ok(_) => {}
+                Err(e) => set_status(
+                    &mut status_message,
+                    &mut status_frames_remaining,
+                    &format!("Weather: {}", e),
+                ),
+            }
+        }
+
+        // Keep the rain out of its dead zones (and, with --rain-halo, away
+        // from the banner, logo, and countdown clock)
+        let countdown_remaining = cli
+            .countdown
+            .map(|total| total - elapsed)
+            .filter(|&remaining| remaining > -COUNTDOWN_FLASH_SECS);
+        effect.set_dead_zones(&rain_dead_zones(
+            &config.dead_zones,
+            rain_halo,
+            banner.as_ref(),
+            logo.as_ref(),
+            countdown_remaining,
+            view,
+        ));
+
+        // Update the effect (skip when paused)
+        if !paused {
+            // The déjà vu stutter holds the effect still, then skips ahead
+            let effect_dt = match deja_vu {
+                Some(ref mut d) => d.advance(clock.delta_time(), view.width, view.height),
+                None => clock.delta_time(),
+            };
+            effect.update(effect_dt);
+
+            // The effect runs underneath the intro, so the dissolve reveals
+            // it already in motion
+            if let Some(ref mut i) = intro {
+                i.update(clock.delta_time());
+                if i.is_finished()
+                    && let Some(finished) = intro.take()
+                {
+                    active_transition = Some(Transition::new(
+                        Box::new(finished),
+                        view.width,
+                        view.height,
+                        INTRO_DISSOLVE_SECS,
+                    ));
+                }
+            }
+
+            // Auto-cycle: ask the scheduler whether it's time for the next config
+            // (held off until the intro is over)
+            if intro.is_none()
+                && let Some(switch) = scheduler.update(clock.delta_time(), &config, &config_file)
+            {
+                config = switch.config;
+                post_chain.crt_mut().set_enabled(config.crt_enabled);
+                active_transition = switch_effect(
+                    &mut effect,
+                    &config,
+                    view,
+                    auto_transition.pick(&mut transition_rng),
+                )
+                .or(active_transition);
+                set_status(
+                    &mut status_message,
+                    &mut status_frames_remaining,
+                    &switch.description,
+                );
             }
 
             // Update transition (fade out outgoing effect)
@@ -361,34 +1707,429 @@ fn main() {
             }
         }
 
-        // Render
+        // Render pipeline: the scene (effect, transition, logo, banner,
+        // countdown), then post filters (CRT), then overlays (stats, help,
+        // prompts, status), then the exit fade. With [overlay]
+        // crt_affects_overlays the CRT pass runs after the overlays instead.
+
+        // Scene
+        phases.mark(Phase::Update);
         buffer.clear();
-        effect.render(&mut buffer);
+        match intro {
+            Some(ref i) => i.render(&mut buffer),
+            None => effect.render(&mut buffer),
+        }
 
-        // Blend outgoing effect during crossfade transition
+        // Blend outgoing effect during a transition
         if let Some(ref mut t) = active_transition {
             t.render(&mut buffer);
         }
 
-        // CRT post-processing (before overlays so help/status text stays crisp)
-        crt_filter.apply(&mut buffer, clock.delta_time());
+        // Logo and banner are part of the scene, so they go under the CRT filter
+        if let Some(ref l) = logo {
+            overlay::render_logo(&mut buffer, l);
+        }
+        if let Some(ref mut b) = banner {
+            b.color = banner_color
+                .unwrap_or_else(|| palette::palette_by_name(&config.palette_name).body_bright);
+            overlay::render_banner(&mut buffer, b, run_start.elapsed().as_secs_f64());
+        }
+        if let Some(ref d) = deja_vu {
+            d.render(&mut buffer);
+        }
+
+        // Countdown clock, then a fading white flash once it hits zero
+        // (the clock stays up at 00:00 until the flash is over)
+        if let Some(total) = cli.countdown {
+            let remaining = total - elapsed;
+            if remaining > -COUNTDOWN_FLASH_SECS {
+                let color = palette::palette_by_name(&config.palette_name).body_bright;
+                overlay::render_countdown(&mut buffer, remaining, color);
+                if remaining <= 0.0 {
+                    buffer.flash(FLASH_COLOR, 1.0 + remaining / COUNTDOWN_FLASH_SECS);
+                }
+            }
+        }
+
+        // Post filters (before overlays so help/status text stays crisp)
+        if !crt_affects_overlays && !power_saver.applied() {
+            phases.mark(Phase::Render);
+            post_chain.apply(&mut buffer, clock.delta_time());
+            phases.mark(Phase::Post);
+        }
+
+        // Screenshot the scene without the overlays on top
+        if let Some(kind) = screenshot_pending.take() {
+            let dir = std::path::Path::new(".");
+            let saved = match kind {
+                Screenshot::Text => headless::save_screenshot(&buffer, dir)
+                    .map(|path| format!("Screenshot: {} (+ .ans)", path.display())),
+                Screenshot::Png => headless::save_png_screenshot(&buffer, dir)
+                    .map(|path| format!("Screenshot: {}", path.display())),
+            };
+            let msg = saved.unwrap_or_else(|e| format!("Screenshot failed: {}", e));
+            set_status(&mut status_message, &mut status_frames_remaining, &msg);
+        }
+
+        // Overlays on top of the scene
+        if show_stats {
+            let flush = presenter
+                .as_ref()
+                .map_or_else(|| buffer.last_flush(), Presenter::last_flush);
+            let stats = overlay::Stats {
+                fps: frame_stats.fps(),
+                target_fps: clock.fps(),
+                power_saver: power_saver.active().map(SaverReason::label),
+                dropped_frames: jank.dropped() + presenter.as_ref().map_or(0, Presenter::skipped),
+                worst_frame: jank.worst().map(|w| (w.frame_time * 1000.0, w.culprit())),
+                frame_avg_ms: frame_stats.average_ms(),
+                frame_p95_ms: frame_stats.percentile_ms(0.95),
+                active_columns: effect.active_columns(),
+                cells_changed: flush.cells_changed,
+                bytes_written: flush.bytes_written,
+                seed: simulation.seed(),
+            };
+            overlay::render_stats(&mut buffer, &overlay_style, &stats);
+        }
+
+        if let Some(ref monitor) = sysmon
+            && cli.sysmon
+        {
+            monitor.with_history(|history| {
+                overlay::render_sysmon(&mut buffer, &overlay_style, history)
+            });
+        }
+
+        overlay::render_toasts(&mut buffer, &overlay_style, &toasts);
 
-        // Draw overlays on top of the effect
         if show_help {
-            overlay::render_help(&mut buffer);
+            let bindings = keymap.help_entries(region.is_some());
+            let footer = format!(
+                "Effect: {} | Palette: {} | Speed: {:.1}x",
+                config.effect_name,
+                config.palette_name,
+                effect.speed()
+            );
+            overlay::render_help(&mut buffer, &overlay_style, &bindings, &footer, help_page);
+        }
+
+        if let Some(ref p) = picker {
+            overlay::render_picker(&mut buffer, &overlay_style, p);
+        }
+
+        if let Some((kind, ref editor)) = prompt {
+            let label = match kind {
+                Prompt::SavePreset => "Save preset as:",
+                Prompt::Command => ":",
+            };
+            overlay::render_prompt(&mut buffer, &overlay_style, label, editor);
         }
 
         // Show status message if active
         if status_frames_remaining > 0 {
             if let Some(ref msg) = status_message {
-                overlay::render_status(&mut buffer, msg);
+                overlay::render_status(&mut buffer, &overlay_style, msg);
             }
             status_frames_remaining -= 1;
         }
 
-        if buffer.flush().is_err() {
+        if crt_affects_overlays && !power_saver.applied() {
+            phases.mark(Phase::Render);
+            post_chain.apply(&mut buffer, clock.delta_time());
+            phases.mark(Phase::Post);
+        }
+
+        // Fade to black over the final seconds of a timed run
+        if cli.exit_fade
+            && let Some(duration) = cli.duration
+        {
+            let fade_secs = EXIT_FADE_SECS.min(duration);
+            let remaining = duration - elapsed;
+            if remaining < fade_secs {
+                buffer.dim(remaining / fade_secs);
+            }
+        }
+
+        // Brightness/contrast/gamma last, so it covers overlays too
+        color_transform.apply(&mut buffer, clock.delta_time());
+
+        phases.mark(Phase::Render);
+        if present(&mut buffer, &mut recorder, &mut presenter).is_err() {
             break;
         }
+        phases.mark(Phase::Flush);
+        last_phases = Some(phases);
+        tracing::trace!(
+            update_ms = phases.get(Phase::Update) * 1000.0,
+            render_ms = phases.get(Phase::Render) * 1000.0,
+            post_ms = phases.get(Phase::Post) * 1000.0,
+            flush_ms = phases.get(Phase::Flush) * 1000.0,
+            "frame"
+        );
+        let work_time = work_start.elapsed();
+        if let Some(fps) = clock.record_work(work_time) {
+            tracing::debug!(fps, "frame rate adjusted");
+        }
+
+        if let Some(ref mut log) = metrics_log {
+            let flush = presenter
+                .as_ref()
+                .map_or_else(|| buffer.last_flush(), Presenter::last_flush);
+            let sample = FrameSample {
+                frame_time: clock.delta_time(),
+                work_time: work_time.as_secs_f64(),
+                active_columns: effect.active_columns(),
+                cells_changed: flush.cells_changed,
+                bytes_written: flush.bytes_written,
+            };
+            if let Err(e) = log.record(run_start.elapsed().as_secs_f64(), sample) {
+                set_status(
+                    &mut status_message,
+                    &mut status_frames_remaining,
+                    &format!("Metrics stopped: {}", e),
+                );
+                metrics_log = None;
+            }
+        }
+    }
+
+    // A region shares the screen with whatever else is there: erase our part
+    if region.is_some() {
+        buffer.clear();
+        present(&mut buffer, &mut recorder, &mut presenter).ok();
+    }
+
+    // The writer draws its last frame before the terminal is restored
+    drop(presenter);
+
+    // Reports go after the terminal is restored, so they stay on screen
+    drop(term);
+    if cli.jank_report {
+        eprint!("{}", jank.report());
+    }
+    tracing::info!("exiting");
+    if let Some(status) = shutdown::exit_status() {
+        std::process::exit(status);
+    }
+}
+
+/// Run the `--ssh` server on `address` until the process ends.
+#[cfg(feature = "ssh")]
+fn serve_ssh(address: &str, cli: &Cli, config: &Config) {
+    let Some(host_key) = cli
+        .ssh_host_key
+        .clone()
+        .or_else(digitalrain::ssh::default_host_key_path)
+    else {
+        eprintln!("Error: no config directory for the SSH host key; pass --ssh-host-key");
+        std::process::exit(1);
+    };
+    let result = digitalrain::ssh::Server::bind(address, &host_key, cli.serve_max_clients)
+        .and_then(|server| {
+            if let Ok(bound) = server.local_addr() {
+                eprintln!("Serving rain over SSH on {} (Ctrl+C to stop)", bound);
+            }
+            server.run(config)
+        });
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(feature = "ssh"))]
+fn serve_ssh(_address: &str, _cli: &Cli, _config: &Config) {
+    eprintln!("Error: --ssh needs a build with the ssh feature");
+    std::process::exit(1);
+}
+
+/// The drawing area: `region` clipped to the terminal, or the whole terminal.
+fn fit_view(region: Option<Region>, term_width: u16, term_height: u16) -> Region {
+    region.map_or(Region::full(term_width, term_height), |r| {
+        r.clip(term_width, term_height)
+    })
+}
+
+/// The areas rain stays out of: the configured dead zones plus, with `halo`,
+/// the banner, logo, and countdown clock with a one-cell margin.
+fn rain_dead_zones(
+    zones: &[Region],
+    halo: bool,
+    banner: Option<&overlay::Banner>,
+    logo: Option<&overlay::Logo>,
+    countdown: Option<f64>,
+    view: Region,
+) -> Vec<Region> {
+    let mut zones = zones.to_vec();
+    if halo {
+        let (w, h) = (view.width, view.height);
+        let scene = banner
+            .map(|b| overlay::banner_areas(b, w, h))
+            .into_iter()
+            .flatten()
+            .chain(logo.and_then(|l| overlay::logo_area(l, w, h)))
+            .chain(
+                countdown
+                    .map(|r| overlay::countdown_areas(r, w, h))
+                    .into_iter()
+                    .flatten(),
+            );
+        zones.extend(scene.map(|area| area.expanded(1)));
+    }
+    zones
+}
+
+/// Flush the frame to the terminal, teeing it into the session recording.
+fn present(
+    buffer: &mut ScreenBuffer,
+    recorder: &mut Option<CastRecorder>,
+    presenter: &mut Option<Presenter>,
+) -> std::io::Result<()> {
+    match (presenter.as_mut(), recorder.as_mut()) {
+        (Some(presenter), _) => presenter.present(buffer),
+        (None, Some(rec)) => buffer.flush_to(&mut rec.tee(std::io::stdout())),
+        (None, None) => buffer.flush(),
+    }
+}
+
+/// What an open text prompt is asking for.
+#[derive(Clone, Copy, PartialEq)]
+enum Prompt {
+    /// A name for the "save preset" key
+    SavePreset,
+    /// A `:` command
+    Command,
+}
+
+/// A headless single-frame export: config, path, duration, width, height.
+type StillExport = fn(&Config, &std::path::Path, f64, u16, u16) -> Result<(), String>;
+
+/// The format a screenshot key asked for.
+#[derive(Clone, Copy)]
+enum Screenshot {
+    /// Plain `.txt` plus colored `.ans`
+    Text,
+    /// Rasterized `.png`
+    Png,
+}
+
+/// Whether `event` quits: Ctrl+C or a key bound to quit.
+fn is_quit(event: &Event, keymap: &KeyMap) -> bool {
+    match event {
+        Event::Key(key) if key.kind == KeyEventKind::Press => {
+            Terminal::is_interrupt(event) || keymap.action(key) == Some(Action::Quit)
+        }
+        _ => false,
+    }
+}
+
+/// Replace the running effect with a fresh one built from `config`, returning
+/// a `kind` transition from the old one (None if the effect name is unknown).
+fn switch_effect(
+    effect: &mut Box<dyn Effect>,
+    config: &Config,
+    view: Region,
+    kind: TransitionKind,
+) -> Option<Transition> {
+    let new_effect =
+        match registry::create_effect(&config.effect_spec(), view.width, view.height, config) {
+            Ok(new_effect) => new_effect,
+            Err(e) => {
+                tracing::warn!("can't switch to {}: {}", config.effect_name, e);
+                return None;
+            }
+        };
+    tracing::info!(
+        from = effect.name(),
+        to = new_effect.name(),
+        "effect switched"
+    );
+    let old_effect = std::mem::replace(effect, new_effect);
+    Some(
+        Transition::new(old_effect, view.width, view.height, TRANSITION_DURATION)
+            .with_kind(kind, config.seed),
+    )
+}
+
+/// Start blending the running effect from palette `from` (or wherever a
+/// fade in progress has got to) to palette `to`.
+fn fade_palette(fade: &mut Option<(String, PaletteFade)>, from: &str, to: &str) {
+    let start = match fade {
+        Some((_, f)) => f.current(),
+        None => palette::palette_by_name(from),
+    };
+    *fade = Some((
+        to.to_string(),
+        PaletteFade::new(start, palette::palette_by_name(to), PALETTE_FADE_SECS),
+    ));
+}
+
+/// The featured palette after `current`, wrapping around (the first one
+/// if `current` isn't featured).
+fn next_featured_palette(current: &str) -> &'static str {
+    let names = palette::hand_tuned_names();
+    let next = names
+        .iter()
+        .position(|&name| name == current)
+        .map_or(0, |i| (i + 1) % names.len());
+    names[next]
+}
+
+/// Put speed, density, mutation, palette, charset, tone, and the CRT settings
+/// back to `startup`, applied to the running effect in place.
+fn reset_tuning(
+    effect: &mut dyn Effect,
+    crt_filter: &mut CrtFilter,
+    config: &mut Config,
+    startup: &Config,
+) {
+    config.speed_multiplier = startup.speed_multiplier;
+    config.density_multiplier = startup.density_multiplier;
+    config.mutation_rate = startup.mutation_rate;
+    config.palette_name = startup.palette_name.clone();
+    config.charset_name = startup.charset_name.clone();
+    config.crt_intensity = startup.crt_intensity;
+    config.crt_curvature = startup.crt_curvature;
+    config.crt_persistence = startup.crt_persistence;
+    config.crt_vignette = startup.crt_vignette;
+    config.crt_vignette_radius = startup.crt_vignette_radius;
+    config.crt_vignette_strength = startup.crt_vignette_strength;
+    config.brightness = startup.brightness;
+    config.contrast = startup.contrast;
+    config.gamma = startup.gamma;
+
+    effect.set_speed(config.speed_multiplier);
+    effect.set_density(config.density_multiplier);
+    effect.set_mutation(config.mutation_rate);
+    effect.set_palette(&palette::palette_by_name(&config.palette_name));
+    effect.set_charset(&chars::charset_by_name(&config.charset_name));
+    crt_filter.set_intensity(config.crt_intensity);
+    crt_filter.set_curvature(config.crt_curvature);
+    crt_filter.set_persistence(config.crt_persistence);
+    crt_filter.set_vignette(
+        config.crt_vignette,
+        config.crt_vignette_radius,
+        config.crt_vignette_strength,
+    );
+}
+
+/// Save the live settings as preset `name`, in the config file and in the
+/// loaded copy (so `:preset load` sees it). Returns the status message.
+fn save_live_preset(
+    cli: &Cli,
+    config_file: &mut ConfigFile,
+    config: &Config,
+    effect: &dyn Effect,
+    crt: bool,
+    name: &str,
+) -> String {
+    let preset = PresetConfig::from_runtime(config, effect.speed(), effect.density(), crt);
+    match config::save_preset_config(cli.config.as_deref(), name, preset.clone(), cli.force) {
+        Ok(_) => {
+            config_file.presets.insert(name.trim().to_string(), preset);
+            format!("Saved preset '{}'", name)
+        }
+        Err(e) => format!("Save failed: {}", e),
     }
 }
 
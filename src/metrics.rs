@@ -0,0 +1,194 @@
+//! Performance log for `--metrics-file`: one CSV row per second.
+//!
+//! The main loop hands over a `FrameSample` after each frame is flushed;
+//! once a second of samples has built up they're summarized into a row:
+//!
+//! ```text
+//! unix_time,elapsed_s,frames,fps,frame_avg_ms,frame_p50_ms,frame_p95_ms,frame_p99_ms,work_avg_ms,work_p95_ms,active_columns,cells_changed,bytes_written
+//! 1760745600,1.00,30,30.0,33.3,33.3,34.1,35.0,2.1,3.4,42,1310,20480
+//! ```
+//!
+//! Frame times are the interval between frames (what the viewer sees);
+//! work times are how much of that interval went into updating, drawing,
+//! and writing the frame. A terminal that can't keep up shows as work
+//! close to the frame time, usually alongside a large `bytes_written`.
+//! Cells and bytes are totals for the second; columns are its average.
+//!
+//! Rows are appended, so several runs can share a file; the header is
+//! written only when the file is new or empty.
+
+use std::fs::OpenOptions;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::timing::FrameStats;
+
+/// Column names, in row order.
+const HEADER: &str = "unix_time,elapsed_s,frames,fps,frame_avg_ms,frame_p50_ms,frame_p95_ms,\
+frame_p99_ms,work_avg_ms,work_p95_ms,active_columns,cells_changed,bytes_written";
+
+/// Seconds of frames summarized per row.
+const ROW_SECS: f64 = 1.0;
+
+/// Most frames one row can hold (a second at a very high frame rate).
+const MAX_FRAMES_PER_ROW: usize = 1000;
+
+/// What one frame cost.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameSample {
+    /// Seconds since the previous frame
+    pub frame_time: f64,
+    /// Seconds spent updating, rendering, and flushing this frame
+    pub work_time: f64,
+    /// Falling rain columns, if the effect is column-based
+    pub active_columns: Option<usize>,
+    /// Cells redrawn by the flush
+    pub cells_changed: usize,
+    /// Bytes written by the flush
+    pub bytes_written: usize,
+}
+
+/// Summarizes frames into per-second CSV rows.
+pub struct MetricsLog<W: Write> {
+    out: W,
+    /// Run time when the current row started
+    row_start: f64,
+    frames: FrameStats,
+    work: FrameStats,
+    frame_count: usize,
+    columns_total: usize,
+    columns_frames: usize,
+    cells_changed: usize,
+    bytes_written: usize,
+}
+
+impl MetricsLog<BufWriter<std::fs::File>> {
+    /// Append to the CSV file at `path`, creating it with a header.
+    pub fn create(path: &Path) -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        let is_new = file.metadata().map(|m| m.len() == 0).unwrap_or(true);
+        Self::new(BufWriter::new(file), is_new).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+impl<W: Write> MetricsLog<W> {
+    /// Log to `out`, starting with the header row if `header` is set.
+    pub fn new(mut out: W, header: bool) -> io::Result<Self> {
+        if header {
+            writeln!(out, "{}", HEADER)?;
+            out.flush()?;
+        }
+        Ok(Self {
+            out,
+            row_start: 0.0,
+            frames: FrameStats::new(MAX_FRAMES_PER_ROW),
+            work: FrameStats::new(MAX_FRAMES_PER_ROW),
+            frame_count: 0,
+            columns_total: 0,
+            columns_frames: 0,
+            cells_changed: 0,
+            bytes_written: 0,
+        })
+    }
+
+    /// Add a frame that finished `elapsed` seconds into the run, writing a
+    /// row once a second's worth has built up.
+    pub fn record(&mut self, elapsed: f64, sample: FrameSample) -> io::Result<()> {
+        self.frames.record(sample.frame_time);
+        self.work.record(sample.work_time);
+        self.frame_count += 1;
+        if let Some(columns) = sample.active_columns {
+            self.columns_total += columns;
+            self.columns_frames += 1;
+        }
+        self.cells_changed += sample.cells_changed;
+        self.bytes_written += sample.bytes_written;
+
+        if elapsed - self.row_start >= ROW_SECS {
+            self.write_row(elapsed)?;
+        }
+        Ok(())
+    }
+
+    fn write_row(&mut self, elapsed: f64) -> io::Result<()> {
+        let unix_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let columns = if self.columns_frames > 0 {
+            (self.columns_total as f64 / self.columns_frames as f64)
+                .round()
+                .to_string()
+        } else {
+            String::new()
+        };
+        writeln!(
+            self.out,
+            "{},{:.2},{},{:.1},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{},{},{}",
+            unix_time,
+            elapsed,
+            self.frame_count,
+            self.frames.fps(),
+            self.frames.average_ms(),
+            self.frames.percentile_ms(0.5),
+            self.frames.percentile_ms(0.95),
+            self.frames.percentile_ms(0.99),
+            self.work.average_ms(),
+            self.work.percentile_ms(0.95),
+            columns,
+            self.cells_changed,
+            self.bytes_written,
+        )?;
+        self.out.flush()?;
+
+        self.row_start = elapsed;
+        self.frames = FrameStats::new(MAX_FRAMES_PER_ROW);
+        self.work = FrameStats::new(MAX_FRAMES_PER_ROW);
+        self.frame_count = 0;
+        self.columns_total = 0;
+        self.columns_frames = 0;
+        self.cells_changed = 0;
+        self.bytes_written = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_each_second_into_a_row() {
+        let mut out = Vec::new();
+        let mut log = MetricsLog::new(&mut out, true).unwrap();
+        // 2.5 seconds at 20 fps: two full rows, half a second pending
+        for frame in 1..=50 {
+            let sample = FrameSample {
+                frame_time: 0.05,
+                work_time: if frame == 10 { 0.04 } else { 0.01 },
+                active_columns: Some(if frame % 2 == 0 { 10 } else { 20 }),
+                cells_changed: 100,
+                bytes_written: 1000,
+            };
+            log.record(frame as f64 * 0.05, sample).unwrap();
+        }
+
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], HEADER);
+        let first: Vec<&str> = lines[1].split(',').collect();
+        assert_eq!(first.len(), HEADER.split(',').count());
+        assert_eq!(&first[1..5], ["1.00", "20", "20.0", "50.00"]);
+        // The slow frame shows in the work percentile, not the average
+        assert_eq!(first[8], "11.50");
+        assert_eq!(first[9], "10.00");
+        assert_eq!(&first[10..], ["15", "2000", "20000"]);
+        assert!(lines[2].split(',').nth(1) == Some("2.00"));
+    }
+}
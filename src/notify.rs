@@ -0,0 +1,140 @@
+//! Notification feed for `--notify`: lines from a named pipe or stdin.
+//!
+//! A background thread reads lines and hands them to the main loop over a
+//! channel, so a quiet pipe never blocks a frame. A FIFO is reopened each
+//! time its writer goes away, which lets separate commands keep sending
+//! (`echo done > /tmp/rain`). Stdin is read once, to EOF.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+/// Receiving end of the notification feed.
+pub struct NotifyFeed {
+    lines: Receiver<String>,
+}
+
+impl NotifyFeed {
+    /// Start reading notifications from `path`, or from stdin when the
+    /// path is `-`. Fails up front if the path can't be read at all.
+    pub fn spawn(path: &Path) -> Result<Self, String> {
+        let (tx, rx) = mpsc::channel();
+        if path.as_os_str() == "-" {
+            thread::spawn(move || {
+                forward_lines(io::stdin().lock(), &tx);
+            });
+        } else {
+            std::fs::metadata(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let path = path.to_path_buf();
+            thread::spawn(move || {
+                read_pipe(path, |file| forward_lines(BufReader::new(file), &tx));
+            });
+        }
+        Ok(Self { lines: rx })
+    }
+
+    /// Lines that arrived since the last call, oldest first.
+    pub fn drain(&self) -> impl Iterator<Item = String> + '_ {
+        self.lines.try_iter()
+    }
+}
+
+/// Hand a FIFO (or file) to `forward` until it returns false (the main
+/// loop went away), reopening after each writer closes it. A regular file
+/// is only read once.
+pub(crate) fn read_pipe(path: PathBuf, mut forward: impl FnMut(File) -> bool) {
+    loop {
+        // Opening a FIFO blocks until a writer shows up
+        let Ok(file) = File::open(&path) else {
+            return;
+        };
+        let is_fifo = is_fifo(&file);
+        if !forward(file) || !is_fifo {
+            return;
+        }
+    }
+}
+
+/// Send each non-blank line; false once the receiver is gone.
+pub(crate) fn forward_lines(reader: impl BufRead, tx: &Sender<String>) -> bool {
+    for line in reader.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let line = clean_line(&line);
+        if !line.is_empty() && tx.send(line).is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+/// Strip ANSI escape sequences and other control characters (they would
+/// corrupt the screen) and surrounding whitespace. Tabs become spaces.
+pub(crate) fn clean_line(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            // CSI sequences run to a final byte in @..~; other escapes
+            // are two characters
+            '\x1b' => {
+                if chars.next() == Some('[') {
+                    for c in chars.by_ref() {
+                        if ('@'..='~').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+            }
+            '\t' => out.push(' '),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out.trim().to_string()
+}
+
+/// Whether `file` is a named pipe (one writer at a time, so it's reopened
+/// when the writer leaves).
+#[cfg(unix)]
+pub fn is_fifo(file: &File) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    file.metadata().is_ok_and(|m| m.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+pub fn is_fifo(_file: &File) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forwards_cleaned_non_blank_lines() {
+        let (tx, rx) = mpsc::channel();
+        let input = "build ok\n\n  \x1b[31mtests\tfailed\x1b[0m \n";
+        assert!(forward_lines(input.as_bytes(), &tx));
+        let lines: Vec<String> = rx.try_iter().collect();
+        assert_eq!(lines, ["build ok", "tests failed"]);
+    }
+
+    #[test]
+    fn reads_a_regular_file_once() {
+        let path = std::env::temp_dir().join(format!("rain_notify_{}.txt", std::process::id()));
+        std::fs::write(&path, "one\ntwo\n").unwrap();
+        let feed = NotifyFeed::spawn(&path).unwrap();
+        let mut lines = Vec::new();
+        while lines.len() < 2 {
+            lines.extend(feed.lines.recv_timeout(std::time::Duration::from_secs(5)));
+        }
+        assert_eq!(lines, ["one", "two"]);
+        std::fs::remove_file(&path).ok();
+
+        assert!(NotifyFeed::spawn(Path::new("/nonexistent/rain.fifo")).is_err());
+    }
+}
@@ -3,9 +3,24 @@
 //! Overlays are drawn on top of the effect buffer after the effect renders.
 //! They use a dark background to remain readable over the rain.
 
-use crossterm::style::Color;
+use std::collections::VecDeque;
+use std::str::FromStr;
 
-use crate::buffer::ScreenBuffer;
+#[cfg(feature = "terminal")]
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::buffer::{Region, ScreenBuffer};
+use crate::color::Color;
+use crate::color::gradient::{lerp_color, scale_color};
+use crate::color::palette::{self, color_by_name};
+use crate::config::OverlayConfig;
+use crate::font;
+use crate::image::{HalfBlockImage, Image};
+#[cfg(feature = "terminal")]
+use crate::keys::KeyBinding;
+use crate::rain::chars;
+#[cfg(feature = "terminal")]
+use crate::sysmon;
 
 /// The dark background color for overlay text boxes.
 const OVERLAY_BG: Color = Color::Rgb {
@@ -26,57 +41,252 @@ const OVERLAY_TITLE: Color = Color::Rgb {
     b: 80,
 };
 
-/// Render the keybindings help overlay centered on screen.
-pub fn render_help(buffer: &mut ScreenBuffer) {
-    let lines = [
-        "",
-        "  KEYBINDINGS",
-        "",
-        "  Space     Pause / Resume",
-        "  +  -      Speed up / down",
-        "  [  ]      Density down / up",
-        "  n         Next effect",
-        "  r         Randomize",
-        "  t         Toggle auto-cycle timer",
-        "  c         Toggle CRT simulation",
-        "  ?         Toggle this help",
-        "  q / Esc   Quit",
-        "",
-    ];
+/// Frame drawn around overlay boxes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BorderStyle {
+    None,
+    Single,
+    Double,
+    Rounded,
+}
 
-    let box_width = 38u16;
-    let box_height = lines.len() as u16;
+impl BorderStyle {
+    /// Corner and edge characters: top-left, top-right, bottom-left,
+    /// bottom-right, horizontal, vertical.
+    fn chars(self) -> Option<[char; 6]> {
+        match self {
+            BorderStyle::None => None,
+            BorderStyle::Single => Some(['┌', '┐', '└', '┘', '─', '│']),
+            BorderStyle::Double => Some(['╔', '╗', '╚', '╝', '═', '║']),
+            BorderStyle::Rounded => Some(['╭', '╮', '╰', '╯', '─', '│']),
+        }
+    }
 
-    let buf_w = buffer.width();
-    let buf_h = buffer.height();
+    /// Cells the border adds on each side.
+    fn thickness(self) -> usize {
+        if self == BorderStyle::None { 0 } else { 1 }
+    }
+}
 
-    // Don't render if terminal is too small
-    if buf_w < box_width + 4 || buf_h < box_height + 2 {
-        return;
+impl FromStr for BorderStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(BorderStyle::None),
+            "single" => Ok(BorderStyle::Single),
+            "double" => Ok(BorderStyle::Double),
+            "rounded" => Ok(BorderStyle::Rounded),
+            _ => Err(format!(
+                "unknown border style '{}' (expected none, single, double, or rounded)",
+                s
+            )),
+        }
     }
+}
 
-    let start_x = (buf_w - box_width) / 2;
-    let start_y = (buf_h - box_height) / 2;
+/// Colors, border, and transparency shared by all overlay boxes
+/// (the `[overlay]` config section).
+#[derive(Clone, Debug)]
+pub struct OverlayStyle {
+    pub background: Color,
+    pub text: Color,
+    pub title: Color,
+    pub border: BorderStyle,
+    /// 1.0 fills boxes solidly; lower blends them with the rain underneath
+    pub opacity: f64,
+}
+
+impl Default for OverlayStyle {
+    fn default() -> Self {
+        Self {
+            background: OVERLAY_BG,
+            text: OVERLAY_FG,
+            title: OVERLAY_TITLE,
+            border: BorderStyle::None,
+            opacity: 1.0,
+        }
+    }
+}
 
-    for (row, line) in lines.iter().enumerate() {
-        let y = start_y + row as u16;
+impl OverlayStyle {
+    /// Build the theme from config, keeping the default for anything
+    /// missing or invalid (with a warning).
+    pub fn from_config(config: &OverlayConfig) -> Self {
+        let mut style = Self::default();
+        let color = |value: &Option<String>, default: Color| match value.as_deref() {
+            Some(name) => color_by_name(name).unwrap_or_else(|| {
+                eprintln!("Unknown overlay color '{}', using the default", name);
+                default
+            }),
+            None => default,
+        };
+        style.background = color(&config.background, style.background);
+        style.text = color(&config.text, style.text);
+        style.title = color(&config.title, style.title);
+        if let Some(ref border) = config.border {
+            match border.parse() {
+                Ok(b) => style.border = b,
+                Err(e) => eprintln!("Overlay {}, using none", e),
+            }
+        }
+        if let Some(opacity) = config.opacity {
+            style.opacity = opacity.clamp(0.0, 1.0);
+        }
+        style
+    }
+
+    /// Draw one overlay cell. When the style is translucent the background
+    /// blends with the cell underneath, and blank cells let the rain show
+    /// through, faded toward the background.
+    fn put(&self, buffer: &mut ScreenBuffer, x: usize, y: usize, ch: char, fg: Color) {
+        let (x, y) = (x as u16, y as u16);
+        if self.opacity >= 1.0 {
+            buffer.set_cell(x, y, ch, fg, self.background);
+            return;
+        }
+        let Some(&under) = buffer.get_cell(x, y) else {
+            return;
+        };
+        let alpha = self.opacity as f32;
+        let bg = lerp_color(under.bg, self.background, alpha);
+        if ch == ' ' {
+            let faded = lerp_color(under.fg, self.background, alpha);
+            buffer.set_cell(x, y, under.ch, faded, bg);
+        } else {
+            buffer.set_cell(x, y, ch, fg, bg);
+        }
+    }
 
-        // Fill the full box width with background
-        for x in start_x..(start_x + box_width) {
-            let col = (x - start_x) as usize;
-            let ch = line.chars().nth(col).unwrap_or(' ');
+    /// Outer size of a box holding `width` x `height` cells of content.
+    fn box_size(&self, width: usize, height: usize) -> (usize, usize) {
+        let edge = 2 * self.border.thickness();
+        (width + edge, height + edge)
+    }
 
-            // Title line gets a different color
-            let fg = if row == 1 { OVERLAY_TITLE } else { OVERLAY_FG };
+    /// Draw a box with its outer top-left corner at (`x`, `y`): the
+    /// border, if any, around `lines` each padded to `width` cells.
+    fn draw_box(
+        &self,
+        buffer: &mut ScreenBuffer,
+        x: usize,
+        y: usize,
+        width: usize,
+        lines: &[(String, Color)],
+    ) {
+        let inset = self.border.thickness();
+        for (row, (line, fg)) in lines.iter().enumerate() {
+            let mut chars = line.chars();
+            for col in 0..width {
+                let ch = chars.next().unwrap_or(' ');
+                self.put(buffer, x + inset + col, y + inset + row, ch, *fg);
+            }
+        }
 
-            buffer.set_cell(x, y, ch, fg, OVERLAY_BG);
+        let Some([tl, tr, bl, br, h, v]) = self.border.chars() else {
+            return;
+        };
+        let (right, bottom) = (x + width + 1, y + lines.len() + 1);
+        for col in x + 1..right {
+            self.put(buffer, col, y, h, self.title);
+            self.put(buffer, col, bottom, h, self.title);
         }
+        for row in y + 1..bottom {
+            self.put(buffer, x, row, v, self.title);
+            self.put(buffer, right, row, v, self.title);
+        }
+        self.put(buffer, x, y, tl, self.title);
+        self.put(buffer, right, y, tr, self.title);
+        self.put(buffer, x, bottom, bl, self.title);
+        self.put(buffer, right, bottom, br, self.title);
     }
 }
 
+/// Rows of the help box around the keybinding list: padding, title, and a
+/// blank line above; a blank line, the footer, and padding below.
+const HELP_CHROME_ROWS: usize = 6;
+/// Minimum width of the key column in the help overlay.
+#[cfg(feature = "terminal")]
+const HELP_KEY_COLUMN: usize = 10;
+
+/// How many keybinding rows fit on one help page at this screen height,
+/// and how many pages that makes.
+pub fn help_layout(height: u16, entries: usize, style: &OverlayStyle) -> (usize, usize) {
+    // Keep a row of rain visible above and below the box
+    let chrome = HELP_CHROME_ROWS + 2 * style.border.thickness();
+    let room = (height as usize).saturating_sub(chrome + 2);
+    if entries <= room {
+        return (entries.max(1), 1);
+    }
+    // Paginated: one more row goes to the page indicator
+    let per_page = room.saturating_sub(1).max(1);
+    (per_page, entries.div_ceil(per_page))
+}
+
+/// Render the keybindings help overlay centered on screen.
+///
+/// The list comes from the keybinding table; when it doesn't fit the
+/// screen height it is split into pages (`page` is clamped). `footer`
+/// shows the current settings under the list.
+#[cfg(feature = "terminal")]
+pub fn render_help(
+    buffer: &mut ScreenBuffer,
+    style: &OverlayStyle,
+    bindings: &[KeyBinding],
+    footer: &str,
+    page: usize,
+) {
+    let buf_w = buffer.width() as usize;
+    let buf_h = buffer.height() as usize;
+
+    let (per_page, pages) = help_layout(buffer.height(), bindings.len(), style);
+    let page = page.min(pages - 1);
+    let key_width = bindings
+        .iter()
+        .map(|b| b.keys.chars().count() + 1)
+        .max()
+        .unwrap_or(0)
+        .max(HELP_KEY_COLUMN);
+
+    let mut lines = vec![
+        (String::new(), style.text),
+        ("  KEYBINDINGS".to_string(), style.title),
+        (String::new(), style.text),
+    ];
+    for binding in bindings.iter().skip(page * per_page).take(per_page) {
+        let text = format!(
+            "  {:<width$}{}",
+            binding.keys,
+            binding.description,
+            width = key_width
+        );
+        lines.push((text, style.text));
+    }
+    if pages > 1 {
+        let text = format!("  Page {}/{}  (PgUp/PgDn)", page + 1, pages);
+        lines.push((text, style.title));
+    }
+    lines.push((String::new(), style.text));
+    lines.push((format!("  {}", footer), style.title));
+    lines.push((String::new(), style.text));
+
+    // Fit the widest line, shrinking (and clipping text) on narrow screens
+    let (edge, _) = style.box_size(0, 0);
+    let width = lines
+        .iter()
+        .map(|(l, _)| l.chars().count() + 2)
+        .max()
+        .unwrap_or(0)
+        .min(buf_w.saturating_sub(edge));
+    let (box_width, box_height) = style.box_size(width, lines.len());
+    let start_x = buf_w.saturating_sub(box_width) / 2;
+    let start_y = buf_h.saturating_sub(box_height) / 2;
+    style.draw_box(buffer, start_x, start_y, width, &lines);
+}
+
 /// Render a brief status message at the bottom of the screen.
 /// Used to show parameter changes ("Speed: 1.5x") that fade after a moment.
-pub fn render_status(buffer: &mut ScreenBuffer, message: &str) {
+pub fn render_status(buffer: &mut ScreenBuffer, style: &OverlayStyle, message: &str) {
     let buf_w = buffer.width();
     let buf_h = buffer.height();
 
@@ -84,7 +294,7 @@ pub fn render_status(buffer: &mut ScreenBuffer, message: &str) {
         return;
     }
 
-    let y = buf_h - 1;
+    let y = (buf_h - 1) as usize;
     let msg_len = message.len().min(buf_w as usize);
     let start_x = (buf_w as usize - msg_len) / 2;
 
@@ -99,6 +309,1203 @@ pub fn render_status(buffer: &mut ScreenBuffer, message: &str) {
         } else {
             ' '
         };
-        buffer.set_cell(x as u16, y, ch, OVERLAY_TITLE, OVERLAY_BG);
+        style.put(buffer, x, y, ch, style.title);
+    }
+}
+
+/// Performance metrics shown by the `f` stats overlay.
+pub struct Stats {
+    pub fps: f64,
+    /// The rate the frame clock is aiming for, which drops below `--fps`
+    /// while the terminal can't keep up
+    pub target_fps: f64,
+    /// Why the `--power-saver` profile is on, if it is
+    pub power_saver: Option<&'static str>,
+    /// Frames dropped since startup
+    pub dropped_frames: u64,
+    /// The slowest frame interval (ms) and the phase blamed for it
+    pub worst_frame: Option<(f64, &'static str)>,
+    /// Average frame time over the recent window, in milliseconds
+    pub frame_avg_ms: f64,
+    /// 95th-percentile frame time over the recent window, in milliseconds
+    pub frame_p95_ms: f64,
+    /// Falling rain columns, if the effect is column-based
+    pub active_columns: Option<usize>,
+    /// Cells redrawn in the last flush
+    pub cells_changed: usize,
+    /// Bytes written to the terminal in the last flush
+    pub bytes_written: usize,
+    /// Session seed, for replaying the run with `--seed`
+    pub seed: u64,
+}
+
+/// Render the performance stats overlay in the top-left corner.
+pub fn render_stats(buffer: &mut ScreenBuffer, style: &OverlayStyle, stats: &Stats) {
+    let columns = stats
+        .active_columns
+        .map_or("-".to_string(), |c| c.to_string());
+    let lines = [
+        "STATS".to_string(),
+        format!(
+            "FPS       {:.1} (target {:.0})",
+            stats.fps, stats.target_fps
+        ),
+        format!(
+            "Frame     {:.1} ms avg / {:.1} ms p95",
+            stats.frame_avg_ms, stats.frame_p95_ms
+        ),
+        format!("Columns   {}", columns),
+        format!("Changed   {} cells", stats.cells_changed),
+        format!("Written   {} bytes", stats.bytes_written),
+        format!("Dropped   {} frames", stats.dropped_frames),
+        format!(
+            "Worst     {}",
+            stats
+                .worst_frame
+                .map_or("-".to_string(), |(ms, phase)| format!(
+                    "{:.1} ms ({})",
+                    ms, phase
+                ))
+        ),
+        format!("Seed      {}", stats.seed),
+        format!(
+            "Power     {}",
+            stats
+                .power_saver
+                .map_or("normal".to_string(), |r| format!("saver ({})", r))
+        ),
+    ];
+    render_corner_box(buffer, style, &lines, false);
+}
+
+/// Draw a small titled box flush against the top-left or top-right
+/// corner. The first line is the title. Skipped if the screen is too small.
+fn render_corner_box(
+    buffer: &mut ScreenBuffer,
+    style: &OverlayStyle,
+    lines: &[String],
+    right: bool,
+) {
+    // One column of padding on each side
+    let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0) + 2;
+    let (box_width, box_height) = style.box_size(width, lines.len());
+    let (buf_w, buf_h) = (buffer.width() as usize, buffer.height() as usize);
+    if buf_w < box_width || buf_h < box_height {
+        return;
+    }
+
+    let lines: Vec<(String, Color)> = lines
+        .iter()
+        .enumerate()
+        .map(|(row, line)| {
+            let fg = if row == 0 { style.title } else { style.text };
+            (format!(" {}", line), fg)
+        })
+        .collect();
+    let x = if right { buf_w - box_width } else { 0 };
+    style.draw_box(buffer, x, 0, width, &lines);
+}
+
+/// Cells in the `--sysmon` RAM bar.
+#[cfg(feature = "terminal")]
+const SYSMON_BAR_WIDTH: usize = sysmon::HISTORY_LEN;
+
+/// Render the `--sysmon` system monitor in the top-right corner.
+///
+/// CPU and network get sparklines over the recent history; RAM is a fill
+/// bar since it changes slowly. Nothing is drawn until the first sample.
+#[cfg(feature = "terminal")]
+pub fn render_sysmon(buffer: &mut ScreenBuffer, style: &OverlayStyle, history: &sysmon::History) {
+    let Some(latest) = history.latest() else {
+        return;
+    };
+
+    let cpu = sysmon::sparkline(history.samples().map(|s| s.cpu_percent as f64), 100.0);
+    let mem_frac = if latest.mem_total > 0 {
+        latest.mem_used as f64 / latest.mem_total as f64
+    } else {
+        0.0
+    };
+    let filled = ((mem_frac * SYSMON_BAR_WIDTH as f64).round() as usize).min(SYSMON_BAR_WIDTH);
+    let mem_bar = format!(
+        "{}{}",
+        "█".repeat(filled),
+        "░".repeat(SYSMON_BAR_WIDTH - filled)
+    );
+    // Scale network to the busiest second in the window
+    let net_peak = history
+        .samples()
+        .map(|s| s.net_rx + s.net_tx)
+        .max()
+        .unwrap_or(0);
+    let net = sysmon::sparkline(
+        history.samples().map(|s| (s.net_rx + s.net_tx) as f64),
+        net_peak as f64,
+    );
+
+    let lines = [
+        "SYSMON".to_string(),
+        format!("CPU {:<16} {:>3.0}%", cpu, latest.cpu_percent),
+        format!(
+            "RAM {} {}/{}",
+            mem_bar,
+            sysmon::format_bytes(latest.mem_used),
+            sysmon::format_bytes(latest.mem_total)
+        ),
+        format!(
+            "NET {:<16} ↓{} ↑{}",
+            net,
+            sysmon::format_bytes(latest.net_rx),
+            sysmon::format_bytes(latest.net_tx)
+        ),
+    ];
+
+    render_corner_box(buffer, style, &lines, true);
+}
+
+/// Where `--banner` text sits vertically.
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum BannerPosition {
+    Top,
+    Center,
+    Bottom,
+}
+
+/// Large block-letter text drawn over the effect (`--banner`).
+pub struct Banner {
+    pub text: String,
+    pub position: BannerPosition,
+    pub color: Color,
+    /// Slowly pulse the letters' brightness
+    pub pulse: bool,
+}
+
+/// Terminal cells per font pixel horizontally (cells are about twice as
+/// tall as they are wide, so this keeps the letters square-ish).
+const BANNER_PIXEL_WIDTH: usize = 2;
+/// Blank cells between letters.
+const BANNER_LETTER_GAP: usize = 2;
+/// Blank rows between wrapped lines.
+const BANNER_LINE_GAP: usize = 1;
+/// Brightness of the rain behind the banner, so the letters stand out.
+const BANNER_BACKDROP_DIM: f64 = 0.3;
+/// Seconds per brightness pulse.
+const BANNER_PULSE_PERIOD: f64 = 2.0;
+
+/// Width in cells of `text` drawn in block letters.
+fn banner_width(text: &str) -> usize {
+    let letter = font::GLYPH_WIDTH * BANNER_PIXEL_WIDTH + BANNER_LETTER_GAP;
+    (text.chars().count() * letter).saturating_sub(BANNER_LETTER_GAP)
+}
+
+/// Word-wrap banner text into lines that fit `max_width` cells. A single
+/// word that is too long gets a line of its own (and is clipped).
+fn layout_banner(text: &str, max_width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if banner_width(&format!("{} {}", line, word)) <= max_width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    lines
+}
+
+/// Lay out the banner's lines on a `width` x `height` screen: each line with
+/// the cell its top-left corner goes in.
+fn place_banner(banner: &Banner, width: u16, height: u16) -> Vec<(String, usize, usize)> {
+    let (buf_w, buf_h) = (width as usize, height as usize);
+    let lines = layout_banner(&banner.text, buf_w.saturating_sub(2));
+    if lines.is_empty() {
+        return Vec::new();
+    }
+
+    let block_height = lines.len() * font::GLYPH_HEIGHT + (lines.len() - 1) * BANNER_LINE_GAP;
+    let top = match banner.position {
+        BannerPosition::Top => 1,
+        BannerPosition::Center => buf_h.saturating_sub(block_height) / 2,
+        BannerPosition::Bottom => buf_h.saturating_sub(block_height + 1),
+    };
+
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let x0 = buf_w.saturating_sub(banner_width(&line)) / 2;
+            (line, x0, top + i * (font::GLYPH_HEIGHT + BANNER_LINE_GAP))
+        })
+        .collect()
+}
+
+/// Where each line of the banner's letters lands on a `width` x `height`
+/// screen (for keeping rain out from behind it).
+pub fn banner_areas(banner: &Banner, width: u16, height: u16) -> Vec<Region> {
+    place_banner(banner, width, height)
+        .into_iter()
+        .map(|(line, x, y)| Region {
+            x: x as u16,
+            y: y as u16,
+            width: banner_width(&line) as u16,
+            height: font::GLYPH_HEIGHT as u16,
+        })
+        .collect()
+}
+
+/// Render banner text as large block letters, centered horizontally.
+///
+/// Rain behind each line is dimmed so the text stays legible. `time` is
+/// seconds since start, used for the optional pulse.
+pub fn render_banner(buffer: &mut ScreenBuffer, banner: &Banner, time: f64) {
+    let color = if banner.pulse {
+        let phase = (time / BANNER_PULSE_PERIOD * std::f64::consts::TAU).sin();
+        scale_color(banner.color, 0.75 + 0.25 * phase)
+    } else {
+        banner.color
+    };
+
+    for (line, x0, y0) in place_banner(banner, buffer.width(), buffer.height()) {
+        // Dim the rain under this line, with a one-cell margin
+        for y in y0.saturating_sub(1)..(y0 + font::GLYPH_HEIGHT + 1) {
+            for x in x0.saturating_sub(1)..(x0 + banner_width(&line) + 1) {
+                if let Some(&cell) = buffer.get_cell(x as u16, y as u16) {
+                    let fg = scale_color(cell.fg, BANNER_BACKDROP_DIM);
+                    buffer.set_cell(x as u16, y as u16, cell.ch, fg, cell.bg);
+                }
+            }
+        }
+
+        for (n, ch) in line.chars().enumerate() {
+            let Some(bits) = font::known_glyph(ch) else {
+                continue;
+            };
+            let letter_x = x0 + n * (font::GLYPH_WIDTH * BANNER_PIXEL_WIDTH + BANNER_LETTER_GAP);
+            for py in 0..font::GLYPH_HEIGHT {
+                for px in 0..font::GLYPH_WIDTH {
+                    if !font::pixel(bits, px, py) {
+                        continue;
+                    }
+                    for dx in 0..BANNER_PIXEL_WIDTH {
+                        let x = letter_x + px * BANNER_PIXEL_WIDTH + dx;
+                        buffer.set_cell(x as u16, (y0 + py) as u16, '█', color, Color::Reset);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Seconds a notification toast stays on screen.
+const TOAST_SECS: f64 = 5.0;
+/// Seconds a toast takes to fade out at the end of its life.
+const TOAST_FADE_SECS: f64 = 0.5;
+/// Toasts on screen at once; later ones wait their turn.
+const TOAST_MAX_VISIBLE: usize = 4;
+/// Widest a toast box gets, in cells.
+const TOAST_MAX_WIDTH: usize = 50;
+
+/// A `--notify` message and how long it has been showing.
+struct Toast {
+    text: String,
+    age: f64,
+}
+
+/// First-in, first-out queue of notification toasts.
+#[derive(Default)]
+pub struct ToastQueue {
+    toasts: VecDeque<Toast>,
+}
+
+impl ToastQueue {
+    pub fn push(&mut self, text: String) {
+        self.toasts.push_back(Toast { text, age: 0.0 });
+    }
+
+    /// Age the visible toasts and drop the expired ones. Queued toasts
+    /// don't start their clock until they are shown.
+    pub fn update(&mut self, delta_time: f64) {
+        for toast in self.toasts.iter_mut().take(TOAST_MAX_VISIBLE) {
+            toast.age += delta_time;
+        }
+        while self.toasts.front().is_some_and(|t| t.age >= TOAST_SECS) {
+            self.toasts.pop_front();
+        }
+    }
+}
+
+/// Render the visible toasts stacked up from the bottom-right corner,
+/// oldest on top, each fading out over its last moments.
+pub fn render_toasts(buffer: &mut ScreenBuffer, style: &OverlayStyle, queue: &ToastQueue) {
+    let buf_w = buffer.width() as usize;
+    let buf_h = buffer.height() as usize;
+    // Accent bar, space, text, space
+    let max_text = TOAST_MAX_WIDTH.min(buf_w.saturating_sub(4));
+    if max_text < 4 {
+        return;
+    }
+
+    let visible: Vec<&Toast> = queue.toasts.iter().take(TOAST_MAX_VISIBLE).collect();
+    // Stay clear of the status line on the bottom row
+    let bottom = buf_h.saturating_sub(2);
+    for (i, toast) in visible.iter().rev().enumerate() {
+        let Some(y) = bottom.checked_sub(i * 2) else {
+            break;
+        };
+
+        let mut text: String = toast.text.chars().take(max_text).collect();
+        if toast.text.chars().count() > max_text {
+            text.pop();
+            text.push('…');
+        }
+        let width = text.chars().count() + 3;
+        let x0 = buf_w - width - 1;
+
+        let fade = ((TOAST_SECS - toast.age) / TOAST_FADE_SECS).clamp(0.0, 1.0);
+        let accent = lerp_color(style.background, style.title, fade as f32);
+        let fg = lerp_color(style.background, style.text, fade as f32);
+
+        let cells = std::iter::once('▌')
+            .chain(std::iter::once(' '))
+            .chain(text.chars())
+            .chain(std::iter::once(' '));
+        for (dx, ch) in cells.enumerate() {
+            let color = if dx == 0 { accent } else { fg };
+            style.put(buffer, x0 + dx, y, ch, color);
+        }
+    }
+}
+
+/// Format the time left on a `--countdown` as `MM:SS`, or `H:MM:SS` from an
+/// hour up. Rounds up, so the clock only reads `00:00` once time is up.
+pub fn format_countdown(remaining: f64) -> String {
+    let secs = remaining.max(0.0).ceil() as u64;
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{:02}:{:02}", m, s)
+    }
+}
+
+/// Render the `--countdown` clock in large digits at the center of the screen.
+pub fn render_countdown(buffer: &mut ScreenBuffer, remaining: f64, color: Color) {
+    render_banner(buffer, &countdown_banner(remaining, color), 0.0);
+}
+
+/// Where the countdown clock's digits land on a `width` x `height` screen.
+pub fn countdown_areas(remaining: f64, width: u16, height: u16) -> Vec<Region> {
+    banner_areas(&countdown_banner(remaining, Color::Reset), width, height)
+}
+
+/// The countdown clock, drawn as a centered banner.
+fn countdown_banner(remaining: f64, color: Color) -> Banner {
+    Banner {
+        text: format_countdown(remaining),
+        position: BannerPosition::Center,
+        color,
+        pulse: false,
+    }
+}
+
+/// Where the `--logo` image sits.
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum LogoPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Center,
+}
+
+/// An image watermark composited over the effect (`--logo`).
+pub struct Logo {
+    image: Image,
+    /// The image resampled for the current screen width
+    blocks: HalfBlockImage,
+    position: LogoPosition,
+    opacity: f64,
+    /// Requested width in cells (default: fit to a third of the screen)
+    width: Option<u16>,
+}
+
+impl Logo {
+    pub fn new(
+        image: Image,
+        position: LogoPosition,
+        opacity: f64,
+        width: Option<u16>,
+        screen_width: u16,
+    ) -> Self {
+        let mut logo = Self {
+            blocks: image.to_half_blocks(1),
+            image,
+            position,
+            opacity: opacity.clamp(0.0, 1.0),
+            width,
+        };
+        logo.fit(screen_width);
+        logo
+    }
+
+    /// Resample for a new screen width.
+    pub fn fit(&mut self, screen_width: u16) {
+        let columns = self
+            .width
+            .unwrap_or((self.image.width as u16).min(screen_width / 3))
+            .min(screen_width.saturating_sub(2))
+            .max(1);
+        self.blocks = self.image.to_half_blocks(columns);
+    }
+}
+
+/// Where the logo sits on a `width` x `height` screen, or `None` if it
+/// doesn't fit (and isn't drawn).
+pub fn logo_area(logo: &Logo, width: u16, height: u16) -> Option<Region> {
+    let blocks = &logo.blocks;
+    if blocks.width + 2 > width || blocks.height + 2 > height {
+        return None;
+    }
+
+    // One cell of margin from the screen edges
+    let (x, y) = match logo.position {
+        LogoPosition::TopLeft => (1, 1),
+        LogoPosition::TopRight => (width - blocks.width - 1, 1),
+        LogoPosition::BottomLeft => (1, height - blocks.height - 1),
+        LogoPosition::BottomRight => (width - blocks.width - 1, height - blocks.height - 1),
+        LogoPosition::Center => ((width - blocks.width) / 2, (height - blocks.height) / 2),
+    };
+    Some(Region {
+        x,
+        y,
+        width: blocks.width,
+        height: blocks.height,
+    })
+}
+
+/// Composite the logo over the buffer with alpha blending.
+///
+/// Opaque image cells become half blocks (`▀`, top pixel as foreground,
+/// bottom as background). Where the image is translucent over a rain glyph,
+/// the glyph is kept and tinted toward the image color instead, so the rain
+/// still shows through a faint watermark.
+pub fn render_logo(buffer: &mut ScreenBuffer, logo: &Logo) {
+    let blocks = &logo.blocks;
+    let Some(area) = logo_area(logo, buffer.width(), buffer.height()) else {
+        return;
+    };
+    let (x0, y0) = (area.x, area.y);
+
+    let rgb = |p: [u8; 4]| Color::Rgb {
+        r: p[0],
+        g: p[1],
+        b: p[2],
+    };
+
+    for (i, block) in blocks.cells.iter().enumerate() {
+        let x = x0 + (i % blocks.width as usize) as u16;
+        let y = y0 + (i / blocks.width as usize) as u16;
+        let Some(&under) = buffer.get_cell(x, y) else {
+            continue;
+        };
+
+        let top_alpha = (block.top[3] as f64 / 255.0 * logo.opacity) as f32;
+        let bottom_alpha = (block.bottom[3] as f64 / 255.0 * logo.opacity) as f32;
+        if top_alpha <= 0.0 && bottom_alpha <= 0.0 {
+            continue;
+        }
+
+        if under.ch != ' ' && (top_alpha < 1.0 || bottom_alpha < 1.0) {
+            let average = lerp_color(rgb(block.top), rgb(block.bottom), 0.5);
+            let alpha = (top_alpha + bottom_alpha) / 2.0;
+            let fg = lerp_color(under.fg, average, alpha);
+            let bg = lerp_color(under.bg, average, alpha);
+            buffer.set_cell(x, y, under.ch, fg, bg);
+        } else {
+            let fg = lerp_color(under.bg, rgb(block.top), top_alpha);
+            let bg = lerp_color(under.bg, rgb(block.bottom), bottom_alpha);
+            buffer.set_cell(x, y, '▀', fg, bg);
+        }
+    }
+}
+
+/// What the caller should do after a key was fed to a [`LineEditor`] or
+/// [`Picker`].
+pub enum EditResult {
+    /// Keep the prompt open
+    Continue,
+    /// Enter was pressed: use the current text
+    Submit,
+    /// Esc was pressed: discard the prompt
+    Cancel,
+}
+
+/// A minimal single-line text editor for prompt overlays.
+///
+/// Supports typing, Backspace/Delete, Left/Right, and Home/End. The cursor
+/// is a char index (not a byte index) so multi-byte input stays safe.
+#[derive(Default)]
+pub struct LineEditor {
+    text: String,
+    cursor: usize,
+}
+
+impl LineEditor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The current contents of the line.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replace the contents and move the cursor to the end.
+    pub fn set_text(&mut self, text: String) {
+        self.cursor = text.chars().count();
+        self.text = text;
+    }
+
+    /// Feed a key press to the editor. Ctrl+C cancels like Esc.
+    #[cfg(feature = "terminal")]
+    pub fn handle_key(&mut self, key: KeyEvent) -> EditResult {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Enter => return EditResult::Submit,
+            KeyCode::Esc => return EditResult::Cancel,
+            KeyCode::Char('c') if ctrl => return EditResult::Cancel,
+            KeyCode::Char(_) if ctrl => {}
+            KeyCode::Char(ch) if !ch.is_control() => {
+                let at = self.byte_index(self.cursor);
+                self.text.insert(at, ch);
+                self.cursor += 1;
+            }
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                let at = self.byte_index(self.cursor);
+                self.text.remove(at);
+            }
+            KeyCode::Delete if self.cursor < self.text.chars().count() => {
+                let at = self.byte_index(self.cursor);
+                self.text.remove(at);
+            }
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.text.chars().count()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.chars().count(),
+            _ => {}
+        }
+        EditResult::Continue
+    }
+
+    /// Convert a char index into a byte index within `text`.
+    #[cfg(feature = "terminal")]
+    fn byte_index(&self, char_idx: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(char_idx)
+            .map(|(i, _)| i)
+            .unwrap_or(self.text.len())
+    }
+}
+
+/// Render a one-line text prompt (label + editable text) near the bottom of the screen.
+///
+/// The cursor cell is drawn with inverted colors. Long input scrolls so the
+/// cursor always stays visible.
+pub fn render_prompt(
+    buffer: &mut ScreenBuffer,
+    style: &OverlayStyle,
+    label: &str,
+    editor: &LineEditor,
+) {
+    let buf_w = buffer.width();
+    let buf_h = buffer.height();
+
+    let box_width = buf_w.saturating_sub(4).min(60);
+    let label_len = label.chars().count() as u16;
+    if buf_h < 3 || box_width < label_len + 6 {
+        return;
+    }
+
+    let start_x = (buf_w - box_width) / 2;
+    let y = buf_h - 3;
+
+    // Visible window of the text: scroll so the cursor stays on screen
+    let field_width = (box_width - label_len - 3) as usize;
+    let scroll = editor.cursor.saturating_sub(field_width - 1);
+    let visible: Vec<char> = editor.text.chars().skip(scroll).take(field_width).collect();
+
+    for x in start_x..(start_x + box_width) {
+        style.put(buffer, x as usize, y as usize, ' ', style.text);
+    }
+    for (i, ch) in label.chars().enumerate() {
+        let x = start_x as usize + 1 + i;
+        style.put(buffer, x, y as usize, ch, style.title);
+    }
+
+    // The text field is always solid so typed spaces read as spaces
+    let field_x = start_x + 2 + label_len;
+    for col in 0..field_width as u16 {
+        let ch = visible.get(col as usize).copied().unwrap_or(' ');
+        buffer.set_cell(field_x + col, y, ch, style.text, style.background);
+    }
+
+    // Inverted cursor cell
+    let cursor_col = (editor.cursor - scroll) as u16;
+    let under = visible.get(cursor_col as usize).copied().unwrap_or(' ');
+    buffer.set_cell(field_x + cursor_col, y, under, style.background, style.text);
+}
+
+/// Rows a picker moves per PgUp/PgDn.
+#[cfg(feature = "terminal")]
+const PICKER_PAGE: usize = 10;
+/// Width of the name column in a picker.
+const PICKER_NAME_WIDTH: usize = 22;
+/// Width of the preview column (palette swatch or charset sample).
+const PICKER_PREVIEW_WIDTH: usize = 10;
+/// Rows of a picker around the list: padding, title, and a blank line
+/// above; a blank line, the key hint, and padding below.
+const PICKER_CHROME_ROWS: usize = 6;
+
+/// What a [`Picker`] chooses between.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PickerKind {
+    Palette,
+    Charset,
+}
+
+/// Scrollable list for the palette and charset pickers. The caller
+/// previews `selected()` as it changes and restores `original()` on cancel.
+pub struct Picker {
+    kind: PickerKind,
+    names: Vec<&'static str>,
+    selected: usize,
+    original: String,
+}
+
+impl Picker {
+    /// Open the list with `current` selected.
+    pub fn new(kind: PickerKind, current: &str) -> Self {
+        let names = match kind {
+            PickerKind::Palette => palette::palette_names(),
+            PickerKind::Charset => chars::charset_names().to_vec(),
+        };
+        let current_lower = current.to_ascii_lowercase();
+        let selected = names.iter().position(|&n| n == current_lower).unwrap_or(0);
+        Self {
+            kind,
+            names,
+            selected,
+            original: current.to_string(),
+        }
+    }
+
+    pub fn kind(&self) -> PickerKind {
+        self.kind
+    }
+
+    pub fn selected(&self) -> &'static str {
+        self.names[self.selected]
+    }
+
+    /// The name that was active when the picker opened.
+    pub fn original(&self) -> &str {
+        &self.original
+    }
+
+    /// Feed a key press to the list: arrows, PgUp/PgDn, and Home/End move
+    /// the selection, a letter jumps to the next name starting with it,
+    /// Enter confirms, and Esc (or Ctrl+C) cancels.
+    #[cfg(feature = "terminal")]
+    pub fn handle_key(&mut self, key: KeyEvent) -> EditResult {
+        let last = self.names.len() - 1;
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Enter => return EditResult::Submit,
+            KeyCode::Esc => return EditResult::Cancel,
+            KeyCode::Char('c') if ctrl => return EditResult::Cancel,
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = (self.selected + 1).min(last),
+            KeyCode::PageUp => self.selected = self.selected.saturating_sub(PICKER_PAGE),
+            KeyCode::PageDown => self.selected = (self.selected + PICKER_PAGE).min(last),
+            KeyCode::Home => self.selected = 0,
+            KeyCode::End => self.selected = last,
+            KeyCode::Char(ch) if !ctrl && ch.is_ascii_alphabetic() => {
+                let ch = ch.to_ascii_lowercase();
+                let count = self.names.len();
+                if let Some(i) = (1..=count)
+                    .map(|step| (self.selected + step) % count)
+                    .find(|&i| self.names[i].starts_with(ch))
+                {
+                    self.selected = i;
+                }
+            }
+            _ => {}
+        }
+        EditResult::Continue
+    }
+}
+
+/// Render a picker centered on screen, scrolled to keep the selection in
+/// view. Each row shows a preview: a swatch of the palette's tail-to-head
+/// colors, or a sample of the charset's characters. The selected row is
+/// marked and drawn in the title color.
+pub fn render_picker(buffer: &mut ScreenBuffer, style: &OverlayStyle, picker: &Picker) {
+    let buf_w = buffer.width() as usize;
+    let buf_h = buffer.height() as usize;
+
+    let chrome = PICKER_CHROME_ROWS + 2 * style.border.thickness();
+    let rows = buf_h.saturating_sub(chrome + 2).min(picker.names.len());
+    if rows == 0 {
+        return;
+    }
+    let scroll = picker
+        .selected
+        .saturating_sub(rows / 2)
+        .min(picker.names.len() - rows);
+
+    let title = match picker.kind {
+        PickerKind::Palette => "PALETTES",
+        PickerKind::Charset => "CHARSETS",
+    };
+    let mut lines = vec![
+        (String::new(), style.text),
+        (
+            format!(
+                "  {}  {}/{}",
+                title,
+                picker.selected + 1,
+                picker.names.len()
+            ),
+            style.title,
+        ),
+        (String::new(), style.text),
+    ];
+    for (i, name) in picker.names.iter().enumerate().skip(scroll).take(rows) {
+        let (marker, fg) = if i == picker.selected {
+            ('>', style.title)
+        } else {
+            (' ', style.text)
+        };
+        lines.push((format!(" {} {}", marker, name), fg));
+    }
+    lines.push((String::new(), style.text));
+    lines.push((
+        "  Up/Down: preview  Enter: keep  Esc: revert".to_string(),
+        style.title,
+    ));
+    lines.push((String::new(), style.text));
+
+    let preview_x = 3 + PICKER_NAME_WIDTH;
+    let (edge, _) = style.box_size(0, 0);
+    let width = lines
+        .iter()
+        .map(|(l, _)| l.chars().count() + 2)
+        .max()
+        .unwrap_or(0)
+        .max(preview_x + PICKER_PREVIEW_WIDTH + 2)
+        .min(buf_w.saturating_sub(edge));
+    let (box_width, box_height) = style.box_size(width, lines.len());
+    let start_x = buf_w.saturating_sub(box_width) / 2;
+    let start_y = buf_h.saturating_sub(box_height) / 2;
+    style.draw_box(buffer, start_x, start_y, width, &lines);
+
+    if width < preview_x + PICKER_PREVIEW_WIDTH {
+        return;
+    }
+    let inset = style.border.thickness();
+    let x = (start_x + inset + preview_x) as u16;
+    for (row, name) in picker.names.iter().skip(scroll).take(rows).enumerate() {
+        let y = (start_y + inset + 3 + row) as u16;
+        match picker.kind {
+            // Two cells per color, dimmest to brightest
+            PickerKind::Palette => {
+                let p = palette::palette_by_name(name);
+                let colors = [p.tail, p.body_mid, p.body_bright, p.head, p.highlight];
+                for (i, color) in colors.iter().enumerate() {
+                    buffer.set_cell(x + 2 * i as u16, y, '█', *color, style.background);
+                    buffer.set_cell(x + 2 * i as u16 + 1, y, '█', *color, style.background);
+                }
+            }
+            PickerKind::Charset => {
+                let sample = chars::charset_by_name(name).sample(PICKER_PREVIEW_WIDTH);
+                for (i, ch) in sample.chars().enumerate() {
+                    style.put(buffer, x as usize + i, y as usize, ch, style.text);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "terminal"))]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn type_str(editor: &mut LineEditor, s: &str) {
+        for ch in s.chars() {
+            editor.handle_key(key(KeyCode::Char(ch)));
+        }
+    }
+
+    #[test]
+    fn line_editor_inserts_and_deletes() {
+        let mut editor = LineEditor::new();
+        type_str(&mut editor, "nght");
+        editor.handle_key(key(KeyCode::Home));
+        editor.handle_key(key(KeyCode::Right));
+        editor.handle_key(key(KeyCode::Char('i')));
+        assert_eq!(editor.text(), "night");
+
+        editor.handle_key(key(KeyCode::End));
+        editor.handle_key(key(KeyCode::Backspace));
+        assert_eq!(editor.text(), "nigh");
+    }
+
+    #[test]
+    fn line_editor_handles_multibyte_chars() {
+        let mut editor = LineEditor::new();
+        type_str(&mut editor, "ｱｲ");
+        editor.handle_key(key(KeyCode::Home));
+        editor.handle_key(key(KeyCode::Delete));
+        assert_eq!(editor.text(), "ｲ");
+    }
+
+    #[test]
+    fn line_editor_reports_submit_and_cancel() {
+        let mut editor = LineEditor::new();
+        assert!(matches!(
+            editor.handle_key(key(KeyCode::Enter)),
+            EditResult::Submit
+        ));
+        assert!(matches!(
+            editor.handle_key(key(KeyCode::Esc)),
+            EditResult::Cancel
+        ));
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert!(matches!(editor.handle_key(ctrl_c), EditResult::Cancel));
+        assert_eq!(editor.text(), "");
+    }
+
+    #[test]
+    fn render_prompt_draws_label_and_text() {
+        let mut buffer = ScreenBuffer::new(40, 10);
+        let mut editor = LineEditor::new();
+        type_str(&mut editor, "abc");
+        render_prompt(&mut buffer, &OverlayStyle::default(), "Name:", &editor);
+
+        let row: String = (0..40).map(|x| buffer.get_cell(x, 7).unwrap().ch).collect();
+        assert!(row.contains("Name: abc"), "row was '{}'", row);
+    }
+
+    #[test]
+    fn picker_moves_jumps_and_renders_previews() {
+        let row = |buffer: &ScreenBuffer, y: u16| -> String {
+            (0..60).map(|x| buffer.get_cell(x, y).unwrap().ch).collect()
+        };
+
+        let mut picker = Picker::new(PickerKind::Palette, "Gold");
+        assert_eq!(picker.selected(), "gold");
+        picker.handle_key(key(KeyCode::Up));
+        picker.handle_key(key(KeyCode::Up));
+        assert_eq!(picker.selected(), "classic");
+        picker.handle_key(key(KeyCode::Char('s')));
+        assert_eq!(picker.selected(), "silver");
+        picker.handle_key(key(KeyCode::Char('s')));
+        assert_eq!(picker.selected(), "synthwave");
+        assert!(matches!(
+            picker.handle_key(key(KeyCode::Esc)),
+            EditResult::Cancel
+        ));
+        assert_eq!(picker.original(), "Gold");
+
+        let mut buffer = ScreenBuffer::new(60, 20);
+        render_picker(&mut buffer, &OverlayStyle::default(), &picker);
+        let selected = (0..20)
+            .find(|&y| row(&buffer, y).contains("> synthwave"))
+            .expect("selected row drawn");
+        let text = row(&buffer, selected);
+        assert!(text.contains("██████████"));
+        let swatch_x = text.chars().position(|c| c == '█').unwrap() as u16;
+        let head = palette::palette_by_name("synthwave").head;
+        assert_eq!(buffer.get_cell(swatch_x + 6, selected).unwrap().fg, head);
+
+        // Charsets preview a sample of their characters instead
+        let mut buffer = ScreenBuffer::new(60, 20);
+        let picker = Picker::new(PickerKind::Charset, "binary");
+        render_picker(&mut buffer, &OverlayStyle::default(), &picker);
+        assert!((0..20).any(|y| {
+            let text = row(&buffer, y);
+            text.contains("> binary") && text.contains("0101010101")
+        }));
+    }
+
+    #[test]
+    fn stats_overlay_draws_metrics_in_corner() {
+        let mut buffer = ScreenBuffer::new(60, 10);
+        let stats = Stats {
+            fps: 29.97,
+            target_fps: 30.0,
+            power_saver: Some("on battery"),
+            dropped_frames: 3,
+            worst_frame: Some((120.0, "flush")),
+            frame_avg_ms: 33.4,
+            frame_p95_ms: 40.0,
+            active_columns: None,
+            cells_changed: 120,
+            bytes_written: 4096,
+            seed: 1234,
+        };
+        render_stats(&mut buffer, &OverlayStyle::default(), &stats);
+        let row = |y: u16| -> String {
+            (0..60)
+                .map(|x| buffer.get_cell(x, y).unwrap().ch)
+                .collect::<String>()
+        };
+        assert!(row(1).contains("FPS       30.0 (target 30)"));
+        assert!(row(2).contains("33.4 ms avg / 40.0 ms p95"));
+        assert!(row(3).contains("Columns   -"));
+        assert!(row(5).contains("4096 bytes"));
+        assert!(row(6).contains("Dropped   3 frames"));
+        assert!(row(7).contains("Worst     120.0 ms (flush)"));
+        assert!(row(8).contains("Seed      1234"));
+        assert!(row(9).contains("Power     saver (on battery)"));
+
+        // Too small to fit: draws nothing
+        let mut tiny = ScreenBuffer::new(10, 3);
+        render_stats(&mut tiny, &OverlayStyle::default(), &stats);
+        assert!(tiny.cells().iter().all(|c| c.ch == ' '));
+    }
+
+    #[test]
+    fn sysmon_overlay_draws_top_right_after_first_sample() {
+        let mut buffer = ScreenBuffer::new(80, 10);
+        let mut history = sysmon::History::default();
+        render_sysmon(&mut buffer, &OverlayStyle::default(), &history);
+        assert!(buffer.cells().iter().all(|c| c.ch == ' '));
+
+        history.push(sysmon::Sample {
+            cpu_percent: 50.0,
+            mem_used: 4 << 30,
+            mem_total: 8 << 30,
+            net_rx: 2048,
+            net_tx: 512,
+            ..Default::default()
+        });
+        render_sysmon(&mut buffer, &OverlayStyle::default(), &history);
+        let row = |y: u16| -> String {
+            (0..80)
+                .map(|x| buffer.get_cell(x, y).unwrap().ch)
+                .collect::<String>()
+        };
+        assert!(row(1).trim_end().ends_with("50%"), "row was '{}'", row(1));
+        assert!(row(2).contains("████████░░░░░░░░ 4.0G/8.0G"));
+        assert!(row(3).contains("↓2.0K ↑512B"));
+        // Anchored to the right edge, clear of the stats box on the left
+        assert_eq!(buffer.get_cell(0, 1).unwrap().ch, ' ');
+    }
+
+    #[test]
+    fn toasts_queue_show_and_expire_in_order() {
+        let mut queue = ToastQueue::default();
+        for i in 0..6 {
+            queue.push(format!("message {}", i));
+        }
+        let mut buffer = ScreenBuffer::new(60, 12);
+        render_toasts(&mut buffer, &OverlayStyle::default(), &queue);
+        let row = |buffer: &ScreenBuffer, y: u16| -> String {
+            (0..60)
+                .map(|x| buffer.get_cell(x, y).unwrap().ch)
+                .collect::<String>()
+        };
+        // Newest visible toast sits just above the status line
+        assert!(row(&buffer, 10).ends_with("▌ message 3  "));
+        assert!(row(&buffer, 4).contains("message 0"));
+        assert!(!row(&buffer, 2).contains("message"));
+
+        // The first batch expires together; the waiting two move in fresh
+        queue.update(TOAST_SECS);
+        buffer.clear();
+        render_toasts(&mut buffer, &OverlayStyle::default(), &queue);
+        assert!(row(&buffer, 8).contains("message 4"));
+        assert!(row(&buffer, 10).contains("message 5"));
+    }
+
+    #[test]
+    fn help_lists_bindings_with_footer_and_pages_when_short() {
+        let bindings = crate::keys::KeyMap::default().help_entries(false);
+        let text = |buffer: &ScreenBuffer| -> String {
+            (0..buffer.height())
+                .map(|y| {
+                    (0..buffer.width())
+                        .map(|x| buffer.get_cell(x, y).unwrap().ch)
+                        .collect::<String>()
+                        + "\n"
+                })
+                .collect()
+        };
+
+        // Tall enough: everything on one page, footer under the list
+        let mut buffer = ScreenBuffer::new(60, bindings.len() as u16 + 10);
+        render_help(
+            &mut buffer,
+            &OverlayStyle::default(),
+            &bindings,
+            "classic | green | 1.0x",
+            0,
+        );
+        let all = text(&buffer);
+        let has_row = |keys: &str, description: &str| {
+            all.lines().any(|l| {
+                l.trim_start().starts_with(&format!("{} ", keys))
+                    && l.trim_end().ends_with(description)
+            })
+        };
+        assert!(has_row("Space", "Pause / Resume"));
+        assert!(has_row("q Esc", "Quit"));
+        assert!(has_row("0 Backspace", "Reset to startup settings"));
+        assert!(has_row("1", "Effect: classic"));
+        assert!(all.contains("classic | green | 1.0x"));
+        assert!(!all.contains("Page 1/"));
+        assert!(!all.contains("Move region"));
+
+        // Short: split into pages instead of disappearing
+        let (per_page, pages) = help_layout(12, bindings.len(), &OverlayStyle::default());
+        assert_eq!((per_page, pages), (3, bindings.len().div_ceil(3)));
+        let mut short = ScreenBuffer::new(60, 12);
+        render_help(&mut short, &OverlayStyle::default(), &bindings, "footer", 1);
+        let page_two = text(&short);
+        assert!(page_two.contains(&format!("Page 2/{}", pages)));
+        assert!(page_two.contains("Density down"));
+        assert!(!page_two.contains("Pause / Resume"));
+        assert!(page_two.contains("footer"));
+    }
+
+    #[test]
+    fn themed_boxes_draw_borders_and_blend() {
+        let config = OverlayConfig {
+            background: Some("#000080".to_string()),
+            border: Some("double".to_string()),
+            opacity: Some(0.5),
+            ..OverlayConfig::default()
+        };
+        let style = OverlayStyle::from_config(&config);
+        assert_eq!(style.border, BorderStyle::Double);
+
+        let mut buffer = ScreenBuffer::new(20, 6);
+        let green = Color::Rgb { r: 0, g: 200, b: 0 };
+        for x in 0..20 {
+            buffer.set_cell(x, 1, 'ｱ', green, Color::Reset);
+        }
+        let lines = [("hi".to_string(), style.text)];
+        style.draw_box(&mut buffer, 0, 0, 4, &lines);
+
+        let ch = |x: u16, y: u16| buffer.get_cell(x, y).unwrap().ch;
+        assert_eq!(
+            [ch(0, 0), ch(5, 0), ch(0, 2), ch(5, 2)],
+            ['╔', '╗', '╚', '╝']
+        );
+        assert_eq!([ch(1, 1), ch(2, 1)], ['h', 'i']);
+        // Blank cells let the rain through, blended halfway to the background
+        let cell = buffer.get_cell(3, 1).unwrap();
+        assert_eq!(cell.ch, 'ｱ');
+        assert!(matches!(cell.bg, Color::Rgb { r: 0, g: 0, b: 64 }));
+    }
+
+    #[test]
+    fn countdown_formats_and_rounds_up() {
+        assert_eq!(format_countdown(1500.0), "25:00");
+        assert_eq!(format_countdown(0.2), "00:01");
+        assert_eq!(format_countdown(0.0), "00:00");
+        assert_eq!(format_countdown(-3.0), "00:00");
+        assert_eq!(format_countdown(3661.0), "1:01:01");
+    }
+
+    #[test]
+    fn banner_wraps_words_to_fit() {
+        // Each letter is 8 cells wide (6 + gap), minus the trailing gap
+        assert_eq!(banner_width("HI"), 14);
+        assert_eq!(layout_banner("wake up neo", 100), ["wake up neo"]);
+        assert_eq!(layout_banner("wake up neo", 60), ["wake up", "neo"]);
+        assert_eq!(layout_banner("   ", 40), Vec::<String>::new());
+    }
+
+    #[test]
+    fn banner_draws_block_letters_centered() {
+        let mut buffer = ScreenBuffer::new(40, 11);
+        let green = Color::Rgb { r: 0, g: 255, b: 0 };
+        let banner = Banner {
+            text: "I".to_string(),
+            position: BannerPosition::Center,
+            color: green,
+            pulse: false,
+        };
+        render_banner(&mut buffer, &banner, 0.0);
+
+        // 'I' is 6 cells wide, centered at x = 17, rows 3-7; top row is ###
+        let top: String = (17..23)
+            .map(|x| buffer.get_cell(x, 3).unwrap().ch)
+            .collect();
+        assert_eq!(top, "██████");
+        // Middle row is .#. so only the center pixel is lit
+        assert_eq!(buffer.get_cell(17, 4).unwrap().ch, ' ');
+        assert_eq!(buffer.get_cell(19, 4).unwrap().ch, '█');
+        assert!(matches!(
+            buffer.get_cell(19, 4).unwrap().fg,
+            Color::Rgb { g: 255, .. }
+        ));
+    }
+
+    #[test]
+    fn logo_blends_opaque_and_translucent_pixels() {
+        // 1x2 image: opaque red over opaque blue, plus a fully transparent
+        // column that must leave the buffer untouched
+        let image = Image {
+            width: 2,
+            height: 2,
+            pixels: vec![
+                [255, 0, 0, 255],
+                [0, 0, 0, 0],
+                [0, 0, 255, 255],
+                [0, 0, 0, 0],
+            ],
+        };
+        let mut buffer = ScreenBuffer::new(10, 5);
+        let green = Color::Rgb { r: 0, g: 200, b: 0 };
+        buffer.set_cell(2, 1, 'x', green, Color::Reset);
+
+        let logo = Logo::new(image, LogoPosition::TopLeft, 1.0, Some(2), 10);
+        render_logo(&mut buffer, &logo);
+        let cell = buffer.get_cell(1, 1).unwrap();
+        assert_eq!(cell.ch, '▀');
+        assert!(matches!(cell.fg, Color::Rgb { r: 255, g: 0, b: 0 }));
+        assert!(matches!(cell.bg, Color::Rgb { r: 0, g: 0, b: 255 }));
+        assert_eq!(buffer.get_cell(2, 1).unwrap().ch, 'x');
+
+        // At half opacity over a rain glyph the glyph survives, tinted
+        let image = Image {
+            width: 1,
+            height: 2,
+            pixels: vec![[255, 255, 255, 255]; 2],
+        };
+        let mut buffer = ScreenBuffer::new(10, 5);
+        buffer.set_cell(1, 1, 'x', green, Color::Reset);
+        let logo = Logo::new(image, LogoPosition::TopLeft, 0.5, Some(1), 10);
+        render_logo(&mut buffer, &logo);
+        let cell = buffer.get_cell(1, 1).unwrap();
+        assert_eq!(cell.ch, 'x');
+        assert!(matches!(
+            cell.fg,
+            Color::Rgb {
+                r: 128,
+                g: 228,
+                b: 128
+            }
+        ));
     }
 }
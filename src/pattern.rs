@@ -0,0 +1,346 @@
+//! A small regular expression matcher for `--tail-highlight`.
+//!
+//! Supports what log filters usually need: literals, `.`, classes like
+//! `[a-z0-9_]` and `[^ ]`, the escapes `\d \w \s` (and `\D \W \S`),
+//! anchors `^` and `$`, groups with `|` alternation, and the greedy
+//! quantifiers `* + ?` and `{n}`, `{n,}`, `{n,m}`. A leading `(?i)` makes
+//! the whole pattern case-insensitive. Matching backtracks, which is fine
+//! for lines of log text.
+
+/// A compiled pattern.
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    alternatives: Vec<Vec<Node>>,
+    ignore_case: bool,
+}
+
+#[derive(Clone, Debug)]
+enum Node {
+    Char(char),
+    Any,
+    /// Inclusive ranges; `negated` matches anything outside them
+    Class {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    Start,
+    End,
+    Group(Vec<Vec<Node>>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+    },
+}
+
+impl Pattern {
+    /// Compile `source`, or explain what's wrong with it.
+    pub fn new(source: &str) -> Result<Self, String> {
+        let (ignore_case, body) = match source.strip_prefix("(?i)") {
+            Some(rest) => (true, rest),
+            None => (false, source),
+        };
+        let mut parser = Parser {
+            chars: body.chars().collect(),
+            pos: 0,
+        };
+        let alternatives = parser.alternatives()?;
+        if parser.pos < parser.chars.len() {
+            return Err(format!("unmatched ')' in pattern '{source}'"));
+        }
+        Ok(Self {
+            alternatives,
+            ignore_case,
+        })
+    }
+
+    /// Whether the pattern matches anywhere in `text`.
+    pub fn is_match(&self, text: &str) -> bool {
+        let text: Vec<char> = if self.ignore_case {
+            text.chars().flat_map(char::to_lowercase).collect()
+        } else {
+            text.chars().collect()
+        };
+        let matcher = Matcher {
+            text: &text,
+            ignore_case: self.ignore_case,
+        };
+        (0..=text.len()).any(|start| {
+            self.alternatives
+                .iter()
+                .any(|seq| matcher.seq(seq, start, &mut |_| true))
+        })
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    /// Sequences separated by `|`, up to a `)` or the end.
+    fn alternatives(&mut self) -> Result<Vec<Vec<Node>>, String> {
+        let mut alternatives = vec![self.sequence()?];
+        while self.eat('|') {
+            alternatives.push(self.sequence()?);
+        }
+        Ok(alternatives)
+    }
+
+    fn sequence(&mut self) -> Result<Vec<Node>, String> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantified(atom)?);
+        }
+        Ok(nodes)
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let c = self.peek().ok_or("pattern ends unexpectedly")?;
+        self.pos += 1;
+        Ok(match c {
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '(' => {
+                let group = self.alternatives()?;
+                if !self.eat(')') {
+                    return Err("unclosed '(' in pattern".to_string());
+                }
+                Node::Group(group)
+            }
+            '[' => self.class()?,
+            '\\' => self.escape()?,
+            '*' | '+' | '?' | '{' => return Err(format!("nothing to repeat before '{c}'")),
+            c => Node::Char(c),
+        })
+    }
+
+    fn escape(&mut self) -> Result<Node, String> {
+        let c = self.peek().ok_or("pattern ends with '\\'")?;
+        self.pos += 1;
+        let class = |ranges: &[(char, char)], negated| Node::Class {
+            ranges: ranges.to_vec(),
+            negated,
+        };
+        const DIGIT: &[(char, char)] = &[('0', '9')];
+        const WORD: &[(char, char)] = &[('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')];
+        const SPACE: &[(char, char)] = &[(' ', ' '), ('\t', '\r')];
+        Ok(match c {
+            'd' => class(DIGIT, false),
+            'D' => class(DIGIT, true),
+            'w' => class(WORD, false),
+            'W' => class(WORD, true),
+            's' => class(SPACE, false),
+            'S' => class(SPACE, true),
+            't' => Node::Char('\t'),
+            c if c.is_alphanumeric() => return Err(format!("unknown escape '\\{c}'")),
+            c => Node::Char(c),
+        })
+    }
+
+    /// A `[...]` class, after the `[`.
+    fn class(&mut self) -> Result<Node, String> {
+        let negated = self.eat('^');
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let c = self.peek().ok_or("unclosed '[' in pattern")?;
+            self.pos += 1;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            let low = match c {
+                '\\' => match self.escape()? {
+                    Node::Char(c) => c,
+                    Node::Class {
+                        ranges: escaped, ..
+                    } => {
+                        ranges.extend(escaped);
+                        continue;
+                    }
+                    _ => unreachable!("escapes are characters or classes"),
+                },
+                c => c,
+            };
+            let high = match (self.peek(), self.chars.get(self.pos + 1)) {
+                (Some('-'), Some(&high)) if high != ']' => {
+                    self.pos += 2;
+                    high
+                }
+                _ => low,
+            };
+            if high < low {
+                return Err(format!("bad range '{low}-{high}' in pattern"));
+            }
+            ranges.push((low, high));
+        }
+        Ok(Node::Class { ranges, negated })
+    }
+
+    fn quantified(&mut self, atom: Node) -> Result<Node, String> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                let close = self.chars[self.pos..]
+                    .iter()
+                    .position(|&c| c == '}')
+                    .ok_or("unclosed '{' in pattern")?;
+                let inside: String = self.chars[self.pos + 1..self.pos + close].iter().collect();
+                let number = |s: &str| {
+                    s.trim()
+                        .parse::<usize>()
+                        .map_err(|_| format!("bad repeat '{{{inside}}}' in pattern"))
+                };
+                let bounds = match inside.split_once(',') {
+                    None => (number(&inside)?, Some(number(&inside)?)),
+                    Some((min, max)) if max.trim().is_empty() => (number(min)?, None),
+                    Some((min, max)) => (number(min)?, Some(number(max)?)),
+                };
+                self.pos += close;
+                bounds
+            }
+            _ => return Ok(atom),
+        };
+        self.pos += 1;
+        if max.is_some_and(|max| max < min) {
+            return Err("repeat maximum is below its minimum".to_string());
+        }
+        Ok(Node::Repeat {
+            node: Box::new(atom),
+            min,
+            max,
+        })
+    }
+}
+
+struct Matcher<'a> {
+    text: &'a [char],
+    ignore_case: bool,
+}
+
+impl Matcher<'_> {
+    /// Match `nodes` at `pos`, then hand each place they could end to
+    /// `next` until it accepts one.
+    fn seq(&self, nodes: &[Node], pos: usize, next: &mut dyn FnMut(usize) -> bool) -> bool {
+        let Some((first, rest)) = nodes.split_first() else {
+            return next(pos);
+        };
+        match first {
+            Node::Start => pos == 0 && self.seq(rest, pos, next),
+            Node::End => pos == self.text.len() && self.seq(rest, pos, next),
+            Node::Group(alternatives) => alternatives
+                .iter()
+                .any(|seq| self.seq(seq, pos, &mut |end| self.seq(rest, end, next))),
+            Node::Repeat { node, min, max } => self.repeat(node, *min, *max, 0, rest, pos, next),
+            single => {
+                pos < self.text.len()
+                    && self.single(single, self.text[pos])
+                    && self.seq(rest, pos + 1, next)
+            }
+        }
+    }
+
+    /// Greedy repetition: take another `node` while allowed, falling back
+    /// to fewer. A repeat that stops consuming text ends there.
+    #[allow(clippy::too_many_arguments)]
+    fn repeat(
+        &self,
+        node: &Node,
+        min: usize,
+        max: Option<usize>,
+        count: usize,
+        rest: &[Node],
+        pos: usize,
+        next: &mut dyn FnMut(usize) -> bool,
+    ) -> bool {
+        if max.is_none_or(|max| count < max)
+            && self.seq(std::slice::from_ref(node), pos, &mut |end| {
+                end != pos && self.repeat(node, min, max, count + 1, rest, end, next)
+            })
+        {
+            return true;
+        }
+        count >= min && self.seq(rest, pos, next)
+    }
+
+    fn single(&self, node: &Node, c: char) -> bool {
+        let fold = |p: char| {
+            if self.ignore_case {
+                p.to_lowercase().next().unwrap_or(p)
+            } else {
+                p
+            }
+        };
+        match node {
+            Node::Char(p) => fold(*p) == c,
+            Node::Any => true,
+            Node::Class { ranges, negated } => {
+                let inside = ranges.iter().any(|&(low, high)| {
+                    (low..=high).contains(&c)
+                        || (self.ignore_case
+                            && c.to_uppercase()
+                                .next()
+                                .is_some_and(|u| (low..=high).contains(&u)))
+                });
+                inside != *negated
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        Pattern::new(pattern).unwrap().is_match(text)
+    }
+
+    #[test]
+    fn matches_log_style_patterns() {
+        assert!(matches("ERROR|WARN", "2024-01-01 WARN disk low"));
+        assert!(!matches("ERROR|WARN", "2024-01-01 INFO ok"));
+        assert!(matches(r"^\d{4}-\d\d-\d\d ", "2024-01-01 INFO ok"));
+        assert!(!matches(r"^\d{4}-", "at 2024-01-01"));
+        assert!(matches(r"status=5\d\d$", "GET / status=503"));
+        assert!(!matches(r"status=5\d\d$", "GET / status=5030"));
+        assert!(matches("(?i)timeout", "Connection TimeOut after 30s"));
+        assert!(matches("[A-Z]+Exception", "java.io.IOException: boom"));
+        assert!(matches(r"user=[^ ]+ denied", "user=bob denied"));
+        assert!(matches("colou?r", "color") && matches("colou?r", "colour"));
+        assert!(matches("a(bc)*d", "ad") && matches("a(bc)*d", "abcbcd"));
+        assert!(!matches("a(bc)+d", "ad"));
+        assert!(matches(r"x{2,3}y", "xxxy") && !matches(r"^x{2,3}y", "xxxxy"));
+        assert!(matches(r"\w+\.rs:\d+", "panicked at src/main.rs:42"));
+        assert!(matches("", "anything"));
+    }
+
+    #[test]
+    fn reports_bad_patterns() {
+        for bad in ["(unclosed", "oops)", "[a-", "*x", r"\q", "a{3,1}", "[z-a]"] {
+            assert!(Pattern::new(bad).is_err(), "{bad}");
+        }
+    }
+}
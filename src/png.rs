@@ -0,0 +1,93 @@
+//! Minimal PNG encoder for screenshots.
+//!
+//! Writes 8-bit truecolor (RGB) images: the signature, an `IHDR` header,
+//! one `IDAT` chunk holding the zlib-compressed scanlines (each unfiltered),
+//! and `IEND`. Colors are kept exactly, unlike the 256-color GIF export.
+
+use std::io::{self, Write};
+
+use crate::deflate::zlib_compress;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+/// Write a `width` x `height` image of packed RGB pixels (3 bytes each,
+/// row by row from the top-left) as a PNG.
+pub fn write_png<W: Write>(out: &mut W, width: u32, height: u32, rgb: &[u8]) -> io::Result<()> {
+    let stride = width as usize * 3;
+    if rgb.len() != stride * height as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "pixel data doesn't match the image size",
+        ));
+    }
+
+    out.write_all(&SIGNATURE)?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend(width.to_be_bytes());
+    header.extend(height.to_be_bytes());
+    // 8 bits per channel, truecolor, deflate, standard filters, no interlace
+    header.extend([8, 2, 0, 0, 0]);
+    chunk(out, b"IHDR", &header)?;
+
+    let mut scanlines = Vec::with_capacity((stride + 1) * height as usize);
+    for row in rgb.chunks(stride.max(1)) {
+        scanlines.push(0); // filter: none
+        scanlines.extend_from_slice(row);
+    }
+    chunk(out, b"IDAT", &zlib_compress(&scanlines))?;
+    chunk(out, b"IEND", &[])
+}
+
+/// Write one chunk: length, type, data, and the CRC of type and data.
+fn chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let crc = crc32(kind.iter().chain(data));
+    out.write_all(&crc.to_be_bytes())
+}
+
+/// CRC-32 (ISO 3309), as PNG chunks use.
+fn crc32<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                0xEDB8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::Image;
+
+    #[test]
+    fn encodes_a_png_the_decoder_reads_back() {
+        let (width, height) = (5u32, 3u32);
+        let rgb: Vec<u8> = (0..width * height * 3).map(|i| (i * 17) as u8).collect();
+        let mut png = Vec::new();
+        write_png(&mut png, width, height, &rgb).unwrap();
+        assert_eq!(&png[..8], &SIGNATURE);
+        // The IEND chunk's well-known CRC
+        assert_eq!(&png[png.len() - 4..], &[0xAE, 0x42, 0x60, 0x82]);
+
+        let image = Image::decode(&png).unwrap();
+        assert_eq!((image.width, image.height), (5, 3));
+        let decoded: Vec<u8> = image
+            .pixels
+            .iter()
+            .flat_map(|&[r, g, b, _]| [r, g, b])
+            .collect();
+        assert_eq!(decoded, rgb);
+
+        assert!(write_png(&mut Vec::new(), 2, 2, &[0; 5]).is_err());
+    }
+}
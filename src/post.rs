@@ -0,0 +1,845 @@
+//! Screen-wide post-processing: filters stacked in a user-chosen order.
+//!
+//! Each filter implements `PostProcessor` and rewrites the finished frame in
+//! place. `PipelineChain` runs them in the order given by `--post` (or
+//! `[post] filters` in the config file), e.g. `--post bloom,crt` blooms the
+//! raw rain and then runs the CRT over it.
+//!
+//! The CRT filter always has a slot in the chain -- where it's listed, or
+//! first otherwise -- because the `c` key can switch it on at any time.
+
+use rand::RngExt;
+
+use crate::buffer::ScreenBuffer;
+use crate::color::Color;
+use crate::color::gradient::{color_to_rgb, lerp_color, scale_color};
+use crate::color::hsl;
+use crate::config::Config;
+use crate::crt::{self, CrtFilter, DEFAULT_VIGNETTE_RADIUS, DEFAULT_VIGNETTE_STRENGTH};
+use crate::effects::corruption::Corruption;
+use crate::event::Event;
+use crate::rng::{self, SimRng, Stream};
+
+/// Every filter name `--post` accepts.
+pub const FILTER_NAMES: &[&str] = &[
+    "crt",
+    "bloom",
+    "vignette",
+    "mirror",
+    "hue-rotate",
+    "vhs",
+    "flash",
+    "glitch",
+];
+
+/// Cells at least this bright (brightest channel) bloom.
+const BLOOM_THRESHOLD: u8 = 150;
+
+/// How far bloom spreads, in cells.
+const BLOOM_RADIUS: i32 = 2;
+
+/// Share of a bright cell's color added to a neighbor's background, before
+/// falling off with distance.
+const BLOOM_STRENGTH: f64 = 0.12;
+
+/// How fast the hue-rotate filter turns the color wheel.
+const HUE_DEGREES_PER_SEC: f64 = 20.0;
+
+/// Largest sideways drift of the VHS tracking wobble, in cells.
+const VHS_WOBBLE_CELLS: f64 = 1.5;
+
+/// How fast the tracking wobble drifts (radians per second).
+const VHS_WOBBLE_SPEED: f64 = 0.7;
+
+/// Phase step between neighboring rows, so the wobble bends down the screen.
+const VHS_WOBBLE_ROW_PHASE: f64 = 0.15;
+
+/// Rows at the bottom torn up by the head-switching band.
+const VHS_HEAD_SWITCH_ROWS: u16 = 2;
+
+/// Share of a cell's color saturation kept (tape colors are washed out).
+const VHS_SATURATION: f64 = 0.55;
+
+/// How much of the color to the left smears into each cell.
+const VHS_BLEED: f64 = 0.45;
+
+/// Average dropouts per second.
+const VHS_DROPOUT_RATE: f64 = 1.5;
+
+/// Characters for the head-switching band noise.
+const VHS_NOISE_CHARS: &[char] = &['~', '-', '=', '_', '.', ':', '^'];
+
+/// Seconds a beat's flash takes to fade out.
+const FLASH_FADE_SECS: f64 = 0.3;
+
+/// How bright the weakest beat flashes (the strongest goes to full white).
+const FLASH_MIN_LEVEL: f64 = 0.35;
+
+/// Allowed brightness for `ColorTransform` (1.0 = unchanged).
+pub const BRIGHTNESS_RANGE: (f64, f64) = (0.1, 2.0);
+
+/// Allowed contrast for `ColorTransform` (1.0 = unchanged).
+pub const CONTRAST_RANGE: (f64, f64) = (0.1, 3.0);
+
+/// Allowed gamma for `ColorTransform` (1.0 = unchanged, above 1.0 lifts
+/// the shadows).
+pub const GAMMA_RANGE: (f64, f64) = (0.2, 5.0);
+
+/// A filter applied to the whole frame after the scene is drawn.
+pub trait PostProcessor {
+    /// Name as written in `--post`.
+    #[allow(dead_code)] // part of the PostProcessor API, used by tests
+    fn name(&self) -> &str;
+
+    /// Rewrite `buffer` in place. `delta_time` is seconds since last frame.
+    fn apply(&mut self, buffer: &mut ScreenBuffer, delta_time: f64);
+
+    /// Adopt a new screen size (drop anything precomputed for the old one).
+    fn resize(&mut self, _width: u16, _height: u16) {}
+
+    /// React to an outside event, such as a beat. Default no-op.
+    fn on_event(&mut self, _event: &Event) {}
+}
+
+/// One step of the chain. The CRT is kept apart so it stays reachable for
+/// runtime toggles.
+enum Stage {
+    Crt,
+    Filter(Box<dyn PostProcessor>),
+}
+
+/// The ordered set of post-processing filters for a run.
+pub struct PipelineChain {
+    crt: CrtFilter,
+    stages: Vec<Stage>,
+}
+
+impl PipelineChain {
+    /// Build the CRT filter and the `config.post_filters` chain for a
+    /// `width` x `height` screen.
+    pub fn from_config(width: u16, height: u16, config: &Config) -> Self {
+        let crt = CrtFilter::new(width, height, config.crt_enabled, config.crt_intensity)
+            .with_curvature(config.crt_curvature)
+            .with_persistence(config.crt_persistence)
+            .with_vignette(
+                config.crt_vignette,
+                config.crt_vignette_radius,
+                config.crt_vignette_strength,
+            )
+            .with_seed(config.seed);
+        Self::new(crt, &config.post_filters, width, height, config.seed)
+    }
+
+    /// Chain `names` around `crt`. Unknown names are skipped with a warning.
+    /// `seed` seeds filters that draw random numbers (for `--seed`).
+    pub fn new(
+        crt: CrtFilter,
+        names: &[String],
+        width: u16,
+        height: u16,
+        seed: Option<u64>,
+    ) -> Self {
+        let mut stages = Vec::new();
+        for name in names {
+            let stage = match name.as_str() {
+                "crt" => Stage::Crt,
+                other => match create_filter(other, width, height, seed) {
+                    Some(filter) => Stage::Filter(filter),
+                    None => {
+                        eprintln!("Unknown post filter '{}', skipping", other);
+                        continue;
+                    }
+                },
+            };
+            stages.push(stage);
+        }
+        if !stages.iter().any(|s| matches!(s, Stage::Crt)) {
+            stages.insert(0, Stage::Crt);
+        }
+        Self { crt, stages }
+    }
+
+    pub fn crt(&self) -> &CrtFilter {
+        &self.crt
+    }
+
+    pub fn crt_mut(&mut self) -> &mut CrtFilter {
+        &mut self.crt
+    }
+
+    /// Filter names in the order they run.
+    #[cfg(test)]
+    fn names(&self) -> Vec<&str> {
+        self.stages
+            .iter()
+            .map(|s| match s {
+                Stage::Crt => self.crt.name(),
+                Stage::Filter(f) => f.name(),
+            })
+            .collect()
+    }
+
+    /// Run every filter over `buffer`, in order.
+    pub fn apply(&mut self, buffer: &mut ScreenBuffer, delta_time: f64) {
+        for stage in &mut self.stages {
+            match stage {
+                Stage::Crt => self.crt.apply(buffer, delta_time),
+                Stage::Filter(f) => f.apply(buffer, delta_time),
+            }
+        }
+    }
+
+    /// Pass an event to every filter.
+    pub fn on_event(&mut self, event: &Event) {
+        for stage in &mut self.stages {
+            if let Stage::Filter(f) = stage {
+                f.on_event(event);
+            }
+        }
+    }
+
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.crt.resize(width, height);
+        for stage in &mut self.stages {
+            if let Stage::Filter(f) = stage {
+                f.resize(width, height);
+            }
+        }
+    }
+}
+
+/// Create a non-CRT filter by name.
+fn create_filter(
+    name: &str,
+    width: u16,
+    height: u16,
+    seed: Option<u64>,
+) -> Option<Box<dyn PostProcessor>> {
+    match name {
+        "bloom" => Some(Box::new(Bloom)),
+        "vignette" => Some(Box::new(Vignette::new(width, height))),
+        "mirror" => Some(Box::new(Mirror)),
+        "hue-rotate" => Some(Box::new(HueRotate { degrees: 0.0 })),
+        "vhs" => Some(Box::new(Vhs::new(seed))),
+        "flash" => Some(Box::new(Flash { level: 0.0 })),
+        "glitch" => Some(Box::new(GlitchBurst::new(width, height, seed))),
+        _ => None,
+    }
+}
+
+/// Bright characters light up the background around them, wider and softer
+/// than the CRT's phosphor glow.
+struct Bloom;
+
+impl PostProcessor for Bloom {
+    fn name(&self) -> &str {
+        "bloom"
+    }
+
+    fn apply(&mut self, buffer: &mut ScreenBuffer, _delta_time: f64) {
+        let (w, h) = (buffer.width() as i32, buffer.height() as i32);
+        // Sources first, so bloom doesn't feed on itself
+        let sources: Vec<(i32, i32, (u8, u8, u8))> = buffer
+            .cells()
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, cell)| {
+                let rgb = color_to_rgb(cell.fg);
+                let bright = rgb.0.max(rgb.1).max(rgb.2);
+                (cell.ch != ' ' && bright >= BLOOM_THRESHOLD).then_some((
+                    idx as i32 % w,
+                    idx as i32 / w,
+                    rgb,
+                ))
+            })
+            .collect();
+
+        for (sx, sy, (r, g, b)) in sources {
+            for dy in -BLOOM_RADIUS..=BLOOM_RADIUS {
+                for dx in -BLOOM_RADIUS..=BLOOM_RADIUS {
+                    let (x, y) = (sx + dx, sy + dy);
+                    let distance = dx.abs().max(dy.abs());
+                    if distance == 0 || x < 0 || y < 0 || x >= w || y >= h {
+                        continue;
+                    }
+                    let weight = BLOOM_STRENGTH / distance as f64;
+                    let Some(&cell) = buffer.get_cell(x as u16, y as u16) else {
+                        continue;
+                    };
+                    let (br, bg, bb) = color_to_rgb(cell.bg);
+                    let add = |base: u8, c: u8| base.saturating_add((c as f64 * weight) as u8);
+                    let bg = Color::Rgb {
+                        r: add(br, r),
+                        g: add(bg, g),
+                        b: add(bb, b),
+                    };
+                    buffer.set_cell(x as u16, y as u16, cell.ch, cell.fg, bg);
+                }
+            }
+        }
+    }
+}
+
+/// Darkens toward the edges: the CRT's vignette on its own, at the default
+/// radius and strength.
+struct Vignette {
+    /// Per-cell brightness, rebuilt when the screen size changes
+    map: Vec<f64>,
+}
+
+impl Vignette {
+    fn new(width: u16, height: u16) -> Self {
+        Self {
+            map: crt::build_vignette(
+                width,
+                height,
+                DEFAULT_VIGNETTE_RADIUS,
+                DEFAULT_VIGNETTE_STRENGTH,
+            ),
+        }
+    }
+}
+
+impl PostProcessor for Vignette {
+    fn name(&self) -> &str {
+        "vignette"
+    }
+
+    fn apply(&mut self, buffer: &mut ScreenBuffer, _delta_time: f64) {
+        if self.map.len() != buffer.cells().len() {
+            *self = Self::new(buffer.width(), buffer.height());
+        }
+        let w = buffer.width() as usize;
+        for (idx, &factor) in self.map.iter().enumerate() {
+            let (x, y) = ((idx % w) as u16, (idx / w) as u16);
+            if let Some(&cell) = buffer.get_cell(x, y) {
+                let fg = scale_color(cell.fg, factor);
+                let bg = scale_color(cell.bg, factor);
+                buffer.set_cell(x, y, cell.ch, fg, bg);
+            }
+        }
+    }
+
+    fn resize(&mut self, width: u16, height: u16) {
+        *self = Self::new(width, height);
+    }
+}
+
+/// The left half reflected onto the right, for a symmetrical screen.
+struct Mirror;
+
+impl PostProcessor for Mirror {
+    fn name(&self) -> &str {
+        "mirror"
+    }
+
+    fn apply(&mut self, buffer: &mut ScreenBuffer, _delta_time: f64) {
+        let w = buffer.width();
+        for y in 0..buffer.height() {
+            for x in w.div_ceil(2)..w {
+                if let Some(&cell) = buffer.get_cell(w - 1 - x, y) {
+                    buffer.set_cell(x, y, cell.ch, cell.fg, cell.bg);
+                }
+            }
+        }
+    }
+}
+
+/// Slowly turns every color around the color wheel.
+struct HueRotate {
+    /// Current rotation in degrees
+    degrees: f64,
+}
+
+impl PostProcessor for HueRotate {
+    fn name(&self) -> &str {
+        "hue-rotate"
+    }
+
+    fn apply(&mut self, buffer: &mut ScreenBuffer, delta_time: f64) {
+        self.degrees = (self.degrees + HUE_DEGREES_PER_SEC * delta_time) % 360.0;
+        let rotate = |color: Color| match color {
+            Color::Rgb { r, g, b } => {
+                let mut c = hsl::rgb_to_hsl(r, g, b);
+                c.h = (c.h + self.degrees) % 360.0;
+                let (r, g, b) = hsl::hsl_to_rgb(&c);
+                Color::Rgb { r, g, b }
+            }
+            other => other,
+        };
+        for y in 0..buffer.height() {
+            for x in 0..buffer.width() {
+                if let Some(&cell) = buffer.get_cell(x, y) {
+                    if cell.ch == ' ' && matches!(cell.bg, Color::Reset) {
+                        continue;
+                    }
+                    buffer.set_cell(x, y, cell.ch, rotate(cell.fg), rotate(cell.bg));
+                }
+            }
+        }
+    }
+}
+
+/// A worn videotape: rows drift sideways on a slow tracking wobble, the
+/// bottom rows tear in the head-switching band, colors are washed out and
+/// smear to the right, and bright dropout streaks flash across now and then.
+struct Vhs {
+    /// Seconds since the filter started, drives the wobble
+    time: f64,
+    /// Live dropouts: (x, y, length, seconds left)
+    dropouts: Vec<(u16, u16, u16, f64)>,
+    rng: SimRng,
+}
+
+impl Vhs {
+    fn new(seed: Option<u64>) -> Self {
+        Self {
+            time: 0.0,
+            dropouts: Vec::new(),
+            rng: rng::make_rng(seed, Stream::Vhs),
+        }
+    }
+
+    /// Spawn and age dropouts for this frame.
+    fn update_dropouts(&mut self, width: u16, height: u16, delta_time: f64) {
+        for dropout in &mut self.dropouts {
+            dropout.3 -= delta_time;
+        }
+        self.dropouts.retain(|d| d.3 > 0.0);
+        if width < 2 || height == 0 {
+            return;
+        }
+        if self.rng.random_range(0.0..1.0) < VHS_DROPOUT_RATE * delta_time {
+            let len = self.rng.random_range(2..=(width / 4).max(2));
+            let x = self.rng.random_range(0..width - len.min(width - 1));
+            let y = self.rng.random_range(0..height);
+            let ttl = self.rng.random_range(0.03..0.12);
+            self.dropouts.push((x, y, len, ttl));
+        }
+    }
+}
+
+/// Shift row `y` of `buffer` right by `offset` cells (left if negative),
+/// leaving blanks where the picture moved away.
+fn shift_row(buffer: &mut ScreenBuffer, y: u16, offset: i32) {
+    let w = buffer.width() as i32;
+    if offset == 0 || y >= buffer.height() {
+        return;
+    }
+    let row: Vec<_> = (0..w)
+        .map(|x| buffer.get_cell(x as u16, y).copied().unwrap_or_default())
+        .collect();
+    for x in 0..w {
+        let src = x - offset;
+        let cell = if (0..w).contains(&src) {
+            row[src as usize]
+        } else {
+            Default::default()
+        };
+        buffer.set_cell(x as u16, y, cell.ch, cell.fg, cell.bg);
+    }
+}
+
+/// Wash out `color` and blend in `left`, the color smeared from the
+/// neighboring cell.
+fn bleed(color: Color, left: Option<Color>) -> Color {
+    let Color::Rgb { r, g, b } = color else {
+        return color;
+    };
+    let mut c = hsl::rgb_to_hsl(r, g, b);
+    c.s *= VHS_SATURATION;
+    let (r, g, b) = hsl::hsl_to_rgb(&c);
+    let muted = Color::Rgb { r, g, b };
+    match left {
+        Some(left) => lerp_color(muted, left, VHS_BLEED as f32),
+        None => muted,
+    }
+}
+
+impl PostProcessor for Vhs {
+    fn name(&self) -> &str {
+        "vhs"
+    }
+
+    fn apply(&mut self, buffer: &mut ScreenBuffer, delta_time: f64) {
+        let (w, h) = (buffer.width(), buffer.height());
+        self.time += delta_time;
+        self.update_dropouts(w, h, delta_time);
+
+        // Color bleed: each row smears left to right
+        for y in 0..h {
+            let mut left: Option<Color> = None;
+            for x in 0..w {
+                let Some(&cell) = buffer.get_cell(x, y) else {
+                    continue;
+                };
+                if cell.ch == ' ' {
+                    left = None;
+                    continue;
+                }
+                let fg = bleed(cell.fg, left);
+                buffer.set_cell(x, y, cell.ch, fg, cell.bg);
+                left = Some(fg);
+            }
+        }
+
+        // Tracking wobble, above the head-switching band
+        let band_top = h.saturating_sub(VHS_HEAD_SWITCH_ROWS);
+        for y in 0..band_top {
+            let phase = self.time * VHS_WOBBLE_SPEED + y as f64 * VHS_WOBBLE_ROW_PHASE;
+            shift_row(buffer, y, (VHS_WOBBLE_CELLS * phase.sin()).round() as i32);
+        }
+
+        // Head-switching band: torn sideways and full of noise
+        for y in band_top..h {
+            let tear = self.rng.random_range(2..=6);
+            shift_row(buffer, y, tear);
+            for x in 0..w {
+                if self.rng.random_range(0.0..1.0) < 0.35 {
+                    let ch = VHS_NOISE_CHARS[self.rng.random_range(0..VHS_NOISE_CHARS.len())];
+                    let v = self.rng.random_range(60..160);
+                    buffer.set_cell(x, y, ch, Color::Rgb { r: v, g: v, b: v }, Color::Reset);
+                }
+            }
+        }
+
+        // Dropouts: short bright streaks where the tape lost signal
+        for &(x, y, len, _) in &self.dropouts {
+            for dx in x..x.saturating_add(len).min(w) {
+                if let Some(&cell) = buffer.get_cell(dx, y) {
+                    let fg = Color::Rgb {
+                        r: 235,
+                        g: 235,
+                        b: 235,
+                    };
+                    buffer.set_cell(dx, y, '─', fg, cell.bg);
+                }
+            }
+        }
+    }
+
+    fn resize(&mut self, _width: u16, _height: u16) {
+        self.dropouts.clear();
+    }
+}
+
+/// Lightning on each beat: the whole frame flares toward white, then
+/// fades. Idle until an event arrives.
+struct Flash {
+    /// Current brightness of the flare (0.0 = none)
+    level: f64,
+}
+
+impl PostProcessor for Flash {
+    fn name(&self) -> &str {
+        "flash"
+    }
+
+    fn apply(&mut self, buffer: &mut ScreenBuffer, delta_time: f64) {
+        if self.level <= 0.0 {
+            return;
+        }
+        let white = Color::Rgb {
+            r: 255,
+            g: 255,
+            b: 255,
+        };
+        let t = self.level as f32;
+        for y in 0..buffer.height() {
+            for x in 0..buffer.width() {
+                if let Some(&cell) = buffer.get_cell(x, y) {
+                    // The sky lights up too, less than the glyphs
+                    let fg = lerp_color(cell.fg, white, t);
+                    let bg = lerp_color(cell.bg, white, t * 0.5);
+                    buffer.set_cell(x, y, cell.ch, fg, bg);
+                }
+            }
+        }
+        self.level -= delta_time / FLASH_FADE_SECS;
+    }
+
+    fn on_event(&mut self, event: &Event) {
+        let Event::Beat { strength } = *event else {
+            return;
+        };
+        let level = FLASH_MIN_LEVEL + (1.0 - FLASH_MIN_LEVEL) * strength;
+        self.level = self.level.max(level);
+    }
+}
+
+/// Bursts of glitch corruption (tears, noise blocks, color splits) on each
+/// beat, over any effect. Idle until an event arrives.
+struct GlitchBurst {
+    corruption: Corruption,
+    rng: SimRng,
+}
+
+impl GlitchBurst {
+    fn new(width: u16, height: u16, seed: Option<u64>) -> Self {
+        Self {
+            corruption: Corruption::new(width, height),
+            rng: rng::make_rng(seed, Stream::Glitch),
+        }
+    }
+}
+
+impl PostProcessor for GlitchBurst {
+    fn name(&self) -> &str {
+        "glitch"
+    }
+
+    fn apply(&mut self, buffer: &mut ScreenBuffer, delta_time: f64) {
+        self.corruption.update(delta_time, &mut self.rng);
+        self.corruption.apply(buffer);
+    }
+
+    fn resize(&mut self, width: u16, height: u16) {
+        self.corruption.resize(width, height);
+    }
+
+    fn on_event(&mut self, event: &Event) {
+        let Event::Beat { strength } = *event else {
+            return;
+        };
+        for _ in 0..2 + (strength * 4.0).round() as usize {
+            self.corruption.spawn(&mut self.rng, 1.0 + strength);
+        }
+    }
+}
+
+/// Brightness, contrast, and gamma as one per-channel color mapping, for
+/// projectors and washed-out terminals. The mapping is baked into a lookup
+/// table whenever a setting changes, so applying it is one index per
+/// channel. Runs last, just before the frame is flushed.
+pub struct ColorTransform {
+    brightness: f64,
+    contrast: f64,
+    gamma: f64,
+    lut: [u8; 256],
+    /// Whether the table maps every level to itself
+    identity: bool,
+}
+
+impl ColorTransform {
+    /// Settings are clamped to `BRIGHTNESS_RANGE`, `CONTRAST_RANGE`, and
+    /// `GAMMA_RANGE`.
+    pub fn new(brightness: f64, contrast: f64, gamma: f64) -> Self {
+        let mut transform = Self {
+            brightness: 1.0,
+            contrast: 1.0,
+            gamma: 1.0,
+            lut: [0; 256],
+            identity: true,
+        };
+        transform.set(brightness, contrast, gamma);
+        transform
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.brightness, config.contrast, config.gamma)
+    }
+
+    /// Change all three settings and rebuild the table.
+    pub fn set(&mut self, brightness: f64, contrast: f64, gamma: f64) {
+        self.brightness = brightness.clamp(BRIGHTNESS_RANGE.0, BRIGHTNESS_RANGE.1);
+        self.contrast = contrast.clamp(CONTRAST_RANGE.0, CONTRAST_RANGE.1);
+        self.gamma = gamma.clamp(GAMMA_RANGE.0, GAMMA_RANGE.1);
+        for (i, out) in self.lut.iter_mut().enumerate() {
+            let v = (i as f64 / 255.0).powf(1.0 / self.gamma);
+            let v = ((v - 0.5) * self.contrast + 0.5) * self.brightness;
+            *out = (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+        self.identity = self.lut.iter().enumerate().all(|(i, &v)| v as usize == i);
+    }
+
+    pub fn brightness(&self) -> f64 {
+        self.brightness
+    }
+
+    pub fn contrast(&self) -> f64 {
+        self.contrast
+    }
+
+    pub fn gamma(&self) -> f64 {
+        self.gamma
+    }
+
+    /// Whether applying would change nothing. Judged by the table rather
+    /// than the settings, so stepping a control away and back (which may
+    /// not land on exactly 1.0) still counts.
+    pub fn is_identity(&self) -> bool {
+        self.identity
+    }
+
+    fn map(&self, color: Color) -> Color {
+        match color {
+            Color::Rgb { r, g, b } => Color::Rgb {
+                r: self.lut[r as usize],
+                g: self.lut[g as usize],
+                b: self.lut[b as usize],
+            },
+            Color::Reset => Color::Reset,
+        }
+    }
+}
+
+impl PostProcessor for ColorTransform {
+    fn name(&self) -> &str {
+        "color-transform"
+    }
+
+    fn apply(&mut self, buffer: &mut ScreenBuffer, _delta_time: f64) {
+        if self.is_identity() {
+            return;
+        }
+        for y in 0..buffer.height() {
+            for x in 0..buffer.width() {
+                if let Some(&cell) = buffer.get_cell(x, y) {
+                    if cell.ch == ' ' && matches!(cell.bg, Color::Reset) {
+                        continue;
+                    }
+                    buffer.set_cell(x, y, cell.ch, self.map(cell.fg), self.map(cell.bg));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(names: &[&str]) -> PipelineChain {
+        let names: Vec<String> = names.iter().map(|n| n.to_string()).collect();
+        PipelineChain::new(CrtFilter::new(10, 4, false, 0.7), &names, 10, 4, Some(7))
+    }
+
+    #[test]
+    fn chain_keeps_order_and_always_has_crt() {
+        assert_eq!(
+            chain(&["bloom", "crt", "mirror"]).names(),
+            ["bloom", "crt", "mirror"]
+        );
+        assert_eq!(chain(&["hue-rotate"]).names(), ["crt", "hue-rotate"]);
+        assert_eq!(chain(&["bogus", "vignette"]).names(), ["crt", "vignette"]);
+        assert_eq!(chain(&[]).names(), ["crt"]);
+    }
+
+    #[test]
+    fn filters_rewrite_the_frame() {
+        let green = Color::Rgb { r: 0, g: 220, b: 0 };
+        let frame = || {
+            let mut buffer = ScreenBuffer::new(10, 4);
+            buffer.set_cell(1, 1, 'A', green, Color::Reset);
+            buffer
+        };
+
+        let mut buffer = frame();
+        chain(&["mirror"]).apply(&mut buffer, 0.1);
+        assert_eq!(buffer.get_cell(8, 1).unwrap().ch, 'A');
+
+        let mut buffer = frame();
+        chain(&["bloom"]).apply(&mut buffer, 0.1);
+        let (_, g, _) = color_to_rgb(buffer.get_cell(3, 1).unwrap().bg);
+        assert!(g > 0, "bloom should reach two cells out");
+
+        let mut buffer = frame();
+        chain(&["hue-rotate"]).apply(&mut buffer, 3.0);
+        // 60 degrees on: green turns toward cyan
+        let (r, _, b) = color_to_rgb(buffer.get_cell(1, 1).unwrap().fg);
+        assert!(b > 100 && r < 20, "hue should have moved toward cyan");
+
+        let mut buffer = frame();
+        buffer.set_cell(0, 0, 'B', green, Color::Reset);
+        chain(&["vignette"]).apply(&mut buffer, 0.1);
+        let (_, g, _) = color_to_rgb(buffer.get_cell(0, 0).unwrap().fg);
+        assert!(g < 220, "corner cell should be darkened");
+    }
+
+    #[test]
+    fn beat_filters_wait_for_an_event_then_fade() {
+        let green = Color::Rgb { r: 0, g: 120, b: 0 };
+        let frame = || {
+            let mut buffer = ScreenBuffer::new(10, 4);
+            for x in 0..10 {
+                buffer.set_cell(x, 1, 'A', green, Color::Reset);
+            }
+            buffer
+        };
+        let mut filters = chain(&["flash", "glitch"]);
+        let mut buffer = frame();
+        filters.apply(&mut buffer, 0.1);
+        assert!(buffer.cells() == frame().cells(), "no event, no change");
+
+        filters.on_event(&Event::Beat { strength: 1.0 });
+        let mut buffer = frame();
+        filters.apply(&mut buffer, 0.1);
+        assert!(buffer.cells() != frame().cells());
+        let (r, _, _) = color_to_rgb(buffer.get_cell(0, 3).unwrap().bg);
+        assert!(r > 100, "the flash lights up the background");
+
+        // Flash and glitches are both gone a second later
+        for _ in 0..10 {
+            filters.apply(&mut frame(), 0.1);
+        }
+        let mut buffer = frame();
+        filters.apply(&mut buffer, 0.1);
+        assert!(buffer.cells() == frame().cells());
+    }
+
+    #[test]
+    fn vhs_tears_the_bottom_rows() {
+        let green = Color::Rgb { r: 0, g: 220, b: 0 };
+        let mut buffer = ScreenBuffer::new(10, 4);
+        for y in 0..4 {
+            for x in 0..10 {
+                buffer.set_cell(x, y, 'A', green, Color::Reset);
+            }
+        }
+        chain(&["vhs"]).apply(&mut buffer, 0.1);
+        // Head-switching tear shifts the bottom row at least two cells
+        assert_ne!(buffer.get_cell(0, 3).unwrap().ch, 'A');
+        assert_ne!(buffer.get_cell(1, 3).unwrap().ch, 'A');
+        // Colors are washed out
+        let (r, g, _) = color_to_rgb(buffer.get_cell(5, 0).unwrap().fg);
+        assert!(g < 220 && r > 0, "colors should be desaturated");
+    }
+
+    #[test]
+    fn color_transform_maps_each_channel_through_its_table() {
+        let gray = |v| Color::Rgb { r: v, g: v, b: v };
+        let frame = || {
+            let mut buffer = ScreenBuffer::new(4, 1);
+            buffer.set_cell(0, 0, 'A', gray(200), gray(40));
+            buffer
+        };
+        let channel = |buffer: &ScreenBuffer| {
+            let cell = buffer.get_cell(0, 0).unwrap();
+            (color_to_rgb(cell.fg).0, color_to_rgb(cell.bg).0)
+        };
+
+        let mut buffer = frame();
+        ColorTransform::new(0.5, 1.0, 1.0).apply(&mut buffer, 0.1);
+        assert_eq!(channel(&buffer), (100, 20));
+
+        // Contrast pushes away from mid-gray, gamma lifts the shadows
+        let mut buffer = frame();
+        ColorTransform::new(1.0, 2.0, 1.0).apply(&mut buffer, 0.1);
+        assert_eq!(channel(&buffer), (255, 0));
+        let mut buffer = frame();
+        ColorTransform::new(1.0, 1.0, 2.0).apply(&mut buffer, 0.1);
+        let (fg, bg) = channel(&buffer);
+        assert!(fg > 200 && bg > 90, "{fg} {bg}");
+
+        // The terminal's own colors and blank cells are left alone
+        assert_eq!(buffer.get_cell(1, 0).unwrap().bg, Color::Reset);
+        let mut identity = ColorTransform::new(1.0, 1.0, 1.0);
+        assert!(identity.is_identity());
+        for step in [0.05, 0.05, 0.05, -0.05, -0.05, -0.05] {
+            let (b, c) = (identity.brightness() + step, identity.contrast() - step);
+            identity.set(b, c, identity.gamma());
+        }
+        assert!(identity.is_identity());
+        assert_eq!(ColorTransform::new(9.0, 0.0, 1.0).brightness(), 2.0);
+    }
+}
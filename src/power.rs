@@ -0,0 +1,356 @@
+//! Power saving (`--power-saver`): a lighter animation on battery or when
+//! nobody is around.
+//!
+//! `PowerSaver` decides when the low-power profile applies; the main loop
+//! applies it (fewer frames, half the columns, no post-processing). The
+//! power source is probed every `PROBE_INTERVAL`, from sysfs on Linux,
+//! `pmset` on macOS, and `GetSystemPowerStatus` on Windows. Elsewhere, or
+//! on machines without a battery, only inactivity counts.
+//!
+//! The power source has to read the same `SETTLE_PROBES` times in a row
+//! before it counts, so a cable that's jiggled, or a probe that briefly
+//! fails, doesn't flip the profile back and forth. Inactivity ends the
+//! moment a key is pressed.
+
+use std::time::{Duration, Instant};
+
+/// How often the power source is read.
+const PROBE_INTERVAL: Duration = Duration::from_secs(10);
+/// Matching probes in a row before a power source change counts.
+const SETTLE_PROBES: u32 = 2;
+/// Inactivity before the profile applies, unless `--power-saver-idle` says otherwise.
+pub const DEFAULT_IDLE: f64 = 600.0;
+
+/// Frame rate cap while saving power.
+pub const SAVER_FPS: u32 = 15;
+/// Density multiplier while saving power.
+pub const SAVER_DENSITY: f64 = 0.5;
+
+/// When to use the low-power profile.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum PowerSaverMode {
+    /// On battery power, or after a stretch without input
+    #[default]
+    Auto,
+    /// Always
+    On,
+    /// Never
+    Off,
+}
+
+/// Where the machine is drawing power from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerSource {
+    Ac,
+    Battery,
+}
+
+/// Why the low-power profile is in effect.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SaverReason {
+    Battery,
+    Idle,
+    /// `--power-saver on`
+    Always,
+}
+
+impl SaverReason {
+    pub fn label(self) -> &'static str {
+        match self {
+            SaverReason::Battery => "on battery",
+            SaverReason::Idle => "idle",
+            SaverReason::Always => "on",
+        }
+    }
+}
+
+/// The frame rate and density the low-power profile replaced, put back
+/// when it ends.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NormalProfile {
+    pub fps: u32,
+    /// The config's density, for effects started later
+    pub density: f64,
+    /// The running effect's density, which the density keys may have moved
+    pub effect_density: f64,
+}
+
+/// Tracks the power source and input to decide when to save power.
+pub struct PowerSaver {
+    mode: PowerSaverMode,
+    idle_after: Duration,
+    last_input: Instant,
+    last_probe: Option<Instant>,
+    /// The settled power source
+    on_battery: bool,
+    /// Probes in a row that disagreed with `on_battery`
+    disagreeing: u32,
+    active: Option<SaverReason>,
+    /// What the main loop had before it applied the profile; `Some` while
+    /// the profile is applied
+    normal: Option<NormalProfile>,
+}
+
+impl PowerSaver {
+    pub fn new(mode: PowerSaverMode, idle_after: Duration, now: Instant) -> Self {
+        Self {
+            mode,
+            idle_after,
+            last_input: now,
+            last_probe: None,
+            on_battery: false,
+            disagreeing: 0,
+            active: (mode == PowerSaverMode::On).then_some(SaverReason::Always),
+            normal: None,
+        }
+    }
+
+    /// Why the profile applies, or `None` when it doesn't.
+    pub fn active(&self) -> Option<SaverReason> {
+        self.active
+    }
+
+    /// Whether the profile is applied (between `enter` and `leave`).
+    pub fn applied(&self) -> bool {
+        self.normal.is_some()
+    }
+
+    /// Note that the profile is now applied, over `normal`.
+    pub fn enter(&mut self, normal: NormalProfile) {
+        self.normal = Some(normal);
+    }
+
+    /// Note that the profile is lifted, returning what it replaced.
+    pub fn leave(&mut self) -> Option<NormalProfile> {
+        self.normal.take()
+    }
+
+    /// Note user input.
+    pub fn input(&mut self, now: Instant) {
+        self.last_input = now;
+    }
+
+    /// Re-check the conditions, probing the power source when one is due.
+    /// Returns the new state when it changed.
+    pub fn update(&mut self, now: Instant) -> Option<Option<SaverReason>> {
+        self.update_with(now, power_source)
+    }
+
+    fn update_with(
+        &mut self,
+        now: Instant,
+        probe: impl FnOnce() -> Option<PowerSource>,
+    ) -> Option<Option<SaverReason>> {
+        if self.mode != PowerSaverMode::Auto {
+            return None;
+        }
+        if self
+            .last_probe
+            .is_none_or(|last| now.duration_since(last) >= PROBE_INTERVAL)
+        {
+            self.last_probe = Some(now);
+            // A failed probe counts as mains, the safe side
+            let battery = probe() == Some(PowerSource::Battery);
+            if battery == self.on_battery {
+                self.disagreeing = 0;
+            } else {
+                self.disagreeing += 1;
+                if self.disagreeing >= SETTLE_PROBES {
+                    self.on_battery = battery;
+                    self.disagreeing = 0;
+                }
+            }
+        }
+
+        let reason = if self.on_battery {
+            Some(SaverReason::Battery)
+        } else if now.duration_since(self.last_input) >= self.idle_after {
+            Some(SaverReason::Idle)
+        } else {
+            None
+        };
+        if reason == self.active {
+            return None;
+        }
+        self.active = reason;
+        Some(reason)
+    }
+}
+
+/// Read the power source, or `None` if it can't be told.
+#[cfg(target_os = "linux")]
+pub fn power_source() -> Option<PowerSource> {
+    let read = |path: std::path::PathBuf| {
+        std::fs::read_to_string(path)
+            .ok()
+            .map(|s| s.trim().to_string())
+    };
+    let supplies: Vec<Supply> = std::fs::read_dir("/sys/class/power_supply")
+        .ok()?
+        .flatten()
+        .map(|entry| {
+            let dir = entry.path();
+            Supply {
+                kind: read(dir.join("type")).unwrap_or_default(),
+                online: read(dir.join("online")),
+                status: read(dir.join("status")),
+            }
+        })
+        .collect();
+    classify(&supplies)
+}
+
+#[cfg(target_os = "macos")]
+pub fn power_source() -> Option<PowerSource> {
+    let output = std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    if text.contains("'Battery Power'") {
+        Some(PowerSource::Battery)
+    } else if text.contains("'AC Power'") {
+        Some(PowerSource::Ac)
+    } else {
+        None
+    }
+}
+
+#[cfg(windows)]
+pub fn power_source() -> Option<PowerSource> {
+    #[repr(C)]
+    #[derive(Default)]
+    #[allow(dead_code)]
+    struct SystemPowerStatus {
+        ac_line_status: u8,
+        battery_flag: u8,
+        battery_life_percent: u8,
+        system_status_flag: u8,
+        battery_life_time: u32,
+        battery_full_life_time: u32,
+    }
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn GetSystemPowerStatus(status: *mut SystemPowerStatus) -> i32;
+    }
+
+    let mut status = SystemPowerStatus::default();
+    // SAFETY: `status` is a properly laid out, writable SYSTEM_POWER_STATUS
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return None;
+    }
+    match status.ac_line_status {
+        0 => Some(PowerSource::Battery),
+        1 => Some(PowerSource::Ac),
+        _ => None,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn power_source() -> Option<PowerSource> {
+    None
+}
+
+/// One entry of `/sys/class/power_supply`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+struct Supply {
+    /// `Mains`, `USB`, `Battery`, ...
+    kind: String,
+    /// `1` when an external supply is plugged in
+    online: Option<String>,
+    /// A battery's `Charging`, `Discharging`, `Full`, ...
+    status: Option<String>,
+}
+
+/// Mains if any external supply is online; battery if there's a battery and
+/// it's discharging (or nothing external is online). Desktops without a
+/// battery are always mains.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn classify(supplies: &[Supply]) -> Option<PowerSource> {
+    let external: Vec<&Supply> = supplies.iter().filter(|s| s.kind != "Battery").collect();
+    if external.iter().any(|s| s.online.as_deref() == Some("1")) {
+        return Some(PowerSource::Ac);
+    }
+    let batteries: Vec<&Supply> = supplies.iter().filter(|s| s.kind == "Battery").collect();
+    if batteries.is_empty() {
+        return (!external.is_empty()).then_some(PowerSource::Ac);
+    }
+    let discharging = batteries
+        .iter()
+        .any(|s| s.status.as_deref() == Some("Discharging"));
+    if discharging || !external.is_empty() {
+        Some(PowerSource::Battery)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn battery_must_settle_and_input_ends_idle() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut saver = PowerSaver::new(PowerSaverMode::Auto, Duration::from_secs(60), start);
+        let battery = || Some(PowerSource::Battery);
+        let ac = || Some(PowerSource::Ac);
+
+        assert_eq!(saver.update_with(at(0), ac), None);
+        // One battery reading isn't enough; the next one settles it
+        assert_eq!(saver.update_with(at(10), battery), None);
+        assert_eq!(saver.update_with(at(15), ac), None);
+        assert_eq!(
+            saver.update_with(at(20), battery),
+            Some(Some(SaverReason::Battery))
+        );
+        // Back on mains, after the input has gone quiet: idle takes over
+        saver.input(at(20));
+        assert_eq!(saver.update_with(at(30), ac), None);
+        assert_eq!(saver.update_with(at(40), ac), Some(None));
+        assert_eq!(saver.update_with(at(50), ac), None);
+        assert_eq!(saver.update_with(at(80), ac), Some(Some(SaverReason::Idle)));
+        saver.input(at(81));
+        assert_eq!(saver.update_with(at(81), ac), Some(None));
+
+        let mut always = PowerSaver::new(PowerSaverMode::On, Duration::from_secs(60), start);
+        assert_eq!(always.active(), Some(SaverReason::Always));
+        assert_eq!(always.update_with(at(1000), ac), None);
+
+        // The profile it replaced comes back once, as it was
+        let normal = NormalProfile {
+            fps: 60,
+            density: 1.5,
+            effect_density: 2.0,
+        };
+        assert!(!always.applied());
+        always.enter(normal);
+        assert!(always.applied());
+        assert_eq!(always.leave(), Some(normal));
+        assert_eq!(always.leave(), None);
+    }
+
+    #[test]
+    fn power_supplies_are_classified() {
+        let supply = |kind: &str, online: Option<&str>, status: Option<&str>| Supply {
+            kind: kind.to_string(),
+            online: online.map(String::from),
+            status: status.map(String::from),
+        };
+        let laptop = |online, status| {
+            classify(&[
+                supply("Mains", Some(online), None),
+                supply("Battery", None, Some(status)),
+            ])
+        };
+        assert_eq!(laptop("1", "Charging"), Some(PowerSource::Ac));
+        assert_eq!(laptop("0", "Discharging"), Some(PowerSource::Battery));
+        assert_eq!(laptop("0", "Unknown"), Some(PowerSource::Battery));
+        assert_eq!(
+            classify(&[supply("Mains", Some("1"), None)]),
+            Some(PowerSource::Ac)
+        );
+        assert_eq!(classify(&[]), None);
+    }
+}
@@ -0,0 +1,206 @@
+//! Terminal output on its own thread (`--render-thread`).
+//!
+//! Normally the main loop diffs each frame and writes it to the terminal
+//! itself, so a terminal that reads slowly (a remote SSH session, say)
+//! holds up the animation and the keyboard along with it. A `Presenter`
+//! hands frames to a writer thread instead and goes straight back to work.
+//!
+//! Frames are double-buffered: the main loop composes into one buffer
+//! while the writer draws the other, and `present` swaps them. The writer
+//! keeps its own copy of what's on screen to diff against. If it's still
+//! busy when the next frame arrives, the frame waiting for it is replaced,
+//! so a slow terminal skips frames instead of falling further behind.
+//! A frame of a new size or position (a resize, or the region moving) makes
+//! the writer redraw everything, so frames from either side of a resize
+//! never get diffed against each other.
+
+use std::io::{self, Write};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+use crate::buffer::{FlushStats, ScreenBuffer};
+
+#[derive(Default)]
+struct Slot {
+    /// The newest frame, not yet taken by the writer
+    pending: Option<ScreenBuffer>,
+    /// A frame the writer is done with, for the main loop to compose into
+    spare: Option<ScreenBuffer>,
+    last_flush: FlushStats,
+    /// Frames replaced before the writer got to them
+    skipped: u64,
+    /// Why the writer stopped
+    error: Option<io::Error>,
+    closed: bool,
+}
+
+#[derive(Default)]
+struct Shared {
+    slot: Mutex<Slot>,
+    changed: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Slot> {
+        self.slot.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Hands frames to a writer thread.
+pub struct Presenter {
+    shared: Arc<Shared>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl Presenter {
+    /// Start a writer thread drawing to `out`.
+    pub fn spawn<W: Write + Send + 'static>(out: W) -> Self {
+        let shared = Arc::new(Shared::default());
+        let writer = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || write_frames(&shared, out))
+        };
+        Self {
+            shared,
+            writer: Some(writer),
+        }
+    }
+
+    /// Queue `buffer`'s frame for drawing. `buffer` gets a recycled frame
+    /// of the same size and position to compose the next one into; it
+    /// isn't cleared. Fails once the writer has hit an error.
+    pub fn present(&mut self, buffer: &mut ScreenBuffer) -> io::Result<()> {
+        let mut slot = self.shared.lock();
+        if let Some(e) = slot.error.take() {
+            return Err(e);
+        }
+        let replaced = slot.pending.take();
+        if replaced.is_some() {
+            slot.skipped += 1;
+        }
+        let mut frame = replaced
+            .or_else(|| slot.spare.take())
+            .unwrap_or_else(|| ScreenBuffer::new(buffer.width(), buffer.height()));
+        std::mem::swap(buffer, &mut frame);
+        buffer.set_background(frame.background());
+        if buffer.region() != frame.region() {
+            buffer.set_region(frame.region());
+        }
+        slot.pending = Some(frame);
+        self.shared.changed.notify_one();
+        Ok(())
+    }
+
+    /// What the writer's last flush sent.
+    pub fn last_flush(&self) -> FlushStats {
+        self.shared.lock().last_flush
+    }
+
+    /// Frames skipped because the writer was still busy.
+    pub fn skipped(&self) -> u64 {
+        self.shared.lock().skipped
+    }
+}
+
+impl Drop for Presenter {
+    /// Let the writer draw the last frame, then stop it. After a panic the
+    /// terminal has already been restored, so the frame is dropped instead.
+    fn drop(&mut self) {
+        let mut slot = self.shared.lock();
+        slot.closed = true;
+        if thread::panicking() {
+            slot.pending = None;
+        }
+        drop(slot);
+        self.shared.changed.notify_one();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+fn write_frames(shared: &Shared, mut out: impl Write) {
+    // What's on screen, for diffing
+    let mut screen = ScreenBuffer::new(0, 0);
+    loop {
+        let frame = {
+            let mut slot = shared.lock();
+            while slot.pending.is_none() && !slot.closed {
+                slot = shared.changed.wait(slot).unwrap_or_else(|e| e.into_inner());
+            }
+            match slot.pending.take() {
+                Some(frame) => frame,
+                None => return,
+            }
+        };
+
+        if screen.region() != frame.region() {
+            screen.set_region(frame.region());
+        }
+        screen.load_frame(&frame);
+        let result = screen.flush_to(&mut out);
+
+        let mut slot = shared.lock();
+        slot.spare = Some(frame);
+        match result {
+            Ok(()) => slot.last_flush = screen.last_flush(),
+            Err(e) => {
+                slot.error = Some(e);
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::Region;
+    use crate::color::Color;
+
+    /// A writer that hands everything it gets to the test.
+    struct Capture(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn frames_are_drawn_on_the_writer_thread_and_recycled() {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let mut presenter = Presenter::spawn(Capture(Arc::clone(&output)));
+        let mut buffer = ScreenBuffer::new(4, 2);
+        let green = Color::Rgb { r: 0, g: 255, b: 0 };
+
+        for ch in ['a', 'b', 'c'] {
+            buffer.clear();
+            buffer.set_cell(1, 1, ch, green, Color::Reset);
+            presenter.present(&mut buffer).unwrap();
+            assert_eq!((buffer.width(), buffer.height()), (4, 2));
+        }
+        // A resize mid-stream: the next frame is drawn in full at its size
+        buffer.set_region(Region {
+            x: 2,
+            y: 0,
+            width: 3,
+            height: 1,
+        });
+        buffer.set_cell(0, 0, 'd', Color::Reset, Color::Reset);
+        presenter.present(&mut buffer).unwrap();
+        assert_eq!(buffer.region().x, 2);
+        drop(presenter);
+
+        let text = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+        // The last frame is always drawn; earlier ones may have been skipped
+        // (colors are only sent when they change)
+        let last = "\x1b[1;3H\x1b[39m\x1b[49md\x1b[1;4H \x1b[1;5H ";
+        assert!(text.ends_with(last));
+    }
+}
@@ -3,23 +3,158 @@
 //! The original Matrix films use half-width katakana characters mixed with
 //! Latin letters and digits. We define character pools that effects can
 //! draw from randomly.
+//!
+//! Besides the built-in sets, users can define their own (`--charset-chars`,
+//! `--charset-file`, or the config's `[charsets]` table). Those are
+//! registered once at startup and then behave like any built-in name.
+//!
+//! Names can also be mixed on the fly: `matrix+digits+latin` rains the
+//! union of those sets, and `matrix+digits:3` draws digits three times as
+//! often as it otherwise would.
+
+use std::sync::{Arc, RwLock};
+
+use rand::{Rng, RngExt};
+
+/// The built-in character set names, in cycle order.
+const BUILTIN_NAMES: &[&str] = &[
+    "matrix", "ascii", "binary", "digits", "katakana", "latin", "greek", "cyrillic", "hebrew",
+    "runes", "box", "blocks",
+];
+
+/// User-defined charsets, in registration order.
+static CUSTOM: RwLock<Vec<(&'static str, CharacterPool)>> = RwLock::new(Vec::new());
+
+/// Returns the list of available character set names: the built-ins, then
+/// any user-defined ones.
+pub fn charset_names() -> Vec<&'static str> {
+    let mut names = BUILTIN_NAMES.to_vec();
+    names.extend(custom_charsets().iter().map(|(name, _)| *name));
+    names
+}
+
+/// Whether `name` is one of the built-in charsets.
+pub fn is_builtin_charset(name: &str) -> bool {
+    BUILTIN_NAMES.contains(&name)
+}
+
+/// Largest per-part weight in a charset mix.
+pub const MAX_MIX_WEIGHT: u32 = 20;
+
+/// Split a charset name into its mixed parts and their weights:
+/// `matrix+digits:3` is `[("matrix", 1), ("digits", 3)]`, and a plain name
+/// is a single part. Part names aren't checked here.
+pub fn charset_parts(name: &str) -> Result<Vec<(&str, u32)>, String> {
+    name.split('+')
+        .map(|part| {
+            let (part_name, weight) = match part.split_once(':') {
+                Some((n, w)) => match w.trim().parse::<u32>() {
+                    Ok(w) if (1..=MAX_MIX_WEIGHT).contains(&w) => (n, w),
+                    _ => {
+                        return Err(format!(
+                            "bad weight '{}' in charset '{}' (expected 1 to {})",
+                            w, name, MAX_MIX_WEIGHT
+                        ));
+                    }
+                },
+                None => (part, 1),
+            };
+            match part_name.trim() {
+                "" => Err(format!("empty part in charset '{}'", name)),
+                n => Ok((n, weight)),
+            }
+        })
+        .collect()
+}
+
+/// Whether `name` is a known charset or a valid mix of known charsets.
+pub fn is_charset_name(name: &str) -> bool {
+    let names = charset_names();
+    charset_parts(name).is_ok_and(|parts| parts.iter().all(|(n, _)| names.contains(n)))
+}
 
-use rand::Rng;
+/// Make a user-defined pool available under `name` (replacing any earlier
+/// one of that name). Built-in names can't be redefined, and names can't
+/// use the mixing characters `+` and `:`.
+pub fn register_charset(name: &str, pool: CharacterPool) -> Result<(), String> {
+    if name.contains(['+', ':']) {
+        return Err(format!("charset name '{}' can't contain '+' or ':'", name));
+    }
+    if is_builtin_charset(name) {
+        return Err(format!(
+            "charset '{}' is built in and can't be redefined",
+            name
+        ));
+    }
+    let mut custom = CUSTOM.write().unwrap_or_else(|e| e.into_inner());
+    match custom.iter_mut().find(|(n, _)| *n == name) {
+        Some(entry) => entry.1 = pool,
+        // Registered once at startup, so leaking the name is fine
+        None => custom.push((Box::leak(name.into()), pool)),
+    }
+    Ok(())
+}
+
+/// The user-defined charsets registered so far.
+pub fn custom_charsets() -> Vec<(&'static str, CharacterPool)> {
+    CUSTOM.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
 
-/// Returns the list of available character set names.
-pub fn charset_names() -> &'static [&'static str] {
-    &["matrix", "ascii", "binary", "digits", "katakana", "latin"]
+/// Get the charset name after the given name in the cycle (wraps around).
+pub fn next_charset_name(current: &str) -> &'static str {
+    let names = charset_names();
+    let current_idx = names.iter().position(|&n| n == current).unwrap_or(0);
+    names[(current_idx + 1) % names.len()]
 }
 
 /// Look up a character pool by name. Returns matrix if the name is unknown.
+///
+/// The scripts and drawing sets added beyond the film's own characters
+/// need a font that covers them; when the locale isn't UTF-8 they fall
+/// back to a plain-ASCII look-alike instead of rendering as boxes.
 pub fn charset_by_name(name: &str) -> CharacterPool {
+    charset_for_terminal(name, unicode_terminal())
+}
+
+/// Best-effort check that the terminal can show non-ASCII text: the
+/// locale (`LC_ALL`, then `LC_CTYPE`, then `LANG`) names UTF-8. Windows
+/// terminals are assumed capable.
+pub fn unicode_terminal() -> bool {
+    if cfg!(windows) {
+        return true;
+    }
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .map(|locale| {
+            let locale = locale.to_ascii_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        })
+        .unwrap_or(false)
+}
+
+/// `charset_by_name`, with the UTF-8 check done by the caller.
+fn charset_for_terminal(name: &str, unicode: bool) -> CharacterPool {
+    if name.contains(['+', ':']) {
+        return mix_charsets(name, unicode);
+    }
     match name {
+        "greek" | "cyrillic" | "hebrew" if !unicode => CharacterPool::latin(),
+        "runes" | "box" | "blocks" if !unicode => CharacterPool::ascii_art(),
+        "greek" => CharacterPool::greek(),
+        "cyrillic" => CharacterPool::cyrillic(),
+        "hebrew" => CharacterPool::hebrew(),
+        "runes" => CharacterPool::runes(),
+        "box" => CharacterPool::box_drawing(),
+        "blocks" => CharacterPool::blocks(),
         "matrix" => CharacterPool::matrix(),
         "ascii" => CharacterPool::ascii(),
         "binary" => CharacterPool::binary(),
         "digits" => CharacterPool::digits(),
         "katakana" => CharacterPool::katakana(),
         "latin" => CharacterPool::latin(),
+        _ if let Some((_, pool)) = custom_charsets().into_iter().find(|(n, _)| *n == name) => pool,
         _ => {
             eprintln!("Unknown charset '{}', using matrix", name);
             CharacterPool::matrix()
@@ -27,10 +162,51 @@ pub fn charset_by_name(name: &str) -> CharacterPool {
     }
 }
 
+/// The pool for a mix like `matrix+digits:3`: every character of every
+/// part, each repeated by the largest weight of a part containing it.
+/// Unknown parts are skipped with a warning.
+fn mix_charsets(name: &str, unicode: bool) -> CharacterPool {
+    let parts = match charset_parts(name) {
+        Ok(parts) => parts,
+        Err(e) => {
+            eprintln!("{}, using matrix", e);
+            return CharacterPool::matrix();
+        }
+    };
+    let known = charset_names();
+    let mut weighted: Vec<(char, u32)> = Vec::new();
+    for (part, weight) in parts {
+        if !known.contains(&part) {
+            eprintln!("Unknown charset '{}' in '{}', skipping it", part, name);
+            continue;
+        }
+        for c in charset_for_terminal(part, unicode).chars {
+            match weighted.iter_mut().find(|(existing, _)| *existing == c) {
+                Some(entry) => entry.1 = entry.1.max(weight),
+                None => weighted.push((c, weight)),
+            }
+        }
+    }
+    if weighted.is_empty() {
+        return CharacterPool::matrix();
+    }
+    CharacterPool {
+        chars: weighted
+            .into_iter()
+            .flat_map(|(c, weight)| std::iter::repeat_n(c, weight as usize))
+            .collect(),
+        source: None,
+    }
+}
+
 /// A pool of characters that rain columns draw from.
+#[derive(Clone)]
 pub struct CharacterPool {
     /// The available characters
     chars: Vec<char>,
+    /// Text that columns read in order instead of drawing at random
+    /// (`--source-file`); `chars` then holds its distinct characters
+    source: Option<Arc<[char]>>,
 }
 
 impl CharacterPool {
@@ -53,7 +229,10 @@ impl CharacterPool {
             chars.push(*c);
         }
 
-        Self { chars }
+        Self {
+            chars,
+            source: None,
+        }
     }
 
     /// ASCII letters + digits + symbols.
@@ -61,13 +240,17 @@ impl CharacterPool {
         let mut chars: Vec<char> = ('!'..='~').collect();
         // Remove space (0x20), start from '!' (0x21)
         chars.retain(|c| !c.is_whitespace());
-        Self { chars }
+        Self {
+            chars,
+            source: None,
+        }
     }
 
     /// Binary: just 0 and 1.
     pub fn binary() -> Self {
         Self {
             chars: vec!['0', '1'],
+            source: None,
         }
     }
 
@@ -75,6 +258,7 @@ impl CharacterPool {
     pub fn digits() -> Self {
         Self {
             chars: ('0'..='9').collect(),
+            source: None,
         }
     }
 
@@ -82,6 +266,7 @@ impl CharacterPool {
     pub fn katakana() -> Self {
         Self {
             chars: ('\u{FF66}'..='\u{FF9F}').collect(),
+            source: None,
         }
     }
 
@@ -89,7 +274,164 @@ impl CharacterPool {
     pub fn latin() -> Self {
         let mut chars: Vec<char> = ('A'..='Z').collect();
         chars.extend('a'..='z');
-        Self { chars }
+        Self {
+            chars,
+            source: None,
+        }
+    }
+
+    /// Greek capitals and small letters (skipping the unassigned U+03A2).
+    pub fn greek() -> Self {
+        let mut chars: Vec<char> = ('\u{0391}'..='\u{03A9}')
+            .filter(|&c| c != '\u{03A2}')
+            .collect();
+        chars.extend('\u{03B1}'..='\u{03C9}');
+        Self {
+            chars,
+            source: None,
+        }
+    }
+
+    /// Russian Cyrillic capitals and small letters (А-я).
+    pub fn cyrillic() -> Self {
+        Self {
+            chars: ('\u{0410}'..='\u{044F}').collect(),
+            source: None,
+        }
+    }
+
+    /// The Hebrew alphabet, final forms included (א-ת).
+    pub fn hebrew() -> Self {
+        Self {
+            chars: ('\u{05D0}'..='\u{05EA}').collect(),
+            source: None,
+        }
+    }
+
+    /// The 24 runes of the Elder Futhark.
+    pub fn runes() -> Self {
+        Self {
+            chars: "ᚠᚢᚦᚨᚱᚲᚷᚹᚺᚾᛁᛃᛇᛈᛉᛊᛏᛒᛖᛗᛚᛜᛞᛟ".chars().collect(),
+            source: None,
+        }
+    }
+
+    /// Box-drawing lines, corners, and junctions (U+2500-U+257F).
+    pub fn box_drawing() -> Self {
+        Self {
+            chars: ('\u{2500}'..='\u{257F}').collect(),
+            source: None,
+        }
+    }
+
+    /// Block elements: partial blocks and shades (U+2580-U+259F).
+    pub fn blocks() -> Self {
+        Self {
+            chars: ('\u{2580}'..='\u{259F}').collect(),
+            source: None,
+        }
+    }
+
+    /// ASCII lines and marks standing in for runes, box drawing, and blocks
+    /// on terminals without UTF-8.
+    fn ascii_art() -> Self {
+        Self {
+            chars: "+-|/\\<>^v#=%*:.".chars().collect(),
+            source: None,
+        }
+    }
+
+    /// A pool of the characters in `text`, in order of first appearance.
+    /// Whitespace is ignored and duplicates are dropped; control and
+    /// zero-width characters (which would garble the screen) are errors.
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let mut chars = Vec::new();
+        for c in text.chars().filter(|c| !c.is_whitespace()) {
+            if c.is_control() || is_zero_width(c) {
+                return Err(format!(
+                    "U+{:04X} can't be used in a charset (control or zero-width character)",
+                    c as u32
+                ));
+            }
+            if !chars.contains(&c) {
+                chars.push(c);
+            }
+        }
+        if chars.is_empty() {
+            return Err("a charset needs at least one visible character".to_string());
+        }
+        Ok(Self {
+            chars,
+            source: None,
+        })
+    }
+
+    /// `count` characters spread evenly across the pool (repeating if the
+    /// pool is smaller), for previews.
+    pub fn sample(&self, count: usize) -> String {
+        let step = (self.chars.len() / count.max(1)).max(1);
+        (0..count)
+            .map(|i| self.chars[i * step % self.chars.len()])
+            .collect()
+    }
+
+    /// A pool that streams `text` in order, so real fragments of it fall
+    /// down the screen. Runs of whitespace become a single space, and
+    /// control and zero-width characters are dropped.
+    pub fn from_source(text: &str) -> Result<Self, String> {
+        let mut source = Vec::new();
+        for c in text.chars() {
+            if c.is_whitespace() {
+                if source.last().is_some_and(|&last| last != ' ') {
+                    source.push(' ');
+                }
+            } else if !c.is_control() && !is_zero_width(c) {
+                source.push(c);
+            }
+        }
+        let mut chars: Vec<char> = Vec::new();
+        for &c in source.iter().filter(|&&c| c != ' ') {
+            if !chars.contains(&c) {
+                chars.push(c);
+            }
+        }
+        if chars.is_empty() {
+            return Err("the source text has no visible characters".to_string());
+        }
+        Ok(Self {
+            chars,
+            source: Some(source.into()),
+        })
+    }
+
+    /// Where a new column starts reading the source text: a random spot,
+    /// so columns show different fragments. `None` for ordinary pools.
+    pub fn start_cursor(&self, rng: &mut impl Rng) -> Option<usize> {
+        let source = self.source.as_ref()?;
+        Some(rng.random_range(0..source.len()))
+    }
+
+    /// The next character for a column: the source text at `cursor`
+    /// (advancing it, and wrapping at the end), or a random character.
+    pub fn next_char(&self, cursor: &mut Option<usize>, rng: &mut impl Rng) -> char {
+        match (&self.source, cursor.as_mut()) {
+            (Some(source), Some(pos)) => {
+                let ch = source[*pos % source.len()];
+                *pos = (*pos + 1) % source.len();
+                ch
+            }
+            _ => self.random_char(rng),
+        }
+    }
+
+    /// Number of distinct characters in the pool.
+    pub fn len(&self) -> usize {
+        self.chars.len()
+    }
+
+    /// Whether the pool has no characters (never true for the built-ins).
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty()
     }
 
     /// Pick a random character from the pool.
@@ -99,6 +441,26 @@ impl CharacterPool {
     }
 }
 
+/// Characters that take no space of their own: combining marks, joiners,
+/// direction marks, and variation selectors.
+fn is_zero_width(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0300}'..='\u{036F}'
+            | '\u{0483}'..='\u{0489}'
+            | '\u{0591}'..='\u{05BD}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{200B}'..='\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{206F}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{FE20}'..='\u{FE2F}'
+            | '\u{FEFF}'
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -142,10 +504,89 @@ mod tests {
         assert!(pool.chars.contains(&'\u{FF66}'));
     }
 
+    #[test]
+    fn charsets_cycle_and_sample() {
+        assert_eq!(next_charset_name("matrix"), "ascii");
+        assert_eq!(next_charset_name("latin"), "greek");
+        assert_eq!(next_charset_name("blocks"), "matrix");
+        assert_eq!(CharacterPool::binary().sample(5), "01010");
+        assert_eq!(CharacterPool::digits().sample(5), "02468");
+    }
+
+    #[test]
+    fn unicode_charsets_fall_back_without_utf8() {
+        let runes = charset_for_terminal("runes", true);
+        assert_eq!(runes.len(), 24);
+        assert!(runes.chars.contains(&'ᚠ'));
+        assert_eq!(CharacterPool::greek().len(), 49);
+        assert_eq!(CharacterPool::hebrew().len(), 27);
+        for name in ["greek", "cyrillic", "hebrew", "runes", "box", "blocks"] {
+            assert!(
+                charset_for_terminal(name, false)
+                    .chars
+                    .iter()
+                    .all(char::is_ascii)
+            );
+        }
+        assert_eq!(
+            charset_for_terminal("matrix", false).len(),
+            CharacterPool::matrix().len()
+        );
+    }
+
+    #[test]
+    fn charsets_mix_with_weights() {
+        let mixed = charset_for_terminal("binary+digits", true);
+        assert_eq!(mixed.len(), 10);
+        let weighted = charset_for_terminal("latin+binary:3", true);
+        assert_eq!(weighted.len(), 52 + 6);
+        assert_eq!(weighted.chars.iter().filter(|&&c| c == '1').count(), 3);
+
+        assert_eq!(
+            charset_parts("matrix+digits:3").unwrap(),
+            vec![("matrix", 1), ("digits", 3)]
+        );
+        assert!(charset_parts("matrix+").is_err());
+        assert!(charset_parts("digits:0").is_err());
+        assert!(is_charset_name("matrix+latin:2"));
+        assert!(!is_charset_name("matrix+plaid"));
+        assert!(register_charset("a+b", CharacterPool::binary()).is_err());
+    }
+
+    #[test]
+    fn custom_charsets_dedupe_validate_and_register() {
+        let pool = CharacterPool::from_text("HELLO WORLD\n01").unwrap();
+        assert_eq!(pool.chars, "HELOWRD01".chars().collect::<Vec<_>>());
+        assert!(CharacterPool::from_text("ab\u{200B}").is_err());
+        assert!(CharacterPool::from_text("a\u{0301}").is_err());
+        assert!(CharacterPool::from_text("\t\x07").is_err());
+        assert!(CharacterPool::from_text("  \n").is_err());
+
+        assert!(register_charset("matrix", pool.clone()).is_err());
+        register_charset("test-hello", pool).unwrap();
+        assert!(charset_names().contains(&"test-hello"));
+        assert_eq!(charset_by_name("test-hello").sample(3), "HOD");
+    }
+
+    #[test]
+    fn source_text_streams_in_order_and_wraps() {
+        let pool = CharacterPool::from_source("fn  main()\n{\u{200B}}").unwrap();
+        let mut rng = crate::rng::make_rng(Some(1), crate::rng::Stream::Rain);
+        let mut cursor = Some(0);
+        let text: String = (0..14)
+            .map(|_| pool.next_char(&mut cursor, &mut rng))
+            .collect();
+        assert_eq!(text, "fn main() {}fn");
+        assert!(!pool.chars.contains(&' '));
+        assert!(pool.start_cursor(&mut rng).is_some_and(|c| c < 12));
+        assert_eq!(CharacterPool::binary().start_cursor(&mut rng), None);
+        assert!(CharacterPool::from_source(" \n\t").is_err());
+    }
+
     #[test]
     fn random_char_returns_valid_char() {
         let pool = CharacterPool::matrix();
-        let mut rng = rand::rng();
+        let mut rng = crate::rng::make_rng(Some(1), crate::rng::Stream::Rain);
         for _ in 0..100 {
             let ch = pool.random_char(&mut rng);
             assert!(pool.chars.contains(&ch));
@@ -3,9 +3,16 @@
 //! Each column has a head position that moves downward, leaving a trail of
 //! characters behind it. The trail has a maximum length; characters at the
 //! tail end fade out and disappear.
+//!
+//! Columns come and go constantly, so finished ones are kept in a
+//! `ColumnPool` and respawned rather than dropped: a column's trail is sized
+//! to the screen height once and reused from then on.
+
+use std::collections::VecDeque;
 
-use rand::Rng;
+use rand::{Rng, RngExt};
 
+use super::DEFAULT_HIGHLIGHT_RATE;
 use super::chars::CharacterPool;
 
 /// A single vertical column of falling rain.
@@ -14,7 +21,7 @@ pub struct RainColumn {
     pub x: u16,
     /// The trail of characters: (y_position, character).
     /// Index 0 is the tail (oldest), last index is the head (newest).
-    pub trail: Vec<(u16, char)>,
+    pub trail: VecDeque<(u16, char)>,
     /// Which trail indices are gold highlights
     pub highlight_positions: Vec<usize>,
     /// Maximum trail length before tail characters start disappearing
@@ -29,11 +36,39 @@ pub struct RainColumn {
     draining: bool,
     /// Probability that a character mutates (changes) each frame
     mutation_rate: f64,
+    /// Probability that a new character is a highlight
+    highlight_rate: f64,
+    /// Position in the pool's source text (`--source-file`), if reading one
+    cursor: Option<usize>,
+    /// Text this column spells out instead of drawing from the pool
+    /// (`--tail`); the column stops growing once it runs out
+    text: Option<std::vec::IntoIter<char>>,
 }
 
 impl RainColumn {
-    /// Spawn a new rain column at the given x position.
-    pub fn spawn(x: u16, screen_height: u16, rng: &mut impl Rng) -> Self {
+    /// Spawn a new rain column at the given x position. `mutation_rate` is
+    /// the chance each character changes per frame.
+    pub fn spawn(x: u16, screen_height: u16, mutation_rate: f64, rng: &mut impl Rng) -> Self {
+        let mut col = Self {
+            x,
+            trail: VecDeque::new(),
+            highlight_positions: Vec::new(),
+            max_trail_len: 0,
+            speed: 0.0,
+            accumulator: 0.0,
+            head_y: 0.0,
+            draining: false,
+            mutation_rate,
+            highlight_rate: DEFAULT_HIGHLIGHT_RATE,
+            cursor: None,
+            text: None,
+        };
+        col.respawn(x, screen_height, mutation_rate, rng);
+        col
+    }
+
+    /// Start over as a freshly spawned column, keeping the trail's memory.
+    fn respawn(&mut self, x: u16, screen_height: u16, mutation_rate: f64, rng: &mut impl Rng) {
         // Randomize speed: faster columns feel "closer" to the viewer
         let speed = rng.random_range(8.0..25.0);
 
@@ -42,19 +77,55 @@ impl RainColumn {
             rng.random_range((screen_height as usize / 3)..=(screen_height as usize));
 
         // Start above the screen so the head "enters" from the top
-        let start_y = -(rng.random_range(0..screen_height / 2) as f64);
+        let start_y = match screen_height / 2 {
+            0 => 0.0,
+            half => -(rng.random_range(0..half) as f64),
+        };
 
-        Self {
-            x,
-            trail: Vec::with_capacity(max_trail_len),
-            highlight_positions: Vec::new(),
-            max_trail_len,
-            speed,
-            accumulator: 0.0,
-            head_y: start_y,
-            draining: false,
-            mutation_rate: 0.02,
-        }
+        // Room for the longest trail this screen allows, so it never grows
+        self.trail.clear();
+        self.trail.reserve(screen_height as usize);
+        self.highlight_positions.clear();
+        self.x = x;
+        self.max_trail_len = max_trail_len;
+        self.speed = speed;
+        self.accumulator = 0.0;
+        self.head_y = start_y;
+        self.draining = false;
+        self.mutation_rate = mutation_rate;
+        self.highlight_rate = DEFAULT_HIGHLIGHT_RATE;
+        self.cursor = None;
+        self.text = None;
+    }
+
+    /// Read new characters from the pool's source text starting at
+    /// `cursor` (see `CharacterPool::start_cursor`).
+    pub fn with_cursor(mut self, cursor: Option<usize>) -> Self {
+        self.cursor = cursor;
+        self
+    }
+
+    /// Spell out `text` from the top of the screen at half speed, one
+    /// character per row, then erase it from the top at the same pace; the
+    /// characters never mutate.
+    pub fn with_text(mut self, text: Vec<char>) -> Self {
+        self.max_trail_len = self.max_trail_len.max(text.len());
+        self.speed *= 0.5;
+        self.head_y = 0.0;
+        self.text = Some(text.into_iter());
+        self
+    }
+
+    /// Use `rate` as the chance each new character is a highlight
+    /// (0 = none).
+    pub fn with_highlight_rate(mut self, rate: f64) -> Self {
+        self.highlight_rate = rate;
+        self
+    }
+
+    /// Change how often this column's characters mutate.
+    pub fn set_mutation_rate(&mut self, rate: f64) {
+        self.mutation_rate = rate;
     }
 
     /// Advance this column by one frame.
@@ -73,13 +144,24 @@ impl RainColumn {
             self.accumulator -= 1.0;
             let y = self.head_y as i32;
 
-            if y >= 0 && y < screen_height as i32 {
-                let ch = char_pool.random_char(rng);
-                self.trail.push((y as u16, ch));
+            // A draining head adds nothing (normally it's off the bottom
+            // anyway, but a terminated column stops mid-screen)
+            if !self.draining && y >= 0 && y < screen_height as i32 {
+                let next = match &mut self.text {
+                    Some(text) => text.next(),
+                    None => Some(char_pool.next_char(&mut self.cursor, rng)),
+                };
+                if let Some(ch) = next {
+                    self.trail.push_back((y as u16, ch));
 
-                // Small chance this character is a gold highlight
-                if rng.random_bool(0.03) {
-                    self.highlight_positions.push(self.trail.len() - 1);
+                    // Small chance this character is a gold highlight
+                    if rng.random_bool(self.highlight_rate) {
+                        self.highlight_positions.push(self.trail.len() - 1);
+                    }
+                } else {
+                    // Text spelled out: erase it at the pace it was written
+                    self.drop_oldest();
+                    self.draining = self.trail.is_empty();
                 }
             }
 
@@ -93,32 +175,18 @@ impl RainColumn {
 
         // Trim trail from the tail if it exceeds max length
         while self.trail.len() > self.max_trail_len {
-            self.trail.remove(0);
-            // Adjust highlight positions
-            self.highlight_positions.retain_mut(|pos| {
-                if *pos == 0 {
-                    false
-                } else {
-                    *pos -= 1;
-                    true
-                }
-            });
+            self.drop_oldest();
         }
 
         // If draining, also remove from the tail each frame
         if self.draining && !self.trail.is_empty() {
-            self.trail.remove(0);
-            self.highlight_positions.retain_mut(|pos| {
-                if *pos == 0 {
-                    false
-                } else {
-                    *pos -= 1;
-                    true
-                }
-            });
+            self.drop_oldest();
         }
 
         // Character mutation: randomly change some characters in the trail
+        if self.text.is_some() {
+            return;
+        }
         for (_, ch) in &mut self.trail {
             if rng.random_bool(self.mutation_rate) {
                 *ch = char_pool.random_char(rng);
@@ -126,13 +194,111 @@ impl RainColumn {
         }
     }
 
+    /// Remove the tail (oldest) character, keeping highlights in place.
+    fn drop_oldest(&mut self) {
+        self.trail.pop_front();
+        self.highlight_positions.retain_mut(|pos| {
+            if *pos == 0 {
+                false
+            } else {
+                *pos -= 1;
+                true
+            }
+        });
+    }
+
     /// Returns true if this column has no visible characters left.
     pub fn is_dead(&self, _screen_height: u16) -> bool {
         self.draining && self.trail.is_empty()
     }
 
+    /// Stop growing and start draining, as if the head hit the bottom.
+    pub fn terminate(&mut self) {
+        self.draining = true;
+    }
+
     /// Returns true if this column is draining (no longer spawning new chars).
     pub fn is_fading(&self) -> bool {
         self.draining
     }
 }
+
+/// Finished columns waiting to be respawned.
+#[derive(Default)]
+pub struct ColumnPool {
+    spare: Vec<RainColumn>,
+}
+
+impl ColumnPool {
+    /// Like `RainColumn::spawn`, reusing a retired column when there is one.
+    pub fn spawn(
+        &mut self,
+        x: u16,
+        screen_height: u16,
+        mutation_rate: f64,
+        rng: &mut impl Rng,
+    ) -> RainColumn {
+        match self.spare.pop() {
+            Some(mut col) => {
+                col.respawn(x, screen_height, mutation_rate, rng);
+                col
+            }
+            None => RainColumn::spawn(x, screen_height, mutation_rate, rng),
+        }
+    }
+
+    /// Keep the columns `keep` returns true for, in order, and retire the
+    /// rest to the pool. Like `Vec::retain_mut`.
+    pub fn retain(
+        &mut self,
+        columns: &mut Vec<RainColumn>,
+        mut keep: impl FnMut(&mut RainColumn) -> bool,
+    ) {
+        let mut kept = 0;
+        for i in 0..columns.len() {
+            if keep(&mut columns[i]) {
+                if kept != i {
+                    columns.swap(kept, i);
+                }
+                kept += 1;
+            }
+        }
+        self.spare.extend(columns.drain(kept..));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::rng::SimRng;
+
+    #[test]
+    fn retired_columns_are_respawned_fresh() {
+        let pool_chars = CharacterPool::matrix();
+        let mut rng = SimRng::seed_from_u64(3);
+        let mut pool = ColumnPool::default();
+        let mut columns: Vec<RainColumn> = (0..4)
+            .map(|x| {
+                pool.spawn(x, 40, 0.0, &mut rng)
+                    .with_text("ok".chars().collect())
+            })
+            .collect();
+        for _ in 0..200 {
+            pool.retain(&mut columns, |col| {
+                col.update(0.1, 40, &pool_chars, &mut rng);
+                col.x % 2 == 1 || !col.is_dead(40)
+            });
+        }
+        // The spelled-out columns finished; the others are kept, in order
+        assert_eq!(columns.iter().map(|c| c.x).collect::<Vec<_>>(), [1, 3]);
+        assert_eq!(pool.spare.len(), 2);
+
+        let col = pool.spawn(7, 40, 0.5, &mut rng);
+        assert_eq!((col.x, col.trail.len(), col.is_fading()), (7, 0, false));
+        assert!(col.text.is_none() && col.highlight_positions.is_empty());
+        assert!(col.trail.capacity() >= 40);
+        assert_eq!(pool.spare.len(), 1);
+    }
+}
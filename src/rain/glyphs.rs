@@ -0,0 +1,100 @@
+//! Fixed per-cell glyphs for `--static-glyphs`.
+//!
+//! In the film the code doesn't actually fall: every screen position holds
+//! its own character, and what travels down a column is only the light.
+//! `GlyphGrid` is that layer of characters. Columns still move and fade as
+//! usual, but render whatever glyph sits under each lit cell instead of the
+//! characters they carry.
+
+use rand::{Rng, RngExt};
+
+use super::chars::CharacterPool;
+use super::column::RainColumn;
+
+/// One fixed character per screen cell.
+pub struct GlyphGrid {
+    width: u16,
+    height: u16,
+    cells: Vec<char>,
+}
+
+impl GlyphGrid {
+    /// A `width` x `height` grid filled with random characters from `pool`.
+    pub fn new(width: u16, height: u16, pool: &CharacterPool, rng: &mut impl Rng) -> Self {
+        let mut grid = Self {
+            width,
+            height,
+            cells: Vec::new(),
+        };
+        grid.fill(pool, rng);
+        grid
+    }
+
+    /// Redraw every cell from `pool` (after a charset change).
+    pub fn fill(&mut self, pool: &CharacterPool, rng: &mut impl Rng) {
+        let len = self.width as usize * self.height as usize;
+        self.cells = (0..len).map(|_| pool.random_char(rng)).collect();
+    }
+
+    /// Adopt a new screen size, redrawing every cell.
+    pub fn resize(&mut self, width: u16, height: u16, pool: &CharacterPool, rng: &mut impl Rng) {
+        self.width = width;
+        self.height = height;
+        self.fill(pool, rng);
+    }
+
+    /// The glyph at (`x`, `y`), if it's on the grid.
+    pub fn get(&self, x: u16, y: u16) -> Option<char> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.cells
+            .get(y as usize * self.width as usize + x as usize)
+            .copied()
+    }
+
+    /// Give each cell lit by `col` a `rate` chance to change, so
+    /// `--mutation` still flickers the code (and 0 keeps it perfectly still).
+    pub fn mutate_under(
+        &mut self,
+        col: &RainColumn,
+        rate: f64,
+        pool: &CharacterPool,
+        rng: &mut impl Rng,
+    ) {
+        if col.x >= self.width {
+            return;
+        }
+        for &(y, _) in &col.trail {
+            if y < self.height && rng.random_bool(rate) {
+                let idx = y as usize * self.width as usize + col.x as usize;
+                self.cells[idx] = pool.random_char(rng);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::rng::SimRng;
+
+    #[test]
+    fn glyphs_stay_put_without_mutation() {
+        let pool = CharacterPool::matrix();
+        let mut rng = SimRng::seed_from_u64(4);
+        let mut grid = GlyphGrid::new(8, 6, &pool, &mut rng);
+        let before: Vec<_> = (0..6).map(|y| grid.get(3, y)).collect();
+
+        let mut col = RainColumn::spawn(3, 6, 0.0, &mut rng);
+        for _ in 0..30 {
+            col.update(0.1, 6, &pool, &mut rng);
+            grid.mutate_under(&col, 0.0, &pool, &mut rng);
+        }
+        let after: Vec<_> = (0..6).map(|y| grid.get(3, y)).collect();
+        assert_eq!(before, after);
+        assert_eq!(grid.get(8, 0), None);
+    }
+}
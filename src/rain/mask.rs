@@ -0,0 +1,55 @@
+//! Per-cell density masks for `RainField`.
+//!
+//! A mask gives each screen cell a weight from 0.0 to 1.0. Columns running
+//! through weighted cells spawn more often, and the rain is drawn brighter
+//! where it crosses them, so a shape painted into the mask shows up in the
+//! rain (the `timefall` clock's digits, for one). Cells off the mask weigh
+//! nothing.
+
+/// A weight per screen cell.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DensityMask {
+    width: u16,
+    height: u16,
+    weights: Vec<f32>,
+}
+
+impl DensityMask {
+    /// An empty mask for a `width` x `height` screen.
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            weights: vec![0.0; width as usize * height as usize],
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// The weight at (`x`, `y`); 0.0 off the mask.
+    pub fn get(&self, x: u16, y: u16) -> f32 {
+        if x >= self.width || y >= self.height {
+            return 0.0;
+        }
+        self.weights[y as usize * self.width as usize + x as usize]
+    }
+
+    /// Set the weight at (`x`, `y`), clamped to 0.0-1.0. Cells off the mask
+    /// are ignored.
+    pub fn set(&mut self, x: u16, y: u16, weight: f32) {
+        if x < self.width && y < self.height {
+            self.weights[y as usize * self.width as usize + x as usize] = weight.clamp(0.0, 1.0);
+        }
+    }
+
+    /// The heaviest weight in column `x`.
+    pub fn column_weight(&self, x: u16) -> f32 {
+        (0..self.height).map(|y| self.get(x, y)).fold(0.0, f32::max)
+    }
+}
@@ -2,19 +2,116 @@
 
 pub mod chars;
 pub mod column;
+pub mod glyphs;
+pub mod mask;
+pub mod stripes;
 
-use rand::Rng;
+use std::collections::VecDeque;
+
+use rand::RngExt;
 
 use self::chars::{CharacterPool, charset_by_name};
-use self::column::RainColumn;
-use crate::buffer::ScreenBuffer;
-use crate::color::gradient::trail_color;
-use crate::color::palette::{Palette, palette_by_name};
+use self::column::{ColumnPool, RainColumn};
+use self::glyphs::GlyphGrid;
+use self::mask::DensityMask;
+use self::stripes::{Coloring, Stripes};
+use crate::buffer::{Region, RenderTarget};
+use crate::color::Color;
+use crate::color::gradient::{lerp_color, trail_color};
+use crate::color::palette::{Palette, color_by_name, palette_by_name};
 use crate::config::Config;
+use crate::event::Event;
+use crate::rng::{self, SimRng, Stream};
+
+/// Largest `--spacing` gap, in columns.
+pub const MAX_SPACING: u16 = 20;
+
+/// Default chance that a trail character changes each frame (`--mutation`).
+pub const DEFAULT_MUTATION: f64 = 0.02;
+
+/// Default chance that a new trail character is a highlight (`--highlight-rate`).
+pub const DEFAULT_HIGHLIGHT_RATE: f64 = 0.03;
+
+/// Longest `--gust-period`, in seconds.
+pub const MAX_GUST_PERIOD: f64 = 600.0;
+
+/// Default `--gust-strength`.
+pub const DEFAULT_GUST_STRENGTH: f64 = 0.5;
+
+/// What rain does at a dead zone (`--dead-zone`). Either way nothing is
+/// drawn inside the zone.
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum DeadZoneMode {
+    /// Columns stop where they reach the zone and drain away
+    #[default]
+    Terminate,
+    /// Columns keep falling behind the zone and come out below it
+    Split,
+}
+
+/// In `Terminate` mode, drain any column whose head has entered a zone.
+pub fn stop_at_dead_zones(columns: &mut [RainColumn], zones: &[Region], mode: DeadZoneMode) {
+    if mode != DeadZoneMode::Terminate || zones.is_empty() {
+        return;
+    }
+    for col in columns.iter_mut().filter(|c| !c.is_fading()) {
+        if let Some(&(y, _)) = col.trail.back()
+            && zones.iter().any(|z| z.contains(col.x, y))
+        {
+            col.terminate();
+        }
+    }
+}
+
+/// Text columns started per second while log lines (`--tail`) are waiting.
+const TEXT_COLUMNS_PER_SEC: f64 = 12.0;
+
+/// Shortest piece a log line is shredded into; longer pieces fill up to
+/// two thirds of the screen height.
+const MIN_TEXT_CHUNK: usize = 8;
+
+/// Most pieces of log text kept waiting; a flood drops the oldest.
+const MAX_QUEUED_TEXT: usize = 512;
+
+/// Spawn rate between gusts at full strength, as a share of normal.
+const GUST_LULL: f64 = 0.2;
+
+/// Spawn rate at the height of a gust at full strength, as a share of normal.
+const GUST_PEAK: f64 = 4.0;
+
+/// Spawn rate in a column crossing full-weight mask cells, as a multiple
+/// of the normal rate on top of it.
+const MASK_SPAWN_BOOST: f64 = 10.0;
+
+/// How far rain in a full-weight mask cell is brightened toward the head
+/// color.
+const MASK_GLOW: f32 = 0.8;
+
+/// Rain that breathes: the spawn rate swells into a downpour once per
+/// period and eases off into a drizzle between surges.
+struct Gusts {
+    period: f64,
+    strength: f64,
+    elapsed: f64,
+}
+
+impl Gusts {
+    /// Spawn rate multiplier right now.
+    fn factor(&self) -> f64 {
+        let phase = (self.elapsed / self.period).fract();
+        // Squared raised cosine: a short surge, then a long lull
+        let wave = (0.5 - 0.5 * (phase * std::f64::consts::TAU).cos()).powi(2);
+        let lull = 1.0 - (1.0 - GUST_LULL) * self.strength;
+        let peak = 1.0 + (GUST_PEAK - 1.0) * self.strength;
+        lull + (peak - lull) * wave
+    }
+}
 
 /// Manages the full rain simulation across all columns of the screen.
 pub struct RainField {
     columns: Vec<RainColumn>,
+    /// Finished columns, kept to respawn
+    pool: ColumnPool,
     char_pool: CharacterPool,
     palette: Palette,
     width: u16,
@@ -25,20 +122,72 @@ pub struct RainField {
     speed_multiplier: f64,
     /// When true, gradient is bright at tail (top) and dim at head (bottom)
     forward: bool,
+    /// Minimum empty columns kept between falling columns
+    spacing: u16,
+    /// Chance that a trail character changes each frame
+    mutation_rate: f64,
+    /// Chance that a new trail character is a highlight, and the color
+    /// that replaces the palette's highlight (`--highlight-color`)
+    highlight_rate: f64,
+    highlight_color: Option<Color>,
+    /// Per-band palettes (`--stripes`), used instead of `palette`
+    stripes: Option<Stripes>,
+    /// Fixed per-cell glyphs (`--static-glyphs`); columns only carry light
+    glyphs: Option<GlyphGrid>,
+    /// Periodic density surges (`--gust-period`)
+    gusts: Option<Gusts>,
+    /// Where the rain falls denser and brighter, and each column's heaviest
+    /// weight in it
+    mask: Option<DensityMask>,
+    mask_columns: Vec<f32>,
+    /// Screen areas kept clear of rain, and what columns do when they get there
+    /// Pieces of log lines (`--tail`) waiting for a column, each with
+    /// whether its line matched `--tail-highlight`
+    text_queue: VecDeque<(Vec<char>, bool)>,
+    /// Text columns owed since the last one started
+    text_budget: f64,
+    dead_zones: Vec<Region>,
+    dead_zone_mode: DeadZoneMode,
+    rng: SimRng,
 }
 
 impl RainField {
     /// Create a new rain field from a Config.
     pub fn with_config(width: u16, height: u16, config: &Config) -> Self {
+        let char_pool = charset_by_name(&config.charset_name);
+        let mut rng = rng::make_rng(config.seed, Stream::Rain);
+        let glyphs = config
+            .static_glyphs
+            .then(|| GlyphGrid::new(width, height, &char_pool, &mut rng));
+        let highlight_color = config.highlight_color.as_deref().and_then(color_by_name);
         Self {
-            columns: Vec::new(),
-            char_pool: charset_by_name(&config.charset_name),
-            palette: palette_by_name(&config.palette_name),
+            columns: Vec::with_capacity(width as usize),
+            pool: ColumnPool::default(),
+            char_pool,
+            palette: palette_by_name(&config.palette_name).with_highlight(highlight_color),
             width,
             height,
             spawn_rate: 0.15 * config.density_multiplier,
             speed_multiplier: config.speed_multiplier,
             forward: config.forward,
+            spacing: config.spacing,
+            mutation_rate: config.mutation_rate,
+            highlight_rate: config.highlight_rate,
+            highlight_color,
+            stripes: Stripes::new(&config.stripes, config.stripe_direction, highlight_color),
+            glyphs,
+            gusts: (config.gust_period > 0.0 && config.gust_strength > 0.0).then_some(Gusts {
+                period: config.gust_period,
+                strength: config.gust_strength,
+                elapsed: 0.0,
+            }),
+            mask: None,
+            mask_columns: Vec::new(),
+            dead_zones: config.dead_zones.clone(),
+            dead_zone_mode: config.dead_zone_mode,
+            text_queue: VecDeque::new(),
+            text_budget: 0.0,
+            rng,
         }
     }
 
@@ -62,54 +211,233 @@ impl RainField {
         self.spawn_rate / 0.15
     }
 
+    /// Replace the areas kept clear of rain.
+    pub fn set_dead_zones(&mut self, zones: &[Region]) {
+        if self.dead_zones != zones {
+            self.dead_zones = zones.to_vec();
+        }
+    }
+
+    /// Make the rain denser and brighter where `mask` is weighted, or
+    /// even again with `None`.
+    pub fn set_density_mask(&mut self, mask: Option<DensityMask>) {
+        self.mask_columns = match &mask {
+            Some(mask) => (0..mask.width()).map(|x| mask.column_weight(x)).collect(),
+            None => Vec::new(),
+        };
+        self.mask = mask;
+    }
+
+    /// Set how often trail characters mutate, for new and falling columns.
+    pub fn set_mutation(&mut self, rate: f64) {
+        self.mutation_rate = rate;
+        for col in &mut self.columns {
+            col.set_mutation_rate(rate);
+        }
+    }
+
+    /// Replace the palette; columns already falling pick it up next frame.
+    /// A `--highlight-color` still overrides the new palette's highlight.
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette.with_highlight(self.highlight_color);
+    }
+
+    /// Replace the character pool; new characters come from it from now on.
+    pub fn set_charset(&mut self, pool: CharacterPool) {
+        self.char_pool = pool;
+        if let Some(glyphs) = &mut self.glyphs {
+            glyphs.fill(&self.char_pool, &mut self.rng);
+        }
+    }
+
+    /// Queue a log line to fall as text (`--tail`), shredded into pieces
+    /// that fit the screen; `highlight` draws it in the highlight color.
+    pub fn ingest(&mut self, line: &str, highlight: bool) {
+        let max_len = (self.height as usize * 2 / 3).max(MIN_TEXT_CHUNK);
+        // Words (split if too long for one piece), packed into pieces
+        let words = line.split_whitespace().flat_map(|word| {
+            let chars: Vec<char> = word.chars().collect();
+            chars
+                .chunks(max_len)
+                .map(<[char]>::to_vec)
+                .collect::<Vec<_>>()
+        });
+        let mut chunk: Vec<char> = Vec::new();
+        for word in words {
+            if !chunk.is_empty() && chunk.len() + 1 + word.len() > max_len {
+                self.text_queue
+                    .push_back((std::mem::take(&mut chunk), highlight));
+            }
+            if !chunk.is_empty() {
+                chunk.push(' ');
+            }
+            chunk.extend(word);
+        }
+        if !chunk.is_empty() {
+            self.text_queue.push_back((chunk, highlight));
+        }
+        while self.text_queue.len() > MAX_QUEUED_TEXT {
+            self.text_queue.pop_front();
+        }
+    }
+
+    /// Take in the events rain cares about: log lines from `--tail`.
+    pub fn on_event(&mut self, event: &Event) {
+        if let Event::Line { text, highlight } = event {
+            self.ingest(text, *highlight);
+        }
+    }
+
+    /// Number of columns currently falling (including fading ones).
+    pub fn column_count(&self) -> usize {
+        self.columns.len()
+    }
+
     /// Resize the field (e.g., when terminal is resized).
     pub fn resize(&mut self, width: u16, height: u16) {
         self.width = width;
         self.height = height;
-        self.columns.retain(|c| c.x < width);
+        self.pool.retain(&mut self.columns, |c| c.x < width);
+        if let Some(glyphs) = &mut self.glyphs {
+            glyphs.resize(width, height, &self.char_pool, &mut self.rng);
+        }
     }
 
     /// Advance the simulation by one frame.
     pub fn update(&mut self, delta_time: f64) {
-        let mut rng = rand::rng();
-
         // Apply speed multiplier to the effective delta time
         let effective_dt = delta_time * self.speed_multiplier;
 
         // Update existing columns, removing any that have fully scrolled off
-        self.columns.retain_mut(|col| {
-            col.update(effective_dt, self.height, &self.char_pool, &mut rng);
+        self.pool.retain(&mut self.columns, |col| {
+            col.update(effective_dt, self.height, &self.char_pool, &mut self.rng);
             !col.is_dead(self.height)
         });
+        stop_at_dead_zones(&mut self.columns, &self.dead_zones, self.dead_zone_mode);
+        if let Some(glyphs) = &mut self.glyphs {
+            for col in &self.columns {
+                glyphs.mutate_under(col, self.mutation_rate, &self.char_pool, &mut self.rng);
+            }
+        }
 
-        // Spawn new columns randomly
+        // Spawn new columns randomly, keeping `spacing` empty columns
+        // either side of every active one. A gap leaves fewer free slots,
+        // so each free slot spawns proportionally more often: at normal
+        // density the screen stays about as busy, while at high density
+        // the gap caps how tightly the columns pack.
+        let gap = self.spacing;
+        let mut blocked = vec![false; self.width as usize];
+        for col in self.columns.iter().filter(|c| !c.is_fading()) {
+            block_around(&mut blocked, col.x, gap);
+        }
+        let gust = match &mut self.gusts {
+            Some(gusts) => {
+                gusts.elapsed += delta_time;
+                gusts.factor()
+            }
+            None => 1.0,
+        };
+        let chance = (self.spawn_rate * gust * delta_time * (gap as f64 + 1.0)).min(1.0);
         for x in 0..self.width {
-            let has_column = self.columns.iter().any(|c| c.x == x && !c.is_fading());
-            if !has_column && rng.random_bool((self.spawn_rate * delta_time).min(1.0)) {
-                self.columns
-                    .push(RainColumn::spawn(x, self.height, &mut rng));
+            let boost = self.mask_columns.get(x as usize).copied().unwrap_or(0.0) as f64;
+            let chance = (chance * (1.0 + MASK_SPAWN_BOOST * boost)).min(1.0);
+            if !blocked[x as usize] && self.rng.random_bool(chance) {
+                let cursor = self.char_pool.start_cursor(&mut self.rng);
+                self.columns.push(
+                    self.pool
+                        .spawn(x, self.height, self.mutation_rate, &mut self.rng)
+                        .with_highlight_rate(self.highlight_rate)
+                        .with_cursor(cursor),
+                );
+                block_around(&mut blocked, x, gap);
+            }
+        }
+
+        // Waiting log text takes free columns at a steady pace
+        if self.text_queue.is_empty() {
+            self.text_budget = 0.0;
+            return;
+        }
+        self.text_budget += TEXT_COLUMNS_PER_SEC * self.speed_multiplier * delta_time;
+        while self.text_budget >= 1.0 && !self.text_queue.is_empty() {
+            let free: Vec<u16> = (0..self.width).filter(|&x| !blocked[x as usize]).collect();
+            if free.is_empty() {
+                // Full screen: wait for a column to free up
+                self.text_budget = 1.0;
+                break;
             }
+            let x = free[self.rng.random_range(0..free.len())];
+            let Some((text, highlight)) = self.text_queue.pop_front() else {
+                break;
+            };
+            self.text_budget -= 1.0;
+            self.columns.push(
+                self.pool
+                    .spawn(x, self.height, 0.0, &mut self.rng)
+                    .with_highlight_rate(if highlight { 1.0 } else { 0.0 })
+                    .with_text(text),
+            );
+            block_around(&mut blocked, x, gap);
         }
     }
 
     /// Render all columns into the screen buffer.
-    pub fn render(&self, buffer: &mut ScreenBuffer) {
+    pub fn render(&self, buffer: &mut dyn RenderTarget) {
         for col in &self.columns {
-            render_rain_column(col, &self.palette, self.height, self.forward, buffer);
+            render_rain_column(
+                col,
+                Coloring::new(&self.palette, self.stripes.as_ref()),
+                self.height,
+                self.forward,
+                self.glyphs.as_ref(),
+                &self.dead_zones,
+                buffer,
+            );
+        }
+
+        // Light up the rain crossing the mask
+        let Some(mask) = &self.mask else {
+            return;
+        };
+        for col in &self.columns {
+            for &(y, _) in &col.trail {
+                let weight = mask.get(col.x, y);
+                if weight > 0.0
+                    && let Some(cell) = buffer.get_cell(col.x, y)
+                    && cell.ch != ' '
+                {
+                    let fg = lerp_color(cell.fg, self.palette.head, weight * MASK_GLOW);
+                    buffer.set_cell(col.x, y, cell.ch, fg, cell.bg);
+                }
+            }
         }
     }
 }
 
+/// Mark column `x` and the `gap` columns either side of it as taken.
+fn block_around(blocked: &mut [bool], x: u16, gap: u16) {
+    let start = x.saturating_sub(gap) as usize;
+    let end = (x as usize + gap as usize + 1).min(blocked.len());
+    if start < end {
+        blocked[start..end].fill(true);
+    }
+}
+
 /// Render a single rain column with gradient trail.
 ///
 /// Extracted as a free function so other effects (e.g., Cascade) can reuse
-/// column rendering without needing a full RainField.
+/// column rendering without needing a full RainField. With `glyphs`, each
+/// lit cell shows the grid's fixed character instead of the trail's. Cells
+/// inside `dead_zones` are left alone. Each cell's colors come from
+/// `coloring`, so striped rain can change palette partway down a column.
 pub fn render_rain_column(
     col: &RainColumn,
-    palette: &Palette,
+    coloring: Coloring,
     screen_height: u16,
     forward: bool,
-    buffer: &mut ScreenBuffer,
+    glyphs: Option<&GlyphGrid>,
+    dead_zones: &[Region],
+    buffer: &mut dyn RenderTarget,
 ) {
     let trail_len = col.trail.len();
     if trail_len == 0 {
@@ -117,7 +445,7 @@ pub fn render_rain_column(
     }
 
     for (i, &(y, ch)) in col.trail.iter().enumerate() {
-        if y >= screen_height {
+        if y >= screen_height || dead_zones.iter().any(|z| z.contains(col.x, y)) {
             continue;
         }
 
@@ -131,6 +459,7 @@ pub fn render_rain_column(
             (trail_len - 1 - i) as f32 / trail_len.max(1) as f32
         };
 
+        let palette = coloring.at(col.x, y, buffer.width(), screen_height);
         let fg = if col.highlight_positions.contains(&i) {
             palette.highlight
         } else {
@@ -143,6 +472,264 @@ pub fn render_rain_column(
             )
         };
 
+        let ch = glyphs.and_then(|g| g.get(col.x, y)).unwrap_or(ch);
         buffer.set_cell(col.x, y, ch, fg, palette.background);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::buffer::ScreenBuffer;
+    use crate::config::{Cli, ConfigFile};
+
+    #[test]
+    fn spacing_keeps_active_columns_apart() {
+        let cli = Cli::parse_from(["digital_rain", "-d", "10", "--spacing", "2", "--seed", "9"]);
+        let config = Config::resolve(&cli, &ConfigFile::default());
+        let mut field = RainField::with_config(60, 20, &config);
+        for _ in 0..60 {
+            field.update(1.0 / 30.0);
+            let mut active: Vec<u16> = field
+                .columns
+                .iter()
+                .filter(|c| !c.is_fading())
+                .map(|c| c.x)
+                .collect();
+            active.sort_unstable();
+            assert!(active.windows(2).all(|w| w[1] - w[0] > 2));
+        }
+        assert!(field.column_count() > 5, "dense rain should still fill in");
+    }
+
+    #[test]
+    fn dead_zones_stop_or_split_the_rain() {
+        let rows_with_rain = |mode: &str| {
+            let cli = Cli::parse_from([
+                "digital_rain",
+                "-d",
+                "5",
+                "--seed",
+                "2",
+                "--dead-zone",
+                "0,6,30,3",
+                "--dead-zone-mode",
+                mode,
+            ]);
+            let config = Config::resolve(&cli, &ConfigFile::default());
+            let mut field = RainField::with_config(30, 20, &config);
+            let mut lit = [false; 20];
+            for _ in 0..120 {
+                field.update(1.0 / 30.0);
+                let mut buffer = ScreenBuffer::new(30, 20);
+                field.render(&mut buffer);
+                for (y, row) in lit.iter_mut().enumerate() {
+                    *row |= (0..30).any(|x| buffer.get_cell(x, y as u16).unwrap().ch != ' ');
+                }
+            }
+            lit
+        };
+
+        let stopped = rows_with_rain("terminate");
+        assert!(stopped[0] && !stopped[6..].iter().any(|&lit| lit));
+        let split = rows_with_rain("split");
+        assert!(!split[6..9].iter().any(|&lit| lit) && split[12]);
+    }
+
+    #[test]
+    fn gusts_swing_between_drizzle_and_downpour() {
+        let mut gusts = Gusts {
+            period: 10.0,
+            strength: 1.0,
+            elapsed: 0.0,
+        };
+        assert!((gusts.factor() - GUST_LULL).abs() < 1e-9);
+        gusts.elapsed = 5.0;
+        assert!((gusts.factor() - GUST_PEAK).abs() < 1e-9);
+        gusts.strength = 0.0;
+        assert!((gusts.factor() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn a_density_mask_draws_its_shape_in_the_rain() {
+        let cli = Cli::parse_from(["digital_rain", "--seed", "6"]);
+        let config = Config::resolve(&cli, &ConfigFile::default());
+        let mut field = RainField::with_config(40, 20, &config);
+        // Columns 10-14 are weighted, lower down only
+        let mut mask = DensityMask::new(40, 20);
+        for x in 10..15 {
+            for y in 10..20 {
+                mask.set(x, y, 1.0);
+            }
+        }
+        field.set_density_mask(Some(mask.clone()));
+
+        let (mut inside, mut outside) = (0, 0);
+        for _ in 0..300 {
+            field.update(1.0 / 30.0);
+            for col in &field.columns {
+                match col.x {
+                    10..15 => inside += 1,
+                    _ => outside += 1,
+                }
+            }
+        }
+        // 5 of 40 columns, with at least twice their share of the rain
+        assert!(inside * 35 > outside * 5 * 2, "{} vs {}", inside, outside);
+
+        // The rain is brighter inside the mask, and only there
+        let mut plain = ScreenBuffer::new(40, 20);
+        let mut masked = ScreenBuffer::new(40, 20);
+        field.render(&mut masked);
+        field.set_density_mask(None);
+        field.render(&mut plain);
+        let mut brightened = 0;
+        for (i, (a, b)) in plain.cells().iter().zip(masked.cells()).enumerate() {
+            let (x, y) = ((i % 40) as u16, (i / 40) as u16);
+            if mask.get(x, y) == 0.0 {
+                assert!(a == b);
+            } else if a.fg != b.fg {
+                brightened += 1;
+            }
+        }
+        assert!(brightened > 0);
+    }
+
+    #[test]
+    fn highlights_follow_rate_and_color() {
+        let args = [
+            "digital_rain",
+            "-d",
+            "5",
+            "--seed",
+            "2",
+            "--highlight-rate",
+            "0",
+        ];
+        let config = Config::resolve(&Cli::parse_from(args), &ConfigFile::default());
+        let mut field = RainField::with_config(40, 20, &config);
+        for _ in 0..90 {
+            field.update(1.0 / 30.0);
+        }
+        assert!(field.column_count() > 0);
+        assert!(
+            field
+                .columns
+                .iter()
+                .all(|c| c.highlight_positions.is_empty())
+        );
+
+        let args = [
+            "digital_rain",
+            "--highlight-rate",
+            "1",
+            "--highlight-color",
+            "#ff0000",
+        ];
+        let config = Config::resolve(&Cli::parse_from(args), &ConfigFile::default());
+        let mut field = RainField::with_config(40, 20, &config);
+        field.set_palette(palette_by_name("ocean"));
+        let red = Color::Rgb { r: 255, g: 0, b: 0 };
+        assert_eq!(field.palette.highlight, red);
+        for _ in 0..30 {
+            field.update(1.0 / 30.0);
+        }
+        let col = field.columns.iter().find(|c| !c.trail.is_empty()).unwrap();
+        assert_eq!(col.highlight_positions.len(), col.trail.len());
+    }
+
+    /// A field that has rained for two seconds and then stopped spawning,
+    /// so every glyph from here on belongs to a column already falling.
+    fn settled_field(mutation: &str) -> RainField {
+        let cli = Cli::parse_from([
+            "digital_rain",
+            "--mutation",
+            mutation,
+            "-d",
+            "3",
+            "--seed",
+            "4",
+        ]);
+        let config = Config::resolve(&cli, &ConfigFile::default());
+        let mut field = RainField::with_config(40, 30, &config);
+        for _ in 0..60 {
+            field.update(1.0 / 30.0);
+        }
+        field.set_density(0.0);
+        field
+    }
+
+    /// Glyphs that changed in place over one frame.
+    fn glyph_changes(field: &mut RainField) -> usize {
+        let glyphs = |field: &RainField| -> std::collections::BTreeMap<(u16, u16), char> {
+            field
+                .columns
+                .iter()
+                .flat_map(|c| c.trail.iter().map(move |&(y, ch)| ((c.x, y), ch)))
+                .collect()
+        };
+        let before = glyphs(field);
+        field.update(1.0 / 30.0);
+        glyphs(field)
+            .iter()
+            .filter(|(at, ch)| before.get(at).is_some_and(|b| b != *ch))
+            .count()
+    }
+
+    #[test]
+    fn mutation_zero_keeps_glyphs_unchanged() {
+        let mut field = settled_field("0");
+        assert!(field.columns.iter().map(|c| c.trail.len()).sum::<usize>() > 20);
+        for _ in 0..10 {
+            assert_eq!(glyph_changes(&mut field), 0);
+        }
+    }
+
+    #[test]
+    fn runtime_mutation_reaches_falling_columns() {
+        let mut field = settled_field("0");
+        field.set_mutation(0.5);
+        assert!(glyph_changes(&mut field) > 5);
+    }
+
+    #[test]
+    fn log_lines_fall_as_readable_text() {
+        let config = Config::resolve(&Cli::parse_from(["digital_rain"]), &ConfigFile::default());
+        let mut field = RainField::with_config(30, 12, &config);
+        field.set_density(0.0);
+        field.set_mutation(0.5);
+        field.ingest("disk quota exceeded on /dev/sda1", false);
+        field.ingest("panic", true);
+        // Pieces fit two thirds of the screen (8 rows here), words whole
+        let pieces: Vec<String> = field
+            .text_queue
+            .iter()
+            .map(|(text, _)| text.iter().collect())
+            .collect();
+        assert_eq!(
+            pieces,
+            ["disk", "quota", "exceeded", "on", "/dev/sda", "1", "panic"]
+        );
+
+        // Each piece reads down its column as it's written and erased,
+        // in the highlight color when its line matched
+        let (mut read_exceeded, mut read_panic) = (false, false);
+        for _ in 0..60 {
+            field.update(1.0 / 30.0);
+            for col in &field.columns {
+                let spelled: String = col.trail.iter().map(|&(_, ch)| ch).collect();
+                read_exceeded |= spelled.len() >= 4 && "exceeded".contains(spelled.as_str());
+                if spelled.len() >= 2 && "panic".contains(spelled.as_str()) {
+                    assert_eq!(col.highlight_positions.len(), col.trail.len());
+                    read_panic = true;
+                } else if spelled.len() >= 2 {
+                    assert!(col.highlight_positions.is_empty());
+                }
+            }
+        }
+        assert!(read_exceeded && read_panic);
+        assert!(field.text_queue.is_empty());
+    }
+}
@@ -0,0 +1,145 @@
+//! Striped rain for `--stripes`: the screen split into bands, each falling
+//! in its own palette.
+//!
+//! Bands run across the screen (horizontal, like most flags) or down it
+//! (vertical). With horizontal bands a column changes color as it falls
+//! from one band into the next. A single flag name such as `pride` expands
+//! to that flag's bands.
+
+use crate::color::Color;
+use crate::color::palette::{Palette, palette_by_name};
+
+/// Which way the `--stripes` bands run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum StripeDirection {
+    /// Bands stacked top to bottom
+    #[default]
+    Horizontal,
+    /// Bands side by side, left to right
+    Vertical,
+}
+
+/// Built-in flags: a name and its bands, top to bottom.
+const FLAGS: &[(&str, &[&str])] = &[
+    (
+        "pride",
+        &["red", "orange", "gold", "classic", "blue", "purple"],
+    ),
+    (
+        "trans",
+        &["lightskyblue", "pink", "silver", "pink", "lightskyblue"],
+    ),
+    ("bi", &["deeppink", "deeppink", "purple", "blue", "blue"]),
+];
+
+/// Names accepted by `--stripes` as a whole flag.
+pub fn flag_names() -> impl Iterator<Item = &'static str> {
+    FLAGS.iter().map(|&(name, _)| name)
+}
+
+/// Whether `name` can be a band: a palette, or a flag (on its own).
+pub fn is_band_name(name: &str) -> bool {
+    crate::color::palette::is_palette_name(name)
+        || flag_names().any(|f| f.eq_ignore_ascii_case(name))
+}
+
+/// The screen's bands, each with its own palette.
+pub struct Stripes {
+    bands: Vec<Palette>,
+    direction: StripeDirection,
+}
+
+impl Stripes {
+    /// Bands from palette names (or one flag name), with every band's
+    /// highlight replaced by `highlight` if given. `None` without names.
+    pub fn new(
+        names: &[String],
+        direction: StripeDirection,
+        highlight: Option<Color>,
+    ) -> Option<Self> {
+        let names: Vec<&str> = match names {
+            [one] => match FLAGS
+                .iter()
+                .find(|(flag, _)| flag.eq_ignore_ascii_case(one))
+            {
+                Some(&(_, bands)) => bands.to_vec(),
+                None => vec![one.as_str()],
+            },
+            _ => names.iter().map(String::as_str).collect(),
+        };
+        (!names.is_empty()).then(|| Self {
+            bands: names
+                .iter()
+                .map(|name| palette_by_name(name).with_highlight(highlight))
+                .collect(),
+            direction,
+        })
+    }
+
+    /// The palette for cell (`x`, `y`) of a `width` x `height` screen.
+    pub fn palette_at(&self, x: u16, y: u16, width: u16, height: u16) -> &Palette {
+        let (pos, extent) = match self.direction {
+            StripeDirection::Horizontal => (y, height),
+            StripeDirection::Vertical => (x, width),
+        };
+        let band = pos as usize * self.bands.len() / extent.max(1) as usize;
+        &self.bands[band.min(self.bands.len() - 1)]
+    }
+}
+
+/// Where rain cells take their colors from.
+#[derive(Clone, Copy)]
+pub enum Coloring<'a> {
+    /// One palette for the whole screen
+    Solid(&'a Palette),
+    /// A palette per `--stripes` band
+    Striped(&'a Stripes),
+}
+
+impl<'a> Coloring<'a> {
+    /// The solid palette, or the stripes if there are any.
+    pub fn new(palette: &'a Palette, stripes: Option<&'a Stripes>) -> Self {
+        match stripes {
+            Some(stripes) => Coloring::Striped(stripes),
+            None => Coloring::Solid(palette),
+        }
+    }
+
+    /// The palette for cell (`x`, `y`) of a `width` x `height` screen.
+    pub fn at(&self, x: u16, y: u16, width: u16, height: u16) -> &'a Palette {
+        match *self {
+            Coloring::Solid(palette) => palette,
+            Coloring::Striped(stripes) => stripes.palette_at(x, y, width, height),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_expand_and_bands_split_the_screen() {
+        let pride =
+            Stripes::new(&["pride".to_string()], StripeDirection::Horizontal, None).unwrap();
+        assert_eq!(pride.bands.len(), 6);
+        let red = palette_by_name("red").head;
+        let purple = palette_by_name("purple").head;
+        assert_eq!(pride.palette_at(5, 0, 40, 12).head, red);
+        assert_eq!(pride.palette_at(5, 11, 40, 12).head, purple);
+
+        let names = ["red".to_string(), "purple".to_string()];
+        let white = Color::Rgb {
+            r: 255,
+            g: 255,
+            b: 255,
+        };
+        let columns = Stripes::new(&names, StripeDirection::Vertical, Some(white)).unwrap();
+        assert_eq!(columns.palette_at(0, 9, 40, 12).head, red);
+        assert_eq!(columns.palette_at(39, 0, 40, 12).head, purple);
+        assert_eq!(columns.palette_at(39, 0, 40, 12).highlight, white);
+
+        assert!(Stripes::new(&[], StripeDirection::Horizontal, None).is_none());
+        assert!(is_band_name("trans") && is_band_name("coral") && !is_band_name("plaid"));
+    }
+}
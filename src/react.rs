@@ -0,0 +1,195 @@
+//! Load-reactive rain for `--react`: system load drives density and speed.
+//!
+//! The `--sysmon` sampler thread reads CPU, network, or disk activity once
+//! a second; the main loop turns the chosen reading into a 0.0-1.0 level
+//! and sends it to the effect as an `Event::Load`. `Reactive` wraps the
+//! effect and eases its density and speed toward that level: an idle
+//! machine drizzles, a busy one pours.
+//!
+//! The wrapper keeps the user's own speed and density (from flags and the
+//! +/- keys) as the base that the load scales, so `speed()` and `density()`
+//! report what the user set rather than the modulated values.
+
+use crate::buffer::{Region, RenderTarget};
+use crate::color::palette::Palette;
+use crate::effects::Effect;
+use crate::event::Event;
+use crate::rain::chars::CharacterPool;
+
+/// Which system reading drives the rain (`--react`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReactSource {
+    /// Average CPU usage across all cores
+    Cpu,
+    /// Network traffic, received plus sent
+    Net,
+    /// Disk traffic, read plus written
+    Disk,
+}
+
+/// Density multiplier at zero load and at full load.
+const DRIZZLE: f64 = 0.25;
+const TORRENT: f64 = 4.0;
+
+/// Speed swings over this share of the density range (on a log scale), so
+/// a downpour falls faster but doesn't blur.
+const SPEED_SHARE: f64 = 0.4;
+
+/// Seconds for the rain to settle most of the way to a new level.
+const EASE_SECS: f64 = 1.5;
+
+/// Byte rates that count as no load and full load, for network and disk.
+const IDLE_BYTES_PER_SEC: f64 = 1024.0;
+const BUSY_BYTES_PER_SEC: f64 = 100.0 * 1024.0 * 1024.0;
+
+/// Load level (0.0-1.0) for a byte rate, on a log scale between idle and
+/// busy, so a trickle and a flood both register.
+pub fn byte_rate_level(bytes_per_sec: u64) -> f64 {
+    let rate = (bytes_per_sec as f64).max(IDLE_BYTES_PER_SEC);
+    ((rate / IDLE_BYTES_PER_SEC).ln() / (BUSY_BYTES_PER_SEC / IDLE_BYTES_PER_SEC).ln())
+        .clamp(0.0, 1.0)
+}
+
+/// An effect whose density and speed follow the `Event::Load` level.
+pub struct Reactive {
+    inner: Box<dyn Effect>,
+    /// The user's speed and density, before the load scales them
+    base_speed: f64,
+    base_density: f64,
+    /// Eased load level, and the latest one reported (`None` until the
+    /// first reading, leaving the rain as configured)
+    level: Option<f64>,
+    target: Option<f64>,
+}
+
+impl Reactive {
+    pub fn new(inner: Box<dyn Effect>) -> Self {
+        Self {
+            base_speed: inner.speed(),
+            base_density: inner.density(),
+            inner,
+            level: None,
+            target: None,
+        }
+    }
+
+    /// Density multiplier for the current level (1.0 before any reading).
+    fn density_factor(&self) -> f64 {
+        self.level
+            .map_or(1.0, |level| DRIZZLE * (TORRENT / DRIZZLE).powf(level))
+    }
+
+    fn apply(&mut self) {
+        let density = self.density_factor();
+        self.inner.set_density(self.base_density * density);
+        self.inner
+            .set_speed(self.base_speed * density.powf(SPEED_SHARE));
+    }
+}
+
+impl Effect for Reactive {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn update(&mut self, delta_time: f64) {
+        if let Some(target) = self.target {
+            let level = self.level.unwrap_or(target);
+            let ease = 1.0 - (-delta_time / EASE_SECS).exp();
+            self.level = Some(level + (target - level) * ease);
+            self.apply();
+        }
+        self.inner.update(delta_time);
+    }
+
+    fn render(&self, buffer: &mut dyn RenderTarget) {
+        self.inner.render(buffer);
+    }
+
+    fn resize(&mut self, width: u16, height: u16) {
+        self.inner.resize(width, height);
+    }
+
+    fn set_speed(&mut self, multiplier: f64) {
+        self.base_speed = multiplier;
+        self.apply();
+    }
+
+    fn speed(&self) -> f64 {
+        self.base_speed
+    }
+
+    fn set_density(&mut self, multiplier: f64) {
+        self.base_density = multiplier;
+        self.apply();
+    }
+
+    fn density(&self) -> f64 {
+        self.base_density
+    }
+
+    fn set_mutation(&mut self, rate: f64) {
+        self.inner.set_mutation(rate);
+    }
+
+    fn set_dead_zones(&mut self, zones: &[Region]) {
+        self.inner.set_dead_zones(zones);
+    }
+
+    fn set_palette(&mut self, palette: &Palette) {
+        self.inner.set_palette(palette);
+    }
+
+    fn set_charset(&mut self, pool: &CharacterPool) {
+        self.inner.set_charset(pool);
+    }
+
+    fn on_event(&mut self, event: &Event) {
+        match *event {
+            Event::Load { level } => self.target = Some(level.clamp(0.0, 1.0)),
+            _ => self.inner.on_event(event),
+        }
+    }
+
+    fn active_columns(&self) -> Option<usize> {
+        self.inner.active_columns()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Cli, Config, ConfigFile};
+    use crate::effects::classic::ClassicRain;
+    use clap::Parser;
+
+    #[test]
+    fn load_scales_the_users_density_and_speed() {
+        let config = Config::resolve(&Cli::parse_from(["digital_rain"]), &ConfigFile::default());
+        let mut effect = Reactive::new(Box::new(ClassicRain::with_config(20, 10, &config)));
+        effect.set_density(2.0);
+        effect.update(0.1);
+        assert_eq!(effect.inner.density(), 2.0);
+
+        // Idle drizzles, a busy machine pours; the base stays as set
+        effect.on_event(&Event::Load { level: 0.0 });
+        effect.update(0.1);
+        assert!((effect.inner.density() - 2.0 * DRIZZLE).abs() < 1e-9);
+        assert!(effect.inner.speed() < 1.0);
+        effect.on_event(&Event::Load { level: 1.0 });
+        for _ in 0..300 {
+            effect.update(0.1);
+        }
+        assert!((effect.inner.density() - 2.0 * TORRENT).abs() < 1e-6);
+        assert!(effect.inner.speed() > 1.5);
+        assert_eq!((effect.density(), effect.speed()), (2.0, 1.0));
+    }
+
+    #[test]
+    fn byte_rates_map_onto_a_log_scale() {
+        assert_eq!(byte_rate_level(0), 0.0);
+        assert_eq!(byte_rate_level(1024), 0.0);
+        assert!((byte_rate_level(1024 * 1024) - 0.6).abs() < 0.01);
+        assert_eq!(byte_rate_level(u64::MAX), 1.0);
+    }
+}
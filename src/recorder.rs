@@ -0,0 +1,164 @@
+//! asciicast v2 session recording (`--record out.cast`).
+//!
+//! The recorder captures the exact escape-sequence stream sent to the
+//! terminal, one output event per flushed frame, so a session can be
+//! replayed with `asciinema play` or converted with `agg`. The file is a
+//! JSON header line followed by one `[time, "o", data]` line per event.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::formats::quote;
+
+/// Setup sequence the live terminal receives from `Terminal::init`: hide the
+/// cursor and clear the screen. Recorded at time zero so replays start clean.
+const INIT_SEQUENCE: &str = "\x1b[?25l\x1b[2J";
+
+/// Writes an asciicast v2 file as the session runs.
+pub struct CastRecorder<W: Write = BufWriter<File>> {
+    out: W,
+    start: Instant,
+}
+
+impl CastRecorder {
+    /// Create `path` and write the cast header for a `width` x `height` terminal.
+    pub fn create(path: &Path, width: u16, height: u16) -> Result<Self, String> {
+        let file = File::create(path)
+            .map_err(|e| format!("Could not create {}: {}", path.display(), e))?;
+        CastRecorder::new(BufWriter::new(file), width, height)
+            .map_err(|e| format!("Could not write {}: {}", path.display(), e))
+    }
+}
+
+impl<W: Write> CastRecorder<W> {
+    /// Write the header and initial screen setup to `out`.
+    pub fn new(mut out: W, width: u16, height: u16) -> io::Result<Self> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let term = std::env::var("TERM").unwrap_or_else(|_| "xterm-256color".to_string());
+        writeln!(
+            out,
+            "{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {}, \"env\": {{\"TERM\": {}}}}}",
+            width,
+            height,
+            timestamp,
+            quote(&term)
+        )?;
+
+        let mut recorder = Self {
+            out,
+            start: Instant::now(),
+        };
+        recorder.event(0.0, "o", INIT_SEQUENCE)?;
+        Ok(recorder)
+    }
+
+    /// Record output bytes at the current time.
+    pub fn output(&mut self, data: &[u8]) -> io::Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let time = self.start.elapsed().as_secs_f64();
+        self.event(time, "o", &String::from_utf8_lossy(data))
+    }
+
+    /// Record a terminal resize at the current time.
+    pub fn resize(&mut self, width: u16, height: u16) -> io::Result<()> {
+        let time = self.start.elapsed().as_secs_f64();
+        self.event(time, "r", &format!("{}x{}", width, height))
+    }
+
+    /// A writer that forwards to `inner` and records everything written to it
+    /// as a single event on each `flush`.
+    pub fn tee<I: Write>(&mut self, inner: I) -> Tee<'_, W, I> {
+        Tee {
+            recorder: self,
+            inner,
+            pending: Vec::new(),
+        }
+    }
+
+    fn event(&mut self, time: f64, kind: &str, data: &str) -> io::Result<()> {
+        writeln!(self.out, "[{:.6}, \"{}\", {}]", time, kind, quote(data))
+    }
+}
+
+/// Output tee returned by `CastRecorder::tee`.
+pub struct Tee<'a, W: Write, I: Write> {
+    recorder: &'a mut CastRecorder<W>,
+    inner: I,
+    pending: Vec<u8>,
+}
+
+impl<W: Write, I: Write> Write for Tee<'_, W, I> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.pending.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        let data = std::mem::take(&mut self.pending);
+        self.recorder.output(&data)?;
+        self.recorder.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::ScreenBuffer;
+    use crate::color::Color;
+    use crate::formats::parse_json;
+    use toml::Value;
+
+    fn record_frame() -> (Vec<u8>, String) {
+        let mut cast = Vec::new();
+        let mut screen = Vec::new();
+        {
+            let mut recorder = CastRecorder::new(&mut cast, 4, 2).unwrap();
+            let mut buffer = ScreenBuffer::new(4, 2);
+            buffer.set_cell(1, 0, 'Z', Color::Rgb { r: 0, g: 255, b: 0 }, Color::Reset);
+            buffer.flush_to(&mut recorder.tee(&mut screen)).unwrap();
+            recorder.resize(8, 3).unwrap();
+        }
+        (screen, String::from_utf8(cast).unwrap())
+    }
+
+    #[test]
+    fn header_and_events_are_valid_json() {
+        let (_, cast) = record_frame();
+        let lines: Vec<&str> = cast.lines().collect();
+        assert_eq!(lines.len(), 4);
+
+        let header = parse_json(lines[0]).unwrap();
+        assert_eq!(header.get("version"), Some(&Value::Integer(2)));
+        assert_eq!(header.get("width"), Some(&Value::Integer(4)));
+        assert_eq!(header.get("height"), Some(&Value::Integer(2)));
+
+        let kinds: Vec<String> = lines[1..]
+            .iter()
+            .map(|l| {
+                let event = parse_json(l).unwrap();
+                let event = event.as_array().unwrap();
+                assert!(event[0].as_float().unwrap() >= 0.0);
+                event[1].as_str().unwrap().to_string()
+            })
+            .collect();
+        assert_eq!(kinds, ["o", "o", "r"]);
+    }
+
+    #[test]
+    fn frame_event_matches_terminal_output() {
+        let (screen, cast) = record_frame();
+        let frame = parse_json(cast.lines().nth(2).unwrap()).unwrap();
+        let data = frame.as_array().unwrap()[2].as_str().unwrap().to_string();
+        assert_eq!(data.as_bytes(), screen.as_slice());
+        assert!(data.contains('Z'));
+    }
+}
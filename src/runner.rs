@@ -0,0 +1,179 @@
+//! Frame runner: advances an effect, composes each frame, and hands it to
+//! any registered observers.
+//!
+//! A `Runner` owns what one frame needs (the effect, the post-processing
+//! chain, and the buffer) so headless export and library users share a
+//! single loop. Observers registered with `on_frame` see every composed
+//! frame, for streaming, analysis, or capture:
+//!
+//! ```
+//! use clap::Parser;
+//! use digitalrain::config::{Cli, Config, ConfigFile};
+//! use digitalrain::runner::Runner;
+//!
+//! let config = Config::resolve(&Cli::parse_from(["digital_rain"]), &ConfigFile::default());
+//! let mut runner = Runner::new(&config, 40, 12).unwrap();
+//! runner.on_frame(|buffer, stats| {
+//!     println!("frame {} at {:.2}s: {} cells", stats.frame, stats.elapsed, buffer.cells().len());
+//! });
+//! runner.step(1.0 / 30.0);
+//! ```
+
+use std::io::{self, Write};
+
+use crate::buffer::ScreenBuffer;
+use crate::config::Config;
+use crate::effects::{Effect, registry};
+use crate::event::Event;
+use crate::post::{ColorTransform, PipelineChain, PostProcessor};
+
+/// Longest step, in seconds, that an embedding host's clock may advance
+/// the rain by at once. A tab coming back from the background or a host
+/// waking from sleep would otherwise ask for minutes of simulation in one
+/// frame, and effects that simulate in fixed ticks would take as long.
+pub const MAX_HOST_STEP: f64 = 0.1;
+
+/// `delta_time` from a host's clock, made safe to `step` by: negative and
+/// NaN become 0 and long gaps are capped at `MAX_HOST_STEP`.
+pub fn host_step(delta_time: f64) -> f64 {
+    if delta_time.is_nan() {
+        0.0
+    } else {
+        delta_time.clamp(0.0, MAX_HOST_STEP)
+    }
+}
+
+/// Where a composed frame falls in the run.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrameStats {
+    /// Frame number, counting from 1
+    pub frame: u64,
+    /// Seconds of simulation time up to and including this frame
+    pub elapsed: f64,
+    /// Time step this frame advanced by
+    pub delta_time: f64,
+}
+
+/// A callback that receives every composed frame.
+pub type FrameObserver = Box<dyn FnMut(&ScreenBuffer, FrameStats)>;
+
+/// Runs one effect frame by frame.
+pub struct Runner {
+    effect: Box<dyn Effect>,
+    post_chain: PipelineChain,
+    color_transform: ColorTransform,
+    buffer: ScreenBuffer,
+    observers: Vec<FrameObserver>,
+    frame: u64,
+    elapsed: f64,
+}
+
+impl Runner {
+    /// `config`'s effect, post filters, and tone at `width` x `height`.
+    pub fn new(config: &Config, width: u16, height: u16) -> Result<Self, String> {
+        let effect = registry::create_effect(&config.effect_spec(), width, height, config)?;
+        let mut buffer = ScreenBuffer::new(width, height);
+        buffer.set_background(config.background);
+        buffer.clear();
+        Ok(Self {
+            effect,
+            post_chain: PipelineChain::from_config(width, height, config),
+            color_transform: ColorTransform::from_config(config),
+            buffer,
+            observers: Vec::new(),
+            frame: 0,
+            elapsed: 0.0,
+        })
+    }
+
+    /// Call `observer` with every frame from now on, in registration order.
+    pub fn on_frame(&mut self, observer: impl FnMut(&ScreenBuffer, FrameStats) + 'static) {
+        self.observers.push(Box::new(observer));
+    }
+
+    /// Advance by `delta_time` seconds, compose the frame, and notify the
+    /// observers. Returns the finished frame.
+    pub fn step(&mut self, delta_time: f64) -> &ScreenBuffer {
+        self.effect.update(delta_time);
+        self.buffer.clear();
+        self.effect.render(&mut self.buffer);
+        self.post_chain.apply(&mut self.buffer, delta_time);
+        self.color_transform.apply(&mut self.buffer, delta_time);
+
+        self.frame += 1;
+        self.elapsed += delta_time;
+        let stats = FrameStats {
+            frame: self.frame,
+            elapsed: self.elapsed,
+            delta_time,
+        };
+        for observer in &mut self.observers {
+            observer(&self.buffer, stats);
+        }
+        &self.buffer
+    }
+
+    /// The last composed frame.
+    pub fn buffer(&self) -> &ScreenBuffer {
+        &self.buffer
+    }
+
+    /// Write the cells that changed since the last flush to `out` as ANSI
+    /// escapes (everything on the first flush or after a resize).
+    pub fn flush_to(&mut self, out: &mut impl Write) -> io::Result<()> {
+        self.buffer.flush_to(out)
+    }
+
+    /// Pass an outside event (such as a beat) to the effect and filters.
+    pub fn on_event(&mut self, event: &Event) {
+        self.effect.on_event(event);
+        self.post_chain.on_event(event);
+    }
+
+    /// The effect, for live changes (speed, palette, ...).
+    pub fn effect_mut(&mut self) -> &mut dyn Effect {
+        self.effect.as_mut()
+    }
+
+    /// Resize the effect, filters, and buffer.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.effect.resize(width, height);
+        self.post_chain.resize(width, height);
+        self.buffer.resize(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Cli, ConfigFile};
+    use clap::Parser;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn observers_see_every_frame_in_order() {
+        let config = Config::resolve(
+            &Cli::parse_from(["digital_rain", "--seed", "5"]),
+            &ConfigFile::default(),
+        );
+        let mut runner = Runner::new(&config, 20, 8).unwrap();
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let log = Rc::clone(&seen);
+        runner.on_frame(move |buffer, stats| {
+            log.borrow_mut()
+                .push((stats.frame, buffer.to_plain_string()))
+        });
+
+        let mut frames = Vec::new();
+        for _ in 0..3 {
+            frames.push(runner.step(0.5).to_plain_string());
+        }
+        runner.resize(10, 4);
+        assert_eq!(runner.step(0.5).height(), 4);
+
+        let seen = seen.borrow();
+        assert_eq!(seen.iter().map(|s| s.0).collect::<Vec<_>>(), [1, 2, 3, 4]);
+        assert!(seen.iter().zip(&frames).all(|(s, f)| &s.1 == f));
+    }
+}
@@ -0,0 +1,250 @@
+//! Auto-cycle scheduling: decides when the main loop should switch configs.
+//!
+//! Two modes share the same timer logic:
+//! - **Random** -- every `interval` seconds, pick a random config (`--timer`)
+//! - **Playlist** -- step through an ordered list of presets, each with its
+//!   own duration, wrapping around at the end (`--playlist`)
+//!
+//! The scheduler only decides *what* and *when*; the main loop still owns
+//! effect creation and the crossfade transition.
+
+use crate::config::{Config, ConfigFile, PlaylistStep, RandomConstraints};
+use crate::rng::{self, SimRng, Stream};
+
+/// Interval used when auto-cycle is toggled on without `--timer`.
+const DEFAULT_CYCLE_SECS: f64 = 30.0;
+
+/// Which kind of schedule is running.
+enum Mode {
+    Random {
+        interval: Option<f64>,
+        constraints: RandomConstraints,
+        /// Boxed: the generator state dwarfs the playlist variant
+        rng: Box<SimRng>,
+    },
+    Playlist {
+        steps: Vec<PlaylistStep>,
+        index: usize,
+    },
+}
+
+/// A config switch requested by the scheduler.
+pub struct ScheduledSwitch {
+    pub config: Config,
+    /// Status line describing the switch (e.g. "Playlist: retro (2/4)")
+    pub description: String,
+}
+
+/// Tracks elapsed time and produces the next config when it's due.
+pub struct Scheduler {
+    mode: Mode,
+    enabled: bool,
+    elapsed: f64,
+}
+
+impl Scheduler {
+    /// Random auto-cycle. Starts enabled only if an interval was given.
+    pub fn random(
+        interval: Option<f64>,
+        constraints: RandomConstraints,
+        seed: Option<u64>,
+    ) -> Self {
+        Self {
+            mode: Mode::Random {
+                interval,
+                constraints,
+                rng: Box::new(rng::make_rng(seed, Stream::Scheduler)),
+            },
+            enabled: interval.is_some(),
+            elapsed: 0.0,
+        }
+    }
+
+    /// Playlist cycling. The first step is assumed to already be showing.
+    pub fn playlist(steps: Vec<PlaylistStep>) -> Self {
+        Self {
+            mode: Mode::Playlist { steps, index: 0 },
+            enabled: true,
+            elapsed: 0.0,
+        }
+    }
+
+    /// The config of the currently showing playlist step (None in random mode).
+    pub fn current_config(&self) -> Option<&Config> {
+        match &self.mode {
+            Mode::Playlist { steps, index } => steps.get(*index).map(|s| &s.config),
+            Mode::Random { .. } => None,
+        }
+    }
+
+    /// Toggle auto-cycling on/off. Returns a status message describing the new state.
+    pub fn toggle(&mut self) -> String {
+        // If no interval was set via --timer, fall back to the default
+        if let Mode::Random { interval, .. } = &mut self.mode
+            && interval.is_none()
+        {
+            *interval = Some(DEFAULT_CYCLE_SECS);
+        }
+        self.enabled = !self.enabled;
+        self.elapsed = 0.0;
+
+        match (&self.mode, self.enabled) {
+            (_, false) => "Auto-cycle: OFF".to_string(),
+            (Mode::Random { interval, .. }, true) => {
+                format!(
+                    "Auto-cycle: ON ({:.0}s)",
+                    interval.unwrap_or(DEFAULT_CYCLE_SECS)
+                )
+            }
+            (Mode::Playlist { steps, .. }, true) => {
+                format!("Playlist: ON ({} entries)", steps.len())
+            }
+        }
+    }
+
+    /// Restart the countdown (e.g. after the user manually switched effects).
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+
+    /// Advance the timer. Returns a switch when the current slot has expired.
+    ///
+    /// `current` is the active config, used to carry settings (like gradient
+    /// direction) that random mode shouldn't change. `config_file` supplies
+    /// per-effect defaults for randomly picked effects.
+    pub fn update(
+        &mut self,
+        delta_time: f64,
+        current: &Config,
+        config_file: &ConfigFile,
+    ) -> Option<ScheduledSwitch> {
+        if !self.enabled {
+            return None;
+        }
+        self.elapsed += delta_time;
+
+        match &mut self.mode {
+            Mode::Random {
+                interval,
+                constraints,
+                rng,
+            } => {
+                let interval = (*interval)?;
+                if self.elapsed < interval {
+                    return None;
+                }
+                self.elapsed = 0.0;
+
+                let mut config = Config::randomized(constraints, rng.as_mut());
+                config.keep_runtime_settings(current);
+                config.apply_effect_defaults(config_file);
+                let description = format!(
+                    "Auto: {} / {} / {:.1}x",
+                    config.effect_name, config.palette_name, config.speed_multiplier,
+                );
+                Some(ScheduledSwitch {
+                    config,
+                    description,
+                })
+            }
+            Mode::Playlist { steps, index } => {
+                if self.elapsed < steps[*index].duration {
+                    return None;
+                }
+                self.elapsed = 0.0;
+
+                *index = (*index + 1) % steps.len();
+                let step = &steps[*index];
+                Some(ScheduledSwitch {
+                    config: step.config.clone(),
+                    description: format!(
+                        "Playlist: {} ({}/{})",
+                        step.preset,
+                        *index + 1,
+                        steps.len()
+                    ),
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Cli, ConfigFile};
+    use clap::Parser;
+
+    fn step(preset: &str, effect: &str, duration: f64) -> PlaylistStep {
+        let cli = Cli::parse_from(["digital_rain", "-e", effect]);
+        PlaylistStep {
+            preset: preset.to_string(),
+            config: Config::resolve(&cli, &ConfigFile::default()),
+            duration,
+        }
+    }
+
+    fn base_config() -> Config {
+        Config::resolve(&Cli::parse_from(["digital_rain"]), &ConfigFile::default())
+    }
+
+    #[test]
+    fn random_without_interval_never_fires() {
+        let mut s = Scheduler::random(None, RandomConstraints::default(), None);
+        assert!(
+            s.update(1000.0, &base_config(), &ConfigFile::default())
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn random_fires_after_interval_and_keeps_forward() {
+        let mut s = Scheduler::random(Some(5.0), RandomConstraints::default(), None);
+        let mut current = base_config();
+        current.forward = true;
+        assert!(s.update(4.0, &current, &ConfigFile::default()).is_none());
+        let switch = s
+            .update(1.5, &current, &ConfigFile::default())
+            .expect("should switch");
+        assert!(switch.config.forward);
+    }
+
+    #[test]
+    fn toggle_enables_random_with_default_interval() {
+        let mut s = Scheduler::random(None, RandomConstraints::default(), None);
+        assert_eq!(s.toggle(), "Auto-cycle: ON (30s)");
+        assert!(
+            s.update(31.0, &base_config(), &ConfigFile::default())
+                .is_some()
+        );
+        assert_eq!(s.toggle(), "Auto-cycle: OFF");
+    }
+
+    #[test]
+    fn playlist_advances_in_order_and_wraps() {
+        let mut s = Scheduler::playlist(vec![step("a", "fire", 2.0), step("b", "ocean", 3.0)]);
+        let current = base_config();
+        assert_eq!(s.current_config().unwrap().effect_name, "fire");
+
+        // First entry lasts 2s
+        assert!(s.update(1.0, &current, &ConfigFile::default()).is_none());
+        let next = s.update(1.0, &current, &ConfigFile::default()).unwrap();
+        assert_eq!(next.config.effect_name, "ocean");
+        assert_eq!(next.description, "Playlist: b (2/2)");
+
+        // Second entry lasts 3s, then wraps to the first
+        assert!(s.update(2.0, &current, &ConfigFile::default()).is_none());
+        let wrapped = s.update(1.0, &current, &ConfigFile::default()).unwrap();
+        assert_eq!(wrapped.config.effect_name, "fire");
+    }
+
+    #[test]
+    fn playlist_pauses_when_toggled_off() {
+        let mut s = Scheduler::playlist(vec![step("a", "fire", 1.0), step("b", "ocean", 1.0)]);
+        s.toggle();
+        assert!(
+            s.update(10.0, &base_config(), &ConfigFile::default())
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,365 @@
+//! Streaming server for `--serve`: rain for anyone who connects, as raw
+//! ANSI over telnet (`telnet host 2323`, or `nc host 2323`).
+//!
+//! Each viewer gets a thread with its own `Runner`, so every connection is
+//! an independent animation sized to its terminal. Sizes come from the
+//! telnet NAWS option (RFC 1073): the server asks for it on connect and
+//! resizes the viewer's frame whenever the terminal reports a new size.
+//! Clients that never answer get 80x24. The server also offers to echo
+//! and suppress go-ahead, which puts telnet clients in character mode so
+//! `q` leaves without Enter.
+//!
+//! At most `max_clients` viewers watch at once; anyone past the cap is
+//! told so and disconnected.
+//!
+//! `play` is the transport-neutral half of a viewer: the `ssh` module feeds
+//! it the same resize and quit inputs from an SSH channel.
+
+use std::io::{self, BufWriter, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::runner::Runner;
+
+/// Viewers served at once when `--serve-max-clients` isn't given.
+pub const DEFAULT_MAX_CLIENTS: usize = 16;
+
+/// Size for clients that don't report one.
+pub const FALLBACK_SIZE: (u16, u16) = (80, 24);
+/// Largest size a client may ask for, so one viewer can't make the server
+/// allocate huge frames.
+const MAX_SIZE: (u16, u16) = (400, 200);
+/// How long to wait for the client's size before starting the animation.
+const NAWS_WAIT: Duration = Duration::from_secs(1);
+
+// Telnet protocol bytes (RFC 854) and options
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const SE: u8 = 240;
+const ECHO: u8 = 1;
+const SUPPRESS_GO_AHEAD: u8 = 3;
+const NAWS: u8 = 31;
+
+/// Clear the screen and hide the cursor before the first frame.
+const SCREEN_START: &[u8] = b"\x1b[0m\x1b[2J\x1b[?25l";
+/// Reset colors, clear, and show the cursor again when a viewer leaves.
+const SCREEN_END: &[u8] = b"\x1b[0m\x1b[2J\x1b[H\x1b[?25h";
+
+/// Something a viewer's terminal sent, with telnet negotiation decoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClientInput {
+    /// The window is now this many columns and rows
+    Resize(u16, u16),
+    /// `q`, Ctrl+C, or Ctrl+D
+    Quit,
+}
+
+#[derive(Clone, Copy, Default)]
+enum TelnetState {
+    #[default]
+    Data,
+    /// After IAC
+    Command,
+    /// After IAC WILL/WONT/DO/DONT, before the option byte
+    Option,
+    /// Inside IAC SB ... IAC SE
+    Subnegotiation,
+    /// After IAC inside a subnegotiation
+    SubnegotiationIac,
+}
+
+/// Decodes the byte stream from a telnet client. Bytes may arrive split
+/// anywhere, including inside a command.
+#[derive(Default)]
+pub struct TelnetParser {
+    state: TelnetState,
+    /// Payload of the current subnegotiation
+    sub: Vec<u8>,
+}
+
+impl TelnetParser {
+    /// Feed received bytes; returns the inputs they complete.
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<ClientInput> {
+        let mut inputs = Vec::new();
+        for &byte in bytes {
+            self.state = match (self.state, byte) {
+                (TelnetState::Data, IAC) => TelnetState::Command,
+                (TelnetState::Data, b'q' | b'Q' | 3 | 4) => {
+                    inputs.push(ClientInput::Quit);
+                    TelnetState::Data
+                }
+                (TelnetState::Data, _) => TelnetState::Data,
+                (TelnetState::Command, WILL..=DONT) => TelnetState::Option,
+                (TelnetState::Command, SB) => {
+                    self.sub.clear();
+                    TelnetState::Subnegotiation
+                }
+                // An escaped 255 data byte, or a command without an option
+                (TelnetState::Command, _) | (TelnetState::Option, _) => TelnetState::Data,
+                (TelnetState::Subnegotiation, IAC) => TelnetState::SubnegotiationIac,
+                (TelnetState::Subnegotiation, _) => {
+                    // Bounded: NAWS needs five bytes, other options are ignored
+                    if self.sub.len() < 64 {
+                        self.sub.push(byte);
+                    }
+                    TelnetState::Subnegotiation
+                }
+                (TelnetState::SubnegotiationIac, IAC) => {
+                    self.sub.push(IAC);
+                    TelnetState::Subnegotiation
+                }
+                (TelnetState::SubnegotiationIac, SE) => {
+                    if let [NAWS, w1, w0, h1, h0, ..] = self.sub[..] {
+                        let width = u16::from_be_bytes([w1, w0]);
+                        let height = u16::from_be_bytes([h1, h0]);
+                        if let Some((width, height)) = viewer_size(width.into(), height.into()) {
+                            inputs.push(ClientInput::Resize(width, height));
+                        }
+                    }
+                    TelnetState::Data
+                }
+                (TelnetState::SubnegotiationIac, _) => TelnetState::Data,
+            };
+        }
+        inputs
+    }
+}
+
+/// A listening `--serve` socket.
+pub struct Server {
+    listener: TcpListener,
+    max_clients: usize,
+}
+
+impl Server {
+    /// Listen on `address`: `host:port`, or `:port` for every interface.
+    pub fn bind(address: &str, max_clients: usize) -> Result<Self, String> {
+        let address = listen_address(address);
+        let listener = TcpListener::bind(&address)
+            .map_err(|e| format!("Can't listen on {}: {}", address, e))?;
+        Ok(Self {
+            listener,
+            max_clients: max_clients.max(1),
+        })
+    }
+
+    /// The address actually bound (useful with port 0).
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accept viewers until the process ends, streaming `config`'s effect
+    /// to each.
+    pub fn run(self, config: &Config) {
+        let viewers = Arc::new(AtomicUsize::new(0));
+        for stream in self.listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let peer = stream
+                .peer_addr()
+                .map(|a| a.to_string())
+                .unwrap_or_else(|_| "unknown".to_string());
+            if viewers.load(Ordering::SeqCst) >= self.max_clients {
+                let _ = stream.write_all(b"Every seat is taken; try again soon.\r\n");
+                eprintln!("Turned away {}: {} viewers already", peer, self.max_clients);
+                continue;
+            }
+            let count = viewers.fetch_add(1, Ordering::SeqCst) + 1;
+            eprintln!("Viewer {} connected ({}/{})", peer, count, self.max_clients);
+
+            let config = config.clone();
+            let viewers = Arc::clone(&viewers);
+            thread::spawn(move || {
+                // Closing explicitly also ends the thread reading the client
+                let socket = stream.try_clone();
+                let result = stream_to(stream, &config);
+                if let Ok(socket) = socket {
+                    let _ = socket.shutdown(Shutdown::Both);
+                }
+                viewers.fetch_sub(1, Ordering::SeqCst);
+                match result {
+                    Ok(()) => eprintln!("Viewer {} left", peer),
+                    Err(e) => eprintln!("Viewer {} dropped: {}", peer, e),
+                }
+            });
+        }
+    }
+}
+
+/// `host:port` to bind for a listen address, where `:port` means every
+/// interface.
+pub fn listen_address(address: &str) -> String {
+    match address.strip_prefix(':') {
+        Some(port) => format!("0.0.0.0:{}", port),
+        None => address.to_string(),
+    }
+}
+
+/// A window size a viewer reported, capped at `MAX_SIZE`; `None` for an
+/// empty window.
+pub fn viewer_size(width: u32, height: u32) -> Option<(u16, u16)> {
+    (width > 0 && height > 0).then(|| {
+        (
+            width.min(MAX_SIZE.0.into()) as u16,
+            height.min(MAX_SIZE.1.into()) as u16,
+        )
+    })
+}
+
+/// Stream the animation to one telnet viewer until they quit or disconnect.
+fn stream_to(stream: TcpStream, config: &Config) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+    let mut out = BufWriter::new(stream.try_clone()?);
+    out.write_all(&[IAC, DO, NAWS, IAC, WILL, ECHO, IAC, WILL, SUPPRESS_GO_AHEAD])?;
+    out.flush()?;
+
+    let inputs = read_inputs(stream);
+    let size = match inputs.recv_timeout(NAWS_WAIT) {
+        Ok(ClientInput::Resize(width, height)) => (width, height),
+        Ok(ClientInput::Quit) => return Ok(()),
+        Err(_) => FALLBACK_SIZE,
+    };
+    play(&mut out, &inputs, config, size)
+}
+
+/// Play `config`'s animation into `out` at `size`, following `inputs`,
+/// until a `Quit` arrives or writing fails. The viewer's screen is cleared
+/// first and restored at the end.
+pub fn play(
+    out: &mut impl Write,
+    inputs: &Receiver<ClientInput>,
+    config: &Config,
+    (width, height): (u16, u16),
+) -> io::Result<()> {
+    out.write_all(SCREEN_START)?;
+    let mut runner = Runner::new(config, width, height).map_err(io::Error::other)?;
+    let frame_time = Duration::from_secs_f64(1.0 / config.target_fps.max(1) as f64);
+
+    loop {
+        let started = Instant::now();
+        for input in inputs.try_iter() {
+            match input {
+                ClientInput::Resize(width, height) => {
+                    runner.resize(width, height);
+                    out.write_all(b"\x1b[2J")?;
+                }
+                ClientInput::Quit => return finish(out),
+            }
+        }
+        runner.step(frame_time.as_secs_f64());
+        runner.flush_to(out)?;
+        thread::sleep(frame_time.saturating_sub(started.elapsed()));
+    }
+}
+
+/// Leave the viewer's terminal as it was.
+fn finish(out: &mut impl Write) -> io::Result<()> {
+    out.write_all(SCREEN_END)?;
+    out.flush()
+}
+
+/// Read and decode the client's bytes on a thread of their own. The
+/// channel gets `Quit` when the connection closes.
+fn read_inputs(mut stream: TcpStream) -> Receiver<ClientInput> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut parser = TelnetParser::default();
+        let mut buf = [0u8; 256];
+        loop {
+            let n = match stream.read(&mut buf) {
+                Ok(0) | Err(_) => {
+                    let _ = tx.send(ClientInput::Quit);
+                    return;
+                }
+                Ok(n) => n,
+            };
+            for input in parser.feed(&buf[..n]) {
+                if tx.send(input).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Cli, ConfigFile};
+    use clap::Parser;
+
+    #[test]
+    fn decodes_window_sizes_and_quit_keys() {
+        let mut parser = TelnetParser::default();
+        // Option replies and a NAWS report split mid-command
+        assert_eq!(parser.feed(&[IAC, WILL, NAWS, IAC, SB, NAWS, 0]), vec![]);
+        assert_eq!(
+            parser.feed(&[100, 0, 30, IAC, SE, b'x']),
+            vec![ClientInput::Resize(100, 30)]
+        );
+        // A width of 255 arrives doubled; absurd sizes are capped
+        assert_eq!(
+            parser.feed(&[IAC, SB, NAWS, 0, IAC, IAC, 0, 40, IAC, SE]),
+            vec![ClientInput::Resize(255, 40)]
+        );
+        assert_eq!(
+            parser.feed(&[IAC, SB, NAWS, IAC, IAC, 0, IAC, IAC, 0, IAC, SE]),
+            vec![ClientInput::Resize(MAX_SIZE.0, MAX_SIZE.1)]
+        );
+        // A zero size is ignored; q inside a command isn't a keypress
+        assert_eq!(parser.feed(&[IAC, SB, NAWS, 0, 0, 0, 0, IAC, SE]), vec![]);
+        assert_eq!(parser.feed(&[IAC, DO, b'q']), vec![]);
+        assert_eq!(parser.feed(b"aq"), vec![ClientInput::Quit]);
+    }
+
+    #[test]
+    fn streams_a_sized_animation_until_the_viewer_quits() {
+        let server = Server::bind("127.0.0.1:0", 1).unwrap();
+        let address = server.local_addr().unwrap();
+        let config = Config::resolve(
+            &Cli::parse_from(["digital_rain", "--seed", "2", "--fps", "30"]),
+            &ConfigFile::default(),
+        );
+        thread::spawn(move || server.run(&config));
+
+        let mut client = TcpStream::connect(address).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        client
+            .write_all(&[IAC, SB, NAWS, 0, 12, 0, 5, IAC, SE])
+            .unwrap();
+
+        let mut received = Vec::new();
+        let mut buf = [0u8; 4096];
+        while !String::from_utf8_lossy(&received).contains("\x1b[5;12H") {
+            let n = client.read(&mut buf).unwrap();
+            assert!(n > 0, "server closed early");
+            received.extend_from_slice(&buf[..n]);
+        }
+        assert!(received.starts_with(&[IAC, DO, NAWS]));
+        let text = String::from_utf8_lossy(&received);
+        // Nothing is drawn outside the reported 12x5 window
+        assert!(!text.contains("\x1b[6;") && !text.contains(";13H"));
+
+        // A second viewer is over the cap
+        let mut extra = TcpStream::connect(address).unwrap();
+        let mut refusal = String::new();
+        extra.read_to_string(&mut refusal).unwrap();
+        assert!(refusal.contains("try again"));
+
+        client.write_all(b"q").unwrap();
+        let mut rest = Vec::new();
+        client.read_to_end(&mut rest).unwrap();
+        received.extend(rest);
+        assert!(String::from_utf8_lossy(&received).contains("\x1b[?25h"));
+    }
+}
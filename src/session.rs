@@ -0,0 +1,483 @@
+//! Session recording and replay (`--record-session`, `--replay`).
+//!
+//! A `.drr` file holds everything needed to run a session again: the
+//! session seed, the command line, the terminal size, and every frame
+//! time and input event in the order the main loop saw them. Since all
+//! randomness is derived from the seed, feeding the same frame times and
+//! input back reproduces the session exactly, as long as it plays in a
+//! terminal of the same size with the same config file.
+//!
+//! The format is JSON lines, like `--record`'s asciicast: a header object,
+//! then one array per step:
+//!
+//! ```text
+//! {"version": 1, "seed": "42", "width": 80, "height": 24, "args": ["-e", "glitch"]}
+//! ["f", 0.0334]           a frame, and its delta time in seconds
+//! ["k", "c", 0]           a key press: key, then crossterm modifier bits
+//! ["w", 1, 2]             mouse wheel: 1 up or -1 down, then modifier bits
+//! ["r", 100, 30]          terminal resize
+//! ```
+//!
+//! The same stream, sent live over TCP, is how `--sync-follow` instances
+//! keep in step with a `--sync-leader` (see [`crate::sync`]).
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseEvent, MouseEventKind,
+};
+use toml::Value;
+
+use crate::formats::{parse_json, quote};
+
+const VERSION: i64 = 1;
+
+/// Keys with names in the file; other keys are single characters or `f1`-`f12`.
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("backspace", KeyCode::Backspace),
+    ("tab", KeyCode::Tab),
+    ("backtab", KeyCode::BackTab),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+];
+
+/// What a session started from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionHeader {
+    pub seed: u64,
+    pub width: u16,
+    pub height: u16,
+    /// Command-line arguments, without the program name
+    pub args: Vec<String>,
+}
+
+/// One step of a recorded session.
+#[derive(Clone, Debug, PartialEq)]
+enum Step {
+    Frame(f64),
+    Input(Event),
+}
+
+/// Flags left out of the recorded command line, so a replay doesn't
+/// overwrite the files the original session wrote.
+const UNRECORDED_FLAGS: &[&str] = &[
+    "--record-session",
+    "--replay",
+    "--record",
+    "--sync-leader",
+    "--sync-follow",
+    "--log-file",
+    "--log-level",
+];
+
+/// The command line to record: `args` without the `UNRECORDED_FLAGS`.
+pub fn recorded_args(args: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut kept = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let flag = arg.split('=').next().unwrap_or_default();
+        if !UNRECORDED_FLAGS.contains(&flag) {
+            kept.push(arg);
+        } else if !arg.contains('=') {
+            // Skip the flag's value too
+            args.next();
+        }
+    }
+    kept
+}
+
+/// Writes a `.drr` file as the session runs.
+pub struct SessionRecorder<W: Write = BufWriter<File>> {
+    out: W,
+}
+
+impl SessionRecorder {
+    /// Create `path` and write `header` to it.
+    pub fn create(path: &Path, header: &SessionHeader) -> Result<Self, String> {
+        let file = File::create(path)
+            .map_err(|e| format!("Could not create {}: {}", path.display(), e))?;
+        SessionRecorder::new(BufWriter::new(file), header)
+            .map_err(|e| format!("Could not write {}: {}", path.display(), e))
+    }
+}
+
+impl<W: Write> SessionRecorder<W> {
+    pub fn new(mut out: W, header: &SessionHeader) -> io::Result<Self> {
+        let args: Vec<String> = header.args.iter().map(|a| quote(a)).collect();
+        writeln!(
+            out,
+            "{{\"version\": {}, \"seed\": \"{}\", \"width\": {}, \"height\": {}, \"args\": [{}]}}",
+            VERSION,
+            header.seed,
+            header.width,
+            header.height,
+            args.join(", ")
+        )?;
+        Ok(Self { out })
+    }
+
+    /// Record a frame that ran for `delta_time` seconds.
+    pub fn frame(&mut self, delta_time: f64) -> io::Result<()> {
+        // Full precision, so the replay steps by exactly the same times
+        writeln!(self.out, "[\"f\", {:?}]", delta_time)?;
+        self.out.flush()
+    }
+
+    /// Record an input event. Only events the main loop acts on are kept:
+    /// key presses, wheel turns, and resizes.
+    pub fn input(&mut self, event: &Event) -> io::Result<()> {
+        match *event {
+            Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                if let Some(key) = key_name(code) {
+                    writeln!(self.out, "[\"k\", {}, {}]", quote(&key), modifiers.bits())?;
+                }
+            }
+            Event::Mouse(MouseEvent {
+                kind: kind @ (MouseEventKind::ScrollUp | MouseEventKind::ScrollDown),
+                modifiers,
+                ..
+            }) => {
+                let direction = if kind == MouseEventKind::ScrollUp {
+                    1
+                } else {
+                    -1
+                };
+                writeln!(self.out, "[\"w\", {}, {}]", direction, modifiers.bits())?;
+            }
+            Event::Resize(width, height) => writeln!(self.out, "[\"r\", {}, {}]", width, height)?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn key_name(code: KeyCode) -> Option<String> {
+    if let Some(&(name, _)) = NAMED_KEYS.iter().find(|(_, c)| *c == code) {
+        return Some(name.to_string());
+    }
+    match code {
+        KeyCode::Char(c) => Some(c.to_string()),
+        KeyCode::F(n) => Some(format!("f{}", n)),
+        _ => None,
+    }
+}
+
+fn key_code(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+    if let Some(&(_, code)) = NAMED_KEYS.iter().find(|(n, _)| *n == name) {
+        return Some(code);
+    }
+    name.strip_prefix('f')?.parse().ok().map(KeyCode::F)
+}
+
+/// A recorded session being played back, or a live one being followed.
+pub struct SessionReplay {
+    header: SessionHeader,
+    steps: VecDeque<Step>,
+    /// Steps still arriving from a live session, until it ends
+    live: Option<Receiver<Step>>,
+}
+
+impl SessionReplay {
+    /// Read a `.drr` file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty());
+        let (_, first) = lines.next().ok_or("empty session file")?;
+        let header = parse_header(&parse_json(first)?)?;
+        let steps = lines
+            .map(|(i, line)| {
+                parse_json(line)
+                    .and_then(|value| parse_step(&value))
+                    .map_err(|e| format!("line {}: {}", i + 1, e))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            header,
+            steps,
+            live: None,
+        })
+    }
+
+    /// Follow a session as it's written to `reader`. Waits for the header;
+    /// the steps are read in the background as they arrive.
+    pub fn follow(reader: impl Read + Send + 'static) -> Result<Self, String> {
+        let mut reader = BufReader::new(reader);
+        let mut first = String::new();
+        reader.read_line(&mut first).map_err(|e| e.to_string())?;
+        if first.trim().is_empty() {
+            return Err("the session ended before it started".to_string());
+        }
+        let header = parse_header(&parse_json(&first)?)?;
+
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for line in reader.lines() {
+                let Ok(line) = line else { break };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                // Skipping a step would put everything after it out of step
+                let Ok(step) = parse_json(&line).and_then(|value| parse_step(&value)) else {
+                    break;
+                };
+                if tx.send(step).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self {
+            header,
+            steps: VecDeque::new(),
+            live: Some(rx),
+        })
+    }
+
+    pub fn header(&self) -> &SessionHeader {
+        &self.header
+    }
+
+    /// Whether steps are still arriving from a live session. Once it ends,
+    /// whatever arrived plays out like a recording.
+    pub fn is_live(&self) -> bool {
+        self.live.is_some()
+    }
+
+    /// Whether the next frame has arrived. Always true for a recording; a
+    /// live session runs its frames when they come, not on the local clock.
+    pub fn frame_ready(&self) -> bool {
+        self.live.is_none() || matches!(self.steps.front(), Some(Step::Frame(_)))
+    }
+
+    /// The next recorded input before the coming frame. When the frame is
+    /// due instead, waits out `timeout` (the time left until the frame, as
+    /// for live input) and returns `None`. A live session waits up to
+    /// `timeout` for its next step instead, and not at all if it's ready.
+    pub fn next_input(&mut self, timeout: Duration) -> Option<Event> {
+        if self.steps.is_empty()
+            && let Some(live) = &self.live
+        {
+            match live.recv_timeout(timeout) {
+                Ok(step) => self.steps.push_back(step),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => self.live = None,
+            }
+        }
+        if let Some(Step::Input(_)) = self.steps.front()
+            && let Some(Step::Input(event)) = self.steps.pop_front()
+        {
+            return Some(event);
+        }
+        if self.live.is_none() {
+            thread::sleep(timeout);
+        }
+        None
+    }
+
+    /// The recorded delta time of the frame about to run, or `None` once
+    /// the recording is over.
+    pub fn next_frame(&mut self) -> Option<f64> {
+        // Input left over before this frame was already consumed by
+        // `next_input`, so a frame is next unless the file has ended
+        while let Some(step) = self.steps.pop_front() {
+            if let Step::Frame(delta_time) = step {
+                return Some(delta_time);
+            }
+        }
+        None
+    }
+}
+
+fn parse_header(value: &Value) -> Result<SessionHeader, String> {
+    let int = |key: &str| value.get(key).and_then(Value::as_integer);
+    if int("version") != Some(VERSION) {
+        return Err("not a version 1 session recording".to_string());
+    }
+    let size = |key: &str| {
+        int(key)
+            .and_then(|n| u16::try_from(n).ok())
+            .ok_or_else(|| format!("bad {} in header", key))
+    };
+    Ok(SessionHeader {
+        seed: value
+            .get("seed")
+            .and_then(Value::as_str)
+            .and_then(|s| s.parse().ok())
+            .ok_or("bad seed in header")?,
+        width: size("width")?,
+        height: size("height")?,
+        args: value
+            .get("args")
+            .and_then(Value::as_array)
+            .ok_or("missing args in header")?
+            .iter()
+            .map(|arg| arg.as_str().map(str::to_string).ok_or("bad args in header"))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+fn parse_step(value: &Value) -> Result<Step, String> {
+    let fields = value.as_array().ok_or("expected an array")?;
+    let int = |i: usize| fields.get(i).and_then(Value::as_integer);
+    let modifiers =
+        |i: usize| KeyModifiers::from_bits_truncate(int(i).unwrap_or(0).clamp(0, 0xff) as u8);
+    let bad = || format!("bad step {}", value);
+    match fields.first().and_then(Value::as_str) {
+        Some("f") => match fields.get(1) {
+            Some(Value::Float(dt)) => Ok(Step::Frame(*dt)),
+            Some(Value::Integer(dt)) => Ok(Step::Frame(*dt as f64)),
+            _ => Err(bad()),
+        },
+        Some("k") => {
+            let code = fields
+                .get(1)
+                .and_then(Value::as_str)
+                .and_then(key_code)
+                .ok_or_else(bad)?;
+            Ok(Step::Input(Event::Key(KeyEvent::new(code, modifiers(2)))))
+        }
+        Some("w") => Ok(Step::Input(Event::Mouse(MouseEvent {
+            kind: if int(1).ok_or_else(bad)? > 0 {
+                MouseEventKind::ScrollUp
+            } else {
+                MouseEventKind::ScrollDown
+            },
+            column: 0,
+            row: 0,
+            modifiers: modifiers(2),
+        }))),
+        Some("r") => match (int(1), int(2)) {
+            (Some(w), Some(h)) => Ok(Step::Input(Event::Resize(
+                w.clamp(0, u16::MAX as i64) as u16,
+                h.clamp(0, u16::MAX as i64) as u16,
+            ))),
+            _ => Err(bad()),
+        },
+        _ => Err(bad()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_recording_replays_step_for_step() {
+        let header = SessionHeader {
+            seed: u64::MAX - 7,
+            width: 80,
+            height: 24,
+            args: vec![
+                "-e".to_string(),
+                "glitch".to_string(),
+                "--banner".to_string(),
+                "say \"hi\"".to_string(),
+            ],
+        };
+        let ctrl_up = Event::Mouse(MouseEvent {
+            kind: MouseEventKind::ScrollUp,
+            column: 0,
+            row: 0,
+            modifiers: KeyModifiers::CONTROL,
+        });
+        let mut file = Vec::new();
+        {
+            let mut recorder = SessionRecorder::new(&mut file, &header).unwrap();
+            recorder.frame(1.0 / 30.0).unwrap();
+            recorder
+                .input(&Event::Key(KeyEvent::new(
+                    KeyCode::Char('c'),
+                    KeyModifiers::NONE,
+                )))
+                .unwrap();
+            recorder
+                .input(&Event::Key(KeyEvent::new(KeyCode::F(5), KeyModifiers::ALT)))
+                .unwrap();
+            recorder.input(&Event::FocusGained).unwrap();
+            recorder.input(&ctrl_up).unwrap();
+            recorder.frame(0.034_123_456_789).unwrap();
+            recorder.input(&Event::Resize(100, 30)).unwrap();
+            recorder.frame(1.0).unwrap();
+        }
+
+        let mut replay = SessionReplay::parse(&String::from_utf8(file).unwrap()).unwrap();
+        assert_eq!(replay.header(), &header);
+        assert_eq!(replay.next_input(Duration::ZERO), None);
+        assert_eq!(replay.next_frame(), Some(1.0 / 30.0));
+        assert_eq!(
+            replay.next_input(Duration::ZERO),
+            Some(Event::Key(KeyEvent::new(
+                KeyCode::Char('c'),
+                KeyModifiers::NONE
+            )))
+        );
+        assert_eq!(
+            replay.next_input(Duration::ZERO),
+            Some(Event::Key(KeyEvent::new(KeyCode::F(5), KeyModifiers::ALT)))
+        );
+        assert_eq!(replay.next_input(Duration::ZERO), Some(ctrl_up));
+        assert_eq!(replay.next_input(Duration::ZERO), None);
+        assert_eq!(replay.next_frame(), Some(0.034_123_456_789));
+        assert_eq!(
+            replay.next_input(Duration::ZERO),
+            Some(Event::Resize(100, 30))
+        );
+        assert_eq!(replay.next_frame(), Some(1.0));
+        assert_eq!(replay.next_frame(), None);
+
+        assert!(SessionReplay::parse("{\"version\": 2}").is_err());
+        assert!(SessionReplay::parse("").is_err());
+    }
+
+    #[test]
+    fn the_recording_flags_are_left_out_of_the_command_line() {
+        let args = [
+            "-e",
+            "fire",
+            "--record-session",
+            "a.drr",
+            "--seed",
+            "3",
+            "--replay=b.drr",
+            "--record",
+            "c.cast",
+            "--recordx",
+        ];
+        assert_eq!(
+            recorded_args(args.map(String::from)),
+            ["-e", "fire", "--seed", "3", "--recordx"]
+        );
+    }
+}
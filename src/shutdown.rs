@@ -0,0 +1,151 @@
+//! Clean shutdown on SIGTERM, SIGHUP, and SIGINT (and console close on
+//! Windows).
+//!
+//! By default these signals kill the process on the spot, leaving the
+//! terminal in raw mode on the alternate screen. `install` replaces that
+//! with a handler that only records the signal; the main loop checks
+//! `requested` every frame and leaves the way `q` does, so the terminal is
+//! restored on the way out. The handler then steps aside, so a second
+//! signal kills a process whose cleanup is stuck.
+//!
+//! Windows has no signals; closing the console window, logging off, and
+//! Ctrl+Break arrive as console control events instead. (Ctrl+C arrives as
+//! a key in raw mode on both.)
+
+use std::sync::atomic::{AtomicI32, Ordering};
+
+/// The signal received, or a negative value for a Windows console event;
+/// 0 while nothing has been.
+static RECEIVED: AtomicI32 = AtomicI32::new(0);
+
+/// Whether the process has been asked to shut down.
+pub fn requested() -> bool {
+    RECEIVED.load(Ordering::SeqCst) != 0
+}
+
+/// The exit status after a shutdown by signal: 128 plus the signal number,
+/// the way a shell reports a signal death.
+pub fn exit_status() -> Option<i32> {
+    let signal = RECEIVED.load(Ordering::SeqCst);
+    (signal > 0).then_some(128 + signal)
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::ffi::c_int;
+    use std::sync::atomic::Ordering;
+
+    use super::RECEIVED;
+
+    const SIGHUP: c_int = 1;
+    const SIGINT: c_int = 2;
+    const SIGTERM: c_int = 15;
+    /// `SIG_DFL`: the default action
+    const DEFAULT: usize = 0;
+
+    unsafe extern "C" {
+        fn signal(signum: c_int, handler: usize) -> usize;
+    }
+
+    extern "C" fn on_signal(signum: c_int) {
+        // Only async-signal-safe calls in here: an atomic store and signal()
+        RECEIVED.store(signum, Ordering::SeqCst);
+        // SAFETY: restoring the default action for a valid signal number
+        unsafe {
+            signal(signum, DEFAULT);
+        }
+    }
+
+    pub fn install() {
+        for signum in [SIGHUP, SIGINT, SIGTERM] {
+            // SAFETY: `on_signal` is an `extern "C" fn(c_int)` that only
+            // touches an atomic and calls signal()
+            unsafe {
+                signal(signum, on_signal as extern "C" fn(c_int) as usize);
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    use super::RECEIVED;
+
+    const CTRL_BREAK_EVENT: u32 = 1;
+    const CTRL_CLOSE_EVENT: u32 = 2;
+    const CTRL_LOGOFF_EVENT: u32 = 5;
+    const CTRL_SHUTDOWN_EVENT: u32 = 6;
+
+    /// How long a closing console waits for the main loop. Windows ends the
+    /// process as soon as the handler returns (and after about 5 seconds
+    /// regardless), while returning from `main` ends it sooner.
+    const CLOSE_GRACE: Duration = Duration::from_secs(4);
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn SetConsoleCtrlHandler(
+            handler: Option<unsafe extern "system" fn(u32) -> i32>,
+            add: i32,
+        ) -> i32;
+    }
+
+    /// Runs on a thread of its own, so it can wait.
+    unsafe extern "system" fn on_console_event(event: u32) -> i32 {
+        match event {
+            CTRL_BREAK_EVENT => {
+                RECEIVED.store(-1, Ordering::SeqCst);
+                1
+            }
+            CTRL_CLOSE_EVENT | CTRL_LOGOFF_EVENT | CTRL_SHUTDOWN_EVENT => {
+                RECEIVED.store(-1, Ordering::SeqCst);
+                std::thread::sleep(CLOSE_GRACE);
+                1
+            }
+            // Ctrl+C: let the default handling (and raw mode) deal with it
+            _ => 0,
+        }
+    }
+
+    pub fn install() {
+        // SAFETY: registering a handler with the signature Windows expects
+        unsafe {
+            SetConsoleCtrlHandler(Some(on_console_event), 1);
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    pub fn install() {}
+}
+
+/// Catch the shutdown signals from now on, so `requested` reports them.
+pub fn install() {
+    platform::install();
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::ffi::c_int;
+
+    use super::*;
+
+    unsafe extern "C" {
+        fn raise(signum: c_int) -> c_int;
+    }
+
+    #[test]
+    fn a_signal_is_recorded_instead_of_killing_the_process() {
+        install();
+        assert!(!requested());
+        // SAFETY: raising SIGHUP, which the handler above now catches
+        unsafe {
+            raise(1);
+        }
+        assert!(requested());
+        assert_eq!(exit_status(), Some(129));
+    }
+}
@@ -0,0 +1,153 @@
+//! Small ASCII sprites that walk across the screen, for easter eggs.
+//!
+//! A `Sprite` is one or more frames of ASCII art, each a list of rows;
+//! spaces are transparent, so whatever's underneath shows through. A
+//! `SpriteWalker` carries a sprite along a row of the screen, from just off
+//! the left edge until it has walked off the right, flipping through its
+//! frames as it goes.
+
+use crate::buffer::RenderTarget;
+use crate::color::Color;
+
+/// Frames of ASCII art, drawn in one color.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sprite {
+    frames: Vec<Vec<String>>,
+    pub color: Color,
+}
+
+impl Sprite {
+    /// A sprite from `frames`, each a list of rows. Every frame is drawn
+    /// from the same top-left corner.
+    pub fn new(frames: &[&[&str]], color: Color) -> Self {
+        Self {
+            frames: frames
+                .iter()
+                .map(|rows| rows.iter().map(|row| row.to_string()).collect())
+                .collect(),
+            color,
+        }
+    }
+
+    /// Width in cells: the longest row of any frame.
+    pub fn width(&self) -> u16 {
+        self.frames
+            .iter()
+            .flatten()
+            .map(|row| row.chars().count() as u16)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Height in rows: the tallest frame.
+    pub fn height(&self) -> u16 {
+        self.frames
+            .iter()
+            .map(|f| f.len() as u16)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Draw frame `index` (wrapping) with its top-left corner at `(x, y)`,
+    /// which may be off screen. Cells off the grid are skipped.
+    pub fn render(&self, buffer: &mut dyn RenderTarget, index: usize, x: i32, y: i32) {
+        if self.frames.is_empty() {
+            return;
+        }
+        let (width, height) = (buffer.width() as i32, buffer.height() as i32);
+        let frame = &self.frames[index % self.frames.len()];
+        for (dy, row) in frame.iter().enumerate() {
+            let cy = y + dy as i32;
+            if !(0..height).contains(&cy) {
+                continue;
+            }
+            for (dx, ch) in row.chars().enumerate() {
+                let cx = x + dx as i32;
+                if ch != ' ' && (0..width).contains(&cx) {
+                    buffer.set_cell(cx as u16, cy as u16, ch, self.color, Color::Reset);
+                }
+            }
+        }
+    }
+}
+
+/// Walks a sprite left to right across the screen.
+#[derive(Clone, Debug)]
+pub struct SpriteWalker {
+    sprite: Sprite,
+    /// Left edge, in cells; starts off screen to the left
+    x: f64,
+    /// Top row
+    y: u16,
+    /// Cells per second
+    speed: f64,
+    /// Seconds per animation frame
+    frame_secs: f64,
+    elapsed: f64,
+}
+
+impl SpriteWalker {
+    /// Walk `sprite` along row `y` at `speed` cells per second, showing a
+    /// new frame every `frame_secs`.
+    pub fn new(sprite: Sprite, y: u16, speed: f64, frame_secs: f64) -> Self {
+        Self {
+            x: -(sprite.width() as f64),
+            sprite,
+            y,
+            speed,
+            frame_secs: frame_secs.max(0.01),
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, dt: f64) {
+        self.elapsed += dt;
+        self.x += self.speed * dt;
+    }
+
+    /// Whether the sprite has walked off a screen `width` cells wide.
+    pub fn is_finished(&self, width: u16) -> bool {
+        self.x >= width as f64
+    }
+
+    pub fn render(&self, buffer: &mut dyn RenderTarget) {
+        let frame = (self.elapsed / self.frame_secs) as usize;
+        self.sprite
+            .render(buffer, frame, self.x.floor() as i32, self.y as i32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::ScreenBuffer;
+
+    #[test]
+    fn walker_crosses_the_screen_clipping_at_the_edges() {
+        let sprite = Sprite::new(&[&["ab", "c"], &["de", " f"]], Color::Reset);
+        assert_eq!((sprite.width(), sprite.height()), (2, 2));
+
+        let mut walker = SpriteWalker::new(sprite, 2, 10.0, 0.1);
+        let mut buffer = ScreenBuffer::new(5, 4);
+        let mut step = |setup: &[(u16, u16)]| -> [String; 2] {
+            walker.update(0.1);
+            buffer.clear();
+            for &(x, y) in setup {
+                buffer.set_cell(x, y, 'x', Color::Reset, Color::Reset);
+            }
+            walker.render(&mut buffer);
+            [2, 3].map(|y| (0..5).map(|x| buffer.get_cell(x, y).unwrap().ch).collect())
+        };
+
+        // One cell on: the second frame, clipped at the left edge
+        assert_eq!(step(&[]), ["e    ", "f    "]);
+        // Back to the first frame, fully on screen
+        assert_eq!(step(&[]), ["ab   ", "c    "]);
+        // Spaces are transparent
+        assert_eq!(step(&[(1, 3)]), [" de  ", " xf  "]);
+
+        assert!(!walker.is_finished(5));
+        walker.update(0.4);
+        assert!(walker.is_finished(5));
+    }
+}
@@ -0,0 +1,418 @@
+//! SSH front end for `--ssh` (the `ssh` feature): `ssh -p 2222 host` drops
+//! a visitor straight into the rain.
+//!
+//! It's the `--serve` idea over SSH instead of telnet. Nobody logs in: the
+//! `none` authentication method is accepted, so any user name works and no
+//! password is asked for. The size comes from the visitor's pty request and
+//! follows their `window-change` messages; `q`, Ctrl+C, or Ctrl+D leaves.
+//! Each viewer is played by `serve::play` on a thread of its own, exactly
+//! like a telnet viewer, while russh handles the protocol on a small tokio
+//! runtime.
+//!
+//! The host key is an Ed25519 key kept in a file. It's generated on first
+//! use, so visitors' `known_hosts` entries stay valid across restarts.
+
+use std::fs;
+use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+use russh::keys::ssh_key::LineEnding;
+use russh::keys::ssh_key::rand_core::OsRng;
+use russh::keys::{Algorithm, PrivateKey};
+use russh::server::{Auth, Handle, Msg, Server as _, Session};
+use russh::{Channel, ChannelId, CryptoVec, MethodKind, MethodSet, Pty};
+use tokio::net::TcpListener;
+use tokio::runtime::Runtime;
+
+use crate::config::{self, Config};
+use crate::serve::{self, ClientInput};
+
+/// Host key file name, kept beside the config file by default.
+const HOST_KEY_FILE: &str = "ssh_host_ed25519_key";
+
+/// A listening `--ssh` server.
+pub struct Server {
+    runtime: Runtime,
+    listener: TcpListener,
+    host_key: PrivateKey,
+    max_clients: usize,
+}
+
+impl Server {
+    /// Listen on `address` (`host:port`, or `:port` for every interface)
+    /// with the host key at `host_key`, creating it if it doesn't exist.
+    pub fn bind(address: &str, host_key: &Path, max_clients: usize) -> Result<Self, String> {
+        let host_key = load_or_create_host_key(host_key)?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .build()
+            .map_err(|e| format!("Can't start the SSH server: {}", e))?;
+        let address = serve::listen_address(address);
+        let listener = runtime
+            .block_on(TcpListener::bind(&address))
+            .map_err(|e| format!("Can't listen on {}: {}", address, e))?;
+        Ok(Self {
+            runtime,
+            listener,
+            host_key,
+            max_clients: max_clients.max(1),
+        })
+    }
+
+    /// The address actually bound (useful with port 0).
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accept visitors until the process ends, streaming `config`'s effect
+    /// to each.
+    pub fn run(self, config: &Config) -> Result<(), String> {
+        let ssh_config = Arc::new(russh::server::Config {
+            methods: MethodSet::from(&[MethodKind::None][..]),
+            keys: vec![self.host_key],
+            auth_rejection_time: std::time::Duration::from_secs(1),
+            auth_rejection_time_initial: Some(std::time::Duration::ZERO),
+            nodelay: true,
+            ..Default::default()
+        });
+        let mut visitors = Visitors {
+            config: Arc::new(config.clone()),
+            viewers: Arc::new(AtomicUsize::new(0)),
+            max_clients: self.max_clients,
+        };
+        self.runtime
+            .block_on(visitors.run_on_socket(ssh_config, &self.listener))
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Where `--ssh` keeps its host key without `--ssh-host-key`.
+pub fn default_host_key_path() -> Option<PathBuf> {
+    Some(config::config_file_path()?.parent()?.join(HOST_KEY_FILE))
+}
+
+/// Read the host key at `path`, or generate one and save it there.
+fn load_or_create_host_key(path: &Path) -> Result<PrivateKey, String> {
+    if path.exists() {
+        return russh::keys::load_secret_key(path, None)
+            .map_err(|e| format!("{}: {}", path.display(), e));
+    }
+    let key = PrivateKey::random(&mut OsRng, Algorithm::Ed25519).map_err(|e| e.to_string())?;
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    // Written readable by the owner only
+    key.write_openssh_file(path, LineEnding::LF)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    eprintln!("Created SSH host key {}", path.display());
+    Ok(key)
+}
+
+/// Hands each connection a `Visitor`.
+struct Visitors {
+    config: Arc<Config>,
+    viewers: Arc<AtomicUsize>,
+    max_clients: usize,
+}
+
+impl russh::server::Server for Visitors {
+    type Handler = Visitor;
+
+    fn new_client(&mut self, peer: Option<SocketAddr>) -> Visitor {
+        Visitor {
+            config: Arc::clone(&self.config),
+            viewers: Arc::clone(&self.viewers),
+            max_clients: self.max_clients,
+            peer: peer.map_or_else(|| "unknown".to_string(), |a| a.to_string()),
+            channel: None,
+            size: serve::FALLBACK_SIZE,
+            inputs: None,
+        }
+    }
+}
+
+/// One SSH connection: a session channel that becomes a viewer once the
+/// client asks for a shell.
+struct Visitor {
+    config: Arc<Config>,
+    viewers: Arc<AtomicUsize>,
+    max_clients: usize,
+    peer: String,
+    /// The session channel, once opened (one per connection)
+    channel: Option<ChannelId>,
+    /// Window size from the pty request, until the animation starts
+    size: (u16, u16),
+    /// Resizes and quits for the playing animation
+    inputs: Option<Sender<ClientInput>>,
+}
+
+impl Visitor {
+    fn send(&self, input: ClientInput) {
+        if let Some(inputs) = &self.inputs {
+            let _ = inputs.send(input);
+        }
+    }
+}
+
+impl russh::server::Handler for Visitor {
+    type Error = russh::Error;
+
+    async fn auth_none(&mut self, _user: &str) -> Result<Auth, Self::Error> {
+        Ok(Auth::Accept)
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        channel: Channel<Msg>,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        if self.channel.is_some() {
+            return Ok(false);
+        }
+        self.channel = Some(channel.id());
+        Ok(true)
+    }
+
+    async fn pty_request(
+        &mut self,
+        channel: ChannelId,
+        _term: &str,
+        width: u32,
+        height: u32,
+        _pix_width: u32,
+        _pix_height: u32,
+        _modes: &[(Pty, u32)],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        if let Some(size) = serve::viewer_size(width, height) {
+            self.size = size;
+        }
+        session.channel_success(channel)
+    }
+
+    async fn window_change_request(
+        &mut self,
+        _channel: ChannelId,
+        width: u32,
+        height: u32,
+        _pix_width: u32,
+        _pix_height: u32,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        if let Some((width, height)) = serve::viewer_size(width, height) {
+            self.size = (width, height);
+            self.send(ClientInput::Resize(width, height));
+        }
+        Ok(())
+    }
+
+    async fn shell_request(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        if self.inputs.is_some() {
+            return session.channel_failure(channel);
+        }
+        session.channel_success(channel)?;
+        if self.viewers.load(Ordering::SeqCst) >= self.max_clients {
+            session.data(
+                channel,
+                CryptoVec::from(&b"Every seat is taken; try again soon.\r\n"[..]),
+            )?;
+            session.exit_status_request(channel, 1)?;
+            session.close(channel)?;
+            eprintln!(
+                "Turned away {}: {} viewers already",
+                self.peer, self.max_clients
+            );
+            return Ok(());
+        }
+        let count = self.viewers.fetch_add(1, Ordering::SeqCst) + 1;
+        eprintln!(
+            "SSH viewer {} connected ({}/{})",
+            self.peer, count, self.max_clients
+        );
+
+        let (tx, rx) = mpsc::channel();
+        self.inputs = Some(tx);
+        let mut out = ChannelWriter {
+            handle: session.handle(),
+            channel,
+            runtime: tokio::runtime::Handle::current(),
+            pending: Vec::new(),
+        };
+        let config = Arc::clone(&self.config);
+        let viewers = Arc::clone(&self.viewers);
+        let peer = self.peer.clone();
+        let size = self.size;
+        thread::spawn(move || {
+            let result = serve::play(&mut out, &rx, &config, size);
+            out.close();
+            viewers.fetch_sub(1, Ordering::SeqCst);
+            match result {
+                Ok(()) => eprintln!("SSH viewer {} left", peer),
+                Err(e) => eprintln!("SSH viewer {} dropped: {}", peer, e),
+            }
+        });
+        Ok(())
+    }
+
+    async fn data(
+        &mut self,
+        _channel: ChannelId,
+        data: &[u8],
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        if data.iter().any(|b| matches!(b, b'q' | b'Q' | 3 | 4)) {
+            self.send(ClientInput::Quit);
+        }
+        Ok(())
+    }
+
+    async fn channel_eof(
+        &mut self,
+        _channel: ChannelId,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        self.send(ClientInput::Quit);
+        Ok(())
+    }
+
+    async fn channel_close(
+        &mut self,
+        _channel: ChannelId,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        self.send(ClientInput::Quit);
+        Ok(())
+    }
+}
+
+impl Drop for Visitor {
+    // A dropped connection stops its animation too
+    fn drop(&mut self) {
+        self.send(ClientInput::Quit);
+    }
+}
+
+/// A viewer's frames, sent down their channel from the thread playing
+/// the animation. Each flush is one channel message.
+struct ChannelWriter {
+    handle: Handle,
+    channel: ChannelId,
+    runtime: tokio::runtime::Handle,
+    pending: Vec<u8>,
+}
+
+impl ChannelWriter {
+    /// End the session: exit status 0, then close the channel, which
+    /// makes `ssh` return.
+    fn close(&mut self) {
+        let _ = self.flush();
+        self.runtime.block_on(async {
+            let _ = self.handle.exit_status_request(self.channel, 0).await;
+            let _ = self.handle.eof(self.channel).await;
+            let _ = self.handle.close(self.channel).await;
+        });
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let data = CryptoVec::from(std::mem::take(&mut self.pending));
+        // Waits while the connection is backed up, slowing only this viewer
+        self.runtime
+            .block_on(self.handle.data(self.channel, data))
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Cli, ConfigFile};
+    use clap::Parser;
+    use russh::ChannelMsg;
+    use russh::keys::PublicKey;
+
+    struct TrustingClient;
+
+    impl russh::client::Handler for TrustingClient {
+        type Error = russh::Error;
+
+        async fn check_server_key(&mut self, _key: &PublicKey) -> Result<bool, Self::Error> {
+            Ok(true)
+        }
+    }
+
+    #[test]
+    fn streams_a_sized_animation_over_ssh_until_the_visitor_quits() {
+        let dir = std::env::temp_dir().join(format!("digitalrain-ssh-{}", std::process::id()));
+        let host_key = dir.join(HOST_KEY_FILE);
+        let server = Server::bind("127.0.0.1:0", &host_key, 1).unwrap();
+        let address = server.local_addr().unwrap();
+        // The key is kept for next time
+        assert!(load_or_create_host_key(&host_key).is_ok());
+        let config = Config::resolve(
+            &Cli::parse_from(["digital_rain", "--seed", "2", "--fps", "30"]),
+            &ConfigFile::default(),
+        );
+        thread::spawn(move || server.run(&config));
+
+        let client = tokio::runtime::Runtime::new().unwrap();
+        let received = client.block_on(async {
+            let config = Arc::new(russh::client::Config::default());
+            let mut session = russh::client::connect(config, address, TrustingClient)
+                .await
+                .unwrap();
+            assert!(session.authenticate_none("anyone").await.unwrap().success());
+            let mut channel = session.channel_open_session().await.unwrap();
+            channel
+                .request_pty(false, "xterm", 12, 5, 0, 0, &[])
+                .await
+                .unwrap();
+            channel.request_shell(false).await.unwrap();
+
+            // Frames fill the 12x5 window, then follow a resize to 20x3
+            let mut received = Vec::new();
+            let mut first_frames = None;
+            let mut quit = false;
+            loop {
+                let text = String::from_utf8_lossy(&received).into_owned();
+                if first_frames.is_none() && text.contains("\x1b[5;12H") {
+                    channel.window_change(20, 3, 0, 0).await.unwrap();
+                    first_frames = Some(text);
+                } else if first_frames.is_some() && !quit && text.contains("\x1b[3;20H") {
+                    channel.data(&b"q"[..]).await.unwrap();
+                    quit = true;
+                }
+                match channel.wait().await {
+                    Some(ChannelMsg::Data { data }) => received.extend_from_slice(&data),
+                    Some(ChannelMsg::Close) | None => break,
+                    Some(_) => {}
+                }
+            }
+            (first_frames.unwrap(), received)
+        });
+        let (first_frames, received) = received;
+        // Nothing was drawn outside the 12x5 window before the resize
+        assert!(!first_frames.contains("\x1b[6;") && !first_frames.contains(";13H"));
+        assert!(String::from_utf8_lossy(&received).ends_with("\x1b[?25h"));
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,171 @@
+//! SVG frame export: a frame as vector text that scales cleanly.
+//!
+//! Each cell is a `CELL_WIDTH x CELL_HEIGHT` box in user units. Glyphs are
+//! `<text>` elements in a generic `monospace` font at `FONT_SIZE`; runs of
+//! same-colored cells on a row share one element, with every glyph placed
+//! by its own `x` coordinate, so columns line up whatever advance width the
+//! viewer's font really has. Metrics assumed for the font:
+//!
+//! - advance about 0.6em (typical of monospace faces), which `CELL_WIDTH`
+//!   rounds up to leave a sliver between glyphs;
+//! - ascent about 0.8em, so a baseline `BASELINE` below the cell top keeps
+//!   capitals and CJK inside the cell with the descenders just fitting.
+//!
+//! Cell backgrounds become `<rect>`s (again merged along rows) over a black
+//! canvas. The viewer's own font draws the glyphs, so the result looks like
+//! a terminal rather than the bitmap font of the PNG and GIF exports.
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+use crate::buffer::{Cell, ScreenBuffer};
+use crate::color::Color;
+use crate::color::gradient::color_to_rgb;
+
+/// Font size in user units.
+pub const FONT_SIZE: u32 = 16;
+/// Horizontal cell pitch: 0.6em advance, rounded up.
+pub const CELL_WIDTH: u32 = 10;
+/// Vertical cell pitch: a 1.25 line height.
+pub const CELL_HEIGHT: u32 = 20;
+/// Baseline offset from the top of a cell.
+const BASELINE: u32 = 15;
+
+/// Foreground for `Color::Reset`, matching the rasterized exports.
+const DEFAULT_FG: (u8, u8, u8) = (204, 204, 204);
+
+/// Write `buffer` as a standalone SVG document.
+pub fn write_svg<W: Write>(out: &mut W, buffer: &ScreenBuffer) -> io::Result<()> {
+    let width = buffer.width() as usize;
+    let (pixel_width, pixel_height) = (
+        buffer.width() as u32 * CELL_WIDTH,
+        buffer.height() as u32 * CELL_HEIGHT,
+    );
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = pixel_width,
+        h = pixel_height
+    )?;
+    writeln!(
+        out,
+        r##"<rect width="100%" height="100%" fill="#000000"/>"##
+    )?;
+
+    let rows: Vec<_> = buffer.cells().chunks(width.max(1)).collect();
+
+    // Backgrounds: one rect per run of cells sharing a non-default color
+    for (row, cells) in rows.iter().enumerate() {
+        for (start, len, bg) in runs(cells, |cell| (cell.bg != Color::Reset).then_some(cell.bg)) {
+            writeln!(
+                out,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                start as u32 * CELL_WIDTH,
+                row as u32 * CELL_HEIGHT,
+                len as u32 * CELL_WIDTH,
+                CELL_HEIGHT,
+                hex(bg, (0, 0, 0))
+            )?;
+        }
+    }
+
+    writeln!(
+        out,
+        r#"<g font-family="monospace" font-size="{}" xml:space="preserve">"#,
+        FONT_SIZE
+    )?;
+    for (row, cells) in rows.iter().enumerate() {
+        let y = row as u32 * CELL_HEIGHT + BASELINE;
+        for (start, len, fg) in runs(cells, |cell| {
+            (!cell.ch.is_whitespace() && !cell.ch.is_control()).then_some(cell.fg)
+        }) {
+            let mut xs = String::new();
+            let mut text = String::new();
+            for (i, cell) in cells[start..start + len].iter().enumerate() {
+                if i > 0 {
+                    xs.push(' ');
+                }
+                let _ = write!(xs, "{}", (start + i) as u32 * CELL_WIDTH);
+                escape(cell.ch, &mut text);
+            }
+            writeln!(
+                out,
+                r#"<text x="{}" y="{}" fill="{}">{}</text>"#,
+                xs,
+                y,
+                hex(fg, DEFAULT_FG),
+                text
+            )?;
+        }
+    }
+    writeln!(out, "</g>")?;
+    writeln!(out, "</svg>")
+}
+
+/// Runs of adjacent cells for which `key` gives the same `Some` value, as
+/// (start, length, value).
+fn runs<T: PartialEq + Copy>(
+    cells: &[Cell],
+    key: impl Fn(&Cell) -> Option<T>,
+) -> Vec<(usize, usize, T)> {
+    let mut runs: Vec<(usize, usize, T)> = Vec::new();
+    for (col, cell) in cells.iter().enumerate() {
+        let Some(value) = key(cell) else { continue };
+        match runs.last_mut() {
+            Some((start, len, last)) if *start + *len == col && *last == value => *len += 1,
+            _ => runs.push((col, 1, value)),
+        }
+    }
+    runs
+}
+
+/// `#rrggbb` for a cell color, using `default` for `Color::Reset`.
+fn hex(color: Color, default: (u8, u8, u8)) -> String {
+    let (r, g, b) = match color {
+        Color::Reset => default,
+        other => color_to_rgb(other),
+    };
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Append `ch` to XML text content.
+fn escape(ch: char, out: &mut String) {
+    match ch {
+        '&' => out.push_str("&amp;"),
+        '<' => out.push_str("&lt;"),
+        '>' => out.push_str("&gt;"),
+        _ => out.push(ch),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_colored_runs_and_backgrounds() {
+        let mut buffer = ScreenBuffer::new(6, 2);
+        let green = Color::Rgb {
+            r: 0,
+            g: 255,
+            b: 70,
+        };
+        buffer.set_cell(0, 0, 'a', green, Color::Reset);
+        buffer.set_cell(1, 0, '<', green, Color::Reset);
+        buffer.set_cell(3, 0, 'b', green, Color::Reset);
+        buffer.set_cell(2, 1, '&', Color::Reset, Color::Rgb { r: 9, g: 9, b: 9 });
+
+        let mut svg = Vec::new();
+        write_svg(&mut svg, &buffer).unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+
+        assert!(svg.starts_with("<svg ") && svg.trim_end().ends_with("</svg>"));
+        assert!(svg.contains(r#"width="60" height="40""#));
+        // Adjacent same-color glyphs share an element, each placed by x
+        assert!(svg.contains(r##"<text x="0 10" y="15" fill="#00ff46">a&lt;</text>"##));
+        assert!(svg.contains(r##"<text x="30" y="15" fill="#00ff46">b</text>"##));
+        assert!(svg.contains(r##"<text x="20" y="35" fill="#cccccc">&amp;</text>"##));
+        assert!(svg.contains(r##"<rect x="20" y="20" width="10" height="20" fill="#090909"/>"##));
+        assert_eq!(svg.matches("<text").count(), 3);
+    }
+}
@@ -0,0 +1,199 @@
+//! Multi-instance sync (`--sync-leader`, `--sync-follow`): several
+//! terminals showing the same animation frame for frame, e.g. a video wall.
+//!
+//! The protocol is the `--record-session` format sent live over TCP. The
+//! leader writes its session (seed, command line, terminal size, then every
+//! frame time and input) to each follower as it runs. A follower starts
+//! from the header the way `--replay` does and runs a frame whenever the
+//! leader's next one arrives, so effect switches and speed changes made on
+//! the leader land on every screen on the same frame.
+//!
+//! A follower that joins late is sent the whole session so far and
+//! fast-forwards through it to catch up. The leader keeps that history in
+//! memory, about 1.5 MB per hour at 30 fps, up to `MAX_HISTORY` (some 40
+//! hours). Past that it lets the history go: followers already connected
+//! stay in step, but new ones are turned away.
+
+use std::io::{self, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::serve::listen_address;
+use crate::session::SessionReplay;
+
+/// A follower that can't take a write for this long is dropped, so one
+/// stalled screen can't freeze the leader.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Most session bytes kept for late joiners.
+const MAX_HISTORY: usize = 64 << 20;
+
+#[derive(Default)]
+struct Followers {
+    /// Everything sent so far, for followers that join late
+    history: Vec<u8>,
+    /// The history outgrew `MAX_HISTORY` and was dropped
+    history_lost: bool,
+    streams: Vec<TcpStream>,
+}
+
+/// The leader's end: a `Write` that goes to every connected follower.
+/// Wrap it in a `SessionRecorder` to send the session.
+pub struct SyncLeader {
+    followers: Arc<Mutex<Followers>>,
+    local_addr: SocketAddr,
+}
+
+impl SyncLeader {
+    /// Listen for followers on `address`: `host:port`, or `:port` for every
+    /// interface.
+    pub fn bind(address: &str) -> Result<Self, String> {
+        let address = listen_address(address);
+        let listener = TcpListener::bind(&address)
+            .map_err(|e| format!("Can't listen on {}: {}", address, e))?;
+        let local_addr = listener
+            .local_addr()
+            .map_err(|e| format!("Can't listen on {}: {}", address, e))?;
+        let followers = Arc::new(Mutex::new(Followers::default()));
+
+        let shared = Arc::clone(&followers);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { continue };
+                let _ = stream.set_nodelay(true);
+                let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+                let shared = Arc::clone(&shared);
+                thread::spawn(move || catch_up(stream, &shared));
+            }
+        });
+
+        Ok(Self {
+            followers,
+            local_addr,
+        })
+    }
+
+    /// The address actually bound (useful with port 0).
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+/// Send a new follower the session so far, then add it to the live
+/// stream. The history is copied out and sent without the lock, so a slow
+/// follower doesn't hold up the leader; whatever was written meanwhile is
+/// sent the same way until there's nothing left, and the follower joins in
+/// the same locked step that finds it current.
+fn catch_up(mut stream: TcpStream, followers: &Mutex<Followers>) {
+    let mut sent = 0;
+    loop {
+        let pending = {
+            let mut followers = followers.lock().unwrap_or_else(|e| e.into_inner());
+            if followers.history_lost {
+                return;
+            }
+            if followers.history.len() == sent {
+                followers.streams.push(stream);
+                return;
+            }
+            followers.history[sent..].to_vec()
+        };
+        if stream.write_all(&pending).is_err() {
+            return;
+        }
+        sent += pending.len();
+    }
+}
+
+impl Write for SyncLeader {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut followers = self.followers.lock().unwrap_or_else(|e| e.into_inner());
+        if followers.history.len() + buf.len() > MAX_HISTORY {
+            followers.history = Vec::new();
+            followers.history_lost = true;
+        }
+        if !followers.history_lost {
+            followers.history.extend_from_slice(buf);
+        }
+        followers
+            .streams
+            .retain_mut(|stream| stream.write_all(buf).is_ok());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Connect to a leader at `host:port` and start following its session.
+pub fn follow(address: &str) -> Result<SessionReplay, String> {
+    let stream = TcpStream::connect(address)
+        .map_err(|e| format!("Can't reach the sync leader at {}: {}", address, e))?;
+    let _ = stream.set_nodelay(true);
+    SessionReplay::follow(stream).map_err(|e| format!("{}: {}", address, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::{SessionHeader, SessionRecorder};
+
+    #[test]
+    fn late_followers_catch_up_then_stay_in_step() {
+        let leader = SyncLeader::bind("127.0.0.1:0").unwrap();
+        let address = leader.local_addr().to_string();
+        let header = SessionHeader {
+            seed: 7,
+            width: 40,
+            height: 12,
+            args: vec!["-e".to_string(), "fire".to_string()],
+        };
+        let mut recorder = SessionRecorder::new(leader, &header).unwrap();
+        recorder.frame(0.25).unwrap();
+
+        let mut early = follow(&address).unwrap();
+        recorder.frame(0.5).unwrap();
+        let mut late = follow(&address).unwrap();
+        recorder.frame(0.75).unwrap();
+
+        for follower in [&mut early, &mut late] {
+            assert_eq!(follower.header(), &header);
+            let mut frames = Vec::new();
+            while frames.len() < 3 {
+                assert_eq!(follower.next_input(Duration::from_secs(5)), None);
+                if follower.frame_ready() {
+                    frames.push(follower.next_frame().unwrap());
+                }
+            }
+            assert_eq!(frames, [0.25, 0.5, 0.75]);
+        }
+    }
+
+    #[test]
+    fn a_slow_late_joiner_never_holds_up_the_leader() {
+        use std::io::Read;
+        use std::time::Instant;
+
+        let mut leader = SyncLeader::bind("127.0.0.1:0").unwrap();
+        let address = leader.local_addr();
+        leader.write_all(&vec![b'x'; 16 << 20]).unwrap();
+
+        // A follower that never reads, stuck partway through the catch-up
+        let _stalled = TcpStream::connect(address).unwrap();
+        thread::sleep(Duration::from_millis(200));
+        let started = Instant::now();
+        leader.write_all(b"frame").unwrap();
+        assert!(started.elapsed() < WRITE_TIMEOUT / 4);
+
+        // Past the cap the history is let go and newcomers are turned away
+        leader.write_all(&vec![b'x'; MAX_HISTORY]).unwrap();
+        let mut refused = TcpStream::connect(address).unwrap();
+        refused
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        assert_eq!(refused.read(&mut [0; 16]).unwrap(), 0);
+    }
+}
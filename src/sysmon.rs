@@ -0,0 +1,204 @@
+//! System monitor for the `--sysmon` overlay.
+//!
+//! A background thread samples CPU usage, memory, and network and disk
+//! throughput once a second via `sysinfo` and appends them to a shared
+//! history; `--react` reads the same samples. The
+//! main loop only reads the history when drawing, so a slow refresh never
+//! stalls a frame. The thread exits on its own once the monitor is dropped.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::Duration;
+
+use sysinfo::{Disks, Networks, System};
+
+use crate::react::{self, ReactSource};
+
+/// How often the background thread samples.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+/// Samples kept for the sparklines.
+pub const HISTORY_LEN: usize = 16;
+
+/// One second's worth of system readings.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Sample {
+    /// Average CPU usage across all cores, 0-100
+    pub cpu_percent: f32,
+    pub mem_used: u64,
+    pub mem_total: u64,
+    /// Bytes per second received/sent, summed over all interfaces
+    pub net_rx: u64,
+    pub net_tx: u64,
+    /// Bytes per second read from/written to disk, summed over all disks
+    pub disk_read: u64,
+    pub disk_written: u64,
+}
+
+impl Sample {
+    /// How loaded `source` is, from 0.0 (idle) to 1.0 (flat out).
+    pub fn load(&self, source: ReactSource) -> f64 {
+        match source {
+            ReactSource::Cpu => (self.cpu_percent as f64 / 100.0).clamp(0.0, 1.0),
+            ReactSource::Net => react::byte_rate_level(self.net_rx + self.net_tx),
+            ReactSource::Disk => react::byte_rate_level(self.disk_read + self.disk_written),
+        }
+    }
+}
+
+/// Recent samples, oldest first.
+#[derive(Default)]
+pub struct History {
+    samples: VecDeque<Sample>,
+}
+
+impl History {
+    pub fn push(&mut self, sample: Sample) {
+        if self.samples.len() == HISTORY_LEN {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn latest(&self) -> Option<&Sample> {
+        self.samples.back()
+    }
+
+    pub fn samples(&self) -> impl Iterator<Item = &Sample> {
+        self.samples.iter()
+    }
+}
+
+/// Handle to the sampling thread and its history.
+pub struct SysMonitor {
+    history: Arc<Mutex<History>>,
+}
+
+impl SysMonitor {
+    /// Start sampling in the background.
+    pub fn spawn() -> Self {
+        let history = Arc::new(Mutex::new(History::default()));
+        let weak = Arc::downgrade(&history);
+        thread::spawn(move || sample_loop(weak));
+        Self { history }
+    }
+
+    /// Run `f` against the current history.
+    pub fn with_history<R>(&self, f: impl FnOnce(&History) -> R) -> R {
+        let history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        f(&history)
+    }
+}
+
+/// Sample until the owning `SysMonitor` is dropped.
+fn sample_loop(history: Weak<Mutex<History>>) {
+    let mut system = System::new();
+    let mut networks = Networks::new_with_refreshed_list();
+    let mut disks = Disks::new_with_refreshed_list();
+    // CPU usage is a delta between refreshes, so prime the first one
+    system.refresh_cpu_usage();
+
+    loop {
+        thread::sleep(SAMPLE_INTERVAL);
+        system.refresh_cpu_usage();
+        system.refresh_memory();
+        networks.refresh(true);
+        disks.refresh(true);
+
+        let (net_rx, net_tx) = networks.list().values().fold((0, 0), |(rx, tx), n| {
+            (rx + n.received(), tx + n.transmitted())
+        });
+        let (disk_read, disk_written) = disks.list().iter().fold((0, 0), |(r, w), d| {
+            let usage = d.usage();
+            (r + usage.read_bytes, w + usage.written_bytes)
+        });
+        let secs = SAMPLE_INTERVAL.as_secs_f64();
+        let sample = Sample {
+            cpu_percent: system.global_cpu_usage(),
+            mem_used: system.used_memory(),
+            mem_total: system.total_memory(),
+            net_rx: (net_rx as f64 / secs) as u64,
+            net_tx: (net_tx as f64 / secs) as u64,
+            disk_read: (disk_read as f64 / secs) as u64,
+            disk_written: (disk_written as f64 / secs) as u64,
+        };
+
+        let Some(history) = history.upgrade() else {
+            return;
+        };
+        history
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(sample);
+    }
+}
+
+/// Render values as a sparkline of block characters, scaled to `max`.
+pub fn sparkline(values: impl Iterator<Item = f64>, max: f64) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    values
+        .map(|v| {
+            let level = if max > 0.0 {
+                (v / max).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            BARS[(level * (BARS.len() - 1) as f64).round() as usize]
+        })
+        .collect()
+}
+
+/// Compact byte count: `512B`, `3.4K`, `1.2M`, `15.5G`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["K", "M", "G", "T"];
+    if bytes < 1024 {
+        return format!("{}B", bytes);
+    }
+    let mut value = bytes as f64;
+    let mut unit = "";
+    for u in UNITS {
+        value /= 1024.0;
+        unit = u;
+        if value < 1024.0 {
+            break;
+        }
+    }
+    format!("{:.1}{}", value, unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparkline_scales_to_max() {
+        assert_eq!(sparkline([0.0, 50.0, 100.0].into_iter(), 100.0), "▁▅█");
+        // Values past max clamp; zero max renders flat
+        assert_eq!(sparkline([200.0].into_iter(), 100.0), "█");
+        assert_eq!(sparkline([5.0, 9.0].into_iter(), 0.0), "▁▁");
+    }
+
+    #[test]
+    fn format_bytes_picks_units() {
+        assert_eq!(format_bytes(512), "512B");
+        assert_eq!(format_bytes(3 * 1024 + 512), "3.5K");
+        assert_eq!(format_bytes(16_642_998_272), "15.5G");
+    }
+
+    #[test]
+    fn history_keeps_most_recent_samples() {
+        let mut history = History::default();
+        for i in 0..(HISTORY_LEN + 4) {
+            history.push(Sample {
+                cpu_percent: i as f32,
+                ..Sample::default()
+            });
+        }
+        assert_eq!(history.samples().count(), HISTORY_LEN);
+        assert_eq!(history.samples().next().unwrap().cpu_percent, 4.0);
+        assert_eq!(
+            history.latest().unwrap().cpu_percent,
+            (HISTORY_LEN + 3) as f32
+        );
+    }
+}
@@ -0,0 +1,154 @@
+//! Log following for `--tail`: new lines of a file, a pipe, or stdin.
+//!
+//! A background thread follows the input and hands each new line to the
+//! main loop over a channel, like `--notify`. A regular file is followed
+//! like `tail -F`: reading starts at its current end, it's polled for
+//! growth, and it's read again from the top when it's truncated or
+//! replaced by log rotation. Pipes and stdin are read as lines arrive.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use crate::notify;
+
+/// How often a followed file is checked for new lines.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Receiving end of the followed log.
+pub struct TailFeed {
+    lines: Receiver<String>,
+}
+
+impl TailFeed {
+    /// Start following `path`, or stdin when the path is `-`. Fails up
+    /// front if the path can't be read at all.
+    pub fn spawn(path: &Path) -> Result<Self, String> {
+        let (tx, rx) = mpsc::channel();
+        if path.as_os_str() == "-" {
+            thread::spawn(move || {
+                notify::forward_lines(io::stdin().lock(), &tx);
+            });
+        } else {
+            let mut file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let path = path.to_path_buf();
+            if notify::is_fifo(&file) {
+                drop(file);
+                thread::spawn(move || {
+                    notify::read_pipe(path, |file| {
+                        notify::forward_lines(BufReader::new(file), &tx)
+                    });
+                });
+            } else {
+                // Only lines written from now on
+                let end = file
+                    .seek(SeekFrom::End(0))
+                    .map_err(|e| format!("{}: {}", path.display(), e))?;
+                thread::spawn(move || follow(path, file, end, tx));
+            }
+        }
+        Ok(Self { lines: rx })
+    }
+
+    /// Lines that arrived since the last call, oldest first.
+    pub fn drain(&self) -> impl Iterator<Item = String> + '_ {
+        self.lines.try_iter()
+    }
+}
+
+/// Follow a regular file from `pos` until the main loop goes away.
+fn follow(path: PathBuf, file: File, mut pos: u64, tx: Sender<String>) {
+    let mut reader = BufReader::new(file);
+    let mut partial = String::new();
+    loop {
+        // Read whatever has been appended; a line without its newline yet
+        // waits for the rest
+        loop {
+            match reader.read_line(&mut partial) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    pos += n as u64;
+                    if partial.ends_with('\n') {
+                        let line = notify::clean_line(&partial);
+                        partial.clear();
+                        if !line.is_empty() && tx.send(line).is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+
+        thread::sleep(POLL_INTERVAL);
+        if replaced_or_truncated(&path, reader.get_ref(), pos) {
+            // Start over on the new (or emptied) file
+            let Ok(file) = File::open(&path) else {
+                continue;
+            };
+            reader = BufReader::new(file);
+            pos = 0;
+            partial.clear();
+        }
+    }
+}
+
+/// Whether `path` no longer holds the open file (rotated away) or has
+/// shrunk below what's been read (truncated).
+fn replaced_or_truncated(path: &Path, open: &File, pos: u64) -> bool {
+    let Ok(current) = std::fs::metadata(path) else {
+        return false;
+    };
+    if current.len() < pos {
+        return true;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        if let Ok(open) = open.metadata() {
+            return (open.dev(), open.ino()) != (current.dev(), current.ino());
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = open;
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn follows_new_lines_through_truncation() {
+        let path = std::env::temp_dir().join(format!("rain_tail_{}.log", std::process::id()));
+        std::fs::write(&path, "old line\n").unwrap();
+        let feed = TailFeed::spawn(&path).unwrap();
+        let next = || {
+            feed.lines
+                .recv_timeout(Duration::from_secs(5))
+                .expect("a line")
+        };
+
+        // Existing content is skipped; a line shows once it's complete
+        let mut log = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        write!(log, "first ").unwrap();
+        log.flush().unwrap();
+        thread::sleep(POLL_INTERVAL * 2);
+        writeln!(log, "half").unwrap();
+        writeln!(log, "\x1b[31msecond\x1b[0m").unwrap();
+        assert_eq!(next(), "first half");
+        assert_eq!(next(), "second");
+
+        std::fs::write(&path, "after truncate\n").unwrap();
+        assert_eq!(next(), "after truncate");
+        std::fs::remove_file(&path).ok();
+
+        assert!(TailFeed::spawn(Path::new("/nonexistent/rain.log")).is_err());
+    }
+}
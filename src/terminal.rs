@@ -2,16 +2,48 @@
 //!
 //! Handles switching to the alternate screen buffer, enabling raw mode,
 //! hiding the cursor, and restoring everything on exit (including panics).
+//!
+//! What the running `Terminal` changed is recorded in `ACTIVE`, and
+//! `restore` undoes it once: from `Drop` on a normal exit, or from the panic
+//! hook (`install_panic_hook`), which has to restore the screen before it
+//! prints the report and so runs before `Drop` does. A panic on a helper
+//! thread (sysmon, audio) leaves the animation running and holds its report
+//! until `restore` runs at exit.
 
+use std::backtrace::Backtrace;
 use std::io;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::{Duration, Instant};
 
 use crossterm::{
     cursor,
-    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers,
+    },
     execute,
     terminal::{self, ClearType},
 };
 
+/// Where bugs get reported, for the panic message.
+const ISSUES_URL: &str = "https://github.com/HerbHall/DigitalRain/issues";
+
+/// Longest wait for input between checks for a shutdown signal.
+const SHUTDOWN_CHECK: Duration = Duration::from_millis(250);
+
+/// Terminal modes currently switched on, as `RAW_MODE` etc. bits.
+static ACTIVE: AtomicU8 = AtomicU8::new(0);
+/// Raw mode, with the cursor hidden
+const RAW_MODE: u8 = 1;
+/// On the alternate screen (otherwise the cursor position was saved)
+const ALTERNATE_SCREEN: u8 = 2;
+/// Capturing mouse events
+const MOUSE_CAPTURE: u8 = 4;
+
+/// Panic reports from helper threads, printed once the screen is restored.
+static DEFERRED_REPORTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
 /// Manages terminal state. On creation, switches to alternate screen and raw mode.
 /// On drop, restores the original terminal state.
 pub struct Terminal {
@@ -37,11 +69,35 @@ impl Terminal {
             terminal::Clear(ClearType::All)
         )?;
 
+        ACTIVE.store(RAW_MODE | ALTERNATE_SCREEN, Ordering::SeqCst);
+
+        let (width, height) = terminal::size()?;
+
+        Ok(Self { width, height })
+    }
+
+    /// Initialize for drawing into part of the screen (`--region`).
+    ///
+    /// Like `init`, but stays on the normal screen and doesn't clear it, so
+    /// everything outside the region is left as it was. The cursor position
+    /// is saved and restored on drop.
+    pub fn init_region() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), cursor::SavePosition, cursor::Hide)?;
+        ACTIVE.store(RAW_MODE, Ordering::SeqCst);
+
         let (width, height) = terminal::size()?;
 
         Ok(Self { width, height })
     }
 
+    /// Start receiving mouse events (movement, clicks, scrolling).
+    pub fn enable_mouse_capture(&mut self) -> io::Result<()> {
+        execute!(io::stdout(), EnableMouseCapture)?;
+        ACTIVE.fetch_or(MOUSE_CAPTURE, Ordering::SeqCst);
+        Ok(())
+    }
+
     /// Update stored dimensions. Call this when a resize event is detected.
     pub fn update_size(&mut self) -> io::Result<()> {
         let (width, height) = terminal::size()?;
@@ -60,20 +116,13 @@ impl Terminal {
         }
     }
 
-    /// Check if the user pressed 'q', Escape, or Ctrl+C.
+    /// Check if the user pressed Ctrl+C, which always quits (the other quit
+    /// keys are remappable and live in the key map).
     /// Only responds to Press events to avoid double-firing on Windows.
-    pub fn should_quit(event: &Event) -> bool {
+    pub fn is_interrupt(event: &Event) -> bool {
         matches!(
             event,
             Event::Key(KeyEvent {
-                code: KeyCode::Char('q'),
-                kind: KeyEventKind::Press,
-                ..
-            }) | Event::Key(KeyEvent {
-                code: KeyCode::Esc,
-                kind: KeyEventKind::Press,
-                ..
-            }) | Event::Key(KeyEvent {
                 code: KeyCode::Char('c'),
                 kind: KeyEventKind::Press,
                 modifiers: KeyModifiers::CONTROL,
@@ -81,14 +130,165 @@ impl Terminal {
             })
         )
     }
+
+    /// Check if an event is user activity that should end a screensaver:
+    /// any key press, mouse action, or paste. Resizes and focus changes
+    /// don't count.
+    pub fn is_activity(event: &Event) -> bool {
+        match event {
+            Event::Key(key) => key.kind == KeyEventKind::Press,
+            Event::Mouse(_) | Event::Paste(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Block until there has been no input for `idle`, before the screensaver
+/// takes over the screen.
+///
+/// Runs in raw mode on the normal screen; every key press restarts the
+/// countdown. Returns `false` if the user cancels with Ctrl+C, or a
+/// shutdown signal arrives.
+pub fn wait_for_idle(idle: Duration) -> io::Result<bool> {
+    terminal::enable_raw_mode()?;
+    let result = (|| {
+        // None: further off than the clock can count, so never
+        let mut deadline = Instant::now().checked_add(idle);
+        loop {
+            if crate::shutdown::requested() {
+                return Ok(false);
+            }
+            let remaining = deadline.map_or(Duration::MAX, |d| {
+                d.saturating_duration_since(Instant::now())
+            });
+            if remaining.is_zero() {
+                return Ok(true);
+            }
+            if !event::poll(remaining.min(SHUTDOWN_CHECK))? {
+                continue;
+            }
+            let event = event::read()?;
+            if let Event::Key(KeyEvent {
+                code: KeyCode::Char('c'),
+                modifiers: KeyModifiers::CONTROL,
+                kind: KeyEventKind::Press,
+                ..
+            }) = event
+            {
+                return Ok(false);
+            }
+            if Terminal::is_activity(&event) {
+                deadline = Instant::now().checked_add(idle);
+            }
+        }
+    })();
+    terminal::disable_raw_mode()?;
+    result
 }
 
 impl Drop for Terminal {
     /// Restore the terminal to its original state.
     /// This runs even if the program panics, as long as the Terminal is in scope.
     fn drop(&mut self) {
-        // Best-effort cleanup — ignore errors since we're in Drop
-        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        restore();
+    }
+}
+
+/// Undo whatever terminal modes are active, then print any held panic
+/// reports. Only the first call changes modes, so leaving the alternate
+/// screen twice can't move the cursor back over a panic report.
+fn restore() {
+    let active = ACTIVE.swap(0, Ordering::SeqCst);
+    // Best-effort cleanup: ignore errors, there's nothing left to do about them
+    let mut stdout = io::stdout();
+    if active & MOUSE_CAPTURE != 0 {
+        let _ = execute!(stdout, DisableMouseCapture);
+    }
+    if active & ALTERNATE_SCREEN != 0 {
+        let _ = execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen);
+    } else if active & RAW_MODE != 0 {
+        let _ = execute!(stdout, cursor::Show, cursor::RestorePosition);
+    }
+    if active & RAW_MODE != 0 {
         let _ = terminal::disable_raw_mode();
     }
+    let reports = std::mem::take(&mut *lock_reports());
+    for report in reports {
+        eprintln!("{}", report);
+    }
+}
+
+fn lock_reports() -> std::sync::MutexGuard<'static, Vec<String>> {
+    DEFERRED_REPORTS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Replace the default panic output with one that restores the terminal
+/// first. Otherwise the message is printed to the alternate screen, which
+/// is thrown away a moment later, and the shell is left in raw mode.
+///
+/// Only a panic on the main thread restores the terminal, since that one
+/// ends the run. A helper thread's panic would otherwise drop the screen
+/// out from under a main loop that keeps drawing, so its report waits for
+/// the terminal to be restored at exit.
+///
+/// The report always includes a backtrace, since a panic mid-animation is
+/// hard to reproduce.
+pub fn install_panic_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        tracing::error!("panic: {}", info);
+        let thread = std::thread::current();
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("(no message)");
+        let location = info
+            .location()
+            .map(|l| format!(" at {}", l))
+            .unwrap_or_default();
+        let report = format!(
+            "digital_rain panicked in thread '{}'{}:\n{}\n\nstack backtrace:\n{}\n\
+             This is a bug. Please report it, with the output above, at {}",
+            thread.name().unwrap_or("<unnamed>"),
+            location,
+            message,
+            Backtrace::force_capture(),
+            ISSUES_URL
+        );
+        if thread.name() == Some("main") || ACTIVE.load(Ordering::SeqCst) == 0 {
+            restore();
+            eprintln!("{}", report);
+        } else {
+            lock_reports().push(report);
+        }
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{MouseEvent, MouseEventKind};
+
+    #[test]
+    fn activity_is_key_press_mouse_or_paste() {
+        let press = KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE);
+        let mut release = press;
+        release.kind = KeyEventKind::Release;
+        let mouse = MouseEvent {
+            kind: MouseEventKind::Moved,
+            column: 3,
+            row: 4,
+            modifiers: KeyModifiers::NONE,
+        };
+
+        assert!(Terminal::is_activity(&Event::Key(press)));
+        assert!(Terminal::is_activity(&Event::Mouse(mouse)));
+        assert!(Terminal::is_activity(&Event::Paste("hi".to_string())));
+        assert!(!Terminal::is_activity(&Event::Key(release)));
+        assert!(!Terminal::is_activity(&Event::Resize(80, 24)));
+        assert!(!Terminal::is_activity(&Event::FocusLost));
+    }
 }
@@ -2,49 +2,170 @@
 //!
 //! Provides a simple frame clock that tracks delta time between frames
 //! and sleeps to maintain a target frame rate.
+//!
+//! Sleeps (and event polls) can wake a timer tick late, which on Windows'
+//! default 15.6ms timer is half a frame. So the clock sleeps only until
+//! `SPIN_MARGIN` before a frame is due and spins through the rest, keeping
+//! frame intervals within a millisecond of the target. `TimerResolution`
+//! asks Windows for a 1ms timer as well, so the sleep part wakes close to
+//! on time and the spin stays short.
+//!
+//! The clock can also govern its own rate. Fed how long each frame took
+//! to produce, it steps the rate down when frames keep overrunning their
+//! budget and back up when there's headroom again, so a slow terminal gets
+//! a steady lower rate instead of frames that arrive late and unevenly.
+//! The thresholds are far apart and recovering takes longer than backing
+//! off, so the rate settles instead of hunting.
 
+use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 
+/// How long before a frame is due the clock stops sleeping and spins: a
+/// bit more than a sleep can overshoot (with a 1ms timer on Windows).
+#[cfg(windows)]
+const SPIN_MARGIN: Duration = Duration::from_millis(2);
+#[cfg(not(windows))]
+const SPIN_MARGIN: Duration = Duration::from_millis(1);
+
+/// Lowest rate the governor steps down to.
+const MIN_ADAPTIVE_FPS: f64 = 10.0;
+/// Each step down multiplies the rate by this; a step up divides by it.
+const ADAPTIVE_STEP: f64 = 0.8;
+/// A frame whose work takes more than this share of its budget overran.
+const OVERRUN_SHARE: f64 = 0.9;
+/// A frame whose work takes less than this share left headroom.
+const HEADROOM_SHARE: f64 = 0.5;
+/// Overrunning frames in a row before the rate steps down.
+const OVERRUNS_TO_SLOW: u32 = 15;
+/// Frames with headroom in a row before the rate steps back up.
+const HEADROOM_TO_RECOVER: u32 = 90;
+
 /// Controls frame timing for the main loop.
 pub struct FrameClock {
+    /// The configured rate, which the governor never exceeds
+    max_fps: f64,
+    /// The rate in effect
+    fps: f64,
     /// Target time per frame (e.g., 33ms for 30fps)
     target_frame_time: Duration,
     /// When the last frame started
     last_frame: Instant,
     /// Time elapsed since the last frame (in seconds)
     delta_time: f64,
+    /// Whether `record_work` adjusts the rate
+    adaptive: bool,
+    /// Frames in a row that overran their budget
+    overruns: u32,
+    /// Frames in a row that left headroom
+    headroom: u32,
 }
 
 impl FrameClock {
     /// Create a new FrameClock targeting the given FPS.
     pub fn new(target_fps: u32) -> Self {
+        let fps = target_fps.max(1) as f64;
         Self {
-            target_frame_time: Duration::from_secs_f64(1.0 / target_fps as f64),
+            max_fps: fps,
+            fps,
+            target_frame_time: Duration::from_secs_f64(1.0 / fps),
             last_frame: Instant::now(),
             delta_time: 0.0,
+            adaptive: false,
+            overruns: 0,
+            headroom: 0,
+        }
+    }
+
+    /// Let `record_work` lower the rate under load (and raise it back, up
+    /// to the configured rate). Turning it off restores the configured rate.
+    pub fn set_adaptive(&mut self, adaptive: bool) {
+        self.adaptive = adaptive;
+        if !adaptive {
+            self.set_fps(self.max_fps);
+        }
+    }
+
+    /// Change the configured rate (the governor's ceiling), e.g. to save
+    /// power. The governor starts over from the new rate.
+    pub fn set_max_fps(&mut self, fps: u32) {
+        self.max_fps = fps.max(1) as f64;
+        self.set_fps(self.max_fps);
+        self.overruns = 0;
+        self.headroom = 0;
+    }
+
+    /// The frame rate currently targeted.
+    pub fn fps(&self) -> f64 {
+        self.fps
+    }
+
+    /// Report how long the last frame took to update, draw, and write.
+    /// With the governor on, returns the new rate when this changed it.
+    pub fn record_work(&mut self, work: Duration) -> Option<f64> {
+        if !self.adaptive {
+            return None;
         }
+        let share = work.as_secs_f64() / self.target_frame_time.as_secs_f64();
+        if share > OVERRUN_SHARE {
+            self.overruns += 1;
+            self.headroom = 0;
+        } else if share < HEADROOM_SHARE {
+            self.headroom += 1;
+            self.overruns = 0;
+        } else {
+            self.overruns = 0;
+            self.headroom = 0;
+        }
+
+        let fps = if self.overruns >= OVERRUNS_TO_SLOW {
+            (self.fps * ADAPTIVE_STEP).max(MIN_ADAPTIVE_FPS.min(self.max_fps))
+        } else if self.headroom >= HEADROOM_TO_RECOVER {
+            (self.fps / ADAPTIVE_STEP).min(self.max_fps)
+        } else {
+            return None;
+        };
+        self.overruns = 0;
+        self.headroom = 0;
+        if fps == self.fps {
+            return None;
+        }
+        self.set_fps(fps);
+        Some(fps)
+    }
+
+    fn set_fps(&mut self, fps: f64) {
+        self.fps = fps;
+        self.target_frame_time = Duration::from_secs_f64(1.0 / fps);
     }
 
     /// How long to wait when polling for events.
-    /// This is the remaining time until the next frame is due.
+    /// This is the time left until the next frame is due, less the part
+    /// `tick` spins through.
     pub fn poll_timeout(&self) -> Duration {
         let elapsed = self.last_frame.elapsed();
-        self.target_frame_time.saturating_sub(elapsed)
+        self.target_frame_time
+            .saturating_sub(elapsed)
+            .saturating_sub(SPIN_MARGIN)
     }
 
     /// Call this at the start of each frame. Returns true if enough time
     /// has passed for a new frame (i.e., we've reached the target frame time).
+    /// Within `SPIN_MARGIN` of the frame, waits it out first.
     pub fn tick(&mut self) -> bool {
-        let now = Instant::now();
-        let elapsed = now.duration_since(self.last_frame);
-
-        if elapsed >= self.target_frame_time {
-            self.delta_time = elapsed.as_secs_f64();
-            self.last_frame = now;
-            true
-        } else {
-            false
+        let remaining = self
+            .target_frame_time
+            .saturating_sub(self.last_frame.elapsed());
+        if remaining > SPIN_MARGIN {
+            return false;
+        }
+        while self.last_frame.elapsed() < self.target_frame_time {
+            std::hint::spin_loop();
         }
+
+        let now = Instant::now();
+        self.delta_time = now.duration_since(self.last_frame).as_secs_f64();
+        self.last_frame = now;
+        true
     }
 
     /// Time in seconds since the last frame. Use this for animation calculations
@@ -52,4 +173,184 @@ impl FrameClock {
     pub fn delta_time(&self) -> f64 {
         self.delta_time
     }
+
+    /// Replace the last frame's delta time (a replayed session steps by
+    /// the recorded frame times, not the live ones).
+    pub fn set_delta_time(&mut self, delta_time: f64) {
+        self.delta_time = delta_time;
+    }
+}
+
+/// Holds the OS timer at 1ms resolution while alive, so sleeps wake close
+/// to when they were asked to. Only Windows needs this; elsewhere it does
+/// nothing. The timer is system-wide and costs some power, so hold it only
+/// while animating.
+pub struct TimerResolution {
+    _held: (),
+}
+
+impl TimerResolution {
+    /// Ask for a 1ms timer until this is dropped.
+    pub fn fine() -> Self {
+        set_timer_period(true);
+        Self { _held: () }
+    }
+}
+
+impl Drop for TimerResolution {
+    fn drop(&mut self) {
+        set_timer_period(false);
+    }
+}
+
+#[cfg(windows)]
+#[link(name = "winmm")]
+unsafe extern "system" {
+    fn timeBeginPeriod(period: u32) -> u32;
+    fn timeEndPeriod(period: u32) -> u32;
+}
+
+/// Begin or end a 1ms timer period.
+#[cfg(windows)]
+fn set_timer_period(begin: bool) {
+    // SAFETY: plain Win32 calls with no pointers; each end pairs with a begin
+    unsafe {
+        if begin {
+            timeBeginPeriod(1);
+        } else {
+            timeEndPeriod(1);
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn set_timer_period(_begin: bool) {}
+
+/// Rolling window of recent frame times, for the stats overlay.
+pub struct FrameStats {
+    /// Frame times in seconds, oldest first
+    samples: VecDeque<f64>,
+    capacity: usize,
+}
+
+impl FrameStats {
+    /// Keep the most recent `capacity` frame times.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Record one frame's duration in seconds.
+    pub fn record(&mut self, frame_time: f64) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(frame_time);
+    }
+
+    /// Average frame time in milliseconds (0 with no samples).
+    pub fn average_ms(&self) -> f64 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        self.samples.iter().sum::<f64>() / self.samples.len() as f64 * 1000.0
+    }
+
+    /// Frames per second implied by the average frame time.
+    pub fn fps(&self) -> f64 {
+        let avg = self.average_ms();
+        if avg > 0.0 { 1000.0 / avg } else { 0.0 }
+    }
+
+    /// The `p`-th percentile frame time (0.0-1.0) in milliseconds.
+    pub fn percentile_ms(&self, p: f64) -> f64 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        let mut sorted: Vec<f64> = self.samples.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        let rank = (p.clamp(0.0, 1.0) * (sorted.len() - 1) as f64).round() as usize;
+        sorted[rank] * 1000.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_are_never_early() {
+        let mut clock = FrameClock::new(200);
+        let target = Duration::from_millis(5).as_secs_f64();
+        let mut frames = 0;
+        while frames < 20 {
+            std::thread::sleep(clock.poll_timeout());
+            if clock.tick() {
+                assert!(clock.delta_time() >= target);
+                frames += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn the_governor_backs_off_under_load_and_recovers() {
+        let mut clock = FrameClock::new(30);
+        let slow = Duration::from_millis(40);
+        let quick = Duration::from_millis(5);
+
+        // Off: the rate is fixed whatever the work
+        for _ in 0..100 {
+            assert_eq!(clock.record_work(slow), None);
+        }
+        clock.set_adaptive(true);
+
+        // A few slow frames are tolerated; a run of them steps the rate down
+        for _ in 0..OVERRUNS_TO_SLOW - 1 {
+            assert_eq!(clock.record_work(slow), None);
+        }
+        assert_eq!(clock.record_work(slow), Some(24.0));
+
+        // 40ms of work fits in neither 24fps nor the next step, but does in
+        // the one after, where the rate holds
+        let mut rates = Vec::new();
+        for _ in 0..200 {
+            rates.extend(clock.record_work(slow));
+        }
+        assert_eq!(rates.len(), 1);
+        assert!((clock.fps() - 19.2).abs() < 1e-9);
+
+        // Headroom brings it back up, one step at a time, never past 30
+        let mut rates = Vec::new();
+        for _ in 0..1000 {
+            rates.extend(clock.record_work(quick));
+        }
+        assert_eq!(rates.len(), 2);
+        assert_eq!(clock.fps(), 30.0);
+        assert!(clock.poll_timeout() <= Duration::from_secs_f64(1.0 / 30.0));
+    }
+
+    #[test]
+    fn frame_stats_average_percentile_and_window() {
+        let mut stats = FrameStats::new(100);
+        assert_eq!(stats.fps(), 0.0);
+
+        // 95 fast frames and 5 slow ones
+        for _ in 0..95 {
+            stats.record(0.010);
+        }
+        for _ in 0..5 {
+            stats.record(0.050);
+        }
+        assert!((stats.average_ms() - 12.0).abs() < 1e-9);
+        assert!((stats.percentile_ms(0.5) - 10.0).abs() < 1e-9);
+        assert!((stats.percentile_ms(0.99) - 50.0).abs() < 1e-9);
+
+        // Old samples fall out of the window
+        for _ in 0..100 {
+            stats.record(0.020);
+        }
+        assert!((stats.fps() - 50.0).abs() < 1e-9);
+    }
 }
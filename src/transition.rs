@@ -1,14 +1,119 @@
-//! Crossfade transition between effects.
+//! Transitions between effects: crossfade, wipes, dissolve, blinds, iris,
+//! and glitch.
 //!
 //! When switching effects, both old and new effects render simultaneously.
-//! Per-cell color blending creates a smooth crossfade over a configurable
-//! duration (default ~0.75 seconds).
+//! Each `TransitionKind` decides when each cell switches from old to new;
+//! the cell then blends over a short soft edge, so every style has smooth
+//! color rather than a hard seam. Duration is configurable (default ~0.75s).
+//!
+//! The glitch style is the exception: it tears up the outgoing frame with
+//! the glitch effect's corruption events, harder and harder, then cuts.
+//!
+//! A palette-only change doesn't need any of that: `PaletteFade` blends the
+//! running effect's colors instead, so its columns carry on undisturbed.
+
+use rand::{Rng, RngExt};
 
 use crate::buffer::ScreenBuffer;
 use crate::color::gradient::lerp_color;
+use crate::color::palette::Palette;
 use crate::effects::Effect;
+use crate::effects::corruption::Corruption;
+use crate::rng::{self, SimRng, Stream};
+
+/// Share of the transition each cell spends blending (the soft edge of
+/// wipes, blinds, and the iris).
+const SOFT_EDGE: f32 = 0.15;
+/// Width in cells of one vertical blind.
+const BLIND_WIDTH: u16 = 8;
+/// Point in a glitch transition where the picture cuts to the new effect.
+const GLITCH_CUT: f32 = 0.85;
+
+/// How the incoming effect replaces the outgoing one (`--transition`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum TransitionKind {
+    /// Every cell fades at once
+    Crossfade,
+    /// The new effect sweeps in from the right edge
+    WipeLeft,
+    /// The new effect sweeps in from the left edge
+    WipeRight,
+    /// The new effect rises from the bottom
+    WipeUp,
+    /// The new effect drops in from the top
+    WipeDown,
+    /// Cells switch one by one in random order
+    Dissolve,
+    /// Vertical slats open left to right
+    Blinds,
+    /// A circle opens from the center
+    Iris,
+    /// The old effect breaks up, then cuts to the new one
+    Glitch,
+    /// A different style for each switch
+    Random,
+}
+
+impl TransitionKind {
+    /// Every concrete style (everything but `Random`).
+    const STYLES: &[TransitionKind] = &[
+        Self::Crossfade,
+        Self::WipeLeft,
+        Self::WipeRight,
+        Self::WipeUp,
+        Self::WipeDown,
+        Self::Dissolve,
+        Self::Blinds,
+        Self::Iris,
+        Self::Glitch,
+    ];
+
+    /// This style, or a randomly chosen one for `Random`.
+    pub fn pick(self, rng: &mut impl Rng) -> Self {
+        match self {
+            Self::Random => Self::STYLES[rng.random_range(0..Self::STYLES.len())],
+            kind => kind,
+        }
+    }
+
+    /// How far cell (x, y) of a `width` x `height` screen is from outgoing
+    /// (0.0) to incoming (1.0) at overall progress `t`.
+    fn cell_progress(self, x: u16, y: u16, width: u16, height: u16, t: f32) -> f32 {
+        let fx = x as f32 / width.saturating_sub(1).max(1) as f32;
+        let fy = y as f32 / height.saturating_sub(1).max(1) as f32;
+        // Point in the transition at which this cell starts to switch
+        let start = match self {
+            Self::Crossfade | Self::Random => return t,
+            Self::Glitch => return if t < GLITCH_CUT { 0.0 } else { 1.0 },
+            Self::WipeLeft => 1.0 - fx,
+            Self::WipeRight => fx,
+            Self::WipeUp => 1.0 - fy,
+            Self::WipeDown => fy,
+            Self::Dissolve => cell_noise(x, y),
+            Self::Blinds => (x % BLIND_WIDTH) as f32 / BLIND_WIDTH as f32,
+            Self::Iris => {
+                // Cells are about twice as tall as wide, so halve dx to
+                // keep the circle round
+                let dx = (fx - 0.5) * width as f32 / 2.0;
+                let dy = (fy - 0.5) * height as f32;
+                let corner = (width as f32 / 4.0).hypot(height as f32 / 2.0).max(1.0);
+                dx.hypot(dy) / corner
+            }
+        };
+        ((t * (1.0 + SOFT_EDGE) - start) / SOFT_EDGE).clamp(0.0, 1.0)
+    }
+}
 
-/// Manages a crossfade transition from an outgoing effect to the new current effect.
+/// Stable pseudo-random value in [0, 1) for a cell, for the dissolve order.
+fn cell_noise(x: u16, y: u16) -> f32 {
+    let mut h = (x as u32).wrapping_mul(0x9E37_79B1) ^ (y as u32).wrapping_mul(0x85EB_CA77);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2C1B_3C6D);
+    h ^= h >> 12;
+    (h & 0xFFFF) as f32 / 65536.0
+}
+
+/// Manages a transition from an outgoing effect to the new current effect.
 ///
 /// The outgoing effect renders into a scratch buffer, then blends with the
 /// incoming effect (already rendered into the main buffer) on a per-cell basis.
@@ -21,6 +126,9 @@ pub struct Transition {
     duration: f64,
     /// Time elapsed since the transition started
     elapsed: f64,
+    kind: TransitionKind,
+    /// Corruption of the outgoing frame, for the glitch style
+    glitch: Option<(Corruption, SimRng)>,
 }
 
 impl Transition {
@@ -34,9 +142,24 @@ impl Transition {
             scratch: ScreenBuffer::new(width, height),
             duration: duration.max(0.05), // minimum duration to avoid division by zero
             elapsed: 0.0,
+            kind: TransitionKind::Crossfade,
+            glitch: None,
         }
     }
 
+    /// Use `kind` instead of a crossfade. `seed` (from `--seed`) fixes the
+    /// glitch style's corruption.
+    pub fn with_kind(mut self, kind: TransitionKind, seed: Option<u64>) -> Self {
+        self.kind = kind;
+        self.glitch = (kind == TransitionKind::Glitch).then(|| {
+            (
+                Corruption::new(self.scratch.width(), self.scratch.height()),
+                rng::make_rng(seed, Stream::Glitch),
+            )
+        });
+        self
+    }
+
     /// Whether the transition has completed (outgoing fully faded out).
     pub fn is_complete(&self) -> bool {
         self.elapsed >= self.duration
@@ -46,6 +169,21 @@ impl Transition {
     pub fn update(&mut self, delta_time: f64) {
         self.outgoing.update(delta_time);
         self.elapsed += delta_time;
+
+        // Glitches come faster and bigger as the cut approaches
+        if let Some((corruption, rng)) = &mut self.glitch {
+            let progress = (self.elapsed / self.duration).clamp(0.0, 1.0);
+            let rate = 10.0 + 150.0 * progress * progress;
+            let severity = 1.0 + 3.0 * progress;
+            let mut due = rate * delta_time;
+            while due > 0.0 {
+                if due >= 1.0 || rng.random_bool(due) {
+                    corruption.spawn(rng, severity);
+                }
+                due -= 1.0;
+            }
+            corruption.update(delta_time, rng);
+        }
     }
 
     /// Blend the outgoing effect into the main buffer.
@@ -54,12 +192,16 @@ impl Transition {
     /// The outgoing effect renders into the scratch buffer, then each cell is
     /// blended based on the transition progress.
     pub fn render(&mut self, buffer: &mut ScreenBuffer) {
-        // Render outgoing into scratch
+        // Render outgoing into scratch, over the same background
+        self.scratch.set_background(buffer.background());
         self.scratch.clear();
         self.outgoing.render(&mut self.scratch);
+        if let Some((corruption, _)) = &self.glitch {
+            corruption.apply(&mut self.scratch);
+        }
 
-        // Blend factor: 0.0 = all outgoing, 1.0 = all incoming
-        let t = (self.elapsed / self.duration).clamp(0.0, 1.0) as f32;
+        // Overall progress: 0.0 = all outgoing, 1.0 = all incoming
+        let progress = (self.elapsed / self.duration).clamp(0.0, 1.0) as f32;
 
         let w = buffer.width();
         let h = buffer.height();
@@ -75,6 +217,7 @@ impl Transition {
                         continue;
                     }
 
+                    let t = self.kind.cell_progress(x, y, w, h, progress);
                     let blended_fg = lerp_color(out.fg, inc.fg, t);
                     let blended_bg = lerp_color(out.bg, inc.bg, t);
                     // Character switches at the midpoint
@@ -90,13 +233,87 @@ impl Transition {
     pub fn resize(&mut self, width: u16, height: u16) {
         self.outgoing.resize(width, height);
         self.scratch.resize(width, height);
+        if let Some((corruption, _)) = &mut self.glitch {
+            corruption.resize(width, height);
+        }
+    }
+}
+
+/// The running effect's palette drifting from one palette to another and
+/// back, easing along a sine wave, once every `period` seconds
+/// (`--color-morph`).
+pub struct PaletteMorph {
+    from: Palette,
+    to: Palette,
+    period: f64,
+    elapsed: f64,
+}
+
+impl PaletteMorph {
+    pub fn new(from: Palette, to: Palette, period: f64) -> Self {
+        Self {
+            from,
+            to,
+            period: period.max(0.05),
+            elapsed: 0.0,
+        }
+    }
+
+    /// The palette at the current point of the drift.
+    pub fn current(&self) -> Palette {
+        let angle = self.elapsed / self.period * std::f64::consts::TAU;
+        let t = (1.0 - angle.cos()) / 2.0;
+        self.from.lerp(&self.to, t as f32)
+    }
+
+    /// Advance the drift and return the palette to show now.
+    pub fn update(&mut self, delta_time: f64) -> Palette {
+        self.elapsed = (self.elapsed + delta_time) % self.period;
+        self.current()
+    }
+}
+
+/// A palette change on the running effect, blended over `duration`.
+pub struct PaletteFade {
+    from: Palette,
+    to: Palette,
+    duration: f64,
+    elapsed: f64,
+}
+
+impl PaletteFade {
+    pub fn new(from: Palette, to: Palette, duration: f64) -> Self {
+        Self {
+            from,
+            to,
+            duration: duration.max(0.05),
+            elapsed: 0.0,
+        }
+    }
+
+    /// The palette at the current point of the fade.
+    pub fn current(&self) -> Palette {
+        let t = (self.elapsed / self.duration).clamp(0.0, 1.0) as f32;
+        self.from.lerp(&self.to, t)
+    }
+
+    /// Advance the fade and return the palette to show now.
+    pub fn update(&mut self, delta_time: f64) -> Palette {
+        self.elapsed += delta_time;
+        self.current()
+    }
+
+    /// Whether the fade has reached the target palette.
+    pub fn is_complete(&self) -> bool {
+        self.elapsed >= self.duration
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crossterm::style::Color;
+    use crate::buffer::RenderTarget;
+    use crate::color::Color;
 
     /// Minimal test effect that fills the buffer with a single character and color.
     struct TestEffect {
@@ -109,7 +326,7 @@ mod tests {
             "test"
         }
         fn update(&mut self, _dt: f64) {}
-        fn render(&self, buffer: &mut ScreenBuffer) {
+        fn render(&self, buffer: &mut dyn RenderTarget) {
             for y in 0..buffer.height() {
                 for x in 0..buffer.width() {
                     buffer.set_cell(x, y, self.ch, self.color, Color::Reset);
@@ -203,4 +420,150 @@ mod tests {
             g
         );
     }
+
+    /// Fill a buffer with the incoming effect's 'B' cells.
+    fn incoming(width: u16, height: u16) -> ScreenBuffer {
+        let mut buffer = ScreenBuffer::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                buffer.set_cell(x, y, 'B', rgb(0, 200, 0), Color::Reset);
+            }
+        }
+        buffer
+    }
+
+    /// Render `kind` halfway through and return the characters, row by row.
+    fn halfway(kind: TransitionKind, width: u16, height: u16) -> Vec<String> {
+        let outgoing = Box::new(TestEffect {
+            ch: 'A',
+            color: rgb(200, 0, 0),
+        });
+        let mut t = Transition::new(outgoing, width, height, 1.0).with_kind(kind, None);
+        t.update(0.5);
+        let mut buffer = incoming(width, height);
+        t.render(&mut buffer);
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| buffer.get_cell(x, y).unwrap().ch)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn wipes_and_iris_switch_cells_by_position() {
+        let rows = halfway(TransitionKind::WipeRight, 20, 3);
+        assert!(
+            rows[1].starts_with('B') && rows[1].ends_with('A'),
+            "{:?}",
+            rows
+        );
+        let rows = halfway(TransitionKind::WipeLeft, 20, 3);
+        assert!(
+            rows[1].starts_with('A') && rows[1].ends_with('B'),
+            "{:?}",
+            rows
+        );
+        let rows = halfway(TransitionKind::WipeDown, 3, 10);
+        assert!(rows[0] == "BBB" && rows[9] == "AAA", "{:?}", rows);
+
+        // Iris: open in the middle, closed in the corners
+        let rows = halfway(TransitionKind::Iris, 21, 11);
+        assert_eq!(rows[5].chars().nth(10), Some('B'));
+        assert_eq!(rows[0].chars().next(), Some('A'));
+
+        // Blinds: every slat is half open
+        let rows = halfway(TransitionKind::Blinds, 16, 1);
+        assert_eq!(rows[0], "BBBBAAAABBBBAAAA");
+    }
+
+    #[test]
+    fn dissolve_switches_a_scattered_share_of_cells() {
+        let rows = halfway(TransitionKind::Dissolve, 40, 10);
+        let switched = rows
+            .iter()
+            .flat_map(|r| r.chars())
+            .filter(|&c| c == 'B')
+            .count();
+        assert!(
+            (120..280).contains(&switched),
+            "{} of 400 switched",
+            switched
+        );
+        // Not a wipe: both halves of a row are mixed
+        assert!(rows[5][..20].contains('A') && rows[5][..20].contains('B'));
+    }
+
+    #[test]
+    fn random_picks_a_concrete_style() {
+        let mut rng = crate::rng::make_rng(Some(1), crate::rng::Stream::Transition);
+        for _ in 0..20 {
+            assert_ne!(
+                TransitionKind::Random.pick(&mut rng),
+                TransitionKind::Random
+            );
+        }
+        assert_eq!(TransitionKind::Iris.pick(&mut rng), TransitionKind::Iris);
+    }
+
+    #[test]
+    fn glitch_corrupts_the_outgoing_frame_then_cuts() {
+        let outgoing = Box::new(TestEffect {
+            ch: 'A',
+            color: rgb(200, 0, 0),
+        });
+        let mut t =
+            Transition::new(outgoing, 40, 20, 1.0).with_kind(TransitionKind::Glitch, Some(7));
+
+        // Before the cut: only outgoing cells, some of them corrupted
+        let mut corrupted = false;
+        for _ in 0..8 {
+            t.update(0.1);
+            let mut buffer = incoming(40, 20);
+            t.render(&mut buffer);
+            let cells: Vec<char> = (0..20)
+                .flat_map(|y| (0..40).map(move |x| (x, y)))
+                .map(|(x, y)| buffer.get_cell(x, y).unwrap().ch)
+                .collect();
+            assert!(!cells.contains(&'B'));
+            corrupted |= cells.iter().any(|&c| c != 'A');
+        }
+        assert!(corrupted);
+
+        // After it: the incoming effect, untouched
+        t.update(0.1);
+        let mut buffer = incoming(40, 20);
+        t.render(&mut buffer);
+        assert!((0..20).all(|y| (0..40).all(|x| buffer.get_cell(x, y).unwrap().ch == 'B')));
+    }
+
+    #[test]
+    fn palette_fade_reaches_the_target() {
+        use crate::color::gradient::color_to_rgb;
+        let from = Palette::classic();
+        let to = Palette::red();
+        let mut fade = PaletteFade::new(from.clone(), to.clone(), 1.0);
+        let half = fade.update(0.5);
+        assert!(!fade.is_complete());
+        assert_ne!(color_to_rgb(half.head), color_to_rgb(from.head));
+        assert_ne!(color_to_rgb(half.head), color_to_rgb(to.head));
+        let end = fade.update(0.6);
+        assert!(fade.is_complete());
+        assert_eq!(color_to_rgb(end.head), color_to_rgb(to.head));
+    }
+
+    #[test]
+    fn palette_morph_swings_there_and_back() {
+        use crate::color::gradient::color_to_rgb;
+        let from = Palette::classic();
+        let to = Palette::purple();
+        let mut morph = PaletteMorph::new(from.clone(), to.clone(), 10.0);
+        assert_eq!(morph.current(), from);
+        let quarter = morph.update(2.5);
+        assert_ne!(color_to_rgb(quarter.body_mid), color_to_rgb(from.body_mid));
+        assert_ne!(color_to_rgb(quarter.body_mid), color_to_rgb(to.body_mid));
+        assert_eq!(morph.update(2.5), to);
+        assert_eq!(morph.update(5.0), from);
+    }
 }
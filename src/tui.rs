@@ -0,0 +1,149 @@
+//! A ratatui widget for embedding effects in TUI apps (`ratatui` feature).
+//!
+//! [`DigitalRainState`] owns the effect and an off-screen [`ScreenBuffer`];
+//! [`DigitalRainWidget`] renders it into any `Rect` of a ratatui frame,
+//! resizing the effect to fit. Blank cells are left untouched, so the rain
+//! can sit behind other widgets as a background.
+//!
+//! ```ignore
+//! let mut rain = DigitalRainState::new("classic", &config, 80, 24).unwrap();
+//! // each frame:
+//! rain.tick(dt);
+//! frame.render_stateful_widget(DigitalRainWidget, frame.area(), &mut rain);
+//! ```
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::widgets::{StatefulWidget, Widget};
+
+use crate::buffer::{Cell, ScreenBuffer};
+use crate::color::Color as CellColor;
+use crate::config::Config;
+use crate::effects::{Effect, registry};
+
+/// A running effect and the buffer it renders into.
+pub struct DigitalRainState {
+    effect: Box<dyn Effect>,
+    screen: ScreenBuffer,
+}
+
+impl DigitalRainState {
+    /// The named effect at `width` x `height`, with any of `config`'s
+    /// parameters it takes. Fails for an unknown name or a bad parameter.
+    pub fn new(effect: &str, config: &Config, width: u16, height: u16) -> Result<Self, String> {
+        Ok(Self::from_effect(
+            registry::create_effect(&config.effect_spec_for(effect), width, height, config)?,
+            width,
+            height,
+        ))
+    }
+
+    /// Wraps an effect that was already created at `width` x `height`.
+    pub fn from_effect(effect: Box<dyn Effect>, width: u16, height: u16) -> Self {
+        Self {
+            effect,
+            screen: ScreenBuffer::new(width, height),
+        }
+    }
+
+    /// Advances the effect by `delta_time` seconds.
+    pub fn tick(&mut self, delta_time: f64) {
+        self.effect.update(delta_time);
+    }
+
+    /// The effect, for live changes (speed, palette, ...).
+    pub fn effect_mut(&mut self) -> &mut dyn Effect {
+        self.effect.as_mut()
+    }
+
+    fn fit(&mut self, width: u16, height: u16) {
+        if (width, height) != (self.screen.width(), self.screen.height()) {
+            self.screen.resize(width, height);
+            self.effect.resize(width, height);
+        }
+    }
+}
+
+/// Renders a [`DigitalRainState`] into a ratatui buffer.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DigitalRainWidget;
+
+impl StatefulWidget for DigitalRainWidget {
+    type State = DigitalRainState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let area = area.intersection(buf.area);
+        if area.is_empty() {
+            return;
+        }
+        state.fit(area.width, area.height);
+        state.screen.clear();
+        state.effect.render(&mut state.screen);
+        let blank = Cell::default();
+        for y in 0..area.height {
+            for x in 0..area.width {
+                let Some(cell) = state.screen.get_cell(x, y) else {
+                    continue;
+                };
+                if *cell == blank {
+                    continue;
+                }
+                if let Some(out) = buf.cell_mut((area.x + x, area.y + y)) {
+                    out.set_char(cell.ch);
+                    out.set_fg(to_ratatui(cell.fg));
+                    if cell.bg != CellColor::Reset {
+                        out.set_bg(to_ratatui(cell.bg));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Widget for &mut DigitalRainState {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        StatefulWidget::render(DigitalRainWidget, area, buf, self);
+    }
+}
+
+/// The ratatui equivalent of a cell color.
+fn to_ratatui(color: CellColor) -> Color {
+    match color {
+        CellColor::Reset => Color::Reset,
+        CellColor::Rgb { r, g, b } => Color::Rgb(r, g, b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Cli, ConfigFile};
+    use clap::Parser;
+
+    #[test]
+    fn renders_inside_the_area_and_keeps_the_background() {
+        let config = Config::resolve(
+            &Cli::parse_from(["digital_rain", "--seed", "7"]),
+            &ConfigFile::default(),
+        );
+        let mut rain = DigitalRainState::new("classic", &config, 10, 5).unwrap();
+        let mut buf = Buffer::empty(Rect::new(0, 0, 30, 12));
+        buf.set_string(0, 0, "#".repeat(30), ratatui::style::Style::default());
+        let area = Rect::new(5, 2, 20, 8);
+        DigitalRainWidget.render(area, &mut buf, &mut rain);
+        for _ in 0..90 {
+            rain.tick(1.0 / 30.0);
+        }
+        DigitalRainWidget.render(area, &mut buf, &mut rain);
+
+        assert_eq!((rain.screen.width(), rain.screen.height()), (20, 8));
+        assert!(buf[(0, 0)].symbol() == "#" && buf[(29, 0)].symbol() == "#");
+        let drawn = |x, y| buf[(x, y)].symbol() != " ";
+        assert!(
+            (0..30)
+                .all(|x| (0..12).all(|y| !drawn(x, y) || y == 0 || area.contains((x, y).into())))
+        );
+        assert!((5..25).any(|x| (2..10).any(|y| drawn(x, y))));
+    }
+}
@@ -0,0 +1,265 @@
+//! Real-weather presets for `--weather`: the sky outside picks the rain.
+//!
+//! A background thread asks a `WeatherProvider` for a city's current
+//! conditions every refresh interval and hands them to the main loop over a
+//! channel. The main loop turns each new reading into a `Look` (effect,
+//! palette, density, and speed) and crossfades to it: a downpour outside
+//! brings heavy classic rain, snow a slow white parallax drift, a clear
+//! night sparse deep-blue parallax.
+//!
+//! Fetching needs an HTTP client, so the Open-Meteo provider is behind the
+//! `weather` feature; the mapping from conditions to looks is always built.
+
+use std::fmt;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+/// Shortest refresh interval, so a typo doesn't hammer the provider.
+pub const MIN_REFRESH_SECS: f64 = 60.0;
+
+/// What the sky is doing, coarsely.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sky {
+    Clear,
+    Cloudy,
+    Fog,
+    Drizzle,
+    Rain,
+    Snow,
+    Thunderstorm,
+}
+
+impl Sky {
+    /// The sky for a WMO weather interpretation code, as reported by most
+    /// weather services (0 clear, 45 fog, 61 rain, 71 snow, 95 storm, ...).
+    pub fn from_wmo(code: u32) -> Self {
+        match code {
+            0 | 1 => Sky::Clear,
+            45 | 48 => Sky::Fog,
+            51..=57 => Sky::Drizzle,
+            61..=67 | 80..=82 => Sky::Rain,
+            71..=77 | 85 | 86 => Sky::Snow,
+            95..=99 => Sky::Thunderstorm,
+            _ => Sky::Cloudy,
+        }
+    }
+}
+
+/// Current conditions from a provider.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Conditions {
+    pub sky: Sky,
+    /// Between sunrise and sunset at the location
+    pub is_day: bool,
+}
+
+impl fmt::Display for Conditions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sky = match self.sky {
+            Sky::Clear if self.is_day => "clear",
+            Sky::Clear => "clear night",
+            Sky::Cloudy => "cloudy",
+            Sky::Fog => "fog",
+            Sky::Drizzle => "drizzle",
+            Sky::Rain => "rain",
+            Sky::Snow => "snow",
+            Sky::Thunderstorm => "thunderstorm",
+        };
+        f.write_str(sky)
+    }
+}
+
+/// The effect settings a kind of weather calls for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Look {
+    pub effect: &'static str,
+    pub palette: &'static str,
+    pub density: f64,
+    pub speed: f64,
+}
+
+impl Conditions {
+    pub fn look(&self) -> Look {
+        let (effect, palette, density, speed) = match self.sky {
+            // The effect brings its own downpour
+            Sky::Thunderstorm => ("thunderstorm", "classic", 1.0, 1.1),
+            Sky::Rain => ("classic", "classic", 2.5, 1.4),
+            Sky::Drizzle => ("classic", "classic", 1.2, 0.8),
+            // Slow white flakes drifting down at different depths
+            Sky::Snow => ("parallax", "snow", 0.6, 0.3),
+            Sky::Fog => ("pulse", "silver", 0.7, 0.6),
+            Sky::Cloudy => ("classic", "classic", 1.0, 1.0),
+            Sky::Clear if self.is_day => ("classic", "gold", 0.5, 0.8),
+            // Far-off specks drifting at depth, like stars
+            Sky::Clear => ("parallax", "ocean", 0.3, 0.4),
+        };
+        Look {
+            effect,
+            palette,
+            density,
+            speed,
+        }
+    }
+}
+
+/// A source of current conditions.
+pub trait WeatherProvider: Send {
+    /// Conditions right now, or why they couldn't be fetched.
+    fn current(&mut self) -> Result<Conditions, String>;
+}
+
+/// Receiving end of the weather thread.
+pub struct WeatherFeed {
+    readings: Receiver<Result<Conditions, String>>,
+}
+
+impl WeatherFeed {
+    /// Ask `provider` for conditions now and then every `refresh`.
+    pub fn spawn(mut provider: Box<dyn WeatherProvider>, refresh: Duration) -> Self {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            while tx.send(provider.current()).is_ok() {
+                thread::sleep(refresh);
+            }
+        });
+        Self { readings: rx }
+    }
+
+    /// The newest reading since the last call, if any arrived.
+    pub fn latest(&self) -> Option<Result<Conditions, String>> {
+        self.readings.try_iter().last()
+    }
+}
+
+/// Current conditions from Open-Meteo (no API key needed). The city is
+/// looked up once through its geocoding service.
+#[cfg(feature = "weather")]
+pub struct OpenMeteo {
+    city: String,
+    /// Latitude and longitude, once the city has been found
+    location: Option<(f64, f64)>,
+}
+
+#[cfg(feature = "weather")]
+impl OpenMeteo {
+    const GEOCODING_URL: &str = "https://geocoding-api.open-meteo.com/v1/search";
+    const FORECAST_URL: &str = "https://api.open-meteo.com/v1/forecast";
+
+    pub fn new(city: &str) -> Self {
+        Self {
+            city: city.to_string(),
+            location: None,
+        }
+    }
+
+    fn get_json(request: ureq::Request) -> Result<toml::Value, String> {
+        let body = request
+            .timeout(Duration::from_secs(15))
+            .call()
+            .map_err(|e| match e {
+                ureq::Error::Status(code, _) => format!("Open-Meteo answered {code}"),
+                ureq::Error::Transport(t) => format!("can't reach Open-Meteo ({})", t.kind()),
+            })?
+            .into_string()
+            .map_err(|e| e.to_string())?;
+        crate::formats::parse_json(&body)
+    }
+
+    fn locate(&self) -> Result<(f64, f64), String> {
+        let found = Self::get_json(
+            ureq::get(Self::GEOCODING_URL)
+                .query("name", &self.city)
+                .query("count", "1"),
+        )?;
+        let place = found
+            .get("results")
+            .and_then(|r| r.get(0))
+            .ok_or_else(|| format!("no place called '{}'", self.city))?;
+        match (number(place, "latitude"), number(place, "longitude")) {
+            (Some(lat), Some(lon)) => Ok((lat, lon)),
+            _ => Err(format!("no coordinates for '{}'", self.city)),
+        }
+    }
+}
+
+#[cfg(feature = "weather")]
+impl WeatherProvider for OpenMeteo {
+    fn current(&mut self) -> Result<Conditions, String> {
+        let (lat, lon) = match self.location {
+            Some(location) => location,
+            None => *self.location.insert(self.locate()?),
+        };
+        let forecast = Self::get_json(
+            ureq::get(Self::FORECAST_URL)
+                .query("latitude", &lat.to_string())
+                .query("longitude", &lon.to_string())
+                .query("current", "weather_code,is_day"),
+        )?;
+        let current = forecast
+            .get("current")
+            .ok_or("forecast without current conditions")?;
+        let code = number(current, "weather_code").ok_or("forecast without a weather code")?;
+        Ok(Conditions {
+            sky: Sky::from_wmo(code as u32),
+            is_day: number(current, "is_day").is_none_or(|d| d != 0.0),
+        })
+    }
+}
+
+/// A JSON number field, whether it was written as an integer or not.
+#[cfg(feature = "weather")]
+fn number(value: &toml::Value, key: &str) -> Option<f64> {
+    match value.get(key)? {
+        toml::Value::Integer(i) => Some(*i as f64),
+        toml::Value::Float(f) => Some(*f),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weather_codes_pick_a_look() {
+        let look = |code, is_day| {
+            Conditions {
+                sky: Sky::from_wmo(code),
+                is_day,
+            }
+            .look()
+        };
+        // Heavy rain pours, drizzle doesn't
+        assert_eq!(look(65, true).effect, "classic");
+        assert!(look(65, true).density > look(53, true).density);
+        assert_eq!(look(73, true).palette, "snow");
+        assert_eq!(look(73, true).effect, "parallax");
+        assert_eq!(look(95, false).effect, "thunderstorm");
+        assert_eq!(look(0, false).effect, "parallax");
+        assert_eq!(look(0, true).palette, "gold");
+        assert_eq!(look(3, true), look(2, false));
+        for look in [look(45, true), look(0, false), look(71, true)] {
+            assert!(crate::effects::registry::effect_names().contains(&look.effect));
+        }
+    }
+
+    #[test]
+    fn the_feed_keeps_the_newest_reading() {
+        struct Scripted(Vec<Result<Conditions, String>>);
+        impl WeatherProvider for Scripted {
+            fn current(&mut self) -> Result<Conditions, String> {
+                self.0.pop().unwrap_or(Err("done".to_string()))
+            }
+        }
+        let snow = Conditions {
+            sky: Sky::Snow,
+            is_day: true,
+        };
+        let feed = WeatherFeed::spawn(Box::new(Scripted(vec![Ok(snow)])), Duration::from_millis(1));
+        let first = feed.readings.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(first, Ok(snow));
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(feed.latest(), Some(Err("done".to_string())));
+    }
+}
@@ -0,0 +1,126 @@
+//! Browser build (`wasm` feature): a few C-ABI exports that the JS shim in
+//! `examples/web` calls to run an effect inside an xterm.js terminal.
+//!
+//! The page writes a command line (the same options the binary takes, such
+//! as `--effect fire --color gold`) into the input buffer, starts a run at
+//! the terminal's size, then calls `dr_frame` from `requestAnimationFrame`.
+//! Each frame comes back as ANSI text in the output buffer: cursor moves and
+//! colors for only the cells that changed, ready for `term.write`.
+//!
+//! There's no OS entropy in the browser, so the page passes the seed.
+
+use std::cell::RefCell;
+
+use clap::Parser;
+
+use crate::config::{Cli, Config, ConfigFile};
+use crate::runner::{self, Runner};
+
+#[derive(Default)]
+struct Web {
+    input: Vec<u8>,
+    output: Vec<u8>,
+    runner: Option<Runner>,
+}
+
+thread_local! {
+    static WEB: RefCell<Web> = RefCell::new(Web::default());
+}
+
+/// Make room for `len` bytes of UTF-8 input and return where to write them.
+#[unsafe(no_mangle)]
+pub extern "C" fn dr_input(len: usize) -> *mut u8 {
+    WEB.with_borrow_mut(|web| {
+        web.input = vec![0; len];
+        web.input.as_mut_ptr()
+    })
+}
+
+/// Start a run at `width` x `height` from the command line in the input
+/// buffer. Returns 0, or the length of an error message left in the output
+/// buffer.
+#[unsafe(no_mangle)]
+pub extern "C" fn dr_start(width: u16, height: u16, seed: u32) -> usize {
+    WEB.with_borrow_mut(|web| match start(&web.input, width, height, seed) {
+        Ok(runner) => {
+            web.runner = Some(runner);
+            0
+        }
+        Err(e) => {
+            web.runner = None;
+            web.output = e.into_bytes();
+            web.output.len()
+        }
+    })
+}
+
+fn start(args: &[u8], width: u16, height: u16, seed: u32) -> Result<Runner, String> {
+    let args = std::str::from_utf8(args).map_err(|e| e.to_string())?;
+    let cli = Cli::try_parse_from(std::iter::once("digital_rain").chain(args.split_whitespace()))
+        .map_err(|e| e.to_string())?;
+    let mut config = Config::resolve(&cli, &ConfigFile::default());
+    config.seed = config.seed.or(Some(seed as u64));
+    Runner::new(&config, width.max(1), height.max(1))
+}
+
+/// Advance by `delta_ms` and leave the frame's ANSI output in the output
+/// buffer. Returns its length (0 before a successful `dr_start`).
+#[unsafe(no_mangle)]
+pub extern "C" fn dr_frame(delta_ms: f64) -> usize {
+    WEB.with_borrow_mut(|web| {
+        let Some(runner) = web.runner.as_mut() else {
+            return 0;
+        };
+        runner.step(runner::host_step(delta_ms / 1000.0));
+        web.output.clear();
+        // Writing to a Vec can't fail
+        let _ = runner.flush_to(&mut web.output);
+        web.output.len()
+    })
+}
+
+/// Adopt a new terminal size; the next frame redraws everything.
+#[unsafe(no_mangle)]
+pub extern "C" fn dr_resize(width: u16, height: u16) {
+    WEB.with_borrow_mut(|web| {
+        if let Some(runner) = web.runner.as_mut() {
+            runner.resize(width.max(1), height.max(1));
+        }
+    });
+}
+
+/// Where the last frame (or error message) starts.
+#[unsafe(no_mangle)]
+pub extern "C" fn dr_output() -> *const u8 {
+    WEB.with_borrow(|web| web.output.as_ptr())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn start_with(args: &str) -> usize {
+        let input = dr_input(args.len());
+        unsafe { std::ptr::copy_nonoverlapping(args.as_ptr(), input, args.len()) };
+        dr_start(20, 6, 9)
+    }
+
+    fn output(len: usize) -> String {
+        let bytes = unsafe { std::slice::from_raw_parts(dr_output(), len) };
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn frames_come_back_as_ansi_and_errors_as_text() {
+        assert_eq!(start_with("--effect binary --color gold"), 0);
+        let first = output(dr_frame(33.0));
+        assert!(first.starts_with("\x1b[1;1H"));
+        // Every cell is drawn on the first frame, only changes after that
+        assert_eq!(first.matches('H').count(), 20 * 6);
+        assert!(dr_frame(33.0) < first.len());
+
+        let error = output(start_with("--effect nope --bogus"));
+        assert!(error.contains("--bogus"));
+        assert_eq!(dr_frame(33.0), 0);
+    }
+}
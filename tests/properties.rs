@@ -0,0 +1,280 @@
+//! Property tests: random sequences of calls against every effect, the
+//! screen buffer, and rain columns, checking that nothing panics, nothing
+//! is drawn outside the grid, and the invariants the renderers rely on
+//! hold.
+//!
+//! `cargo test` runs a small number of cases so the suite stays quick. Set
+//! `PROPTEST_CASES` to fuzz harder, e.g.
+//! `PROPTEST_CASES=5000 cargo test --release --test properties`.
+//! A failing case is shrunk and saved to `properties.proptest-regressions`
+//! next to this file, so later runs try it first.
+
+use std::collections::HashMap;
+
+use clap::Parser;
+use digitalrain::buffer::{Cell, Region, RenderTarget, ScreenBuffer};
+use digitalrain::color::Color;
+use digitalrain::config::{Cli, Config, ConfigFile};
+use digitalrain::effects::registry;
+use digitalrain::rain::chars::CharacterPool;
+use digitalrain::rain::column::RainColumn;
+use digitalrain::rng::SimRng;
+use proptest::prelude::*;
+use rand::SeedableRng;
+
+/// `cases`, unless `PROPTEST_CASES` asks for a different number.
+fn cases(cases: u32) -> ProptestConfig {
+    let cases = std::env::var("PROPTEST_CASES")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(cases);
+    ProptestConfig::with_cases(cases)
+}
+
+/// A render target that counts writes outside its grid instead of
+/// dropping them silently.
+struct CheckedTarget {
+    buffer: ScreenBuffer,
+    out_of_bounds: Vec<(u16, u16)>,
+}
+
+impl CheckedTarget {
+    fn new(width: u16, height: u16) -> Self {
+        Self {
+            buffer: ScreenBuffer::new(width, height),
+            out_of_bounds: Vec::new(),
+        }
+    }
+}
+
+impl RenderTarget for CheckedTarget {
+    fn width(&self) -> u16 {
+        self.buffer.width()
+    }
+
+    fn height(&self) -> u16 {
+        self.buffer.height()
+    }
+
+    fn get_cell(&self, x: u16, y: u16) -> Option<Cell> {
+        self.buffer.get_cell(x, y).copied()
+    }
+
+    fn set_cell(&mut self, x: u16, y: u16, ch: char, fg: Color, bg: Color) {
+        if x >= self.buffer.width() || y >= self.buffer.height() {
+            self.out_of_bounds.push((x, y));
+        }
+        self.buffer.set_cell(x, y, ch, fg, bg);
+    }
+
+    fn clear(&mut self) {
+        self.buffer.clear();
+    }
+}
+
+#[derive(Clone, Debug)]
+enum EffectOp {
+    Resize(u16, u16),
+    /// Update by this many seconds, then render
+    Update(f64),
+    SetSpeed(f64),
+    SetDensity(f64),
+}
+
+fn effect_op() -> impl Strategy<Value = EffectOp> {
+    prop_oneof![
+        1 => (0u16..=100, 0u16..=40).prop_map(|(w, h)| EffectOp::Resize(w, h)),
+        // Mostly frame-sized steps, sometimes a stall or a zero step
+        6 => prop_oneof![0.0..0.1, Just(0.0), 0.1..3.0].prop_map(EffectOp::Update),
+        1 => (0.0..10.0).prop_map(EffectOp::SetSpeed),
+        1 => (0.0..5.0).prop_map(EffectOp::SetDensity),
+    ]
+}
+
+proptest! {
+    #![proptest_config(cases(16))]
+
+    #[test]
+    fn effects_survive_random_calls(
+        seed in any::<u64>(),
+        size in (0u16..=100, 0u16..=40),
+        ops in prop::collection::vec(effect_op(), 1..24),
+    ) {
+        let seed = seed.to_string();
+        let cli = Cli::parse_from(["digital_rain", "--seed", seed.as_str()]);
+        let config = Config::resolve(&cli, &ConfigFile::default());
+        for name in registry::effect_names() {
+            let (mut width, mut height) = size;
+            let spec = config.effect_spec_for(name);
+            let mut effect = registry::create_effect(&spec, width, height, &config)
+                .map_err(TestCaseError::fail)?;
+            for op in &ops {
+                match *op {
+                    EffectOp::Resize(w, h) => {
+                        (width, height) = (w, h);
+                        effect.resize(w, h);
+                    }
+                    EffectOp::Update(dt) => {
+                        effect.update(dt);
+                        let mut target = CheckedTarget::new(width, height);
+                        effect.render(&mut target);
+                        prop_assert!(
+                            target.out_of_bounds.is_empty(),
+                            "{} drew outside {}x{} at {:?}",
+                            name,
+                            width,
+                            height,
+                            target.out_of_bounds
+                        );
+                    }
+                    EffectOp::SetSpeed(speed) => effect.set_speed(speed),
+                    EffectOp::SetDensity(density) => effect.set_density(density),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum BufferOp {
+    Set(u16, u16, char, u8),
+    Clear,
+    Resize(u16, u16),
+    SetRegion(Region),
+    Flush,
+}
+
+fn buffer_op() -> impl Strategy<Value = BufferOp> {
+    let origin = prop_oneof![0u16..8, (u16::MAX - 8)..=u16::MAX];
+    prop_oneof![
+        8 => (0u16..24, 0u16..16, prop::sample::select(vec!['a', 'b', ' ']), 0u8..3)
+            .prop_map(|(x, y, ch, shade)| BufferOp::Set(x, y, ch, shade)),
+        1 => Just(BufferOp::Clear),
+        1 => (0u16..=20, 0u16..=12).prop_map(|(w, h)| BufferOp::Resize(w, h)),
+        1 => (origin.clone(), origin, 0u16..=20, 0u16..=12).prop_map(|(x, y, width, height)| {
+            BufferOp::SetRegion(Region { x, y, width, height })
+        }),
+        3 => Just(BufferOp::Flush),
+    ]
+}
+
+/// Play a flush's output onto `screen`, returning the positions written,
+/// zero-based.
+fn apply_flush(output: &str, screen: &mut HashMap<(u32, u32), char>) -> Vec<(u32, u32)> {
+    let mut written = Vec::new();
+    let mut cursor = (0, 0);
+    let mut chars = output.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\x1b' {
+            screen.insert(cursor, ch);
+            written.push(cursor);
+            cursor.0 += 1;
+            continue;
+        }
+        chars.next(); // '['
+        let mut params = String::new();
+        for c in chars.by_ref() {
+            if c.is_ascii_alphabetic() {
+                // Colors end in 'm' and don't move the cursor
+                if let ('H', Some((row, col))) = (c, params.split_once(';')) {
+                    cursor = (
+                        col.parse::<u32>().unwrap() - 1,
+                        row.parse::<u32>().unwrap() - 1,
+                    );
+                }
+                break;
+            }
+            params.push(c);
+        }
+    }
+    written
+}
+
+proptest! {
+    #![proptest_config(cases(64))]
+
+    #[test]
+    fn buffer_flushes_exactly_the_changed_cells(
+        ops in prop::collection::vec(buffer_op(), 1..60),
+    ) {
+        let shades = [Color::Reset, Color::Rgb { r: 0, g: 255, b: 0 }, Color::Rgb { r: 0, g: 90, b: 0 }];
+        let mut buffer = ScreenBuffer::new(10, 6);
+        // What the terminal shows, and what the last flush showed
+        let mut screen = HashMap::new();
+        let mut shown: Option<Vec<Cell>> = None;
+        for op in ops {
+            match op {
+                BufferOp::Set(x, y, ch, shade) => {
+                    let fg = shades[shade as usize];
+                    buffer.set_cell(x, y, ch, fg, Color::Reset);
+                }
+                BufferOp::Clear => buffer.clear(),
+                BufferOp::Resize(w, h) => {
+                    buffer.resize(w, h);
+                    shown = None;
+                }
+                BufferOp::SetRegion(region) => {
+                    buffer.set_region(region);
+                    shown = None;
+                }
+                BufferOp::Flush => {
+                    let frame = buffer.cells().to_vec();
+                    prop_assert_eq!(
+                        frame.len(),
+                        buffer.width() as usize * buffer.height() as usize
+                    );
+                    let region = buffer.region();
+                    let mut out = Vec::new();
+                    buffer.flush_to(&mut out).unwrap();
+                    let written = apply_flush(&String::from_utf8(out).unwrap(), &mut screen);
+
+                    let (left, top) = (region.x as u32, region.y as u32);
+                    for &(x, y) in &written {
+                        prop_assert!(
+                            x >= left && x < left + region.width as u32
+                                && y >= top && y < top + region.height as u32,
+                            "wrote ({}, {}) outside {:?}", x, y, region
+                        );
+                    }
+                    let changed = match &shown {
+                        Some(shown) => frame.iter().zip(shown).filter(|(a, b)| a != b).count(),
+                        None => frame.len(),
+                    };
+                    prop_assert_eq!(buffer.last_flush().cells_changed, changed);
+                    prop_assert_eq!(written.len(), changed);
+                    for (i, cell) in frame.iter().enumerate() {
+                        let x = left + (i % region.width as usize) as u32;
+                        let y = top + (i / region.width as usize) as u32;
+                        prop_assert_eq!(screen.get(&(x, y)), Some(&cell.ch));
+                    }
+                    // Nothing is carried over into the next frame
+                    prop_assert!(buffer.cells().iter().all(|c| *c == Cell::default()));
+                    shown = Some(frame);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rain_column_trails_stay_on_screen(
+        seed in any::<u64>(),
+        height in 0u16..=60,
+        text in prop::option::of("[a-z]{0,12}"),
+        steps in prop::collection::vec(0.0f64..2.0, 1..80),
+    ) {
+        let chars = CharacterPool::matrix();
+        let mut rng = SimRng::seed_from_u64(seed);
+        let mut column = RainColumn::spawn(0, height, 0.05, &mut rng).with_highlight_rate(0.2);
+        if let Some(text) = text {
+            column = column.with_text(text.chars().collect());
+        }
+        for dt in steps {
+            column.update(dt, height, &chars, &mut rng);
+            prop_assert!(column.trail.iter().all(|&(y, _)| y < height));
+            // The trail runs down the screen, one row per character
+            prop_assert!(column.trail.iter().zip(column.trail.iter().skip(1)).all(|(a, b)| a.0 < b.0));
+            prop_assert!(column.highlight_positions.iter().all(|&i| i < column.trail.len()));
+            prop_assert!(column.highlight_positions.windows(2).all(|w| w[0] < w[1]));
+        }
+    }
+}